@@ -1,4 +1,15 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_prost_build::compile_protos("proto/pricefeed.proto")?;
+    tonic_prost_build::compile_protos("proto/trading.proto")?;
+
+    // Publish the schema locations to dependent build scripts as
+    // `DEP_STOCK_EXCHANGE_SIM_CORE_PROTO_DIR` / `DEP_STOCK_EXCHANGE_SIM_CORE_OPENAPI`,
+    // so clients can generate their own bindings from the exact same files.
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    println!("cargo:proto_dir={}/proto", manifest_dir);
+    println!("cargo:openapi={}/api/openapi.json", manifest_dir);
+    println!("cargo:rerun-if-changed=proto");
+    println!("cargo:rerun-if-changed=api/openapi.json");
+
     Ok(())
-}
\ No newline at end of file
+}
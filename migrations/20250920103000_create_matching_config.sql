@@ -0,0 +1,12 @@
+-- Add migration script here
+-- Runtime-tunable matching parameters, a single row that admins can update during live sessions
+CREATE TABLE matching_config (
+    id INT PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+    price_band_percent DOUBLE PRECISION NOT NULL DEFAULT 10.0,
+    max_order_quantity INT NOT NULL DEFAULT 10000,
+    volatility_halt_percent DOUBLE PRECISION NOT NULL DEFAULT 20.0,
+    halt_duration_secs INT NOT NULL DEFAULT 300,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+INSERT INTO matching_config (id) VALUES (1);
@@ -0,0 +1,42 @@
+-- Add migration script here
+CREATE TABLE user_settings (
+    user_id INT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    cost_basis_method VARCHAR(10) NOT NULL DEFAULT 'average' CHECK (cost_basis_method IN ('fifo', 'lifo', 'average')),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+CREATE TABLE tax_lots (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    ticker VARCHAR(10) NOT NULL,
+    transaction_id INT REFERENCES transactions(id),
+    quantity INT NOT NULL,
+    remaining_quantity INT NOT NULL CHECK (remaining_quantity >= 0),
+    price NUMERIC(20, 10) NOT NULL,
+    acquired_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+CREATE INDEX idx_tax_lots_open ON tax_lots (user_id, ticker, acquired_at) WHERE remaining_quantity > 0;
+
+CREATE TABLE realized_gains (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    ticker VARCHAR(10) NOT NULL,
+    sell_transaction_id INT NOT NULL REFERENCES transactions(id),
+    lot_id INT REFERENCES tax_lots(id),
+    quantity INT NOT NULL,
+    cost_basis NUMERIC(20, 10) NOT NULL,
+    proceeds NUMERIC(20, 10) NOT NULL,
+    gain NUMERIC(20, 10) NOT NULL,
+    cost_basis_method VARCHAR(10) NOT NULL,
+    acquired_at TIMESTAMPTZ,
+    realized_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+CREATE INDEX idx_realized_gains_user ON realized_gains (user_id, realized_at);
+
+-- Existing positions become a single lot at their average price
+INSERT INTO tax_lots (user_id, ticker, quantity, remaining_quantity, price)
+SELECT user_id, ticker, quantity, quantity, average_price
+FROM holdings
+WHERE quantity > 0;
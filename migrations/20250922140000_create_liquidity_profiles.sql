@@ -0,0 +1,9 @@
+-- Add migration script here
+-- Per-ticker liquidity used by the slippage model; tickers without a row use the default profile
+CREATE TABLE liquidity_profiles (
+    ticker VARCHAR(10) PRIMARY KEY,
+    depth INT NOT NULL CHECK (depth > 0),
+    spread_bps DOUBLE PRECISION NOT NULL CHECK (spread_bps >= 0),
+    resilience DOUBLE PRECISION NOT NULL CHECK (resilience > 0 AND resilience <= 1),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
@@ -0,0 +1,11 @@
+-- Add migration script here
+CREATE TABLE portfolio_snapshots (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    snapshot_date DATE NOT NULL,
+    cash NUMERIC(20, 10) NOT NULL,
+    market_value NUMERIC(20, 10) NOT NULL,
+    equity NUMERIC(20, 10) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    UNIQUE (user_id, snapshot_date)
+);
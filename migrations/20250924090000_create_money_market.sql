@@ -0,0 +1,9 @@
+-- Add migration script here
+ALTER TABLE user_settings ADD COLUMN cash_sweep_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE money_market_accounts (
+    user_id INT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    balance NUMERIC(20, 10) NOT NULL DEFAULT 0 CHECK (balance >= 0),
+    total_interest NUMERIC(20, 10) NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
@@ -0,0 +1,9 @@
+-- Add migration script here
+CREATE TABLE cash_flows (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    amount NUMERIC(20, 10) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+CREATE INDEX idx_cash_flows_user ON cash_flows (user_id, created_at);
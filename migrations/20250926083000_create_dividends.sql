@@ -0,0 +1,32 @@
+-- Add migration script here
+ALTER TABLE transactions DROP CONSTRAINT transactions_transaction_type_check;
+ALTER TABLE transactions ADD CONSTRAINT transactions_transaction_type_check
+    CHECK (transaction_type IN ('buy', 'sell', 'dividend'));
+
+ALTER TABLE user_settings ADD COLUMN drip_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE dividends (
+    id SERIAL PRIMARY KEY,
+    ticker VARCHAR(10) NOT NULL,
+    ex_date DATE NOT NULL,
+    pay_date DATE NOT NULL CHECK (pay_date >= ex_date),
+    amount_per_share NUMERIC(10, 2) NOT NULL CHECK (amount_per_share > 0),
+    status VARCHAR(10) NOT NULL DEFAULT 'announced' CHECK (status IN ('announced', 'recorded', 'paid')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+CREATE INDEX idx_dividends_status ON dividends (status, ex_date);
+
+-- Holders entitled to a dividend, captured on the ex-date
+CREATE TABLE dividend_payments (
+    id SERIAL PRIMARY KEY,
+    dividend_id INT NOT NULL REFERENCES dividends(id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    quantity INT NOT NULL,
+    amount NUMERIC(20, 10) NOT NULL,
+    transaction_id INT REFERENCES transactions(id),
+    paid_at TIMESTAMPTZ,
+    UNIQUE (dividend_id, user_id)
+);
+
+CREATE INDEX idx_dividend_payments_unpaid ON dividend_payments (dividend_id) WHERE paid_at IS NULL;
@@ -0,0 +1,20 @@
+-- Add migration script here
+CREATE TABLE corporate_actions (
+    id SERIAL PRIMARY KEY,
+    ticker VARCHAR(10) NOT NULL,
+    action_type VARCHAR(15) NOT NULL CHECK (action_type IN ('split', 'symbol_change')),
+    effective_date DATE NOT NULL,
+    -- Splits turn every ratio_from shares into ratio_to shares
+    ratio_from INT CHECK (ratio_from > 0),
+    ratio_to INT CHECK (ratio_to > 0),
+    new_ticker VARCHAR(10),
+    status VARCHAR(10) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'applied')),
+    applied_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    CHECK (
+        (action_type = 'split' AND ratio_from IS NOT NULL AND ratio_to IS NOT NULL)
+        OR (action_type = 'symbol_change' AND new_ticker IS NOT NULL)
+    )
+);
+
+CREATE INDEX idx_corporate_actions_pending ON corporate_actions (effective_date) WHERE status = 'pending';
@@ -0,0 +1,25 @@
+-- Add migration script here
+CREATE TABLE loans (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    principal NUMERIC(20, 2) NOT NULL CHECK (principal > 0),
+    -- Principal plus accrued interest minus repayments
+    outstanding NUMERIC(20, 10) NOT NULL CHECK (outstanding >= 0),
+    interest_rate_percent DOUBLE PRECISION NOT NULL,
+    status VARCHAR(10) NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'repaid', 'liquidated')),
+    interest_accrued_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    closed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_loans_user ON loans (user_id, created_at);
+CREATE INDEX idx_loans_open ON loans (id) WHERE status = 'open';
+
+-- Shares pledged to a loan; they cannot be sold while the loan is open
+CREATE TABLE loan_collateral (
+    id SERIAL PRIMARY KEY,
+    loan_id INT NOT NULL REFERENCES loans(id) ON DELETE CASCADE,
+    ticker VARCHAR(10) NOT NULL,
+    quantity INT NOT NULL CHECK (quantity >= 0),
+    UNIQUE (loan_id, ticker)
+);
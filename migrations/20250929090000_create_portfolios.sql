@@ -0,0 +1,59 @@
+-- Add migration script here
+CREATE TABLE portfolios (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(50) NOT NULL,
+    balance NUMERIC NOT NULL DEFAULT 0,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    UNIQUE (user_id, name)
+);
+
+-- Every user has exactly one default portfolio, used when no portfolio is selected
+CREATE UNIQUE INDEX idx_portfolios_default ON portfolios (user_id) WHERE is_default;
+
+-- Existing accounts become the default portfolio of their user
+INSERT INTO portfolios (user_id, name, balance, is_default)
+SELECT id, 'Main', balance, TRUE
+FROM users;
+
+ALTER TABLE users DROP COLUMN balance;
+
+ALTER TABLE holdings ADD COLUMN portfolio_id INT REFERENCES portfolios(id) ON DELETE CASCADE;
+UPDATE holdings SET portfolio_id = portfolios.id
+FROM portfolios
+WHERE portfolios.user_id = holdings.user_id AND portfolios.is_default;
+ALTER TABLE holdings ALTER COLUMN portfolio_id SET NOT NULL;
+ALTER TABLE holdings DROP CONSTRAINT holdings_user_id_ticker_key;
+ALTER TABLE holdings ADD CONSTRAINT holdings_portfolio_id_ticker_key UNIQUE (portfolio_id, ticker);
+
+ALTER TABLE transactions ADD COLUMN portfolio_id INT REFERENCES portfolios(id) ON DELETE CASCADE;
+UPDATE transactions SET portfolio_id = portfolios.id
+FROM portfolios
+WHERE portfolios.user_id = transactions.user_id AND portfolios.is_default;
+ALTER TABLE transactions ALTER COLUMN portfolio_id SET NOT NULL;
+CREATE INDEX idx_transactions_portfolio ON transactions (portfolio_id, id);
+
+ALTER TABLE tax_lots ADD COLUMN portfolio_id INT REFERENCES portfolios(id) ON DELETE CASCADE;
+UPDATE tax_lots SET portfolio_id = portfolios.id
+FROM portfolios
+WHERE portfolios.user_id = tax_lots.user_id AND portfolios.is_default;
+ALTER TABLE tax_lots ALTER COLUMN portfolio_id SET NOT NULL;
+DROP INDEX idx_tax_lots_open;
+CREATE INDEX idx_tax_lots_open ON tax_lots (portfolio_id, ticker, acquired_at) WHERE remaining_quantity > 0;
+
+ALTER TABLE loans ADD COLUMN portfolio_id INT REFERENCES portfolios(id) ON DELETE CASCADE;
+UPDATE loans SET portfolio_id = portfolios.id
+FROM portfolios
+WHERE portfolios.user_id = loans.user_id AND portfolios.is_default;
+ALTER TABLE loans ALTER COLUMN portfolio_id SET NOT NULL;
+CREATE INDEX idx_loans_portfolio ON loans (portfolio_id, created_at);
+
+-- Dividends are paid into the portfolio holding the shares
+ALTER TABLE dividend_payments ADD COLUMN portfolio_id INT REFERENCES portfolios(id) ON DELETE CASCADE;
+UPDATE dividend_payments SET portfolio_id = portfolios.id
+FROM portfolios
+WHERE portfolios.user_id = dividend_payments.user_id AND portfolios.is_default;
+ALTER TABLE dividend_payments ALTER COLUMN portfolio_id SET NOT NULL;
+ALTER TABLE dividend_payments DROP CONSTRAINT dividend_payments_dividend_id_user_id_key;
+ALTER TABLE dividend_payments ADD CONSTRAINT dividend_payments_dividend_id_portfolio_id_key UNIQUE (dividend_id, portfolio_id);
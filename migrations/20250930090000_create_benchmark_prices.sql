@@ -0,0 +1,12 @@
+-- Add migration script here
+ALTER TABLE user_settings ADD COLUMN benchmark_ticker VARCHAR(10);
+
+-- Daily prices of the tickers users compare their performance against,
+-- captured together with the portfolio snapshots
+CREATE TABLE benchmark_prices (
+    ticker VARCHAR(10) NOT NULL,
+    price_date DATE NOT NULL,
+    price NUMERIC(20, 10) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    PRIMARY KEY (ticker, price_date)
+);
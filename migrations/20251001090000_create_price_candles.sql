@@ -0,0 +1,18 @@
+-- Add migration script here
+-- OHLC candles aggregated from the price feed, one row per ticker, interval
+-- and bucket. Volume is not stored: the feed carries none, so it is derived
+-- from the trades executed in the simulator.
+CREATE TABLE price_candles (
+    ticker VARCHAR(10) NOT NULL,
+    interval VARCHAR(3) NOT NULL CHECK (interval IN ('1m', '5m', '1h', '1d')),
+    bucket_start TIMESTAMPTZ NOT NULL,
+    open NUMERIC(20, 10) NOT NULL,
+    high NUMERIC(20, 10) NOT NULL,
+    low NUMERIC(20, 10) NOT NULL,
+    close NUMERIC(20, 10) NOT NULL,
+    tick_count INT NOT NULL DEFAULT 1,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    PRIMARY KEY (ticker, interval, bucket_start)
+);
+
+CREATE INDEX idx_transactions_ticker_created ON transactions (ticker, created_at);
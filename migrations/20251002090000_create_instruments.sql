@@ -0,0 +1,28 @@
+-- Add migration script here
+-- Catalog of tradable instruments; buys and sells are only accepted for
+-- listed tickers, whatever prices the feed publishes
+CREATE TABLE instruments (
+    ticker VARCHAR(10) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    sector VARCHAR(100),
+    asset_class VARCHAR(20) NOT NULL DEFAULT 'equity' CHECK (
+        asset_class IN ('equity', 'etf', 'bond', 'commodity', 'crypto')
+    ),
+    tick_size NUMERIC(20, 10) NOT NULL DEFAULT 0.01 CHECK (tick_size > 0),
+    lot_size INT NOT NULL DEFAULT 1 CHECK (lot_size > 0),
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+-- List every ticker already traded or configured, so existing positions stay tradable
+INSERT INTO instruments (ticker, name)
+SELECT ticker, ticker
+FROM (
+    SELECT ticker FROM holdings
+    UNION
+    SELECT ticker FROM transactions
+    UNION
+    SELECT ticker FROM liquidity_profiles
+) AS known
+ON CONFLICT (ticker) DO NOTHING;
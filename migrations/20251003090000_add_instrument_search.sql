@@ -0,0 +1,6 @@
+-- Add migration script here
+-- Trigram indexes for fuzzy ticker search by symbol and name
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX idx_instruments_ticker_trgm ON instruments USING GIN (ticker gin_trgm_ops);
+CREATE INDEX idx_instruments_name_trgm ON instruments USING GIN (LOWER(name) gin_trgm_ops);
@@ -0,0 +1,17 @@
+-- Add migration script here
+-- Simulated news per ticker, applied to prices by the synthetic provider once published
+CREATE TABLE news_events (
+    id SERIAL PRIMARY KEY,
+    ticker VARCHAR(10) NOT NULL,
+    event_type VARCHAR(20) NOT NULL CHECK (event_type IN ('earnings_beat', 'earnings_miss', 'upgrade', 'downgrade', 'other')),
+    headline VARCHAR(200) NOT NULL,
+    price_impact_percent DOUBLE PRECISION NOT NULL CHECK (price_impact_percent > -100),
+    volatility_multiplier DOUBLE PRECISION NOT NULL DEFAULT 1 CHECK (volatility_multiplier > 0),
+    volatility_duration_secs INT NOT NULL DEFAULT 0 CHECK (volatility_duration_secs >= 0),
+    publish_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    applied_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+CREATE INDEX idx_news_events_publish_at ON news_events (publish_at DESC);
+CREATE INDEX idx_news_events_pending ON news_events (publish_at) WHERE applied_at IS NULL;
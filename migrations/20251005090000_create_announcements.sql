@@ -0,0 +1,12 @@
+-- Add migration script here
+-- System announcements broadcast to WebSocket clients on the `system` channel;
+-- active ones are replayed to clients subscribing later
+CREATE TABLE announcements (
+    id SERIAL PRIMARY KEY,
+    message VARCHAR(500) NOT NULL,
+    severity VARCHAR(10) NOT NULL DEFAULT 'info' CHECK (severity IN ('info', 'warning', 'critical')),
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+CREATE INDEX idx_announcements_created_at ON announcements (created_at DESC);
@@ -0,0 +1,4 @@
+-- Add migration script here
+-- Role of each account; admins may use the admin endpoints with their own token
+ALTER TABLE users
+ADD COLUMN role VARCHAR(20) NOT NULL DEFAULT 'user' CHECK (role IN ('user', 'moderator', 'admin'));
@@ -0,0 +1,11 @@
+-- Add migration script here
+ALTER TABLE user_settings ADD COLUMN display_name VARCHAR(50);
+ALTER TABLE user_settings ADD COLUMN base_currency VARCHAR(3) NOT NULL DEFAULT 'USD';
+
+-- Account events pushed to the user's WebSocket connections
+ALTER TABLE user_settings ADD COLUMN notify_order_fills BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE user_settings ADD COLUMN notify_margin_calls BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE user_settings ADD COLUMN notify_deposits BOOLEAN NOT NULL DEFAULT TRUE;
+
+-- Free-form preferences of the user's clients, stored as given
+ALTER TABLE user_settings ADD COLUMN ui_settings JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,13 @@
+-- Add migration script here
+-- Runtime switches for risky features. A flag is on for the users listed in
+-- `user_ids` and for `rollout_percent` percent of everyone else, and off for
+-- everyone while `enabled` is false.
+CREATE TABLE feature_flags (
+    name VARCHAR(50) PRIMARY KEY,
+    description VARCHAR(255) NOT NULL DEFAULT '',
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    rollout_percent SMALLINT NOT NULL DEFAULT 0 CHECK (rollout_percent BETWEEN 0 AND 100),
+    user_ids INT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
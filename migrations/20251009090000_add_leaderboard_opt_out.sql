@@ -0,0 +1,3 @@
+-- Add migration script here
+-- Users ranked on the leaderboard unless they opt out
+ALTER TABLE user_settings ADD COLUMN leaderboard_opt_out BOOLEAN NOT NULL DEFAULT FALSE;
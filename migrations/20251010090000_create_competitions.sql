@@ -0,0 +1,32 @@
+-- Add migration script here
+CREATE TABLE competitions (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    -- Cash every entrant's competition portfolio starts with
+    starting_balance NUMERIC NOT NULL CHECK (starting_balance > 0),
+    -- Set once the final standings are recorded
+    finalized_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX idx_competitions_unfinalized ON competitions (ends_at) WHERE finalized_at IS NULL;
+
+-- Competition portfolios are isolated from the rest of their user's account
+ALTER TABLE portfolios ADD COLUMN competition_id INT REFERENCES competitions(id) ON DELETE CASCADE;
+
+CREATE TABLE competition_entries (
+    competition_id INT NOT NULL REFERENCES competitions(id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    portfolio_id INT NOT NULL UNIQUE REFERENCES portfolios(id) ON DELETE CASCADE,
+    -- Recorded when the competition is finalized
+    final_equity NUMERIC,
+    final_rank INT,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    PRIMARY KEY (competition_id, user_id)
+);
+
+CREATE INDEX idx_competition_entries_user ON competition_entries (user_id);
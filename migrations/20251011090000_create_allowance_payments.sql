@@ -0,0 +1,13 @@
+-- Add migration script here
+ALTER TABLE users ADD COLUMN last_login_at TIMESTAMPTZ;
+
+-- One weekly allowance per user and week, so repeated runs never pay twice
+CREATE TABLE allowance_payments (
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Monday of the week the allowance is for
+    week_start DATE NOT NULL,
+    portfolio_id INT NOT NULL REFERENCES portfolios(id) ON DELETE CASCADE,
+    amount NUMERIC NOT NULL,
+    paid_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    PRIMARY KEY (user_id, week_start)
+);
@@ -0,0 +1,18 @@
+-- Add migration script here
+-- Public portfolios show their positions and trades to other users
+ALTER TABLE portfolios ADD COLUMN is_public BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- How the user's public trades show up in their followers' feeds
+ALTER TABLE user_settings
+    ADD COLUMN trade_delay_minutes INT NOT NULL DEFAULT 15 CHECK (trade_delay_minutes BETWEEN 0 AND 10080),
+    ADD COLUMN hide_trade_quantities BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE follows (
+    follower_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    followee_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    PRIMARY KEY (follower_id, followee_id),
+    CHECK (follower_id <> followee_id)
+);
+
+CREATE INDEX idx_follows_followee ON follows (followee_id, created_at);
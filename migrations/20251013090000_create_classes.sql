@@ -0,0 +1,31 @@
+-- Add migration script here
+CREATE TABLE classes (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(50) NOT NULL,
+    -- Cash every student's class portfolio starts with
+    starting_balance NUMERIC NOT NULL CHECK (starting_balance > 0),
+    -- Tickers students may buy, NULL for every ticker
+    allowed_tickers TEXT[],
+    -- Shared with students so they can join
+    invite_code VARCHAR(16) NOT NULL UNIQUE,
+    created_by INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+-- Class portfolios are isolated from the rest of their user's account
+ALTER TABLE portfolios ADD COLUMN class_id INT REFERENCES classes(id) ON DELETE CASCADE;
+
+CREATE TABLE class_members (
+    class_id INT NOT NULL REFERENCES classes(id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(20) NOT NULL CHECK (role IN ('instructor', 'student')),
+    -- Students trade in a class portfolio; instructors have none
+    portfolio_id INT UNIQUE REFERENCES portfolios(id) ON DELETE CASCADE,
+    -- The class's starting balance when the student joined
+    starting_balance NUMERIC,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    PRIMARY KEY (class_id, user_id),
+    CHECK ((role = 'student') = (portfolio_id IS NOT NULL AND starting_balance IS NOT NULL))
+);
+
+CREATE INDEX idx_class_members_user ON class_members (user_id);
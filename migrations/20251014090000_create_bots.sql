@@ -0,0 +1,9 @@
+-- Add migration script here
+-- Server-managed accounts trading on their own
+CREATE TABLE bots (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    strategy VARCHAR(20) NOT NULL CHECK (strategy IN ('momentum', 'mean_reversion', 'random')),
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
@@ -0,0 +1,11 @@
+-- Add migration script here
+ALTER TABLE user_settings
+    ADD COLUMN difficulty VARCHAR(20) NOT NULL DEFAULT 'realistic'
+        CHECK (difficulty IN ('beginner', 'realistic'));
+
+ALTER TABLE competitions
+    ADD COLUMN difficulty VARCHAR(20) NOT NULL DEFAULT 'realistic'
+        CHECK (difficulty IN ('beginner', 'realistic'));
+
+-- Commission charged on a buy or sell, on top of the price
+ALTER TABLE transactions ADD COLUMN fee NUMERIC(15, 2) NOT NULL DEFAULT 0;
@@ -0,0 +1,14 @@
+-- Add migration script here
+-- Simulated seconds per real second; 24 runs one trading day per real hour
+ALTER TABLE competitions
+    ADD COLUMN clock_speed DOUBLE PRECISION NOT NULL DEFAULT 1
+        CHECK (clock_speed >= 1 AND clock_speed <= 1440);
+
+-- Dividends whose holders were recorded for an accelerated competition, by
+-- its simulated date rather than the calendar
+CREATE TABLE competition_dividends (
+    competition_id INT NOT NULL REFERENCES competitions(id) ON DELETE CASCADE,
+    dividend_id INT NOT NULL REFERENCES dividends(id) ON DELETE CASCADE,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (competition_id, dividend_id)
+);
@@ -0,0 +1,30 @@
+-- Add migration script here
+-- Historical tick datasets replayed through the price pipeline
+CREATE TABLE replays (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    -- Historical seconds replayed per real second
+    speed DOUBLE PRECISION NOT NULL CHECK (speed > 0 AND speed <= 1000),
+    status VARCHAR(10) NOT NULL DEFAULT 'ready' CHECK (status IN ('ready', 'running', 'finished')),
+    tick_count INT NOT NULL,
+    -- Ticks published so far in the current run
+    position INT NOT NULL DEFAULT 0,
+    -- Historical time of the first and last tick
+    data_from TIMESTAMPTZ NOT NULL,
+    data_to TIMESTAMPTZ NOT NULL,
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_replays_running ON replays (id) WHERE status = 'running';
+
+-- Ticks of a replay in the order they are published
+CREATE TABLE replay_ticks (
+    replay_id INT NOT NULL REFERENCES replays(id) ON DELETE CASCADE,
+    seq INT NOT NULL,
+    at TIMESTAMPTZ NOT NULL,
+    ticker VARCHAR(10) NOT NULL,
+    price DOUBLE PRECISION NOT NULL CHECK (price > 0),
+    PRIMARY KEY (replay_id, seq)
+);
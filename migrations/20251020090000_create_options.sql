@@ -0,0 +1,50 @@
+-- Add migration script here
+-- Call and put contracts on a listed underlying, each covering 100 shares
+CREATE TABLE option_contracts (
+    id SERIAL PRIMARY KEY,
+    underlying VARCHAR(10) NOT NULL,
+    kind VARCHAR(4) NOT NULL CHECK (kind IN ('call', 'put')),
+    strike NUMERIC(20, 4) NOT NULL CHECK (strike > 0),
+    expires_at TIMESTAMPTZ NOT NULL,
+    -- Underlying price the contract was cash-settled at after expiry
+    settlement_price NUMERIC(20, 4),
+    settled_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    UNIQUE (underlying, kind, strike, expires_at)
+);
+
+CREATE INDEX idx_option_contracts_unsettled ON option_contracts (expires_at) WHERE settled_at IS NULL;
+
+-- Contracts held in a portfolio; short positions have a negative quantity
+-- and hold cash as margin until closed or settled
+CREATE TABLE option_positions (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    portfolio_id INT NOT NULL REFERENCES portfolios(id) ON DELETE CASCADE,
+    contract_id INT NOT NULL REFERENCES option_contracts(id) ON DELETE CASCADE,
+    quantity INT NOT NULL CHECK (quantity <> 0),
+    -- Average premium per share paid for long or received for short contracts
+    average_premium NUMERIC(20, 4) NOT NULL,
+    margin NUMERIC(20, 2) NOT NULL DEFAULT 0 CHECK (margin >= 0),
+    UNIQUE (portfolio_id, contract_id)
+);
+
+CREATE INDEX idx_option_positions_contract ON option_positions (contract_id);
+
+-- Option trades, and exercises, assignments and expiries at settlement
+CREATE TABLE option_trades (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    portfolio_id INT NOT NULL REFERENCES portfolios(id) ON DELETE CASCADE,
+    contract_id INT NOT NULL REFERENCES option_contracts(id) ON DELETE CASCADE,
+    trade_type VARCHAR(10) NOT NULL CHECK (
+        trade_type IN ('buy', 'sell', 'exercise', 'assignment', 'expiry')
+    ),
+    quantity INT NOT NULL CHECK (quantity > 0),
+    -- Premium per share, or the intrinsic value per share at settlement
+    price NUMERIC(20, 4) NOT NULL,
+    fee NUMERIC(20, 2) NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+CREATE INDEX idx_option_trades_portfolio ON option_trades (portfolio_id, id);
@@ -0,0 +1,20 @@
+-- Add migration script here
+ALTER TABLE transactions DROP CONSTRAINT transactions_transaction_type_check;
+ALTER TABLE transactions ADD CONSTRAINT transactions_transaction_type_check
+    CHECK (transaction_type IN ('buy', 'sell', 'dividend', 'coupon', 'redemption'));
+
+-- Terms of instruments of the bond asset class; one unit is one bond
+CREATE TABLE bonds (
+    ticker VARCHAR(10) PRIMARY KEY REFERENCES instruments(ticker) ON UPDATE CASCADE ON DELETE CASCADE,
+    face_value NUMERIC(20, 4) NOT NULL CHECK (face_value > 0),
+    coupon_rate_percent NUMERIC(8, 4) NOT NULL CHECK (coupon_rate_percent >= 0),
+    coupons_per_year INT NOT NULL CHECK (coupons_per_year IN (1, 2, 4, 12)),
+    maturity_date DATE NOT NULL,
+    -- Coupon dates step back from the maturity date; this is the next one to pay
+    next_coupon_date DATE NOT NULL CHECK (next_coupon_date <= maturity_date),
+    redeemed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+CREATE INDEX idx_bonds_next_coupon ON bonds (next_coupon_date) WHERE redeemed_at IS NULL;
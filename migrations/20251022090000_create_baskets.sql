@@ -0,0 +1,17 @@
+-- Add migration script here
+ALTER TABLE instruments DROP CONSTRAINT instruments_asset_class_check;
+ALTER TABLE instruments ADD CONSTRAINT instruments_asset_class_check CHECK (
+    asset_class IN ('equity', 'etf', 'bond', 'commodity', 'crypto', 'index')
+);
+
+-- Constituents of index and ETF instruments, priced as the weighted sum of
+-- the constituents' prices instead of by the feed
+CREATE TABLE basket_constituents (
+    basket VARCHAR(10) NOT NULL REFERENCES instruments(ticker) ON UPDATE CASCADE ON DELETE CASCADE,
+    ticker VARCHAR(10) NOT NULL REFERENCES instruments(ticker) ON UPDATE CASCADE,
+    weight NUMERIC(20, 10) NOT NULL CHECK (weight > 0),
+    PRIMARY KEY (basket, ticker),
+    CHECK (basket <> ticker)
+);
+
+CREATE INDEX idx_basket_constituents_ticker ON basket_constituents (ticker);
@@ -0,0 +1,26 @@
+-- Add migration script here
+ALTER TABLE users ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW ();
+UPDATE users SET updated_at = created_at;
+
+ALTER TABLE holdings
+    ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT NOW (),
+    ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW ();
+
+-- Existing positions were opened by their first trade and last changed by their latest one
+UPDATE holdings h
+SET created_at = traded.first_at, updated_at = traded.last_at
+FROM (
+    SELECT portfolio_id, ticker,
+           MIN(created_at) AT TIME ZONE 'UTC' AS first_at,
+           MAX(created_at) AT TIME ZONE 'UTC' AS last_at
+    FROM transactions
+    WHERE created_at IS NOT NULL
+    GROUP BY portfolio_id, ticker
+) traded
+WHERE traded.portfolio_id = h.portfolio_id AND traded.ticker = h.ticker;
+
+UPDATE transactions SET created_at = CURRENT_TIMESTAMP WHERE created_at IS NULL;
+UPDATE transactions SET updated_at = created_at WHERE updated_at IS NULL;
+ALTER TABLE transactions
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET NOT NULL;
@@ -0,0 +1,91 @@
+-- Add migration script here
+-- Append-only journal of every change to the cash of a portfolio. Each entry
+-- moves `amount` between the portfolio's cash and the account named by its
+-- type (the outside world for deposits, the market for trades, the broker for
+-- fees, ...), or another portfolio for transfers.
+CREATE TABLE ledger_entries (
+    id BIGSERIAL PRIMARY KEY,
+    portfolio_id INT NOT NULL REFERENCES portfolios(id) ON DELETE CASCADE,
+    entry_type VARCHAR(20) NOT NULL CHECK (entry_type IN (
+        'opening', 'deposit', 'withdrawal', 'allowance', 'transfer', 'buy', 'sell', 'fee',
+        'dividend', 'coupon', 'redemption', 'loan', 'sweep', 'premium', 'margin', 'settlement',
+        'cash_in_lieu'
+    )),
+    -- Positive amounts credit the portfolio's cash, negative ones debit it
+    amount NUMERIC NOT NULL,
+    -- Cash of the portfolio once the entry was posted
+    balance_after NUMERIC NOT NULL,
+    transaction_id INT REFERENCES transactions(id) ON DELETE SET NULL,
+    counter_portfolio_id INT REFERENCES portfolios(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+CREATE INDEX idx_ledger_entries_portfolio ON ledger_entries (portfolio_id, id);
+CREATE UNIQUE INDEX idx_ledger_entries_opening ON ledger_entries (portfolio_id)
+    WHERE entry_type = 'opening';
+
+-- Cash held before the ledger existed opens it
+INSERT INTO ledger_entries (portfolio_id, entry_type, amount, balance_after, created_at)
+SELECT id, 'opening', balance, balance, created_at
+FROM portfolios;
+
+-- Posting an entry applies it to the portfolio's cash
+CREATE FUNCTION post_ledger_entry() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.entry_type = 'opening' THEN
+        NEW.balance_after := NEW.amount;
+        RETURN NEW;
+    END IF;
+
+    UPDATE portfolios
+    SET balance = balance + NEW.amount
+    WHERE id = NEW.portfolio_id
+    RETURNING balance INTO NEW.balance_after;
+    IF NOT FOUND THEN
+        RAISE EXCEPTION 'portfolio % does not exist', NEW.portfolio_id;
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER ledger_entries_post BEFORE INSERT ON ledger_entries
+    FOR EACH ROW EXECUTE FUNCTION post_ledger_entry();
+
+-- Entries are never changed or removed, except along with their portfolio
+CREATE FUNCTION reject_ledger_change() RETURNS TRIGGER AS $$
+BEGIN
+    IF pg_trigger_depth() < 2 THEN
+        RAISE EXCEPTION 'ledger entries are append-only';
+    END IF;
+    RETURN COALESCE(NEW, OLD);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER ledger_entries_append_only BEFORE UPDATE OR DELETE ON ledger_entries
+    FOR EACH ROW EXECUTE FUNCTION reject_ledger_change();
+
+-- Cash only changes by posting entries; new portfolios open the ledger with
+-- their starting cash
+CREATE FUNCTION guard_portfolio_balance() RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.balance IS DISTINCT FROM OLD.balance AND pg_trigger_depth() < 2 THEN
+        RAISE EXCEPTION 'portfolio balances change only through ledger entries';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER portfolios_balance_guard BEFORE UPDATE OF balance ON portfolios
+    FOR EACH ROW EXECUTE FUNCTION guard_portfolio_balance();
+
+CREATE FUNCTION open_portfolio_ledger() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO ledger_entries (portfolio_id, entry_type, amount)
+    VALUES (NEW.id, 'opening', NEW.balance);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER portfolios_open_ledger AFTER INSERT ON portfolios
+    FOR EACH ROW EXECUTE FUNCTION open_portfolio_ledger();
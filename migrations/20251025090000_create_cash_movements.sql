@@ -0,0 +1,20 @@
+-- Add migration script here
+-- Deposits and withdrawals of a portfolio, each posted to the ledger as one
+-- entry. Amounts are positive; the type gives the direction.
+CREATE TABLE cash_movements (
+    id SERIAL PRIMARY KEY,
+    portfolio_id INT NOT NULL REFERENCES portfolios(id) ON DELETE CASCADE,
+    movement_type VARCHAR(20) NOT NULL CHECK (movement_type IN ('deposit', 'withdrawal')),
+    amount NUMERIC NOT NULL CHECK (amount > 0),
+    ledger_entry_id BIGINT NOT NULL UNIQUE REFERENCES ledger_entries(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
+
+CREATE INDEX idx_cash_movements_portfolio ON cash_movements (portfolio_id, id);
+
+-- Deposits and withdrawals posted since the ledger opened
+INSERT INTO cash_movements (portfolio_id, movement_type, amount, ledger_entry_id, created_at)
+SELECT portfolio_id, entry_type, ABS(amount), id, created_at
+FROM ledger_entries
+WHERE entry_type IN ('deposit', 'withdrawal')
+ORDER BY id;
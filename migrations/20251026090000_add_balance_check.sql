@@ -0,0 +1,6 @@
+-- Add migration script here
+-- Portfolio cash never goes negative. Entries are applied to the balance
+-- with a relative update, so a debit racing another one is rejected here
+-- instead of overdrawing the portfolio.
+ALTER TABLE portfolios
+    ADD CONSTRAINT portfolios_balance_non_negative CHECK (balance >= 0);
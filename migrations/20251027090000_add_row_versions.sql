@@ -0,0 +1,6 @@
+-- Add migration script here
+-- Row versions for optimistic concurrency: an update only applies to the
+-- version it was computed from and bumps it, so a concurrent write makes it
+-- match no row instead of being overwritten.
+ALTER TABLE users ADD COLUMN version INT NOT NULL DEFAULT 0;
+ALTER TABLE holdings ADD COLUMN version INT NOT NULL DEFAULT 0;
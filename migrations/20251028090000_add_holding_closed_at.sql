@@ -0,0 +1,15 @@
+-- Add migration script here
+-- A position is closed once its last share leaves it. Closed holdings stay
+-- as the history of the position and buying the ticker again opens a new
+-- one, so only open positions are unique per portfolio and ticker.
+ALTER TABLE holdings ADD COLUMN closed_at TIMESTAMPTZ;
+
+UPDATE holdings SET closed_at = updated_at WHERE quantity = 0;
+
+ALTER TABLE holdings
+    ADD CONSTRAINT holdings_closed_when_empty CHECK ((quantity = 0) = (closed_at IS NOT NULL));
+
+ALTER TABLE holdings DROP CONSTRAINT holdings_portfolio_id_ticker_key;
+CREATE UNIQUE INDEX holdings_open_position ON holdings (portfolio_id, ticker)
+    WHERE closed_at IS NULL;
+CREATE INDEX idx_holdings_closed ON holdings (portfolio_id, id) WHERE closed_at IS NOT NULL;
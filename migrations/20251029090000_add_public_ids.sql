@@ -0,0 +1,12 @@
+-- Add migration script here
+-- Random public IDs for the rows the API hands out. Serial keys reveal how
+-- many users and trades there are and can be walked one by one, so they stay
+-- internal and routes and responses name rows by these instead.
+ALTER TABLE users ADD COLUMN public_id UUID NOT NULL DEFAULT gen_random_uuid();
+ALTER TABLE users ADD CONSTRAINT users_public_id_key UNIQUE (public_id);
+
+ALTER TABLE transactions ADD COLUMN public_id UUID NOT NULL DEFAULT gen_random_uuid();
+ALTER TABLE transactions ADD CONSTRAINT transactions_public_id_key UNIQUE (public_id);
+
+ALTER TABLE holdings ADD COLUMN public_id UUID NOT NULL DEFAULT gen_random_uuid();
+ALTER TABLE holdings ADD CONSTRAINT holdings_public_id_key UNIQUE (public_id);
@@ -0,0 +1,98 @@
+-- Add migration script here
+-- Transactions older than the archive cutoff move out of the table trades are
+-- written to into transactions_archive, partitioned by month of execution, so
+-- the hot table stays small however long the simulator runs. Archived rows
+-- keep their ids; lots, gains, payments and ledger entries keep pointing at
+-- them, so those references are no longer enforced against the hot table.
+ALTER TABLE tax_lots DROP CONSTRAINT tax_lots_transaction_id_fkey;
+ALTER TABLE realized_gains DROP CONSTRAINT realized_gains_sell_transaction_id_fkey;
+ALTER TABLE dividend_payments DROP CONSTRAINT dividend_payments_transaction_id_fkey;
+ALTER TABLE ledger_entries DROP CONSTRAINT ledger_entries_transaction_id_fkey;
+
+CREATE TABLE transactions_archive (
+    id INT NOT NULL,
+    public_id UUID NOT NULL,
+    user_id INT NOT NULL,
+    portfolio_id INT NOT NULL REFERENCES portfolios(id) ON DELETE CASCADE,
+    ticker VARCHAR(10) NOT NULL,
+    quantity INT NOT NULL,
+    price NUMERIC(10, 2) NOT NULL,
+    fee NUMERIC(15, 2) NOT NULL,
+    transaction_type VARCHAR(10) NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (id, created_at)
+) PARTITION BY RANGE (created_at);
+
+CREATE INDEX idx_transactions_archive_portfolio ON transactions_archive (portfolio_id, id);
+CREATE INDEX idx_transactions_archive_ticker_created ON transactions_archive (ticker, created_at);
+CREATE INDEX idx_transactions_archive_public_id ON transactions_archive (public_id);
+
+-- Indexes matching the history filters, and the cutoff scan of the archival job
+CREATE INDEX idx_transactions_portfolio_ticker ON transactions (portfolio_id, ticker, id);
+CREATE INDEX idx_transactions_portfolio_created ON transactions (portfolio_id, created_at);
+CREATE INDEX idx_transactions_created ON transactions (created_at);
+
+-- Every transaction, hot or archived
+CREATE VIEW transaction_history AS
+SELECT id, public_id, user_id, portfolio_id, ticker, quantity, price, fee, transaction_type,
+    created_at, updated_at
+FROM transactions
+UNION ALL
+SELECT id, public_id, user_id, portfolio_id, ticker, quantity, price, fee, transaction_type,
+    created_at, updated_at
+FROM transactions_archive;
+
+-- Move up to `batch` transactions executed before `cutoff` into the archive,
+-- creating the partition of each month on first use; returns how many moved
+CREATE FUNCTION archive_transactions(cutoff TIMESTAMP, batch INT) RETURNS INT AS $$
+DECLARE
+    month TIMESTAMP;
+    partition TEXT;
+    moved INT;
+BEGIN
+    -- Instances archiving at the same time take turns
+    PERFORM pg_advisory_xact_lock(hashtext('archive_transactions'));
+
+    FOR month IN
+        SELECT DISTINCT date_trunc('month', created_at)
+        FROM (
+            SELECT created_at FROM transactions
+            WHERE created_at < cutoff
+            ORDER BY id
+            LIMIT batch
+        ) due
+    LOOP
+        partition := 'transactions_archive_' || to_char(month, 'YYYY_MM');
+        IF to_regclass(partition) IS NULL THEN
+            EXECUTE format(
+                'CREATE TABLE %I PARTITION OF transactions_archive FOR VALUES FROM (%L) TO (%L)',
+                partition,
+                month,
+                month + INTERVAL '1 month'
+            );
+        END IF;
+    END LOOP;
+
+    WITH due AS (
+        DELETE FROM transactions
+        WHERE id IN (
+            SELECT id FROM transactions
+            WHERE created_at < cutoff
+            ORDER BY id
+            LIMIT batch
+        )
+        RETURNING id, public_id, user_id, portfolio_id, ticker, quantity, price, fee,
+            transaction_type, created_at, updated_at
+    )
+    INSERT INTO transactions_archive (id, public_id, user_id, portfolio_id, ticker, quantity,
+        price, fee, transaction_type, created_at, updated_at)
+    SELECT id, public_id, user_id, portfolio_id, ticker, quantity, price, fee,
+        transaction_type, created_at, updated_at
+    FROM due;
+    GET DIAGNOSTICS moved = ROW_COUNT;
+
+    RETURN moved;
+END;
+$$ LANGUAGE plpgsql;
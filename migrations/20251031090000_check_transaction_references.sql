@@ -0,0 +1,66 @@
+-- Add migration script here
+-- Lots, gains, dividend payments and ledger entries refer to transactions
+-- whether they are hot or archived, which foreign keys cannot express. These
+-- triggers enforce the references instead: a reference must name a
+-- transaction of the history, and a referenced transaction may only leave the
+-- hot table for the archive.
+CREATE FUNCTION check_transaction_reference() RETURNS TRIGGER AS $$
+DECLARE
+    referenced INT := (to_jsonb(NEW) ->> TG_ARGV[0])::INT;
+BEGIN
+    IF referenced IS NOT NULL
+        AND NOT EXISTS (SELECT 1 FROM transaction_history WHERE id = referenced) THEN
+        RAISE foreign_key_violation USING MESSAGE = format(
+            '%s.%s refers to missing transaction %s', TG_TABLE_NAME, TG_ARGV[0], referenced
+        );
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER tax_lots_transaction_exists
+    BEFORE INSERT OR UPDATE OF transaction_id ON tax_lots
+    FOR EACH ROW EXECUTE FUNCTION check_transaction_reference('transaction_id');
+CREATE TRIGGER realized_gains_transaction_exists
+    BEFORE INSERT OR UPDATE OF sell_transaction_id ON realized_gains
+    FOR EACH ROW EXECUTE FUNCTION check_transaction_reference('sell_transaction_id');
+CREATE TRIGGER dividend_payments_transaction_exists
+    BEFORE INSERT OR UPDATE OF transaction_id ON dividend_payments
+    FOR EACH ROW EXECUTE FUNCTION check_transaction_reference('transaction_id');
+CREATE TRIGGER ledger_entries_transaction_exists
+    BEFORE INSERT ON ledger_entries
+    FOR EACH ROW EXECUTE FUNCTION check_transaction_reference('transaction_id');
+
+-- Checked once the deleting statement is done, when archived rows have arrived
+CREATE FUNCTION release_transaction_references() RETURNS TRIGGER AS $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM transactions_archive
+        WHERE id = OLD.id AND created_at = OLD.created_at
+    ) THEN
+        RETURN NULL;
+    END IF;
+
+    IF EXISTS (SELECT 1 FROM tax_lots WHERE transaction_id = OLD.id)
+        OR EXISTS (SELECT 1 FROM realized_gains WHERE sell_transaction_id = OLD.id)
+        OR EXISTS (SELECT 1 FROM dividend_payments WHERE transaction_id = OLD.id) THEN
+        RAISE foreign_key_violation USING MESSAGE = format(
+            'transaction %s is still referenced', OLD.id
+        );
+    END IF;
+    -- Ledger entries outlive the transactions they settled
+    UPDATE ledger_entries SET transaction_id = NULL WHERE transaction_id = OLD.id;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE CONSTRAINT TRIGGER transactions_references_released
+    AFTER DELETE ON transactions
+    FOR EACH ROW EXECUTE FUNCTION release_transaction_references();
+
+CREATE INDEX idx_tax_lots_transaction ON tax_lots (transaction_id);
+CREATE INDEX idx_realized_gains_sell_transaction ON realized_gains (sell_transaction_id);
+CREATE INDEX idx_dividend_payments_transaction ON dividend_payments (transaction_id);
+CREATE INDEX idx_ledger_entries_transaction ON ledger_entries (transaction_id);
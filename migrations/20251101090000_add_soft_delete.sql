@@ -0,0 +1,79 @@
+-- Add migration script here
+-- Users, holdings and transactions are soft-deleted: deleted_at marks a row
+-- instead of removing it, reads leave marked rows out and uniqueness only
+-- holds among the rows that are not deleted.
+ALTER TABLE users ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE holdings ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE transactions ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE transactions_archive ADD COLUMN deleted_at TIMESTAMPTZ;
+
+ALTER TABLE users DROP CONSTRAINT users_email_key;
+CREATE UNIQUE INDEX users_email_key ON users (email) WHERE deleted_at IS NULL;
+
+DROP INDEX holdings_open_position;
+CREATE UNIQUE INDEX holdings_open_position ON holdings (portfolio_id, ticker)
+    WHERE closed_at IS NULL AND deleted_at IS NULL;
+
+-- Every transaction that is not deleted, hot or archived
+CREATE OR REPLACE VIEW transaction_history AS
+SELECT id, public_id, user_id, portfolio_id, ticker, quantity, price, fee, transaction_type,
+    created_at, updated_at
+FROM transactions
+WHERE deleted_at IS NULL
+UNION ALL
+SELECT id, public_id, user_id, portfolio_id, ticker, quantity, price, fee, transaction_type,
+    created_at, updated_at
+FROM transactions_archive
+WHERE deleted_at IS NULL;
+
+-- Deleted transactions are archived along with the others, still deleted
+CREATE OR REPLACE FUNCTION archive_transactions(cutoff TIMESTAMP, batch INT) RETURNS INT AS $$
+DECLARE
+    month TIMESTAMP;
+    partition TEXT;
+    moved INT;
+BEGIN
+    -- Instances archiving at the same time take turns
+    PERFORM pg_advisory_xact_lock(hashtext('archive_transactions'));
+
+    FOR month IN
+        SELECT DISTINCT date_trunc('month', created_at)
+        FROM (
+            SELECT created_at FROM transactions
+            WHERE created_at < cutoff
+            ORDER BY id
+            LIMIT batch
+        ) due
+    LOOP
+        partition := 'transactions_archive_' || to_char(month, 'YYYY_MM');
+        IF to_regclass(partition) IS NULL THEN
+            EXECUTE format(
+                'CREATE TABLE %I PARTITION OF transactions_archive FOR VALUES FROM (%L) TO (%L)',
+                partition,
+                month,
+                month + INTERVAL '1 month'
+            );
+        END IF;
+    END LOOP;
+
+    WITH due AS (
+        DELETE FROM transactions
+        WHERE id IN (
+            SELECT id FROM transactions
+            WHERE created_at < cutoff
+            ORDER BY id
+            LIMIT batch
+        )
+        RETURNING id, public_id, user_id, portfolio_id, ticker, quantity, price, fee,
+            transaction_type, created_at, updated_at, deleted_at
+    )
+    INSERT INTO transactions_archive (id, public_id, user_id, portfolio_id, ticker, quantity,
+        price, fee, transaction_type, created_at, updated_at, deleted_at)
+    SELECT id, public_id, user_id, portfolio_id, ticker, quantity, price, fee,
+        transaction_type, created_at, updated_at, deleted_at
+    FROM due;
+    GET DIAGNOSTICS moved = ROW_COUNT;
+
+    RETURN moved;
+END;
+$$ LANGUAGE plpgsql;
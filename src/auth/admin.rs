@@ -0,0 +1,100 @@
+use axum::extract::FromRequestParts;
+
+use crate::{
+    AppState, Error,
+    auth::jwt::Claims,
+    models::user::Role,
+    timing::{self, Phase},
+};
+
+/// Header carrying the admin API key
+pub const ADMIN_KEY_HEADER: &str = "x-admin-key";
+
+/// Extractor guarding admin endpoints
+///
+/// Succeeds when the request carries an `X-Admin-Key` header matching the
+/// configured `ADMIN_API_KEY`, or, without the header, the bearer token of an
+/// admin (see [`AdminClaims`]). Without a configured key only admins get in.
+pub struct AdminKey;
+
+impl<S> FromRequestParts<S> for AdminKey
+where
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        if !parts.headers.contains_key(ADMIN_KEY_HEADER) {
+            let AdminClaims(claims) = AdminClaims::from_request_parts(parts, state).await?;
+            tracing::info!(
+                "Admin request to {} by user ID {}",
+                parts.uri.path(),
+                claims.user_id
+            );
+            return Ok(AdminKey);
+        }
+
+        let _timing = timing::span(Phase::Auth).entered();
+
+        let state = parts.extensions.get::<AppState>().ok_or_else(|| {
+            tracing::error!("AppState extension missing for admin request");
+            Error::InternalServerError
+        })?;
+
+        let expected = state
+            .config
+            .admin_api_key
+            .as_deref()
+            .ok_or(Error::Unauthorized)?;
+
+        let provided = parts
+            .headers
+            .get(ADMIN_KEY_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .ok_or(Error::Unauthorized)?;
+
+        if provided != expected {
+            tracing::warn!("Rejected admin request with invalid key");
+            return Err(Error::Unauthorized);
+        }
+
+        Ok(AdminKey)
+    }
+}
+
+/// Extractor for endpoints reserved to admin accounts
+///
+/// Rejects requests without a valid token with `401` and tokens of other
+/// roles with `403`. Roles are read from the token, so a changed role takes
+/// effect at the user's next login.
+pub struct AdminClaims(pub Claims);
+
+impl<S> FromRequestParts<S> for AdminClaims
+where
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let claims = Claims::from_request_parts(parts, state)
+            .await
+            .map_err(|_| Error::Unauthorized)?;
+
+        if claims.role < Role::Admin {
+            tracing::warn!(
+                "Rejected admin request from user ID {} with role {}",
+                claims.user_id,
+                claims.role.as_str()
+            );
+            return Err(Error::Forbidden);
+        }
+
+        Ok(AdminClaims(claims))
+    }
+}
@@ -0,0 +1,79 @@
+//! # Email Changes
+//!
+//! Changing the email of an account takes effect only once the new address
+//! proves it receives mail: the request is kept in Redis under
+//! `email_change:{token}` for [`CONFIRMATION_TTL_SECS`] and the token is
+//! mailed to the new address. Confirming claims the token, so each can be
+//! used once; requesting again does not cancel earlier tokens.
+
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{
+    AppState, Error, Result,
+    timing::{self, Phase},
+};
+
+/// Seconds a confirmation token stays valid
+pub const CONFIRMATION_TTL_SECS: u64 = 24 * 3600;
+
+fn change_key(token: &str) -> String {
+    format!("email_change:{}", token)
+}
+
+/// Email change awaiting confirmation
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingEmailChange {
+    pub user_id: i32,
+    pub new_email: String,
+}
+
+/// Keep `change` until confirmed, returning the token confirming it
+pub async fn request(state: &AppState, change: &PendingEmailChange) -> Result<String> {
+    let token = Uuid::new_v4().simple().to_string();
+    let payload = serde_json::to_string(change).map_err(|e| {
+        tracing::error!("Failed to encode email change: {}", e);
+        Error::InternalServerError
+    })?;
+
+    async {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        conn.set_ex::<_, _, ()>(change_key(&token), payload, CONFIRMATION_TTL_SECS)
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await?;
+
+    Ok(token)
+}
+
+/// Claim the change confirmed by `token`, or `None` when it expired or was
+/// already confirmed
+pub async fn take(state: &AppState, token: &str) -> Result<Option<PendingEmailChange>> {
+    let payload: Option<String> = async {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        conn.get_del(change_key(token))
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await?;
+
+    payload
+        .map(|payload| {
+            serde_json::from_str(&payload)
+                .map_err(|e| Error::RedisError(format!("Malformed email change: {}", e)))
+        })
+        .transpose()
+}
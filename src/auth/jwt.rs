@@ -1,41 +1,124 @@
-use axum::extract::FromRequestParts;
+use std::collections::HashMap;
+
+use axum::extract::{FromRequestParts, OptionalFromRequestParts};
 use chrono::{Duration, Utc};
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::revocation;
+use crate::{
+    AppState,
+    config::Config,
+    error_reporting,
+    models::user::Role,
+    timing::{self, Phase},
+};
+
+/// `kid` of the signing key when none is configured
+pub const DEFAULT_KEY_ID: &str = "primary";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub user_id: i32, // user id
     pub exp: usize,   // expiration timestamp
+    #[serde(default)]
+    pub iat: usize, // issue timestamp, for revoking all tokens of a user
+    pub jti: String,  // token id, for revocation
+    #[serde(default)]
+    pub role: Role, // role when the token was issued
 }
 
-pub fn create_jwt(user_id: i32, secret: &str, expiration_hours: i64) -> anyhow::Result<String> {
-    let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(expiration_hours))
-        .ok_or_else(|| anyhow::anyhow!("Failed to calculate expiration time"))?
-        .timestamp();
-
-    let claims = Claims {
-        user_id,
-        exp: expiration as usize,
-    };
-
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_ref()),
-    )?;
-    Ok(token)
+/// Issues and validates access tokens
+///
+/// Built once from the configured secrets and lifetime and shared through
+/// [`AppState`], so no request reads the environment. Tokens name the key
+/// that signed them in their `kid` header, so a new signing key can be
+/// rolled out while tokens of the retired ones stay valid until they expire.
+pub struct AuthService {
+    key_id: String,
+    encoding_key: EncodingKey,
+    decoding_keys: HashMap<String, DecodingKey>,
+    expiration_hours: i64,
 }
 
-pub fn decode_jwt(token: &str, secret: &str) -> anyhow::Result<Claims> {
-    let data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_ref()),
-        &Validation::default(),
-    )?;
+impl AuthService {
+    pub fn new(secret: &str, expiration_hours: i64) -> Self {
+        AuthService::with_keys(DEFAULT_KEY_ID, secret, &[], expiration_hours)
+    }
+
+    /// Sign with `secret` as `key_id` and also accept tokens signed with the
+    /// retired `(kid, secret)` pairs of `verification_keys`
+    pub fn with_keys(
+        key_id: &str,
+        secret: &str,
+        verification_keys: &[(String, String)],
+        expiration_hours: i64,
+    ) -> Self {
+        let decoding_keys = verification_keys
+            .iter()
+            .map(|(kid, secret)| (kid.as_str(), secret.as_str()))
+            .chain([(key_id, secret)])
+            .map(|(kid, secret)| (kid.to_string(), DecodingKey::from_secret(secret.as_ref())))
+            .collect();
+
+        AuthService {
+            key_id: key_id.to_string(),
+            encoding_key: EncodingKey::from_secret(secret.as_ref()),
+            decoding_keys,
+            expiration_hours,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        AuthService::with_keys(
+            &config.jwt_key_id,
+            &config.jwt_secret,
+            &config.jwt_verification_keys,
+            config.jwt_expiration_hours,
+        )
+    }
+
+    /// Sign a new access token for `user_id` acting with `role`, returning it
+    /// with its claims
+    pub fn create_token(&self, user_id: i32, role: Role) -> anyhow::Result<(String, Claims)> {
+        let now = Utc::now();
+        let expiration = now
+            .checked_add_signed(Duration::hours(self.expiration_hours))
+            .ok_or_else(|| anyhow::anyhow!("Failed to calculate expiration time"))?
+            .timestamp();
 
-    Ok(data.claims)
+        let claims = Claims {
+            user_id,
+            exp: expiration as usize,
+            iat: now.timestamp() as usize,
+            jti: Uuid::new_v4().to_string(),
+            role,
+        };
+
+        let header = Header {
+            kid: Some(self.key_id.clone()),
+            ..Header::default()
+        };
+        let token = encode(&header, &claims, &self.encoding_key)?;
+        Ok((token, claims))
+    }
+
+    /// Check the signature and expiry of `token`
+    ///
+    /// Tokens without a `kid`, issued before keys had IDs, are checked
+    /// against the current signing key.
+    pub fn decode_token(&self, token: &str) -> anyhow::Result<Claims> {
+        let kid = decode_header(token)?.kid;
+        let kid = kid.as_deref().unwrap_or(&self.key_id);
+        let key = self
+            .decoding_keys
+            .get(kid)
+            .ok_or_else(|| anyhow::anyhow!("Unknown signing key {}", kid))?;
+        let data = decode::<Claims>(token, key, &Validation::default())?;
+
+        Ok(data.claims)
+    }
 }
 
 impl<S> FromRequestParts<S> for Claims
@@ -48,39 +131,87 @@ where
         parts: &mut axum::http::request::Parts,
         _state: &S,
     ) -> Result<Self, Self::Rejection> {
-        let auth_header = parts
-            .headers
-            .get(axum::http::header::AUTHORIZATION)
-            .and_then(|h| h.to_str().ok())
-            .ok_or((
-                axum::http::StatusCode::UNAUTHORIZED,
-                "Missing Authorization header".into(),
-            ))?;
-
-        if !auth_header.starts_with("Bearer ") {
-            return Err((
-                axum::http::StatusCode::UNAUTHORIZED,
-                "Invalid Authorization header".into(),
-            ));
-        }
-
-        let token = &auth_header[7..]; // Skip "Bearer "
-
-        let secret = std::env::var("JWT_SECRET").map_err(|_| {
-            tracing::error!("JWT_SECRET not set in environment");
+        let state = parts.extensions.get::<AppState>().ok_or_else(|| {
+            tracing::error!("AppState extension missing for authenticated request");
             (
                 axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                "Internal server error".into(),
+                "Internal server error".to_string(),
             )
         })?;
+        let claims = decode_request(parts, &state.auth)?;
 
-        let claims = decode_jwt(token, &secret).map_err(|_| {
-            (
-                axum::http::StatusCode::UNAUTHORIZED,
-                "Invalid or expired token".into(),
-            )
-        })?;
+        // An unreachable denylist must not lock every user out
+        match revocation::is_revoked(state, &claims).await {
+            Ok(true) => {
+                return Err((
+                    axum::http::StatusCode::UNAUTHORIZED,
+                    "Token has been revoked".into(),
+                ));
+            }
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Failed to check token revocation: {}", e),
+        }
+        error_reporting::set_user(claims.user_id);
 
         Ok(claims)
     }
 }
+
+/// Requests without an `Authorization` header are anonymous; a header with
+/// an invalid or revoked token is still rejected
+impl<S> OptionalFromRequestParts<S> for Claims
+where
+    S: Send + Sync,
+{
+    type Rejection = (axum::http::StatusCode, String);
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        if !parts
+            .headers
+            .contains_key(axum::http::header::AUTHORIZATION)
+        {
+            return Ok(None);
+        }
+        <Claims as FromRequestParts<S>>::from_request_parts(parts, state)
+            .await
+            .map(Some)
+    }
+}
+
+/// Validate the bearer token of a request
+fn decode_request(
+    parts: &axum::http::request::Parts,
+    auth: &AuthService,
+) -> Result<Claims, (axum::http::StatusCode, String)> {
+    let _timing = timing::span(Phase::Auth).entered();
+
+    let auth_header = parts
+        .headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .ok_or((
+            axum::http::StatusCode::UNAUTHORIZED,
+            "Missing Authorization header".into(),
+        ))?;
+
+    if !auth_header.starts_with("Bearer ") {
+        return Err((
+            axum::http::StatusCode::UNAUTHORIZED,
+            "Invalid Authorization header".into(),
+        ));
+    }
+
+    let token = &auth_header[7..]; // Skip "Bearer "
+
+    let claims = auth.decode_token(token).map_err(|_| {
+        (
+            axum::http::StatusCode::UNAUTHORIZED,
+            "Invalid or expired token".into(),
+        )
+    })?;
+
+    Ok(claims)
+}
@@ -0,0 +1,163 @@
+//! # Login Throttling
+//!
+//! Failed logins are counted per email and per client IP in Redis under
+//! `login_failures:{email|ip}:{id}`. Every failure for an email blocks its
+//! next attempt for a delay that doubles from one second; reaching
+//! `LOGIN_MAX_FAILURES` for an email, or `LOGIN_MAX_FAILURES_PER_IP` from an
+//! IP, blocks it for `LOGIN_LOCKOUT_SECS`. IPs only get the lockout, since
+//! users behind one address would otherwise slow each other down. Counters
+//! are forgotten after a lockout period without failures, and a successful
+//! login clears those of its email.
+//!
+//! Emails are counted whether or not an account exists, so being throttled
+//! says nothing about the email either.
+
+use std::net::IpAddr;
+
+use redis::AsyncCommands;
+use tracing::Instrument;
+
+use crate::{
+    AppState, Error, Result,
+    timing::{self, Phase},
+};
+
+/// Longest doubling delay before the lockout takes over
+const MAX_BACKOFF_EXPONENT: u32 = 16;
+
+fn failures_key(scope: &str, id: &str) -> String {
+    format!("login_failures:{}:{}", scope, id)
+}
+
+fn blocked_key(scope: &str, id: &str) -> String {
+    format!("login_blocked:{}:{}", scope, id)
+}
+
+/// A counter a login attempt is subject to
+struct Scope {
+    name: &'static str,
+    id: String,
+    limit: u32,
+    backoff: bool,
+}
+
+fn scopes(state: &AppState, email: &str, ip: IpAddr) -> [Scope; 2] {
+    [
+        Scope {
+            name: "email",
+            id: email.to_lowercase(),
+            limit: state.config.login_max_failures,
+            backoff: true,
+        },
+        Scope {
+            name: "ip",
+            id: ip.to_string(),
+            limit: state.config.login_max_failures_per_ip,
+            backoff: false,
+        },
+    ]
+}
+
+/// Seconds to block `scope` after its `failures`-th failure in a row
+fn block_secs(scope: &Scope, failures: u32, lockout_secs: u64) -> u64 {
+    if failures >= scope.limit {
+        return lockout_secs;
+    }
+    if !scope.backoff {
+        return 0;
+    }
+    let backoff = 1u64 << (failures.saturating_sub(1)).min(MAX_BACKOFF_EXPONENT);
+    backoff.min(lockout_secs)
+}
+
+/// Seconds until `email` may try to log in from `ip` again, if it is blocked
+pub async fn retry_after(state: &AppState, email: &str, ip: IpAddr) -> Result<Option<u64>> {
+    async {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+
+        let mut wait_ms = 0;
+        for scope in scopes(state, email, ip) {
+            // PTTL answers negative for missing keys
+            let ttl: i64 = conn
+                .pttl(blocked_key(scope.name, &scope.id))
+                .await
+                .map_err(|e| Error::RedisError(e.to_string()))?;
+            wait_ms = wait_ms.max(ttl);
+        }
+
+        Ok((wait_ms > 0).then(|| (wait_ms as u64 + 999) / 1000))
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await
+}
+
+/// Count a failed login of `email` from `ip` and block the next attempts
+pub async fn record_failure(state: &AppState, email: &str, ip: IpAddr) -> Result<()> {
+    let lockout_secs = state.config.login_lockout_secs;
+
+    async {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+
+        for scope in scopes(state, email, ip) {
+            let failures_key = failures_key(scope.name, &scope.id);
+            let (failures,): (u32,) = redis::pipe()
+                .atomic()
+                .incr(&failures_key, 1)
+                .expire(&failures_key, lockout_secs as i64)
+                .ignore()
+                .query_async(&mut *conn)
+                .await
+                .map_err(|e| Error::RedisError(e.to_string()))?;
+
+            let block = block_secs(&scope, failures, lockout_secs);
+            if block > 0 {
+                conn.set_ex::<_, _, ()>(blocked_key(scope.name, &scope.id), failures, block)
+                    .await
+                    .map_err(|e| Error::RedisError(e.to_string()))?;
+            }
+
+            if failures == scope.limit {
+                tracing::warn!(
+                    "Locking out logins by {} {} for {}s after {} failures",
+                    scope.name,
+                    scope.id,
+                    lockout_secs,
+                    failures
+                );
+            }
+        }
+
+        Ok(())
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await
+}
+
+/// Forget the failed logins of `email` after it logged in
+///
+/// The IP's count is kept, so one valid account does not let an address
+/// keep guessing the passwords of others.
+pub async fn clear(state: &AppState, email: &str) -> Result<()> {
+    let id = email.to_lowercase();
+
+    async {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        conn.del::<_, ()>(&[failures_key("email", &id), blocked_key("email", &id)])
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await
+}
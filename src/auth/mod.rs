@@ -1,2 +1,9 @@
+pub mod admin;
+pub mod api_key;
+pub mod email_change;
 pub mod jwt;
+pub mod lockout;
 pub mod password;
+pub mod portfolio;
+pub mod revocation;
+pub mod sessions;
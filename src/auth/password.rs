@@ -1,11 +1,18 @@
+use std::sync::OnceLock;
+
 use argon2::{
     Argon2,
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
 };
 
-use crate::{Error, Result};
+use crate::{
+    Error, Result,
+    config::Config,
+    timing::{self, Phase},
+};
 
 pub fn hash_password(password: &str) -> Result<String> {
+    let _timing = timing::span(Phase::Auth).entered();
     let salt = SaltString::generate(&mut OsRng);
 
     let argon2 = Argon2::default();
@@ -20,6 +27,7 @@ pub fn hash_password(password: &str) -> Result<String> {
 }
 
 pub fn verify_password(password: &str, password_hash: &str) -> Result<bool> {
+    let _timing = timing::span(Phase::Auth).entered();
     let parsed_hash = PasswordHash::new(password_hash).map_err(|e| {
         tracing::error!("Failed to parse password hash: {}", e);
         Error::InternalServerError
@@ -35,3 +43,54 @@ pub fn verify_password(password: &str, password_hash: &str) -> Result<bool> {
         }
     }
 }
+
+/// Take as long as checking a password when there is no hash to check
+///
+/// Keeps logins for unknown emails from answering measurably faster than
+/// those with a wrong password.
+pub fn verify_dummy_password(password: &str) {
+    static DUMMY_HASH: OnceLock<Option<String>> = OnceLock::new();
+
+    let hash = DUMMY_HASH.get_or_init(|| hash_password("not a real password").ok());
+    if let Some(hash) = hash.as_deref() {
+        let _ = verify_password(password, hash);
+    }
+}
+
+/// Refuse a new password weaker than the configured policy
+///
+/// Passwords are scored from 0 to 4 by zxcvbn, which finds common passwords,
+/// dictionary words, keyboard patterns, sequences and dates, and the
+/// account's `email`. Passwords on `PASSWORD_DENY_LIST` are refused whatever
+/// their score. Refusals say how to pick a better password.
+pub fn check_password_strength(config: &Config, password: &str, email: &str) -> Result<()> {
+    let _timing = timing::span(Phase::Auth).entered();
+    if config
+        .password_deny_list
+        .contains(&password.trim().to_lowercase())
+    {
+        return Err(Error::BadRequest(
+            "Password is too common: it is on the list of refused passwords. Choose another one"
+                .into(),
+        ));
+    }
+
+    let user_inputs: Vec<&str> = email.split('@').chain([email]).collect();
+    let entropy = zxcvbn::zxcvbn(password, &user_inputs);
+    if u8::from(entropy.score()) >= config.password_min_score {
+        return Ok(());
+    }
+
+    let mut advice = Vec::new();
+    if let Some(feedback) = entropy.feedback() {
+        advice.extend(feedback.warning().map(|warning| warning.to_string()));
+        advice.extend(feedback.suggestions().iter().map(ToString::to_string));
+    }
+    if advice.is_empty() {
+        advice.push("Use a longer password with a few uncommon words.".to_string());
+    }
+    Err(Error::BadRequest(format!(
+        "Password is too weak: {}",
+        advice.join(" ")
+    )))
+}
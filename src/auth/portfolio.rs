@@ -0,0 +1,105 @@
+use axum::{
+    extract::FromRequestParts,
+    response::{IntoResponse, Response},
+};
+use utoipa::{
+    IntoParams, PartialSchema,
+    openapi::{
+        Required,
+        path::{Parameter, ParameterBuilder, ParameterIn},
+    },
+};
+
+use crate::{
+    AppState, Error, auth::jwt::Claims, models::portfolio::Portfolio,
+    repository::portfolio_repository::PortfolioRepository,
+};
+
+/// Header selecting the portfolio a request acts on
+pub const PORTFOLIO_HEADER: &str = "x-portfolio-id";
+
+/// Extractor resolving the portfolio a trading request acts on
+///
+/// Uses the portfolio named by the `X-Portfolio-Id` header, which must belong
+/// to the authenticated user, or the user's default portfolio when the header
+/// is absent.
+pub struct SelectedPortfolio(pub Portfolio);
+
+impl<S> FromRequestParts<S> for SelectedPortfolio
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let claims = Claims::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        let app_state = parts.extensions.get::<AppState>().ok_or_else(|| {
+            tracing::error!("AppState extension missing for portfolio request");
+            Error::InternalServerError.into_response()
+        })?;
+
+        let selected = match parts.headers.get(PORTFOLIO_HEADER) {
+            Some(value) => Some(
+                value
+                    .to_str()
+                    .ok()
+                    .and_then(|v| v.trim().parse::<i32>().ok())
+                    .ok_or_else(|| {
+                        Error::BadRequest("Invalid X-Portfolio-Id header".into()).into_response()
+                    })?,
+            ),
+            None => None,
+        };
+
+        let portfolio = select(app_state, claims.user_id, selected)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        Ok(SelectedPortfolio(portfolio))
+    }
+}
+
+/// The portfolio of `user_id` with ID `selected`, or the user's default portfolio
+///
+/// Portfolios of other users are reported as missing.
+pub async fn select(
+    state: &AppState,
+    user_id: i32,
+    selected: Option<i32>,
+) -> crate::Result<Portfolio> {
+    let repository = PortfolioRepository::new(&state.pg_pool);
+    match selected {
+        Some(portfolio_id) => repository
+            .get_portfolio(portfolio_id)
+            .await?
+            .filter(|portfolio| portfolio.user_id == user_id)
+            .ok_or(Error::NotFound),
+        None => repository
+            .get_default_portfolio(user_id)
+            .await?
+            .ok_or(Error::Unauthorized),
+    }
+}
+
+/// Documents the `X-Portfolio-Id` header in the OpenAPI description
+impl IntoParams for SelectedPortfolio {
+    fn into_params(_: impl Fn() -> Option<ParameterIn>) -> Vec<Parameter> {
+        vec![
+            ParameterBuilder::new()
+                .name("X-Portfolio-Id")
+                .parameter_in(ParameterIn::Header)
+                .required(Required::False)
+                .description(Some(
+                    "Portfolio to act on, owned by the user; defaults to the default portfolio",
+                ))
+                .schema(Some(i32::schema()))
+                .build(),
+        ]
+    }
+}
@@ -0,0 +1,100 @@
+//! # Token Revocation
+//!
+//! Logging out revokes the access token by its `jti` claim. Revoked IDs are
+//! kept in Redis under `revoked_token:{jti}` until the token would have
+//! expired anyway, so the denylist never outgrows the tokens in circulation.
+//!
+//! Changing the password revokes every token of the user at once: tokens
+//! issued before the time kept under `revoked_before:{user_id}` are rejected
+//! until the last of them would have expired. A token issued within the same
+//! second as the change survives it.
+//!
+//! Revoked tokens stop counting as [`sessions`](super::sessions).
+
+use chrono::Utc;
+use redis::AsyncCommands;
+use tracing::Instrument;
+
+use super::{jwt::Claims, sessions};
+use crate::{
+    AppState, Error, Result,
+    timing::{self, Phase},
+};
+
+fn revoked_key(jti: &str) -> String {
+    format!("revoked_token:{}", jti)
+}
+
+fn revoked_before_key(user_id: i32) -> String {
+    format!("revoked_before:{}", user_id)
+}
+
+/// Reject the token carrying `claims` from now on
+pub async fn revoke(state: &AppState, claims: &Claims) -> Result<()> {
+    sessions::end(state, claims).await;
+    let remaining = claims.exp as i64 - Utc::now().timestamp();
+    if remaining <= 0 {
+        return Ok(());
+    }
+
+    async {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        conn.set_ex::<_, _, ()>(revoked_key(&claims.jti), claims.user_id, remaining as u64)
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await
+}
+
+/// Reject every token of `user_id` issued before now
+pub async fn revoke_all(state: &AppState, user_id: i32) -> Result<()> {
+    sessions::end_all(state, user_id).await;
+    let lifetime = state.config.jwt_expiration_hours.max(0) as u64 * 3600;
+    if lifetime == 0 {
+        return Ok(());
+    }
+
+    async {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        conn.set_ex::<_, _, ()>(
+            revoked_before_key(user_id),
+            Utc::now().timestamp(),
+            lifetime,
+        )
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await
+}
+
+/// Whether the token carrying `claims` was revoked, by itself or along with
+/// every other token of its user
+pub async fn is_revoked(state: &AppState, claims: &Claims) -> Result<bool> {
+    async {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        let (revoked, revoked_before): (Option<i32>, Option<i64>) = redis::pipe()
+            .get(revoked_key(&claims.jti))
+            .get(revoked_before_key(claims.user_id))
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+
+        Ok(revoked.is_some() || revoked_before.is_some_and(|before| (claims.iat as i64) < before))
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await
+}
@@ -0,0 +1,121 @@
+//! # Session Tracking
+//!
+//! Access tokens are stateless, so sessions are counted from the tokens
+//! issued: every token handed out at login goes into the Redis sorted set
+//! `sessions` as `{user_id}:{jti}`, scored by its expiry. Revoking a token
+//! removes it, revoking every token of a user removes all of theirs, and
+//! expired ones are dropped when counting.
+//!
+//! Tracking is best effort: a failure is logged and never fails a login.
+
+use chrono::Utc;
+use redis::AsyncCommands;
+use tracing::Instrument;
+
+use super::jwt::Claims;
+use crate::{
+    AppState, Error, Result,
+    timing::{self, Phase},
+};
+
+const SESSIONS_KEY: &str = "sessions";
+/// Members examined per `ZSCAN` round trip
+const SCAN_COUNT: usize = 500;
+
+fn member(user_id: i32, jti: &str) -> String {
+    format!("{}:{}", user_id, jti)
+}
+
+/// Count the session of a newly issued token
+pub async fn record(state: &AppState, claims: &Claims) {
+    let result = async {
+        let mut conn = state.redis_pool.get().await.map_err(|e| e.to_string())?;
+        conn.zadd::<_, _, _, ()>(
+            SESSIONS_KEY,
+            member(claims.user_id, &claims.jti),
+            claims.exp as i64,
+        )
+        .await
+        .map_err(|e| e.to_string())
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("Failed to record session: {}", e);
+    }
+}
+
+/// Stop counting the session of a revoked token
+pub async fn end(state: &AppState, claims: &Claims) {
+    let result = async {
+        let mut conn = state.redis_pool.get().await.map_err(|e| e.to_string())?;
+        conn.zrem::<_, _, ()>(SESSIONS_KEY, member(claims.user_id, &claims.jti))
+            .await
+            .map_err(|e| e.to_string())
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("Failed to end session: {}", e);
+    }
+}
+
+/// Stop counting every session of `user_id`
+pub async fn end_all(state: &AppState, user_id: i32) {
+    let result = async {
+        let mut conn = state.redis_pool.get().await.map_err(|e| e.to_string())?;
+        let pattern = format!("{}:*", user_id);
+        let mut members = Vec::new();
+        let mut cursor = 0u64;
+        loop {
+            let (next, page): (u64, Vec<(String, f64)>) = redis::cmd("ZSCAN")
+                .arg(SESSIONS_KEY)
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(SCAN_COUNT)
+                .query_async(&mut *conn)
+                .await
+                .map_err(|e| e.to_string())?;
+            members.extend(page.into_iter().map(|(member, _)| member));
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        if members.is_empty() {
+            return Ok(());
+        }
+        conn.zrem::<_, _, ()>(SESSIONS_KEY, members)
+            .await
+            .map_err(|e| e.to_string())
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("Failed to end sessions of user ID {}: {}", user_id, e);
+    }
+}
+
+/// Tokens issued and neither expired nor revoked
+pub async fn count(state: &AppState) -> Result<u64> {
+    async {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        let (active,): (u64,) = redis::pipe()
+            .atomic()
+            .zrembyscore(SESSIONS_KEY, "-inf", Utc::now().timestamp())
+            .ignore()
+            .zcard(SESSIONS_KEY)
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        Ok(active)
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await
+}
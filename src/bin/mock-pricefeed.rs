@@ -0,0 +1,298 @@
+//! # Mock Price Feed
+//!
+//! A stand-in for the external gRPC price feed, for development: it serves
+//! the `PriceFeed` service of `proto/pricefeed.proto` with prices it makes up,
+//! so the core runs against `PRICE_PROVIDER=grpc` without the real feed.
+//!
+//! Every ticker follows a random walk stepping every
+//! `MOCK_FEED_INTERVAL_MS`, each step a normal move of `MOCK_FEED_VOLATILITY`
+//! (a fraction of the price). Tickers start at the prices listed in
+//! `MOCK_FEED_TICKERS` as `TICKER=PRICE` pairs; tickers requested by a client
+//! and not listed there start at 100. With `MOCK_FEED_SEED` set, walks are
+//! drawn from a generator seeded with it. The feed listens on
+//! `MOCK_FEED_ADDR`.
+//!
+//! ```bash
+//! MOCK_FEED_TICKERS=AAPL=190,MSFT=420 cargo run --bin mock-pricefeed
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures_util::{Stream, StreamExt, stream};
+use rand::{SeedableRng, rngs::StdRng};
+use rand_distr::{Distribution, StandardNormal};
+use tokio::sync::broadcast;
+use tonic::{Request, Response, Status, transport::Server};
+
+use price_feed::{
+    PriceRequest, PriceResponse,
+    price_feed_server::{PriceFeed, PriceFeedServer},
+};
+
+mod price_feed {
+    tonic::include_proto!("pricefeed");
+}
+
+/// Price of tickers not listed in `MOCK_FEED_TICKERS`
+const DEFAULT_START_PRICE: f64 = 100.0;
+/// Lowest price a walk may reach, so prices never round to zero
+const MIN_PRICE: f64 = 0.01;
+/// Updates a slow client may fall behind before it misses some
+const UPDATE_BUFFER: usize = 4096;
+
+/// Settings read from the environment
+struct Settings {
+    addr: SocketAddr,
+    interval: Duration,
+    volatility: f64,
+    seed: Option<u64>,
+    tickers: Vec<(String, f64)>,
+}
+
+impl Settings {
+    fn from_env() -> anyhow::Result<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+        let addr = var("MOCK_FEED_ADDR")
+            .unwrap_or_else(|| "0.0.0.0:50051".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("MOCK_FEED_ADDR must be an ip:port"))?;
+        let interval_ms: u64 = var("MOCK_FEED_INTERVAL_MS")
+            .unwrap_or_else(|| "1000".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("MOCK_FEED_INTERVAL_MS must be a number"))?;
+        if interval_ms == 0 {
+            anyhow::bail!("MOCK_FEED_INTERVAL_MS must be positive");
+        }
+        let volatility: f64 = var("MOCK_FEED_VOLATILITY")
+            .unwrap_or_else(|| "0.002".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("MOCK_FEED_VOLATILITY must be a number"))?;
+        if !(0.0..1.0).contains(&volatility) {
+            anyhow::bail!("MOCK_FEED_VOLATILITY must be at least 0 and below 1");
+        }
+        let seed = var("MOCK_FEED_SEED")
+            .map(|seed| seed.parse())
+            .transpose()
+            .map_err(|_| anyhow::anyhow!("MOCK_FEED_SEED must be an integer"))?;
+
+        let mut tickers = Vec::new();
+        for entry in var("MOCK_FEED_TICKERS").unwrap_or_default().split(',') {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                continue;
+            }
+            let (ticker, price) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("MOCK_FEED_TICKERS entries are TICKER=PRICE"))?;
+            let price: f64 = price
+                .trim()
+                .parse()
+                .ok()
+                .filter(|price| *price >= MIN_PRICE)
+                .ok_or_else(|| anyhow::anyhow!("Invalid starting price of {}", ticker))?;
+            tickers.push((ticker.trim().to_string(), price));
+        }
+
+        Ok(Settings {
+            addr,
+            interval: Duration::from_millis(interval_ms),
+            volatility,
+            seed,
+            tickers,
+        })
+    }
+}
+
+/// Current prices of every ticker seen so far, and the walk moving them
+struct Market {
+    prices: Mutex<HashMap<String, f64>>,
+    rng: Mutex<StdRng>,
+    volatility: f64,
+    updates: broadcast::Sender<PriceResponse>,
+}
+
+impl Market {
+    fn new(settings: &Settings) -> Self {
+        let rng = match settings.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng(),
+        };
+        Market {
+            prices: Mutex::new(settings.tickers.iter().cloned().collect()),
+            rng: Mutex::new(rng),
+            volatility: settings.volatility,
+            updates: broadcast::channel(UPDATE_BUFFER).0,
+        }
+    }
+
+    /// Current prices of `tickers`, starting the walk of tickers not seen yet
+    fn quotes(&self, tickers: &[String]) -> Vec<PriceResponse> {
+        let mut prices = self.prices.lock().unwrap();
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        tickers
+            .iter()
+            .map(|ticker| PriceResponse {
+                ticker: ticker.clone(),
+                price: round(*prices.entry(ticker.clone()).or_insert(DEFAULT_START_PRICE)),
+                timestamp,
+            })
+            .collect()
+    }
+
+    /// Current prices of every ticker
+    fn all_quotes(&self) -> Vec<PriceResponse> {
+        let tickers: Vec<String> = self.prices.lock().unwrap().keys().cloned().collect();
+        self.quotes(&tickers)
+    }
+
+    /// Move every price one step and announce the new prices
+    fn step(&self) {
+        let mut prices = self.prices.lock().unwrap();
+        let mut rng = self.rng.lock().unwrap();
+        let timestamp = chrono::Utc::now().timestamp_millis();
+
+        // Sorted so a seeded feed draws the same moves for the same tickers
+        let mut tickers: Vec<&String> = prices.keys().collect();
+        tickers.sort();
+        let moves: Vec<(String, f64)> = tickers
+            .into_iter()
+            .map(|ticker| {
+                let z: f64 = StandardNormal.sample(&mut *rng);
+                let price = (prices[ticker] * (1.0 + self.volatility * z)).max(MIN_PRICE);
+                (ticker.clone(), price)
+            })
+            .collect();
+
+        for (ticker, price) in moves {
+            prices.insert(ticker.clone(), price);
+            // Nobody may be listening yet
+            let _ = self.updates.send(PriceResponse {
+                ticker,
+                price: round(price),
+                timestamp,
+            });
+        }
+    }
+}
+
+fn round(price: f64) -> f64 {
+    (price * 100.0).round() / 100.0
+}
+
+/// Tickers a request asks for; `None` for every ticker
+fn requested(request: &PriceRequest) -> Option<Vec<String>> {
+    if !request.tickers.is_empty() {
+        return Some(request.tickers.clone());
+    }
+    match request.ticker.as_str() {
+        "" | "ALL" => None,
+        ticker => Some(vec![ticker.to_string()]),
+    }
+}
+
+struct MockPriceFeed {
+    market: Arc<Market>,
+}
+
+#[tonic::async_trait]
+impl PriceFeed for MockPriceFeed {
+    async fn get_price(
+        &self,
+        request: Request<PriceRequest>,
+    ) -> Result<Response<PriceResponse>, Status> {
+        let ticker = request.into_inner().ticker;
+        if ticker.is_empty() || ticker == "ALL" {
+            return Err(Status::invalid_argument("ticker must name a single ticker"));
+        }
+
+        let quote = self.market.quotes(&[ticker]).remove(0);
+        Ok(Response::new(quote))
+    }
+
+    type StreamPricesStream = Pin<Box<dyn Stream<Item = Result<PriceResponse, Status>> + Send>>;
+
+    async fn stream_prices(
+        &self,
+        request: Request<PriceRequest>,
+    ) -> Result<Response<Self::StreamPricesStream>, Status> {
+        let tickers = requested(request.get_ref());
+        // Subscribe before reading the current prices, so no step falls in between
+        let updates = self.market.updates.subscribe();
+        let current = match &tickers {
+            Some(tickers) => self.market.quotes(tickers),
+            None => self.market.all_quotes(),
+        };
+        tracing::info!(
+            "Streaming {} to a client",
+            tickers
+                .as_ref()
+                .map_or("every ticker".to_string(), |t| t.join(","))
+        );
+
+        let filter: Option<HashSet<String>> = tickers.map(|tickers| tickers.into_iter().collect());
+        let updates = stream::unfold(updates, |mut updates| async move {
+            loop {
+                match updates.recv().await {
+                    Ok(update) => return Some((update, updates)),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("Slow client missed {} updates", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+        .filter(move |update| {
+            let wanted = filter
+                .as_ref()
+                .map_or(true, |filter| filter.contains(&update.ticker));
+            async move { wanted }
+        });
+
+        let prices = stream::iter(current).chain(updates).map(Ok);
+        Ok(Response::new(Box::pin(prices)))
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()),
+        )
+        .init();
+
+    let settings = Settings::from_env()?;
+    let market = Arc::new(Market::new(&settings));
+
+    let stepping = market.clone();
+    let interval = settings.interval;
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            ticks.tick().await;
+            stepping.step();
+        }
+    });
+
+    tracing::info!(
+        "Serving mock prices of {} listed tickers on {}",
+        settings.tickers.len(),
+        settings.addr
+    );
+    Server::builder()
+        .add_service(PriceFeedServer::new(MockPriceFeed { market }))
+        .serve_with_shutdown(settings.addr, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,893 @@
+//! # Typed API Client
+//!
+//! A `reqwest`-based client for the simulator's REST API, available with the
+//! `client` feature. Request and response types mirror the server DTOs, so bot
+//! authors don't have to hand-write them.
+//!
+//! ```no_run
+//! # async fn run() -> stock_exchange_sim_core::client::Result<()> {
+//! use stock_exchange_sim_core::client::Client;
+//!
+//! let mut client = Client::new("http://localhost:3000");
+//! client.login("bot@example.com", "secure_password").await?;
+//! let trade = client.buy("AAPL", 10).await?;
+//! println!("Bought {} {} at {}", trade.quantity, trade.ticker, trade.price);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Trading, balance, holdings and loan calls act on the user's default
+//! portfolio unless another one is selected with [`Client::with_portfolio`].
+//!
+//! The client speaks version 1 of the API, under `/api/v1`. Programs trading
+//! over gRPC instead use the generated client in [`trading`].
+
+use std::collections::BTreeMap;
+
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use reqwest::{RequestBuilder, StatusCode};
+use serde::{Serialize, de::DeserializeOwned};
+use uuid::Uuid;
+
+pub mod trading;
+pub mod types;
+pub mod ws;
+
+use types::{
+    AmountRequest, ApiKey, Basket, Bond, Candle, CandleQuery, CashAmountRequest, CashHistoryPage,
+    CashMovement, ChangeEmailRequest, ChangePasswordRequest, Class, ClassDashboard,
+    ClosedPositionPage, Collateral, Competition, CompetitionStandings, ConfirmEmailRequest,
+    CostBasisMethod, CreateApiKeyRequest, CreateClassRequest, CreateLoanRequest,
+    CreatePortfolioRequest, CreateWebhookRequest, CreatedApiKey, CreatedWebhook, Credentials,
+    DeliveryPage, Difficulty, ErrorResponse, FeedPage, Follow, Health, Holding, ImportReport,
+    InstrumentMatch, JoinClassRequest, Leaderboard, LeaderboardPeriod, LedgerPage, Loan,
+    LoginResponse, MarketDepth, MarketMovers, NewsItem, OptionChain, OptionOrderRequest,
+    OptionPosition, OptionTrade, PerformanceMetrics, Portfolio, PortfolioInfo, PortfolioSnapshot,
+    Profile, PublicPortfolio, PublicProfile, Quote, QuotesRequest, RealizedGainsReport, Settings,
+    TradeRequest, Transaction, TransactionPage, TransactionQuery, TransferRequest,
+    UpdateClassRequest, UpdatePortfolioRequest, UpdateProfileRequest, UpdateSettingsRequest,
+    Webhook,
+};
+
+/// Header selecting the portfolio a request acts on
+const PORTFOLIO_HEADER: &str = "X-Portfolio-Id";
+/// Path prefix of the API version the client speaks
+const API_PREFIX: &str = "/api/v1";
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// Errors returned by the API client
+#[derive(Debug)]
+pub enum ClientError {
+    /// The request could not be sent or the response could not be decoded
+    Http(reqwest::Error),
+    /// The server answered with a non-success status code
+    Api { status: StatusCode, message: String },
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Http(e) => write!(f, "HTTP error: {}", e),
+            ClientError::Api { status, message } => {
+                write!(f, "API error ({}): {}", status, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(e: reqwest::Error) -> Self {
+        ClientError::Http(e)
+    }
+}
+
+/// Client for the simulator REST API
+///
+/// Holds the base URL, the selected portfolio and, after a successful
+/// [`Client::login`], the bearer token used for all authenticated endpoints.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+    portfolio_id: Option<i32>,
+}
+
+impl Client {
+    /// Create a client for the API served at `base_url` (e.g. `http://localhost:3000`)
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_http_client(reqwest::Client::new(), base_url)
+    }
+
+    /// Create a client reusing an existing `reqwest::Client`
+    pub fn with_http_client(http: reqwest::Client, base_url: impl Into<String>) -> Self {
+        Client {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: None,
+            portfolio_id: None,
+        }
+    }
+
+    /// Use an existing access token instead of logging in
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Act on the given portfolio instead of the user's default one
+    pub fn with_portfolio(mut self, portfolio_id: i32) -> Self {
+        self.portfolio_id = Some(portfolio_id);
+        self
+    }
+
+    /// The access token currently in use, if any
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// URL of the WebSocket endpoint, requesting the protocol version of
+    /// [`ws::ServerMessage`]; connect with the `Authorization: Bearer` header
+    pub fn ws_url(&self) -> String {
+        let url = self
+            .base_url
+            .replacen("https://", "wss://", 1)
+            .replacen("http://", "ws://", 1);
+        format!("{}/ws?version={}", url, ws::PROTOCOL_VERSION)
+    }
+
+    /// WebSocket URL restoring the subscriptions of `session`, the ID from an
+    /// earlier connection's welcome frame, and replaying missed account events
+    pub fn ws_resume_url(&self, session: &str) -> String {
+        format!("{}&resume={}", self.ws_url(), session)
+    }
+
+    pub async fn register(&self, email: &str, password: &str) -> Result<String> {
+        self.post("/auth/register", &credentials(email, password))
+            .await
+    }
+
+    /// Log in and remember the returned access token for subsequent calls
+    pub async fn login(&mut self, email: &str, password: &str) -> Result<LoginResponse> {
+        let response: LoginResponse = self
+            .post("/auth/login", &credentials(email, password))
+            .await?;
+        self.token = Some(response.access_token.clone());
+        Ok(response)
+    }
+
+    pub async fn logout(&self) -> Result<String> {
+        self.send(self.request(reqwest::Method::POST, "/auth/logout"))
+            .await
+    }
+
+    /// Change the password, which signs out every session; the client keeps
+    /// working with the fresh token of the response
+    pub async fn change_password(
+        &mut self,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<LoginResponse> {
+        let request = ChangePasswordRequest {
+            current_password: current_password.to_string(),
+            new_password: new_password.to_string(),
+        };
+        let response: LoginResponse = self.post("/auth/change-password", &request).await?;
+        self.token = Some(response.access_token.clone());
+        Ok(response)
+    }
+
+    /// Mail a confirmation token to `new_email`, see [`Client::confirm_email`]
+    pub async fn change_email(&self, new_email: &str, password: &str) -> Result<String> {
+        let request = ChangeEmailRequest {
+            new_email: new_email.to_string(),
+            password: password.to_string(),
+        };
+        self.post("/auth/change-email", &request).await
+    }
+
+    /// Finish an email change with the token mailed to the new address
+    pub async fn confirm_email(&self, token: &str) -> Result<String> {
+        let request = ConfirmEmailRequest {
+            token: token.to_string(),
+        };
+        self.post("/auth/confirm-email", &request).await
+    }
+
+    pub async fn balance(&self) -> Result<BigDecimal> {
+        self.get("/balance").await
+    }
+
+    /// Deposit `amount`, a decimal string with at most two decimal places
+    pub async fn deposit(&self, amount: &str) -> Result<CashMovement> {
+        self.post(
+            "/balance/deposit",
+            &CashAmountRequest {
+                amount: amount.to_string(),
+            },
+        )
+        .await
+    }
+
+    /// Withdraw `amount`, a decimal string with at most two decimal places
+    pub async fn withdraw(&self, amount: &str) -> Result<CashMovement> {
+        self.post(
+            "/balance/withdraw",
+            &CashAmountRequest {
+                amount: amount.to_string(),
+            },
+        )
+        .await
+    }
+
+    /// One page of the selected portfolio's deposits and withdrawals, newest first
+    pub async fn cash_history(
+        &self,
+        before: Option<i32>,
+        limit: Option<usize>,
+    ) -> Result<CashHistoryPage> {
+        let mut request = self.request(reqwest::Method::GET, "/balance/history");
+        if let Some(before) = before {
+            request = request.query(&[("before", before)]);
+        }
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        self.send(request).await
+    }
+
+    /// One page of the selected portfolio's cash ledger, newest entry first
+    pub async fn ledger(&self, before: Option<i64>, limit: Option<usize>) -> Result<LedgerPage> {
+        let mut request = self.request(reqwest::Method::GET, "/balance/ledger");
+        if let Some(before) = before {
+            request = request.query(&[("before", before)]);
+        }
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        self.send(request).await
+    }
+
+    /// The selected portfolio's whole transaction history, newest first,
+    /// following the cursor through every page
+    pub async fn transactions(&self) -> Result<Vec<Transaction>> {
+        let mut query = TransactionQuery::default();
+        let mut transactions = Vec::new();
+        loop {
+            let page = self.transaction_page(&query).await?;
+            transactions.extend(page.transactions);
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => return Ok(transactions),
+            }
+        }
+    }
+
+    /// One page of transactions matching `query`
+    pub async fn transaction_page(&self, query: &TransactionQuery) -> Result<TransactionPage> {
+        self.send(
+            self.request(reqwest::Method::GET, "/transactions")
+                .query(query),
+        )
+        .await
+    }
+
+    /// One page of archived transactions matching `query`
+    pub async fn archived_transaction_page(
+        &self,
+        query: &TransactionQuery,
+    ) -> Result<TransactionPage> {
+        self.send(
+            self.request(reqwest::Method::GET, "/transactions/archived")
+                .query(query),
+        )
+        .await
+    }
+
+    /// Import a CSV trade history into the selected portfolio
+    pub async fn import_transactions(&self, csv: &str) -> Result<ImportReport> {
+        let file = reqwest::multipart::Part::text(csv.to_string())
+            .file_name("transactions.csv")
+            .mime_str("text/csv")?;
+        self.send(
+            self.request(reqwest::Method::POST, "/transactions/import")
+                .multipart(reqwest::multipart::Form::new().part("file", file)),
+        )
+        .await
+    }
+
+    pub async fn buy(&self, ticker: &str, quantity: i32) -> Result<Transaction> {
+        self.post("/transactions/buy", &trade(ticker, quantity))
+            .await
+    }
+
+    pub async fn sell(&self, ticker: &str, quantity: i32) -> Result<Transaction> {
+        self.post("/transactions/sell", &trade(ticker, quantity))
+            .await
+    }
+
+    pub async fn holdings(&self) -> Result<Vec<Holding>> {
+        self.get("/holdings").await
+    }
+
+    /// One page of the selected portfolio's closed positions, newest first
+    pub async fn closed_positions(
+        &self,
+        before: Option<Uuid>,
+        limit: Option<usize>,
+    ) -> Result<ClosedPositionPage> {
+        let mut request = self.request(reqwest::Method::GET, "/holdings/closed");
+        if let Some(before) = before {
+            request = request.query(&[("before", before)]);
+        }
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        self.send(request).await
+    }
+
+    pub async fn portfolio(&self) -> Result<Portfolio> {
+        self.get("/portfolio").await
+    }
+
+    /// Daily equity snapshots between `from` and `to` (inclusive); the server
+    /// defaults to the last year when both are omitted
+    pub async fn portfolio_history(
+        &self,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<Vec<PortfolioSnapshot>> {
+        self.send(
+            self.request(reqwest::Method::GET, "/portfolio/history")
+                .query(&date_range(from, to)),
+        )
+        .await
+    }
+
+    /// Performance statistics over the snapshots between `from` and `to`
+    pub async fn portfolio_metrics(
+        &self,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<PerformanceMetrics> {
+        self.send(
+            self.request(reqwest::Method::GET, "/portfolio/metrics")
+                .query(&date_range(from, to)),
+        )
+        .await
+    }
+
+    /// Realized gains report for `year`; the server defaults to the current year
+    pub async fn realized_gains(&self, year: Option<i32>) -> Result<RealizedGainsReport> {
+        let mut request = self.request(reqwest::Method::GET, "/reports/realized-gains");
+        if let Some(year) = year {
+            request = request.query(&[("year", year)]);
+        }
+        self.send(request).await
+    }
+
+    /// Last price, day change and day range of `ticker`
+    pub async fn quote(&self, ticker: &str) -> Result<Quote> {
+        self.get(&format!("/market/quote/{}", ticker)).await
+    }
+
+    /// Quotes of several tickers in one request; tickers without a price are left out
+    pub async fn quotes(&self, tickers: &[&str]) -> Result<Vec<Quote>> {
+        self.post(
+            "/market/quotes",
+            &QuotesRequest {
+                tickers: tickers.iter().map(|t| t.to_string()).collect(),
+            },
+        )
+        .await
+    }
+
+    /// Today's top gainers, losers and most traded tickers; the server lists
+    /// 5 of each unless `limit` is given
+    pub async fn market_movers(&self, limit: Option<usize>) -> Result<MarketMovers> {
+        let mut request = self.request(reqwest::Method::GET, "/market/movers");
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        self.send(request).await
+    }
+
+    /// Synthetic order book of a ticker; the server shows 10 levels per side
+    /// unless `levels` is given
+    pub async fn market_depth(&self, ticker: &str, levels: Option<usize>) -> Result<MarketDepth> {
+        let mut request = self.request(reqwest::Method::GET, &format!("/market/depth/{}", ticker));
+        if let Some(levels) = levels {
+            request = request.query(&[("levels", levels)]);
+        }
+        self.send(request).await
+    }
+
+    /// Published news, latest first, optionally of one ticker; the server
+    /// returns 20 items unless `limit` is given
+    pub async fn market_news(
+        &self,
+        ticker: Option<&str>,
+        limit: Option<i64>,
+    ) -> Result<Vec<NewsItem>> {
+        let mut request = self.request(reqwest::Method::GET, "/market/news");
+        if let Some(ticker) = ticker {
+            request = request.query(&[("ticker", ticker)]);
+        }
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        self.send(request).await
+    }
+
+    /// Active instruments matching `query` by symbol or name, best match first;
+    /// the server returns 10 results unless `limit` is given
+    pub async fn search_instruments(
+        &self,
+        query: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<InstrumentMatch>> {
+        let mut request = self
+            .request(reqwest::Method::GET, "/market/search")
+            .query(&[("q", query)]);
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        self.send(request).await
+    }
+
+    /// OHLCV candles of `ticker`, oldest first
+    pub async fn candles(&self, ticker: &str, query: &CandleQuery) -> Result<Vec<Candle>> {
+        self.send(
+            self.request(reqwest::Method::GET, &format!("/market/candles/{}", ticker))
+                .query(query),
+        )
+        .await
+    }
+
+    /// Terms of the bond `ticker`, with its accrued interest
+    pub async fn bond(&self, ticker: &str) -> Result<Bond> {
+        self.get(&format!("/market/bonds/{}", ticker)).await
+    }
+
+    /// Constituents of the index or ETF `ticker`, with its derived price
+    pub async fn basket(&self, ticker: &str) -> Result<Basket> {
+        self.get(&format!("/market/baskets/{}", ticker)).await
+    }
+
+    pub async fn loans(&self) -> Result<Vec<Loan>> {
+        self.get("/loans").await
+    }
+
+    /// Borrow `amount` against the pledged `collateral`
+    pub async fn borrow(&self, amount: f64, collateral: Vec<Collateral>) -> Result<Loan> {
+        self.post("/loans", &CreateLoanRequest { amount, collateral })
+            .await
+    }
+
+    /// Repay up to `amount` of a loan; paying the outstanding amount closes it
+    pub async fn repay_loan(&self, loan_id: i32, amount: f64) -> Result<Loan> {
+        self.post(
+            &format!("/loans/{}/repay", loan_id),
+            &AmountRequest { amount },
+        )
+        .await
+    }
+
+    /// Unexpired option contracts on `ticker` with their model prices
+    pub async fn option_chain(&self, ticker: &str) -> Result<OptionChain> {
+        self.get(&format!("/options/chain/{}", ticker)).await
+    }
+
+    pub async fn option_positions(&self) -> Result<Vec<OptionPosition>> {
+        self.get("/options/positions").await
+    }
+
+    /// Buy `quantity` contracts, closing a short position first
+    pub async fn buy_option(&self, contract_id: i32, quantity: i32) -> Result<OptionTrade> {
+        self.post(
+            "/options/buy",
+            &OptionOrderRequest {
+                contract_id,
+                quantity,
+            },
+        )
+        .await
+    }
+
+    /// Sell `quantity` contracts, writing them when none are held
+    pub async fn sell_option(&self, contract_id: i32, quantity: i32) -> Result<OptionTrade> {
+        self.post(
+            "/options/sell",
+            &OptionOrderRequest {
+                contract_id,
+                quantity,
+            },
+        )
+        .await
+    }
+
+    pub async fn portfolios(&self) -> Result<Vec<PortfolioInfo>> {
+        self.get("/portfolios").await
+    }
+
+    pub async fn create_portfolio(&self, name: &str) -> Result<PortfolioInfo> {
+        self.post(
+            "/portfolios",
+            &CreatePortfolioRequest {
+                name: name.to_string(),
+            },
+        )
+        .await
+    }
+
+    /// Share a portfolio and its trades with the user's followers, or stop
+    pub async fn set_portfolio_public(
+        &self,
+        portfolio_id: i32,
+        is_public: bool,
+    ) -> Result<PortfolioInfo> {
+        self.send(
+            self.request(
+                reqwest::Method::PATCH,
+                &format!("/portfolios/{}", portfolio_id),
+            )
+            .json(&UpdatePortfolioRequest {
+                is_public: Some(is_public),
+            }),
+        )
+        .await
+    }
+
+    /// Move cash between two of the user's portfolios
+    pub async fn transfer(
+        &self,
+        from_portfolio_id: i32,
+        to_portfolio_id: i32,
+        amount: f64,
+    ) -> Result<String> {
+        self.post(
+            "/portfolios/transfer",
+            &TransferRequest {
+                from_portfolio_id,
+                to_portfolio_id,
+                amount,
+            },
+        )
+        .await
+    }
+
+    pub async fn competitions(&self) -> Result<Vec<Competition>> {
+        self.get("/competitions").await
+    }
+
+    /// Join a competition; trade in it by selecting the returned `portfolio_id`
+    /// with [`Client::with_portfolio`]
+    pub async fn join_competition(&self, competition_id: i32) -> Result<Competition> {
+        self.send(self.request(
+            reqwest::Method::POST,
+            &format!("/competitions/{}/join", competition_id),
+        ))
+        .await
+    }
+
+    pub async fn competition_standings(&self, competition_id: i32) -> Result<CompetitionStandings> {
+        self.get(&format!("/competitions/{}/standings", competition_id))
+            .await
+    }
+
+    /// Classes the user teaches or attends
+    pub async fn classes(&self) -> Result<Vec<Class>> {
+        self.get("/classes").await
+    }
+
+    /// Create a class taught by the user
+    pub async fn create_class(&self, class: &CreateClassRequest) -> Result<Class> {
+        self.post("/classes", class).await
+    }
+
+    /// Join a class as a student; trade in it by selecting the returned
+    /// `portfolio_id` with [`Client::with_portfolio`]
+    pub async fn join_class(&self, invite_code: &str) -> Result<Class> {
+        self.post(
+            "/classes/join",
+            &JoinClassRequest {
+                invite_code: invite_code.to_string(),
+            },
+        )
+        .await
+    }
+
+    pub async fn class(&self, class_id: i32) -> Result<Class> {
+        self.get(&format!("/classes/{}", class_id)).await
+    }
+
+    pub async fn update_class(&self, class_id: i32, class: &UpdateClassRequest) -> Result<Class> {
+        self.send(
+            self.request(reqwest::Method::PATCH, &format!("/classes/{}", class_id))
+                .json(class),
+        )
+        .await
+    }
+
+    /// Replace the invite code of a class the user teaches
+    pub async fn reset_class_invite_code(&self, class_id: i32) -> Result<Class> {
+        self.send(self.request(
+            reqwest::Method::POST,
+            &format!("/classes/{}/invite-code", class_id),
+        ))
+        .await
+    }
+
+    pub async fn class_dashboard(&self, class_id: i32) -> Result<ClassDashboard> {
+        self.get(&format!("/classes/{}/dashboard", class_id)).await
+    }
+
+    pub async fn user(&self, user_id: Uuid) -> Result<PublicProfile> {
+        self.get(&format!("/users/{}", user_id)).await
+    }
+
+    pub async fn follow(&self, user_id: Uuid) -> Result<String> {
+        self.send(self.request(reqwest::Method::PUT, &format!("/users/{}/follow", user_id)))
+            .await
+    }
+
+    pub async fn unfollow(&self, user_id: Uuid) -> Result<String> {
+        self.send(self.request(
+            reqwest::Method::DELETE,
+            &format!("/users/{}/follow", user_id),
+        ))
+        .await
+    }
+
+    pub async fn followers(&self, user_id: Uuid) -> Result<Vec<Follow>> {
+        self.get(&format!("/users/{}/followers", user_id)).await
+    }
+
+    pub async fn following(&self, user_id: Uuid) -> Result<Vec<Follow>> {
+        self.get(&format!("/users/{}/following", user_id)).await
+    }
+
+    pub async fn public_portfolio(
+        &self,
+        user_id: Uuid,
+        portfolio_id: i32,
+    ) -> Result<PublicPortfolio> {
+        self.get(&format!("/users/{}/portfolios/{}", user_id, portfolio_id))
+            .await
+    }
+
+    /// One page of followed users' trades, newest first; pass the previous
+    /// page's `next_cursor` as `before` to continue
+    pub async fn feed(&self, before: Option<Uuid>, limit: Option<usize>) -> Result<FeedPage> {
+        let mut request = self.request(reqwest::Method::GET, "/feed");
+        if let Some(before) = before {
+            request = request.query(&[("before", before)]);
+        }
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        self.send(request).await
+    }
+
+    pub async fn settings(&self) -> Result<Settings> {
+        self.get("/settings").await
+    }
+
+    pub async fn update_settings(&self, settings: &UpdateSettingsRequest) -> Result<Settings> {
+        self.send(
+            self.request(reqwest::Method::PATCH, "/settings")
+                .json(settings),
+        )
+        .await
+    }
+
+    pub async fn profile(&self) -> Result<Profile> {
+        self.get("/me").await
+    }
+
+    pub async fn update_profile(&self, profile: &UpdateProfileRequest) -> Result<Profile> {
+        self.send(self.request(reqwest::Method::PATCH, "/me").json(profile))
+            .await
+    }
+
+    pub async fn api_keys(&self) -> Result<Vec<ApiKey>> {
+        self.get("/me/api-keys").await
+    }
+
+    /// Create an API key for the gRPC trading API; the key cannot be retrieved later
+    pub async fn create_api_key(&self, name: &str) -> Result<CreatedApiKey> {
+        let body = CreateApiKeyRequest {
+            name: name.to_string(),
+        };
+        self.post("/me/api-keys", &body).await
+    }
+
+    pub async fn delete_api_key(&self, api_key_id: i32) -> Result<String> {
+        self.send(self.request(
+            reqwest::Method::DELETE,
+            &format!("/me/api-keys/{}", api_key_id),
+        ))
+        .await
+    }
+
+    pub async fn webhooks(&self) -> Result<Vec<Webhook>> {
+        self.get("/webhooks").await
+    }
+
+    /// Register `url` for account events of the given kinds; the signing
+    /// secret cannot be retrieved later
+    pub async fn create_webhook(&self, url: &str, events: &[&str]) -> Result<CreatedWebhook> {
+        let body = CreateWebhookRequest {
+            url: url.to_string(),
+            events: events.iter().map(|event| event.to_string()).collect(),
+        };
+        self.post("/webhooks", &body).await
+    }
+
+    pub async fn delete_webhook(&self, webhook_id: i32) -> Result<String> {
+        self.send(self.request(
+            reqwest::Method::DELETE,
+            &format!("/webhooks/{}", webhook_id),
+        ))
+        .await
+    }
+
+    /// Delivery log of a webhook, newest first
+    pub async fn webhook_deliveries(
+        &self,
+        webhook_id: i32,
+        before: Option<i64>,
+        limit: Option<usize>,
+    ) -> Result<DeliveryPage> {
+        let mut request = self.request(
+            reqwest::Method::GET,
+            &format!("/webhooks/{}/deliveries", webhook_id),
+        );
+        if let Some(before) = before {
+            request = request.query(&[("before", before)]);
+        }
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        self.send(request).await
+    }
+
+    /// Users with the best returns over `period`; the server shows 10 unless
+    /// `limit` is given
+    pub async fn leaderboard(
+        &self,
+        period: LeaderboardPeriod,
+        limit: Option<usize>,
+    ) -> Result<Leaderboard> {
+        let mut request = self
+            .request(reqwest::Method::GET, "/leaderboard")
+            .query(&[("period", period)]);
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        self.send(request).await
+    }
+
+    /// Every feature and whether it is on for the user
+    pub async fn features(&self) -> Result<BTreeMap<String, bool>> {
+        self.get("/me/features").await
+    }
+
+    pub async fn set_cost_basis_method(
+        &self,
+        cost_basis_method: CostBasisMethod,
+    ) -> Result<Settings> {
+        self.update_settings(&UpdateSettingsRequest {
+            cost_basis_method: Some(cost_basis_method),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Opt into or out of reinvesting dividends into additional shares
+    pub async fn set_drip_enabled(&self, enabled: bool) -> Result<Settings> {
+        self.update_settings(&UpdateSettingsRequest {
+            drip_enabled: Some(enabled),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Opt into or out of the nightly money market cash sweep
+    pub async fn set_cash_sweep_enabled(&self, enabled: bool) -> Result<Settings> {
+        self.update_settings(&UpdateSettingsRequest {
+            cash_sweep_enabled: Some(enabled),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Compare the portfolio history and metrics against `ticker`, or stop comparing
+    pub async fn set_benchmark(&self, ticker: Option<&str>) -> Result<Settings> {
+        self.update_settings(&UpdateSettingsRequest {
+            benchmark_ticker: Some(ticker.unwrap_or_default().to_string()),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Trade and borrow under the `difficulty` preset outside competitions
+    pub async fn set_difficulty(&self, difficulty: Difficulty) -> Result<Settings> {
+        self.update_settings(&UpdateSettingsRequest {
+            difficulty: Some(difficulty),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Service health, including the state of the price feed
+    pub async fn health(&self) -> Result<Health> {
+        self.send(self.http.get(format!("{}/health", self.base_url)))
+            .await
+    }
+
+    /// Request to `path` of the API version this client speaks
+    fn request(&self, method: reqwest::Method, path: &str) -> RequestBuilder {
+        let mut builder = self
+            .http
+            .request(method, format!("{}{}{}", self.base_url, API_PREFIX, path));
+        if let Some(portfolio_id) = self.portfolio_id {
+            builder = builder.header(PORTFOLIO_HEADER, portfolio_id);
+        }
+        match &self.token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.send(self.request(reqwest::Method::GET, path)).await
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        self.send(self.request(reqwest::Method::POST, path).json(body))
+            .await
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let response = request.send().await?;
+        let status = response.status();
+
+        if status.is_success() {
+            return Ok(response.json().await?);
+        }
+
+        // Errors are either the standard JSON envelope or a plain-text rejection
+        let text = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<ErrorResponse>(&text)
+            .map(|e| e.error)
+            .unwrap_or(text);
+
+        Err(ClientError::Api { status, message })
+    }
+}
+
+fn credentials(email: &str, password: &str) -> Credentials {
+    Credentials {
+        email: email.to_string(),
+        password: password.to_string(),
+    }
+}
+
+fn date_range(from: Option<NaiveDate>, to: Option<NaiveDate>) -> Vec<(&'static str, String)> {
+    [("from", from), ("to", to)]
+        .into_iter()
+        .filter_map(|(name, date)| Some((name, date?.to_string())))
+        .collect()
+}
+
+fn trade(ticker: &str, quantity: i32) -> TradeRequest {
+    TradeRequest {
+        ticker: ticker.to_string(),
+        quantity,
+    }
+}
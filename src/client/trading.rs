@@ -0,0 +1,27 @@
+//! Generated client for the gRPC trading API, see `proto/trading.proto`.
+//!
+//! Calls authenticate with `x-api-key` metadata holding a key from
+//! [`Client::create_api_key`](super::Client::create_api_key), or an
+//! `authorization: Bearer` access token:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use stock_exchange_sim_core::client::trading::{
+//!     PlaceOrderRequest, Side, trading_client::TradingClient,
+//! };
+//!
+//! let mut trading = TradingClient::connect("http://localhost:50051").await?;
+//! let mut request = tonic::Request::new(PlaceOrderRequest {
+//!     ticker: "AAPL".into(),
+//!     side: Side::Buy.into(),
+//!     quantity: 10,
+//!     portfolio_id: None,
+//! });
+//! request.metadata_mut().insert("x-api-key", "sk_...".parse()?);
+//! let order = trading.place_order(request).await?.into_inner();
+//! println!("Bought {} {} at {}", order.quantity, order.ticker, order.price);
+//! # Ok(())
+//! # }
+//! ```
+
+tonic::include_proto!("trading");
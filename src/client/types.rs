@@ -0,0 +1,1166 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Credentials used for registration and login
+#[derive(Debug, Clone, Serialize)]
+pub struct Credentials {
+    pub email: String,
+    pub password: String,
+}
+
+/// Response returned by `POST /auth/login`
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoginResponse {
+    pub access_token: String,
+    pub token_type: String,
+}
+
+/// Request body for `POST /auth/change-password`
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// Request body for `POST /auth/change-email`
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeEmailRequest {
+    pub new_email: String,
+    pub password: String,
+}
+
+/// Request body for `POST /auth/confirm-email`
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfirmEmailRequest {
+    pub token: String,
+}
+
+/// Request body for `POST /loans/{id}/repay`
+#[derive(Debug, Clone, Serialize)]
+pub struct AmountRequest {
+    pub amount: f64,
+}
+
+/// Request body for `POST /balance/deposit` and `POST /balance/withdraw`
+#[derive(Debug, Clone, Serialize)]
+pub struct CashAmountRequest {
+    /// Decimal string with at most two decimal places
+    pub amount: String,
+}
+
+/// Request body for `POST /transactions/buy` and `POST /transactions/sell`
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeRequest {
+    pub ticker: String,
+    pub quantity: i32,
+}
+
+/// A single executed buy or sell transaction
+#[derive(Debug, Clone, Deserialize)]
+pub struct Transaction {
+    pub id: Uuid,
+    pub ticker: String,
+    pub quantity: i32,
+    pub price: BigDecimal,
+    /// Commission paid on top of the price
+    #[serde(default)]
+    pub fee: BigDecimal,
+    /// `buy`, `sell`, `dividend`, `coupon` or `redemption`; dividends and
+    /// coupons report the units held as `quantity` and the amount per unit as
+    /// `price`
+    pub transaction_type: String,
+    pub created_at: NaiveDateTime,
+    /// Differs from `created_at` only for backdated seed trades
+    #[serde(default)]
+    pub updated_at: Option<NaiveDateTime>,
+    /// Gain realized by a sell under the user's cost-basis method
+    #[serde(default)]
+    pub realized_gain: Option<BigDecimal>,
+}
+
+/// One page of `GET /transactions`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TransactionPage {
+    pub transactions: Vec<Transaction>,
+    /// Pass as [`TransactionQuery::cursor`] to fetch the next page; `None` on the last page
+    #[serde(default)]
+    pub next_cursor: Option<Uuid>,
+}
+
+/// Outcome of `POST /transactions/import`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportReport {
+    pub rows: usize,
+    /// Zero unless every line was valid
+    pub imported: usize,
+    /// The first invalid lines
+    pub errors: Vec<ImportError>,
+    pub error_count: usize,
+}
+
+/// An invalid line of an imported CSV file
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Filters and paging for `GET /transactions`; unset fields are not sent
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TransactionQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<Uuid>,
+    /// Page size, 1 to 200 (server default 50)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ticker: Option<String>,
+    /// `buy`, `sell`, `dividend`, `coupon` or `redemption`
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub transaction_type: Option<String>,
+    /// First day (inclusive)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<NaiveDate>,
+    /// Last day (inclusive)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_price: Option<BigDecimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_price: Option<BigDecimal>,
+    /// Newest first unless set to [`SortOrder::Asc`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<SortOrder>,
+}
+
+/// Order of listed transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// A position held by the authenticated user, valued at the latest price
+#[derive(Debug, Clone, Deserialize)]
+pub struct Holding {
+    pub id: Uuid,
+    pub ticker: String,
+    pub quantity: i32,
+    pub average_price: BigDecimal,
+    pub current_price: Option<BigDecimal>,
+    /// Interest accrued on a bond since its last coupon, included in the market value
+    #[serde(default)]
+    pub accrued_interest: BigDecimal,
+    pub market_value: BigDecimal,
+    pub unrealized_pnl: BigDecimal,
+    /// Share of the market value of all holdings, in percent
+    pub percent_of_portfolio: BigDecimal,
+    /// When the position was opened
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    /// Last trade or corporate action that changed the position
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// One page of `GET /holdings/closed`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClosedPositionPage {
+    pub positions: Vec<ClosedPosition>,
+    /// Pass as `before` to fetch the next page; `None` on the last page
+    #[serde(default)]
+    pub next_cursor: Option<Uuid>,
+}
+
+/// A position whose last share left the portfolio
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClosedPosition {
+    pub id: Uuid,
+    pub ticker: String,
+    pub opened_at: DateTime<Utc>,
+    pub closed_at: DateTime<Utc>,
+    /// Gain realized by the sells while the position was open
+    pub realized_gain: BigDecimal,
+}
+
+/// Valuation of a single position returned by `GET /portfolio`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Position {
+    pub ticker: String,
+    pub quantity: i32,
+    pub average_price: BigDecimal,
+    pub current_price: Option<BigDecimal>,
+    /// Interest accrued on a bond since its last coupon, included in the market value
+    #[serde(default)]
+    pub accrued_interest: BigDecimal,
+    pub cost_basis: BigDecimal,
+    pub market_value: BigDecimal,
+    pub unrealized_pnl: BigDecimal,
+    pub unrealized_pnl_percent: BigDecimal,
+    /// When the position was opened
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    /// Last trade or corporate action that changed the position
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Account valuation returned by `GET /portfolio`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Portfolio {
+    pub cash: BigDecimal,
+    pub money_market: BigDecimal,
+    /// Outstanding debt on open loans, deducted from equity
+    pub loans: BigDecimal,
+    /// Model value of option positions plus the margin they hold
+    #[serde(default)]
+    pub options: BigDecimal,
+    pub market_value: BigDecimal,
+    pub cost_basis: BigDecimal,
+    pub unrealized_pnl: BigDecimal,
+    pub unrealized_pnl_percent: BigDecimal,
+    pub equity: BigDecimal,
+    pub positions: Vec<Position>,
+}
+
+/// Daily equity snapshot returned by `GET /portfolio/history`
+#[derive(Debug, Clone, Deserialize)]
+pub struct PortfolioSnapshot {
+    pub date: NaiveDate,
+    pub cash: BigDecimal,
+    pub market_value: BigDecimal,
+    pub equity: BigDecimal,
+    /// Benchmark price of the day; set only with a benchmark selected
+    #[serde(default)]
+    pub benchmark_price: Option<BigDecimal>,
+    /// Time-weighted return of the account since the first day with a benchmark price
+    #[serde(default)]
+    pub cumulative_return: Option<f64>,
+    /// Return of the benchmark since the first day with a benchmark price
+    #[serde(default)]
+    pub benchmark_cumulative_return: Option<f64>,
+}
+
+/// Performance statistics returned by `GET /portfolio/metrics`
+///
+/// Returns, volatility and drawdown are fractions (0.05 = 5%).
+#[derive(Debug, Clone, Deserialize)]
+pub struct PerformanceMetrics {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub periods: usize,
+    pub time_weighted_return: f64,
+    pub annualized_return: f64,
+    pub annualized_volatility: Option<f64>,
+    pub sharpe_ratio: Option<f64>,
+    pub max_drawdown: f64,
+    /// Set when a benchmark is selected and has prices for at least two days
+    #[serde(default)]
+    pub benchmark: Option<BenchmarkComparison>,
+}
+
+/// Performance relative to the benchmark over the days both are known
+#[derive(Debug, Clone, Deserialize)]
+pub struct BenchmarkComparison {
+    pub ticker: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub periods: usize,
+    pub portfolio_return: f64,
+    pub benchmark_return: f64,
+    /// Portfolio return minus benchmark return
+    pub tracking_difference: f64,
+    pub tracking_error: Option<f64>,
+    pub beta: Option<f64>,
+    /// Annualized Jensen's alpha
+    pub alpha: Option<f64>,
+}
+
+/// Quote returned by `GET /market/quote/{ticker}` and `POST /market/quotes`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Quote {
+    pub ticker: String,
+    pub price: BigDecimal,
+    /// When the price was published, if known
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Close of the previous day with a candle
+    pub previous_close: Option<BigDecimal>,
+    pub change: Option<BigDecimal>,
+    pub change_percent: Option<BigDecimal>,
+    pub day_high: BigDecimal,
+    pub day_low: BigDecimal,
+    /// `cache`, or `database` when the price came from the latest daily candle
+    pub source: String,
+    /// Whether the price is too old to trade on
+    pub stale: bool,
+}
+
+/// Request body for `POST /market/quotes`
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotesRequest {
+    pub tickers: Vec<String>,
+}
+
+/// Daily market statistics returned by `GET /market/movers`
+#[derive(Debug, Clone, Deserialize)]
+pub struct MarketMovers {
+    /// UTC day the statistics cover
+    pub date: NaiveDate,
+    pub gainers: Vec<Mover>,
+    pub losers: Vec<Mover>,
+    pub most_traded: Vec<TradedVolume>,
+}
+
+/// Price change of a ticker since the previous day's close
+#[derive(Debug, Clone, Deserialize)]
+pub struct Mover {
+    pub ticker: String,
+    pub price: BigDecimal,
+    pub previous_close: BigDecimal,
+    pub change: BigDecimal,
+    pub change_percent: BigDecimal,
+}
+
+/// Shares of a ticker bought and sold in the simulator today
+#[derive(Debug, Clone, Deserialize)]
+pub struct TradedVolume {
+    pub ticker: String,
+    pub volume: i64,
+    pub trades: i64,
+}
+
+/// Synthetic order book returned by `GET /market/depth/{ticker}`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MarketDepth {
+    pub ticker: String,
+    /// Last price the book is centred on
+    pub mid: BigDecimal,
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Best (highest) bid first
+    pub bids: Vec<BookLevel>,
+    /// Best (lowest) ask first
+    pub asks: Vec<BookLevel>,
+}
+
+/// Published news item returned by `GET /market/news`
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewsItem {
+    pub id: i32,
+    pub ticker: String,
+    /// `earnings_beat`, `earnings_miss`, `upgrade`, `downgrade` or `other`
+    pub event_type: String,
+    pub headline: String,
+    pub published_at: DateTime<Utc>,
+}
+
+/// A price level of a [`MarketDepth`] book
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BookLevel {
+    pub price: BigDecimal,
+    pub quantity: i64,
+}
+
+/// Instrument returned by `GET /market/search`
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstrumentMatch {
+    pub ticker: String,
+    pub name: String,
+    pub sector: Option<String>,
+    pub asset_class: String,
+    pub tick_size: BigDecimal,
+    /// Orders must be a multiple of this many shares
+    pub lot_size: i32,
+}
+
+/// Candle returned by `GET /market/candles/{ticker}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Candle {
+    /// Start of the bucket
+    pub time: DateTime<Utc>,
+    pub open: BigDecimal,
+    pub high: BigDecimal,
+    pub low: BigDecimal,
+    pub close: BigDecimal,
+    /// Shares bought and sold in the simulator during the bucket
+    pub volume: i64,
+    /// Price updates aggregated into the candle
+    pub ticks: i32,
+}
+
+/// Width of a candle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CandleInterval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+/// Range and width for `GET /market/candles/{ticker}`; unset fields are not sent
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CandleQuery {
+    /// Server default `1m`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<CandleInterval>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Report returned by `GET /reports/realized-gains`
+#[derive(Debug, Clone, Deserialize)]
+pub struct RealizedGainsReport {
+    pub year: i32,
+    pub proceeds: BigDecimal,
+    pub cost_basis: BigDecimal,
+    pub short_term_gain: BigDecimal,
+    pub long_term_gain: BigDecimal,
+    /// Gains on shares without an acquisition date
+    pub unknown_term_gain: BigDecimal,
+    pub total_gain: BigDecimal,
+    pub lots: Vec<RealizedLot>,
+}
+
+/// Shares of one tax lot sold by a single sell
+#[derive(Debug, Clone, Deserialize)]
+pub struct RealizedLot {
+    pub id: i32,
+    pub ticker: String,
+    pub sell_transaction_id: Uuid,
+    /// `None` for shares bought before lot tracking
+    pub lot_id: Option<i32>,
+    pub quantity: i32,
+    pub acquired_at: Option<DateTime<Utc>>,
+    pub realized_at: DateTime<Utc>,
+    pub cost_basis: BigDecimal,
+    pub proceeds: BigDecimal,
+    pub gain: BigDecimal,
+    pub cost_basis_method: CostBasisMethod,
+    pub holding_period: HoldingPeriod,
+}
+
+/// Holding period classification of realized gains
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HoldingPeriod {
+    /// Held one year or less
+    ShortTerm,
+    /// Held more than one year
+    LongTerm,
+    /// No acquisition date is known
+    Unknown,
+}
+
+/// Accounting method used to compute realized gains on sells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CostBasisMethod {
+    Fifo,
+    Lifo,
+    Average,
+}
+
+/// Simulation preset deciding which trading costs apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    /// No commission, spread, slippage or loan interest
+    Beginner,
+    #[default]
+    Realistic,
+}
+
+/// User settings returned by `GET /settings`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settings {
+    pub cost_basis_method: CostBasisMethod,
+    pub cash_sweep_enabled: bool,
+    pub drip_enabled: bool,
+    /// Ticker the portfolio history and metrics are compared against
+    #[serde(default)]
+    pub benchmark_ticker: Option<String>,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Request body for `PATCH /settings`; only the provided fields are changed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateSettingsRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_basis_method: Option<CostBasisMethod>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cash_sweep_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drip_enabled: Option<bool>,
+    /// An empty string clears the benchmark
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub benchmark_ticker: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<Difficulty>,
+}
+
+/// Account events pushed to the user's WebSocket connections
+#[derive(Debug, Clone, Deserialize)]
+pub struct Notifications {
+    pub order_fills: bool,
+    pub margin_calls: bool,
+    pub deposits: bool,
+}
+
+/// Profile returned by `GET /me`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Profile {
+    pub id: Uuid,
+    pub email: String,
+    /// `user`, `moderator` or `admin`
+    pub role: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    pub base_currency: String,
+    pub cost_basis_method: CostBasisMethod,
+    pub notifications: Notifications,
+    /// Preferences of the user's clients, stored as given
+    pub ui_settings: serde_json::Value,
+    /// Whether the user is left off the leaderboard
+    #[serde(default)]
+    pub leaderboard_opt_out: bool,
+    #[serde(default)]
+    pub privacy: Privacy,
+    /// When the account was registered
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    /// Last change of the account or its settings
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// API key returned by `GET /me/api-keys`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiKey {
+    pub id: i32,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// API key returned by `POST /me/api-keys`, with the key shown only once
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreatedApiKey {
+    /// Send as `x-api-key` metadata to the gRPC trading API
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+}
+
+/// Request body for `POST /me/api-keys`
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+}
+
+/// Webhook returned by `GET /webhooks`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+    pub events: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Webhook returned by `POST /webhooks`, with the secret shown only once
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreatedWebhook {
+    /// Key of the `X-Webhook-Signature` HMAC of every delivery
+    pub secret: String,
+    #[serde(flatten)]
+    pub webhook: Webhook,
+}
+
+/// Request body for `POST /webhooks`
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    /// `order_filled`, `margin_call` or `deposit_settled`
+    pub events: Vec<String>,
+}
+
+/// An event posted, or to be posted, to a webhook
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub event: String,
+    pub payload: serde_json::Value,
+    /// `pending`, `delivered` or `failed`
+    pub status: String,
+    pub attempts: i32,
+    #[serde(default)]
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// One page of `GET /webhooks/{id}/deliveries`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeliveryPage {
+    pub deliveries: Vec<WebhookDelivery>,
+    /// Pass as `before` to fetch the next page; absent on the last page
+    #[serde(default)]
+    pub next_cursor: Option<i64>,
+}
+
+/// How followers see the trades of the user's public portfolios
+#[derive(Debug, Clone, Deserialize)]
+pub struct Privacy {
+    /// Minutes before a trade shows up in followers' feeds
+    pub trade_delay_minutes: i32,
+    /// Whether followers see trades and positions without quantities
+    pub hide_quantities: bool,
+}
+
+impl Default for Privacy {
+    fn default() -> Self {
+        Privacy {
+            trade_delay_minutes: 15,
+            hide_quantities: false,
+        }
+    }
+}
+
+/// Notification kinds to turn on or off in `PATCH /me`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateNotificationsRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_fills: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub margin_calls: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deposits: Option<bool>,
+}
+
+/// Request body for `PATCH /me`; only the provided fields are changed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateProfileRequest {
+    /// An empty string clears the display name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_currency: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_basis_method: Option<CostBasisMethod>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<UpdateNotificationsRequest>,
+    /// Replaces the stored object as a whole
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ui_settings: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leaderboard_opt_out: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privacy: Option<UpdatePrivacyRequest>,
+}
+
+/// Privacy settings to change in `PATCH /me`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdatePrivacyRequest {
+    /// 0 to 10080 (a week)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trade_delay_minutes: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hide_quantities: Option<bool>,
+}
+
+/// Time span of the leaderboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LeaderboardPeriod {
+    Day,
+    #[default]
+    Week,
+    All,
+}
+
+/// Ranking returned by `GET /leaderboard`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Leaderboard {
+    pub period: LeaderboardPeriod,
+    pub entries: Vec<LeaderboardEntry>,
+    /// The caller's own place, `None` when unranked
+    pub you: Option<LeaderboardEntry>,
+}
+
+/// A user's place on the leaderboard
+#[derive(Debug, Clone, Deserialize)]
+pub struct LeaderboardEntry {
+    pub rank: u64,
+    pub display_name: Option<String>,
+    /// Fraction, 0.05 = 5%
+    pub time_weighted_return: f64,
+    pub is_you: bool,
+}
+
+/// Shares pledged to a loan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collateral {
+    pub ticker: String,
+    pub quantity: i32,
+}
+
+/// Request body for `POST /loans`
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateLoanRequest {
+    pub amount: f64,
+    pub collateral: Vec<Collateral>,
+}
+
+/// Secured loan returned by `GET /loans`, `POST /loans` and `POST /loans/{id}/repay`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Loan {
+    pub id: i32,
+    pub principal: BigDecimal,
+    /// Principal plus accrued interest minus repayments
+    pub outstanding: BigDecimal,
+    pub interest_rate_percent: f64,
+    /// `open`, `repaid` or `liquidated`
+    pub status: String,
+    pub collateral: Vec<Collateral>,
+    pub collateral_value: BigDecimal,
+    /// `None` while a collateral price is unavailable
+    pub ltv_percent: Option<f64>,
+    pub created_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+/// Terms of a bond returned by `GET /market/bonds/{ticker}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Bond {
+    pub ticker: String,
+    pub face_value: BigDecimal,
+    pub coupon_rate_percent: BigDecimal,
+    pub coupons_per_year: i32,
+    pub maturity_date: NaiveDate,
+    /// `None` once the bond has been redeemed
+    pub next_coupon_date: Option<NaiveDate>,
+    /// Coupon paid per bond on each coupon date
+    pub coupon: BigDecimal,
+    /// Interest accrued per bond since the last coupon date
+    pub accrued_interest: BigDecimal,
+    pub redeemed_at: Option<DateTime<Utc>>,
+}
+
+/// Constituents of an index or ETF returned by `GET /market/baskets/{ticker}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Basket {
+    pub ticker: String,
+    /// `None` until every constituent has a price
+    pub price: Option<BigDecimal>,
+    pub constituents: Vec<BasketConstituent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BasketConstituent {
+    pub ticker: String,
+    /// Units of the constituent in one unit of the basket
+    pub weight: BigDecimal,
+}
+
+/// Option chain of an underlying returned by `GET /options/chain/{ticker}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct OptionChain {
+    pub underlying: String,
+    pub underlying_price: Option<BigDecimal>,
+    /// Shares covered by one contract
+    pub multiplier: i32,
+    pub contracts: Vec<OptionContract>,
+}
+
+/// Listed option contract with its model price
+#[derive(Debug, Clone, Deserialize)]
+pub struct OptionContract {
+    pub id: i32,
+    /// `call` or `put`
+    pub kind: String,
+    pub strike: BigDecimal,
+    pub expires_at: DateTime<Utc>,
+    /// Model premium per share, `None` while the underlying has no price
+    pub price: Option<BigDecimal>,
+    pub delta: Option<f64>,
+}
+
+/// Request body for `POST /options/buy` and `POST /options/sell`
+#[derive(Debug, Clone, Serialize)]
+pub struct OptionOrderRequest {
+    pub contract_id: i32,
+    pub quantity: i32,
+}
+
+/// Executed option trade
+#[derive(Debug, Clone, Deserialize)]
+pub struct OptionTrade {
+    pub id: i32,
+    pub contract_id: i32,
+    /// `buy` or `sell`
+    pub trade_type: String,
+    pub quantity: i32,
+    /// Premium per share
+    pub price: BigDecimal,
+    pub fee: BigDecimal,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Option position returned by `GET /options/positions`
+#[derive(Debug, Clone, Deserialize)]
+pub struct OptionPosition {
+    pub contract_id: i32,
+    pub underlying: String,
+    pub kind: String,
+    pub strike: BigDecimal,
+    pub expires_at: DateTime<Utc>,
+    /// Number of contracts, negative for short positions
+    pub quantity: i32,
+    pub average_premium: BigDecimal,
+    pub current_price: Option<BigDecimal>,
+    pub market_value: BigDecimal,
+    pub unrealized_pnl: BigDecimal,
+    /// Cash held against a short position
+    pub margin: BigDecimal,
+}
+
+/// One of the user's portfolios, returned by `GET /portfolios` and `POST /portfolios`
+#[derive(Debug, Clone, Deserialize)]
+pub struct PortfolioInfo {
+    pub id: i32,
+    pub name: String,
+    pub cash: BigDecimal,
+    /// Used when a request does not select a portfolio
+    pub is_default: bool,
+    /// Competition the portfolio was entered in
+    #[serde(default)]
+    pub competition_id: Option<i32>,
+    /// Class the portfolio belongs to
+    #[serde(default)]
+    pub class_id: Option<i32>,
+    /// Whether followers can see the portfolio and its trades
+    #[serde(default)]
+    pub is_public: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Request body for `PATCH /portfolios/{id}`
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdatePortfolioRequest {
+    pub is_public: Option<bool>,
+}
+
+/// Another user's profile returned by `GET /users/{id}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct PublicProfile {
+    pub id: Uuid,
+    pub display_name: Option<String>,
+    pub followers: i64,
+    pub following: i64,
+    /// Whether the caller follows the user
+    pub you_follow: bool,
+    pub portfolios: Vec<PublicPortfolioSummary>,
+}
+
+/// A public portfolio listed on a profile
+#[derive(Debug, Clone, Deserialize)]
+pub struct PublicPortfolioSummary {
+    pub id: i32,
+    pub name: String,
+}
+
+/// A user on either side of a follow, returned by `GET /users/{id}/followers`
+/// and `GET /users/{id}/following`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Follow {
+    pub user_id: Uuid,
+    pub display_name: Option<String>,
+    pub followed_at: DateTime<Utc>,
+}
+
+/// Positions of another user's public portfolio, returned by
+/// `GET /users/{id}/portfolios/{portfolio_id}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct PublicPortfolio {
+    pub id: i32,
+    pub user_id: Uuid,
+    pub name: String,
+    pub positions: Vec<PublicPosition>,
+}
+
+/// A position of a public portfolio
+#[derive(Debug, Clone, Deserialize)]
+pub struct PublicPosition {
+    pub ticker: String,
+    /// `None` when the owner hides quantities
+    pub quantity: Option<i32>,
+    /// Share of the portfolio's equity in percent
+    pub weight_percent: BigDecimal,
+    pub unrealized_pnl_percent: BigDecimal,
+}
+
+/// A deposit or withdrawal, returned by `POST /balance/deposit` and
+/// `POST /balance/withdraw`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CashMovement {
+    pub id: i32,
+    pub portfolio_id: i32,
+    /// `deposit` or `withdrawal`
+    pub movement_type: String,
+    pub amount: BigDecimal,
+    pub balance_after: BigDecimal,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One page of `GET /balance/history`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CashHistoryPage {
+    pub movements: Vec<CashMovement>,
+    /// Pass as `before` to fetch the next page; `None` on the last page
+    #[serde(default)]
+    pub next_cursor: Option<i32>,
+}
+
+/// One page of `GET /balance/ledger`
+#[derive(Debug, Clone, Deserialize)]
+pub struct LedgerPage {
+    pub entries: Vec<LedgerEntry>,
+    /// Pass as `before` to fetch the next page; `None` on the last page
+    #[serde(default)]
+    pub next_cursor: Option<i64>,
+}
+
+/// A change to the cash balance of a portfolio
+#[derive(Debug, Clone, Deserialize)]
+pub struct LedgerEntry {
+    pub id: i64,
+    pub portfolio_id: i32,
+    /// `deposit`, `buy`, `fee`, `transfer`, ...
+    pub entry_type: String,
+    /// Positive amounts credit the cash, negative ones debit it
+    pub amount: BigDecimal,
+    pub balance_after: BigDecimal,
+    pub transaction_id: Option<Uuid>,
+    pub counter_portfolio_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One page of `GET /feed`
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedPage {
+    pub trades: Vec<FeedTrade>,
+    /// Pass as `before` to fetch the next page; `None` on the last page
+    #[serde(default)]
+    pub next_cursor: Option<Uuid>,
+}
+
+/// A followed user's trade
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedTrade {
+    pub transaction_id: Uuid,
+    pub user_id: Uuid,
+    pub display_name: Option<String>,
+    pub portfolio_id: i32,
+    pub portfolio_name: String,
+    pub ticker: String,
+    /// `buy` or `sell`
+    pub transaction_type: String,
+    /// `None` when the trader hides quantities
+    pub quantity: Option<i32>,
+    pub price: BigDecimal,
+    pub created_at: NaiveDateTime,
+}
+
+/// Where a competition is in its schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompetitionStatus {
+    Upcoming,
+    Running,
+    Ended,
+}
+
+/// Trading competition returned by `GET /competitions` and `POST /competitions/{id}/join`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Competition {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub starting_balance: BigDecimal,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// Simulated seconds per real second
+    pub clock_speed: f64,
+    /// Current time on the competition's clock
+    pub sim_time: DateTime<Utc>,
+    pub status: CompetitionStatus,
+    /// Whether the final standings are recorded
+    pub r#final: bool,
+    /// The user's competition portfolio, `None` when they did not join
+    pub portfolio_id: Option<i32>,
+}
+
+/// Standings returned by `GET /competitions/{id}/standings`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompetitionStandings {
+    pub competition_id: i32,
+    pub status: CompetitionStatus,
+    /// Whether the standings are frozen
+    pub r#final: bool,
+    pub entrants: usize,
+    pub standings: Vec<Standing>,
+    /// The user's own place, `None` when they did not join
+    pub you: Option<Standing>,
+}
+
+/// An entrant's place in a competition
+#[derive(Debug, Clone, Deserialize)]
+pub struct Standing {
+    pub rank: i32,
+    pub display_name: Option<String>,
+    pub equity: BigDecimal,
+    /// Gain on the starting balance in percent
+    pub return_percent: BigDecimal,
+    pub is_you: bool,
+}
+
+/// A member's role in a class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClassRole {
+    Instructor,
+    Student,
+}
+
+/// Class returned by the `/classes` endpoints
+#[derive(Debug, Clone, Deserialize)]
+pub struct Class {
+    pub id: i32,
+    pub name: String,
+    pub starting_balance: BigDecimal,
+    /// Tickers students may buy, `None` for every ticker
+    pub allowed_tickers: Option<Vec<String>>,
+    /// The user's role in the class
+    pub role: ClassRole,
+    /// The user's class portfolio, `None` for instructors
+    pub portfolio_id: Option<i32>,
+    /// Shown to instructors only
+    pub invite_code: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// Request body for `POST /classes`
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateClassRequest {
+    pub name: String,
+    pub starting_balance: f64,
+    /// Every ticker when `None` or empty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_tickers: Option<Vec<String>>,
+}
+
+/// Request body for `PATCH /classes/{id}`; only the provided fields are changed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateClassRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub starting_balance: Option<f64>,
+    /// Replaces the list; empty allows every ticker
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_tickers: Option<Vec<String>>,
+}
+
+/// Request body for `POST /classes/join`
+#[derive(Debug, Clone, Serialize)]
+pub struct JoinClassRequest {
+    pub invite_code: String,
+}
+
+/// Students of a class returned by `GET /classes/{id}/dashboard`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClassDashboard {
+    pub class_id: i32,
+    pub name: String,
+    /// Highest equity first
+    pub students: Vec<ClassStudent>,
+}
+
+/// A student's class portfolio on the dashboard
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClassStudent {
+    pub user_id: Uuid,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub portfolio_id: i32,
+    pub cash: BigDecimal,
+    pub market_value: BigDecimal,
+    pub equity: BigDecimal,
+    /// Gain on the starting balance in percent
+    pub return_percent: BigDecimal,
+    /// Number of open positions
+    pub positions: usize,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// Request body for `POST /portfolios`
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatePortfolioRequest {
+    pub name: String,
+}
+
+/// Request body for `POST /portfolios/transfer`
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferRequest {
+    pub from_portfolio_id: i32,
+    pub to_portfolio_id: i32,
+    pub amount: f64,
+}
+
+/// Service health returned by `GET /health`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Health {
+    /// `ok`, or `degraded` while the price feed is not streaming
+    pub status: String,
+    pub price_feed: FeedHealth,
+}
+
+/// State of the server's price feed connection
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedHealth {
+    pub status: FeedStatus,
+    pub connected_since: Option<DateTime<Utc>>,
+    pub last_update_at: Option<DateTime<Utc>>,
+    /// Why the feed last disconnected
+    pub last_error: Option<String>,
+    pub restarts: u64,
+}
+
+/// Connection state of the price feed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedStatus {
+    Connecting,
+    Connected,
+    Reconnecting,
+}
+
+/// Error envelope returned by the API on failure
+#[derive(Debug, Clone, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+    pub timestamp: String,
+}
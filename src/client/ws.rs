@@ -0,0 +1,278 @@
+//! WebSocket protocol types for the `/ws` endpoint.
+//!
+//! Frames are JSON objects tagged with `type`, or the same objects as
+//! MessagePack binary frames when the connection offers the
+//! [`MSGPACK_SUBPROTOCOL`]. [`Client::ws_url`] requests
+//! [`PROTOCOL_VERSION`], so the server refuses the upgrade rather than
+//! speaking a protocol these types do not describe.
+//!
+//! [`Client::ws_url`]: super::Client::ws_url
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::types::{BookLevel, CandleInterval};
+
+/// Version of the protocol described by these types
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// `Sec-WebSocket-Protocol` value asking the server for MessagePack frames
+pub const MSGPACK_SUBPROTOCOL: &str = "msgpack";
+
+/// Kind of updates a subscription delivers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    /// Latest prices
+    Price,
+    /// Order book snapshots
+    Depth,
+    /// Equity of the user's whole account; takes no tickers
+    Portfolio,
+    /// Forming and finalized candles of an interval
+    Candles,
+    /// Announcements from the operators; takes no tickers
+    System,
+}
+
+/// Message sent from the client to the server
+///
+/// One connection may hold many subscriptions; every ticker of a message is
+/// acknowledged or rejected separately.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Start receiving updates for tickers
+    Subscribe {
+        channel: Channel,
+        tickers: Vec<String>,
+        /// Candle width, required by the candles channel
+        #[serde(skip_serializing_if = "Option::is_none")]
+        interval: Option<CandleInterval>,
+    },
+    /// Stop receiving updates for tickers
+    Unsubscribe {
+        channel: Channel,
+        tickers: Vec<String>,
+        /// Candle width, required by the candles channel
+        #[serde(skip_serializing_if = "Option::is_none")]
+        interval: Option<CandleInterval>,
+    },
+}
+
+impl ClientMessage {
+    /// Subscribe to the prices of `tickers`
+    pub fn subscribe<T: ToString>(tickers: &[T]) -> Self {
+        ClientMessage::Subscribe {
+            channel: Channel::Price,
+            tickers: tickers.iter().map(ToString::to_string).collect(),
+            interval: None,
+        }
+    }
+
+    /// Stop receiving the prices of `tickers`
+    pub fn unsubscribe<T: ToString>(tickers: &[T]) -> Self {
+        ClientMessage::Unsubscribe {
+            channel: Channel::Price,
+            tickers: tickers.iter().map(ToString::to_string).collect(),
+            interval: None,
+        }
+    }
+
+    /// Stream the equity of the whole account
+    pub fn subscribe_portfolio() -> Self {
+        ClientMessage::Subscribe {
+            channel: Channel::Portfolio,
+            tickers: Vec::new(),
+            interval: None,
+        }
+    }
+
+    /// Receive the active announcements and every new one
+    pub fn subscribe_system() -> Self {
+        ClientMessage::Subscribe {
+            channel: Channel::System,
+            tickers: Vec::new(),
+            interval: None,
+        }
+    }
+
+    /// Stream the candles of `tickers` at the given width
+    pub fn subscribe_candles<T: ToString>(tickers: &[T], interval: CandleInterval) -> Self {
+        ClientMessage::Subscribe {
+            channel: Channel::Candles,
+            tickers: tickers.iter().map(ToString::to_string).collect(),
+            interval: Some(interval),
+        }
+    }
+
+    /// Encode the message as a WebSocket text frame payload
+    pub fn to_text(&self) -> String {
+        serde_json::to_string(self).expect("client messages always serialize")
+    }
+
+    /// Encode the message as a WebSocket binary frame payload
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        rmp_serde::to_vec_named(self).expect("client messages always serialize")
+    }
+}
+
+/// Message received from the server
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Sent once after connecting
+    Welcome {
+        version: u32,
+        /// Session to resume after reconnecting, see [`Client::ws_resume_url`]
+        ///
+        /// [`Client::ws_resume_url`]: super::Client::ws_resume_url
+        #[serde(default)]
+        session: String,
+        /// Whether the subscriptions of the resumed session were restored;
+        /// they are acknowledged again with `subscribed` frames
+        #[serde(default)]
+        resumed: bool,
+    },
+    /// A subscription was added
+    Subscribed {
+        channel: Channel,
+        /// Empty for the portfolio channel
+        #[serde(default)]
+        ticker: String,
+        /// Set for the candles channel
+        #[serde(default)]
+        interval: Option<CandleInterval>,
+    },
+    /// A subscription was removed
+    Unsubscribed {
+        channel: Channel,
+        #[serde(default)]
+        ticker: String,
+        #[serde(default)]
+        interval: Option<CandleInterval>,
+    },
+    /// Latest price of a subscribed ticker
+    Price {
+        ticker: String,
+        price: BigDecimal,
+        /// When the price was published, if known
+        ts: Option<DateTime<Utc>>,
+    },
+    /// Order book snapshot of a subscribed ticker, best levels first
+    Depth {
+        ticker: String,
+        mid: BigDecimal,
+        ts: Option<DateTime<Utc>>,
+        bids: Vec<BookLevel>,
+        asks: Vec<BookLevel>,
+    },
+    /// Candle of a subscribed ticker, sent on every price update while it
+    /// forms and once more with `is_final` set when its interval closes
+    Candle {
+        ticker: String,
+        interval: CandleInterval,
+        /// Start of the bucket
+        time: DateTime<Utc>,
+        open: BigDecimal,
+        high: BigDecimal,
+        low: BigDecimal,
+        close: BigDecimal,
+        ticks: i32,
+        #[serde(rename = "final")]
+        is_final: bool,
+    },
+    /// Equity of the user's whole account, sent on subscribing and at most
+    /// once a second as prices of held tickers change
+    Portfolio {
+        equity: BigDecimal,
+        cash: BigDecimal,
+        money_market: BigDecimal,
+        loans: BigDecimal,
+        market_value: BigDecimal,
+        unrealized_pnl: BigDecimal,
+        ts: DateTime<Utc>,
+    },
+    /// Announcement from the operators, sent on subscribing to the system
+    /// channel while active and as soon as it is made
+    Announcement {
+        id: i32,
+        message: String,
+        /// `info`, `warning` or `critical`
+        severity: String,
+        expires_at: Option<DateTime<Utc>>,
+        ts: DateTime<Utc>,
+    },
+    /// The server rejected a message or one of its tickers
+    Error {
+        code: ErrorCode,
+        message: String,
+        ticker: Option<String>,
+    },
+    /// Something happened to the account of the connected user
+    Event {
+        #[serde(flatten)]
+        event: AccountEvent,
+        ts: DateTime<Utc>,
+    },
+}
+
+/// Event on the connected user's account, received without subscribing
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AccountEvent {
+    /// A buy or sell executed, including sales forced by a margin call
+    OrderFilled {
+        portfolio_id: i32,
+        transaction_id: Uuid,
+        ticker: String,
+        /// `buy` or `sell`
+        side: String,
+        quantity: i32,
+        price: BigDecimal,
+    },
+    /// A loan reached the margin call loan-to-value and its collateral is
+    /// being liquidated
+    MarginCall {
+        portfolio_id: i32,
+        loan_id: i32,
+        ltv_percent: f64,
+    },
+    /// A deposit was credited to a portfolio
+    DepositSettled {
+        portfolio_id: i32,
+        amount: BigDecimal,
+        balance: BigDecimal,
+    },
+}
+
+/// Machine-readable reason of an error frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidMessage,
+    InvalidTicker,
+    NotSubscribed,
+    TooManySubscriptions,
+    /// The user already holds the maximum number of connections
+    TooManyConnections,
+    /// The message was ignored because the client sent too many
+    RateLimited,
+    /// A code added by a newer server
+    #[serde(other)]
+    Unknown,
+}
+
+impl ServerMessage {
+    /// Decode a WebSocket text frame payload
+    pub fn parse(text: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(text)
+    }
+
+    /// Decode a WebSocket binary frame payload
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(bytes)
+    }
+}
@@ -4,7 +4,10 @@
 //! with proper validation and default values.
 
 use serde::Deserialize;
-use std::env;
+use std::{
+    env,
+    net::{IpAddr, SocketAddr},
+};
 
 /// Application configuration structure
 ///
@@ -14,26 +17,152 @@ use std::env;
 pub struct Config {
     /// PostgreSQL database connection URL
     pub database_url: String,
+    /// Read replica serving history reads (served by the primary when unset)
+    pub database_read_url: Option<String>,
+    /// Days after which transactions move to the archive (never archived when 0)
+    pub transaction_archive_after_days: u32,
     /// Redis connection URL for caching
     pub redis_url: String,
+    /// Source of live prices: `grpc`, `rest` or `synthetic`
+    pub price_provider: String,
     /// gRPC server URL for price feed
     pub grpc_server_url: String,
+    /// URL polled by the REST price provider, with a `{ticker}` placeholder
+    pub price_rest_url: Option<String>,
+    /// JSON pointer to the price in REST provider responses
+    pub price_rest_pointer: String,
+    /// Seconds between REST polls and synthetic price moves
+    pub price_poll_interval_secs: u64,
+    /// Age in seconds after which a price is stale and cannot be traded at (0 disables)
+    pub max_price_age_secs: u64,
+    /// Annualized drift of synthetic prices
+    pub synthetic_drift: f64,
+    /// Annualized volatility of synthetic prices
+    pub synthetic_volatility: f64,
+    /// Simulated seconds per real second for synthetic prices
+    pub synthetic_time_scale: f64,
+    /// Seed making synthetic price paths reproducible; random when unset
+    pub synthetic_seed: Option<u64>,
     /// JWT signing secret key
     pub jwt_secret: String,
-    /// Server host address
-    pub server_host: String,
+    /// `kid` of tokens signed with `jwt_secret`
+    pub jwt_key_id: String,
+    /// Retired signing secrets by `kid`, still accepted for tokens they signed
+    pub jwt_verification_keys: Vec<(String, String)>,
+    /// Address the API listens on
+    pub server_host: IpAddr,
     /// Server port number
     pub server_port: u16,
+    /// Separate listener for the admin API, which the API listener then no longer serves
+    pub admin_listen_addr: Option<SocketAddr>,
+    /// Listener serving the gRPC trading API, which is off without one
+    pub grpc_listen_addr: Option<SocketAddr>,
+    /// PEM certificate chain served by every listener, which then speaks HTTPS only
+    pub tls_cert_path: Option<String>,
+    /// PEM private key of the certificate
+    pub tls_key_path: Option<String>,
     /// Maximum number of database connections in the pool
     pub max_db_connections: u32,
     /// Application log level (trace, debug, info, warn, error)
     pub log_level: String,
+    /// DSN errors and panics are reported to (not reported when unset)
+    pub sentry_dsn: Option<String>,
+    /// Environment reported errors are tagged with, like `production`
+    pub sentry_environment: Option<String>,
     /// Maximum request body size in bytes (default: 1MB)
     pub max_request_size: usize,
+    /// Seconds between pings sent to WebSocket clients
+    pub ws_ping_interval_secs: u64,
+    /// Seconds without any frame from a WebSocket client before it is dropped
+    pub ws_idle_timeout_secs: u64,
+    /// Most WebSocket connections a user may hold across all instances
+    pub ws_max_connections_per_user: usize,
+    /// Most subscriptions a single WebSocket connection may hold
+    pub ws_max_subscriptions: usize,
+    /// Messages per second a WebSocket client may send on average
+    pub ws_max_messages_per_sec: u32,
+    /// Messages a WebSocket client may send at once after being quiet
+    pub ws_message_burst: u32,
+    /// Seconds a closed WebSocket session stays resumable, and account events are kept for replay
+    pub ws_resume_window_secs: u64,
     /// Enable TLS for gRPC connections
     pub grpc_tls_enabled: bool,
+    /// PEM file with an extra CA trusted for the gRPC feed
+    pub grpc_tls_ca_cert: Option<String>,
+    /// Name to verify the gRPC feed's certificate against, if not the URL host
+    pub grpc_tls_domain: Option<String>,
+    /// PEM certificate presented to the gRPC feed for mutual TLS
+    pub grpc_tls_client_cert: Option<String>,
+    /// PEM private key of the client certificate
+    pub grpc_tls_client_key: Option<String>,
+    /// Bearer token sent to the gRPC feed
+    pub grpc_auth_token: Option<String>,
     /// JWT token expiration time in hours
     pub jwt_expiration_hours: i64,
+    /// API key required by admin endpoints (admin API disabled when unset)
+    pub admin_api_key: Option<String>,
+    /// Failed logins of an email before it is locked out
+    pub login_max_failures: u32,
+    /// Failed logins from a client IP before it is locked out
+    pub login_max_failures_per_ip: u32,
+    /// Seconds a login lockout lasts, and failures are remembered
+    pub login_lockout_secs: u64,
+    /// Lowest password strength score from 0 to 4 accepted for new passwords
+    pub password_min_score: u8,
+    /// Passwords refused whatever their score, lowercased
+    pub password_deny_list: Vec<String>,
+    /// Annual yield in percent paid on swept cash
+    pub money_market_yield_percent: f64,
+    /// Annual interest in percent charged on secured loans
+    pub loan_interest_percent: f64,
+    /// Highest loan-to-value in percent at which a loan can be taken
+    pub loan_max_ltv_percent: f64,
+    /// Loan-to-value in percent at which collateral is liquidated
+    pub loan_margin_call_ltv_percent: f64,
+    /// Commission in percent of the order value charged on trades under the
+    /// realistic difficulty
+    pub trade_fee_percent: f64,
+    /// Annual volatility in percent that option contracts are priced with
+    pub option_volatility_percent: f64,
+    /// Annual risk-free rate in percent that option contracts are priced with
+    pub option_risk_free_percent: f64,
+    /// Cash in the default portfolio of a new account
+    pub starting_balance: f64,
+    /// Cash credited to every active account once a week (no allowance when 0)
+    pub weekly_allowance: f64,
+    /// Days since the last login within which an account counts as active
+    pub allowance_active_days: u32,
+    /// Bot accounts kept trading on the server (no bots when 0)
+    pub bot_count: u32,
+    /// Seconds between the bots' trading rounds
+    pub bot_trade_interval_secs: u64,
+    /// Attempts at delivering an event to a webhook before giving up
+    pub webhook_max_attempts: u32,
+    /// Seconds before the first retry of a webhook delivery, doubling with every retry
+    pub webhook_retry_base_secs: u64,
+    /// Whether webhooks may use plain `http` and reach private addresses, for development
+    pub webhook_allow_insecure: bool,
+    /// Broker domain events are streamed to: `none`, `redis` or `nats`
+    pub event_broker: String,
+    /// URL of the event broker; the `redis` broker defaults to `redis_url`
+    pub event_broker_url: Option<String>,
+    /// First part of the subject or stream name of every domain event
+    pub event_subject_prefix: String,
+    /// Attach a `Server-Timing` latency breakdown to every response
+    pub server_timing_enabled: bool,
+    /// Seconds a request may take before it is answered with `408`
+    pub request_timeout_secs: u64,
+    /// `max-age` of the `Strict-Transport-Security` header (not sent when 0)
+    pub hsts_max_age_secs: u64,
+    /// `Content-Security-Policy` header of every response (not sent when empty)
+    pub content_security_policy: String,
+    /// Requests per minute per client IP and per user (rate limiting off when 0)
+    pub rate_limit_per_minute: u32,
+    /// Requests per minute per client IP and per user to `/auth` (not limited when 0)
+    pub rate_limit_auth_per_minute: u32,
+    /// Writes per minute per client IP and per user to `/transactions` and
+    /// `/options` (not limited when 0)
+    pub rate_limit_trades_per_minute: u32,
 }
 
 impl Config {
@@ -51,24 +180,88 @@ impl Config {
     ///
     /// - `DATABASE_URL`: PostgreSQL connection string
     /// - `REDIS_URL`: Redis connection string  
-    /// - `GRPC_SERVER_URL`: gRPC server URL for price feed
+    /// - `GRPC_SERVER_URL`: gRPC server URL for price feed (only with the `grpc` provider)
     /// - `JWT_SECRET`: Secret key for JWT signing (minimum 32 characters)
     ///
     /// # Optional Environment Variables
     ///
-    /// - `SERVER_HOST`: Server host (default: "127.0.0.1")
+    /// - `PRICE_PROVIDER`: `grpc`, `rest` or `synthetic` (default: "grpc")
+    /// - `PRICE_REST_URL`: URL template for the `rest` provider, e.g. `https://example.com/quote/{ticker}`
+    /// - `PRICE_REST_POINTER`: JSON pointer to the price in REST responses (default: "/price")
+    /// - `PRICE_POLL_INTERVAL_SECS`: Seconds between REST polls and synthetic moves (default: 5)
+    /// - `MAX_PRICE_AGE_SECS`: Age after which prices are stale and trades are refused, 0 to disable (default: 300)
+    /// - `SYNTHETIC_DRIFT`: Annualized drift of synthetic prices (default: 0.05)
+    /// - `SYNTHETIC_VOLATILITY`: Annualized volatility of synthetic prices (default: 0.3)
+    /// - `SYNTHETIC_TIME_SCALE`: Simulated seconds per real second for synthetic prices (default: 1.0)
+    /// - `SYNTHETIC_SEED`: Seed for reproducible synthetic price paths (default: unset, random)
+    /// - `SERVER_HOST`: IP address to listen on, e.g. "0.0.0.0" for all interfaces (default: "127.0.0.1")
     /// - `SERVER_PORT`: Server port (default: 3000)
+    /// - `ADMIN_LISTEN_ADDR`: `ip:port` serving `/admin` instead of the API listener (default: unset)
+    /// - `GRPC_LISTEN_ADDR`: `ip:port` serving the gRPC trading API (default: unset, disabled)
+    /// - `TLS_CERT_PATH` / `TLS_KEY_PATH`: PEM certificate chain and key to serve HTTPS with (default: unset)
+    /// - `DATABASE_READ_URL`: Read replica for transaction, closed position and price history (default: unset, primary)
+    /// - `TRANSACTION_ARCHIVE_AFTER_DAYS`: Days after which transactions are archived, 0 to disable (default: 0)
     /// - `MAX_DB_CONNECTIONS`: Max DB connections (default: 5)
     /// - `LOG_LEVEL`: Log level (default: "info")
+    /// - `SENTRY_DSN`: Sentry-compatible DSN panics and server errors are reported to (default: unset)
+    /// - `SENTRY_ENVIRONMENT`: Environment reported errors are tagged with (default: unset)
     /// - `MAX_REQUEST_SIZE`: Max request body size in bytes (default: 1048576)
+    /// - `WS_PING_INTERVAL_SECS`: Seconds between pings to WebSocket clients (default: 20)
+    /// - `WS_IDLE_TIMEOUT_SECS`: Seconds of client silence before a WebSocket is dropped (default: 60)
+    /// - `WS_MAX_CONNECTIONS_PER_USER`: Concurrent WebSocket connections per user (default: 5)
+    /// - `WS_MAX_SUBSCRIPTIONS`: Subscriptions per WebSocket connection (default: 50)
+    /// - `WS_MAX_MESSAGES_PER_SEC`: Average messages per second a WebSocket client may send (default: 10)
+    /// - `WS_MESSAGE_BURST`: Messages a WebSocket client may send at once (default: 20)
+    /// - `WS_RESUME_WINDOW_SECS`: Seconds a disconnected WebSocket session can be resumed (default: 60)
     /// - `GRPC_TLS_ENABLED`: Enable TLS for gRPC (default: false)
+    /// - `GRPC_TLS_CA_CERT`: PEM file with an extra CA to trust for the feed (default: unset)
+    /// - `GRPC_TLS_DOMAIN`: Certificate name to verify if not the URL host (default: unset)
+    /// - `GRPC_TLS_CLIENT_CERT` / `GRPC_TLS_CLIENT_KEY`: PEM client certificate and key for mutual TLS (default: unset)
+    /// - `GRPC_AUTH_TOKEN`: Bearer token sent in the `authorization` metadata (default: unset)
     /// - `JWT_EXPIRATION_HOURS`: JWT token expiration in hours (default: 24)
+    /// - `JWT_KEY_ID`: `kid` header of tokens signed with `JWT_SECRET` (default: "primary")
+    /// - `JWT_VERIFICATION_KEYS`: Retired secrets still accepted, as `kid:secret,...` (default: unset)
+    /// - `ADMIN_API_KEY`: Key for the `X-Admin-Key` header on admin endpoints (default: unset)
+    /// - `LOGIN_MAX_FAILURES`: Failed logins of an email before a lockout (default: 5)
+    /// - `LOGIN_MAX_FAILURES_PER_IP`: Failed logins from an IP before a lockout (default: 20)
+    /// - `LOGIN_LOCKOUT_SECS`: Seconds a login lockout lasts (default: 900)
+    /// - `PASSWORD_MIN_SCORE`: Lowest strength score (0-4) of new passwords (default: 3)
+    /// - `PASSWORD_DENY_LIST`: Comma-separated passwords always refused (default: unset)
+    /// - `MONEY_MARKET_YIELD_PERCENT`: Annual yield paid on swept cash (default: 4.0)
+    /// - `LOAN_INTEREST_PERCENT`: Annual interest charged on secured loans (default: 8.0)
+    /// - `LOAN_MAX_LTV_PERCENT`: Maximum loan-to-value when borrowing (default: 50.0)
+    /// - `LOAN_MARGIN_CALL_LTV_PERCENT`: Loan-to-value triggering liquidation (default: 75.0)
+    /// - `TRADE_FEE_PERCENT`: Commission on trades under the realistic difficulty (default: 0.1)
+    /// - `OPTION_VOLATILITY_PERCENT`: Annual volatility of the option pricing model (default: 30.0)
+    /// - `OPTION_RISK_FREE_PERCENT`: Annual risk-free rate of the option pricing model (default: 4.0)
+    /// - `STARTING_BALANCE`: Cash a new account starts with (default: 1000.0)
+    /// - `WEEKLY_ALLOWANCE`: Cash credited weekly to active accounts, 0 to disable (default: 0)
+    /// - `ALLOWANCE_ACTIVE_DAYS`: Days since the last login an account counts as active (default: 7)
+    /// - `BOT_COUNT`: Bot accounts created to trade on their own, 0 to disable (default: 0)
+    /// - `BOT_TRADE_INTERVAL_SECS`: Seconds between the bots' trading rounds (default: 30)
+    /// - `WEBHOOK_MAX_ATTEMPTS`: Attempts at delivering an event to a webhook (default: 8)
+    /// - `WEBHOOK_RETRY_BASE_SECS`: Seconds before the first webhook retry, doubling with
+    ///   every retry (default: 30)
+    /// - `WEBHOOK_ALLOW_INSECURE`: Allow `http` webhook URLs and deliveries to loopback and
+    ///   private addresses, for development only (default: false)
+    /// - `EVENT_BROKER`: `none`, `redis` or `nats` to stream domain events to (default: "none")
+    /// - `EVENT_BROKER_URL`: Broker URL, e.g. `nats://localhost:4222`; required by `nats`
+    ///   (default: `REDIS_URL` for `redis`)
+    /// - `EVENT_SUBJECT_PREFIX`: Prefix of event subjects and stream names (default: "sim")
+    /// - `SERVER_TIMING_ENABLED`: Add `Server-Timing` headers for profiling (default: false)
+    /// - `REQUEST_TIMEOUT_SECS`: Seconds before a request is cut off with `408` (default: 30)
+    /// - `HSTS_MAX_AGE_SECS`: `Strict-Transport-Security` max-age, 0 to omit it (default: 0)
+    /// - `CONTENT_SECURITY_POLICY`: `Content-Security-Policy` header, empty to omit it
+    ///   (default: "default-src 'none'; frame-ancestors 'none'")
+    /// - `RATE_LIMIT_PER_MINUTE`: Requests per minute per IP and per user, 0 to disable (default: 300)
+    /// - `RATE_LIMIT_AUTH_PER_MINUTE`: Requests per minute to `/auth` (default: 20)
+    /// - `RATE_LIMIT_TRADES_PER_MINUTE`: Writes per minute to `/transactions` and `/options` (default: 60)
     pub fn from_env() -> anyhow::Result<Self> {
         dotenvy::dotenv().ok();
 
         let jwt_secret = env::var("JWT_SECRET")
             .map_err(|_| anyhow::anyhow!("JWT_SECRET environment variable is required"))?;
-        
+
         // Validate JWT secret strength (minimum 32 characters for security)
         if jwt_secret.len() < 32 {
             return Err(anyhow::anyhow!(
@@ -76,36 +269,483 @@ impl Config {
             ));
         }
 
+        let jwt_key_id = env::var("JWT_KEY_ID").unwrap_or_else(|_| "primary".to_string());
+        if jwt_key_id.is_empty() || jwt_key_id.contains([':', ',']) {
+            return Err(anyhow::anyhow!("Invalid JWT_KEY_ID"));
+        }
+        // Retired keys only verify the tokens they signed until those expire
+        let mut jwt_verification_keys = Vec::new();
+        for entry in env::var("JWT_VERIFICATION_KEYS")
+            .ok()
+            .iter()
+            .flat_map(|keys| keys.split(','))
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (kid, secret) = entry.split_once(':').ok_or_else(|| {
+                anyhow::anyhow!("JWT_VERIFICATION_KEYS entries must be kid:secret")
+            })?;
+            if kid.is_empty()
+                || kid == jwt_key_id
+                || jwt_verification_keys.iter().any(|(other, _)| other == kid)
+            {
+                return Err(anyhow::anyhow!(
+                    "JWT_VERIFICATION_KEYS has an empty or duplicate kid: {}",
+                    kid
+                ));
+            }
+            if secret.len() < 32 {
+                return Err(anyhow::anyhow!(
+                    "JWT_VERIFICATION_KEYS secret of {} must be at least 32 characters long",
+                    kid
+                ));
+            }
+            jwt_verification_keys.push((kid.to_string(), secret.to_string()));
+        }
+
+        let loan_max_ltv_percent: f64 = env::var("LOAN_MAX_LTV_PERCENT")
+            .unwrap_or_else(|_| "50.0".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid LOAN_MAX_LTV_PERCENT"))?;
+        let loan_margin_call_ltv_percent: f64 = env::var("LOAN_MARGIN_CALL_LTV_PERCENT")
+            .unwrap_or_else(|_| "75.0".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid LOAN_MARGIN_CALL_LTV_PERCENT"))?;
+        if !(loan_max_ltv_percent > 0.0
+            && loan_max_ltv_percent < loan_margin_call_ltv_percent
+            && loan_margin_call_ltv_percent < 100.0)
+        {
+            return Err(anyhow::anyhow!(
+                "LOAN_MAX_LTV_PERCENT must be positive and below LOAN_MARGIN_CALL_LTV_PERCENT, which must be below 100"
+            ));
+        }
+
+        let trade_fee_percent: f64 = env::var("TRADE_FEE_PERCENT")
+            .unwrap_or_else(|_| "0.1".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid TRADE_FEE_PERCENT"))?;
+        if !(0.0..=10.0).contains(&trade_fee_percent) {
+            return Err(anyhow::anyhow!(
+                "TRADE_FEE_PERCENT must be between 0 and 10"
+            ));
+        }
+
+        let option_volatility_percent: f64 = env::var("OPTION_VOLATILITY_PERCENT")
+            .unwrap_or_else(|_| "30.0".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid OPTION_VOLATILITY_PERCENT"))?;
+        if !(option_volatility_percent > 0.0 && option_volatility_percent <= 500.0) {
+            return Err(anyhow::anyhow!(
+                "OPTION_VOLATILITY_PERCENT must be above 0 and at most 500"
+            ));
+        }
+        let option_risk_free_percent: f64 = env::var("OPTION_RISK_FREE_PERCENT")
+            .unwrap_or_else(|_| "4.0".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid OPTION_RISK_FREE_PERCENT"))?;
+        if !(0.0..=50.0).contains(&option_risk_free_percent) {
+            return Err(anyhow::anyhow!(
+                "OPTION_RISK_FREE_PERCENT must be between 0 and 50"
+            ));
+        }
+
+        let starting_balance: f64 = env::var("STARTING_BALANCE")
+            .unwrap_or_else(|_| "1000.0".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid STARTING_BALANCE"))?;
+        let weekly_allowance: f64 = env::var("WEEKLY_ALLOWANCE")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid WEEKLY_ALLOWANCE"))?;
+        if !(starting_balance >= 0.0 && weekly_allowance >= 0.0)
+            || starting_balance > 1_000_000_000.0
+            || weekly_allowance > 1_000_000_000.0
+        {
+            return Err(anyhow::anyhow!(
+                "STARTING_BALANCE and WEEKLY_ALLOWANCE must be between 0 and 1000000000"
+            ));
+        }
+        let allowance_active_days: u32 = env::var("ALLOWANCE_ACTIVE_DAYS")
+            .unwrap_or_else(|_| "7".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid ALLOWANCE_ACTIVE_DAYS"))?;
+        if allowance_active_days == 0 {
+            return Err(anyhow::anyhow!("ALLOWANCE_ACTIVE_DAYS must be at least 1"));
+        }
+        let transaction_archive_after_days: u32 = env::var("TRANSACTION_ARCHIVE_AFTER_DAYS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid TRANSACTION_ARCHIVE_AFTER_DAYS"))?;
+        let bot_count: u32 = env::var("BOT_COUNT")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid BOT_COUNT"))?;
+        if bot_count > 1000 {
+            return Err(anyhow::anyhow!("BOT_COUNT must be at most 1000"));
+        }
+        let bot_trade_interval_secs: u64 = env::var("BOT_TRADE_INTERVAL_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid BOT_TRADE_INTERVAL_SECS"))?;
+        if bot_trade_interval_secs < 2 {
+            return Err(anyhow::anyhow!(
+                "BOT_TRADE_INTERVAL_SECS must be at least 2"
+            ));
+        }
+        let webhook_max_attempts: u32 = env::var("WEBHOOK_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| "8".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid WEBHOOK_MAX_ATTEMPTS"))?;
+        if !(1..=20).contains(&webhook_max_attempts) {
+            return Err(anyhow::anyhow!(
+                "WEBHOOK_MAX_ATTEMPTS must be between 1 and 20"
+            ));
+        }
+        let webhook_retry_base_secs: u64 = env::var("WEBHOOK_RETRY_BASE_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid WEBHOOK_RETRY_BASE_SECS"))?;
+        if webhook_retry_base_secs == 0 {
+            return Err(anyhow::anyhow!(
+                "WEBHOOK_RETRY_BASE_SECS must be at least 1"
+            ));
+        }
+        let webhook_allow_insecure: bool = env::var("WEBHOOK_ALLOW_INSECURE")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid WEBHOOK_ALLOW_INSECURE"))?;
+
+        let price_provider = env::var("PRICE_PROVIDER").unwrap_or_else(|_| "grpc".to_string());
+        let grpc_server_url = match price_provider.as_str() {
+            "grpc" => env::var("GRPC_SERVER_URL")
+                .map_err(|_| anyhow::anyhow!("GRPC_SERVER_URL environment variable is required"))?,
+            "rest" | "synthetic" => env::var("GRPC_SERVER_URL").unwrap_or_default(),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "PRICE_PROVIDER must be grpc, rest or synthetic"
+                ));
+            }
+        };
+        let grpc_tls_enabled: bool = env::var("GRPC_TLS_ENABLED")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid GRPC_TLS_ENABLED"))?;
+        let optional = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+        let grpc_tls_client_cert = optional("GRPC_TLS_CLIENT_CERT");
+        let grpc_tls_client_key = optional("GRPC_TLS_CLIENT_KEY");
+        if grpc_tls_client_cert.is_some() != grpc_tls_client_key.is_some() {
+            return Err(anyhow::anyhow!(
+                "GRPC_TLS_CLIENT_CERT and GRPC_TLS_CLIENT_KEY must be set together"
+            ));
+        }
+        if grpc_tls_enabled && price_provider == "grpc" && !grpc_server_url.starts_with("https://")
+        {
+            return Err(anyhow::anyhow!(
+                "GRPC_SERVER_URL must use https:// when GRPC_TLS_ENABLED is set"
+            ));
+        }
+
+        let admin_listen_addr = optional("ADMIN_LISTEN_ADDR")
+            .map(|addr| addr.parse::<SocketAddr>())
+            .transpose()
+            .map_err(|_| anyhow::anyhow!("ADMIN_LISTEN_ADDR must be ip:port"))?;
+        let grpc_listen_addr = optional("GRPC_LISTEN_ADDR")
+            .map(|addr| addr.parse::<SocketAddr>())
+            .transpose()
+            .map_err(|_| anyhow::anyhow!("GRPC_LISTEN_ADDR must be ip:port"))?;
+        let sentry_dsn = optional("SENTRY_DSN");
+        if let Some(dsn) = &sentry_dsn {
+            dsn.parse::<sentry::types::Dsn>()
+                .map_err(|_| anyhow::anyhow!("Invalid SENTRY_DSN"))?;
+        }
+        let tls_cert_path = optional("TLS_CERT_PATH");
+        let tls_key_path = optional("TLS_KEY_PATH");
+        if tls_cert_path.is_some() != tls_key_path.is_some() {
+            return Err(anyhow::anyhow!(
+                "TLS_CERT_PATH and TLS_KEY_PATH must be set together"
+            ));
+        }
+
+        let event_broker = env::var("EVENT_BROKER").unwrap_or_else(|_| "none".to_string());
+        let event_broker_url = optional("EVENT_BROKER_URL");
+        match event_broker.as_str() {
+            "none" | "redis" => {}
+            "nats" => {
+                if !event_broker_url
+                    .as_deref()
+                    .is_some_and(|url| url.starts_with("nats://"))
+                {
+                    return Err(anyhow::anyhow!(
+                        "EVENT_BROKER_URL with a nats:// URL is required by the nats event broker"
+                    ));
+                }
+            }
+            _ => {
+                return Err(anyhow::anyhow!("EVENT_BROKER must be none, redis or nats"));
+            }
+        }
+        let event_subject_prefix =
+            env::var("EVENT_SUBJECT_PREFIX").unwrap_or_else(|_| "sim".to_string());
+        if event_subject_prefix.is_empty()
+            || !event_subject_prefix.split('.').all(|token| {
+                !token.is_empty()
+                    && token
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            })
+        {
+            return Err(anyhow::anyhow!(
+                "EVENT_SUBJECT_PREFIX must be dot-separated letters, digits, '-' and '_'"
+            ));
+        }
+
+        let price_rest_url = env::var("PRICE_REST_URL")
+            .ok()
+            .filter(|url| !url.is_empty());
+        if price_provider == "rest"
+            && !price_rest_url
+                .as_deref()
+                .is_some_and(|url| url.contains("{ticker}"))
+        {
+            return Err(anyhow::anyhow!(
+                "PRICE_REST_URL with a {{ticker}} placeholder is required by the rest price provider"
+            ));
+        }
+        let price_poll_interval_secs: u64 = env::var("PRICE_POLL_INTERVAL_SECS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid PRICE_POLL_INTERVAL_SECS"))?;
+        if price_poll_interval_secs == 0 {
+            return Err(anyhow::anyhow!(
+                "PRICE_POLL_INTERVAL_SECS must be at least 1"
+            ));
+        }
+
+        let synthetic_drift: f64 = env::var("SYNTHETIC_DRIFT")
+            .unwrap_or_else(|_| "0.05".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid SYNTHETIC_DRIFT"))?;
+        let synthetic_volatility: f64 = env::var("SYNTHETIC_VOLATILITY")
+            .unwrap_or_else(|_| "0.3".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid SYNTHETIC_VOLATILITY"))?;
+        let synthetic_time_scale: f64 = env::var("SYNTHETIC_TIME_SCALE")
+            .unwrap_or_else(|_| "1.0".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid SYNTHETIC_TIME_SCALE"))?;
+        if !(synthetic_drift.is_finite()
+            && synthetic_volatility.is_finite()
+            && synthetic_volatility >= 0.0
+            && synthetic_time_scale.is_finite()
+            && synthetic_time_scale > 0.0)
+        {
+            return Err(anyhow::anyhow!(
+                "SYNTHETIC_VOLATILITY must not be negative and SYNTHETIC_TIME_SCALE must be positive"
+            ));
+        }
+        let synthetic_seed = optional("SYNTHETIC_SEED")
+            .map(|seed| seed.parse::<u64>())
+            .transpose()
+            .map_err(|_| anyhow::anyhow!("SYNTHETIC_SEED must be a non-negative integer"))?;
+
+        let ws_ping_interval_secs: u64 = env::var("WS_PING_INTERVAL_SECS")
+            .unwrap_or_else(|_| "20".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid WS_PING_INTERVAL_SECS"))?;
+        let ws_idle_timeout_secs: u64 = env::var("WS_IDLE_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid WS_IDLE_TIMEOUT_SECS"))?;
+        let ws_max_messages_per_sec: u32 = env::var("WS_MAX_MESSAGES_PER_SEC")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid WS_MAX_MESSAGES_PER_SEC"))?;
+        let ws_message_burst: u32 = env::var("WS_MESSAGE_BURST")
+            .unwrap_or_else(|_| "20".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid WS_MESSAGE_BURST"))?;
+        if ws_max_messages_per_sec == 0 || ws_message_burst == 0 {
+            return Err(anyhow::anyhow!(
+                "WS_MAX_MESSAGES_PER_SEC and WS_MESSAGE_BURST must be at least 1"
+            ));
+        }
+        let ws_resume_window_secs: u64 = env::var("WS_RESUME_WINDOW_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid WS_RESUME_WINDOW_SECS"))?;
+        if ws_resume_window_secs == 0 {
+            return Err(anyhow::anyhow!("WS_RESUME_WINDOW_SECS must be at least 1"));
+        }
+        let request_timeout_secs: u64 = env::var("REQUEST_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid REQUEST_TIMEOUT_SECS"))?;
+        if request_timeout_secs == 0 {
+            return Err(anyhow::anyhow!("REQUEST_TIMEOUT_SECS must be at least 1"));
+        }
+        let login_max_failures: u32 = env::var("LOGIN_MAX_FAILURES")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid LOGIN_MAX_FAILURES"))?;
+        let login_max_failures_per_ip: u32 = env::var("LOGIN_MAX_FAILURES_PER_IP")
+            .unwrap_or_else(|_| "20".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid LOGIN_MAX_FAILURES_PER_IP"))?;
+        let login_lockout_secs: u64 = env::var("LOGIN_LOCKOUT_SECS")
+            .unwrap_or_else(|_| "900".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid LOGIN_LOCKOUT_SECS"))?;
+        if login_max_failures == 0 || login_max_failures_per_ip == 0 || login_lockout_secs == 0 {
+            return Err(anyhow::anyhow!(
+                "LOGIN_MAX_FAILURES, LOGIN_MAX_FAILURES_PER_IP and LOGIN_LOCKOUT_SECS must be at least 1"
+            ));
+        }
+        let password_min_score: u8 = env::var("PASSWORD_MIN_SCORE")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid PASSWORD_MIN_SCORE"))?;
+        if password_min_score > 4 {
+            return Err(anyhow::anyhow!(
+                "PASSWORD_MIN_SCORE must be between 0 and 4"
+            ));
+        }
+        let password_deny_list = env::var("PASSWORD_DENY_LIST")
+            .unwrap_or_default()
+            .split(',')
+            .map(|password| password.trim().to_lowercase())
+            .filter(|password| !password.is_empty())
+            .collect();
+        // Clients answer pings, so a live client is never idle for a whole timeout
+        if ws_ping_interval_secs == 0 || ws_idle_timeout_secs <= ws_ping_interval_secs {
+            return Err(anyhow::anyhow!(
+                "WS_PING_INTERVAL_SECS must be at least 1 and below WS_IDLE_TIMEOUT_SECS"
+            ));
+        }
+
         Ok(Config {
             database_url: env::var("DATABASE_URL")
                 .map_err(|_| anyhow::anyhow!("DATABASE_URL environment variable is required"))?,
+            database_read_url: optional("DATABASE_READ_URL"),
+            transaction_archive_after_days,
             redis_url: env::var("REDIS_URL")
                 .map_err(|_| anyhow::anyhow!("REDIS_URL environment variable is required"))?,
-            grpc_server_url: env::var("GRPC_SERVER_URL")
-                .map_err(|_| anyhow::anyhow!("GRPC_SERVER_URL environment variable is required"))?,
+            price_provider,
+            grpc_server_url,
+            price_rest_url,
+            price_rest_pointer: env::var("PRICE_REST_POINTER")
+                .unwrap_or_else(|_| "/price".to_string()),
+            price_poll_interval_secs,
+            max_price_age_secs: env::var("MAX_PRICE_AGE_SECS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid MAX_PRICE_AGE_SECS"))?,
+            synthetic_drift,
+            synthetic_volatility,
+            synthetic_time_scale,
+            synthetic_seed,
             jwt_secret,
-            server_host: env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
+            jwt_key_id,
+            jwt_verification_keys,
+            server_host: env::var("SERVER_HOST")
+                .unwrap_or_else(|_| "127.0.0.1".to_string())
+                .parse()
+                .map_err(|_| anyhow::anyhow!("SERVER_HOST must be an IP address"))?,
             server_port: env::var("SERVER_PORT")
                 .unwrap_or_else(|_| "3000".to_string())
                 .parse()
                 .map_err(|_| anyhow::anyhow!("Invalid SERVER_PORT"))?,
+            admin_listen_addr,
+            grpc_listen_addr,
+            tls_cert_path,
+            tls_key_path,
             max_db_connections: env::var("MAX_DB_CONNECTIONS")
                 .unwrap_or_else(|_| "5".to_string())
                 .parse()
                 .map_err(|_| anyhow::anyhow!("Invalid MAX_DB_CONNECTIONS"))?,
             log_level: env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()),
+            sentry_dsn,
+            sentry_environment: optional("SENTRY_ENVIRONMENT"),
             max_request_size: env::var("MAX_REQUEST_SIZE")
                 .unwrap_or_else(|_| "1048576".to_string()) // 1MB default
                 .parse()
                 .map_err(|_| anyhow::anyhow!("Invalid MAX_REQUEST_SIZE"))?,
-            grpc_tls_enabled: env::var("GRPC_TLS_ENABLED")
-                .unwrap_or_else(|_| "false".to_string())
+            ws_ping_interval_secs,
+            ws_idle_timeout_secs,
+            ws_max_connections_per_user: env::var("WS_MAX_CONNECTIONS_PER_USER")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid WS_MAX_CONNECTIONS_PER_USER"))?,
+            ws_max_subscriptions: env::var("WS_MAX_SUBSCRIPTIONS")
+                .unwrap_or_else(|_| "50".to_string())
                 .parse()
-                .map_err(|_| anyhow::anyhow!("Invalid GRPC_TLS_ENABLED"))?,
+                .map_err(|_| anyhow::anyhow!("Invalid WS_MAX_SUBSCRIPTIONS"))?,
+            ws_max_messages_per_sec,
+            ws_message_burst,
+            ws_resume_window_secs,
+            grpc_tls_enabled,
+            grpc_tls_ca_cert: optional("GRPC_TLS_CA_CERT"),
+            grpc_tls_domain: optional("GRPC_TLS_DOMAIN"),
+            grpc_tls_client_cert,
+            grpc_tls_client_key,
+            grpc_auth_token: optional("GRPC_AUTH_TOKEN"),
             jwt_expiration_hours: env::var("JWT_EXPIRATION_HOURS")
                 .unwrap_or_else(|_| "24".to_string())
                 .parse()
                 .map_err(|_| anyhow::anyhow!("Invalid JWT_EXPIRATION_HOURS"))?,
+            admin_api_key: env::var("ADMIN_API_KEY").ok().filter(|key| !key.is_empty()),
+            login_max_failures,
+            login_max_failures_per_ip,
+            login_lockout_secs,
+            password_min_score,
+            password_deny_list,
+            money_market_yield_percent: env::var("MONEY_MARKET_YIELD_PERCENT")
+                .unwrap_or_else(|_| "4.0".to_string())
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid MONEY_MARKET_YIELD_PERCENT"))?,
+            loan_interest_percent: env::var("LOAN_INTEREST_PERCENT")
+                .unwrap_or_else(|_| "8.0".to_string())
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid LOAN_INTEREST_PERCENT"))?,
+            loan_max_ltv_percent,
+            loan_margin_call_ltv_percent,
+            trade_fee_percent,
+            option_volatility_percent,
+            option_risk_free_percent,
+            starting_balance,
+            weekly_allowance,
+            allowance_active_days,
+            bot_count,
+            bot_trade_interval_secs,
+            webhook_max_attempts,
+            webhook_retry_base_secs,
+            webhook_allow_insecure,
+            event_broker,
+            event_broker_url,
+            event_subject_prefix,
+            server_timing_enabled: env::var("SERVER_TIMING_ENABLED")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid SERVER_TIMING_ENABLED"))?,
+            rate_limit_per_minute: env::var("RATE_LIMIT_PER_MINUTE")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid RATE_LIMIT_PER_MINUTE"))?,
+            rate_limit_auth_per_minute: env::var("RATE_LIMIT_AUTH_PER_MINUTE")
+                .unwrap_or_else(|_| "20".to_string())
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid RATE_LIMIT_AUTH_PER_MINUTE"))?,
+            rate_limit_trades_per_minute: env::var("RATE_LIMIT_TRADES_PER_MINUTE")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid RATE_LIMIT_TRADES_PER_MINUTE"))?,
+            request_timeout_secs,
+            hsts_max_age_secs: env::var("HSTS_MAX_AGE_SECS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid HSTS_MAX_AGE_SECS"))?,
+            // The API only serves JSON, so nothing needs to load or frame it
+            content_security_policy: env::var("CONTENT_SECURITY_POLICY")
+                .unwrap_or_else(|_| "default-src 'none'; frame-ancestors 'none'".to_string()),
         })
     }
 }
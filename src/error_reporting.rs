@@ -0,0 +1,102 @@
+//! # Error Reporting
+//!
+//! With `SENTRY_DSN` set, panics and the server errors answered to requests
+//! (`Error::Database` and `Error::InternalServerError`) are reported to
+//! Sentry, or any service accepting its protocol like GlitchTip. Nothing is
+//! reported without a DSN.
+//!
+//! Every request is handled with a Sentry scope of its own, so its reports
+//! carry the request ID, method and route, the ID of the user once the
+//! bearer token has been checked, and the warnings and errors logged while
+//! handling it as breadcrumbs.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use sentry::{
+    Hub, Level, SentryFutureExt,
+    integrations::tracing::{EventFilter, SentryLayer},
+};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::{Error, config::Config, request_id};
+
+/// Start reporting to `SENTRY_DSN`, until the returned guard is dropped
+///
+/// Reports still queued when the guard drops are flushed first.
+pub fn init(config: &Config) -> Option<sentry::ClientInitGuard> {
+    let dsn = config.sentry_dsn.as_deref()?;
+    let guard = sentry::init((
+        dsn,
+        sentry::ClientOptions {
+            release: sentry::release_name!(),
+            environment: config.sentry_environment.clone().map(Into::into),
+            ..Default::default()
+        },
+    ));
+    tracing::info!("Reporting errors to Sentry");
+
+    Some(guard)
+}
+
+/// Tracing layer keeping warnings and errors as breadcrumbs of the next report
+///
+/// Errors are reported where they are answered, so log lines are never
+/// reported on their own.
+pub fn layer<S>(config: &Config) -> Option<SentryLayer<S>>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    config.sentry_dsn.as_ref()?;
+    let layer =
+        sentry::integrations::tracing::layer().event_filter(|metadata| match *metadata.level() {
+            tracing::Level::ERROR | tracing::Level::WARN => EventFilter::Breadcrumb,
+            _ => EventFilter::Ignore,
+        });
+
+    Some(layer)
+}
+
+/// Middleware handling every request with a Sentry scope of its own
+pub async fn error_context(request: Request, next: Next) -> Response {
+    let hub = Arc::new(Hub::new_from_top(Hub::current()));
+    hub.configure_scope(|scope| {
+        if let Some(id) = request_id::current() {
+            scope.set_tag("request_id", id);
+        }
+        scope.set_tag("method", request.method());
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map_or_else(|| request.uri().path(), MatchedPath::as_str);
+        scope.set_tag("route", route);
+    });
+
+    next.run(request).bind_hub(hub).await
+}
+
+/// Attach the authenticated user to the reports of the current request
+pub fn set_user(user_id: i32) {
+    sentry::configure_scope(|scope| {
+        scope.set_user(Some(sentry::User {
+            id: Some(user_id.to_string()),
+            ..Default::default()
+        }));
+    });
+}
+
+/// Report a server error answered to a request
+pub fn capture(error: &Error) {
+    match error {
+        Error::Database(e) => {
+            sentry::capture_error(e);
+        }
+        other => {
+            sentry::capture_message(&other.to_string(), Level::Error);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use axum::{http::StatusCode, response::IntoResponse};
-use serde_json::json;
-use chrono;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -9,6 +10,7 @@ pub enum Error {
     Database(sqlx::Error),
     NotFound,
     Unauthorized,
+    Forbidden,
     BadRequest(String),
     InternalServerError,
     LoginFailed,
@@ -16,14 +18,42 @@ pub enum Error {
     Conflict(String),
     GrpcError(String),
     RedisError(String),
+    PriceFeed(String),
+    /// Throttled; the client may retry after this many seconds
+    TooManyRequests(u64),
+    PayloadTooLarge,
 }
 
-impl IntoResponse for Error {
-    fn into_response(self) -> axum::response::Response {
-        let (status, error_message) = match &self {
+/// Body of every error response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: String,
+    pub timestamp: DateTime<Utc>,
+    /// ID of the failed request, as in its `X-Request-Id` header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ErrorResponse {
+    /// Error of the request being handled, happening now
+    pub fn new(error: impl Into<String>) -> Self {
+        ErrorResponse {
+            error: error.into(),
+            timestamp: Utc::now(),
+            request_id: crate::request_id::current(),
+        }
+    }
+}
+
+impl Error {
+    /// Status and client-facing message of the error, logging and reporting
+    /// the details that are not exposed
+    pub fn status_and_message(&self) -> (StatusCode, String) {
+        match self {
             Error::Database(_e) => {
                 // Log the actual error but don't expose it to users
                 tracing::error!("Database error: {}", _e);
+                crate::error_reporting::capture(self);
                 (
                     axum::http::StatusCode::INTERNAL_SERVER_ERROR,
                     "Internal server error".to_string(),
@@ -37,6 +67,10 @@ impl IntoResponse for Error {
                 axum::http::StatusCode::UNAUTHORIZED,
                 "Unauthorized".to_string(),
             ),
+            Error::Forbidden => (
+                axum::http::StatusCode::FORBIDDEN,
+                "Forbidden".to_string(),
+            ),
             Error::BadRequest(msg) => {
                 // Sanitize error messages to prevent information disclosure
                 let sanitized_msg = if msg.len() > 200 {
@@ -49,10 +83,13 @@ impl IntoResponse for Error {
                     format!("Bad request: {}", sanitized_msg),
                 )
             },
-            Error::InternalServerError => (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                "Internal server error".to_string(),
-            ),
+            Error::InternalServerError => {
+                crate::error_reporting::capture(self);
+                (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            },
             Error::LoginFailed => (
                 axum::http::StatusCode::UNAUTHORIZED,
                 "Invalid credentials".to_string(),
@@ -81,14 +118,39 @@ impl IntoResponse for Error {
                     "Cache service unavailable".to_string(),
                 )
             },
-        };
+            Error::PriceFeed(_msg) => {
+                // Log the actual error but provide generic message
+                tracing::error!("Price feed error: {}", _msg);
+                (
+                    StatusCode::BAD_GATEWAY,
+                    "External service unavailable".to_string(),
+                )
+            },
+            Error::TooManyRequests(_) => (
+                axum::http::StatusCode::TOO_MANY_REQUESTS,
+                "Too many requests".to_string(),
+            ),
+            Error::PayloadTooLarge => (
+                axum::http::StatusCode::PAYLOAD_TOO_LARGE,
+                "Request body too large".to_string(),
+            ),
+        }
+    }
+}
 
-        let body = axum::Json(json!({
-            "error": error_message,
-            "timestamp": chrono::Utc::now().to_rfc3339(),
-        }));
+impl IntoResponse for Error {
+    fn into_response(self) -> axum::response::Response {
+        let (status, error_message) = self.status_and_message();
+        let body = axum::Json(ErrorResponse::new(error_message));
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if let Error::TooManyRequests(retry_after) = self {
+            response.headers_mut().insert(
+                axum::http::header::RETRY_AFTER,
+                axum::http::HeaderValue::from(retry_after),
+            );
+        }
+        response
     }
 }
 
@@ -98,6 +160,7 @@ impl std::fmt::Display for Error {
             Error::Database(e) => write!(f, "Database error: {}", e),
             Error::NotFound => write!(f, "Resource not found"),
             Error::Unauthorized => write!(f, "Unauthorized"),
+            Error::Forbidden => write!(f, "Forbidden"),
             Error::BadRequest(msg) => write!(f, "Bad request: {}", msg),
             Error::InternalServerError => write!(f, "Internal server error"),
             Error::LoginFailed => write!(f, "Login failed"),
@@ -105,6 +168,9 @@ impl std::fmt::Display for Error {
             Error::Conflict(msg) => write!(f, "Conflict: {}", msg),
             Error::GrpcError(msg) => write!(f, "gRPC error: {}", msg),
             Error::RedisError(msg) => write!(f, "Redis error: {}", msg),
+            Error::PriceFeed(msg) => write!(f, "Price feed error: {}", msg),
+            Error::TooManyRequests(secs) => write!(f, "Too many requests, retry after {}s", secs),
+            Error::PayloadTooLarge => write!(f, "Payload too large"),
         }
     }
 }
@@ -0,0 +1,166 @@
+//! # GraphQL API
+//!
+//! A GraphQL endpoint next to the REST API, for frontends that would
+//! otherwise stitch a screen together from several REST calls. Queries at
+//! `POST /graphql` select exactly the fields they need of quotes and the
+//! user's portfolios with their valuation, holdings and transactions; the
+//! mutations place market orders. Only the selected fields are resolved, so
+//! a query for a portfolio's cash does not value its positions.
+//!
+//! Subscriptions are served at `/graphql/ws` over the `graphql-transport-ws`
+//! protocol (or the older `graphql-ws`) and stream prices from the
+//! instance's [`fanout`](crate::ws::fanout), like price subscriptions of
+//! `/ws`. Both endpoints authenticate with the bearer token of the REST API;
+//! queries without one may only read quotes. Subscription connections count
+//! against `WS_MAX_CONNECTIONS_PER_USER` together with `/ws` connections.
+//!
+//! Errors carry the status the REST API would answer with as their `status`
+//! extension.
+
+use async_graphql::{
+    ErrorExtensions, Schema,
+    http::{ALL_WEBSOCKET_PROTOCOLS, WebSocket as GraphQLWebSocket, WebSocketProtocols, WsMessage},
+};
+use axum::{
+    Extension, Router,
+    extract::{
+        WebSocketUpgrade,
+        ws::{CloseFrame, Message, WebSocket, close_code},
+    },
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use futures_util::{SinkExt, StreamExt, future};
+
+use crate::{AppState, Error, auth::jwt::Claims, timing::Json, ws::limits::ConnectionSlot};
+
+mod schema;
+mod types;
+
+use schema::{MutationRoot, QueryRoot, SubscriptionRoot};
+
+/// Deepest nesting of fields a query may select
+const MAX_DEPTH: usize = 10;
+/// Most fields a query may select, counting every field of every list once
+const MAX_COMPLEXITY: usize = 500;
+
+type ApiSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
+
+/// `POST /graphql` and the subscriptions at `/graphql/ws`
+pub fn routes(state: &AppState) -> Router {
+    let schema = Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
+        .data(state.clone())
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish();
+
+    Router::new()
+        .route("/graphql", post(execute))
+        .route("/graphql/ws", get(subscribe))
+        .layer(Extension(schema))
+}
+
+/// GraphQL error of an API error, with its REST status as `status` extension
+fn api_error(error: Error) -> async_graphql::Error {
+    let (status, message) = error.status_and_message();
+    async_graphql::Error::new(message).extend_with(|_, e| e.set("status", status.as_u16()))
+}
+
+async fn execute(
+    Extension(schema): Extension<ApiSchema>,
+    claims: Option<Claims>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let request = match claims {
+        Some(claims) => request.data(claims),
+        None => request,
+    };
+
+    Json(schema.execute(request).await)
+}
+
+async fn subscribe(
+    Extension(schema): Extension<ApiSchema>,
+    state: Extension<AppState>,
+    claims: Claims,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let ws = ws.protocols(ALL_WEBSOCKET_PROTOCOLS);
+    let protocol = ws
+        .selected_protocol()
+        .and_then(|protocol| protocol.to_str().ok()?.parse().ok())
+        .unwrap_or(WebSocketProtocols::GraphQLWS);
+
+    ws.on_upgrade(move |socket| serve(socket, schema, state.0, claims, protocol))
+        .into_response()
+}
+
+/// Run the subscriptions of a connection until either side closes it
+async fn serve(
+    socket: WebSocket,
+    schema: ApiSchema,
+    state: AppState,
+    claims: Claims,
+    protocol: WebSocketProtocols,
+) {
+    let (mut sink, stream) = socket.split();
+
+    // Without Redis the limit cannot be checked, which must not take subscriptions down
+    let slot = match ConnectionSlot::acquire(&state, claims.user_id).await {
+        Ok(Some(slot)) => Some(slot),
+        Ok(None) => {
+            let _ = sink
+                .send(Message::Close(Some(CloseFrame {
+                    code: close_code::POLICY,
+                    reason: "too many connections".into(),
+                })))
+                .await;
+            return;
+        }
+        Err(e) => {
+            tracing::warn!("Failed to count WebSocket connections: {}", e);
+            None
+        }
+    };
+
+    let incoming = stream
+        .take_while(|message| future::ready(message.is_ok()))
+        .filter_map(|message| {
+            let state = &state;
+            let slot = slot.as_ref();
+            async move {
+                if let Some(slot) = slot {
+                    slot.refresh(state).await;
+                }
+                match message {
+                    Ok(Message::Text(text)) => Some(text.as_str().as_bytes().to_vec()),
+                    Ok(Message::Binary(bytes)) => Some(bytes.to_vec()),
+                    _ => None,
+                }
+            }
+        });
+
+    let mut outgoing = std::pin::pin!(
+        GraphQLWebSocket::new(schema, incoming, protocol).connection_data({
+            let mut data = async_graphql::Data::default();
+            data.insert(claims);
+            data
+        })
+    );
+    while let Some(message) = outgoing.next().await {
+        let message = match message {
+            WsMessage::Text(text) => Message::Text(text.into()),
+            WsMessage::Close(code, reason) => Message::Close(Some(CloseFrame {
+                code,
+                reason: reason.into(),
+            })),
+        };
+        if sink.send(message).await.is_err() {
+            break;
+        }
+    }
+
+    if let Some(slot) = &slot {
+        slot.release(&state).await;
+    }
+}
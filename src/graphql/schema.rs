@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, Result, Subscription};
+use futures_util::{Stream, stream};
+use tokio::sync::broadcast::{Receiver, error::RecvError};
+
+use super::{
+    api_error,
+    types::{Portfolio, PriceUpdate, Quote, Transaction},
+};
+use crate::{
+    AppState, Error,
+    auth::{jwt::Claims, portfolio},
+    models::portfolio::Portfolio as PortfolioModel,
+    rate_limit,
+    repository::portfolio_repository::PortfolioRepository,
+    services::{price_updater::PriceMessage, quotes, trading},
+};
+
+/// The user a request was authenticated as
+fn claims<'a>(ctx: &'a Context<'_>) -> Result<&'a Claims> {
+    ctx.data_opt::<Claims>()
+        .ok_or_else(|| api_error(Error::Unauthorized))
+}
+
+/// The user's portfolio with ID `portfolio_id`, or their default portfolio
+///
+/// Resolves the portfolio like the `X-Portfolio-Id` header of the REST API.
+async fn select_portfolio(ctx: &Context<'_>, portfolio_id: Option<i32>) -> Result<PortfolioModel> {
+    let claims = claims(ctx)?;
+    let state = ctx.data::<AppState>()?;
+
+    portfolio::select(state, claims.user_id, portfolio_id)
+        .await
+        .map_err(api_error)
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Quote of a ticker, null when no price is known for it
+    async fn quote(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(min_length = 1, max_length = 10))] ticker: String,
+    ) -> Result<Option<Quote>> {
+        let state = ctx.data::<AppState>()?;
+        let mut quotes = quotes::quotes(state, &[ticker]).await.map_err(api_error)?;
+
+        Ok(quotes.pop().map(Quote))
+    }
+
+    /// Quotes of tickers in the given order; tickers without any price are skipped
+    async fn quotes(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(min_items = 1, max_items = 50))] tickers: Vec<String>,
+    ) -> Result<Vec<Quote>> {
+        let state = ctx.data::<AppState>()?;
+        let quotes = quotes::quotes(state, &tickers).await.map_err(api_error)?;
+
+        Ok(quotes.into_iter().map(Quote).collect())
+    }
+
+    /// A portfolio of the authenticated user, by default their default portfolio
+    async fn portfolio(&self, ctx: &Context<'_>, id: Option<i32>) -> Result<Portfolio> {
+        Ok(Portfolio::new(select_portfolio(ctx, id).await?))
+    }
+
+    /// All portfolios of the authenticated user
+    async fn portfolios(&self, ctx: &Context<'_>) -> Result<Vec<Portfolio>> {
+        let claims = claims(ctx)?;
+        let state = ctx.data::<AppState>()?;
+        let portfolios = PortfolioRepository::new(&state.pg_pool)
+            .get_portfolios_by_user(claims.user_id)
+            .await
+            .map_err(api_error)?;
+
+        Ok(portfolios.into_iter().map(Portfolio::new).collect())
+    }
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    /// Buy shares at market, into the default portfolio unless `portfolioId` is given
+    async fn buy(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(min_length = 1, max_length = 10))] ticker: String,
+        #[graphql(validator(minimum = 1, maximum = 10000))] quantity: i32,
+        portfolio_id: Option<i32>,
+    ) -> Result<Transaction> {
+        let state = ctx.data::<AppState>()?;
+        let portfolio = select_portfolio(ctx, portfolio_id).await?;
+        rate_limit::count_trade(state, portfolio.user_id)
+            .await
+            .map_err(api_error)?;
+        let transaction = trading::buy(state, &portfolio, &ticker, quantity)
+            .await
+            .map_err(api_error)?;
+
+        Ok(transaction.into())
+    }
+
+    /// Sell shares at market, out of the default portfolio unless `portfolioId` is given
+    async fn sell(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(min_length = 1, max_length = 10))] ticker: String,
+        #[graphql(validator(minimum = 1, maximum = 10000))] quantity: i32,
+        portfolio_id: Option<i32>,
+    ) -> Result<Transaction> {
+        let state = ctx.data::<AppState>()?;
+        let portfolio = select_portfolio(ctx, portfolio_id).await?;
+        rate_limit::count_trade(state, portfolio.user_id)
+            .await
+            .map_err(api_error)?;
+        let (transaction, realized_gain) = trading::sell(state, &portfolio, &ticker, quantity)
+            .await
+            .map_err(api_error)?;
+
+        let mut transaction = Transaction::from(transaction);
+        transaction.realized_gain = Some(realized_gain);
+
+        Ok(transaction)
+    }
+}
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Every price the feed publishes for `tickers`
+    ///
+    /// Updates a subscriber is too slow to receive are skipped.
+    async fn prices(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(validator(min_items = 1, max_items = 50))] tickers: Vec<String>,
+    ) -> Result<impl Stream<Item = PriceUpdate> + use<>> {
+        let state = ctx.data::<AppState>()?;
+        let receivers = tickers
+            .iter()
+            .map(|ticker| updates(state.price_fanout.subscribe(ticker)));
+
+        Ok(stream::select_all(receivers))
+    }
+}
+
+/// The updates of a fanout receiver, ending when the fanout goes away
+fn updates(receiver: Receiver<Arc<PriceMessage>>) -> impl Stream<Item = PriceUpdate> + Unpin {
+    Box::pin(stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(message) => return Some((PriceUpdate::from(&*message), receiver)),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }))
+}
@@ -0,0 +1,322 @@
+use async_graphql::{ComplexObject, Context, Object, Result, SimpleObject};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use super::api_error;
+use crate::{
+    AppState,
+    models::{
+        portfolio::Portfolio as PortfolioModel, transaction::Transaction as TransactionModel,
+    },
+    repository::transaction_repository::TransactionFilter,
+    services::{
+        portfolio::{self, PortfolioValuation, PositionValuation},
+        price_updater::PriceMessage,
+        quotes::Quote as QuoteModel,
+    },
+};
+
+/// Last price, day change and day range of a ticker
+pub struct Quote(pub QuoteModel);
+
+#[Object]
+impl Quote {
+    async fn ticker(&self) -> &str {
+        &self.0.ticker
+    }
+
+    async fn price(&self) -> &BigDecimal {
+        &self.0.price
+    }
+
+    /// When the price was last updated, unknown for prices read from candles
+    async fn timestamp(&self) -> Option<DateTime<Utc>> {
+        self.0.timestamp
+    }
+
+    async fn previous_close(&self) -> Option<&BigDecimal> {
+        self.0.previous_close.as_ref()
+    }
+
+    async fn change(&self) -> Option<&BigDecimal> {
+        self.0.change.as_ref()
+    }
+
+    async fn change_percent(&self) -> Option<&BigDecimal> {
+        self.0.change_percent.as_ref()
+    }
+
+    async fn day_high(&self) -> &BigDecimal {
+        &self.0.day_high
+    }
+
+    async fn day_low(&self) -> &BigDecimal {
+        &self.0.day_low
+    }
+
+    /// The price is older than `MAX_PRICE_AGE_SECS`
+    async fn stale(&self) -> bool {
+        self.0.stale
+    }
+}
+
+/// A portfolio of the authenticated user
+///
+/// Valuation fields share one valuation at the latest cached prices, computed
+/// on first use.
+pub struct Portfolio {
+    portfolio: PortfolioModel,
+    valuation: OnceCell<PortfolioValuation>,
+}
+
+impl Portfolio {
+    pub fn new(portfolio: PortfolioModel) -> Self {
+        Portfolio {
+            portfolio,
+            valuation: OnceCell::new(),
+        }
+    }
+
+    async fn valuation(&self, ctx: &Context<'_>) -> Result<&PortfolioValuation> {
+        let state = ctx.data::<AppState>()?;
+        self.valuation
+            .get_or_try_init(|| portfolio::value_portfolio(state, &self.portfolio))
+            .await
+            .map_err(api_error)
+    }
+}
+
+#[Object]
+impl Portfolio {
+    async fn id(&self) -> i32 {
+        self.portfolio.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.portfolio.name
+    }
+
+    /// Cash held in this portfolio
+    async fn cash(&self) -> &BigDecimal {
+        &self.portfolio.balance
+    }
+
+    async fn is_default(&self) -> bool {
+        self.portfolio.is_default
+    }
+
+    async fn competition_id(&self) -> Option<i32> {
+        self.portfolio.competition_id
+    }
+
+    async fn class_id(&self) -> Option<i32> {
+        self.portfolio.class_id
+    }
+
+    async fn is_public(&self) -> bool {
+        self.portfolio.is_public
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.portfolio.created_at
+    }
+
+    /// Cash, money market balance, options and market value of all positions,
+    /// less loans
+    async fn equity(&self, ctx: &Context<'_>) -> Result<&BigDecimal> {
+        Ok(&self.valuation(ctx).await?.equity)
+    }
+
+    async fn market_value(&self, ctx: &Context<'_>) -> Result<&BigDecimal> {
+        Ok(&self.valuation(ctx).await?.market_value)
+    }
+
+    async fn unrealized_pnl(&self, ctx: &Context<'_>) -> Result<&BigDecimal> {
+        Ok(&self.valuation(ctx).await?.unrealized_pnl)
+    }
+
+    async fn holdings(&self, ctx: &Context<'_>) -> Result<Vec<Holding>> {
+        let valuation = self.valuation(ctx).await?;
+        Ok(valuation
+            .positions
+            .iter()
+            .map(|position| Holding {
+                position: position.clone(),
+                percent_of_portfolio: portfolio::percent_of(
+                    &position.market_value,
+                    &valuation.equity,
+                ),
+            })
+            .collect())
+    }
+
+    /// Transactions, newest first
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 50, validator(minimum = 1, maximum = 200))] first: i32,
+        #[graphql(desc = "`nextCursor` of the previous page")] after: Option<Uuid>,
+        #[graphql(validator(min_length = 1, max_length = 10))] ticker: Option<String>,
+    ) -> Result<TransactionPage> {
+        let state = ctx.data::<AppState>()?;
+        let filter = TransactionFilter {
+            ticker,
+            transaction_type: None,
+            from: None,
+            to: None,
+            min_price: None,
+            max_price: None,
+        };
+        let limit = first as usize;
+
+        // Fetch one extra row to learn whether another page follows
+        let mut transactions = state
+            .read_repos
+            .transactions
+            .get_transactions_page(self.portfolio.id, &filter, after, true, limit as i64 + 1)
+            .await
+            .map_err(api_error)?;
+        let next_cursor = if transactions.len() > limit {
+            transactions.truncate(limit);
+            transactions.last().map(|transaction| transaction.public_id)
+        } else {
+            None
+        };
+
+        Ok(TransactionPage {
+            transactions: transactions.into_iter().map(Transaction::from).collect(),
+            next_cursor,
+        })
+    }
+}
+
+/// A position valued at the latest cached price
+pub struct Holding {
+    position: PositionValuation,
+    percent_of_portfolio: BigDecimal,
+}
+
+#[Object]
+impl Holding {
+    async fn ticker(&self) -> &str {
+        &self.position.ticker
+    }
+
+    async fn quantity(&self) -> i32 {
+        self.position.quantity
+    }
+
+    async fn average_price(&self) -> &BigDecimal {
+        &self.position.average_price
+    }
+
+    /// Latest price, null when no price is cached for the ticker
+    async fn current_price(&self) -> Option<&BigDecimal> {
+        self.position.current_price.as_ref()
+    }
+
+    async fn cost_basis(&self) -> &BigDecimal {
+        &self.position.cost_basis
+    }
+
+    async fn market_value(&self) -> &BigDecimal {
+        &self.position.market_value
+    }
+
+    async fn unrealized_pnl(&self) -> &BigDecimal {
+        &self.position.unrealized_pnl
+    }
+
+    async fn unrealized_pnl_percent(&self) -> &BigDecimal {
+        &self.position.unrealized_pnl_percent
+    }
+
+    /// Market value in percent of the portfolio's equity
+    async fn percent_of_portfolio(&self) -> &BigDecimal {
+        &self.percent_of_portfolio
+    }
+
+    /// When the position was opened
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.position.created_at
+    }
+
+    /// Last trade or corporate action that changed the position
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.position.updated_at
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct Transaction {
+    id: Uuid,
+    ticker: String,
+    quantity: i32,
+    price: BigDecimal,
+    /// Commission paid on top of the price
+    fee: BigDecimal,
+    /// `buy` or `sell`
+    transaction_type: String,
+    #[graphql(skip)]
+    created_at: NaiveDateTime,
+    #[graphql(skip)]
+    updated_at: NaiveDateTime,
+    /// Gain realized by a sell, only set on the result of `sell`
+    pub realized_gain: Option<BigDecimal>,
+}
+
+#[ComplexObject]
+impl Transaction {
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.created_at.and_utc()
+    }
+
+    /// Differs from `createdAt` only for backdated seed trades
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at.and_utc()
+    }
+}
+
+impl From<TransactionModel> for Transaction {
+    fn from(transaction: TransactionModel) -> Self {
+        Transaction {
+            id: transaction.public_id,
+            ticker: transaction.ticker,
+            quantity: transaction.quantity,
+            price: transaction.price,
+            fee: transaction.fee,
+            transaction_type: transaction.transaction_type,
+            created_at: transaction.created_at,
+            updated_at: transaction.updated_at,
+            realized_gain: None,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct TransactionPage {
+    transactions: Vec<Transaction>,
+    /// Pass as `after` to fetch the next page; null on the last page
+    next_cursor: Option<Uuid>,
+}
+
+/// A price published by the feed
+#[derive(SimpleObject)]
+pub struct PriceUpdate {
+    ticker: String,
+    price: f64,
+    timestamp: DateTime<Utc>,
+}
+
+impl From<&PriceMessage> for PriceUpdate {
+    fn from(message: &PriceMessage) -> Self {
+        PriceUpdate {
+            ticker: message.ticker.clone(),
+            price: message.price,
+            timestamp: message.timestamp,
+        }
+    }
+}
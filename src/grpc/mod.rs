@@ -1,62 +1,151 @@
-use std::sync::Arc;
-
+use futures_util::{StreamExt, TryStreamExt, future::BoxFuture};
 use price_feed::PriceRequest;
-use redis::AsyncCommands;
-use tonic::transport::Channel;
+use tonic::{
+    metadata::{Ascii, MetadataValue},
+    transport::{Certificate, Channel, ClientTlsConfig, Identity},
+};
 
-use crate::{AppState, Result};
+use crate::{
+    Error, Result,
+    config::Config,
+    services::price_provider::{PriceProvider, PriceStream, PriceTick},
+};
 use price_feed::price_feed_client::PriceFeedClient;
 
+pub mod trading;
+
 pub mod price_feed {
     tonic::include_proto!("pricefeed");
 }
 
-pub async fn price_updater(state: Arc<AppState>) -> Result<()> {
-    let channel = Channel::from_shared(state.config.grpc_server_url.clone())
-        .map_err(|e| crate::errors::Error::GrpcError(e.to_string()))?
-        .connect()
-        .await
-        .map_err(|e| crate::errors::Error::GrpcError(e.to_string()))?;
+/// Streams prices from the external gRPC price feed
+///
+/// Certificates are read on every connection attempt, so rotated files are
+/// picked up when the updater reconnects.
+pub struct GrpcPriceProvider {
+    url: String,
+    tls_enabled: bool,
+    tls_ca_cert: Option<String>,
+    tls_domain: Option<String>,
+    tls_client_identity: Option<(String, String)>,
+    auth_token: Option<String>,
+}
+
+impl GrpcPriceProvider {
+    pub fn new(config: &Config) -> Self {
+        GrpcPriceProvider {
+            url: config.grpc_server_url.clone(),
+            tls_enabled: config.grpc_tls_enabled,
+            tls_ca_cert: config.grpc_tls_ca_cert.clone(),
+            tls_domain: config.grpc_tls_domain.clone(),
+            tls_client_identity: config
+                .grpc_tls_client_cert
+                .clone()
+                .zip(config.grpc_tls_client_key.clone()),
+            auth_token: config.grpc_auth_token.clone(),
+        }
+    }
 
-    let mut client = PriceFeedClient::new(channel);
+    /// TLS settings for the channel: system roots plus the configured CA,
+    /// and a client certificate for mutual TLS when one is configured
+    async fn tls_config(&self) -> Result<ClientTlsConfig> {
+        let mut tls = ClientTlsConfig::new().with_native_roots();
+        if let Some(path) = &self.tls_ca_cert {
+            tls = tls.ca_certificate(Certificate::from_pem(read_pem(path).await?));
+        }
+        if let Some(domain) = &self.tls_domain {
+            tls = tls.domain_name(domain);
+        }
+        if let Some((cert, key)) = &self.tls_client_identity {
+            tls = tls.identity(Identity::from_pem(
+                read_pem(cert).await?,
+                read_pem(key).await?,
+            ));
+        }
 
-    let request = tonic::Request::new(PriceRequest {
-        ticker: "ALL".into(),
-    });
+        Ok(tls)
+    }
 
-    let mut stream = client
-        .stream_prices(request)
-        .await
-        .map_err(|e| crate::errors::Error::GrpcError(e.to_string()))?
-        .into_inner();
+    async fn connect(&self) -> Result<Channel> {
+        let mut endpoint =
+            Channel::from_shared(self.url.clone()).map_err(|e| Error::GrpcError(e.to_string()))?;
+        if self.tls_enabled {
+            endpoint = endpoint
+                .tls_config(self.tls_config().await?)
+                .map_err(|e| Error::GrpcError(e.to_string()))?;
+        }
 
-    while let Some(update) = stream
-        .message()
-        .await
-        .map_err(|e| crate::errors::Error::GrpcError(e.to_string()))?
-    {
-        // tracing::info!("Received price update: {:?}", update);
-
-        // TODO: save the price update to redis (maybe utilize redis pub/sub here?) or database
-        state
-            .redis_pool
-            .get()
-            .await
-            .map_err(|e| crate::errors::Error::RedisError(e.to_string()))?
-            .set::<_, _, ()>(&update.ticker, update.price)
+        endpoint
+            .connect()
             .await
-            .map_err(|e| crate::errors::Error::RedisError(e.to_string()))?;
-
-        // // publish to a redis channel for subscribers
-        // let _: () = state
-        //     .redis_pool
-        //     .get()
-        //     .await
-        //     .map_err(|e| crate::errors::Error::RedisError(e.to_string()))?
-        //     .publish(format!("price_update:{}", update.ticker), format!("{}:{}", update.ticker, update.price))
-        //     .await
-        //     .map_err(|e| crate::errors::Error::RedisError(e.to_string()))?;
+            .map_err(|e| Error::GrpcError(e.to_string()))
     }
+}
+
+async fn read_pem(path: &str) -> Result<Vec<u8>> {
+    tokio::fs::read(path)
+        .await
+        .map_err(|e| Error::GrpcError(format!("Failed to read {}: {}", path, e)))
+}
+
+impl PriceProvider for GrpcPriceProvider {
+    fn name(&self) -> &'static str {
+        "grpc"
+    }
+
+    fn subscribe(&self, tickers: Vec<String>) -> BoxFuture<'_, Result<PriceStream>> {
+        Box::pin(async move {
+            let channel = self.connect().await?;
+            let authorization: Option<MetadataValue<Ascii>> = self
+                .auth_token
+                .as_ref()
+                .map(|token| format!("Bearer {}", token).parse())
+                .transpose()
+                .map_err(|_| Error::GrpcError("Invalid GRPC_AUTH_TOKEN".into()))?;
+
+            // "ALL" keeps feeds that do not know the ticker list streaming everything
+            let request = tonic::Request::new(PriceRequest {
+                ticker: "ALL".into(),
+                tickers,
+            });
 
-    Ok(())
+            // Lets the feed's logs be matched with ours
+            let request_id = crate::request_id::current_or_new();
+            let request_id_value: MetadataValue<Ascii> = request_id
+                .parse()
+                .map_err(|_| Error::GrpcError("Invalid request ID".into()))?;
+            tracing::info!(
+                "Subscribing to the gRPC price feed as request {}",
+                request_id
+            );
+
+            // Every call carries the bearer token when the feed requires one
+            let mut client = PriceFeedClient::with_interceptor(
+                channel,
+                move |mut request: tonic::Request<()>| {
+                    if let Some(authorization) = &authorization {
+                        request
+                            .metadata_mut()
+                            .insert("authorization", authorization.clone());
+                    }
+                    request
+                        .metadata_mut()
+                        .insert("x-request-id", request_id_value.clone());
+                    Ok(request)
+                },
+            );
+            let stream = client
+                .stream_prices(request)
+                .await
+                .map_err(|e| Error::GrpcError(e.to_string()))?
+                .into_inner()
+                .map_ok(|update| PriceTick {
+                    ticker: update.ticker,
+                    price: update.price,
+                })
+                .map_err(|e| Error::GrpcError(e.to_string()))
+                .boxed();
+            Ok(stream)
+        })
+    }
 }
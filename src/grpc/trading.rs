@@ -0,0 +1,340 @@
+//! # gRPC Trading API
+//!
+//! Serves the `Trading` service of `proto/trading.proto` on
+//! `GRPC_LISTEN_ADDR`, so bots can trade over gRPC instead of REST. Orders go
+//! through the same execution as `POST /transactions/buy` and `/sell` and
+//! count against the user's `trades` rate limit.
+//!
+//! Calls authenticate with `authorization: Bearer <access token>` metadata,
+//! like the REST API, or with `x-api-key: <API key>` holding one of the user's
+//! [`api_key`]s, which unlike access tokens do not expire.
+//!
+//! Orders are market orders filled when placed, so there is never an open one
+//! to cancel: `CancelOrder` answers `FAILED_PRECONDITION` for the user's
+//! orders and `NOT_FOUND` for others. `StreamOrderEvents` streams the fills
+//! pushed to the user's WebSocket connections as account events, so fills the
+//! user turned off in their notification settings are not streamed either.
+//!
+//! Errors carry the gRPC code matching the status the REST API would answer
+//! with. The listener terminates TLS with the certificate of the HTTP
+//! listeners when `TLS_CERT_PATH` is set.
+
+use std::{net::SocketAddr, pin::Pin};
+
+use axum::http::StatusCode;
+use bigdecimal::BigDecimal;
+use futures_util::{Stream, stream};
+use tonic::{
+    Code, Request, Response, Status,
+    transport::{Identity, Server, ServerTlsConfig},
+};
+use uuid::Uuid;
+
+use crate::{
+    AppState, Error,
+    auth::{api_key, portfolio, revocation},
+    models::transaction::Transaction,
+    rate_limit,
+    services::{self, trading},
+    ws::{
+        messages::{AccountEvent, ServerMessage},
+        outbox::Outbox,
+    },
+};
+use proto::{
+    CancelOrderRequest, GetPortfolioRequest, Order, OrderEvent, PlaceOrderRequest, Portfolio,
+    Position, Side, StreamOrderEventsRequest,
+    trading_server::{Trading, TradingServer},
+};
+
+pub mod proto {
+    tonic::include_proto!("trading");
+}
+
+/// Longest accepted ticker, as on the REST API
+const MAX_TICKER_LEN: usize = 10;
+/// Largest accepted order, as on the REST API
+const MAX_ORDER_QUANTITY: i32 = 10_000;
+
+/// Serve the trading API on `addr` until the server fails
+pub async fn serve(state: AppState, addr: SocketAddr) -> anyhow::Result<()> {
+    let mut server = Server::builder();
+    match (&state.config.tls_cert_path, &state.config.tls_key_path) {
+        (Some(cert), Some(key)) => {
+            let identity = Identity::from_pem(
+                tokio::fs::read(cert)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", cert, e))?,
+                tokio::fs::read(key)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", key, e))?,
+            );
+            server = server.tls_config(ServerTlsConfig::new().identity(identity))?;
+            tracing::info!("Serving grpc on https://{}", addr);
+        }
+        _ => tracing::info!("Serving grpc on http://{}", addr),
+    }
+
+    server
+        .add_service(TradingServer::new(TradingService { state }))
+        .serve(addr)
+        .await?;
+
+    Ok(())
+}
+
+struct TradingService {
+    state: AppState,
+}
+
+impl TradingService {
+    /// The user a call acts for, from its API key or bearer token
+    async fn authenticate<T>(&self, request: &Request<T>) -> Result<i32, Status> {
+        let metadata = request.metadata();
+        if let Some(key) = metadata.get(api_key::API_KEY_HEADER) {
+            let key = key
+                .to_str()
+                .map_err(|_| Status::unauthenticated("Invalid API key"))?;
+            return api_key::authenticate(&self.state, key)
+                .await
+                .map_err(status);
+        }
+
+        let token = metadata
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| {
+                Status::unauthenticated("Missing authorization or x-api-key metadata")
+            })?;
+        let claims = self
+            .state
+            .auth
+            .decode_token(token)
+            .map_err(|_| Status::unauthenticated("Invalid or expired token"))?;
+
+        // An unreachable denylist must not lock every user out
+        match revocation::is_revoked(&self.state, &claims).await {
+            Ok(true) => return Err(Status::unauthenticated("Token has been revoked")),
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Failed to check token revocation: {}", e),
+        }
+
+        Ok(claims.user_id)
+    }
+}
+
+#[tonic::async_trait]
+impl Trading for TradingService {
+    async fn place_order(
+        &self,
+        request: Request<PlaceOrderRequest>,
+    ) -> Result<Response<Order>, Status> {
+        let user_id = self.authenticate(&request).await?;
+        let request = request.into_inner();
+        if request.ticker.is_empty() || request.ticker.len() > MAX_TICKER_LEN {
+            return Err(Status::invalid_argument(format!(
+                "ticker must have 1 to {} characters",
+                MAX_TICKER_LEN
+            )));
+        }
+        if !(1..=MAX_ORDER_QUANTITY).contains(&request.quantity) {
+            return Err(Status::invalid_argument(format!(
+                "quantity must be between 1 and {}",
+                MAX_ORDER_QUANTITY
+            )));
+        }
+
+        let portfolio = portfolio::select(&self.state, user_id, request.portfolio_id)
+            .await
+            .map_err(status)?;
+        rate_limit::count_trade(&self.state, user_id)
+            .await
+            .map_err(status)?;
+
+        let order = match request.side() {
+            Side::Buy => {
+                let transaction =
+                    trading::buy(&self.state, &portfolio, &request.ticker, request.quantity)
+                        .await
+                        .map_err(status)?;
+                order(transaction, None)
+            }
+            Side::Sell => {
+                let (transaction, realized_gain) =
+                    trading::sell(&self.state, &portfolio, &request.ticker, request.quantity)
+                        .await
+                        .map_err(status)?;
+                order(transaction, Some(realized_gain))
+            }
+            Side::Unspecified => return Err(Status::invalid_argument("side must be set")),
+        };
+
+        Ok(Response::new(order))
+    }
+
+    async fn cancel_order(
+        &self,
+        request: Request<CancelOrderRequest>,
+    ) -> Result<Response<Order>, Status> {
+        let user_id = self.authenticate(&request).await?;
+        let order_id = request.into_inner().order_id;
+        let not_found = || Status::not_found(format!("No order {}", order_id));
+        let public_id = Uuid::parse_str(&order_id).map_err(|_| not_found())?;
+
+        self.state
+            .repos
+            .transactions
+            .get_transaction_by_public_id(public_id)
+            .await
+            .map_err(status)?
+            .filter(|transaction| transaction.user_id == user_id)
+            .ok_or_else(not_found)?;
+
+        Err(Status::failed_precondition(format!(
+            "Order {} was filled when it was placed",
+            order_id
+        )))
+    }
+
+    async fn get_portfolio(
+        &self,
+        request: Request<GetPortfolioRequest>,
+    ) -> Result<Response<Portfolio>, Status> {
+        let user_id = self.authenticate(&request).await?;
+        let portfolio = portfolio::select(&self.state, user_id, request.into_inner().portfolio_id)
+            .await
+            .map_err(status)?;
+        let valuation = services::portfolio::value_portfolio(&self.state, &portfolio)
+            .await
+            .map_err(status)?;
+
+        Ok(Response::new(Portfolio {
+            id: portfolio.id,
+            name: portfolio.name,
+            cash: valuation.cash.to_string(),
+            market_value: valuation.market_value.to_string(),
+            unrealized_pnl: valuation.unrealized_pnl.to_string(),
+            equity: valuation.equity.to_string(),
+            positions: valuation
+                .positions
+                .into_iter()
+                .map(|position| Position {
+                    ticker: position.ticker,
+                    quantity: position.quantity,
+                    average_price: position.average_price.to_string(),
+                    current_price: position.current_price.map(|price| price.to_string()),
+                    market_value: position.market_value.to_string(),
+                    unrealized_pnl: position.unrealized_pnl.to_string(),
+                    created_at: position.created_at.timestamp_millis(),
+                    updated_at: position.updated_at.timestamp_millis(),
+                })
+                .collect(),
+        }))
+    }
+
+    type StreamOrderEventsStream = Pin<Box<dyn Stream<Item = Result<OrderEvent, Status>> + Send>>;
+
+    async fn stream_order_events(
+        &self,
+        request: Request<StreamOrderEventsRequest>,
+    ) -> Result<Response<Self::StreamOrderEventsStream>, Status> {
+        let user_id = self.authenticate(&request).await?;
+
+        let outbox = Outbox::default();
+        let id = self.state.user_sockets.register(user_id, outbox.clone());
+        let registration = Registration {
+            state: self.state.clone(),
+            user_id,
+            id,
+            outbox,
+        };
+
+        let events = stream::unfold(registration, |registration| async move {
+            loop {
+                let message = registration.outbox.pop().await?;
+                if let ServerMessage::Event {
+                    event:
+                        AccountEvent::OrderFilled {
+                            portfolio_id,
+                            transaction_id,
+                            ticker,
+                            side,
+                            quantity,
+                            price,
+                        },
+                    ts,
+                } = message
+                {
+                    let event = OrderEvent {
+                        portfolio_id,
+                        order_id: transaction_id.to_string(),
+                        ticker,
+                        side: side_of(&side).into(),
+                        quantity,
+                        price: price.to_string(),
+                        timestamp: ts.timestamp_millis(),
+                    };
+                    return Some((Ok(event), registration));
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(events)))
+    }
+}
+
+/// A stream's place among the user's sockets, given up when the client goes away
+struct Registration {
+    state: AppState,
+    user_id: i32,
+    id: u64,
+    outbox: Outbox,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.state.user_sockets.unregister(self.user_id, self.id);
+        self.outbox.close();
+    }
+}
+
+fn order(transaction: Transaction, realized_gain: Option<BigDecimal>) -> Order {
+    Order {
+        id: transaction.public_id.to_string(),
+        side: side_of(&transaction.transaction_type).into(),
+        ticker: transaction.ticker,
+        quantity: transaction.quantity,
+        price: transaction.price.to_string(),
+        fee: transaction.fee.to_string(),
+        realized_gain: realized_gain.map(|gain| gain.to_string()),
+        created_at: transaction.created_at.and_utc().timestamp_millis(),
+        updated_at: transaction.updated_at.and_utc().timestamp_millis(),
+    }
+}
+
+fn side_of(transaction_type: &str) -> Side {
+    match transaction_type {
+        "buy" => Side::Buy,
+        "sell" => Side::Sell,
+        _ => Side::Unspecified,
+    }
+}
+
+/// gRPC status of an API error, with the code matching its REST status
+fn status(error: Error) -> Status {
+    let (status, message) = error.status_and_message();
+    let code = match status {
+        StatusCode::BAD_REQUEST | StatusCode::PAYLOAD_TOO_LARGE => Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::PermissionDenied,
+        StatusCode::NOT_FOUND => Code::NotFound,
+        StatusCode::CONFLICT => Code::FailedPrecondition,
+        StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+        StatusCode::NOT_IMPLEMENTED => Code::Unimplemented,
+        StatusCode::BAD_GATEWAY => Code::Unavailable,
+        _ => Code::Internal,
+    };
+
+    Status::new(code, message)
+}
@@ -0,0 +1,15 @@
+//! # Stock Exchange Simulator Core
+//!
+//! Library surface of the simulator for client authors. The trading service
+//! itself is the `stock-exchange-sim-core` binary; this crate exposes the API
+//! schema and, with the `client` feature enabled, a typed Rust client for the
+//! REST endpoints and the WebSocket protocol. The `loadgen` feature adds the
+//! load generator behind the binary's `loadgen` subcommand.
+
+pub mod schema;
+
+#[cfg(feature = "client")]
+pub mod client;
+
+#[cfg(feature = "loadgen")]
+pub mod loadgen;
@@ -0,0 +1,77 @@
+//! # Listeners
+//!
+//! The API is served on `SERVER_HOST:SERVER_PORT`. Setting
+//! `ADMIN_LISTEN_ADDR` moves the admin API to a listener of its own, e.g. on
+//! an internal interface the load balancer does not reach, and the API
+//! listener stops serving `/admin`. Both listeners answer `/health`.
+//!
+//! With `TLS_CERT_PATH` and `TLS_KEY_PATH` set, every listener terminates TLS
+//! with rustls and serves HTTPS only; otherwise they serve plain HTTP, as
+//! behind a terminating proxy.
+
+use std::net::SocketAddr;
+
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use futures_util::future::try_join_all;
+
+use crate::config::Config;
+
+/// A router and the address it is served on
+pub struct Listener {
+    /// Name in the logs, like `api` or `admin`
+    pub name: &'static str,
+    pub addr: SocketAddr,
+    pub app: Router,
+}
+
+/// The TLS settings of the listeners, when `TLS_CERT_PATH` is set
+pub async fn tls_config(config: &Config) -> anyhow::Result<Option<RustlsConfig>> {
+    let (Some(cert), Some(key)) = (&config.tls_cert_path, &config.tls_key_path) else {
+        return Ok(None);
+    };
+
+    // ring is the only provider built in, but it must be installed before use
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let tls = RustlsConfig::from_pem_file(cert, key)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to load TLS certificate {}: {}", cert, e))?;
+
+    Ok(Some(tls))
+}
+
+/// Serve every listener until one of them fails
+pub async fn serve(listeners: Vec<Listener>, tls: Option<RustlsConfig>) -> anyhow::Result<()> {
+    let servers = listeners.into_iter().map(|listener| {
+        let tls = tls.clone();
+        async move {
+            let app = listener
+                .app
+                .into_make_service_with_connect_info::<SocketAddr>();
+            match tls {
+                Some(tls) => {
+                    tracing::info!("Serving {} on https://{}", listener.name, listener.addr);
+                    axum_server::bind_rustls(listener.addr, tls)
+                        .serve(app)
+                        .await
+                }
+                None => {
+                    tracing::info!("Serving {} on http://{}", listener.name, listener.addr);
+                    axum_server::bind(listener.addr).serve(app).await
+                }
+            }
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "{} listener on {} failed: {}",
+                    listener.name,
+                    listener.addr,
+                    e
+                )
+            })
+        }
+    });
+
+    try_join_all(servers).await?;
+
+    Ok(())
+}
@@ -0,0 +1,316 @@
+//! # Load Generator
+//!
+//! Simulates concurrent users against a running instance and reports latency
+//! percentiles per operation, for comparing throughput before and after
+//! changes to the pub/sub and trading paths. Run it through the binary:
+//!
+//! ```text
+//! cargo run --features loadgen -- loadgen --target http://127.0.0.1:3000 \
+//!     --users 50 --duration 60 --mix quote=60,trade=30,ws=10 --tickers AAPL,MSFT
+//! ```
+//!
+//! Every simulated user registers a fresh account, deposits cash and then
+//! performs randomly chosen operations until the duration elapses:
+//!
+//! - `quote`: fetches the quote of a random ticker (`GET /market/quote/{ticker}`)
+//! - `trade`: buys one share of a random ticker, or sells one it holds
+//! - `ws`: opens a WebSocket, subscribes to a ticker and waits for the first
+//!   price update; the latency covers the handshake and the first update
+//!
+//! The tickers must be listed in the instrument catalog and have prices in the
+//! target instance.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use futures_util::{SinkExt, StreamExt};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest, http::HeaderValue};
+
+use crate::client::{
+    Client,
+    ws::{ClientMessage, ServerMessage},
+};
+
+/// How long a WebSocket operation waits for its first price update
+const WS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Command line arguments of the `loadgen` subcommand
+#[derive(Debug, Clone, Parser)]
+#[command(
+    name = "loadgen",
+    about = "Generate load against a running simulator instance"
+)]
+pub struct Args {
+    /// Base URL of the instance under test
+    #[arg(long, default_value = "http://127.0.0.1:3000")]
+    pub target: String,
+    /// Number of concurrent simulated users
+    #[arg(long, default_value_t = 10)]
+    pub users: usize,
+    /// Test duration in seconds
+    #[arg(long, default_value_t = 30)]
+    pub duration: u64,
+    /// Relative weights of the operations, e.g. `quote=60,trade=30,ws=10`
+    #[arg(long, default_value = "quote=60,trade=30,ws=10", value_parser = parse_mix)]
+    pub mix: Mix,
+    /// Tickers to trade and subscribe to
+    #[arg(long, value_delimiter = ',', default_value = "AAPL")]
+    pub tickers: Vec<String>,
+    /// Pause between two operations of the same user, in milliseconds
+    #[arg(long, default_value_t = 0)]
+    pub think_time_ms: u64,
+    /// Cash deposited into every simulated account before the run
+    #[arg(long, default_value_t = 100_000.0)]
+    pub deposit: f64,
+}
+
+/// Operation performed by a simulated user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Operation {
+    Quote,
+    Trade,
+    Ws,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operation::Quote => "quote",
+            Operation::Trade => "trade",
+            Operation::Ws => "ws",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Weighted operation mix
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mix(Vec<(Operation, u32)>);
+
+impl Mix {
+    fn pick(&self, rng: &mut impl Rng) -> Operation {
+        let total: u32 = self.0.iter().map(|(_, weight)| weight).sum();
+        let mut roll = rng.random_range(0..total);
+        for (operation, weight) in &self.0 {
+            if roll < *weight {
+                return *operation;
+            }
+            roll -= weight;
+        }
+        self.0[0].0
+    }
+}
+
+/// Parse a mix such as `quote=60,trade=30,ws=10`
+pub fn parse_mix(s: &str) -> Result<Mix, String> {
+    let mut weights = Vec::new();
+    for part in s.split(',').filter(|part| !part.trim().is_empty()) {
+        let (name, weight) = part
+            .split_once('=')
+            .ok_or_else(|| format!("expected name=weight, got `{}`", part))?;
+        let operation = match name.trim() {
+            "quote" => Operation::Quote,
+            "trade" => Operation::Trade,
+            "ws" => Operation::Ws,
+            other => return Err(format!("unknown operation `{}`", other)),
+        };
+        let weight: u32 = weight
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid weight `{}`", weight))?;
+        if weight > 0 {
+            weights.push((operation, weight));
+        }
+    }
+
+    if weights.is_empty() {
+        return Err("the mix needs at least one operation with a positive weight".into());
+    }
+    Ok(Mix(weights))
+}
+
+/// Latencies and failures recorded for one operation
+#[derive(Debug, Default)]
+struct OperationStats {
+    latencies: Vec<Duration>,
+    errors: u64,
+}
+
+type Stats = BTreeMap<Operation, OperationStats>;
+
+/// Run the load test and print the report to stdout
+pub async fn run(args: Args) -> anyhow::Result<()> {
+    if args.users == 0 || args.tickers.is_empty() {
+        anyhow::bail!("at least one user and one ticker are required");
+    }
+
+    println!(
+        "Running {} users against {} for {}s (mix: {})",
+        args.users,
+        args.target,
+        args.duration,
+        args.mix
+            .0
+            .iter()
+            .map(|(operation, weight)| format!("{}={}", operation, weight))
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(args.duration);
+    let users: Vec<_> = (0..args.users)
+        .map(|_| tokio::spawn(simulate_user(args.clone(), deadline)))
+        .collect();
+
+    let mut stats = Stats::new();
+    let mut failed_users = 0;
+    for user in users {
+        match user.await? {
+            Ok(user_stats) => {
+                for (operation, s) in user_stats {
+                    let total = stats.entry(operation).or_default();
+                    total.latencies.extend(s.latencies);
+                    total.errors += s.errors;
+                }
+            }
+            Err(e) => {
+                failed_users += 1;
+                eprintln!("Simulated user failed to start: {}", e);
+            }
+        }
+    }
+
+    if failed_users == args.users {
+        anyhow::bail!("no simulated user could be set up");
+    }
+
+    report(&mut stats, started.elapsed());
+    Ok(())
+}
+
+async fn simulate_user(args: Args, deadline: Instant) -> anyhow::Result<Stats> {
+    let mut rng = StdRng::from_os_rng();
+    let email = format!("loadgen-{}@example.com", uuid::Uuid::new_v4());
+    let password = uuid::Uuid::new_v4().to_string();
+
+    let mut client = Client::new(&args.target);
+    client.register(&email, &password).await?;
+    client.login(&email, &password).await?;
+    client.deposit(&args.deposit.to_string()).await?;
+
+    let mut stats = Stats::new();
+    let mut held: BTreeMap<String, i32> = BTreeMap::new();
+
+    while Instant::now() < deadline {
+        let operation = args.mix.pick(&mut rng);
+        let ticker = args.tickers.choose(&mut rng).cloned().unwrap_or_default();
+
+        let started = Instant::now();
+        let ok = match operation {
+            Operation::Quote => client.quote(&ticker).await.is_ok(),
+            Operation::Trade => {
+                let shares = held.entry(ticker.clone()).or_default();
+                if *shares > 0 && rng.random_bool(0.5) {
+                    let ok = client.sell(&ticker, 1).await.is_ok();
+                    *shares -= i32::from(ok);
+                    ok
+                } else {
+                    let ok = client.buy(&ticker, 1).await.is_ok();
+                    *shares += i32::from(ok);
+                    ok
+                }
+            }
+            Operation::Ws => first_price_update(&client, &ticker).await.is_ok(),
+        };
+        let elapsed = started.elapsed();
+
+        let entry = stats.entry(operation).or_default();
+        if ok {
+            entry.latencies.push(elapsed);
+        } else {
+            entry.errors += 1;
+        }
+
+        if args.think_time_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(args.think_time_ms)).await;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Connect to the WebSocket, subscribe to `ticker` and wait for its first update
+async fn first_price_update(client: &Client, ticker: &str) -> anyhow::Result<()> {
+    let mut request = client.ws_url().into_client_request()?;
+    let token = client.token().unwrap_or_default();
+    request.headers_mut().insert(
+        "Authorization",
+        HeaderValue::from_str(&format!("Bearer {}", token))?,
+    );
+
+    let wait = async {
+        let (mut socket, _) = tokio_tungstenite::connect_async(request).await?;
+        socket
+            .send(tungstenite::Message::text(
+                ClientMessage::subscribe(&[ticker]).to_text(),
+            ))
+            .await?;
+
+        while let Some(message) = socket.next().await {
+            if let tungstenite::Message::Text(text) = message? {
+                match ServerMessage::parse(&text)? {
+                    ServerMessage::Price { .. } => {
+                        let _ = socket.close(None).await;
+                        return Ok(());
+                    }
+                    ServerMessage::Error { message, .. } => {
+                        anyhow::bail!("subscription rejected: {}", message)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        anyhow::bail!("connection closed before the first update")
+    };
+
+    tokio::time::timeout(WS_TIMEOUT, wait).await?
+}
+
+fn report(stats: &mut Stats, elapsed: Duration) {
+    let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+
+    println!();
+    println!(
+        "{:<8} {:>8} {:>8} {:>9} {:>9} {:>9} {:>9} {:>9}",
+        "op", "ok", "errors", "p50 ms", "p90 ms", "p99 ms", "max ms", "ops/s"
+    );
+    for (operation, s) in stats.iter_mut() {
+        s.latencies.sort();
+        println!(
+            "{:<8} {:>8} {:>8} {:>9.2} {:>9.2} {:>9.2} {:>9.2} {:>9.1}",
+            operation.to_string(),
+            s.latencies.len(),
+            s.errors,
+            percentile_ms(&s.latencies, 50.0),
+            percentile_ms(&s.latencies, 90.0),
+            percentile_ms(&s.latencies, 99.0),
+            percentile_ms(&s.latencies, 100.0),
+            s.latencies.len() as f64 / seconds,
+        );
+    }
+}
+
+/// Nearest-rank percentile of sorted latencies, in milliseconds
+fn percentile_ms(sorted: &[Duration], percentile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1].as_secs_f64() * 1000.0
+}
@@ -1,19 +1,37 @@
-use crate::{errors::not_found_handler, ws::handler::ws_handler};
+use crate::{
+    errors::not_found_handler, models::matching_config::MatchingConfig,
+    repository::matching_config_repository::MatchingConfigRepository, ws::handler::ws_handler,
+};
 
-pub use self::errors::{Error, Result};
-use axum::{Extension, Router, routing::get};
+pub use self::errors::{Error, ErrorResponse, Result};
+use axum::{Extension, Router, extract::DefaultBodyLimit, middleware, routing::get};
+use serde::Serialize;
 use sqlx::{PgPool, postgres::PgPoolOptions};
-use std::{net::SocketAddr, sync::Arc};
-use tracing_subscriber::{EnvFilter, fmt};
+use std::{
+    net::SocketAddr,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tracing_subscriber::{EnvFilter, Layer, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+use utoipa::ToSchema;
 
 mod auth;
 mod config;
+mod error_reporting;
 mod errors;
+mod graphql;
 mod grpc;
+mod listeners;
 mod models;
+mod openapi;
+mod rate_limit;
 mod repository;
+mod request_id;
 mod routes;
+mod security;
+mod seed;
 mod services;
+mod timing;
 mod ws;
 
 use config::Config;
@@ -26,27 +44,62 @@ use config::Config;
 pub struct AppState {
     /// PostgreSQL connection pool
     pub pg_pool: Arc<PgPool>,
+    /// Pool for history reads that may lag writes, the primary's without a replica
+    pub pg_read_pool: Arc<PgPool>,
+    /// Users, holdings and transactions on the primary
+    pub repos: repository::Repositories,
+    /// The same repositories on the read pool, for history reads
+    pub read_repos: repository::Repositories,
     /// Redis connection pool for caching and session management
     pub redis_pool: Arc<bb8::Pool<bb8_redis::RedisConnectionManager>>,
     /// Application configuration
     pub config: Config,
+    /// Access token issuing and validation, keyed by the configured secret
+    pub auth: Arc<auth::jwt::AuthService>,
+    /// Active matching parameters, hot-reloaded when admins change them
+    pub matching_config: Arc<RwLock<MatchingConfig>>,
+    /// Price feed connection state, updated by the price updater
+    pub feed_health: Arc<RwLock<services::price_updater::FeedHealth>>,
+    /// Price updates handed to this instance's WebSocket subscribers
+    pub price_fanout: Arc<ws::fanout::PriceFanout>,
+    /// WebSocket connections of this instance by user, for account events
+    pub user_sockets: Arc<ws::events::UserSockets>,
+    /// Announcements handed to this instance's `system` subscribers
+    pub announcements: Arc<ws::announcements::Announcements>,
+    /// Domain events waiting to be streamed to the message broker
+    pub event_stream: Arc<services::event_stream::EventStream>,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // `stock-exchange-sim-core loadgen ...` benchmarks a running instance instead of serving
+    if std::env::args().nth(1).as_deref() == Some("loadgen") {
+        return run_loadgen().await;
+    }
+    // `stock-exchange-sim-core openapi` prints the API description, e.g. to
+    // refresh `api/openapi.json`
+    if std::env::args().nth(1).as_deref() == Some("openapi") {
+        println!("{}", openapi::spec().to_pretty_json()?);
+        return Ok(());
+    }
+
     // Load configuration
     let config = Config::from_env()?;
 
     // Initialize tracing with proper level filtering
-    fmt()
-        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-            format!(
-                "stock_exchange_sim_core={},tower_http=debug",
-                config.log_level
-            )
-            .into()
-        }))
+    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        format!(
+            "stock_exchange_sim_core={},tower_http=debug",
+            config.log_level
+        )
+        .into()
+    });
+    tracing_subscriber::registry()
+        .with(fmt::layer().with_filter(env_filter))
+        .with(config.server_timing_enabled.then(timing::layer))
+        .with(error_reporting::layer(&config))
         .init();
+    let _sentry = error_reporting::init(&config);
 
     tracing::info!("Starting Stock Exchange Simulator API");
     tracing::info!("Log level: {}", config.log_level);
@@ -74,6 +127,22 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("Database migrations completed");
 
+    let read_pool = match &config.database_read_url {
+        Some(url) => {
+            let read_pool = PgPoolOptions::new()
+                .max_connections(config.max_db_connections)
+                .connect(url)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to create read replica pool: {}", e);
+                    e
+                })?;
+            tracing::info!("Read replica connected successfully");
+            read_pool
+        }
+        None => pool.clone(),
+    };
+
     // Create Redis pool
     let manager = bb8_redis::RedisConnectionManager::new(config.redis_url.clone())?;
     let redis_pool = bb8::Pool::builder().build(manager).await.map_err(|e| {
@@ -83,40 +152,276 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("Redis connected successfully");
 
+    let matching_config = MatchingConfigRepository::new(&pool).get_config().await?;
+
     let state = AppState {
+        repos: repository::Repositories::postgres(&pool),
+        read_repos: repository::Repositories::postgres(&read_pool),
         pg_pool: Arc::new(pool),
+        pg_read_pool: Arc::new(read_pool),
         redis_pool: Arc::new(redis_pool),
         config: config.clone(),
+        auth: Arc::new(auth::jwt::AuthService::from_config(&config)),
+        matching_config: Arc::new(RwLock::new(matching_config)),
+        feed_health: Arc::new(RwLock::new(services::price_updater::FeedHealth::default())),
+        price_fanout: Arc::new(ws::fanout::PriceFanout::default()),
+        user_sockets: Arc::new(ws::events::UserSockets::default()),
+        announcements: Arc::new(ws::announcements::Announcements::default()),
+        event_stream: Arc::new(services::event_stream::EventStream::from_config(&config)),
     };
 
-    let grpc_state = state.clone();
+    // `stock-exchange-sim-core seed` fills an empty database with demo data instead of serving
+    if std::env::args().nth(1).as_deref() == Some("seed") {
+        seed::run(&state).await?;
+        tracing::info!("Demo data seeded");
+        return Ok(());
+    }
+
+    let updater_state = state.clone();
+    tokio::spawn(services::price_updater::supervise(Arc::new(updater_state)));
+
+    let fanout_state = state.clone();
+    tokio::spawn(ws::fanout::price_dispatcher(Arc::new(fanout_state)));
+
+    let events_state = state.clone();
+    tokio::spawn(ws::events::account_dispatcher(Arc::new(events_state)));
+
+    let announcement_state = state.clone();
+    tokio::spawn(ws::announcements::announcement_dispatcher(Arc::new(
+        announcement_state,
+    )));
+
+    let reloader_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::matching::config_reloader(Arc::new(reloader_state)).await {
+            tracing::error!("Matching config reloader failed: {}", e);
+        }
+    });
+
+    let snapshot_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::snapshots::snapshot_worker(Arc::new(snapshot_state)).await {
+            tracing::error!("Portfolio snapshot worker failed: {}", e);
+        }
+    });
+
+    let sweep_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::sweep::sweep_worker(Arc::new(sweep_state)).await {
+            tracing::error!("Cash sweep worker failed: {}", e);
+        }
+    });
+
+    let dividend_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::dividends::dividend_worker(Arc::new(dividend_state)).await {
+            tracing::error!("Dividend worker failed: {}", e);
+        }
+    });
+
+    let basket_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::baskets::basket_worker(Arc::new(basket_state)).await {
+            tracing::error!("Basket worker failed: {}", e);
+        }
+    });
+
+    let bond_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::bonds::bond_worker(Arc::new(bond_state)).await {
+            tracing::error!("Bond worker failed: {}", e);
+        }
+    });
+
+    let corporate_action_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) =
+            services::corporate_actions::corporate_action_worker(Arc::new(corporate_action_state))
+                .await
+        {
+            tracing::error!("Corporate action worker failed: {}", e);
+        }
+    });
+
+    let allowance_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::allowance::allowance_worker(Arc::new(allowance_state)).await {
+            tracing::error!("Allowance worker failed: {}", e);
+        }
+    });
+
+    let archive_state = state.clone();
     tokio::spawn(async move {
-        if let Err(e) = grpc::price_updater(Arc::new(grpc_state)).await {
-            tracing::error!("gRPC price updater failed: {}", e);
+        if let Err(e) = services::archive::archive_worker(Arc::new(archive_state)).await {
+            tracing::error!("Transaction archive worker failed: {}", e);
         }
     });
 
-    let app = Router::new()
+    let competition_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) =
+            services::competitions::competition_worker(Arc::new(competition_state)).await
+        {
+            tracing::error!("Competition worker failed: {}", e);
+        }
+    });
+
+    let replay_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::replay::replay_worker(Arc::new(replay_state)).await {
+            tracing::error!("Replay worker failed: {}", e);
+        }
+    });
+
+    let bot_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::bots::bot_worker(Arc::new(bot_state)).await {
+            tracing::error!("Bot worker failed: {}", e);
+        }
+    });
+
+    let option_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::options::expiry_worker(Arc::new(option_state)).await {
+            tracing::error!("Option expiry worker failed: {}", e);
+        }
+    });
+
+    let margin_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::loans::margin_worker(Arc::new(margin_state)).await {
+            tracing::error!("Loan margin worker failed: {}", e);
+        }
+    });
+
+    let event_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::event_stream::publisher(Arc::new(event_state)).await {
+            tracing::error!("Event stream publisher failed: {}", e);
+        }
+    });
+
+    let webhook_state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = services::webhooks::delivery_worker(Arc::new(webhook_state)).await {
+            tracing::error!("Webhook delivery worker failed: {}", e);
+        }
+    });
+
+    let api = Router::new()
         .route("/", get(|| async { "Hello, stock-sim!" }))
         .route("/health", get(health_check))
         .route("/ws", get(ws_handler))
         .merge(routes::routes())
-        .layer(Extension(state))
+        .merge(graphql::routes(&state))
+        .merge(openapi::routes());
+    if config.server_timing_enabled {
+        tracing::info!("Server-Timing headers enabled");
+    }
+
+    let api_addr = SocketAddr::new(config.server_host, config.server_port);
+    let listeners = match config.admin_listen_addr {
+        Some(admin_addr) => {
+            let admin = routes::admin_routes().route("/health", get(health_check));
+            vec![
+                listeners::Listener {
+                    name: "api",
+                    addr: api_addr,
+                    app: with_middleware(api, &state)?,
+                },
+                listeners::Listener {
+                    name: "admin",
+                    addr: admin_addr,
+                    app: with_middleware(admin, &state)?,
+                },
+            ]
+        }
+        None => vec![listeners::Listener {
+            name: "api",
+            addr: api_addr,
+            app: with_middleware(api.merge(routes::admin_routes()), &state)?,
+        }],
+    };
+    let tls = listeners::tls_config(&config).await?;
+
+    match config.grpc_listen_addr {
+        Some(grpc_addr) => {
+            tokio::try_join!(
+                listeners::serve(listeners, tls),
+                grpc::trading::serve(state.clone(), grpc_addr)
+            )?;
+            Ok(())
+        }
+        None => listeners::serve(listeners, tls).await,
+    }
+}
+
+/// Wrap a router in the middleware every listener shares
+fn with_middleware(mut app: Router, state: &AppState) -> anyhow::Result<Router> {
+    let config = &state.config;
+    if config.server_timing_enabled {
+        app = app.layer(middleware::from_fn(timing::server_timing));
+    }
+    let security_headers = Arc::new(security::SecurityHeaders::from_config(config)?);
+    let app = app
         .fallback(not_found_handler)
-        .into_make_service();
+        .layer(DefaultBodyLimit::max(config.max_request_size))
+        .layer(middleware::from_fn(security::body_limit))
+        .layer(middleware::from_fn(rate_limit::rate_limit))
+        .layer(middleware::from_fn_with_state(
+            Duration::from_secs(config.request_timeout_secs),
+            security::request_timeout,
+        ))
+        .layer(middleware::from_fn_with_state(
+            security_headers,
+            security::security_headers,
+        ))
+        .layer(middleware::from_fn(error_reporting::error_context))
+        .layer(middleware::from_fn(request_id::request_id))
+        .layer(Extension(state.clone()));
+
+    Ok(app)
+}
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], config.server_port));
-    tracing::info!("Server listening on http://{}", addr);
+#[cfg(feature = "loadgen")]
+async fn run_loadgen() -> anyhow::Result<()> {
+    use clap::Parser;
 
-    axum::serve(tokio::net::TcpListener::bind(addr).await?, app).await?;
+    let args = stock_exchange_sim_core::loadgen::Args::parse_from(std::env::args().skip(1));
+    stock_exchange_sim_core::loadgen::run(args).await
+}
 
-    Ok(())
+#[cfg(not(feature = "loadgen"))]
+async fn run_loadgen() -> anyhow::Result<()> {
+    Err(anyhow::anyhow!(
+        "the loadgen subcommand requires building with `--features loadgen`"
+    ))
 }
 
 /// Health check endpoint
 ///
-/// Returns "OK" if the service is running properly.
-/// This endpoint is useful for load balancers and monitoring systems.
-async fn health_check() -> &'static str {
-    "OK"
+/// Always answers `200` while the service is up so load balancers keep
+/// routing to it; trading continues on cached prices when the price feed is
+/// down. The status is `degraded` until the feed is streaming, with the
+/// feed's state included for operators.
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, description = "Service and price feed state", body = HealthResponse))
+)]
+async fn health_check(state: Extension<AppState>) -> timing::Json<HealthResponse> {
+    let price_feed = services::price_updater::health(&state);
+    let status = match price_feed.status {
+        services::price_updater::FeedStatus::Connected => "ok",
+        _ => "degraded",
+    };
+
+    timing::Json(HealthResponse { status, price_feed })
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct HealthResponse {
+    status: &'static str,
+    price_feed: services::price_updater::FeedHealth,
 }
@@ -0,0 +1,11 @@
+use bigdecimal::BigDecimal;
+
+/// A weekly allowance credited to a user's default portfolio
+#[derive(sqlx::FromRow, Debug)]
+pub struct AllowancePayment {
+    pub user_id: i32,
+    pub portfolio_id: i32,
+    pub amount: BigDecimal,
+    /// Cash in the portfolio after the allowance
+    pub balance: BigDecimal,
+}
@@ -0,0 +1,14 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Announcement to every WebSocket client, also published as is on Redis
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct Announcement {
+    pub id: i32,
+    pub message: String,
+    /// `info`, `warning` or `critical`
+    pub severity: String,
+    /// The announcement is no longer shown after this time
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
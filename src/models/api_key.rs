@@ -0,0 +1,12 @@
+use chrono::{DateTime, Utc};
+
+/// A key a user's programs authenticate with instead of an access token
+///
+/// Only a hash of the key is stored; the key itself is shown once on creation.
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct ApiKey {
+    pub id: i32,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,11 @@
+use bigdecimal::BigDecimal;
+
+/// A ticker held in the basket of an index or ETF
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct BasketConstituent {
+    /// Ticker of the index or ETF
+    pub basket: String,
+    pub ticker: String,
+    /// Units of the constituent in one unit of the basket
+    pub weight: BigDecimal,
+}
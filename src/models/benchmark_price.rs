@@ -0,0 +1,8 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct BenchmarkPrice {
+    pub price_date: NaiveDate,
+    pub price: BigDecimal,
+}
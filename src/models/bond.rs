@@ -0,0 +1,20 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Terms of a bond instrument; one unit of the ticker is one bond
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct Bond {
+    pub ticker: String,
+    /// Amount repaid per bond at maturity
+    pub face_value: BigDecimal,
+    /// Annual coupon in percent of the face value
+    pub coupon_rate_percent: BigDecimal,
+    /// 1, 2, 4 or 12 coupons a year, dated back from the maturity date
+    pub coupons_per_year: i32,
+    pub maturity_date: NaiveDate,
+    /// Next coupon to pay; the last one falls on the maturity date
+    pub next_coupon_date: NaiveDate,
+    pub redeemed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,62 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A server-managed account trading on its own
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct Bot {
+    pub id: i32,
+    pub user_id: i32,
+    pub user_public_id: Uuid,
+    pub display_name: Option<String>,
+    /// `momentum`, `mean_reversion` or `random`
+    pub strategy: String,
+    /// Paused bots keep their account but stop trading
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// How a bot picks its trades
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BotStrategy {
+    /// Buys what has been rising and sells what has been falling
+    Momentum,
+    /// Buys below the recent average price and sells above it
+    MeanReversion,
+    /// Trades at random
+    Random,
+}
+
+impl BotStrategy {
+    /// Every strategy, in the order new bots are assigned them
+    pub const ALL: [BotStrategy; 3] = [
+        BotStrategy::Momentum,
+        BotStrategy::MeanReversion,
+        BotStrategy::Random,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BotStrategy::Momentum => "momentum",
+            BotStrategy::MeanReversion => "mean_reversion",
+            BotStrategy::Random => "random",
+        }
+    }
+}
+
+impl FromStr for BotStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "momentum" => Ok(BotStrategy::Momentum),
+            "mean_reversion" => Ok(BotStrategy::MeanReversion),
+            "random" => Ok(BotStrategy::Random),
+            other => Err(format!("Unknown bot strategy: {}", other)),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+
+/// Net external cash flow of a user on one UTC day
+#[derive(sqlx::FromRow, Debug)]
+pub struct DailyCashFlow {
+    pub flow_date: NaiveDate,
+    pub amount: BigDecimal,
+}
@@ -0,0 +1,16 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+
+/// A deposit into or withdrawal from a portfolio
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct CashMovement {
+    pub id: i32,
+    pub portfolio_id: i32,
+    /// `deposit` or `withdrawal`
+    pub movement_type: String,
+    /// Always positive; the type gives the direction
+    pub amount: BigDecimal,
+    /// Cash of the portfolio once the movement was posted
+    pub balance_after: BigDecimal,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,75 @@
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct Class {
+    pub id: i32,
+    pub name: String,
+    /// Cash every student's class portfolio starts with
+    pub starting_balance: BigDecimal,
+    /// Tickers students may buy, `None` for every ticker
+    pub allowed_tickers: Option<Vec<String>>,
+    /// Lets students join the class
+    pub invite_code: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A user's place in a class
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct ClassMember {
+    pub class_id: i32,
+    /// `instructor` or `student`
+    pub role: String,
+    /// The student's class portfolio, `None` for instructors
+    pub portfolio_id: Option<i32>,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// A student of a class, as instructors see them
+#[derive(sqlx::FromRow, Debug)]
+pub struct ClassStudent {
+    /// Public ID of the student
+    pub user_id: Uuid,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub portfolio_id: i32,
+    /// The class's starting balance when the student joined
+    pub starting_balance: BigDecimal,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// What a member may do in a class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ClassRole {
+    /// Configures the class and sees every student's portfolio
+    Instructor,
+    /// Trades in a class portfolio
+    Student,
+}
+
+impl ClassRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClassRole::Instructor => "instructor",
+            ClassRole::Student => "student",
+        }
+    }
+}
+
+impl FromStr for ClassRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "instructor" => Ok(ClassRole::Instructor),
+            "student" => Ok(ClassRole::Student),
+            other => Err(format!("Unknown class role: {}", other)),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct Competition {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    /// Cash every entrant's competition portfolio starts with
+    pub starting_balance: BigDecimal,
+    /// `beginner` or `realistic`, applied to every entrant's portfolio
+    pub difficulty: String,
+    /// Simulated seconds per real second, 1 for real time
+    pub clock_speed: f64,
+    /// When the final standings were recorded, `None` until then
+    pub finalized_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A user taking part in a competition through a portfolio of its own
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct CompetitionEntry {
+    pub competition_id: i32,
+    pub user_id: i32,
+    pub portfolio_id: i32,
+    /// Equity when the competition was finalized
+    pub final_equity: Option<BigDecimal>,
+    /// 1 for the winner, set when the competition is finalized
+    pub final_rank: Option<i32>,
+}
@@ -0,0 +1,20 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct CorporateAction {
+    pub id: i32,
+    pub ticker: String,
+    /// `split` or `symbol_change`
+    pub action_type: String,
+    /// The action is applied at the start of this day
+    pub effective_date: NaiveDate,
+    /// Set for splits: every `ratio_from` shares become `ratio_to` shares
+    pub ratio_from: Option<i32>,
+    pub ratio_to: Option<i32>,
+    /// Set for symbol changes
+    pub new_ticker: Option<String>,
+    /// `pending` or `applied`
+    pub status: String,
+    pub applied_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
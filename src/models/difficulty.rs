@@ -0,0 +1,36 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Simulation preset deciding which trading costs apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    /// Trades fill at the quoted price without fees, loans are interest-free
+    Beginner,
+    /// Trades pay commission, spread and slippage, loans accrue interest
+    #[default]
+    Realistic,
+}
+
+impl Difficulty {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Difficulty::Beginner => "beginner",
+            Difficulty::Realistic => "realistic",
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "beginner" => Ok(Difficulty::Beginner),
+            "realistic" => Ok(Difficulty::Realistic),
+            other => Err(format!("Unknown difficulty: {}", other)),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDate, Utc};
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct Dividend {
+    pub id: i32,
+    pub ticker: String,
+    /// Holders at the start of this day are entitled to the dividend
+    pub ex_date: NaiveDate,
+    pub pay_date: NaiveDate,
+    pub amount_per_share: BigDecimal,
+    /// `announced`, `recorded` once holders are captured, `paid` once credited
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A dividend payment that has been claimed for crediting
+#[derive(sqlx::FromRow, Debug)]
+pub struct DuePayment {
+    pub id: i32,
+    pub user_id: i32,
+    pub portfolio_id: i32,
+    pub ticker: String,
+    pub quantity: i32,
+    pub amount_per_share: BigDecimal,
+    pub amount: BigDecimal,
+}
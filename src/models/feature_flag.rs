@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Rollout of a feature, also cached as is on Redis
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureFlag {
+    pub name: String,
+    pub description: String,
+    /// Kill switch: the feature is off for everyone while false
+    pub enabled: bool,
+    /// Share of users, 0 to 100, the feature is on for
+    pub rollout_percent: i16,
+    /// Users the feature is on for regardless of the percentage
+    pub user_ids: Vec<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,31 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use uuid::Uuid;
+
+/// A user on either side of a follow
+#[derive(sqlx::FromRow, Debug)]
+pub struct FollowedUser {
+    /// Public ID of the user
+    pub user_id: Uuid,
+    pub display_name: Option<String>,
+    pub followed_at: DateTime<Utc>,
+}
+
+/// A trade in a public portfolio of a followed user
+#[derive(sqlx::FromRow, Debug)]
+pub struct FeedTrade {
+    /// Public IDs of the transaction and its trader
+    pub transaction_id: Uuid,
+    pub user_id: Uuid,
+    pub display_name: Option<String>,
+    pub portfolio_id: i32,
+    pub portfolio_name: String,
+    pub ticker: String,
+    /// `buy` or `sell`
+    pub transaction_type: String,
+    pub quantity: i32,
+    pub price: BigDecimal,
+    /// The trader hides quantities from their followers
+    pub hide_quantity: bool,
+    pub created_at: NaiveDateTime,
+}
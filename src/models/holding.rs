@@ -1,10 +1,33 @@
 use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
 
-#[derive(sqlx::FromRow, Debug)]
+#[derive(sqlx::FromRow, Debug, Clone)]
 pub struct Holding {
     pub id: i32,
+    /// ID of the position in the API; `id` stays internal
+    pub public_id: Uuid,
     pub user_id: i32,
+    pub portfolio_id: i32,
     pub ticker: String,
     pub quantity: i32,
     pub average_price: BigDecimal,
+    /// When the position was first opened
+    pub created_at: DateTime<Utc>,
+    /// Last change of the quantity, cost or ticker
+    pub updated_at: DateTime<Utc>,
+    /// Bumped by every change, guarding updates against concurrent writes
+    pub version: i32,
+}
+
+/// A position whose last share was sold, redeemed or split away
+#[derive(sqlx::FromRow, Debug)]
+pub struct ClosedPosition {
+    pub public_id: Uuid,
+    pub ticker: String,
+    /// When the position was opened
+    pub created_at: DateTime<Utc>,
+    pub closed_at: DateTime<Utc>,
+    /// Gain realized by the sells while the position was open
+    pub realized_gain: BigDecimal,
 }
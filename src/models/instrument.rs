@@ -0,0 +1,30 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct Instrument {
+    pub ticker: String,
+    pub name: String,
+    pub sector: Option<String>,
+    /// `equity`, `etf`, `bond`, `commodity` or `crypto`
+    pub asset_class: String,
+    /// Smallest price increment
+    pub tick_size: BigDecimal,
+    /// Orders must be a multiple of this many shares
+    pub lot_size: i32,
+    /// Inactive instruments can be sold but not bought
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Editable fields of an instrument
+#[derive(Debug, Clone)]
+pub struct InstrumentDetails {
+    pub name: String,
+    pub sector: Option<String>,
+    pub asset_class: String,
+    pub tick_size: BigDecimal,
+    pub lot_size: i32,
+    pub active: bool,
+}
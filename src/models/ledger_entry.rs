@@ -0,0 +1,25 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A change to the cash of a portfolio, balanced by the account its type
+/// names: the outside world, the market, the broker, a loan, the money
+/// market or, for transfers, another portfolio
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct LedgerEntry {
+    pub id: i64,
+    pub portfolio_id: i32,
+    /// `opening`, `deposit`, `withdrawal`, `allowance`, `transfer`, `buy`,
+    /// `sell`, `fee`, `dividend`, `coupon`, `redemption`, `loan`, `sweep`,
+    /// `premium`, `margin`, `settlement` or `cash_in_lieu`
+    pub entry_type: String,
+    /// Positive amounts credit the cash, negative ones debit it
+    pub amount: BigDecimal,
+    /// Cash of the portfolio once the entry was posted
+    pub balance_after: BigDecimal,
+    /// Public ID of the trade, dividend or bond payment the entry settles
+    pub transaction_id: Option<Uuid>,
+    /// Other side of a transfer
+    pub counter_portfolio_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,13 @@
+use chrono::{DateTime, Utc};
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct LiquidityProfile {
+    pub ticker: String,
+    /// Shares available per spread-width of book on each side
+    pub depth: i32,
+    /// Quoted bid-ask spread in basis points
+    pub spread_bps: f64,
+    /// Fraction of consumed depth replenished per second
+    pub resilience: f64,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,24 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct Loan {
+    pub id: i32,
+    pub user_id: i32,
+    pub portfolio_id: i32,
+    pub principal: BigDecimal,
+    /// Principal plus accrued interest minus repayments
+    pub outstanding: BigDecimal,
+    pub interest_rate_percent: f64,
+    /// `open`, `repaid`, or `liquidated` once the collateral ran out
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct LoanCollateral {
+    pub loan_id: i32,
+    pub ticker: String,
+    pub quantity: i32,
+}
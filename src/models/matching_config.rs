@@ -0,0 +1,10 @@
+use chrono::{DateTime, Utc};
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct MatchingConfig {
+    pub price_band_percent: f64,
+    pub max_order_quantity: i32,
+    pub volatility_halt_percent: f64,
+    pub halt_duration_secs: i32,
+    pub updated_at: DateTime<Utc>,
+}
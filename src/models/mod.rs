@@ -1,3 +1,35 @@
+pub mod allowance;
+pub mod announcement;
+pub mod api_key;
+pub mod basket;
+pub mod benchmark_price;
+pub mod bond;
+pub mod bot;
+pub mod cash_flow;
+pub mod cash_movement;
+pub mod class;
+pub mod competition;
+pub mod corporate_action;
+pub mod difficulty;
+pub mod dividend;
+pub mod feature_flag;
+pub mod follow;
 pub mod holding;
+pub mod instrument;
+pub mod ledger_entry;
+pub mod liquidity_profile;
+pub mod loan;
+pub mod matching_config;
+pub mod money_market_account;
+pub mod news_event;
+pub mod option;
+pub mod portfolio;
+pub mod portfolio_snapshot;
+pub mod price_candle;
+pub mod realized_gain;
+pub mod replay;
+pub mod tax_lot;
 pub mod transaction;
 pub mod user;
+pub mod user_settings;
+pub mod webhook;
@@ -0,0 +1,6 @@
+use bigdecimal::BigDecimal;
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct MoneyMarketAccount {
+    pub balance: BigDecimal,
+}
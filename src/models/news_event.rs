@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct NewsEvent {
+    pub id: i32,
+    pub ticker: String,
+    /// `earnings_beat`, `earnings_miss`, `upgrade`, `downgrade` or `other`
+    pub event_type: String,
+    pub headline: String,
+    /// Jump applied to the price when the news is published
+    pub price_impact_percent: f64,
+    /// Factor applied to the volatility for `volatility_duration_secs` after publishing
+    pub volatility_multiplier: f64,
+    pub volatility_duration_secs: i32,
+    /// The news becomes public and takes effect at this time
+    pub publish_at: DateTime<Utc>,
+    /// When the synthetic provider applied the news to the price
+    pub applied_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Details of a news event to schedule
+#[derive(Debug, Clone)]
+pub struct NewsDetails {
+    pub ticker: String,
+    pub event_type: String,
+    pub headline: String,
+    pub price_impact_percent: f64,
+    pub volatility_multiplier: f64,
+    pub volatility_duration_secs: i32,
+    pub publish_at: DateTime<Utc>,
+}
@@ -0,0 +1,52 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+
+/// A call or put on a listed underlying, covering `MULTIPLIER` shares
+///
+/// [`MULTIPLIER`]: crate::services::options::MULTIPLIER
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct OptionContract {
+    pub id: i32,
+    pub underlying: String,
+    /// `call` or `put`
+    pub kind: String,
+    pub strike: BigDecimal,
+    pub expires_at: DateTime<Utc>,
+    /// Underlying price the contract was cash-settled at after expiry
+    pub settlement_price: Option<BigDecimal>,
+    pub settled_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Contracts held in a portfolio, with the contract's terms
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct OptionPosition {
+    pub id: i32,
+    pub user_id: i32,
+    pub portfolio_id: i32,
+    pub contract_id: i32,
+    /// Negative for short positions
+    pub quantity: i32,
+    /// Average premium per share paid for long or received for short contracts
+    pub average_premium: BigDecimal,
+    /// Cash held against a short position
+    pub margin: BigDecimal,
+    pub underlying: String,
+    pub kind: String,
+    pub strike: BigDecimal,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// An option trade, or the exercise, assignment or expiry of a position
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct OptionTrade {
+    pub id: i32,
+    pub contract_id: i32,
+    /// `buy`, `sell`, `exercise`, `assignment` or `expiry`
+    pub trade_type: String,
+    pub quantity: i32,
+    /// Premium per share, or the intrinsic value per share at settlement
+    pub price: BigDecimal,
+    pub fee: BigDecimal,
+    pub created_at: DateTime<Utc>,
+}
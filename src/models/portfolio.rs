@@ -0,0 +1,24 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+
+/// Name of the default portfolio every account starts with
+pub const DEFAULT_PORTFOLIO_NAME: &str = "Main";
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct Portfolio {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    /// Cash held in this portfolio
+    pub balance: BigDecimal,
+    /// Used when a request does not select a portfolio
+    pub is_default: bool,
+    /// Set for the portfolio of a competition entry, which is kept apart from
+    /// the rest of the account
+    pub competition_id: Option<i32>,
+    /// Set for a student's class portfolio, kept apart like competition ones
+    pub class_id: Option<i32>,
+    /// Positions and trades are visible to other users
+    pub is_public: bool,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,10 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct PortfolioSnapshot {
+    pub snapshot_date: NaiveDate,
+    pub cash: BigDecimal,
+    pub market_value: BigDecimal,
+    pub equity: BigDecimal,
+}
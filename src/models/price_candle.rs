@@ -0,0 +1,115 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct PriceCandle {
+    pub bucket_start: DateTime<Utc>,
+    pub open: BigDecimal,
+    pub high: BigDecimal,
+    pub low: BigDecimal,
+    pub close: BigDecimal,
+    /// Price updates aggregated into the candle
+    pub tick_count: i32,
+    /// Shares bought and sold in the simulator during the bucket
+    pub volume: i64,
+}
+
+impl PriceCandle {
+    /// Candle opened by the first price update of a bucket
+    pub fn opened(bucket_start: DateTime<Utc>, price: BigDecimal) -> Self {
+        PriceCandle {
+            bucket_start,
+            open: price.clone(),
+            high: price.clone(),
+            low: price.clone(),
+            close: price,
+            tick_count: 1,
+            volume: 0,
+        }
+    }
+
+    /// Fold a later price update of the same bucket into the candle, the way
+    /// [`record_tick`] does
+    ///
+    /// [`record_tick`]: crate::repository::price_candle_repository::PriceCandleRepository::record_tick
+    pub fn apply(&mut self, price: BigDecimal) {
+        if price > self.high {
+            self.high = price.clone();
+        }
+        if price < self.low {
+            self.low = price.clone();
+        }
+        self.close = price;
+        self.tick_count += 1;
+    }
+}
+
+/// Daily candle of a ticker with the time of its latest update
+#[derive(sqlx::FromRow, Debug)]
+pub struct DailyCandle {
+    pub ticker: String,
+    pub bucket_start: DateTime<Utc>,
+    pub high: BigDecimal,
+    pub low: BigDecimal,
+    pub close: BigDecimal,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Latest price of a ticker on a day and the close of its previous day
+#[derive(sqlx::FromRow, Debug)]
+pub struct DailyMove {
+    pub ticker: String,
+    pub close: BigDecimal,
+    pub previous_close: BigDecimal,
+}
+
+/// Width of a candle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum CandleInterval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "1d")]
+    OneDay,
+}
+
+impl CandleInterval {
+    pub const ALL: [CandleInterval; 4] = [
+        CandleInterval::OneMinute,
+        CandleInterval::FiveMinutes,
+        CandleInterval::OneHour,
+        CandleInterval::OneDay,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CandleInterval::OneMinute => "1m",
+            CandleInterval::FiveMinutes => "5m",
+            CandleInterval::OneHour => "1h",
+            CandleInterval::OneDay => "1d",
+        }
+    }
+
+    pub fn seconds(self) -> i64 {
+        match self {
+            CandleInterval::OneMinute => 60,
+            CandleInterval::FiveMinutes => 5 * 60,
+            CandleInterval::OneHour => 60 * 60,
+            CandleInterval::OneDay => 24 * 60 * 60,
+        }
+    }
+
+    /// Start of the bucket containing `at`
+    ///
+    /// Buckets are aligned to the Unix epoch, so daily candles run from UTC midnight.
+    pub fn bucket_start(self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let seconds = at.timestamp();
+        let start = seconds - seconds.rem_euclid(self.seconds());
+        Utc.timestamp_opt(start, 0).single().unwrap_or(at)
+    }
+}
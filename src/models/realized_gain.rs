@@ -0,0 +1,19 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct RealizedGain {
+    pub id: i32,
+    pub ticker: String,
+    /// Public ID of the sell
+    pub sell_transaction_id: Uuid,
+    pub lot_id: Option<i32>,
+    pub quantity: i32,
+    pub cost_basis: BigDecimal,
+    pub proceeds: BigDecimal,
+    pub gain: BigDecimal,
+    pub cost_basis_method: String,
+    pub acquired_at: Option<DateTime<Utc>>,
+    pub realized_at: DateTime<Utc>,
+}
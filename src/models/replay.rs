@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+
+/// A historical tick dataset replayed through the price pipeline
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct Replay {
+    pub id: i32,
+    pub name: String,
+    /// Historical seconds replayed per real second
+    pub speed: f64,
+    /// `ready`, `running` or `finished`
+    pub status: String,
+    pub tick_count: i32,
+    /// Ticks published so far in the current run
+    pub position: i32,
+    /// Historical time of the first tick
+    pub data_from: DateTime<Utc>,
+    /// Historical time of the last tick
+    pub data_to: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A historical price of a replay
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct ReplayTick {
+    pub seq: i32,
+    pub at: DateTime<Utc>,
+    pub ticker: String,
+    pub price: f64,
+}
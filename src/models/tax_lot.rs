@@ -0,0 +1,10 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct TaxLot {
+    pub id: i32,
+    pub remaining_quantity: i32,
+    pub price: BigDecimal,
+    pub acquired_at: DateTime<Utc>,
+}
@@ -1,11 +1,28 @@
 use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
 
-#[derive(sqlx::FromRow, Debug)]
+#[derive(sqlx::FromRow, Debug, Clone)]
 pub struct Transaction {
     pub id: i32,
+    /// ID of the transaction in the API; `id` stays internal
+    pub public_id: Uuid,
     pub user_id: i32,
     pub ticker: String,
     pub quantity: i32,
     pub price: BigDecimal,
+    /// Commission paid on top of the price
+    pub fee: BigDecimal,
     pub transaction_type: String,
+    pub created_at: NaiveDateTime,
+    /// Last change, such as a backdated seed trade
+    pub updated_at: NaiveDateTime,
+}
+
+/// Shares of a ticker bought and sold in the simulator over some period
+#[derive(sqlx::FromRow, Debug)]
+pub struct TradedVolume {
+    pub ticker: String,
+    pub volume: i64,
+    pub trades: i64,
 }
@@ -1,9 +1,59 @@
-use bigdecimal::BigDecimal;
+use std::str::FromStr;
 
-#[derive(sqlx::FromRow, Debug)]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     pub id: i32,
+    /// ID of the user in the API; `id` stays internal
+    pub public_id: Uuid,
     pub email: String,
+    /// Password hash, left out of serialized users such as cached ones
+    #[serde(skip)]
     pub password: String,
-    pub balance: BigDecimal,
+    /// `user`, `moderator` or `admin`
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+    /// Last change of the email, password or role
+    pub updated_at: DateTime<Utc>,
+    /// Bumped by every change, guarding updates against concurrent writes
+    pub version: i32,
+}
+
+/// What an account may do; every role may do what the roles below it may
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    User,
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(Role::User),
+            "moderator" => Ok(Role::Moderator),
+            "admin" => Ok(Role::Admin),
+            other => Err(format!("Unknown role: {}", other)),
+        }
+    }
 }
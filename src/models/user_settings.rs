@@ -0,0 +1,71 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct UserSettings {
+    pub cost_basis_method: String,
+    pub cash_sweep_enabled: bool,
+    pub drip_enabled: bool,
+    /// Ticker performance is compared against
+    pub benchmark_ticker: Option<String>,
+    /// Name shown instead of the email
+    pub display_name: Option<String>,
+    /// ISO 4217 code of the currency amounts are reported in
+    pub base_currency: String,
+    /// Push fills to the user's WebSocket connections
+    pub notify_order_fills: bool,
+    /// Push margin calls to the user's WebSocket connections
+    pub notify_margin_calls: bool,
+    /// Push settled deposits to the user's WebSocket connections
+    pub notify_deposits: bool,
+    /// Preferences of the user's clients, opaque to the server
+    pub ui_settings: serde_json::Value,
+    /// Keep the user off the leaderboard
+    pub leaderboard_opt_out: bool,
+    /// Minutes before the user's public trades show up in followers' feeds
+    pub trade_delay_minutes: i32,
+    /// Leave quantities out of the user's trades in followers' feeds
+    pub hide_trade_quantities: bool,
+    /// `beginner` or `realistic`, applied outside competitions
+    pub difficulty: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Accounting method used to pick the lots consumed by a sell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CostBasisMethod {
+    /// First in, first out
+    Fifo,
+    /// Last in, first out
+    Lifo,
+    /// Weighted average cost of the whole position
+    #[default]
+    Average,
+}
+
+impl CostBasisMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CostBasisMethod::Fifo => "fifo",
+            CostBasisMethod::Lifo => "lifo",
+            CostBasisMethod::Average => "average",
+        }
+    }
+}
+
+impl FromStr for CostBasisMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fifo" => Ok(CostBasisMethod::Fifo),
+            "lifo" => Ok(CostBasisMethod::Lifo),
+            "average" => Ok(CostBasisMethod::Average),
+            other => Err(format!("Unknown cost basis method: {}", other)),
+        }
+    }
+}
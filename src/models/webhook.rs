@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+
+/// A URL account events of a user are posted to
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+    /// Key of the HMAC-SHA256 signature of every delivery
+    pub secret: String,
+    /// Account events delivered, e.g. `order_filled`
+    pub events: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An event posted, or to be posted, to a webhook
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub event: String,
+    pub payload: serde_json::Value,
+    /// `pending`, `delivered` or `failed`
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    /// HTTP status of the latest attempt, unset when no response was received
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// A delivery claimed for an attempt, with where to post it
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct DueDelivery {
+    pub id: i64,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub url: String,
+    pub secret: String,
+}
@@ -0,0 +1,92 @@
+//! # OpenAPI
+//!
+//! The OpenAPI 3 document of the REST API is generated from the handlers and
+//! their request and response types, so it cannot drift from the code. It is
+//! served at `/openapi.json` with Swagger UI at `/docs`, and
+//! `stock-exchange-sim-core openapi` prints it to refresh `api/openapi.json`,
+//! the copy the library ships to client authors.
+//!
+//! Every route module describes its own handlers; [`crate::routes::openapi`]
+//! nests them under the same prefixes as the router. Only the versioned
+//! paths are documented, not their deprecated unversioned aliases.
+
+use axum::{
+    Router,
+    extract::Request,
+    http::{HeaderValue, header},
+    middleware::{self, Next},
+    response::Response,
+};
+use utoipa::{
+    Modify, OpenApi,
+    openapi::{
+        self,
+        security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    },
+};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::routes;
+
+/// Lets Swagger UI load its own scripts, styles and inline images
+const DOCS_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; img-src 'self' data:; \
+     style-src 'self' 'unsafe-inline'; frame-ancestors 'none'";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Stock Exchange Simulator Core",
+        description = "REST API of the stock exchange simulator",
+        license(name = "AGPL-3.0")
+    ),
+    servers((url = "http://localhost:3000")),
+    paths(crate::health_check, crate::ws::handler::ws_handler),
+    modifiers(&SecuritySchemes)
+)]
+struct ApiDoc;
+
+/// Declares how requests authenticate
+///
+/// - `bearerAuth`: access token from `POST /api/v1/auth/login`
+/// - `adminKey`: `X-Admin-Key` header matching `ADMIN_API_KEY`
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearerAuth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "adminKey",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Admin-Key"))),
+        );
+    }
+}
+
+/// The OpenAPI document of every route
+pub fn spec() -> openapi::OpenApi {
+    ApiDoc::openapi().merge_from(routes::openapi())
+}
+
+/// `/openapi.json` and Swagger UI at `/docs`
+pub fn routes() -> Router {
+    Router::from(SwaggerUi::new("/docs").url("/openapi.json", spec()))
+        .layer(middleware::from_fn(docs_content_security_policy))
+}
+
+/// Middleware relaxing the default `Content-Security-Policy` for Swagger UI
+async fn docs_content_security_policy(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    response.headers_mut().insert(
+        header::CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static(DOCS_CONTENT_SECURITY_POLICY),
+    );
+    response
+}
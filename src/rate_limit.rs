@@ -0,0 +1,240 @@
+//! # Rate Limiting
+//!
+//! Requests are counted per client IP and, when they carry a valid token,
+//! per user, in sliding one-minute windows kept in Redis so that every
+//! instance enforces the same limits. Each request counts against the
+//! `global` bucket (`RATE_LIMIT_PER_MINUTE`) and, on the routes that need
+//! tighter limits, against a stricter one as well:
+//!
+//! - `auth`: everything under `/auth` (`RATE_LIMIT_AUTH_PER_MINUTE`)
+//! - `trades`: orders and other writes under `/transactions` and `/options`
+//!   (`RATE_LIMIT_TRADES_PER_MINUTE`)
+//!
+//! Versioned and unversioned paths of a route share its bucket. Orders
+//! placed through the GraphQL and gRPC APIs count against the user's `trades`
+//! bucket too, see [`count_trade`].
+//!
+//! Every window is the sorted set `rate_limit:{bucket}:{ip|user}:{id}` of the
+//! request times in it. Rejected requests count too, so a client has to slow
+//! down to get through again. Responses carry the `RateLimit-Limit`,
+//! `RateLimit-Remaining` and `RateLimit-Reset` headers of the window closest
+//! to its limit; rejections are answered with `429` and `Retry-After`.
+//!
+//! `/health` is exempt, and an unreachable Redis lets every request through.
+
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{HeaderValue, Method, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{
+    AppState, Error, Result, routes,
+    timing::{self, Phase},
+};
+
+/// Length of every window in milliseconds
+const WINDOW_MS: i64 = 60_000;
+
+/// A window a request counts against
+struct Window {
+    key: String,
+    limit: u32,
+}
+
+/// State of the window closest to its limit after counting a request
+#[derive(Debug, Clone, Copy)]
+struct Quota {
+    limit: u32,
+    remaining: u32,
+    /// Seconds until the oldest request leaves the window
+    reset_secs: u64,
+    /// Whether the request went over the limit
+    exceeded: bool,
+}
+
+impl Quota {
+    fn add_headers(&self, response: &mut Response) {
+        let headers = response.headers_mut();
+        headers.insert("ratelimit-limit", HeaderValue::from(self.limit));
+        headers.insert("ratelimit-remaining", HeaderValue::from(self.remaining));
+        headers.insert("ratelimit-reset", HeaderValue::from(self.reset_secs));
+    }
+}
+
+/// Stricter bucket of the route, with its limit
+fn route_bucket(state: &AppState, method: &Method, path: &str) -> Option<(&'static str, u32)> {
+    let path = path.strip_prefix(routes::V1_PREFIX).unwrap_or(path);
+    if path.starts_with("/auth/") {
+        Some(("auth", state.config.rate_limit_auth_per_minute))
+    } else if (path.starts_with("/transactions/") || path.starts_with("/options/"))
+        && method != Method::GET
+    {
+        Some(("trades", state.config.rate_limit_trades_per_minute))
+    } else {
+        None
+    }
+}
+
+/// User of a request with a valid bearer token
+///
+/// Revocation is not checked; a revoked token is rejected by the handler.
+fn user_id(state: &AppState, request: &Request) -> Option<i32> {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")?;
+    state
+        .auth
+        .decode_token(token)
+        .ok()
+        .map(|claims| claims.user_id)
+}
+
+/// Count a request against `windows`, returning the tightest quota left
+async fn count(state: &AppState, windows: &[Window]) -> Result<Option<Quota>> {
+    let now = Utc::now().timestamp_millis();
+    let member = format!("{}:{}", now, Uuid::new_v4().simple());
+
+    let mut pipe = redis::pipe();
+    for window in windows {
+        pipe.zrembyscore(&window.key, "-inf", now - WINDOW_MS)
+            .ignore()
+            .zadd(&window.key, &member, now)
+            .ignore()
+            .pexpire(&window.key, WINDOW_MS)
+            .ignore()
+            .zcard(&window.key)
+            .zrange_withscores(&window.key, 0, 0);
+    }
+
+    let replies: Vec<redis::Value> = async {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        pipe.atomic()
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await?;
+
+    // Per window: the requests in it, and the oldest of them with its time
+    let mut quotas = Vec::with_capacity(windows.len());
+    for (window, replies) in windows.iter().zip(replies.chunks(2)) {
+        let [used, oldest] = replies else {
+            return Err(Error::RedisError("Unexpected rate limit replies".into()));
+        };
+        let used: u32 =
+            redis::from_redis_value(used).map_err(|e| Error::RedisError(e.to_string()))?;
+        let oldest: Vec<(String, f64)> =
+            redis::from_redis_value(oldest).map_err(|e| Error::RedisError(e.to_string()))?;
+        quotas.push((
+            window,
+            used,
+            oldest.first().map_or(now, |(_, at)| *at as i64),
+        ));
+    }
+
+    Ok(quotas
+        .into_iter()
+        .map(|(window, used, oldest)| {
+            let reset_ms = (oldest + WINDOW_MS - now).max(0) as u64;
+            Quota {
+                limit: window.limit,
+                remaining: window.limit.saturating_sub(used),
+                reset_secs: (reset_ms + 999) / 1000,
+                exceeded: used > window.limit,
+            }
+        })
+        .min_by_key(|quota| (!quota.exceeded, quota.remaining)))
+}
+
+/// Count an order placed through the GraphQL or gRPC API against the user's
+/// `trades` bucket
+///
+/// All GraphQL requests go to `/graphql` and gRPC calls bypass the HTTP
+/// middleware, so the middleware cannot count these orders itself.
+pub async fn count_trade(state: &AppState, user_id: i32) -> Result<()> {
+    let limit = state.config.rate_limit_trades_per_minute;
+    if state.config.rate_limit_per_minute == 0 || limit == 0 {
+        return Ok(());
+    }
+
+    let window = Window {
+        key: format!("rate_limit:trades:user:{}", user_id),
+        limit,
+    };
+    match count(state, &[window]).await {
+        Ok(Some(quota)) if quota.exceeded => Err(Error::TooManyRequests(quota.reset_secs)),
+        Ok(_) => Ok(()),
+        Err(e) => {
+            tracing::warn!("Failed to apply rate limits: {}", e);
+            Ok(())
+        }
+    }
+}
+
+/// Middleware enforcing the configured request rates
+pub async fn rate_limit(request: Request, next: Next) -> Response {
+    let Some(state) = request.extensions().get::<AppState>().cloned() else {
+        tracing::error!("AppState extension missing for rate limiting");
+        return next.run(request).await;
+    };
+    let global = state.config.rate_limit_per_minute;
+    if global == 0 || request.uri().path() == "/health" {
+        return next.run(request).await;
+    }
+
+    let mut clients = Vec::new();
+    if let Some(ConnectInfo(addr)) = request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        clients.push(format!("ip:{}", addr.ip()));
+    }
+    if let Some(user_id) = user_id(&state, &request) {
+        clients.push(format!("user:{}", user_id));
+    }
+
+    let mut buckets = vec![("global", global)];
+    buckets.extend(route_bucket(&state, request.method(), request.uri().path()));
+    let windows: Vec<Window> = buckets
+        .iter()
+        .filter(|(_, limit)| *limit > 0)
+        .flat_map(|(bucket, limit)| {
+            clients.iter().map(move |client| Window {
+                key: format!("rate_limit:{}:{}", bucket, client),
+                limit: *limit,
+            })
+        })
+        .collect();
+
+    let quota = match count(&state, &windows).await {
+        Ok(quota) => quota,
+        Err(e) => {
+            tracing::warn!("Failed to apply rate limits: {}", e);
+            None
+        }
+    };
+
+    let mut response = match quota {
+        Some(quota) if quota.exceeded => {
+            tracing::warn!("Rate limited request to {}", request.uri().path());
+            Error::TooManyRequests(quota.reset_secs).into_response()
+        }
+        _ => next.run(request).await,
+    };
+    if let Some(quota) = quota {
+        quota.add_headers(&mut response);
+    }
+    response
+}
@@ -0,0 +1,64 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::PgPool;
+
+use crate::{Error, Result, models::allowance::AllowancePayment};
+
+pub struct AllowanceRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> AllowanceRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        AllowanceRepository { pool }
+    }
+
+    /// Credit `amount` to the default portfolio of every user who logged in
+    /// since `active_since` and was not paid for `week_start` yet
+    ///
+    /// Recording the payment, posting it to the ledger and recording it as a
+    /// cash flow happen in one statement, so concurrent workers cannot pay
+    /// twice.
+    pub async fn pay_allowances(
+        &self,
+        week_start: NaiveDate,
+        amount: BigDecimal,
+        active_since: DateTime<Utc>,
+    ) -> Result<Vec<AllowancePayment>> {
+        let payments = sqlx::query_as!(
+            AllowancePayment,
+            r#"
+            WITH paid AS (
+                INSERT INTO allowance_payments (user_id, week_start, portfolio_id, amount)
+                SELECT p.user_id, $1, p.id, $2
+                FROM portfolios p
+                JOIN users u ON u.id = p.user_id
+                WHERE p.is_default AND u.last_login_at >= $3 AND u.deleted_at IS NULL
+                ON CONFLICT (user_id, week_start) DO NOTHING
+                RETURNING user_id, portfolio_id, amount
+            ),
+            flows AS (
+                INSERT INTO cash_flows (user_id, amount)
+                SELECT user_id, amount FROM paid
+            ),
+            posted AS (
+                INSERT INTO ledger_entries (portfolio_id, entry_type, amount)
+                SELECT portfolio_id, 'allowance', amount FROM paid
+                RETURNING portfolio_id, balance_after
+            )
+            SELECT paid.user_id AS "user_id!", paid.portfolio_id AS "portfolio_id!",
+                paid.amount AS "amount!", posted.balance_after AS balance
+            FROM paid
+            JOIN posted ON posted.portfolio_id = paid.portfolio_id
+            "#,
+            week_start,
+            amount,
+            active_since
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(payments)
+    }
+}
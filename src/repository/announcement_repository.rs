@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::{Error, Result, models::announcement::Announcement};
+
+pub struct AnnouncementRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> AnnouncementRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        AnnouncementRepository { pool }
+    }
+
+    pub async fn create_announcement(
+        &self,
+        message: &str,
+        severity: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Announcement> {
+        let announcement = sqlx::query_as!(
+            Announcement,
+            r#"
+            INSERT INTO announcements (message, severity, expires_at)
+            VALUES ($1, $2, $3)
+            RETURNING id, message, severity, expires_at, created_at
+            "#,
+            message,
+            severity,
+            expires_at
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(announcement)
+    }
+
+    /// All announcements, latest first
+    pub async fn get_announcements(&self) -> Result<Vec<Announcement>> {
+        let announcements = sqlx::query_as!(
+            Announcement,
+            r#"
+            SELECT id, message, severity, expires_at, created_at
+            FROM announcements
+            ORDER BY created_at DESC, id DESC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(announcements)
+    }
+
+    /// Announcements that have not expired, oldest first
+    pub async fn get_active(&self) -> Result<Vec<Announcement>> {
+        let announcements = sqlx::query_as!(
+            Announcement,
+            r#"
+            SELECT id, message, severity, expires_at, created_at
+            FROM announcements
+            WHERE expires_at IS NULL OR expires_at > NOW()
+            ORDER BY created_at, id
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(announcements)
+    }
+
+    pub async fn delete_announcement(&self, announcement_id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM announcements
+            WHERE id = $1
+            "#,
+            announcement_id
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,76 @@
+use sqlx::PgPool;
+
+use crate::{Error, Result, models::api_key::ApiKey};
+
+pub struct ApiKeyRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> ApiKeyRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        ApiKeyRepository { pool }
+    }
+
+    pub async fn create_api_key(&self, user_id: i32, name: &str, key_hash: &str) -> Result<ApiKey> {
+        sqlx::query_as!(
+            ApiKey,
+            r#"
+            INSERT INTO api_keys (user_id, name, key_hash)
+            VALUES ($1, $2, $3)
+            RETURNING id, name, created_at, last_used_at
+            "#,
+            user_id,
+            name,
+            key_hash
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    pub async fn get_api_keys_by_user(&self, user_id: i32) -> Result<Vec<ApiKey>> {
+        sqlx::query_as!(
+            ApiKey,
+            r#"
+            SELECT id, name, created_at, last_used_at
+            FROM api_keys
+            WHERE user_id = $1
+            ORDER BY id
+            "#,
+            user_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    /// Delete a key of `user_id`, returning whether it existed
+    pub async fn delete_api_key(&self, api_key_id: i32, user_id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM api_keys WHERE id = $1 AND user_id = $2",
+            api_key_id,
+            user_id
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Owner of the key with `key_hash`, recording that the key was used
+    pub async fn use_api_key(&self, key_hash: &str) -> Result<Option<i32>> {
+        sqlx::query_scalar!(
+            r#"
+            UPDATE api_keys
+            SET last_used_at = NOW()
+            WHERE key_hash = $1
+            RETURNING user_id
+            "#,
+            key_hash
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+}
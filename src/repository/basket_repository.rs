@@ -0,0 +1,101 @@
+use bigdecimal::BigDecimal;
+use sqlx::PgPool;
+
+use crate::{Error, Result, models::basket::BasketConstituent};
+
+pub struct BasketRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> BasketRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        BasketRepository { pool }
+    }
+
+    /// Constituents of every basket, by basket and ticker
+    pub async fn get_all(&self) -> Result<Vec<BasketConstituent>> {
+        sqlx::query_as!(
+            BasketConstituent,
+            "SELECT basket, ticker, weight FROM basket_constituents ORDER BY basket, ticker"
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    pub async fn get_constituents(&self, basket: &str) -> Result<Vec<BasketConstituent>> {
+        sqlx::query_as!(
+            BasketConstituent,
+            r#"
+            SELECT basket, ticker, weight
+            FROM basket_constituents
+            WHERE basket = $1
+            ORDER BY ticker
+            "#,
+            basket
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    /// Whether `ticker` is held in some basket
+    pub async fn is_constituent(&self, ticker: &str) -> Result<bool> {
+        let found = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM basket_constituents WHERE ticker = $1) AS "found!""#,
+            ticker
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(found)
+    }
+
+    /// Replace the constituents of `basket`
+    pub async fn replace_basket(
+        &self,
+        basket: &str,
+        constituents: &[(String, BigDecimal)],
+    ) -> Result<Vec<BasketConstituent>> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        sqlx::query!("DELETE FROM basket_constituents WHERE basket = $1", basket)
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+
+        let mut replaced = Vec::with_capacity(constituents.len());
+        for (ticker, weight) in constituents {
+            let constituent = sqlx::query_as!(
+                BasketConstituent,
+                r#"
+                INSERT INTO basket_constituents (basket, ticker, weight)
+                VALUES ($1, $2, $3)
+                RETURNING basket, ticker, weight
+                "#,
+                basket,
+                ticker,
+                weight
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+            replaced.push(constituent);
+        }
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(replaced)
+    }
+
+    /// Remove the basket of `basket`; returns whether it had one
+    pub async fn delete_basket(&self, basket: &str) -> Result<bool> {
+        let result = sqlx::query!("DELETE FROM basket_constituents WHERE basket = $1", basket)
+            .execute(self.pool)
+            .await
+            .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,67 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::{Error, Result, models::benchmark_price::BenchmarkPrice};
+
+pub struct BenchmarkPriceRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> BenchmarkPriceRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        BenchmarkPriceRepository { pool }
+    }
+
+    /// Store the price for a day, replacing any earlier capture of the same day
+    pub async fn upsert_price(
+        &self,
+        ticker: &str,
+        price_date: NaiveDate,
+        price: BigDecimal,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO benchmark_prices (ticker, price_date, price)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (ticker, price_date) DO UPDATE
+            SET price = EXCLUDED.price,
+                created_at = NOW()
+            "#,
+            ticker,
+            price_date,
+            price
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    /// Prices between `from` and `to` (inclusive), oldest first
+    pub async fn get_prices(
+        &self,
+        ticker: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<BenchmarkPrice>> {
+        let prices = sqlx::query_as!(
+            BenchmarkPrice,
+            r#"
+            SELECT price_date, price
+            FROM benchmark_prices
+            WHERE ticker = $1 AND price_date BETWEEN $2 AND $3
+            ORDER BY price_date
+            "#,
+            ticker,
+            from,
+            to
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(prices)
+    }
+}
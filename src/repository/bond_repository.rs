@@ -0,0 +1,174 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::{Error, Result, models::bond::Bond};
+
+pub struct BondRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> BondRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        BondRepository { pool }
+    }
+
+    /// Set the terms of a bond, returning `None` when it has been redeemed
+    pub async fn upsert_bond(
+        &self,
+        ticker: &str,
+        face_value: BigDecimal,
+        coupon_rate_percent: BigDecimal,
+        coupons_per_year: i32,
+        maturity_date: NaiveDate,
+        next_coupon_date: NaiveDate,
+    ) -> Result<Option<Bond>> {
+        sqlx::query_as!(
+            Bond,
+            r#"
+            INSERT INTO bonds (ticker, face_value, coupon_rate_percent, coupons_per_year,
+                               maturity_date, next_coupon_date)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (ticker) DO UPDATE
+            SET face_value = EXCLUDED.face_value,
+                coupon_rate_percent = EXCLUDED.coupon_rate_percent,
+                coupons_per_year = EXCLUDED.coupons_per_year,
+                maturity_date = EXCLUDED.maturity_date,
+                next_coupon_date = EXCLUDED.next_coupon_date,
+                updated_at = NOW()
+            WHERE bonds.redeemed_at IS NULL
+            RETURNING ticker, face_value, coupon_rate_percent, coupons_per_year, maturity_date,
+                      next_coupon_date, redeemed_at, created_at, updated_at
+            "#,
+            ticker,
+            face_value,
+            coupon_rate_percent,
+            coupons_per_year,
+            maturity_date,
+            next_coupon_date
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    pub async fn get_bond(&self, ticker: &str) -> Result<Option<Bond>> {
+        sqlx::query_as!(
+            Bond,
+            r#"
+            SELECT ticker, face_value, coupon_rate_percent, coupons_per_year, maturity_date,
+                   next_coupon_date, redeemed_at, created_at, updated_at
+            FROM bonds
+            WHERE ticker = $1
+            "#,
+            ticker
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    /// Outstanding bonds among `tickers`
+    pub async fn get_outstanding(&self, tickers: &[String]) -> Result<Vec<Bond>> {
+        sqlx::query_as!(
+            Bond,
+            r#"
+            SELECT ticker, face_value, coupon_rate_percent, coupons_per_year, maturity_date,
+                   next_coupon_date, redeemed_at, created_at, updated_at
+            FROM bonds
+            WHERE ticker = ANY($1) AND redeemed_at IS NULL
+            "#,
+            tickers
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    pub async fn get_bonds(&self) -> Result<Vec<Bond>> {
+        sqlx::query_as!(
+            Bond,
+            r#"
+            SELECT ticker, face_value, coupon_rate_percent, coupons_per_year, maturity_date,
+                   next_coupon_date, redeemed_at, created_at, updated_at
+            FROM bonds
+            ORDER BY maturity_date, ticker
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    /// Outstanding bonds with a coupon due on or before `today`
+    pub async fn get_due(&self, today: NaiveDate) -> Result<Vec<Bond>> {
+        sqlx::query_as!(
+            Bond,
+            r#"
+            SELECT ticker, face_value, coupon_rate_percent, coupons_per_year, maturity_date,
+                   next_coupon_date, redeemed_at, created_at, updated_at
+            FROM bonds
+            WHERE redeemed_at IS NULL AND next_coupon_date <= $1
+            ORDER BY next_coupon_date, ticker
+            "#,
+            today
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    /// Move a bond from the coupon `due` to `next`, returning false when
+    /// another instance paid it first
+    pub async fn claim_coupon(
+        &self,
+        ticker: &str,
+        due: NaiveDate,
+        next: NaiveDate,
+    ) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE bonds
+            SET next_coupon_date = $3, updated_at = NOW()
+            WHERE ticker = $1 AND next_coupon_date = $2 AND redeemed_at IS NULL
+            "#,
+            ticker,
+            due,
+            next
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Mark a bond redeemed and delist it, returning false when another
+    /// instance redeemed it first
+    pub async fn claim_redemption(&self, ticker: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE bonds
+            SET redeemed_at = NOW(), updated_at = NOW()
+            WHERE ticker = $1 AND redeemed_at IS NULL
+            "#,
+            ticker
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            "UPDATE instruments SET active = FALSE, updated_at = NOW() WHERE ticker = $1",
+            ticker
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(true)
+    }
+}
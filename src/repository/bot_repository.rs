@@ -0,0 +1,162 @@
+use bigdecimal::BigDecimal;
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::bot::{Bot, BotStrategy},
+};
+
+pub struct BotRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> BotRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        BotRepository { pool }
+    }
+
+    /// Create a bot account with a default portfolio holding
+    /// `starting_balance`
+    ///
+    /// The bot is named after its strategy and ID. Returns `None` when an
+    /// account with `email` exists, e.g. created by another instance.
+    pub async fn create_bot(
+        &self,
+        email: &str,
+        password: &str,
+        strategy: BotStrategy,
+        portfolio_name: &str,
+        starting_balance: BigDecimal,
+    ) -> Result<Option<Bot>> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        let user_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO users (email, password)
+            VALUES ($1, $2)
+            ON CONFLICT (email) WHERE deleted_at IS NULL DO NOTHING
+            RETURNING id
+            "#,
+            email,
+            password
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+        let Some(user_id) = user_id else {
+            return Ok(None);
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO portfolios (user_id, name, balance, is_default)
+            VALUES ($1, $2, $3, TRUE)
+            "#,
+            user_id,
+            portfolio_name,
+            starting_balance
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        let bot_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO bots (user_id, strategy)
+            VALUES ($1, $2)
+            RETURNING id
+            "#,
+            user_id,
+            strategy.as_str()
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        let display_name = format!("{} Bot {}", strategy_label(strategy), bot_id);
+        sqlx::query!(
+            r#"
+            INSERT INTO user_settings (user_id, display_name)
+            VALUES ($1, $2)
+            "#,
+            user_id,
+            display_name
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        self.get_bot(bot_id).await
+    }
+
+    pub async fn get_bot(&self, bot_id: i32) -> Result<Option<Bot>> {
+        let bot = sqlx::query_as!(
+            Bot,
+            r#"
+            SELECT b.id, b.user_id, u.public_id AS user_public_id,
+                s.display_name AS "display_name?", b.strategy, b.active, b.created_at
+            FROM bots b
+            JOIN users u ON u.id = b.user_id AND u.deleted_at IS NULL
+            LEFT JOIN user_settings s ON s.user_id = b.user_id
+            WHERE b.id = $1
+            "#,
+            bot_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(bot)
+    }
+
+    /// Every bot, oldest first
+    pub async fn get_bots(&self) -> Result<Vec<Bot>> {
+        let bots = sqlx::query_as!(
+            Bot,
+            r#"
+            SELECT b.id, b.user_id, u.public_id AS user_public_id,
+                s.display_name AS "display_name?", b.strategy, b.active, b.created_at
+            FROM bots b
+            JOIN users u ON u.id = b.user_id AND u.deleted_at IS NULL
+            LEFT JOIN user_settings s ON s.user_id = b.user_id
+            ORDER BY b.id
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(bots)
+    }
+
+    /// Pause or resume a bot, returning `None` for an unknown bot
+    pub async fn set_active(&self, bot_id: i32, active: bool) -> Result<Option<Bot>> {
+        let updated = sqlx::query!(
+            r#"
+            UPDATE bots
+            SET active = $2
+            WHERE id = $1
+            "#,
+            bot_id,
+            active
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        self.get_bot(bot_id).await
+    }
+}
+
+fn strategy_label(strategy: BotStrategy) -> &'static str {
+    match strategy {
+        BotStrategy::Momentum => "Momentum",
+        BotStrategy::MeanReversion => "Mean Reversion",
+        BotStrategy::Random => "Random",
+    }
+}
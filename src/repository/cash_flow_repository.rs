@@ -0,0 +1,59 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::{Error, Result, models::cash_flow::DailyCashFlow};
+
+pub struct CashFlowRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> CashFlowRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        CashFlowRepository { pool }
+    }
+
+    /// Record an external deposit (positive) or withdrawal (negative)
+    pub async fn record_flow(&self, user_id: i32, amount: BigDecimal) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO cash_flows (user_id, amount)
+            VALUES ($1, $2)
+            "#,
+            user_id,
+            amount
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    /// Net flows per UTC day between `from` and `to` (inclusive), oldest first
+    pub async fn get_daily_flows(
+        &self,
+        user_id: i32,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyCashFlow>> {
+        let flows = sqlx::query_as!(
+            DailyCashFlow,
+            r#"
+            SELECT (created_at AT TIME ZONE 'UTC')::date AS "flow_date!", SUM(amount) AS "amount!"
+            FROM cash_flows
+            WHERE user_id = $1 AND (created_at AT TIME ZONE 'UTC')::date BETWEEN $2 AND $3
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+            user_id,
+            from,
+            to
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(flows)
+    }
+}
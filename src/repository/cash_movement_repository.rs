@@ -0,0 +1,79 @@
+use bigdecimal::BigDecimal;
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result, models::cash_movement::CashMovement,
+    repository::ledger_repository::posting_error,
+};
+
+pub struct CashMovementRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> CashMovementRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        CashMovementRepository { pool }
+    }
+
+    /// Record a `deposit` or `withdrawal` of `amount` and post it to the
+    /// portfolio's ledger in one statement
+    pub async fn record(
+        &self,
+        portfolio_id: i32,
+        movement_type: &str,
+        amount: BigDecimal,
+    ) -> Result<CashMovement> {
+        sqlx::query_as!(
+            CashMovement,
+            r#"
+            WITH entry AS (
+                INSERT INTO ledger_entries (portfolio_id, entry_type, amount)
+                VALUES ($1, $2::varchar, CASE WHEN $2 = 'withdrawal' THEN -$3::numeric ELSE $3 END)
+                RETURNING id, balance_after
+            ),
+            movement AS (
+                INSERT INTO cash_movements (portfolio_id, movement_type, amount, ledger_entry_id)
+                SELECT $1, $2::varchar, $3, id FROM entry
+                RETURNING id, portfolio_id, movement_type, amount, created_at
+            )
+            SELECT movement.id AS "id!", movement.portfolio_id AS "portfolio_id!",
+                movement.movement_type AS "movement_type!", movement.amount AS "amount!",
+                entry.balance_after AS "balance_after!", movement.created_at AS "created_at!"
+            FROM movement, entry
+            "#,
+            portfolio_id,
+            movement_type,
+            amount
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(posting_error)
+    }
+
+    /// One page of the portfolio's deposits and withdrawals, newest first;
+    /// `before` is the id of the last movement of the previous page
+    pub async fn get_movements_page(
+        &self,
+        portfolio_id: i32,
+        before: Option<i32>,
+        limit: i64,
+    ) -> Result<Vec<CashMovement>> {
+        sqlx::query_as!(
+            CashMovement,
+            r#"
+            SELECT m.id, m.portfolio_id, m.movement_type, m.amount, l.balance_after, m.created_at
+            FROM cash_movements m
+            JOIN ledger_entries l ON l.id = m.ledger_entry_id
+            WHERE m.portfolio_id = $1 AND ($2::int IS NULL OR m.id < $2)
+            ORDER BY m.id DESC
+            LIMIT $3
+            "#,
+            portfolio_id,
+            before,
+            limit
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+}
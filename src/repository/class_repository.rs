@@ -0,0 +1,304 @@
+use bigdecimal::BigDecimal;
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::{
+        class::{Class, ClassMember, ClassRole, ClassStudent},
+        portfolio::Portfolio,
+    },
+};
+
+pub struct ClassRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> ClassRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        ClassRepository { pool }
+    }
+
+    /// Create a class with `instructor_id` as its first instructor
+    pub async fn create_class(
+        &self,
+        instructor_id: i32,
+        name: &str,
+        starting_balance: BigDecimal,
+        allowed_tickers: Option<&[String]>,
+        invite_code: &str,
+    ) -> Result<Class> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        let class = sqlx::query_as!(
+            Class,
+            r#"
+            INSERT INTO classes (name, starting_balance, allowed_tickers, invite_code, created_by)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, name, starting_balance, allowed_tickers, invite_code, created_at
+            "#,
+            name,
+            starting_balance,
+            allowed_tickers,
+            invite_code,
+            instructor_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO class_members (class_id, user_id, role)
+            VALUES ($1, $2, $3)
+            "#,
+            class.id,
+            instructor_id,
+            ClassRole::Instructor.as_str()
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(class)
+    }
+
+    pub async fn get_class(&self, class_id: i32) -> Result<Option<Class>> {
+        let class = sqlx::query_as!(
+            Class,
+            r#"
+            SELECT id, name, starting_balance, allowed_tickers, invite_code, created_at
+            FROM classes
+            WHERE id = $1
+            "#,
+            class_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(class)
+    }
+
+    pub async fn get_class_by_invite_code(&self, invite_code: &str) -> Result<Option<Class>> {
+        let class = sqlx::query_as!(
+            Class,
+            r#"
+            SELECT id, name, starting_balance, allowed_tickers, invite_code, created_at
+            FROM classes
+            WHERE invite_code = $1
+            "#,
+            invite_code
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(class)
+    }
+
+    /// Classes the user is a member of, latest joined first
+    pub async fn get_classes_by_user(&self, user_id: i32) -> Result<Vec<(Class, ClassMember)>> {
+        let members = sqlx::query_as!(
+            ClassMember,
+            r#"
+            SELECT class_id, role, portfolio_id, joined_at
+            FROM class_members
+            WHERE user_id = $1
+            ORDER BY joined_at DESC, class_id DESC
+            "#,
+            user_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        let class_ids: Vec<i32> = members.iter().map(|m| m.class_id).collect();
+        let classes = sqlx::query_as!(
+            Class,
+            r#"
+            SELECT id, name, starting_balance, allowed_tickers, invite_code, created_at
+            FROM classes
+            WHERE id = ANY($1)
+            "#,
+            &class_ids
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(members
+            .into_iter()
+            .filter_map(|member| {
+                let class = classes.iter().find(|c| c.id == member.class_id)?.clone();
+                Some((class, member))
+            })
+            .collect())
+    }
+
+    pub async fn get_member(&self, class_id: i32, user_id: i32) -> Result<Option<ClassMember>> {
+        let member = sqlx::query_as!(
+            ClassMember,
+            r#"
+            SELECT class_id, role, portfolio_id, joined_at
+            FROM class_members
+            WHERE class_id = $1 AND user_id = $2
+            "#,
+            class_id,
+            user_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(member)
+    }
+
+    /// Replace the rules of a class; students who already joined keep their cash
+    pub async fn update_class(
+        &self,
+        class_id: i32,
+        name: &str,
+        starting_balance: BigDecimal,
+        allowed_tickers: Option<&[String]>,
+    ) -> Result<Class> {
+        let class = sqlx::query_as!(
+            Class,
+            r#"
+            UPDATE classes
+            SET name = $2, starting_balance = $3, allowed_tickers = $4
+            WHERE id = $1
+            RETURNING id, name, starting_balance, allowed_tickers, invite_code, created_at
+            "#,
+            class_id,
+            name,
+            starting_balance,
+            allowed_tickers
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(class)
+    }
+
+    pub async fn set_invite_code(&self, class_id: i32, invite_code: &str) -> Result<Class> {
+        let class = sqlx::query_as!(
+            Class,
+            r#"
+            UPDATE classes
+            SET invite_code = $2
+            WHERE id = $1
+            RETURNING id, name, starting_balance, allowed_tickers, invite_code, created_at
+            "#,
+            class_id,
+            invite_code
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(class)
+    }
+
+    /// Enroll the user as a student with a new portfolio holding the
+    /// starting balance
+    ///
+    /// Returns `None` when the user already is a member or already has a
+    /// portfolio named `portfolio_name`.
+    pub async fn join(
+        &self,
+        class: &Class,
+        user_id: i32,
+        portfolio_name: &str,
+    ) -> Result<Option<Portfolio>> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        let portfolio = sqlx::query_as!(
+            Portfolio,
+            r#"
+            INSERT INTO portfolios (user_id, name, balance, is_default, class_id)
+            VALUES ($1, $2, $3, FALSE, $4)
+            ON CONFLICT (user_id, name) DO NOTHING
+            RETURNING id, user_id, name, balance, is_default, competition_id, class_id, is_public,
+                created_at
+            "#,
+            user_id,
+            portfolio_name,
+            class.starting_balance,
+            class.id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+        let Some(portfolio) = portfolio else {
+            return Ok(None);
+        };
+
+        let joined = sqlx::query!(
+            r#"
+            INSERT INTO class_members (class_id, user_id, role, portfolio_id, starting_balance)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (class_id, user_id) DO NOTHING
+            "#,
+            class.id,
+            user_id,
+            ClassRole::Student.as_str(),
+            portfolio.id,
+            class.starting_balance
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+        if joined.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(Some(portfolio))
+    }
+
+    /// Students of a class, first joined first
+    pub async fn get_students(&self, class_id: i32) -> Result<Vec<ClassStudent>> {
+        let students = sqlx::query_as!(
+            ClassStudent,
+            r#"
+            SELECT u.public_id AS user_id, u.email, s.display_name, m.portfolio_id AS "portfolio_id!",
+                m.starting_balance AS "starting_balance!", m.joined_at
+            FROM class_members m
+            JOIN users u ON u.id = m.user_id AND u.deleted_at IS NULL
+            LEFT JOIN user_settings s ON s.user_id = m.user_id
+            WHERE m.class_id = $1 AND m.role = 'student'
+            ORDER BY m.joined_at, m.user_id
+            "#,
+            class_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(students)
+    }
+
+    /// The class portfolios of a class's students
+    pub async fn get_portfolios(&self, class_id: i32) -> Result<Vec<Portfolio>> {
+        let portfolios = sqlx::query_as!(
+            Portfolio,
+            r#"
+            SELECT id, user_id, name, balance, is_default, competition_id, class_id, is_public,
+                created_at
+            FROM portfolios
+            WHERE class_id = $1
+            ORDER BY id
+            "#,
+            class_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(portfolios)
+    }
+}
@@ -0,0 +1,332 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::{
+        competition::{Competition, CompetitionEntry},
+        difficulty::Difficulty,
+        portfolio::Portfolio,
+    },
+};
+
+pub struct CompetitionRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> CompetitionRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        CompetitionRepository { pool }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_competition(
+        &self,
+        name: &str,
+        description: &str,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+        starting_balance: BigDecimal,
+        difficulty: Difficulty,
+        clock_speed: f64,
+    ) -> Result<Competition> {
+        let competition = sqlx::query_as!(
+            Competition,
+            r#"
+            INSERT INTO competitions (name, description, starts_at, ends_at, starting_balance,
+                difficulty, clock_speed)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, name, description, starts_at, ends_at, starting_balance, difficulty,
+                clock_speed, finalized_at, created_at
+            "#,
+            name,
+            description,
+            starts_at,
+            ends_at,
+            starting_balance,
+            difficulty.as_str(),
+            clock_speed
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(competition)
+    }
+
+    /// Every competition, latest start first
+    pub async fn get_competitions(&self) -> Result<Vec<Competition>> {
+        let competitions = sqlx::query_as!(
+            Competition,
+            r#"
+            SELECT id, name, description, starts_at, ends_at, starting_balance, difficulty,
+                clock_speed, finalized_at, created_at
+            FROM competitions
+            ORDER BY starts_at DESC, id DESC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(competitions)
+    }
+
+    pub async fn get_competition(&self, competition_id: i32) -> Result<Option<Competition>> {
+        let competition = sqlx::query_as!(
+            Competition,
+            r#"
+            SELECT id, name, description, starts_at, ends_at, starting_balance, difficulty,
+                clock_speed, finalized_at, created_at
+            FROM competitions
+            WHERE id = $1
+            "#,
+            competition_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(competition)
+    }
+
+    /// Competitions that have ended but whose standings are not recorded yet
+    pub async fn get_due_for_finalization(&self) -> Result<Vec<Competition>> {
+        let competitions = sqlx::query_as!(
+            Competition,
+            r#"
+            SELECT id, name, description, starts_at, ends_at, starting_balance, difficulty,
+                clock_speed, finalized_at, created_at
+            FROM competitions
+            WHERE finalized_at IS NULL AND ends_at <= NOW()
+            ORDER BY ends_at
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(competitions)
+    }
+
+    /// Running competitions whose clock runs faster than real time
+    pub async fn get_running_accelerated(&self) -> Result<Vec<Competition>> {
+        let competitions = sqlx::query_as!(
+            Competition,
+            r#"
+            SELECT id, name, description, starts_at, ends_at, starting_balance, difficulty,
+                clock_speed, finalized_at, created_at
+            FROM competitions
+            WHERE clock_speed > 1 AND starts_at <= NOW() AND ends_at > NOW()
+            ORDER BY id
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(competitions)
+    }
+
+    /// Delete a competition that has not started, with its entrants' portfolios
+    pub async fn delete_upcoming(&self, competition_id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM competitions
+            WHERE id = $1 AND starts_at > NOW()
+            "#,
+            competition_id
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Enter the user with a new portfolio holding the starting balance
+    ///
+    /// Returns `None` when the user already entered or already has a
+    /// portfolio named `portfolio_name`.
+    pub async fn join(
+        &self,
+        competition: &Competition,
+        user_id: i32,
+        portfolio_name: &str,
+    ) -> Result<Option<Portfolio>> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        let portfolio = sqlx::query_as!(
+            Portfolio,
+            r#"
+            INSERT INTO portfolios (user_id, name, balance, is_default, competition_id)
+            VALUES ($1, $2, $3, FALSE, $4)
+            ON CONFLICT (user_id, name) DO NOTHING
+            RETURNING id, user_id, name, balance, is_default, competition_id, class_id, is_public,
+                created_at
+            "#,
+            user_id,
+            portfolio_name,
+            competition.starting_balance,
+            competition.id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+        let Some(portfolio) = portfolio else {
+            return Ok(None);
+        };
+
+        let entered = sqlx::query!(
+            r#"
+            INSERT INTO competition_entries (competition_id, user_id, portfolio_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (competition_id, user_id) DO NOTHING
+            "#,
+            competition.id,
+            user_id,
+            portfolio.id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+        if entered.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(Some(portfolio))
+    }
+
+    pub async fn get_entry(
+        &self,
+        competition_id: i32,
+        user_id: i32,
+    ) -> Result<Option<CompetitionEntry>> {
+        let entry = sqlx::query_as!(
+            CompetitionEntry,
+            r#"
+            SELECT competition_id, user_id, portfolio_id, final_equity, final_rank
+            FROM competition_entries
+            WHERE competition_id = $1 AND user_id = $2
+            "#,
+            competition_id,
+            user_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(entry)
+    }
+
+    /// Every competition the user entered
+    pub async fn get_entries_by_user(&self, user_id: i32) -> Result<Vec<CompetitionEntry>> {
+        let entries = sqlx::query_as!(
+            CompetitionEntry,
+            r#"
+            SELECT competition_id, user_id, portfolio_id, final_equity, final_rank
+            FROM competition_entries
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(entries)
+    }
+
+    /// The recorded standings of a finalized competition, winner first
+    pub async fn get_final_entries(&self, competition_id: i32) -> Result<Vec<CompetitionEntry>> {
+        let entries = sqlx::query_as!(
+            CompetitionEntry,
+            r#"
+            SELECT competition_id, user_id, portfolio_id, final_equity, final_rank
+            FROM competition_entries
+            WHERE competition_id = $1 AND final_rank IS NOT NULL
+            ORDER BY final_rank, portfolio_id
+            "#,
+            competition_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(entries)
+    }
+
+    /// The portfolios entered in a competition
+    pub async fn get_portfolios(&self, competition_id: i32) -> Result<Vec<Portfolio>> {
+        let portfolios = sqlx::query_as!(
+            Portfolio,
+            r#"
+            SELECT id, user_id, name, balance, is_default, competition_id, class_id, is_public,
+                created_at
+            FROM portfolios
+            WHERE competition_id = $1
+            ORDER BY id
+            "#,
+            competition_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(portfolios)
+    }
+
+    /// Record the final equity and rank of every entry and mark the
+    /// competition finalized
+    ///
+    /// `standings` holds `(portfolio_id, equity, rank)`. Returns false when
+    /// the competition was already finalized, e.g. by another instance.
+    pub async fn finalize(
+        &self,
+        competition_id: i32,
+        standings: &[(i32, BigDecimal, i32)],
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        let claimed = sqlx::query!(
+            r#"
+            UPDATE competitions
+            SET finalized_at = NOW()
+            WHERE id = $1 AND finalized_at IS NULL
+            "#,
+            competition_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+        if claimed.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        let portfolio_ids: Vec<i32> = standings.iter().map(|s| s.0).collect();
+        let equities: Vec<BigDecimal> = standings.iter().map(|s| s.1.clone()).collect();
+        let ranks: Vec<i32> = standings.iter().map(|s| s.2).collect();
+        sqlx::query!(
+            r#"
+            UPDATE competition_entries e
+            SET final_equity = s.equity, final_rank = s.rank
+            FROM UNNEST($2::INT[], $3::NUMERIC[], $4::INT[]) AS s (portfolio_id, equity, rank)
+            WHERE e.competition_id = $1 AND e.portfolio_id = s.portfolio_id
+            "#,
+            competition_id,
+            &portfolio_ids,
+            &equities,
+            &ranks
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(true)
+    }
+}
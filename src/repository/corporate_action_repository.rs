@@ -0,0 +1,372 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::{corporate_action::CorporateAction, holding::Holding, tax_lot::TaxLot},
+    repository::ledger_repository::{TradePosting, post_trade_entries},
+    services::corporate_actions::SplitPosition,
+};
+
+pub struct CorporateActionRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> CorporateActionRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        CorporateActionRepository { pool }
+    }
+
+    pub async fn create_split(
+        &self,
+        ticker: &str,
+        effective_date: NaiveDate,
+        ratio_from: i32,
+        ratio_to: i32,
+    ) -> Result<CorporateAction> {
+        let action = sqlx::query_as!(
+            CorporateAction,
+            r#"
+            INSERT INTO corporate_actions (ticker, action_type, effective_date, ratio_from, ratio_to)
+            VALUES ($1, 'split', $2, $3, $4)
+            RETURNING id, ticker, action_type, effective_date, ratio_from, ratio_to, new_ticker,
+                      status, applied_at, created_at
+            "#,
+            ticker,
+            effective_date,
+            ratio_from,
+            ratio_to
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(action)
+    }
+
+    pub async fn create_symbol_change(
+        &self,
+        ticker: &str,
+        effective_date: NaiveDate,
+        new_ticker: &str,
+    ) -> Result<CorporateAction> {
+        let action = sqlx::query_as!(
+            CorporateAction,
+            r#"
+            INSERT INTO corporate_actions (ticker, action_type, effective_date, new_ticker)
+            VALUES ($1, 'symbol_change', $2, $3)
+            RETURNING id, ticker, action_type, effective_date, ratio_from, ratio_to, new_ticker,
+                      status, applied_at, created_at
+            "#,
+            ticker,
+            effective_date,
+            new_ticker
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(action)
+    }
+
+    pub async fn get_actions(&self) -> Result<Vec<CorporateAction>> {
+        let actions = sqlx::query_as!(
+            CorporateAction,
+            r#"
+            SELECT id, ticker, action_type, effective_date, ratio_from, ratio_to, new_ticker,
+                   status, applied_at, created_at
+            FROM corporate_actions
+            ORDER BY effective_date DESC, id DESC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(actions)
+    }
+
+    /// Delete an action that has not been applied yet
+    pub async fn delete_pending(&self, action_id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM corporate_actions
+            WHERE id = $1 AND status = 'pending'
+            "#,
+            action_id
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Claim every pending action whose effective date has arrived, oldest first
+    ///
+    /// Actions are marked as applied while claiming, so concurrent workers
+    /// cannot apply the same action twice.
+    pub async fn claim_due(&self, today: NaiveDate) -> Result<Vec<CorporateAction>> {
+        let mut actions = sqlx::query_as!(
+            CorporateAction,
+            r#"
+            UPDATE corporate_actions
+            SET status = 'applied', applied_at = NOW()
+            WHERE status = 'pending' AND effective_date <= $1
+            RETURNING id, ticker, action_type, effective_date, ratio_from, ratio_to, new_ticker,
+                      status, applied_at, created_at
+            "#,
+            today
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        actions.sort_by_key(|action| (action.effective_date, action.id));
+        Ok(actions)
+    }
+
+    /// Apply a `ratio_from`-to-`ratio_to` split of a ticker effective on
+    /// `effective_date`, in a single database transaction
+    ///
+    /// Every open position in the ticker is locked with its open lots, oldest
+    /// first, and `restate` computes its split quantity, average price, lots
+    /// and cash in lieu, which is posted to the portfolio's cash. Loan
+    /// collateral in the ticker is split, and the candles and benchmark prices
+    /// recorded before the effective date are restated at the split ratio, so
+    /// the price history lines up with the split-adjusted prices the feed
+    /// publishes from then on. A failure leaves the ticker unsplit.
+    pub async fn split_ticker<F>(
+        &self,
+        ticker: &str,
+        effective_date: NaiveDate,
+        ratio_from: i32,
+        ratio_to: i32,
+        restate: F,
+    ) -> Result<()>
+    where
+        F: Fn(&Holding, &[TaxLot]) -> Result<SplitPosition>,
+    {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        let holdings = sqlx::query_as!(
+            Holding,
+            r#"
+            SELECT id, public_id, user_id, portfolio_id, ticker, quantity, average_price, created_at,
+                updated_at, version
+            FROM holdings
+            WHERE ticker = $1 AND closed_at IS NULL AND deleted_at IS NULL
+            ORDER BY id
+            FOR UPDATE
+            "#,
+            ticker
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        for holding in &holdings {
+            let lots = sqlx::query_as!(
+                TaxLot,
+                r#"
+                SELECT id, remaining_quantity, price, acquired_at
+                FROM tax_lots
+                WHERE portfolio_id = $1 AND ticker = $2 AND remaining_quantity > 0
+                ORDER BY acquired_at, id
+                FOR UPDATE
+                "#,
+                holding.portfolio_id,
+                ticker
+            )
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+
+            let position = restate(holding, &lots)?;
+
+            sqlx::query!(
+                r#"
+                UPDATE holdings
+                SET quantity = $1, average_price = $2, updated_at = NOW(), version = version + 1,
+                    closed_at = CASE WHEN $1 = 0 THEN NOW() END
+                WHERE id = $3
+                "#,
+                position.quantity,
+                position.average_price,
+                holding.id
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+
+            for (lot, (remaining_quantity, price)) in lots.iter().zip(position.lots) {
+                sqlx::query!(
+                    r#"
+                    UPDATE tax_lots
+                    SET remaining_quantity = $1, price = $2
+                    WHERE id = $3
+                    "#,
+                    remaining_quantity,
+                    price,
+                    lot.id
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(Error::Database)?;
+            }
+
+            if !position.cash_in_lieu.is_zero() {
+                let posting = TradePosting {
+                    entry_type: "cash_in_lieu",
+                    value: position.cash_in_lieu,
+                    fee: BigDecimal::zero(),
+                };
+                post_trade_entries(&mut tx, holding.portfolio_id, posting, None).await?;
+            }
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE loan_collateral
+            SET quantity = quantity * $3 / $2
+            FROM loans
+            WHERE loans.id = loan_collateral.loan_id
+              AND loans.status = 'open'
+              AND loan_collateral.ticker = $1
+            "#,
+            ticker,
+            ratio_from,
+            ratio_to
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        let effective_at = effective_date.and_time(chrono::NaiveTime::MIN).and_utc();
+        sqlx::query!(
+            r#"
+            UPDATE price_candles
+            SET open = open * $2 / $3, high = high * $2 / $3, low = low * $2 / $3,
+                close = close * $2 / $3, updated_at = NOW()
+            WHERE ticker = $1 AND bucket_start < $4
+            "#,
+            ticker,
+            BigDecimal::from(ratio_from),
+            BigDecimal::from(ratio_to),
+            effective_at
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx::query!(
+            r#"
+            UPDATE benchmark_prices
+            SET price = price * $2 / $3
+            WHERE ticker = $1 AND price_date < $4
+            "#,
+            ticker,
+            BigDecimal::from(ratio_from),
+            BigDecimal::from(ratio_to),
+            effective_date
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    /// Move open positions, lots, loan collateral, liquidity settings, the
+    /// instrument listing and upcoming dividends to a new ticker
+    ///
+    /// Runs in a single database transaction, so a failure leaves every table
+    /// on the old ticker. Transactions, realized gains and closed positions
+    /// keep the ticker they were recorded with.
+    pub async fn rename_ticker(&self, ticker: &str, new_ticker: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        sqlx::query!(
+            r#"
+            UPDATE holdings SET ticker = $2, updated_at = NOW(), version = version + 1
+            WHERE ticker = $1 AND closed_at IS NULL AND deleted_at IS NULL
+            "#,
+            ticker,
+            new_ticker
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx::query!(
+            "UPDATE tax_lots SET ticker = $2 WHERE ticker = $1",
+            ticker,
+            new_ticker
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx::query!(
+            "UPDATE loan_collateral SET ticker = $2 WHERE ticker = $1",
+            ticker,
+            new_ticker
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx::query!(
+            r#"
+            UPDATE liquidity_profiles
+            SET ticker = $2
+            WHERE ticker = $1
+              AND NOT EXISTS (SELECT 1 FROM liquidity_profiles WHERE ticker = $2)
+            "#,
+            ticker,
+            new_ticker
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx::query!(
+            r#"
+            UPDATE instruments
+            SET ticker = $2, updated_at = NOW()
+            WHERE ticker = $1
+              AND NOT EXISTS (SELECT 1 FROM instruments WHERE ticker = $2)
+            "#,
+            ticker,
+            new_ticker
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx::query!(
+            "UPDATE dividends SET ticker = $2 WHERE ticker = $1 AND status = 'announced'",
+            ticker,
+            new_ticker
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx::query!(
+            "UPDATE corporate_actions SET ticker = $2 WHERE ticker = $1 AND status = 'pending'",
+            ticker,
+            new_ticker
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(())
+    }
+}
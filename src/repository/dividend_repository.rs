@@ -0,0 +1,259 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::dividend::{Dividend, DuePayment},
+};
+
+pub struct DividendRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> DividendRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        DividendRepository { pool }
+    }
+
+    pub async fn create_dividend(
+        &self,
+        ticker: &str,
+        ex_date: NaiveDate,
+        pay_date: NaiveDate,
+        amount_per_share: BigDecimal,
+    ) -> Result<Dividend> {
+        let dividend = sqlx::query_as!(
+            Dividend,
+            r#"
+            INSERT INTO dividends (ticker, ex_date, pay_date, amount_per_share)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, ticker, ex_date, pay_date, amount_per_share, status, created_at
+            "#,
+            ticker,
+            ex_date,
+            pay_date,
+            amount_per_share
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(dividend)
+    }
+
+    pub async fn get_dividends(&self) -> Result<Vec<Dividend>> {
+        let dividends = sqlx::query_as!(
+            Dividend,
+            r#"
+            SELECT id, ticker, ex_date, pay_date, amount_per_share, status, created_at
+            FROM dividends
+            ORDER BY ex_date DESC, id DESC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(dividends)
+    }
+
+    /// Delete a dividend whose holders have not been recorded yet, on the
+    /// calendar or in an accelerated competition
+    pub async fn delete_announced(&self, dividend_id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM dividends
+            WHERE id = $1 AND status = 'announced'
+              AND NOT EXISTS (SELECT 1 FROM competition_dividends WHERE dividend_id = $1)
+            "#,
+            dividend_id
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Capture the holders of every announced dividend whose ex-date has arrived
+    ///
+    /// Marking the dividend as recorded and inserting the entitlements happen in
+    /// one statement, so concurrent workers cannot record holders twice.
+    /// Portfolios of accelerated competitions are left to
+    /// [`DividendRepository::record_competition_holders`].
+    pub async fn record_holders(&self, today: NaiveDate) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            WITH recorded AS (
+                UPDATE dividends
+                SET status = 'recorded'
+                WHERE status = 'announced' AND ex_date <= $1
+                RETURNING id, ticker, amount_per_share
+            )
+            INSERT INTO dividend_payments (dividend_id, user_id, portfolio_id, quantity, amount)
+            SELECT recorded.id, holdings.user_id, holdings.portfolio_id, holdings.quantity,
+                   holdings.quantity * recorded.amount_per_share
+            FROM recorded
+            JOIN holdings ON holdings.ticker = recorded.ticker AND holdings.quantity > 0
+                AND holdings.deleted_at IS NULL
+            JOIN portfolios ON portfolios.id = holdings.portfolio_id
+            LEFT JOIN competitions ON competitions.id = portfolios.competition_id
+            WHERE competitions.clock_speed IS NULL OR competitions.clock_speed = 1
+            "#,
+            today
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Claim all unpaid payments of dividends whose pay date has arrived,
+    /// except those of accelerated competitions
+    pub async fn claim_due_payments(&self, today: NaiveDate) -> Result<Vec<DuePayment>> {
+        let payments = sqlx::query_as!(
+            DuePayment,
+            r#"
+            UPDATE dividend_payments
+            SET paid_at = NOW()
+            FROM dividends
+            WHERE dividend_payments.dividend_id = dividends.id
+              AND dividends.status = 'recorded'
+              AND dividends.pay_date <= $1
+              AND dividend_payments.paid_at IS NULL
+              AND NOT EXISTS (
+                  SELECT 1
+                  FROM portfolios
+                  JOIN competitions ON competitions.id = portfolios.competition_id
+                  WHERE portfolios.id = dividend_payments.portfolio_id
+                    AND competitions.clock_speed > 1
+              )
+            RETURNING dividend_payments.id, dividend_payments.user_id,
+                      dividend_payments.portfolio_id, dividends.ticker,
+                      dividend_payments.quantity, dividends.amount_per_share,
+                      dividend_payments.amount
+            "#,
+            today
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(payments)
+    }
+
+    /// Capture the holders in a competition's portfolios of every dividend
+    /// that went ex between the competition's start and its simulated `today`
+    ///
+    /// Each dividend is recorded once per competition, whatever its status
+    /// on the calendar.
+    pub async fn record_competition_holders(
+        &self,
+        competition_id: i32,
+        today: NaiveDate,
+    ) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            WITH recorded AS (
+                INSERT INTO competition_dividends (competition_id, dividend_id)
+                SELECT competitions.id, dividends.id
+                FROM competitions
+                JOIN dividends ON dividends.ex_date > competitions.starts_at::date
+                    AND dividends.ex_date <= $2
+                WHERE competitions.id = $1
+                ON CONFLICT DO NOTHING
+                RETURNING dividend_id
+            )
+            INSERT INTO dividend_payments (dividend_id, user_id, portfolio_id, quantity, amount)
+            SELECT dividends.id, holdings.user_id, holdings.portfolio_id, holdings.quantity,
+                   holdings.quantity * dividends.amount_per_share
+            FROM recorded
+            JOIN dividends ON dividends.id = recorded.dividend_id
+            JOIN holdings ON holdings.ticker = dividends.ticker AND holdings.quantity > 0
+                AND holdings.deleted_at IS NULL
+            JOIN portfolios ON portfolios.id = holdings.portfolio_id
+            WHERE portfolios.competition_id = $1
+            "#,
+            competition_id,
+            today
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Claim the unpaid payments in a competition's portfolios of dividends
+    /// whose pay date has arrived on its simulated `today`
+    pub async fn claim_competition_payments(
+        &self,
+        competition_id: i32,
+        today: NaiveDate,
+    ) -> Result<Vec<DuePayment>> {
+        let payments = sqlx::query_as!(
+            DuePayment,
+            r#"
+            UPDATE dividend_payments
+            SET paid_at = NOW()
+            FROM dividends, portfolios
+            WHERE dividend_payments.dividend_id = dividends.id
+              AND portfolios.id = dividend_payments.portfolio_id
+              AND portfolios.competition_id = $1
+              AND dividends.pay_date <= $2
+              AND dividend_payments.paid_at IS NULL
+            RETURNING dividend_payments.id, dividend_payments.user_id,
+                      dividend_payments.portfolio_id, dividends.ticker,
+                      dividend_payments.quantity, dividends.amount_per_share,
+                      dividend_payments.amount
+            "#,
+            competition_id,
+            today
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(payments)
+    }
+
+    pub async fn set_payment_transaction(
+        &self,
+        payment_id: i32,
+        transaction_id: i32,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE dividend_payments
+            SET transaction_id = $2
+            WHERE id = $1
+            "#,
+            payment_id,
+            transaction_id
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    /// Mark recorded dividends as paid once their pay date has arrived
+    pub async fn mark_paid(&self, today: NaiveDate) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE dividends
+            SET status = 'paid'
+            WHERE status = 'recorded' AND pay_date <= $1
+            "#,
+            today
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected())
+    }
+}
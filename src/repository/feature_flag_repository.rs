@@ -0,0 +1,76 @@
+use sqlx::PgPool;
+
+use crate::{Error, Result, models::feature_flag::FeatureFlag};
+
+pub struct FeatureFlagRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> FeatureFlagRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        FeatureFlagRepository { pool }
+    }
+
+    pub async fn get_flags(&self) -> Result<Vec<FeatureFlag>> {
+        let flags = sqlx::query_as!(
+            FeatureFlag,
+            r#"
+            SELECT name, description, enabled, rollout_percent, user_ids, created_at, updated_at
+            FROM feature_flags
+            ORDER BY name
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(flags)
+    }
+
+    pub async fn upsert_flag(
+        &self,
+        name: &str,
+        description: &str,
+        enabled: bool,
+        rollout_percent: i16,
+        user_ids: &[i32],
+    ) -> Result<FeatureFlag> {
+        let flag = sqlx::query_as!(
+            FeatureFlag,
+            r#"
+            INSERT INTO feature_flags (name, description, enabled, rollout_percent, user_ids)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (name)
+            DO UPDATE SET description = EXCLUDED.description, enabled = EXCLUDED.enabled,
+                          rollout_percent = EXCLUDED.rollout_percent,
+                          user_ids = EXCLUDED.user_ids, updated_at = NOW()
+            RETURNING name, description, enabled, rollout_percent, user_ids, created_at, updated_at
+            "#,
+            name,
+            description,
+            enabled,
+            rollout_percent,
+            user_ids
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(flag)
+    }
+
+    pub async fn delete_flag(&self, name: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM feature_flags
+            WHERE name = $1
+            "#,
+            name
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
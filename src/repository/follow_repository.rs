@@ -0,0 +1,171 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    Error, Result,
+    models::follow::{FeedTrade, FollowedUser},
+};
+
+pub struct FollowRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> FollowRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        FollowRepository { pool }
+    }
+
+    /// Returns false when the user already follows `followee_id`
+    pub async fn follow(&self, follower_id: i32, followee_id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO follows (follower_id, followee_id)
+            VALUES ($1, $2)
+            ON CONFLICT (follower_id, followee_id) DO NOTHING
+            "#,
+            follower_id,
+            followee_id
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Returns false when the user did not follow `followee_id`
+    pub async fn unfollow(&self, follower_id: i32, followee_id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM follows
+            WHERE follower_id = $1 AND followee_id = $2
+            "#,
+            follower_id,
+            followee_id
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn is_following(&self, follower_id: i32, followee_id: i32) -> Result<bool> {
+        let following = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM follows WHERE follower_id = $1 AND followee_id = $2
+            ) AS "following!"
+            "#,
+            follower_id,
+            followee_id
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(following)
+    }
+
+    /// Users following `user_id`, latest first
+    pub async fn get_followers(&self, user_id: i32) -> Result<Vec<FollowedUser>> {
+        let followers = sqlx::query_as!(
+            FollowedUser,
+            r#"
+            SELECT u.public_id AS user_id, s.display_name, f.created_at AS followed_at
+            FROM follows f
+            JOIN users u ON u.id = f.follower_id AND u.deleted_at IS NULL
+            LEFT JOIN user_settings s ON s.user_id = f.follower_id
+            WHERE f.followee_id = $1
+            ORDER BY f.created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(followers)
+    }
+
+    /// Users `user_id` follows, latest first
+    pub async fn get_following(&self, user_id: i32) -> Result<Vec<FollowedUser>> {
+        let following = sqlx::query_as!(
+            FollowedUser,
+            r#"
+            SELECT u.public_id AS user_id, s.display_name, f.created_at AS followed_at
+            FROM follows f
+            JOIN users u ON u.id = f.followee_id AND u.deleted_at IS NULL
+            LEFT JOIN user_settings s ON s.user_id = f.followee_id
+            WHERE f.follower_id = $1
+            ORDER BY f.created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(following)
+    }
+
+    /// Numbers of followers of `user_id` and of users it follows
+    pub async fn get_counts(&self, user_id: i32) -> Result<(i64, i64)> {
+        let counts = sqlx::query!(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM follows WHERE followee_id = $1) AS "followers!",
+                (SELECT COUNT(*) FROM follows WHERE follower_id = $1) AS "following!"
+            "#,
+            user_id
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok((counts.followers, counts.following))
+    }
+
+    /// Trades in the public portfolios of users `follower_id` follows, newest
+    /// first, each held back for its trader's delay
+    ///
+    /// `before` is the public ID of the transaction to continue after.
+    pub async fn get_feed(
+        &self,
+        follower_id: i32,
+        before: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<FeedTrade>> {
+        let trades = sqlx::query_as!(
+            FeedTrade,
+            r#"
+            SELECT t.public_id AS transaction_id, u.public_id AS user_id, s.display_name,
+                p.id AS portfolio_id,
+                p.name AS portfolio_name, t.ticker, t.transaction_type, t.quantity, t.price,
+                COALESCE(s.hide_trade_quantities, FALSE) AS "hide_quantity!",
+                t.created_at AS "created_at!"
+            FROM follows f
+            JOIN transactions t ON t.user_id = f.followee_id AND t.deleted_at IS NULL
+            JOIN portfolios p ON p.id = t.portfolio_id AND p.is_public
+            JOIN users u ON u.id = t.user_id AND u.deleted_at IS NULL
+            LEFT JOIN user_settings s ON s.user_id = t.user_id
+            WHERE f.follower_id = $1
+                AND t.transaction_type IN ('buy', 'sell')
+                -- Transaction times are stored without a zone, like LOCALTIMESTAMP
+                AND t.created_at <= LOCALTIMESTAMP
+                    - make_interval(mins => COALESCE(s.trade_delay_minutes, 15))
+                AND ($2::UUID IS NULL OR t.id < (SELECT id FROM transactions WHERE public_id = $2))
+            ORDER BY t.id DESC
+            LIMIT $3
+            "#,
+            follower_id,
+            before,
+            limit
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(trades)
+    }
+}
@@ -1,104 +1,247 @@
+use async_trait::async_trait;
 use bigdecimal::BigDecimal;
 use sqlx::PgPool;
+use uuid::Uuid;
 
-use crate::{Error, Result, models::holding::Holding};
+use crate::{
+    Error, Result,
+    models::holding::{ClosedPosition, Holding},
+};
 
-pub struct HoldingsRepository<'a> {
-    pool: &'a PgPool,
+/// Open and closed positions
+#[async_trait]
+pub trait HoldingsRepo: Send + Sync {
+    /// Open positions of the user outside competition and class portfolios
+    async fn get_holdings_by_user(&self, user_id: i32) -> Result<Vec<Holding>>;
+
+    /// Open positions of the portfolio
+    async fn get_holdings_by_portfolio(&self, portfolio_id: i32) -> Result<Vec<Holding>>;
+
+    /// Open positions of every user in a ticker
+    async fn get_holdings_by_ticker(&self, ticker: &str) -> Result<Vec<Holding>>;
+
+    /// Open position of the portfolio in a ticker
+    async fn get_holding_by_portfolio_and_ticker(
+        &self,
+        portfolio_id: i32,
+        ticker: &str,
+    ) -> Result<Option<Holding>>;
+
+    /// Open a position, returning `None` if another request opened it first
+    async fn create_holding(
+        &self,
+        user_id: i32,
+        portfolio_id: i32,
+        ticker: &str,
+        quantity: i32,
+        average_price: BigDecimal,
+    ) -> Result<Option<Holding>>;
+
+    /// Restate a position read at `version`, returning `None` if it has
+    /// changed since; a position left without shares is closed
+    async fn update_holding(
+        &self,
+        holding_id: i32,
+        version: i32,
+        quantity: i32,
+        average_price: BigDecimal,
+    ) -> Result<Option<Holding>>;
+
+    /// One page of the portfolio's closed positions with the gain realized
+    /// while each was open, newest first; `before` is the public ID of the
+    /// last position of the previous page
+    async fn get_closed_positions_page(
+        &self,
+        portfolio_id: i32,
+        before: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<ClosedPosition>>;
+}
+
+/// [`HoldingsRepo`] on Postgres
+pub struct HoldingsRepository {
+    pool: PgPool,
 }
 
-impl<'a> HoldingsRepository<'a> {
-    pub fn new(pool: &'a PgPool) -> Self {
-        HoldingsRepository { pool }
+impl HoldingsRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        HoldingsRepository { pool: pool.clone() }
     }
+}
 
-    pub async fn get_holdings_by_user(&self, user_id: i32) -> Result<Vec<Holding>> {
+#[async_trait]
+impl HoldingsRepo for HoldingsRepository {
+    async fn get_holdings_by_user(&self, user_id: i32) -> Result<Vec<Holding>> {
         let holdings = sqlx::query_as!(
             Holding,
             r#"
-            SELECT id, user_id, ticker, quantity, average_price
-            FROM holdings
-            WHERE user_id = $1 AND quantity > 0
+            SELECT h.id, h.public_id, h.user_id, h.portfolio_id, h.ticker, h.quantity, h.average_price,
+                h.created_at, h.updated_at, h.version
+            FROM holdings h
+            JOIN portfolios p ON p.id = h.portfolio_id
+            WHERE h.user_id = $1 AND h.closed_at IS NULL AND h.deleted_at IS NULL
+                AND p.competition_id IS NULL
+                AND p.class_id IS NULL
             "#,
             user_id
         )
-        .fetch_all(self.pool)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(holdings)
+    }
+
+    async fn get_holdings_by_portfolio(&self, portfolio_id: i32) -> Result<Vec<Holding>> {
+        let holdings = sqlx::query_as!(
+            Holding,
+            r#"
+            SELECT id, public_id, user_id, portfolio_id, ticker, quantity, average_price, created_at,
+                updated_at, version
+            FROM holdings
+            WHERE portfolio_id = $1 AND closed_at IS NULL AND deleted_at IS NULL
+            "#,
+            portfolio_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(holdings)
+    }
+
+    async fn get_holdings_by_ticker(&self, ticker: &str) -> Result<Vec<Holding>> {
+        let holdings = sqlx::query_as!(
+            Holding,
+            r#"
+            SELECT id, public_id, user_id, portfolio_id, ticker, quantity, average_price, created_at,
+                updated_at, version
+            FROM holdings
+            WHERE ticker = $1 AND closed_at IS NULL AND deleted_at IS NULL
+            "#,
+            ticker
+        )
+        .fetch_all(&self.pool)
         .await
         .map_err(Error::Database)?;
 
         Ok(holdings)
     }
 
-    pub async fn get_holding_by_user_and_ticker(
+    async fn get_holding_by_portfolio_and_ticker(
         &self,
-        user_id: i32,
+        portfolio_id: i32,
         ticker: &str,
     ) -> Result<Option<Holding>> {
         let holding = sqlx::query_as!(
             Holding,
             r#"
-            SELECT id, user_id, ticker, quantity, average_price
+            SELECT id, public_id, user_id, portfolio_id, ticker, quantity, average_price, created_at,
+                updated_at, version
             FROM holdings
-            WHERE user_id = $1 AND ticker = $2
+            WHERE portfolio_id = $1 AND ticker = $2 AND closed_at IS NULL AND deleted_at IS NULL
             "#,
-            user_id,
+            portfolio_id,
             ticker
         )
-        .fetch_optional(self.pool)
+        .fetch_optional(&self.pool)
         .await
         .map_err(Error::Database)?;
 
         Ok(holding)
     }
 
-    pub async fn create_holding(
+    async fn create_holding(
         &self,
         user_id: i32,
+        portfolio_id: i32,
         ticker: &str,
         quantity: i32,
         average_price: BigDecimal,
-    ) -> Result<Holding> {
+    ) -> Result<Option<Holding>> {
         let holding = sqlx::query_as!(
             Holding,
             r#"
-            INSERT INTO holdings (user_id, ticker, quantity, average_price)
-            VALUES ($1, $2, $3, $4)
-            RETURNING id, user_id, ticker, quantity, average_price
+            INSERT INTO holdings (user_id, portfolio_id, ticker, quantity, average_price)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (portfolio_id, ticker) WHERE closed_at IS NULL AND deleted_at IS NULL
+                DO NOTHING
+            RETURNING id, public_id, user_id, portfolio_id, ticker, quantity, average_price, created_at,
+                updated_at, version
             "#,
             user_id,
+            portfolio_id,
             ticker,
             quantity,
             average_price
         )
-        .fetch_one(self.pool)
+        .fetch_optional(&self.pool)
         .await
         .map_err(Error::Database)?;
 
         Ok(holding)
     }
 
-    pub async fn update_holding(
+    async fn update_holding(
         &self,
         holding_id: i32,
+        version: i32,
         quantity: i32,
         average_price: BigDecimal,
-    ) -> Result<Holding> {
+    ) -> Result<Option<Holding>> {
         let holding = sqlx::query_as!(
             Holding,
             r#"
             UPDATE holdings
-            SET quantity = $1, average_price = $2
-            WHERE id = $3
-            RETURNING id, user_id, ticker, quantity, average_price
+            SET quantity = $1, average_price = $2, updated_at = NOW(), version = version + 1,
+                closed_at = CASE WHEN $1 = 0 THEN NOW() END
+            WHERE id = $3 AND version = $4 AND closed_at IS NULL AND deleted_at IS NULL
+            RETURNING id, public_id, user_id, portfolio_id, ticker, quantity, average_price, created_at,
+                updated_at, version
             "#,
             quantity,
             average_price,
-            holding_id
+            holding_id,
+            version
         )
-        .fetch_one(self.pool)
+        .fetch_optional(&self.pool)
         .await
         .map_err(Error::Database)?;
 
         Ok(holding)
     }
+
+    async fn get_closed_positions_page(
+        &self,
+        portfolio_id: i32,
+        before: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<ClosedPosition>> {
+        let positions = sqlx::query_as!(
+            ClosedPosition,
+            r#"
+            SELECT h.public_id, h.ticker, h.created_at, h.closed_at AS "closed_at!",
+                COALESCE((
+                    SELECT SUM(rg.gain)
+                    FROM realized_gains rg
+                    JOIN transaction_history t ON t.id = rg.sell_transaction_id
+                    WHERE t.portfolio_id = h.portfolio_id AND rg.ticker = h.ticker
+                        AND rg.realized_at BETWEEN h.created_at AND h.closed_at
+                ), 0) AS "realized_gain!"
+            FROM holdings h
+            WHERE h.portfolio_id = $1 AND h.closed_at IS NOT NULL AND h.deleted_at IS NULL
+                AND ($2::uuid IS NULL OR h.id < (SELECT id FROM holdings WHERE public_id = $2))
+            ORDER BY h.id DESC
+            LIMIT $3
+            "#,
+            portfolio_id,
+            before,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(positions)
+    }
 }
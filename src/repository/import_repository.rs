@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use bigdecimal::Zero;
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::user_settings::CostBasisMethod,
+    repository::ledger_repository::posting_error,
+    services::transaction_import::{ImportedPosition, ImportedTrade},
+};
+
+pub struct ImportRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> ImportRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        ImportRepository { pool }
+    }
+
+    /// Book an imported trade history into a portfolio that never traded, in
+    /// a single database transaction
+    ///
+    /// The portfolio is locked first, so concurrent imports into it wait, and
+    /// nothing is booked when it already has transactions or holdings, in
+    /// which case false is returned. Each trade, in order, is recorded at its
+    /// own date with its cash postings: buys open a lot, sells take their
+    /// slices out of the lots opened by earlier buys and realize the gains
+    /// under `method`. The `positions` are then opened, and closed when sold
+    /// out. Either all of it is booked or, on any error, none of it is.
+    pub async fn import_history(
+        &self,
+        user_id: i32,
+        portfolio_id: i32,
+        method: CostBasisMethod,
+        trades: &[ImportedTrade<'_>],
+        positions: &[ImportedPosition],
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        sqlx::query!(
+            "SELECT id FROM portfolios WHERE id = $1 FOR UPDATE",
+            portfolio_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+        let traded = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM transaction_history WHERE portfolio_id = $1)
+                OR EXISTS (SELECT 1 FROM holdings WHERE portfolio_id = $1 AND deleted_at IS NULL)
+                AS "traded!"
+            "#,
+            portfolio_id
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+        if traded {
+            return Ok(false);
+        }
+
+        // Lots by the index of the trade that opened them
+        let mut lot_ids: HashMap<i32, i32> = HashMap::new();
+        for (index, trade) in trades.iter().enumerate() {
+            let row = trade.row;
+            let transaction_id = sqlx::query_scalar!(
+                r#"
+                INSERT INTO transactions (user_id, portfolio_id, ticker, quantity, price,
+                    transaction_type, fee, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING id
+                "#,
+                user_id,
+                portfolio_id,
+                row.ticker,
+                row.quantity,
+                row.price,
+                row.side,
+                row.fee,
+                row.at.naive_utc()
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+
+            let value = &row.price * row.quantity;
+            if row.side == "buy" {
+                let lot_id = sqlx::query_scalar!(
+                    r#"
+                    INSERT INTO tax_lots (user_id, portfolio_id, ticker, transaction_id, quantity,
+                                          remaining_quantity, price, acquired_at)
+                    VALUES ($1, $2, $3, $4, $5, $5, $6, $7)
+                    RETURNING id
+                    "#,
+                    user_id,
+                    portfolio_id,
+                    row.ticker,
+                    transaction_id,
+                    row.quantity,
+                    row.price,
+                    row.at
+                )
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(Error::Database)?;
+                lot_ids.insert(index as i32, lot_id);
+            }
+            for lot in &trade.realized {
+                let lot_id = lot.lot_id.map(|index| lot_ids[&index]);
+                if let Some(lot_id) = lot_id {
+                    sqlx::query!(
+                        r#"
+                        UPDATE tax_lots
+                        SET remaining_quantity = remaining_quantity - $1
+                        WHERE id = $2
+                        "#,
+                        lot.quantity,
+                        lot_id
+                    )
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(Error::Database)?;
+                }
+
+                sqlx::query!(
+                    r#"
+                    INSERT INTO realized_gains (user_id, ticker, sell_transaction_id, lot_id,
+                                                quantity, cost_basis, proceeds, gain,
+                                                cost_basis_method, acquired_at, realized_at)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                    "#,
+                    user_id,
+                    row.ticker,
+                    transaction_id,
+                    lot_id,
+                    lot.quantity,
+                    lot.cost_basis,
+                    lot.proceeds,
+                    lot.gain,
+                    method.as_str(),
+                    lot.acquired_at,
+                    row.at
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(Error::Database)?;
+            }
+
+            // The shares are bought or sold before the cash moves, as for market orders
+            sqlx::query!(
+                r#"
+                INSERT INTO ledger_entries (portfolio_id, entry_type, amount, transaction_id)
+                VALUES ($1, $2, $3, $4)
+                "#,
+                portfolio_id,
+                row.side,
+                if row.side == "buy" { -value } else { value },
+                transaction_id
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(posting_error)?;
+            if !row.fee.is_zero() {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO ledger_entries (portfolio_id, entry_type, amount, transaction_id)
+                    VALUES ($1, 'fee', $2, $3)
+                    "#,
+                    portfolio_id,
+                    -row.fee.clone(),
+                    transaction_id
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(posting_error)?;
+            }
+        }
+
+        for position in positions {
+            sqlx::query!(
+                r#"
+                INSERT INTO holdings (user_id, portfolio_id, ticker, quantity, average_price,
+                    created_at, closed_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+                user_id,
+                portfolio_id,
+                position.ticker,
+                position.quantity,
+                position.average_price,
+                position.opened_at,
+                position.closed_at
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+        }
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,218 @@
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::instrument::{Instrument, InstrumentDetails},
+};
+
+pub struct InstrumentRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> InstrumentRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        InstrumentRepository { pool }
+    }
+
+    pub async fn get_instruments(&self) -> Result<Vec<Instrument>> {
+        let instruments = sqlx::query_as!(
+            Instrument,
+            r#"
+            SELECT ticker, name, sector, asset_class, tick_size, lot_size, active,
+                   created_at, updated_at
+            FROM instruments
+            ORDER BY ticker
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(instruments)
+    }
+
+    /// Tickers of every listed instrument, active or not
+    /// Listed tickers the price feed streams, leaving out baskets priced
+    /// from their constituents
+    pub async fn get_feed_tickers(&self) -> Result<Vec<String>> {
+        let tickers = sqlx::query_scalar!(
+            r#"
+            SELECT ticker
+            FROM instruments
+            WHERE NOT EXISTS (
+                SELECT 1 FROM basket_constituents WHERE basket = instruments.ticker
+            )
+            ORDER BY ticker
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(tickers)
+    }
+
+    pub async fn get_tickers(&self) -> Result<Vec<String>> {
+        let tickers = sqlx::query_scalar!("SELECT ticker FROM instruments ORDER BY ticker")
+            .fetch_all(self.pool)
+            .await
+            .map_err(Error::Database)?;
+
+        Ok(tickers)
+    }
+
+    pub async fn get_instrument(&self, ticker: &str) -> Result<Option<Instrument>> {
+        let instrument = sqlx::query_as!(
+            Instrument,
+            r#"
+            SELECT ticker, name, sector, asset_class, tick_size, lot_size, active,
+                   created_at, updated_at
+            FROM instruments
+            WHERE ticker = $1
+            "#,
+            ticker
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(instrument)
+    }
+
+    /// Active instruments whose symbol or name matches `query`, best match first
+    ///
+    /// Exact symbols rank first, then symbol prefixes, name prefixes, names
+    /// with a word starting with the query, and finally fuzzy matches by
+    /// trigram similarity, which catch typos.
+    pub async fn search(&self, query: &str, limit: i64) -> Result<Vec<Instrument>> {
+        // Escape LIKE wildcards so they only match themselves
+        let pattern = query
+            .to_lowercase()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+
+        let instruments = sqlx::query_as!(
+            Instrument,
+            r#"
+            SELECT ticker, name, sector, asset_class, tick_size, lot_size, active,
+                   created_at, updated_at
+            FROM instruments
+            WHERE active
+              AND (
+                  LOWER(ticker) LIKE $1 || '%'
+                  OR LOWER(name) LIKE '%' || $1 || '%'
+                  OR similarity(LOWER(ticker), $2) > 0.3
+                  OR word_similarity($2, LOWER(name)) > 0.5
+              )
+            ORDER BY
+                CASE
+                    WHEN LOWER(ticker) = $2 THEN 0
+                    WHEN LOWER(ticker) LIKE $1 || '%' THEN 1
+                    WHEN LOWER(name) LIKE $1 || '%' THEN 2
+                    WHEN LOWER(name) LIKE '% ' || $1 || '%' THEN 3
+                    ELSE 4
+                END,
+                GREATEST(similarity(LOWER(ticker), $2), word_similarity($2, LOWER(name))) DESC,
+                ticker
+            LIMIT $3
+            "#,
+            pattern,
+            query.to_lowercase(),
+            limit
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(instruments)
+    }
+
+    /// List a new instrument; `None` when the ticker is already listed
+    pub async fn create_instrument(
+        &self,
+        ticker: &str,
+        details: &InstrumentDetails,
+    ) -> Result<Option<Instrument>> {
+        let instrument = sqlx::query_as!(
+            Instrument,
+            r#"
+            INSERT INTO instruments (ticker, name, sector, asset_class, tick_size, lot_size, active)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (ticker) DO NOTHING
+            RETURNING ticker, name, sector, asset_class, tick_size, lot_size, active,
+                      created_at, updated_at
+            "#,
+            ticker,
+            details.name,
+            details.sector,
+            details.asset_class,
+            details.tick_size,
+            details.lot_size,
+            details.active
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(instrument)
+    }
+
+    /// Replace the details of a listed instrument; `None` when it is not listed
+    pub async fn update_instrument(
+        &self,
+        ticker: &str,
+        details: &InstrumentDetails,
+    ) -> Result<Option<Instrument>> {
+        let instrument = sqlx::query_as!(
+            Instrument,
+            r#"
+            UPDATE instruments
+            SET name = $2, sector = $3, asset_class = $4, tick_size = $5, lot_size = $6,
+                active = $7, updated_at = NOW()
+            WHERE ticker = $1
+            RETURNING ticker, name, sector, asset_class, tick_size, lot_size, active,
+                      created_at, updated_at
+            "#,
+            ticker,
+            details.name,
+            details.sector,
+            details.asset_class,
+            details.tick_size,
+            details.lot_size,
+            details.active
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(instrument)
+    }
+
+    /// Remove an instrument nobody holds, directly or through options, and
+    /// no basket contains; returns whether it was deleted
+    pub async fn delete_unheld(&self, ticker: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM instruments
+            WHERE ticker = $1
+              AND NOT EXISTS (
+                  SELECT 1 FROM holdings WHERE ticker = $1 AND quantity > 0 AND deleted_at IS NULL
+              )
+              AND NOT EXISTS (
+                  SELECT 1
+                  FROM option_positions p
+                  JOIN option_contracts c ON c.id = p.contract_id
+                  WHERE c.underlying = $1
+              )
+              AND NOT EXISTS (SELECT 1 FROM basket_constituents WHERE ticker = $1)
+            "#,
+            ticker
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
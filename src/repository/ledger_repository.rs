@@ -0,0 +1,203 @@
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::{PgConnection, PgPool};
+
+use crate::{Error, Result, models::ledger_entry::LedgerEntry};
+
+/// Check constraint keeping the cash of portfolios from going negative
+const BALANCE_CONSTRAINT: &str = "portfolios_balance_non_negative";
+
+/// Cash moved by a trade: its `value` (negative for purchases) posted as an
+/// entry of `entry_type`, and its commission posted as a fee
+pub struct TradePosting<'a> {
+    pub entry_type: &'a str,
+    pub value: BigDecimal,
+    pub fee: BigDecimal,
+}
+
+/// Posts to the append-only cash ledger of portfolios
+///
+/// The database applies every entry to the portfolio's cash as it is posted
+/// and rejects any other change to the cash, so the balance of a portfolio is
+/// always the sum of its entries. Debits the cash does not cover are rejected
+/// as insufficient funds.
+pub struct LedgerRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> LedgerRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        LedgerRepository { pool }
+    }
+
+    /// Add `amount` (negative to debit) to the portfolio's cash as an entry
+    /// of `entry_type`, settling `transaction_id` when given
+    pub async fn post(
+        &self,
+        portfolio_id: i32,
+        entry_type: &str,
+        amount: BigDecimal,
+        transaction_id: Option<i32>,
+    ) -> Result<LedgerEntry> {
+        sqlx::query_as!(
+            LedgerEntry,
+            r#"
+            INSERT INTO ledger_entries (portfolio_id, entry_type, amount, transaction_id)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, portfolio_id, entry_type, amount, balance_after,
+                (SELECT public_id FROM transaction_history WHERE id = transaction_id) AS transaction_id,
+                counter_portfolio_id, created_at
+            "#,
+            portfolio_id,
+            entry_type,
+            amount,
+            transaction_id
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(posting_error)
+    }
+
+    /// Post the `value` of a trade (negative for purchases) and its commission
+    /// together, returning the cash left afterwards
+    pub async fn post_trade(
+        &self,
+        portfolio_id: i32,
+        entry_type: &str,
+        value: BigDecimal,
+        fee: BigDecimal,
+        transaction_id: Option<i32>,
+    ) -> Result<BigDecimal> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+        let posting = TradePosting {
+            entry_type,
+            value,
+            fee,
+        };
+        let balance = post_trade_entries(&mut tx, portfolio_id, posting, transaction_id).await?;
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(balance)
+    }
+
+    /// Move cash between two portfolios, returning false if the source holds too little
+    pub async fn transfer(&self, from_id: i32, to_id: i32, amount: BigDecimal) -> Result<bool> {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        // Locks the source so concurrent debits cannot overdraw it
+        let balance = sqlx::query_scalar!(
+            "SELECT balance FROM portfolios WHERE id = $1 FOR UPDATE",
+            from_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+        if !balance.is_some_and(|balance| balance >= amount) {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO ledger_entries (portfolio_id, entry_type, amount, counter_portfolio_id)
+            VALUES ($1, 'transfer', -$3::numeric, $2), ($2, 'transfer', $3, $1)
+            "#,
+            from_id,
+            to_id,
+            amount
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(posting_error)?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(true)
+    }
+
+    /// One page of the portfolio's entries, newest first; `before` is the id
+    /// of the last entry of the previous page
+    pub async fn get_entries_page(
+        &self,
+        portfolio_id: i32,
+        before: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<LedgerEntry>> {
+        sqlx::query_as!(
+            LedgerEntry,
+            r#"
+            SELECT l.id, l.portfolio_id, l.entry_type, l.amount, l.balance_after,
+                t.public_id AS "transaction_id?", l.counter_portfolio_id, l.created_at
+            FROM ledger_entries l
+            LEFT JOIN transaction_history t ON t.id = l.transaction_id
+            WHERE l.portfolio_id = $1 AND ($2::bigint IS NULL OR l.id < $2)
+            ORDER BY l.id DESC
+            LIMIT $3
+            "#,
+            portfolio_id,
+            before,
+            limit
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+}
+
+/// Post the entries of a trade as part of an open database transaction,
+/// returning the cash left afterwards
+pub async fn post_trade_entries(
+    conn: &mut PgConnection,
+    portfolio_id: i32,
+    posting: TradePosting<'_>,
+    transaction_id: Option<i32>,
+) -> Result<BigDecimal> {
+    let mut balance = sqlx::query_scalar!(
+        r#"
+        INSERT INTO ledger_entries (portfolio_id, entry_type, amount, transaction_id)
+        VALUES ($1, $2, $3, $4)
+        RETURNING balance_after
+        "#,
+        portfolio_id,
+        posting.entry_type,
+        posting.value,
+        transaction_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(posting_error)?;
+
+    if !posting.fee.is_zero() {
+        balance = sqlx::query_scalar!(
+            r#"
+            INSERT INTO ledger_entries (portfolio_id, entry_type, amount, transaction_id)
+            VALUES ($1, 'fee', $2, $3)
+            RETURNING balance_after
+            "#,
+            portfolio_id,
+            -posting.fee,
+            transaction_id
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(posting_error)?;
+    }
+
+    Ok(balance)
+}
+
+/// Error returned when a debit exceeds the cash of the portfolio
+pub fn insufficient_funds() -> Error {
+    Error::BadRequest("Insufficient funds".into())
+}
+
+/// Report entries rejected by the balance check as insufficient funds
+pub fn posting_error(e: sqlx::Error) -> Error {
+    let overdraft = e
+        .as_database_error()
+        .and_then(|e| e.constraint())
+        .is_some_and(|constraint| constraint == BALANCE_CONSTRAINT);
+    if overdraft {
+        insufficient_funds()
+    } else {
+        Error::Database(e)
+    }
+}
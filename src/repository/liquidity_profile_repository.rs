@@ -0,0 +1,90 @@
+use sqlx::PgPool;
+
+use crate::{Error, Result, models::liquidity_profile::LiquidityProfile};
+
+pub struct LiquidityProfileRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> LiquidityProfileRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        LiquidityProfileRepository { pool }
+    }
+
+    pub async fn get_profiles(&self) -> Result<Vec<LiquidityProfile>> {
+        let profiles = sqlx::query_as!(
+            LiquidityProfile,
+            r#"
+            SELECT ticker, depth, spread_bps, resilience, updated_at
+            FROM liquidity_profiles
+            ORDER BY ticker
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(profiles)
+    }
+
+    pub async fn get_profile(&self, ticker: &str) -> Result<Option<LiquidityProfile>> {
+        let profile = sqlx::query_as!(
+            LiquidityProfile,
+            r#"
+            SELECT ticker, depth, spread_bps, resilience, updated_at
+            FROM liquidity_profiles
+            WHERE ticker = $1
+            "#,
+            ticker
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(profile)
+    }
+
+    pub async fn upsert_profile(
+        &self,
+        ticker: &str,
+        depth: i32,
+        spread_bps: f64,
+        resilience: f64,
+    ) -> Result<LiquidityProfile> {
+        let profile = sqlx::query_as!(
+            LiquidityProfile,
+            r#"
+            INSERT INTO liquidity_profiles (ticker, depth, spread_bps, resilience)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (ticker)
+            DO UPDATE SET depth = EXCLUDED.depth, spread_bps = EXCLUDED.spread_bps,
+                          resilience = EXCLUDED.resilience, updated_at = NOW()
+            RETURNING ticker, depth, spread_bps, resilience, updated_at
+            "#,
+            ticker,
+            depth,
+            spread_bps,
+            resilience
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(profile)
+    }
+
+    pub async fn delete_profile(&self, ticker: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM liquidity_profiles
+            WHERE ticker = $1
+            "#,
+            ticker
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,269 @@
+use bigdecimal::BigDecimal;
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::loan::{Loan, LoanCollateral},
+};
+
+pub struct LoanRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> LoanRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        LoanRepository { pool }
+    }
+
+    pub async fn create_loan(
+        &self,
+        user_id: i32,
+        portfolio_id: i32,
+        principal: BigDecimal,
+        interest_rate_percent: f64,
+    ) -> Result<Loan> {
+        let loan = sqlx::query_as!(
+            Loan,
+            r#"
+            INSERT INTO loans (user_id, portfolio_id, principal, outstanding, interest_rate_percent)
+            VALUES ($1, $2, $3, $3, $4)
+            RETURNING id, user_id, portfolio_id, principal, outstanding, interest_rate_percent, status,
+                      created_at, closed_at
+            "#,
+            user_id,
+            portfolio_id,
+            principal,
+            interest_rate_percent
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(loan)
+    }
+
+    pub async fn add_collateral(&self, loan_id: i32, ticker: &str, quantity: i32) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO loan_collateral (loan_id, ticker, quantity)
+            VALUES ($1, $2, $3)
+            "#,
+            loan_id,
+            ticker,
+            quantity
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    pub async fn get_loan(&self, loan_id: i32) -> Result<Option<Loan>> {
+        let loan = sqlx::query_as!(
+            Loan,
+            r#"
+            SELECT id, user_id, portfolio_id, principal, outstanding, interest_rate_percent, status,
+                   created_at, closed_at
+            FROM loans
+            WHERE id = $1
+            "#,
+            loan_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(loan)
+    }
+
+    /// All loans of a portfolio, newest first
+    pub async fn get_loans_by_portfolio(&self, portfolio_id: i32) -> Result<Vec<Loan>> {
+        let loans = sqlx::query_as!(
+            Loan,
+            r#"
+            SELECT id, user_id, portfolio_id, principal, outstanding, interest_rate_percent, status,
+                   created_at, closed_at
+            FROM loans
+            WHERE portfolio_id = $1
+            ORDER BY created_at DESC, id DESC
+            "#,
+            portfolio_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(loans)
+    }
+
+    pub async fn get_open_loans(&self) -> Result<Vec<Loan>> {
+        let loans = sqlx::query_as!(
+            Loan,
+            r#"
+            SELECT id, user_id, portfolio_id, principal, outstanding, interest_rate_percent, status,
+                   created_at, closed_at
+            FROM loans
+            WHERE status = 'open'
+            ORDER BY id
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(loans)
+    }
+
+    /// Total amount owed on the user's open loans
+    pub async fn get_outstanding_total(&self, user_id: i32) -> Result<BigDecimal> {
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(outstanding), 0) AS "total!"
+            FROM loans
+            WHERE user_id = $1 AND status = 'open'
+            "#,
+            user_id
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(total)
+    }
+
+    /// Total amount owed on a portfolio's open loans
+    pub async fn get_portfolio_outstanding_total(&self, portfolio_id: i32) -> Result<BigDecimal> {
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(outstanding), 0) AS "total!"
+            FROM loans
+            WHERE portfolio_id = $1 AND status = 'open'
+            "#,
+            portfolio_id
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(total)
+    }
+
+    /// Collateral of the given loans, in the order it was pledged
+    pub async fn get_collateral(&self, loan_ids: &[i32]) -> Result<Vec<LoanCollateral>> {
+        let collateral = sqlx::query_as!(
+            LoanCollateral,
+            r#"
+            SELECT loan_id, ticker, quantity
+            FROM loan_collateral
+            WHERE loan_id = ANY($1)
+            ORDER BY loan_id, id
+            "#,
+            loan_ids
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(collateral)
+    }
+
+    /// Shares of `ticker` in a portfolio pledged to open loans
+    pub async fn get_pledged_quantity(&self, portfolio_id: i32, ticker: &str) -> Result<i32> {
+        let pledged = sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(loan_collateral.quantity), 0)::INT AS "pledged!"
+            FROM loan_collateral
+            JOIN loans ON loans.id = loan_collateral.loan_id
+            WHERE loans.portfolio_id = $1 AND loans.status = 'open' AND loan_collateral.ticker = $2
+            "#,
+            portfolio_id,
+            ticker
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(pledged)
+    }
+
+    pub async fn reduce_collateral(&self, loan_id: i32, ticker: &str, quantity: i32) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE loan_collateral
+            SET quantity = quantity - $3
+            WHERE loan_id = $1 AND ticker = $2
+            "#,
+            loan_id,
+            ticker,
+            quantity
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    /// Add interest accrued on open loans since the last accrual
+    pub async fn accrue_interest(&self) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE loans
+            SET outstanding = outstanding * (1 + interest_rate_percent::NUMERIC / 100
+                    * EXTRACT(EPOCH FROM NOW() - interest_accrued_at)::NUMERIC / 31536000),
+                interest_accrued_at = NOW()
+            WHERE status = 'open'
+            "#
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Pay `amount` off an open loan, closing it as repaid once nothing is owed
+    ///
+    /// Returns `None` when the loan is not open or owes less than `amount`.
+    pub async fn repay(&self, loan_id: i32, amount: BigDecimal) -> Result<Option<Loan>> {
+        let loan = sqlx::query_as!(
+            Loan,
+            r#"
+            UPDATE loans
+            SET outstanding = outstanding - $2,
+                status = CASE WHEN outstanding - $2 = 0 THEN 'repaid' ELSE status END,
+                closed_at = CASE WHEN outstanding - $2 = 0 THEN NOW() ELSE closed_at END
+            WHERE id = $1 AND status = 'open' AND outstanding >= $2
+            RETURNING id, user_id, portfolio_id, principal, outstanding, interest_rate_percent, status,
+                      created_at, closed_at
+            "#,
+            loan_id,
+            amount
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(loan)
+    }
+
+    /// Close a loan whose collateral has been sold off entirely
+    ///
+    /// Any amount still outstanding is kept on record as the shortfall.
+    pub async fn close_liquidated(&self, loan_id: i32) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE loans
+            SET status = 'liquidated', closed_at = NOW()
+            WHERE id = $1 AND status = 'open'
+            "#,
+            loan_id
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+}
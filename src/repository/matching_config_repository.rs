@@ -0,0 +1,59 @@
+use sqlx::PgPool;
+
+use crate::{Error, Result, models::matching_config::MatchingConfig};
+
+pub struct MatchingConfigRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> MatchingConfigRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        MatchingConfigRepository { pool }
+    }
+
+    pub async fn get_config(&self) -> Result<MatchingConfig> {
+        let config = sqlx::query_as!(
+            MatchingConfig,
+            r#"
+            SELECT price_band_percent, max_order_quantity, volatility_halt_percent,
+                   halt_duration_secs, updated_at
+            FROM matching_config
+            WHERE id = 1
+            "#
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(config)
+    }
+
+    pub async fn update_config(
+        &self,
+        price_band_percent: f64,
+        max_order_quantity: i32,
+        volatility_halt_percent: f64,
+        halt_duration_secs: i32,
+    ) -> Result<MatchingConfig> {
+        let config = sqlx::query_as!(
+            MatchingConfig,
+            r#"
+            UPDATE matching_config
+            SET price_band_percent = $1, max_order_quantity = $2,
+                volatility_halt_percent = $3, halt_duration_secs = $4, updated_at = NOW()
+            WHERE id = 1
+            RETURNING price_band_percent, max_order_quantity, volatility_halt_percent,
+                      halt_duration_secs, updated_at
+            "#,
+            price_band_percent,
+            max_order_quantity,
+            volatility_halt_percent,
+            halt_duration_secs
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(config)
+    }
+}
@@ -0,0 +1,674 @@
+//! # In-Memory Repositories
+//!
+//! Stand-ins for the Postgres repositories behind [`Repositories`], so
+//! handlers and services can be tested without a database. Each keeps its
+//! rows in a `Vec` behind a mutex and follows the contract of the trait it
+//! implements: versions guard updates, at most one position per portfolio and
+//! ticker is open, and transactions page by ID like the SQL they replace.
+//!
+//! Only what these tables hold themselves is modelled. Every portfolio counts
+//! as a regular one, closed positions realize no gain since realized gains
+//! live elsewhere, and backdating moves only the transaction.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    Error, Result,
+    models::{
+        holding::{ClosedPosition, Holding},
+        transaction::{TradedVolume, Transaction},
+        user::User,
+    },
+    repository::{
+        Repositories,
+        holdings_repository::HoldingsRepo,
+        transaction_repository::{TransactionFilter, TransactionRepo},
+        user_repository::UserRepo,
+    },
+};
+
+impl Repositories {
+    /// Empty in-memory repositories
+    pub fn in_memory() -> Self {
+        Repositories {
+            users: Arc::new(MemoryUsers::default()),
+            holdings: Arc::new(MemoryHoldings::default()),
+            transactions: Arc::new(MemoryTransactions::default()),
+        }
+    }
+}
+
+/// [`UserRepo`] in memory
+#[derive(Default)]
+pub struct MemoryUsers {
+    users: Mutex<Vec<User>>,
+}
+
+impl MemoryUsers {
+    /// Apply `change` to the user read at `version`, bumping the version
+    fn update(
+        &self,
+        user_id: i32,
+        version: Option<i32>,
+        change: impl FnOnce(&mut User),
+    ) -> Option<User> {
+        let mut users = self.users.lock().unwrap();
+        let user = users
+            .iter_mut()
+            .find(|user| user.id == user_id && version.map_or(true, |v| v == user.version))?;
+        change(user);
+        user.updated_at = Utc::now();
+        user.version += 1;
+        Some(user.clone())
+    }
+}
+
+#[async_trait]
+impl UserRepo for MemoryUsers {
+    async fn create_user(&self, email: &str, password: &str) -> Result<User> {
+        let mut users = self.users.lock().unwrap();
+        if users.iter().any(|user| user.email == email) {
+            return Err(Error::Conflict("Email already exists".into()));
+        }
+
+        let now = Utc::now();
+        let user = User {
+            id: users.last().map_or(1, |user| user.id + 1),
+            public_id: Uuid::new_v4(),
+            email: email.to_string(),
+            password: password.to_string(),
+            role: "user".to_string(),
+            created_at: now,
+            updated_at: now,
+            version: 1,
+        };
+        users.push(user.clone());
+
+        Ok(user)
+    }
+
+    // Last logins are not kept
+    async fn record_login(&self, _user_id: i32) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<User>> {
+        let users = self.users.lock().unwrap();
+        Ok(users.iter().find(|user| user.email == email).cloned())
+    }
+
+    async fn get_user_by_id(&self, user_id: i32) -> Result<Option<User>> {
+        let users = self.users.lock().unwrap();
+        Ok(users.iter().find(|user| user.id == user_id).cloned())
+    }
+
+    async fn get_user_by_public_id(&self, public_id: Uuid) -> Result<Option<User>> {
+        let users = self.users.lock().unwrap();
+        Ok(users
+            .iter()
+            .find(|user| user.public_id == public_id)
+            .cloned())
+    }
+
+    async fn get_public_ids(&self, user_ids: &[i32]) -> Result<HashMap<i32, Uuid>> {
+        let users = self.users.lock().unwrap();
+        Ok(users
+            .iter()
+            .filter(|user| user_ids.contains(&user.id))
+            .map(|user| (user.id, user.public_id))
+            .collect())
+    }
+
+    async fn get_ids_by_public_ids(&self, public_ids: &[Uuid]) -> Result<Vec<i32>> {
+        let users = self.users.lock().unwrap();
+        Ok(users
+            .iter()
+            .filter(|user| public_ids.contains(&user.public_id))
+            .map(|user| user.id)
+            .collect())
+    }
+
+    async fn set_role(&self, user_id: i32, role: &str) -> Result<Option<User>> {
+        Ok(self.update(user_id, None, |user| user.role = role.to_string()))
+    }
+
+    async fn set_password(
+        &self,
+        user_id: i32,
+        version: i32,
+        password: &str,
+    ) -> Result<Option<User>> {
+        Ok(self.update(user_id, Some(version), |user| {
+            user.password = password.to_string()
+        }))
+    }
+
+    async fn set_email(&self, user_id: i32, version: i32, email: &str) -> Result<Option<User>> {
+        Ok(self.update(user_id, Some(version), |user| {
+            user.email = email.to_string()
+        }))
+    }
+
+    async fn get_user_ids(&self) -> Result<Vec<i32>> {
+        let users = self.users.lock().unwrap();
+        Ok(users.iter().map(|user| user.id).collect())
+    }
+
+    async fn count_users(&self) -> Result<i64> {
+        Ok(self.users.lock().unwrap().len() as i64)
+    }
+}
+
+/// A position with the time it was closed, if it was
+struct StoredHolding {
+    holding: Holding,
+    closed_at: Option<DateTime<Utc>>,
+}
+
+/// [`HoldingsRepo`] in memory
+#[derive(Default)]
+pub struct MemoryHoldings {
+    holdings: Mutex<Vec<StoredHolding>>,
+}
+
+impl MemoryHoldings {
+    /// Open positions matching `filter`
+    fn open(&self, filter: impl Fn(&Holding) -> bool) -> Vec<Holding> {
+        let holdings = self.holdings.lock().unwrap();
+        holdings
+            .iter()
+            .filter(|stored| stored.closed_at.is_none() && filter(&stored.holding))
+            .map(|stored| stored.holding.clone())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl HoldingsRepo for MemoryHoldings {
+    async fn get_holdings_by_user(&self, user_id: i32) -> Result<Vec<Holding>> {
+        Ok(self.open(|holding| holding.user_id == user_id))
+    }
+
+    async fn get_holdings_by_portfolio(&self, portfolio_id: i32) -> Result<Vec<Holding>> {
+        Ok(self.open(|holding| holding.portfolio_id == portfolio_id))
+    }
+
+    async fn get_holdings_by_ticker(&self, ticker: &str) -> Result<Vec<Holding>> {
+        Ok(self.open(|holding| holding.ticker == ticker))
+    }
+
+    async fn get_holding_by_portfolio_and_ticker(
+        &self,
+        portfolio_id: i32,
+        ticker: &str,
+    ) -> Result<Option<Holding>> {
+        Ok(self
+            .open(|holding| holding.portfolio_id == portfolio_id && holding.ticker == ticker)
+            .pop())
+    }
+
+    async fn create_holding(
+        &self,
+        user_id: i32,
+        portfolio_id: i32,
+        ticker: &str,
+        quantity: i32,
+        average_price: BigDecimal,
+    ) -> Result<Option<Holding>> {
+        let mut holdings = self.holdings.lock().unwrap();
+        let open = holdings.iter().any(|stored| {
+            stored.closed_at.is_none()
+                && stored.holding.portfolio_id == portfolio_id
+                && stored.holding.ticker == ticker
+        });
+        if open {
+            return Ok(None);
+        }
+
+        let now = Utc::now();
+        let holding = Holding {
+            id: holdings.last().map_or(1, |stored| stored.holding.id + 1),
+            public_id: Uuid::new_v4(),
+            user_id,
+            portfolio_id,
+            ticker: ticker.to_string(),
+            quantity,
+            average_price,
+            created_at: now,
+            updated_at: now,
+            version: 1,
+        };
+        holdings.push(StoredHolding {
+            holding: holding.clone(),
+            closed_at: None,
+        });
+
+        Ok(Some(holding))
+    }
+
+    async fn update_holding(
+        &self,
+        holding_id: i32,
+        version: i32,
+        quantity: i32,
+        average_price: BigDecimal,
+    ) -> Result<Option<Holding>> {
+        let mut holdings = self.holdings.lock().unwrap();
+        let Some(stored) = holdings.iter_mut().find(|stored| {
+            stored.holding.id == holding_id
+                && stored.holding.version == version
+                && stored.closed_at.is_none()
+        }) else {
+            return Ok(None);
+        };
+
+        let now = Utc::now();
+        stored.holding.quantity = quantity;
+        stored.holding.average_price = average_price;
+        stored.holding.updated_at = now;
+        stored.holding.version += 1;
+        if quantity == 0 {
+            stored.closed_at = Some(now);
+        }
+
+        Ok(Some(stored.holding.clone()))
+    }
+
+    async fn get_closed_positions_page(
+        &self,
+        portfolio_id: i32,
+        before: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<ClosedPosition>> {
+        let holdings = self.holdings.lock().unwrap();
+        let before = match before {
+            Some(public_id) => match holdings.iter().find(|s| s.holding.public_id == public_id) {
+                Some(stored) => Some(stored.holding.id),
+                None => return Ok(Vec::new()),
+            },
+            None => None,
+        };
+
+        Ok(holdings
+            .iter()
+            .rev()
+            .filter(|stored| stored.holding.portfolio_id == portfolio_id)
+            .filter(|stored| before.map_or(true, |id| stored.holding.id < id))
+            .filter_map(|stored| {
+                Some(ClosedPosition {
+                    public_id: stored.holding.public_id,
+                    ticker: stored.holding.ticker.clone(),
+                    created_at: stored.holding.created_at,
+                    closed_at: stored.closed_at?,
+                    realized_gain: BigDecimal::from(0),
+                })
+            })
+            .take(limit as usize)
+            .collect())
+    }
+}
+
+/// A transaction with the portfolio it belongs to
+struct StoredTransaction {
+    portfolio_id: i32,
+    transaction: Transaction,
+}
+
+#[derive(Default)]
+struct TransactionTables {
+    hot: Vec<StoredTransaction>,
+    archive: Vec<StoredTransaction>,
+    next_id: i32,
+}
+
+/// [`TransactionRepo`] in memory, with the archive as a second list
+#[derive(Default)]
+pub struct MemoryTransactions {
+    tables: Mutex<TransactionTables>,
+}
+
+/// One page of `table` like `TransactionRepository::get_transactions_page`
+fn page(
+    table: &[StoredTransaction],
+    portfolio_id: i32,
+    filter: &TransactionFilter,
+    after: Option<Uuid>,
+    newest_first: bool,
+    limit: i64,
+) -> Vec<Transaction> {
+    let after = match after {
+        Some(public_id) => match table
+            .iter()
+            .find(|stored| stored.transaction.public_id == public_id)
+        {
+            Some(stored) => Some(stored.transaction.id),
+            // Like the SQL, a cursor naming no transaction of the table matches nothing
+            None => return Vec::new(),
+        },
+        None => None,
+    };
+
+    let mut matching: Vec<&Transaction> = table
+        .iter()
+        .filter(|stored| stored.portfolio_id == portfolio_id)
+        .map(|stored| &stored.transaction)
+        .filter(|tx| matches_filter(tx, filter))
+        .filter(|tx| match after {
+            Some(id) if newest_first => tx.id < id,
+            Some(id) => tx.id > id,
+            None => true,
+        })
+        .collect();
+    matching.sort_by_key(|tx| tx.id);
+    if newest_first {
+        matching.reverse();
+    }
+
+    matching.into_iter().take(limit as usize).cloned().collect()
+}
+
+fn matches_filter(tx: &Transaction, filter: &TransactionFilter) -> bool {
+    let day = tx.created_at.date();
+    filter.ticker.as_ref().map_or(true, |t| *t == tx.ticker)
+        && filter
+            .transaction_type
+            .as_ref()
+            .map_or(true, |t| *t == tx.transaction_type)
+        && filter.from.map_or(true, |from| day >= from)
+        && filter.to.map_or(true, |to| day <= to)
+        && filter
+            .min_price
+            .as_ref()
+            .map_or(true, |min| tx.price >= *min)
+        && filter
+            .max_price
+            .as_ref()
+            .map_or(true, |max| tx.price <= *max)
+}
+
+#[async_trait]
+impl TransactionRepo for MemoryTransactions {
+    async fn create_transaction(
+        &self,
+        user_id: i32,
+        portfolio_id: i32,
+        ticker: &str,
+        quantity: i32,
+        price: BigDecimal,
+        transaction_type: &str,
+        fee: BigDecimal,
+    ) -> Result<Transaction> {
+        let mut tables = self.tables.lock().unwrap();
+        tables.next_id += 1;
+
+        let now = Utc::now().naive_utc();
+        let transaction = Transaction {
+            id: tables.next_id,
+            public_id: Uuid::new_v4(),
+            user_id,
+            ticker: ticker.to_string(),
+            quantity,
+            price,
+            fee,
+            transaction_type: transaction_type.to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        tables.hot.push(StoredTransaction {
+            portfolio_id,
+            transaction: transaction.clone(),
+        });
+
+        Ok(transaction)
+    }
+
+    async fn get_transactions_page(
+        &self,
+        portfolio_id: i32,
+        filter: &TransactionFilter,
+        after: Option<Uuid>,
+        newest_first: bool,
+        limit: i64,
+    ) -> Result<Vec<Transaction>> {
+        let tables = self.tables.lock().unwrap();
+        Ok(page(
+            &tables.hot,
+            portfolio_id,
+            filter,
+            after,
+            newest_first,
+            limit,
+        ))
+    }
+
+    async fn get_archived_page(
+        &self,
+        portfolio_id: i32,
+        filter: &TransactionFilter,
+        after: Option<Uuid>,
+        newest_first: bool,
+        limit: i64,
+    ) -> Result<Vec<Transaction>> {
+        let tables = self.tables.lock().unwrap();
+        Ok(page(
+            &tables.archive,
+            portfolio_id,
+            filter,
+            after,
+            newest_first,
+            limit,
+        ))
+    }
+
+    async fn archive_before(&self, cutoff: NaiveDateTime, batch: i32) -> Result<i32> {
+        let mut tables = self.tables.lock().unwrap();
+        let mut moved = 0;
+        while moved < batch {
+            let Some(index) = tables
+                .hot
+                .iter()
+                .position(|stored| stored.transaction.created_at < cutoff)
+            else {
+                break;
+            };
+            let stored = tables.hot.remove(index);
+            tables.archive.push(stored);
+            moved += 1;
+        }
+
+        Ok(moved)
+    }
+
+    async fn get_most_traded(&self, since: NaiveDateTime, limit: i64) -> Result<Vec<TradedVolume>> {
+        let tables = self.tables.lock().unwrap();
+        let mut volumes: HashMap<&str, TradedVolume> = HashMap::new();
+        for tx in tables.hot.iter().map(|stored| &stored.transaction) {
+            if !matches!(tx.transaction_type.as_str(), "buy" | "sell") || tx.created_at < since {
+                continue;
+            }
+            let volume = volumes.entry(&tx.ticker).or_insert_with(|| TradedVolume {
+                ticker: tx.ticker.clone(),
+                volume: 0,
+                trades: 0,
+            });
+            volume.volume += i64::from(tx.quantity);
+            volume.trades += 1;
+        }
+
+        let mut volumes: Vec<TradedVolume> = volumes.into_values().collect();
+        volumes.sort_by(|a, b| {
+            b.volume
+                .cmp(&a.volume)
+                .then_with(|| a.ticker.cmp(&b.ticker))
+        });
+        volumes.truncate(limit as usize);
+
+        Ok(volumes)
+    }
+
+    async fn get_transaction_by_public_id(&self, public_id: Uuid) -> Result<Option<Transaction>> {
+        let tables = self.tables.lock().unwrap();
+        Ok(tables
+            .hot
+            .iter()
+            .chain(&tables.archive)
+            .find(|stored| stored.transaction.public_id == public_id)
+            .map(|stored| stored.transaction.clone()))
+    }
+
+    async fn delete_transaction(&self, transaction_id: i32) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+        tables
+            .hot
+            .retain(|stored| stored.transaction.id != transaction_id);
+
+        Ok(())
+    }
+
+    async fn backdate(&self, transaction_id: i32, at: DateTime<Utc>) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+        if let Some(stored) = tables
+            .hot
+            .iter_mut()
+            .find(|stored| stored.transaction.id == transaction_id)
+        {
+            stored.transaction.created_at = at.naive_utc();
+            stored.transaction.updated_at = Utc::now().naive_utc();
+        }
+
+        Ok(())
+    }
+}
+
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn stale_versions_do_not_update_users() {
+        let users = MemoryUsers::default();
+        let user = users.create_user("a@example.com", "hash").await.unwrap();
+
+        let changed = users
+            .set_email(user.id, user.version, "b@example.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(changed.version, user.version + 1);
+        let stale = users
+            .set_password(user.id, user.version, "other")
+            .await
+            .unwrap();
+        assert!(stale.is_none());
+        assert!(matches!(
+            users.create_user("b@example.com", "hash").await,
+            Err(Error::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn emptied_positions_close_and_reopen() {
+        let holdings = MemoryHoldings::default();
+        let opened = holdings
+            .create_holding(1, 7, "AAPL", 5, BigDecimal::from(10))
+            .await
+            .unwrap()
+            .unwrap();
+        let again = holdings
+            .create_holding(1, 7, "AAPL", 1, BigDecimal::from(11))
+            .await
+            .unwrap();
+        assert!(again.is_none());
+
+        holdings
+            .update_holding(opened.id, opened.version, 0, BigDecimal::from(10))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(
+            holdings
+                .get_holdings_by_portfolio(7)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let closed = holdings
+            .get_closed_positions_page(7, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].public_id, opened.public_id);
+
+        let reopened = holdings
+            .create_holding(1, 7, "AAPL", 2, BigDecimal::from(12))
+            .await
+            .unwrap();
+        assert!(reopened.is_some());
+    }
+
+    #[tokio::test]
+    async fn transactions_page_by_id_and_archive() {
+        let transactions = MemoryTransactions::default();
+        let mut created = Vec::new();
+        for (ticker, price) in [("AAPL", 10), ("MSFT", 20), ("AAPL", 30), ("AAPL", 40)] {
+            let tx = transactions
+                .create_transaction(1, 7, ticker, 1, BigDecimal::from(price), "buy", 0.into())
+                .await
+                .unwrap();
+            created.push(tx);
+        }
+        transactions
+            .create_transaction(2, 8, "AAPL", 1, BigDecimal::from(50), "buy", 0.into())
+            .await
+            .unwrap();
+
+        let filter = TransactionFilter {
+            ticker: Some("AAPL".into()),
+            ..Default::default()
+        };
+        let first = transactions
+            .get_transactions_page(7, &filter, None, true, 2)
+            .await
+            .unwrap();
+        let ids: Vec<i32> = first.iter().map(|tx| tx.id).collect();
+        assert_eq!(ids, [created[3].id, created[2].id]);
+        let rest = transactions
+            .get_transactions_page(7, &filter, Some(first[1].public_id), true, 2)
+            .await
+            .unwrap();
+        let ids: Vec<i32> = rest.iter().map(|tx| tx.id).collect();
+        assert_eq!(ids, [created[0].id]);
+
+        transactions
+            .backdate(created[0].id, Utc::now() - TimeDelta::days(400))
+            .await
+            .unwrap();
+        let cutoff = Utc::now().naive_utc() - TimeDelta::days(365);
+        assert_eq!(transactions.archive_before(cutoff, 10).await.unwrap(), 1);
+
+        let hot = transactions
+            .get_transactions_page(7, &TransactionFilter::default(), None, false, 10)
+            .await
+            .unwrap();
+        assert_eq!(hot.len(), 3);
+        let archived = transactions
+            .get_archived_page(7, &TransactionFilter::default(), None, false, 10)
+            .await
+            .unwrap();
+        assert_eq!(archived[0].id, created[0].id);
+        let found = transactions
+            .get_transaction_by_public_id(created[0].public_id)
+            .await
+            .unwrap();
+        assert!(found.is_some());
+    }
+}
@@ -1,3 +1,67 @@
+pub mod allowance_repository;
+pub mod announcement_repository;
+pub mod api_key_repository;
+pub mod basket_repository;
+pub mod benchmark_price_repository;
+pub mod bond_repository;
+pub mod bot_repository;
+pub mod cash_flow_repository;
+pub mod cash_movement_repository;
+pub mod class_repository;
+pub mod competition_repository;
+pub mod corporate_action_repository;
+pub mod dividend_repository;
+pub mod feature_flag_repository;
+pub mod follow_repository;
 pub mod holdings_repository;
+pub mod import_repository;
+pub mod instrument_repository;
+pub mod ledger_repository;
+pub mod liquidity_profile_repository;
+pub mod loan_repository;
+pub mod matching_config_repository;
+#[cfg(test)]
+pub mod memory;
+pub mod money_market_repository;
+pub mod news_repository;
+pub mod option_repository;
+pub mod portfolio_repository;
+pub mod portfolio_snapshot_repository;
+pub mod price_candle_repository;
+pub mod replay_repository;
+pub mod tax_lot_repository;
 pub mod transaction_repository;
 pub mod user_repository;
+pub mod user_settings_repository;
+pub mod webhook_repository;
+
+use std::sync::Arc;
+
+use sqlx::PgPool;
+
+use self::{
+    holdings_repository::{HoldingsRepo, HoldingsRepository},
+    transaction_repository::{TransactionRepo, TransactionRepository},
+    user_repository::{UserRepo, UserRepository},
+};
+
+/// Repositories reached through the application state rather than built on a
+/// pool, so handlers and services can be tested against the in-memory ones of
+/// `repository::memory` without Postgres
+#[derive(Clone)]
+pub struct Repositories {
+    pub users: Arc<dyn UserRepo>,
+    pub holdings: Arc<dyn HoldingsRepo>,
+    pub transactions: Arc<dyn TransactionRepo>,
+}
+
+impl Repositories {
+    /// Repositories reading and writing through `pool`
+    pub fn postgres(pool: &PgPool) -> Self {
+        Repositories {
+            users: Arc::new(UserRepository::new(pool)),
+            holdings: Arc::new(HoldingsRepository::new(pool)),
+            transactions: Arc::new(TransactionRepository::new(pool)),
+        }
+    }
+}
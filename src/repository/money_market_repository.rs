@@ -0,0 +1,86 @@
+use bigdecimal::BigDecimal;
+use sqlx::PgPool;
+
+use crate::{Error, Result, models::money_market_account::MoneyMarketAccount};
+
+pub struct MoneyMarketRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> MoneyMarketRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        MoneyMarketRepository { pool }
+    }
+
+    pub async fn get_account(&self, user_id: i32) -> Result<Option<MoneyMarketAccount>> {
+        let account = sqlx::query_as!(
+            MoneyMarketAccount,
+            r#"
+            SELECT balance
+            FROM money_market_accounts
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(account)
+    }
+
+    pub async fn deposit(&self, user_id: i32, amount: BigDecimal) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO money_market_accounts (user_id, balance)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id)
+            DO UPDATE SET balance = money_market_accounts.balance + EXCLUDED.balance, updated_at = NOW()
+            "#,
+            user_id,
+            amount
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    /// Take `amount` out of the account, returning false if the balance is insufficient
+    pub async fn withdraw(&self, user_id: i32, amount: BigDecimal) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE money_market_accounts
+            SET balance = balance - $2, updated_at = NOW()
+            WHERE user_id = $1 AND balance >= $2
+            "#,
+            user_id,
+            amount
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Credit one period of interest at `rate` to every account
+    pub async fn accrue_interest(&self, rate: BigDecimal) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE money_market_accounts
+            SET balance = balance + ROUND(balance * $1, 10),
+                total_interest = total_interest + ROUND(balance * $1, 10),
+                updated_at = NOW()
+            WHERE balance > 0
+            "#,
+            rate
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,124 @@
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::news_event::{NewsDetails, NewsEvent},
+};
+
+pub struct NewsRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> NewsRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        NewsRepository { pool }
+    }
+
+    pub async fn create_news(&self, details: &NewsDetails) -> Result<NewsEvent> {
+        let news = sqlx::query_as!(
+            NewsEvent,
+            r#"
+            INSERT INTO news_events (ticker, event_type, headline, price_impact_percent,
+                                     volatility_multiplier, volatility_duration_secs, publish_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, ticker, event_type, headline, price_impact_percent,
+                      volatility_multiplier, volatility_duration_secs, publish_at, applied_at,
+                      created_at
+            "#,
+            details.ticker,
+            details.event_type,
+            details.headline,
+            details.price_impact_percent,
+            details.volatility_multiplier,
+            details.volatility_duration_secs,
+            details.publish_at
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(news)
+    }
+
+    /// All scheduled and published news, latest publish time first
+    pub async fn get_news(&self) -> Result<Vec<NewsEvent>> {
+        let news = sqlx::query_as!(
+            NewsEvent,
+            r#"
+            SELECT id, ticker, event_type, headline, price_impact_percent,
+                   volatility_multiplier, volatility_duration_secs, publish_at, applied_at,
+                   created_at
+            FROM news_events
+            ORDER BY publish_at DESC, id DESC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(news)
+    }
+
+    /// Published news, optionally of one ticker, latest first
+    pub async fn get_published(&self, ticker: Option<&str>, limit: i64) -> Result<Vec<NewsEvent>> {
+        let news = sqlx::query_as!(
+            NewsEvent,
+            r#"
+            SELECT id, ticker, event_type, headline, price_impact_percent,
+                   volatility_multiplier, volatility_duration_secs, publish_at, applied_at,
+                   created_at
+            FROM news_events
+            WHERE publish_at <= NOW() AND ($1::TEXT IS NULL OR ticker = $1)
+            ORDER BY publish_at DESC, id DESC
+            LIMIT $2
+            "#,
+            ticker,
+            limit
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(news)
+    }
+
+    /// Delete news that has not been published yet
+    pub async fn delete_scheduled(&self, news_id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM news_events
+            WHERE id = $1 AND publish_at > NOW()
+            "#,
+            news_id
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Claim the published news of `tickers` that has not been applied yet
+    ///
+    /// Marking the news as applied in the same statement keeps concurrent
+    /// price engines from applying it twice.
+    pub async fn claim_due(&self, tickers: &[String]) -> Result<Vec<NewsEvent>> {
+        let news = sqlx::query_as!(
+            NewsEvent,
+            r#"
+            UPDATE news_events
+            SET applied_at = NOW()
+            WHERE applied_at IS NULL AND publish_at <= NOW() AND ticker = ANY($1)
+            RETURNING id, ticker, event_type, headline, price_impact_percent,
+                      volatility_multiplier, volatility_duration_secs, publish_at, applied_at,
+                      created_at
+            "#,
+            tickers
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(news)
+    }
+}
@@ -0,0 +1,292 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::option::{OptionContract, OptionPosition, OptionTrade},
+};
+
+pub struct OptionRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> OptionRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        OptionRepository { pool }
+    }
+
+    /// List a contract, returning `None` when one with the same terms exists
+    pub async fn create_contract(
+        &self,
+        underlying: &str,
+        kind: &str,
+        strike: BigDecimal,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Option<OptionContract>> {
+        sqlx::query_as!(
+            OptionContract,
+            r#"
+            INSERT INTO option_contracts (underlying, kind, strike, expires_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (underlying, kind, strike, expires_at) DO NOTHING
+            RETURNING id, underlying, kind, strike, expires_at, settlement_price, settled_at,
+                      created_at
+            "#,
+            underlying,
+            kind,
+            strike,
+            expires_at
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    pub async fn get_contract(&self, contract_id: i32) -> Result<Option<OptionContract>> {
+        sqlx::query_as!(
+            OptionContract,
+            r#"
+            SELECT id, underlying, kind, strike, expires_at, settlement_price, settled_at,
+                   created_at
+            FROM option_contracts
+            WHERE id = $1
+            "#,
+            contract_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    /// Unexpired contracts on `underlying`, by expiry, strike and kind
+    pub async fn get_chain(&self, underlying: &str) -> Result<Vec<OptionContract>> {
+        sqlx::query_as!(
+            OptionContract,
+            r#"
+            SELECT id, underlying, kind, strike, expires_at, settlement_price, settled_at,
+                   created_at
+            FROM option_contracts
+            WHERE underlying = $1 AND settled_at IS NULL AND expires_at > NOW()
+            ORDER BY expires_at, strike, kind
+            "#,
+            underlying
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    /// Remove a contract nobody holds; returns whether it was deleted
+    pub async fn delete_unheld_contract(&self, contract_id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM option_contracts
+            WHERE id = $1
+              AND NOT EXISTS (SELECT 1 FROM option_positions WHERE contract_id = $1)
+            "#,
+            contract_id
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Expired contracts not settled yet
+    pub async fn get_due_contracts(&self) -> Result<Vec<OptionContract>> {
+        sqlx::query_as!(
+            OptionContract,
+            r#"
+            SELECT id, underlying, kind, strike, expires_at, settlement_price, settled_at,
+                   created_at
+            FROM option_contracts
+            WHERE settled_at IS NULL AND expires_at <= NOW()
+            ORDER BY expires_at
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    /// Mark a contract settled at `settlement_price`, returning false when
+    /// another instance settled it first
+    pub async fn settle_contract(
+        &self,
+        contract_id: i32,
+        settlement_price: BigDecimal,
+    ) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE option_contracts
+            SET settlement_price = $2, settled_at = NOW()
+            WHERE id = $1 AND settled_at IS NULL
+            "#,
+            contract_id,
+            settlement_price
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_position(
+        &self,
+        portfolio_id: i32,
+        contract_id: i32,
+    ) -> Result<Option<OptionPosition>> {
+        sqlx::query_as!(
+            OptionPosition,
+            r#"
+            SELECT p.id, p.user_id, p.portfolio_id, p.contract_id, p.quantity, p.average_premium,
+                   p.margin, c.underlying, c.kind, c.strike, c.expires_at
+            FROM option_positions p
+            JOIN option_contracts c ON c.id = p.contract_id
+            WHERE p.portfolio_id = $1 AND p.contract_id = $2
+            "#,
+            portfolio_id,
+            contract_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    /// Positions of a portfolio, by expiry
+    pub async fn get_positions_by_portfolio(
+        &self,
+        portfolio_id: i32,
+    ) -> Result<Vec<OptionPosition>> {
+        sqlx::query_as!(
+            OptionPosition,
+            r#"
+            SELECT p.id, p.user_id, p.portfolio_id, p.contract_id, p.quantity, p.average_premium,
+                   p.margin, c.underlying, c.kind, c.strike, c.expires_at
+            FROM option_positions p
+            JOIN option_contracts c ON c.id = p.contract_id
+            WHERE p.portfolio_id = $1
+            ORDER BY c.expires_at, c.underlying, c.strike, p.id
+            "#,
+            portfolio_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    /// Positions in the user's portfolios other than competition and class ones
+    pub async fn get_positions_by_user(&self, user_id: i32) -> Result<Vec<OptionPosition>> {
+        sqlx::query_as!(
+            OptionPosition,
+            r#"
+            SELECT p.id, p.user_id, p.portfolio_id, p.contract_id, p.quantity, p.average_premium,
+                   p.margin, c.underlying, c.kind, c.strike, c.expires_at
+            FROM option_positions p
+            JOIN option_contracts c ON c.id = p.contract_id
+            JOIN portfolios pf ON pf.id = p.portfolio_id
+            WHERE p.user_id = $1 AND pf.competition_id IS NULL AND pf.class_id IS NULL
+            ORDER BY c.expires_at, c.underlying, c.strike, p.id
+            "#,
+            user_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    pub async fn get_positions_by_contract(&self, contract_id: i32) -> Result<Vec<OptionPosition>> {
+        sqlx::query_as!(
+            OptionPosition,
+            r#"
+            SELECT p.id, p.user_id, p.portfolio_id, p.contract_id, p.quantity, p.average_premium,
+                   p.margin, c.underlying, c.kind, c.strike, c.expires_at
+            FROM option_positions p
+            JOIN option_contracts c ON c.id = p.contract_id
+            WHERE p.contract_id = $1
+            ORDER BY p.id
+            "#,
+            contract_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    /// Set the size of a position, creating it when the portfolio had none
+    pub async fn upsert_position(
+        &self,
+        user_id: i32,
+        portfolio_id: i32,
+        contract_id: i32,
+        quantity: i32,
+        average_premium: BigDecimal,
+        margin: BigDecimal,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO option_positions (user_id, portfolio_id, contract_id, quantity,
+                                          average_premium, margin)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (portfolio_id, contract_id) DO UPDATE
+            SET quantity = EXCLUDED.quantity, average_premium = EXCLUDED.average_premium,
+                margin = EXCLUDED.margin
+            "#,
+            user_id,
+            portfolio_id,
+            contract_id,
+            quantity,
+            average_premium,
+            margin
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    pub async fn delete_position(&self, position_id: i32) -> Result<()> {
+        sqlx::query!("DELETE FROM option_positions WHERE id = $1", position_id)
+            .execute(self.pool)
+            .await
+            .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record_trade(
+        &self,
+        user_id: i32,
+        portfolio_id: i32,
+        contract_id: i32,
+        trade_type: &str,
+        quantity: i32,
+        price: BigDecimal,
+        fee: BigDecimal,
+    ) -> Result<OptionTrade> {
+        sqlx::query_as!(
+            OptionTrade,
+            r#"
+            INSERT INTO option_trades (user_id, portfolio_id, contract_id, trade_type, quantity,
+                                       price, fee)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, contract_id, trade_type, quantity, price, fee, created_at
+            "#,
+            user_id,
+            portfolio_id,
+            contract_id,
+            trade_type,
+            quantity,
+            price,
+            fee
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+}
@@ -0,0 +1,154 @@
+use bigdecimal::BigDecimal;
+use sqlx::PgPool;
+
+use crate::{Error, Result, models::portfolio::Portfolio};
+
+pub struct PortfolioRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> PortfolioRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        PortfolioRepository { pool }
+    }
+
+    pub async fn create_portfolio(
+        &self,
+        user_id: i32,
+        name: &str,
+        balance: BigDecimal,
+        is_default: bool,
+    ) -> Result<Portfolio> {
+        let portfolio = sqlx::query_as!(
+            Portfolio,
+            r#"
+            INSERT INTO portfolios (user_id, name, balance, is_default)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, user_id, name, balance, is_default, competition_id, class_id, is_public,
+                created_at
+            "#,
+            user_id,
+            name,
+            balance,
+            is_default
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(portfolio)
+    }
+
+    pub async fn get_portfolio(&self, portfolio_id: i32) -> Result<Option<Portfolio>> {
+        let portfolio = sqlx::query_as!(
+            Portfolio,
+            r#"
+            SELECT id, user_id, name, balance, is_default, competition_id, class_id, is_public,
+                created_at
+            FROM portfolios
+            WHERE id = $1
+            "#,
+            portfolio_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(portfolio)
+    }
+
+    pub async fn get_default_portfolio(&self, user_id: i32) -> Result<Option<Portfolio>> {
+        let portfolio = sqlx::query_as!(
+            Portfolio,
+            r#"
+            SELECT id, user_id, name, balance, is_default, competition_id, class_id, is_public,
+                created_at
+            FROM portfolios
+            WHERE user_id = $1 AND is_default
+            "#,
+            user_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(portfolio)
+    }
+
+    /// The user's portfolios, default first
+    pub async fn get_portfolios_by_user(&self, user_id: i32) -> Result<Vec<Portfolio>> {
+        let portfolios = sqlx::query_as!(
+            Portfolio,
+            r#"
+            SELECT id, user_id, name, balance, is_default, competition_id, class_id, is_public,
+                created_at
+            FROM portfolios
+            WHERE user_id = $1
+            ORDER BY is_default DESC, id
+            "#,
+            user_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(portfolios)
+    }
+
+    pub async fn set_public(&self, portfolio_id: i32, is_public: bool) -> Result<Portfolio> {
+        let portfolio = sqlx::query_as!(
+            Portfolio,
+            r#"
+            UPDATE portfolios
+            SET is_public = $2
+            WHERE id = $1
+            RETURNING id, user_id, name, balance, is_default, competition_id, class_id, is_public,
+                created_at
+            "#,
+            portfolio_id,
+            is_public
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(portfolio)
+    }
+
+    /// The user's public portfolios, default first
+    pub async fn get_public_portfolios(&self, user_id: i32) -> Result<Vec<Portfolio>> {
+        let portfolios = sqlx::query_as!(
+            Portfolio,
+            r#"
+            SELECT id, user_id, name, balance, is_default, competition_id, class_id, is_public,
+                created_at
+            FROM portfolios
+            WHERE user_id = $1 AND is_public
+            ORDER BY is_default DESC, id
+            "#,
+            user_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(portfolios)
+    }
+
+    /// Cash across all of the user's portfolios outside competitions and classes
+    pub async fn get_total_balance(&self, user_id: i32) -> Result<BigDecimal> {
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(balance), 0) AS "total!"
+            FROM portfolios
+            WHERE user_id = $1 AND competition_id IS NULL AND class_id IS NULL
+            "#,
+            user_id
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(total)
+    }
+}
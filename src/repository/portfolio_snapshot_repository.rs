@@ -0,0 +1,73 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use sqlx::PgPool;
+
+use crate::{Error, Result, models::portfolio_snapshot::PortfolioSnapshot};
+
+pub struct PortfolioSnapshotRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> PortfolioSnapshotRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        PortfolioSnapshotRepository { pool }
+    }
+
+    /// Store the snapshot for a day, replacing any earlier capture of the same day
+    pub async fn upsert_snapshot(
+        &self,
+        user_id: i32,
+        snapshot_date: NaiveDate,
+        cash: BigDecimal,
+        market_value: BigDecimal,
+        equity: BigDecimal,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO portfolio_snapshots (user_id, snapshot_date, cash, market_value, equity)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id, snapshot_date) DO UPDATE
+            SET cash = EXCLUDED.cash,
+                market_value = EXCLUDED.market_value,
+                equity = EXCLUDED.equity,
+                created_at = NOW()
+            "#,
+            user_id,
+            snapshot_date,
+            cash,
+            market_value,
+            equity
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    /// Snapshots between `from` and `to` (inclusive), oldest first
+    pub async fn get_snapshots(
+        &self,
+        user_id: i32,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<PortfolioSnapshot>> {
+        let snapshots = sqlx::query_as!(
+            PortfolioSnapshot,
+            r#"
+            SELECT snapshot_date, cash, market_value, equity
+            FROM portfolio_snapshots
+            WHERE user_id = $1 AND snapshot_date BETWEEN $2 AND $3
+            ORDER BY snapshot_date
+            "#,
+            user_id,
+            from,
+            to
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(snapshots)
+    }
+}
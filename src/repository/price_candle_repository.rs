@@ -0,0 +1,210 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::price_candle::{CandleInterval, DailyCandle, DailyMove, PriceCandle},
+};
+
+pub struct PriceCandleRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> PriceCandleRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        PriceCandleRepository { pool }
+    }
+
+    /// Fold a price update received at `at` into the candles of every interval
+    ///
+    /// The first update of a bucket opens its candle; later ones extend the
+    /// high and low and replace the close.
+    pub async fn record_tick(
+        &self,
+        ticker: &str,
+        price: BigDecimal,
+        at: DateTime<Utc>,
+    ) -> Result<()> {
+        let intervals: Vec<String> = CandleInterval::ALL
+            .iter()
+            .map(|interval| interval.as_str().to_string())
+            .collect();
+        let bucket_starts: Vec<DateTime<Utc>> = CandleInterval::ALL
+            .iter()
+            .map(|interval| interval.bucket_start(at))
+            .collect();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO price_candles (ticker, interval, bucket_start, open, high, low, close)
+            SELECT $1, buckets.interval, buckets.bucket_start, $4, $4, $4, $4
+            FROM UNNEST($2::VARCHAR[], $3::TIMESTAMPTZ[]) AS buckets (interval, bucket_start)
+            ON CONFLICT (ticker, interval, bucket_start) DO UPDATE
+            SET high = GREATEST(price_candles.high, EXCLUDED.high),
+                low = LEAST(price_candles.low, EXCLUDED.low),
+                close = EXCLUDED.close,
+                tick_count = price_candles.tick_count + 1,
+                updated_at = NOW()
+            "#,
+            ticker,
+            &intervals,
+            &bucket_starts,
+            price
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    /// Load historical candles of `interval`, replacing those of the same buckets
+    ///
+    /// Every candle's `bucket_start` must be aligned to `interval`. Returns the
+    /// number of candles written.
+    pub async fn backfill(
+        &self,
+        ticker: &str,
+        interval: CandleInterval,
+        candles: &[PriceCandle],
+    ) -> Result<u64> {
+        let bucket_starts: Vec<DateTime<Utc>> = candles.iter().map(|c| c.bucket_start).collect();
+        let opens: Vec<BigDecimal> = candles.iter().map(|c| c.open.clone()).collect();
+        let highs: Vec<BigDecimal> = candles.iter().map(|c| c.high.clone()).collect();
+        let lows: Vec<BigDecimal> = candles.iter().map(|c| c.low.clone()).collect();
+        let closes: Vec<BigDecimal> = candles.iter().map(|c| c.close.clone()).collect();
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO price_candles (ticker, interval, bucket_start, open, high, low, close)
+            SELECT $1, $2, rows.bucket_start, rows.open, rows.high, rows.low, rows.close
+            FROM UNNEST($3::TIMESTAMPTZ[], $4::NUMERIC[], $5::NUMERIC[], $6::NUMERIC[], $7::NUMERIC[])
+                AS rows (bucket_start, open, high, low, close)
+            ON CONFLICT (ticker, interval, bucket_start) DO UPDATE
+            SET open = EXCLUDED.open,
+                high = EXCLUDED.high,
+                low = EXCLUDED.low,
+                close = EXCLUDED.close,
+                updated_at = NOW()
+            "#,
+            ticker,
+            interval.as_str(),
+            &bucket_starts,
+            &opens,
+            &highs,
+            &lows,
+            &closes
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Candles of `interval` starting between `from` and `to` (inclusive), oldest first
+    ///
+    /// Volume counts the shares bought and sold in the simulator during each bucket.
+    pub async fn get_candles(
+        &self,
+        ticker: &str,
+        interval: CandleInterval,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<PriceCandle>> {
+        // Transaction timestamps are stored as UTC without a time zone
+        let candles = sqlx::query_as!(
+            PriceCandle,
+            r#"
+            SELECT
+                c.bucket_start,
+                c.open,
+                c.high,
+                c.low,
+                c.close,
+                c.tick_count,
+                COALESCE(
+                    (
+                        SELECT SUM(t.quantity)
+                        FROM transaction_history t
+                        WHERE t.ticker = c.ticker
+                          AND t.transaction_type IN ('buy', 'sell')
+                          AND t.created_at >= c.bucket_start AT TIME ZONE 'UTC'
+                          AND t.created_at < (c.bucket_start + make_interval(secs => $5)) AT TIME ZONE 'UTC'
+                    ),
+                    0
+                )::BIGINT AS "volume!"
+            FROM price_candles c
+            WHERE c.ticker = $1 AND c.interval = $2 AND c.bucket_start BETWEEN $3 AND $4
+            ORDER BY c.bucket_start
+            "#,
+            ticker,
+            interval.as_str(),
+            from,
+            to,
+            interval.seconds() as f64
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(candles)
+    }
+
+    /// The two most recent daily candles of each of `tickers`, newest first
+    pub async fn get_latest_daily(&self, tickers: &[String]) -> Result<Vec<DailyCandle>> {
+        let candles = sqlx::query_as!(
+            DailyCandle,
+            r#"
+            SELECT
+                ticker AS "ticker!",
+                bucket_start AS "bucket_start!",
+                high AS "high!",
+                low AS "low!",
+                close AS "close!",
+                updated_at AS "updated_at!"
+            FROM (
+                SELECT c.*,
+                       ROW_NUMBER() OVER (PARTITION BY c.ticker ORDER BY c.bucket_start DESC) AS n
+                FROM price_candles c
+                WHERE c.ticker = ANY($1) AND c.interval = '1d'
+            ) latest
+            WHERE n <= 2
+            ORDER BY ticker, bucket_start DESC
+            "#,
+            tickers
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(candles)
+    }
+
+    /// Tickers with a daily candle starting at `day`, with their latest price
+    /// and the close of their previous daily candle
+    pub async fn get_daily_moves(&self, day: DateTime<Utc>) -> Result<Vec<DailyMove>> {
+        let moves = sqlx::query_as!(
+            DailyMove,
+            r#"
+            SELECT c.ticker, c.close, previous.close AS previous_close
+            FROM price_candles c
+            JOIN LATERAL (
+                SELECT p.close
+                FROM price_candles p
+                WHERE p.ticker = c.ticker AND p.interval = '1d' AND p.bucket_start < c.bucket_start
+                ORDER BY p.bucket_start DESC
+                LIMIT 1
+            ) previous ON TRUE
+            WHERE c.interval = '1d' AND c.bucket_start = $1
+            "#,
+            day
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(moves)
+    }
+}
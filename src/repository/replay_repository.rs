@@ -0,0 +1,236 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::replay::{Replay, ReplayTick},
+};
+
+pub struct ReplayRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> ReplayRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        ReplayRepository { pool }
+    }
+
+    /// Store a replay with its ticks, which must be in chronological order
+    pub async fn create_replay(
+        &self,
+        name: &str,
+        speed: f64,
+        ticks: &[ReplayTick],
+    ) -> Result<Replay> {
+        let (Some(first), Some(last)) = (ticks.first(), ticks.last()) else {
+            return Err(Error::BadRequest("A replay needs at least one tick".into()));
+        };
+        let ats: Vec<DateTime<Utc>> = ticks.iter().map(|t| t.at).collect();
+        let tickers: Vec<String> = ticks.iter().map(|t| t.ticker.clone()).collect();
+        let prices: Vec<f64> = ticks.iter().map(|t| t.price).collect();
+
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        let replay = sqlx::query_as!(
+            Replay,
+            r#"
+            INSERT INTO replays (name, speed, tick_count, data_from, data_to)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, name, speed, status, tick_count, position, data_from, data_to,
+                started_at, finished_at, created_at
+            "#,
+            name,
+            speed,
+            ticks.len() as i32,
+            first.at,
+            last.at
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO replay_ticks (replay_id, seq, at, ticker, price)
+            SELECT $1, rows.seq, rows.at, rows.ticker, rows.price
+            FROM UNNEST($2::TIMESTAMPTZ[], $3::TEXT[], $4::FLOAT8[])
+                WITH ORDINALITY AS rows (at, ticker, price, seq)
+            "#,
+            replay.id,
+            &ats,
+            &tickers,
+            &prices
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(replay)
+    }
+
+    /// Every replay, latest first
+    pub async fn get_replays(&self) -> Result<Vec<Replay>> {
+        let replays = sqlx::query_as!(
+            Replay,
+            r#"
+            SELECT id, name, speed, status, tick_count, position, data_from, data_to,
+                started_at, finished_at, created_at
+            FROM replays
+            ORDER BY id DESC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(replays)
+    }
+
+    pub async fn get_running(&self) -> Result<Vec<Replay>> {
+        let replays = sqlx::query_as!(
+            Replay,
+            r#"
+            SELECT id, name, speed, status, tick_count, position, data_from, data_to,
+                started_at, finished_at, created_at
+            FROM replays
+            WHERE status = 'running'
+            ORDER BY id
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(replays)
+    }
+
+    /// Tickers the replay publishes prices of
+    pub async fn get_tickers(&self, replay_id: i32) -> Result<Vec<String>> {
+        let tickers = sqlx::query_scalar!(
+            r#"
+            SELECT DISTINCT ticker
+            FROM replay_ticks
+            WHERE replay_id = $1
+            ORDER BY ticker
+            "#,
+            replay_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(tickers)
+    }
+
+    /// Up to `limit` ticks after position `after` up to the historical time `until`
+    pub async fn get_ticks(
+        &self,
+        replay_id: i32,
+        after: i32,
+        until: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<ReplayTick>> {
+        let ticks = sqlx::query_as!(
+            ReplayTick,
+            r#"
+            SELECT seq, at, ticker, price
+            FROM replay_ticks
+            WHERE replay_id = $1 AND seq > $2 AND at <= $3
+            ORDER BY seq
+            LIMIT $4
+            "#,
+            replay_id,
+            after,
+            until,
+            limit
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(ticks)
+    }
+
+    /// Run a replay from its first tick, unless it is already running
+    pub async fn start(&self, replay_id: i32) -> Result<Option<Replay>> {
+        let replay = sqlx::query_as!(
+            Replay,
+            r#"
+            UPDATE replays
+            SET status = 'running', position = 0, started_at = NOW(), finished_at = NULL
+            WHERE id = $1 AND status <> 'running'
+            RETURNING id, name, speed, status, tick_count, position, data_from, data_to,
+                started_at, finished_at, created_at
+            "#,
+            replay_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(replay)
+    }
+
+    /// Stop a running replay where it is
+    pub async fn stop(&self, replay_id: i32) -> Result<Option<Replay>> {
+        let replay = sqlx::query_as!(
+            Replay,
+            r#"
+            UPDATE replays
+            SET status = 'finished', finished_at = NOW()
+            WHERE id = $1 AND status = 'running'
+            RETURNING id, name, speed, status, tick_count, position, data_from, data_to,
+                started_at, finished_at, created_at
+            "#,
+            replay_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(replay)
+    }
+
+    /// Move a running replay from position `from` to `to`, finishing it after
+    /// its last tick
+    ///
+    /// Returns false when another instance moved it first, so every tick is
+    /// claimed by exactly one publisher.
+    pub async fn advance(&self, replay_id: i32, from: i32, to: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE replays
+            SET position = $3,
+                status = CASE WHEN $3 >= tick_count THEN 'finished' ELSE status END,
+                finished_at = CASE WHEN $3 >= tick_count THEN NOW() ELSE finished_at END
+            WHERE id = $1 AND status = 'running' AND position = $2
+            "#,
+            replay_id,
+            from,
+            to
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete a replay that is not running
+    pub async fn delete_replay(&self, replay_id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM replays
+            WHERE id = $1 AND status <> 'running'
+            "#,
+            replay_id
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
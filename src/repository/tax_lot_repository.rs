@@ -0,0 +1,282 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::{
+        holding::Holding, realized_gain::RealizedGain, tax_lot::TaxLot,
+        user_settings::CostBasisMethod,
+    },
+    repository::ledger_repository::{TradePosting, post_trade_entries},
+    services::cost_basis::RealizedLot,
+};
+
+pub struct TaxLotRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> TaxLotRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        TaxLotRepository { pool }
+    }
+
+    /// Buy `quantity` shares at `price` into the position of a portfolio in a
+    /// ticker, in a single database transaction
+    ///
+    /// The open position is locked first, so other trades in the position
+    /// wait until the purchase is recorded. An open position takes the shares
+    /// at the average price `average` computes from it, otherwise one is
+    /// opened at `price`. A lot of `buy_transaction_id` is opened and its
+    /// `posting` is booked to the portfolio's cash. Returns false, changing
+    /// nothing, when another purchase opened the position at the same time.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn buy_position<F>(
+        &self,
+        user_id: i32,
+        portfolio_id: i32,
+        ticker: &str,
+        quantity: i32,
+        price: &BigDecimal,
+        buy_transaction_id: i32,
+        posting: TradePosting<'_>,
+        average: F,
+    ) -> Result<bool>
+    where
+        F: FnOnce(&Holding) -> BigDecimal,
+    {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        let holding = sqlx::query_as!(
+            Holding,
+            r#"
+            SELECT id, public_id, user_id, portfolio_id, ticker, quantity, average_price, created_at,
+                updated_at, version
+            FROM holdings
+            WHERE portfolio_id = $1 AND ticker = $2 AND closed_at IS NULL AND deleted_at IS NULL
+            FOR UPDATE
+            "#,
+            portfolio_id,
+            ticker
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        if let Some(holding) = holding {
+            sqlx::query!(
+                r#"
+                UPDATE holdings
+                SET quantity = quantity + $1, average_price = $2, updated_at = NOW(),
+                    version = version + 1
+                WHERE id = $3
+                "#,
+                quantity,
+                average(&holding),
+                holding.id
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+        } else {
+            let opened = sqlx::query_scalar!(
+                r#"
+                INSERT INTO holdings (user_id, portfolio_id, ticker, quantity, average_price)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (portfolio_id, ticker) WHERE closed_at IS NULL AND deleted_at IS NULL
+                DO NOTHING
+                RETURNING id
+                "#,
+                user_id,
+                portfolio_id,
+                ticker,
+                quantity,
+                price
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+            if opened.is_none() {
+                return Ok(false);
+            }
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO tax_lots (user_id, portfolio_id, ticker, transaction_id, quantity,
+                                  remaining_quantity, price)
+            VALUES ($1, $2, $3, $4, $5, $5, $6)
+            "#,
+            user_id,
+            portfolio_id,
+            ticker,
+            buy_transaction_id,
+            quantity,
+            price
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        post_trade_entries(&mut tx, portfolio_id, posting, Some(buy_transaction_id)).await?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(true)
+    }
+
+    /// Sell `quantity` shares out of the open position of a portfolio in a
+    /// ticker, in a single database transaction
+    ///
+    /// The position and its open lots are locked first, so other trades in
+    /// the position wait until the sale is recorded. `realize` then matches
+    /// the sale against the lots, oldest first, returning the slices sold and
+    /// the average price of the shares left. The position keeps the shares
+    /// left at that price (and is closed when none are), every lot loses the
+    /// shares taken from it, every slice is recorded as a realized gain of
+    /// `sell_transaction_id` and the `posting` is booked to the portfolio's
+    /// cash. Returns `None`, changing nothing, when the portfolio holds fewer
+    /// than `quantity` shares of the ticker.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn sell_position<F>(
+        &self,
+        portfolio_id: i32,
+        ticker: &str,
+        quantity: i32,
+        sell_transaction_id: i32,
+        method: CostBasisMethod,
+        posting: TradePosting<'_>,
+        realize: F,
+    ) -> Result<Option<Vec<RealizedLot>>>
+    where
+        F: FnOnce(&Holding, &[TaxLot]) -> (Vec<RealizedLot>, BigDecimal),
+    {
+        let mut tx = self.pool.begin().await.map_err(Error::Database)?;
+
+        let holding = sqlx::query_as!(
+            Holding,
+            r#"
+            SELECT id, public_id, user_id, portfolio_id, ticker, quantity, average_price, created_at,
+                updated_at, version
+            FROM holdings
+            WHERE portfolio_id = $1 AND ticker = $2 AND closed_at IS NULL AND deleted_at IS NULL
+            FOR UPDATE
+            "#,
+            portfolio_id,
+            ticker
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+        let Some(holding) = holding.filter(|holding| holding.quantity >= quantity) else {
+            return Ok(None);
+        };
+
+        let lots = sqlx::query_as!(
+            TaxLot,
+            r#"
+            SELECT id, remaining_quantity, price, acquired_at
+            FROM tax_lots
+            WHERE portfolio_id = $1 AND ticker = $2 AND remaining_quantity > 0
+            ORDER BY acquired_at, id
+            FOR UPDATE
+            "#,
+            portfolio_id,
+            ticker
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        let (realized, average_price) = realize(&holding, &lots);
+
+        sqlx::query!(
+            r#"
+            UPDATE holdings
+            SET quantity = quantity - $1, average_price = $2, updated_at = NOW(),
+                version = version + 1,
+                closed_at = CASE WHEN quantity = $1 THEN NOW() END
+            WHERE id = $3
+            "#,
+            quantity,
+            average_price,
+            holding.id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(Error::Database)?;
+
+        for lot in &realized {
+            if let Some(lot_id) = lot.lot_id {
+                sqlx::query!(
+                    r#"
+                    UPDATE tax_lots
+                    SET remaining_quantity = remaining_quantity - $1
+                    WHERE id = $2
+                    "#,
+                    lot.quantity,
+                    lot_id
+                )
+                .execute(&mut *tx)
+                .await
+                .map_err(Error::Database)?;
+            }
+
+            sqlx::query!(
+                r#"
+                INSERT INTO realized_gains (user_id, ticker, sell_transaction_id, lot_id, quantity,
+                                            cost_basis, proceeds, gain, cost_basis_method, acquired_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                "#,
+                holding.user_id,
+                ticker,
+                sell_transaction_id,
+                lot.lot_id,
+                lot.quantity,
+                lot.cost_basis,
+                lot.proceeds,
+                lot.gain,
+                method.as_str(),
+                lot.acquired_at
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(Error::Database)?;
+        }
+
+        post_trade_entries(&mut tx, portfolio_id, posting, Some(sell_transaction_id)).await?;
+
+        tx.commit().await.map_err(Error::Database)?;
+
+        Ok(Some(realized))
+    }
+
+    /// Realized gains of all the user's portfolios in `[from, to)`, oldest first
+    pub async fn get_realized_gains(
+        &self,
+        user_id: i32,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<RealizedGain>> {
+        let gains = sqlx::query_as!(
+            RealizedGain,
+            r#"
+            SELECT rg.id, rg.ticker, t.public_id AS "sell_transaction_id!", rg.lot_id, rg.quantity,
+                   rg.cost_basis, rg.proceeds, rg.gain, rg.cost_basis_method, rg.acquired_at,
+                   rg.realized_at
+            FROM realized_gains rg
+            JOIN transaction_history t ON t.id = rg.sell_transaction_id
+            WHERE rg.user_id = $1 AND rg.realized_at >= $2 AND rg.realized_at < $3
+            ORDER BY rg.realized_at, rg.id
+            "#,
+            user_id,
+            from,
+            to
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(gains)
+    }
+}
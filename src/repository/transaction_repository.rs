@@ -1,76 +1,311 @@
+use async_trait::async_trait;
 use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use sqlx::PgPool;
+use uuid::Uuid;
 
-use crate::{Error, Result, models::transaction::Transaction};
+use crate::{
+    Error, Result,
+    models::transaction::{TradedVolume, Transaction},
+};
 
-pub struct TransactionRepository<'a> {
-    pool: &'a PgPool,
+/// Criteria a listed transaction must match; `None` matches everything
+#[derive(Debug, Default)]
+pub struct TransactionFilter {
+    pub ticker: Option<String>,
+    pub transaction_type: Option<String>,
+    /// First day (inclusive)
+    pub from: Option<NaiveDate>,
+    /// Last day (inclusive)
+    pub to: Option<NaiveDate>,
+    pub min_price: Option<BigDecimal>,
+    pub max_price: Option<BigDecimal>,
 }
 
-impl<'a> TransactionRepository<'a> {
-    pub fn new(pool: &'a PgPool) -> Self {
-        TransactionRepository { pool }
+/// Executed transactions, hot and archived
+#[async_trait]
+pub trait TransactionRepo: Send + Sync {
+    /// Record a transaction executed now
+    #[allow(clippy::too_many_arguments)]
+    async fn create_transaction(
+        &self,
+        user_id: i32,
+        portfolio_id: i32,
+        ticker: &str,
+        quantity: i32,
+        price: BigDecimal,
+        transaction_type: &str,
+        fee: BigDecimal,
+    ) -> Result<Transaction>;
+
+    /// One page of the portfolio's transactions matching `filter`
+    ///
+    /// Transactions are ordered by id, which follows execution order. `after`
+    /// is the public ID of the last transaction of the previous page.
+    async fn get_transactions_page(
+        &self,
+        portfolio_id: i32,
+        filter: &TransactionFilter,
+        after: Option<Uuid>,
+        newest_first: bool,
+        limit: i64,
+    ) -> Result<Vec<Transaction>>;
+
+    /// One page of the portfolio's archived transactions matching `filter`,
+    /// like [`TransactionRepo::get_transactions_page`]
+    async fn get_archived_page(
+        &self,
+        portfolio_id: i32,
+        filter: &TransactionFilter,
+        after: Option<Uuid>,
+        newest_first: bool,
+        limit: i64,
+    ) -> Result<Vec<Transaction>>;
+
+    /// Move up to `batch` transactions executed before `cutoff` to the
+    /// archive, returning how many moved
+    async fn archive_before(&self, cutoff: NaiveDateTime, batch: i32) -> Result<i32>;
+
+    /// Tickers with the most shares bought and sold since `since`
+    async fn get_most_traded(&self, since: NaiveDateTime, limit: i64) -> Result<Vec<TradedVolume>>;
+
+    /// The transaction the API knows as `public_id`, archived or not
+    async fn get_transaction_by_public_id(&self, public_id: Uuid) -> Result<Option<Transaction>>;
+
+    /// Soft-delete a transaction that could not be settled
+    async fn delete_transaction(&self, transaction_id: i32) -> Result<()>;
+
+    /// Move a transaction to `at`, with the tax lot it opened and the gains
+    /// it realized
+    ///
+    /// Used to seed demo history; trades are otherwise stamped when executed.
+    async fn backdate(&self, transaction_id: i32, at: DateTime<Utc>) -> Result<()>;
+}
+
+/// [`TransactionRepo`] on Postgres
+pub struct TransactionRepository {
+    pool: PgPool,
+}
+
+impl TransactionRepository {
+    pub fn new(pool: &PgPool) -> Self {
+        TransactionRepository { pool: pool.clone() }
     }
+}
 
-    pub async fn create_transaction(
+#[async_trait]
+impl TransactionRepo for TransactionRepository {
+    #[allow(clippy::too_many_arguments)]
+    async fn create_transaction(
         &self,
         user_id: i32,
+        portfolio_id: i32,
         ticker: &str,
         quantity: i32,
         price: BigDecimal,
         transaction_type: &str,
+        fee: BigDecimal,
     ) -> Result<Transaction> {
         let transaction = sqlx::query_as!(
             Transaction,
             r#"
-            INSERT INTO transactions (user_id, ticker, quantity, price, transaction_type)
-            VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, user_id, ticker, quantity, price, transaction_type
+            INSERT INTO transactions (user_id, portfolio_id, ticker, quantity, price, transaction_type,
+                fee)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, public_id, user_id, ticker, quantity, price, fee, transaction_type,
+                created_at, updated_at
             "#,
             user_id,
+            portfolio_id,
             ticker,
             quantity,
             price,
-            transaction_type
+            transaction_type,
+            fee
         )
-        .fetch_one(self.pool)
+        .fetch_one(&self.pool)
         .await
         .map_err(Error::Database)?;
 
         Ok(transaction)
     }
 
-    pub async fn get_transactions_by_user(&self, user_id: i32) -> Result<Vec<Transaction>> {
+    async fn get_transactions_page(
+        &self,
+        portfolio_id: i32,
+        filter: &TransactionFilter,
+        after: Option<Uuid>,
+        newest_first: bool,
+        limit: i64,
+    ) -> Result<Vec<Transaction>> {
         let transactions = sqlx::query_as!(
             Transaction,
             r#"
-            SELECT id, user_id, ticker, quantity, price, transaction_type
-            FROM transactions
-            WHERE user_id = $1
+            SELECT id, public_id, user_id, ticker, quantity, price, fee, transaction_type,
+                created_at, updated_at
+            FROM transactions t
+            WHERE portfolio_id = $1 AND deleted_at IS NULL
+                AND ($2::text IS NULL OR ticker = $2)
+                AND ($3::text IS NULL OR transaction_type = $3)
+                AND ($4::date IS NULL OR created_at >= $4)
+                AND ($5::date IS NULL OR created_at < $5 + 1)
+                AND ($6::numeric IS NULL OR price >= $6)
+                AND ($7::numeric IS NULL OR price <= $7)
+                AND ($8::uuid IS NULL OR (
+                    SELECT CASE WHEN $9 THEN t.id < prev.id ELSE t.id > prev.id END
+                    FROM transactions prev
+                    WHERE prev.public_id = $8
+                ))
+            ORDER BY CASE WHEN $9 THEN id END DESC, id
+            LIMIT $10
             "#,
-            user_id
+            portfolio_id,
+            filter.ticker.as_deref(),
+            filter.transaction_type.as_deref(),
+            filter.from,
+            filter.to,
+            filter.min_price.as_ref(),
+            filter.max_price.as_ref(),
+            after,
+            newest_first,
+            limit
         )
-        .fetch_all(self.pool)
+        .fetch_all(&self.pool)
         .await
         .map_err(Error::Database)?;
 
         Ok(transactions)
     }
 
-    pub async fn get_transaction_by_id(&self, transaction_id: i32) -> Result<Option<Transaction>> {
-        let transaction = sqlx::query_as!(
+    async fn get_archived_page(
+        &self,
+        portfolio_id: i32,
+        filter: &TransactionFilter,
+        after: Option<Uuid>,
+        newest_first: bool,
+        limit: i64,
+    ) -> Result<Vec<Transaction>> {
+        let transactions = sqlx::query_as!(
             Transaction,
             r#"
-            SELECT id, user_id, ticker, quantity, price, transaction_type
+            SELECT id, public_id, user_id, ticker, quantity, price, fee, transaction_type,
+                created_at, updated_at
+            FROM transactions_archive t
+            WHERE portfolio_id = $1 AND deleted_at IS NULL
+                AND ($2::text IS NULL OR ticker = $2)
+                AND ($3::text IS NULL OR transaction_type = $3)
+                AND ($4::date IS NULL OR created_at >= $4)
+                AND ($5::date IS NULL OR created_at < $5 + 1)
+                AND ($6::numeric IS NULL OR price >= $6)
+                AND ($7::numeric IS NULL OR price <= $7)
+                AND ($8::uuid IS NULL OR (
+                    SELECT CASE WHEN $9 THEN t.id < prev.id ELSE t.id > prev.id END
+                    FROM transactions_archive prev
+                    WHERE prev.public_id = $8
+                ))
+            ORDER BY CASE WHEN $9 THEN id END DESC, id
+            LIMIT $10
+            "#,
+            portfolio_id,
+            filter.ticker.as_deref(),
+            filter.transaction_type.as_deref(),
+            filter.from,
+            filter.to,
+            filter.min_price.as_ref(),
+            filter.max_price.as_ref(),
+            after,
+            newest_first,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(transactions)
+    }
+
+    async fn archive_before(&self, cutoff: NaiveDateTime, batch: i32) -> Result<i32> {
+        sqlx::query_scalar!(
+            r#"SELECT archive_transactions($1, $2) AS "moved!""#,
+            cutoff,
+            batch
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    async fn get_most_traded(&self, since: NaiveDateTime, limit: i64) -> Result<Vec<TradedVolume>> {
+        let volumes = sqlx::query_as!(
+            TradedVolume,
+            r#"
+            SELECT ticker, SUM(quantity) AS "volume!", COUNT(*) AS "trades!"
             FROM transactions
-            WHERE id = $1
+            WHERE transaction_type IN ('buy', 'sell') AND created_at >= $1 AND deleted_at IS NULL
+            GROUP BY ticker
+            ORDER BY 2 DESC, ticker
+            LIMIT $2
             "#,
-            transaction_id
+            since,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(volumes)
+    }
+
+    async fn get_transaction_by_public_id(&self, public_id: Uuid) -> Result<Option<Transaction>> {
+        let transaction = sqlx::query_as!(
+            Transaction,
+            r#"
+            SELECT id AS "id!", public_id AS "public_id!", user_id AS "user_id!",
+                ticker AS "ticker!", quantity AS "quantity!", price AS "price!", fee AS "fee!",
+                transaction_type AS "transaction_type!", created_at AS "created_at!",
+                updated_at AS "updated_at!"
+            FROM transaction_history
+            WHERE public_id = $1
+            "#,
+            public_id
         )
-        .fetch_optional(self.pool)
+        .fetch_optional(&self.pool)
         .await
         .map_err(Error::Database)?;
 
         Ok(transaction)
     }
+
+    async fn delete_transaction(&self, transaction_id: i32) -> Result<()> {
+        sqlx::query!(
+            "UPDATE transactions SET deleted_at = NOW(), updated_at = NOW() WHERE id = $1",
+            transaction_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    async fn backdate(&self, transaction_id: i32, at: DateTime<Utc>) -> Result<()> {
+        sqlx::query!(
+            r#"
+            WITH moved AS (
+                UPDATE transactions SET created_at = $2, updated_at = NOW() WHERE id = $1
+            ), lots AS (
+                UPDATE tax_lots SET acquired_at = $3 WHERE transaction_id = $1
+            )
+            UPDATE realized_gains SET realized_at = $3 WHERE sell_transaction_id = $1
+            "#,
+            transaction_id,
+            at.naive_utc(),
+            at
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
 }
@@ -1,82 +1,278 @@
-use bigdecimal::BigDecimal;
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use uuid::Uuid;
 
 use crate::{Error, Result, models::user::User};
 
-pub struct UserRepository<'a> {
-    pool: &'a sqlx::PgPool,
+/// Accounts, by internal ID, public ID or email
+#[async_trait]
+pub trait UserRepo: Send + Sync {
+    async fn create_user(&self, email: &str, password: &str) -> Result<User>;
+
+    /// Remember that the user logged in now, for finding active accounts
+    async fn record_login(&self, user_id: i32) -> Result<()>;
+
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<User>>;
+
+    async fn get_user_by_id(&self, user_id: i32) -> Result<Option<User>>;
+
+    /// The user the API knows as `public_id`
+    async fn get_user_by_public_id(&self, public_id: Uuid) -> Result<Option<User>>;
+
+    /// Public IDs of those of the users that exist, by user ID
+    async fn get_public_ids(&self, user_ids: &[i32]) -> Result<HashMap<i32, Uuid>>;
+
+    /// User IDs of those of the public IDs that belong to a user
+    async fn get_ids_by_public_ids(&self, public_ids: &[Uuid]) -> Result<Vec<i32>>;
+
+    async fn set_role(&self, user_id: i32, role: &str) -> Result<Option<User>>;
+
+    /// Change the password of the user read at `version`, returning `None` if
+    /// the user has changed since
+    async fn set_password(
+        &self,
+        user_id: i32,
+        version: i32,
+        password: &str,
+    ) -> Result<Option<User>>;
+
+    /// Change the email of the user read at `version`, returning `None` if
+    /// the user has changed since
+    async fn set_email(&self, user_id: i32, version: i32, email: &str) -> Result<Option<User>>;
+
+    async fn get_user_ids(&self) -> Result<Vec<i32>>;
+
+    /// Number of registered accounts
+    async fn count_users(&self) -> Result<i64>;
+}
+
+/// [`UserRepo`] on Postgres
+pub struct UserRepository {
+    pool: sqlx::PgPool,
 }
 
-impl<'a> UserRepository<'a> {
-    pub fn new(pool: &'a sqlx::PgPool) -> Self {
-        Self { pool }
+impl UserRepository {
+    pub fn new(pool: &sqlx::PgPool) -> Self {
+        Self { pool: pool.clone() }
     }
+}
 
-    pub async fn create_user(&self, email: &str, password: &str) -> Result<User> {
+#[async_trait]
+impl UserRepo for UserRepository {
+    async fn create_user(&self, email: &str, password: &str) -> Result<User> {
         let user = sqlx::query_as!(
             User,
             r#"
-            INSERT INTO users (email, password, balance)
-            VALUES ($1, $2, 1000.0)
-            RETURNING id, email, password, balance
+            INSERT INTO users (email, password)
+            VALUES ($1, $2)
+            RETURNING id, public_id, email, password, role, created_at, updated_at, version
             "#,
             email,
             password
         )
-        .fetch_one(self.pool)
+        .fetch_one(&self.pool)
         .await
         .map_err(Error::Database)?;
 
         Ok(user)
     }
 
-    pub async fn get_user_by_email(&self, email: &str) -> Result<Option<User>> {
+    async fn record_login(&self, user_id: i32) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET last_login_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    async fn get_user_by_email(&self, email: &str) -> Result<Option<User>> {
         let user = sqlx::query_as!(
             User,
             r#"
-            SELECT id, email, password, balance
+            SELECT id, public_id, email, password, role, created_at, updated_at, version
             FROM users
-            WHERE email = $1
+            WHERE email = $1 AND deleted_at IS NULL
             "#,
             email
         )
-        .fetch_optional(self.pool)
+        .fetch_optional(&self.pool)
         .await
         .map_err(Error::Database)?;
 
         Ok(user)
     }
 
-    pub async fn get_user_by_id(&self, user_id: i32) -> Result<Option<User>> {
+    async fn get_user_by_id(&self, user_id: i32) -> Result<Option<User>> {
         let user = sqlx::query_as!(
             User,
             r#"
-            SELECT id, email, password, balance
+            SELECT id, public_id, email, password, role, created_at, updated_at, version
             FROM users
-            WHERE id = $1
+            WHERE id = $1 AND deleted_at IS NULL
             "#,
             user_id
         )
-        .fetch_optional(self.pool)
+        .fetch_optional(&self.pool)
         .await
         .map_err(Error::Database)?;
 
         Ok(user)
     }
 
-    pub async fn update_user_balance(&self, user_id: i32, new_balance: BigDecimal) -> Result<()> {
-        sqlx::query!(
+    async fn get_user_by_public_id(&self, public_id: Uuid) -> Result<Option<User>> {
+        let user = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id, public_id, email, password, role, created_at, updated_at, version
+            FROM users
+            WHERE public_id = $1 AND deleted_at IS NULL
+            "#,
+            public_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(user)
+    }
+
+    async fn get_public_ids(&self, user_ids: &[i32]) -> Result<HashMap<i32, Uuid>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, public_id
+            FROM users
+            WHERE id = ANY($1) AND deleted_at IS NULL
+            "#,
+            user_ids
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.id, row.public_id))
+            .collect())
+    }
+
+    async fn get_ids_by_public_ids(&self, public_ids: &[Uuid]) -> Result<Vec<i32>> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT id
+            FROM users
+            WHERE public_id = ANY($1) AND deleted_at IS NULL
+            ORDER BY id
+            "#,
+            public_ids
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(ids)
+    }
+
+    async fn set_role(&self, user_id: i32, role: &str) -> Result<Option<User>> {
+        let user = sqlx::query_as!(
+            User,
             r#"
             UPDATE users
-            SET balance = $1
-            WHERE id = $2
+            SET role = $2, updated_at = NOW(), version = version + 1
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING id, public_id, email, password, role, created_at, updated_at, version
             "#,
-            new_balance,
-            user_id
+            user_id,
+            role
         )
-        .execute(self.pool)
+        .fetch_optional(&self.pool)
         .await
         .map_err(Error::Database)?;
 
-        Ok(())
+        Ok(user)
+    }
+
+    async fn set_password(
+        &self,
+        user_id: i32,
+        version: i32,
+        password: &str,
+    ) -> Result<Option<User>> {
+        let user = sqlx::query_as!(
+            User,
+            r#"
+            UPDATE users
+            SET password = $2, updated_at = NOW(), version = version + 1
+            WHERE id = $1 AND version = $3 AND deleted_at IS NULL
+            RETURNING id, public_id, email, password, role, created_at, updated_at, version
+            "#,
+            user_id,
+            password,
+            version
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(user)
+    }
+
+    async fn set_email(&self, user_id: i32, version: i32, email: &str) -> Result<Option<User>> {
+        let user = sqlx::query_as!(
+            User,
+            r#"
+            UPDATE users
+            SET email = $2, updated_at = NOW(), version = version + 1
+            WHERE id = $1 AND version = $3 AND deleted_at IS NULL
+            RETURNING id, public_id, email, password, role, created_at, updated_at, version
+            "#,
+            user_id,
+            email,
+            version
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(user)
+    }
+
+    async fn get_user_ids(&self) -> Result<Vec<i32>> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT id
+            FROM users
+            WHERE deleted_at IS NULL
+            ORDER BY id
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(ids)
+    }
+
+    async fn count_users(&self) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM users
+            WHERE deleted_at IS NULL
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(count)
     }
 }
@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::{
+        difficulty::Difficulty,
+        user_settings::{CostBasisMethod, UserSettings},
+    },
+};
+
+pub struct UserSettingsRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> UserSettingsRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        UserSettingsRepository { pool }
+    }
+
+    pub async fn get_settings(&self, user_id: i32) -> Result<Option<UserSettings>> {
+        let settings = sqlx::query_as!(
+            UserSettings,
+            r#"
+            SELECT cost_basis_method, cash_sweep_enabled, drip_enabled, benchmark_ticker,
+                   display_name, base_currency, notify_order_fills, notify_margin_calls,
+                   notify_deposits, ui_settings, leaderboard_opt_out, trade_delay_minutes,
+                   hide_trade_quantities, difficulty, updated_at
+            FROM user_settings
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(settings)
+    }
+
+    /// The user's cost-basis method, falling back to the default when never set
+    pub async fn get_cost_basis_method(&self, user_id: i32) -> Result<CostBasisMethod> {
+        match self.get_settings(user_id).await? {
+            Some(settings) => settings.cost_basis_method.parse().map_err(|e| {
+                tracing::error!("Invalid cost basis method for user ID {}: {}", user_id, e);
+                Error::InternalServerError
+            }),
+            None => Ok(CostBasisMethod::default()),
+        }
+    }
+
+    pub async fn set_cost_basis_method(
+        &self,
+        user_id: i32,
+        cost_basis_method: CostBasisMethod,
+    ) -> Result<UserSettings> {
+        let settings = sqlx::query_as!(
+            UserSettings,
+            r#"
+            INSERT INTO user_settings (user_id, cost_basis_method)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id)
+            DO UPDATE SET cost_basis_method = EXCLUDED.cost_basis_method, updated_at = NOW()
+            RETURNING cost_basis_method, cash_sweep_enabled, drip_enabled, benchmark_ticker,
+                      display_name, base_currency, notify_order_fills, notify_margin_calls,
+                      notify_deposits, ui_settings, leaderboard_opt_out, trade_delay_minutes,
+                      hide_trade_quantities, difficulty, updated_at
+            "#,
+            user_id,
+            cost_basis_method.as_str()
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(settings)
+    }
+
+    pub async fn set_cash_sweep_enabled(
+        &self,
+        user_id: i32,
+        enabled: bool,
+    ) -> Result<UserSettings> {
+        let settings = sqlx::query_as!(
+            UserSettings,
+            r#"
+            INSERT INTO user_settings (user_id, cash_sweep_enabled)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id)
+            DO UPDATE SET cash_sweep_enabled = EXCLUDED.cash_sweep_enabled, updated_at = NOW()
+            RETURNING cost_basis_method, cash_sweep_enabled, drip_enabled, benchmark_ticker,
+                      display_name, base_currency, notify_order_fills, notify_margin_calls,
+                      notify_deposits, ui_settings, leaderboard_opt_out, trade_delay_minutes,
+                      hide_trade_quantities, difficulty, updated_at
+            "#,
+            user_id,
+            enabled
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(settings)
+    }
+
+    pub async fn set_drip_enabled(&self, user_id: i32, enabled: bool) -> Result<UserSettings> {
+        let settings = sqlx::query_as!(
+            UserSettings,
+            r#"
+            INSERT INTO user_settings (user_id, drip_enabled)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id)
+            DO UPDATE SET drip_enabled = EXCLUDED.drip_enabled, updated_at = NOW()
+            RETURNING cost_basis_method, cash_sweep_enabled, drip_enabled, benchmark_ticker,
+                      display_name, base_currency, notify_order_fills, notify_margin_calls,
+                      notify_deposits, ui_settings, leaderboard_opt_out, trade_delay_minutes,
+                      hide_trade_quantities, difficulty, updated_at
+            "#,
+            user_id,
+            enabled
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(settings)
+    }
+
+    /// Set the ticker the user's performance is compared against; `None` clears it
+    pub async fn set_benchmark_ticker(
+        &self,
+        user_id: i32,
+        ticker: Option<&str>,
+    ) -> Result<UserSettings> {
+        let settings = sqlx::query_as!(
+            UserSettings,
+            r#"
+            INSERT INTO user_settings (user_id, benchmark_ticker)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id)
+            DO UPDATE SET benchmark_ticker = EXCLUDED.benchmark_ticker, updated_at = NOW()
+            RETURNING cost_basis_method, cash_sweep_enabled, drip_enabled, benchmark_ticker,
+                      display_name, base_currency, notify_order_fills, notify_margin_calls,
+                      notify_deposits, ui_settings, leaderboard_opt_out, trade_delay_minutes,
+                      hide_trade_quantities, difficulty, updated_at
+            "#,
+            user_id,
+            ticker
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(settings)
+    }
+
+    /// Set the name shown instead of the email; `None` clears it
+    pub async fn set_display_name(
+        &self,
+        user_id: i32,
+        display_name: Option<&str>,
+    ) -> Result<UserSettings> {
+        let settings = sqlx::query_as!(
+            UserSettings,
+            r#"
+            INSERT INTO user_settings (user_id, display_name)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id)
+            DO UPDATE SET display_name = EXCLUDED.display_name, updated_at = NOW()
+            RETURNING cost_basis_method, cash_sweep_enabled, drip_enabled, benchmark_ticker,
+                      display_name, base_currency, notify_order_fills, notify_margin_calls,
+                      notify_deposits, ui_settings, leaderboard_opt_out, trade_delay_minutes,
+                      hide_trade_quantities, difficulty, updated_at
+            "#,
+            user_id,
+            display_name
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(settings)
+    }
+
+    pub async fn set_base_currency(&self, user_id: i32, currency: &str) -> Result<UserSettings> {
+        let settings = sqlx::query_as!(
+            UserSettings,
+            r#"
+            INSERT INTO user_settings (user_id, base_currency)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id)
+            DO UPDATE SET base_currency = EXCLUDED.base_currency, updated_at = NOW()
+            RETURNING cost_basis_method, cash_sweep_enabled, drip_enabled, benchmark_ticker,
+                      display_name, base_currency, notify_order_fills, notify_margin_calls,
+                      notify_deposits, ui_settings, leaderboard_opt_out, trade_delay_minutes,
+                      hide_trade_quantities, difficulty, updated_at
+            "#,
+            user_id,
+            currency
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(settings)
+    }
+
+    /// Choose which account events are pushed to the user
+    pub async fn set_notifications(
+        &self,
+        user_id: i32,
+        order_fills: bool,
+        margin_calls: bool,
+        deposits: bool,
+    ) -> Result<UserSettings> {
+        let settings = sqlx::query_as!(
+            UserSettings,
+            r#"
+            INSERT INTO user_settings (user_id, notify_order_fills, notify_margin_calls,
+                                       notify_deposits)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id)
+            DO UPDATE SET notify_order_fills = EXCLUDED.notify_order_fills,
+                          notify_margin_calls = EXCLUDED.notify_margin_calls,
+                          notify_deposits = EXCLUDED.notify_deposits,
+                          updated_at = NOW()
+            RETURNING cost_basis_method, cash_sweep_enabled, drip_enabled, benchmark_ticker,
+                      display_name, base_currency, notify_order_fills, notify_margin_calls,
+                      notify_deposits, ui_settings, leaderboard_opt_out, trade_delay_minutes,
+                      hide_trade_quantities, difficulty, updated_at
+            "#,
+            user_id,
+            order_fills,
+            margin_calls,
+            deposits
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(settings)
+    }
+
+    pub async fn set_ui_settings(
+        &self,
+        user_id: i32,
+        ui_settings: &serde_json::Value,
+    ) -> Result<UserSettings> {
+        let settings = sqlx::query_as!(
+            UserSettings,
+            r#"
+            INSERT INTO user_settings (user_id, ui_settings)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id)
+            DO UPDATE SET ui_settings = EXCLUDED.ui_settings, updated_at = NOW()
+            RETURNING cost_basis_method, cash_sweep_enabled, drip_enabled, benchmark_ticker,
+                      display_name, base_currency, notify_order_fills, notify_margin_calls,
+                      notify_deposits, ui_settings, leaderboard_opt_out, trade_delay_minutes,
+                      hide_trade_quantities, difficulty, updated_at
+            "#,
+            user_id,
+            ui_settings
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(settings)
+    }
+
+    /// Keep the user off the leaderboard, or put them back on
+    pub async fn set_leaderboard_opt_out(
+        &self,
+        user_id: i32,
+        opt_out: bool,
+    ) -> Result<UserSettings> {
+        let settings = sqlx::query_as!(
+            UserSettings,
+            r#"
+            INSERT INTO user_settings (user_id, leaderboard_opt_out)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id)
+            DO UPDATE SET leaderboard_opt_out = EXCLUDED.leaderboard_opt_out, updated_at = NOW()
+            RETURNING cost_basis_method, cash_sweep_enabled, drip_enabled, benchmark_ticker,
+                      display_name, base_currency, notify_order_fills, notify_margin_calls,
+                      notify_deposits, ui_settings, leaderboard_opt_out, trade_delay_minutes,
+                      hide_trade_quantities, difficulty, updated_at
+            "#,
+            user_id,
+            opt_out
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(settings)
+    }
+
+    /// How the user's public trades show up in their followers' feeds
+    pub async fn set_trade_privacy(
+        &self,
+        user_id: i32,
+        trade_delay_minutes: i32,
+        hide_trade_quantities: bool,
+    ) -> Result<UserSettings> {
+        let settings = sqlx::query_as!(
+            UserSettings,
+            r#"
+            INSERT INTO user_settings (user_id, trade_delay_minutes, hide_trade_quantities)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id)
+            DO UPDATE SET trade_delay_minutes = EXCLUDED.trade_delay_minutes,
+                          hide_trade_quantities = EXCLUDED.hide_trade_quantities,
+                          updated_at = NOW()
+            RETURNING cost_basis_method, cash_sweep_enabled, drip_enabled, benchmark_ticker,
+                      display_name, base_currency, notify_order_fills, notify_margin_calls,
+                      notify_deposits, ui_settings, leaderboard_opt_out, trade_delay_minutes,
+                      hide_trade_quantities, difficulty, updated_at
+            "#,
+            user_id,
+            trade_delay_minutes,
+            hide_trade_quantities
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(settings)
+    }
+
+    /// The user's difficulty, falling back to the default when never set
+    pub async fn get_difficulty(&self, user_id: i32) -> Result<Difficulty> {
+        match self.get_settings(user_id).await? {
+            Some(settings) => settings.difficulty.parse().map_err(|e| {
+                tracing::error!("Invalid difficulty for user ID {}: {}", user_id, e);
+                Error::InternalServerError
+            }),
+            None => Ok(Difficulty::default()),
+        }
+    }
+
+    pub async fn set_difficulty(
+        &self,
+        user_id: i32,
+        difficulty: Difficulty,
+    ) -> Result<UserSettings> {
+        let settings = sqlx::query_as!(
+            UserSettings,
+            r#"
+            INSERT INTO user_settings (user_id, difficulty)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id)
+            DO UPDATE SET difficulty = EXCLUDED.difficulty, updated_at = NOW()
+            RETURNING cost_basis_method, cash_sweep_enabled, drip_enabled, benchmark_ticker,
+                      display_name, base_currency, notify_order_fills, notify_margin_calls,
+                      notify_deposits, ui_settings, leaderboard_opt_out, trade_delay_minutes,
+                      hide_trade_quantities, difficulty, updated_at
+            "#,
+            user_id,
+            difficulty.as_str()
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(settings)
+    }
+
+    /// Users that have not opted out of the leaderboard
+    pub async fn get_leaderboard_user_ids(&self) -> Result<Vec<i32>> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT u.id
+            FROM users u
+            LEFT JOIN user_settings s ON s.user_id = u.id
+            WHERE NOT COALESCE(s.leaderboard_opt_out, FALSE) AND u.deleted_at IS NULL
+            ORDER BY u.id
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(ids)
+    }
+
+    /// Display names of those of the users that set one
+    pub async fn get_display_names(&self, user_ids: &[i32]) -> Result<HashMap<i32, String>> {
+        let names = sqlx::query!(
+            r#"
+            SELECT user_id, display_name AS "display_name!"
+            FROM user_settings
+            WHERE user_id = ANY($1) AND display_name IS NOT NULL
+            "#,
+            user_ids
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(names
+            .into_iter()
+            .map(|row| (row.user_id, row.display_name))
+            .collect())
+    }
+
+    /// Every ticker selected as a benchmark by at least one user
+    pub async fn get_benchmark_tickers(&self) -> Result<Vec<String>> {
+        let tickers = sqlx::query_scalar!(
+            r#"
+            SELECT DISTINCT benchmark_ticker AS "ticker!"
+            FROM user_settings
+            WHERE benchmark_ticker IS NOT NULL
+            ORDER BY 1
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(tickers)
+    }
+
+    /// Users that opted into the nightly cash sweep
+    pub async fn get_cash_sweep_user_ids(&self) -> Result<Vec<i32>> {
+        let ids = sqlx::query_scalar!(
+            r#"
+            SELECT user_id
+            FROM user_settings
+            WHERE cash_sweep_enabled
+            ORDER BY user_id
+            "#
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(ids)
+    }
+}
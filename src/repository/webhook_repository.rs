@@ -0,0 +1,231 @@
+use sqlx::PgPool;
+
+use crate::{
+    Error, Result,
+    models::webhook::{DueDelivery, Webhook, WebhookDelivery},
+};
+
+pub struct WebhookRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> WebhookRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        WebhookRepository { pool }
+    }
+
+    pub async fn create_webhook(
+        &self,
+        user_id: i32,
+        url: &str,
+        secret: &str,
+        events: &[String],
+    ) -> Result<Webhook> {
+        sqlx::query_as!(
+            Webhook,
+            r#"
+            INSERT INTO webhooks (user_id, url, secret, events)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, url, secret, events, created_at
+            "#,
+            user_id,
+            url,
+            secret,
+            events
+        )
+        .fetch_one(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    pub async fn get_webhooks_by_user(&self, user_id: i32) -> Result<Vec<Webhook>> {
+        sqlx::query_as!(
+            Webhook,
+            r#"
+            SELECT id, url, secret, events, created_at
+            FROM webhooks
+            WHERE user_id = $1
+            ORDER BY id
+            "#,
+            user_id
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    pub async fn get_webhook(&self, webhook_id: i32, user_id: i32) -> Result<Option<Webhook>> {
+        sqlx::query_as!(
+            Webhook,
+            r#"
+            SELECT id, url, secret, events, created_at
+            FROM webhooks
+            WHERE id = $1 AND user_id = $2
+            "#,
+            webhook_id,
+            user_id
+        )
+        .fetch_optional(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    /// Delete a webhook of `user_id` with its deliveries, returning whether it existed
+    pub async fn delete_webhook(&self, webhook_id: i32, user_id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM webhooks WHERE id = $1 AND user_id = $2",
+            webhook_id,
+            user_id
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Queue `payload` for every webhook of `user_id` subscribed to `event`
+    pub async fn enqueue_deliveries(
+        &self,
+        user_id: i32,
+        event: &str,
+        payload: &serde_json::Value,
+    ) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO webhook_deliveries (webhook_id, event, payload)
+            SELECT id, $2::TEXT, $3
+            FROM webhooks
+            WHERE user_id = $1 AND $2 = ANY(events)
+            "#,
+            user_id,
+            event,
+            payload
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Deliveries of a webhook newest first, starting below the ID `before`
+    pub async fn get_deliveries(
+        &self,
+        webhook_id: i32,
+        before: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>> {
+        sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            SELECT id, event, payload, status, attempts, next_attempt_at,
+                   response_status, error, created_at, delivered_at
+            FROM webhook_deliveries
+            WHERE webhook_id = $1 AND ($2::BIGINT IS NULL OR id < $2)
+            ORDER BY id DESC
+            LIMIT $3
+            "#,
+            webhook_id,
+            before,
+            limit
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    /// Claim up to `limit` pending deliveries that are due, postponing them by
+    /// `lease_secs` so that no other instance attempts them meanwhile
+    pub async fn claim_due_deliveries(
+        &self,
+        limit: i64,
+        lease_secs: f64,
+    ) -> Result<Vec<DueDelivery>> {
+        sqlx::query_as!(
+            DueDelivery,
+            r#"
+            WITH due AS (
+                SELECT id
+                FROM webhook_deliveries
+                WHERE status = 'pending' AND next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE webhook_deliveries d
+            SET next_attempt_at = NOW() + make_interval(secs => $2)
+            FROM due, webhooks w
+            WHERE d.id = due.id AND w.id = d.webhook_id
+            RETURNING d.id, d.event, d.payload, d.attempts, w.url, w.secret
+            "#,
+            limit,
+            lease_secs
+        )
+        .fetch_all(self.pool)
+        .await
+        .map_err(Error::Database)
+    }
+
+    pub async fn mark_delivered(&self, delivery_id: i64, response_status: i32) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'delivered', attempts = attempts + 1, response_status = $2,
+                error = NULL, delivered_at = NOW()
+            WHERE id = $1
+            "#,
+            delivery_id,
+            response_status
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    /// Record a failed attempt, retrying after `retry_in_secs` or giving up when unset
+    pub async fn mark_failed(
+        &self,
+        delivery_id: i64,
+        response_status: Option<i32>,
+        error: &str,
+        retry_in_secs: Option<f64>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET attempts = attempts + 1, response_status = $2, error = $3,
+                status = CASE WHEN $4::FLOAT8 IS NULL THEN 'failed' ELSE 'pending' END,
+                next_attempt_at = NOW() + make_interval(secs => COALESCE($4, 0))
+            WHERE id = $1
+            "#,
+            delivery_id,
+            response_status,
+            error,
+            retry_in_secs
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(())
+    }
+
+    /// Delete finished deliveries created before `days` ago, returning how many
+    pub async fn delete_finished_deliveries(&self, days: i32) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM webhook_deliveries
+            WHERE status <> 'pending' AND created_at < NOW() - make_interval(days => $1)
+            "#,
+            days
+        )
+        .execute(self.pool)
+        .await
+        .map_err(Error::Database)?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,77 @@
+//! # Request IDs
+//!
+//! Every request gets an ID, taken from its `X-Request-Id` header when the
+//! client or a proxy sent a usable one and generated otherwise. The ID is
+//! echoed in the `X-Request-Id` response header, recorded on the `request`
+//! span every log line of the request is emitted in, and added to error
+//! bodies as `request_id`.
+//!
+//! While a request is handled, [`current`] returns its ID, so that work it
+//! hands to other systems can carry it along: account events and mail
+//! published on Redis include it, and the gRPC price feed receives the ID
+//! of its subscription as `x-request-id` metadata.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+/// Longest accepted client-provided ID
+const MAX_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// ID of the request being handled by the current task
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// ID of the current request, or a new one for work no request started
+pub fn current_or_new() -> String {
+    current().unwrap_or_else(new_id)
+}
+
+fn new_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// Whether a client-provided ID is safe to log and echo
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LENGTH
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// Middleware assigning every request its ID
+pub async fn request_id(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid(id))
+        .map_or_else(new_id, str::to_string);
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    let mut response = REQUEST_ID
+        .scope(id.clone(), next.run(request).instrument(span))
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
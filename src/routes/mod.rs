@@ -1,14 +1,70 @@
-use axum::Router;
+//! # Routes
+//!
+//! The REST API is versioned by path: version 1 is served under `/api/v1`.
+//! Every version is a module of its own, such as [`v1`], holding the route
+//! modules with their handlers and request and response types, so the shapes
+//! a version exchanges stay fixed once it ships. A breaking change starts a
+//! new version module that reuses the unchanged route modules of the previous
+//! one and replaces the changed ones, while the previous version keeps
+//! serving its clients under its own prefix.
+//!
+//! Paths from before versioning are still served without a prefix, by the
+//! version 1 handlers. Their responses carry a `Deprecation` header and a
+//! `Link` to the versioned path, so clients can find and migrate their calls.
 
-mod auth;
-mod balance;
-mod holdings;
-mod transactions;
+use axum::{
+    Router,
+    extract::Request,
+    http::{HeaderValue, header},
+    middleware::{self, Next},
+    response::Response,
+};
 
+mod v1;
+
+/// Prefix of API version 1
+pub const V1_PREFIX: &str = "/api/v1";
+
+/// Every route but the admin API
 pub fn routes() -> Router {
     Router::new()
-        .nest("/auth", auth::routes())
-        .nest("/balance", balance::routes())
-        .nest("/transactions", transactions::routes())
-        .nest("/holdings", holdings::routes())
+        .nest(V1_PREFIX, v1::routes())
+        .merge(unversioned(v1::routes()))
+}
+
+/// The admin API, served by [`routes`]' listener unless it has one of its own
+pub fn admin_routes() -> Router {
+    Router::new()
+        .nest(V1_PREFIX, v1::admin_routes())
+        .merge(unversioned(v1::admin_routes()))
+}
+
+/// OpenAPI description of [`routes`] and [`admin_routes`], documenting only
+/// the versioned paths
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    utoipa::openapi::OpenApiBuilder::new()
+        .build()
+        .nest(V1_PREFIX, v1::openapi())
+}
+
+/// Serve `router` at its paths from before versioning, marked deprecated
+fn unversioned(router: Router) -> Router {
+    router.layer(middleware::from_fn(deprecated))
+}
+
+/// Point responses of unversioned paths to their version 1 successor
+async fn deprecated(request: Request, next: Next) -> Response {
+    let successor = format!(
+        "<{}{}>; rel=\"successor-version\"",
+        V1_PREFIX,
+        request.uri().path()
+    );
+    let mut response = next.run(request).await;
+
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.insert(header::LINK, link);
+    }
+    response
 }
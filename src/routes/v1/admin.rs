@@ -0,0 +1,2815 @@
+use axum::{
+    Extension, Router,
+    extract::{DefaultBodyLimit, Path, Query},
+    routing::{delete, get, patch, post, put},
+};
+use std::collections::HashMap;
+
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode, Zero};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::{admin::AdminKey, sessions},
+    models::{
+        announcement::Announcement,
+        basket::BasketConstituent,
+        bond::Bond,
+        bot::{Bot, BotStrategy},
+        competition::Competition,
+        corporate_action::CorporateAction,
+        difficulty::Difficulty,
+        dividend::Dividend,
+        feature_flag::FeatureFlag,
+        instrument::{Instrument, InstrumentDetails},
+        liquidity_profile::LiquidityProfile,
+        matching_config::MatchingConfig,
+        news_event::{NewsDetails, NewsEvent},
+        option::OptionContract,
+        price_candle::{CandleInterval, PriceCandle},
+        replay::{Replay, ReplayTick},
+        transaction::TradedVolume,
+        user::{Role, User},
+    },
+    repository::{
+        announcement_repository::AnnouncementRepository, basket_repository::BasketRepository,
+        bond_repository::BondRepository, bot_repository::BotRepository,
+        competition_repository::CompetitionRepository,
+        corporate_action_repository::CorporateActionRepository,
+        dividend_repository::DividendRepository, feature_flag_repository::FeatureFlagRepository,
+        instrument_repository::InstrumentRepository,
+        liquidity_profile_repository::LiquidityProfileRepository, news_repository::NewsRepository,
+        option_repository::OptionRepository, price_candle_repository::PriceCandleRepository,
+        replay_repository::ReplayRepository,
+    },
+    services::{
+        archive, baskets, bonds, bots, feature_flags, instruments, matching, options,
+        price_updater::{self, FeedStatus},
+        replay, user_cache,
+    },
+    timing::Json,
+    ws::{announcements, limits},
+};
+
+/// Largest accepted candle backfill upload, in bytes
+const MAX_BACKFILL_SIZE: usize = 16 * 1024 * 1024;
+/// Most candles a single backfill may load
+const MAX_BACKFILL_CANDLES: usize = 100_000;
+/// Most ticks a single replay may hold
+const MAX_REPLAY_TICKS: usize = 500_000;
+/// Most constituents a basket may hold
+const MAX_CONSTITUENTS: u64 = 100;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_matching_config,
+    update_matching_config,
+    get_instruments,
+    get_instrument,
+    create_instrument,
+    update_instrument,
+    delete_instrument,
+    set_basket,
+    delete_basket,
+    get_bonds,
+    set_bond_terms,
+    override_price,
+    backfill_candles,
+    get_liquidity_profiles,
+    upsert_liquidity_profile,
+    delete_liquidity_profile,
+    get_feature_flags,
+    upsert_feature_flag,
+    delete_feature_flag,
+    get_dividends,
+    create_dividend,
+    delete_dividend,
+    create_option_contract,
+    delete_option_contract,
+    create_competition,
+    delete_competition,
+    get_corporate_actions,
+    create_corporate_action,
+    delete_corporate_action,
+    get_news,
+    create_news,
+    delete_news,
+    get_announcements,
+    create_announcement,
+    delete_announcement,
+    update_user_role,
+    get_bots,
+    create_bot,
+    update_bot,
+    get_replays,
+    create_replay,
+    start_replay,
+    stop_replay,
+    delete_replay,
+    get_stats,
+    archive_transactions
+))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route(
+            "/matching/config",
+            get(get_matching_config).put(update_matching_config),
+        )
+        .route("/instruments", get(get_instruments).post(create_instrument))
+        .route(
+            "/instruments/{ticker}",
+            get(get_instrument)
+                .put(update_instrument)
+                .delete(delete_instrument),
+        )
+        .route(
+            "/instruments/{ticker}/basket",
+            put(set_basket).delete(delete_basket),
+        )
+        .route("/bonds", get(get_bonds))
+        .route("/bonds/{ticker}", put(set_bond_terms))
+        .route("/prices/{ticker}", post(override_price))
+        .route(
+            "/prices/{ticker}/candles",
+            post(backfill_candles).layer(DefaultBodyLimit::max(MAX_BACKFILL_SIZE)),
+        )
+        .route("/liquidity", get(get_liquidity_profiles))
+        .route(
+            "/liquidity/{ticker}",
+            put(upsert_liquidity_profile).delete(delete_liquidity_profile),
+        )
+        .route("/feature-flags", get(get_feature_flags))
+        .route(
+            "/feature-flags/{name}",
+            put(upsert_feature_flag).delete(delete_feature_flag),
+        )
+        .route("/dividends", get(get_dividends).post(create_dividend))
+        .route("/dividends/{id}", delete(delete_dividend))
+        .route("/options", post(create_option_contract))
+        .route("/options/{id}", delete(delete_option_contract))
+        .route("/competitions", post(create_competition))
+        .route("/competitions/{id}", delete(delete_competition))
+        .route(
+            "/corporate-actions",
+            get(get_corporate_actions).post(create_corporate_action),
+        )
+        .route("/corporate-actions/{id}", delete(delete_corporate_action))
+        .route("/news", get(get_news).post(create_news))
+        .route("/news/{id}", delete(delete_news))
+        .route(
+            "/announcements",
+            get(get_announcements).post(create_announcement),
+        )
+        .route("/announcements/{id}", delete(delete_announcement))
+        .route("/users/{id}/role", put(update_user_role))
+        .route("/bots", get(get_bots).post(create_bot))
+        .route("/bots/{id}", patch(update_bot))
+        .route(
+            "/replays",
+            get(get_replays)
+                .post(create_replay)
+                .layer(DefaultBodyLimit::max(MAX_BACKFILL_SIZE)),
+        )
+        .route("/replays/{id}", delete(delete_replay))
+        .route("/replays/{id}/start", post(start_replay))
+        .route("/replays/{id}/stop", post(stop_replay))
+        .route("/stats", get(get_stats))
+        .route("/transactions/archive", post(archive_transactions))
+}
+
+/// Get the active matching parameters
+#[utoipa::path(
+    get,
+    path = "/matching/config",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Active matching parameters", body = MatchingConfigResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn get_matching_config(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+) -> Result<Json<MatchingConfigResponse>> {
+    Ok(Json(matching::current(&state).into()))
+}
+
+/// Update the matching parameters
+///
+/// The new values are persisted and hot-reloaded by every running instance,
+/// taking effect on the next tick or trade without a restart.
+#[utoipa::path(
+    put,
+    path = "/matching/config",
+    tag = "admin",
+    request_body = UpdateMatchingConfigRequest,
+    responses(
+        (status = 200, description = "Updated matching parameters", body = MatchingConfigResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn update_matching_config(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Json(payload): Json<UpdateMatchingConfigRequest>,
+) -> Result<Json<MatchingConfigResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let config = matching::update(
+        &state,
+        payload.price_band_percent,
+        payload.max_order_quantity,
+        payload.volatility_halt_percent,
+        payload.halt_duration_secs,
+    )
+    .await?;
+
+    tracing::info!("Matching config updated by admin: {:?}", config);
+
+    Ok(Json(config.into()))
+}
+
+/// List the instrument catalog
+#[utoipa::path(
+    get,
+    path = "/instruments",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Instrument catalog", body = Vec<InstrumentResponse>),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn get_instruments(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<InstrumentResponse>>> {
+    let instruments = InstrumentRepository::new(&state.pg_pool)
+        .get_instruments()
+        .await?;
+
+    Ok(Json(instruments.into_iter().map(Into::into).collect()))
+}
+
+/// Get a listed instrument
+#[utoipa::path(
+    get,
+    path = "/instruments/{ticker}",
+    tag = "admin",
+    params(("ticker" = String, Path, description = "Instrument symbol")),
+    responses(
+        (status = 200, description = "Listed instrument", body = InstrumentResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 404, description = "Instrument not listed", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn get_instrument(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(ticker): Path<String>,
+) -> Result<Json<InstrumentResponse>> {
+    let instrument = InstrumentRepository::new(&state.pg_pool)
+        .get_instrument(&ticker.trim().to_uppercase())
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    Ok(Json(instrument.into()))
+}
+
+/// List a new instrument, making its ticker tradable
+#[utoipa::path(
+    post,
+    path = "/instruments",
+    tag = "admin",
+    request_body = CreateInstrumentRequest,
+    responses(
+        (status = 200, description = "Listed instrument", body = InstrumentResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 409, description = "Ticker already listed", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn create_instrument(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateInstrumentRequest>,
+) -> Result<Json<InstrumentResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let ticker = payload.ticker.trim().to_uppercase();
+    if ticker.is_empty() {
+        return Err(Error::BadRequest("Invalid ticker".into()));
+    }
+    let details = payload.details.into_details()?;
+
+    let instrument = InstrumentRepository::new(&state.pg_pool)
+        .create_instrument(&ticker, &details)
+        .await?
+        .ok_or_else(|| Error::Conflict(format!("{} is already listed", ticker)))?;
+
+    tracing::info!("Instrument listed by admin: {:?}", instrument);
+    instruments::announce_catalog_change(&state).await;
+
+    Ok(Json(instrument.into()))
+}
+
+/// Replace the details of a listed instrument
+///
+/// Setting `active` to false stops new buys; holders can still sell.
+#[utoipa::path(
+    put,
+    path = "/instruments/{ticker}",
+    tag = "admin",
+    params(("ticker" = String, Path, description = "Instrument symbol")),
+    request_body = InstrumentDetailsRequest,
+    responses(
+        (status = 200, description = "Updated instrument", body = InstrumentResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 404, description = "Instrument not listed", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn update_instrument(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(ticker): Path<String>,
+    Json(payload): Json<InstrumentDetailsRequest>,
+) -> Result<Json<InstrumentResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+    let details = payload.into_details()?;
+
+    let instrument = InstrumentRepository::new(&state.pg_pool)
+        .update_instrument(&ticker.trim().to_uppercase(), &details)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    tracing::info!("Instrument updated by admin: {:?}", instrument);
+
+    Ok(Json(instrument.into()))
+}
+
+/// Delist an instrument nobody holds
+///
+/// Instruments with open positions can only be deactivated, so holders are
+/// never left with shares they cannot sell.
+#[utoipa::path(
+    delete,
+    path = "/instruments/{ticker}",
+    tag = "admin",
+    params(("ticker" = String, Path, description = "Instrument symbol")),
+    responses(
+        (status = 200, description = "Instrument deleted", body = String),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 404, description = "Instrument not listed", body = ErrorResponse),
+        (status = 409, description = "Instrument has open positions or is in a basket", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn delete_instrument(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(ticker): Path<String>,
+) -> Result<Json<&'static str>> {
+    let ticker = ticker.trim().to_uppercase();
+    let repository = InstrumentRepository::new(&state.pg_pool);
+
+    if repository.get_instrument(&ticker).await?.is_none() {
+        return Err(Error::NotFound);
+    }
+    if !repository.delete_unheld(&ticker).await? {
+        return Err(Error::Conflict(
+            "Instrument has open positions or is in a basket; deactivate it instead".into(),
+        ));
+    }
+    instruments::announce_catalog_change(&state).await;
+
+    Ok(Json("Instrument deleted"))
+}
+
+/// Price an index or ETF from a weighted basket of constituents
+///
+/// Replaces the instrument's constituents. From then on its price is the sum
+/// of the constituents' prices times their weights, updated on every
+/// constituent tick, instead of coming from the price feed.
+#[utoipa::path(
+    put,
+    path = "/instruments/{ticker}/basket",
+    tag = "admin",
+    params(("ticker" = String, Path, description = "Index or ETF symbol")),
+    request_body = BasketRequest,
+    responses(
+        (status = 200, description = "Constituents of the basket", body = Vec<BasketConstituentResponse>),
+        (status = 400, description = "Validation error, an instrument that is not an index or ETF, or an unknown, repeated or basket constituent", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 404, description = "Instrument not listed", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn set_basket(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(ticker): Path<String>,
+    Json(payload): Json<BasketRequest>,
+) -> Result<Json<Vec<BasketConstituentResponse>>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let constituents = payload
+        .constituents
+        .into_iter()
+        .map(|constituent| {
+            let weight = BigDecimal::from_f64(constituent.weight)
+                .ok_or_else(|| Error::BadRequest("Invalid weight format".into()))?
+                .with_scale_round(10, RoundingMode::HalfUp);
+            Ok((constituent.ticker.trim().to_uppercase(), weight))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let ticker = ticker.trim().to_uppercase();
+    let basket = baskets::set_basket(&state, &ticker, constituents).await?;
+
+    tracing::info!("Basket of {} set by admin: {:?}", ticker, basket);
+
+    Ok(Json(basket.into_iter().map(Into::into).collect()))
+}
+
+/// Stop pricing an index or ETF from its basket
+///
+/// The price feed prices the instrument again.
+#[utoipa::path(
+    delete,
+    path = "/instruments/{ticker}/basket",
+    tag = "admin",
+    params(("ticker" = String, Path, description = "Index or ETF symbol")),
+    responses(
+        (status = 200, description = "Basket removed", body = String),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 404, description = "Instrument has no basket", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn delete_basket(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(ticker): Path<String>,
+) -> Result<Json<&'static str>> {
+    let deleted = BasketRepository::new(&state.pg_pool)
+        .delete_basket(&ticker.trim().to_uppercase())
+        .await?;
+    if !deleted {
+        return Err(Error::NotFound);
+    }
+    instruments::announce_catalog_change(&state).await;
+
+    Ok(Json("Basket removed"))
+}
+
+/// List the terms of all bonds, by maturity
+#[utoipa::path(
+    get,
+    path = "/bonds",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Bond terms", body = Vec<BondTermsResponse>),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn get_bonds(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<BondTermsResponse>>> {
+    let bonds = BondRepository::new(&state.pg_pool).get_bonds().await?;
+
+    Ok(Json(bonds.into_iter().map(Into::into).collect()))
+}
+
+/// Set the terms of a listed bond
+///
+/// The instrument must be of the `bond` asset class. Coupon dates step back
+/// from `maturity_date` by 12 / `coupons_per_year` months; the next one after
+/// today is paid first. Terms of a redeemed bond cannot change.
+#[utoipa::path(
+    put,
+    path = "/bonds/{ticker}",
+    tag = "admin",
+    params(("ticker" = String, Path, description = "Bond symbol")),
+    request_body = BondTermsRequest,
+    responses(
+        (status = 200, description = "Bond terms", body = BondTermsResponse),
+        (status = 400, description = "Validation error, an instrument that is not a bond, or a past maturity", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 404, description = "Instrument not listed", body = ErrorResponse),
+        (status = 409, description = "Bond already redeemed", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn set_bond_terms(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(ticker): Path<String>,
+    Json(payload): Json<BondTermsRequest>,
+) -> Result<Json<BondTermsResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let ticker = ticker.trim().to_uppercase();
+    let instrument = InstrumentRepository::new(&state.pg_pool)
+        .get_instrument(&ticker)
+        .await?
+        .ok_or(Error::NotFound)?;
+    if instrument.asset_class != "bond" {
+        return Err(Error::BadRequest(format!("{} is not a bond", ticker)));
+    }
+    if !bonds::COUPON_FREQUENCIES.contains(&payload.coupons_per_year) {
+        return Err(Error::BadRequest(
+            "coupons_per_year must be 1, 2, 4 or 12".into(),
+        ));
+    }
+    let today = Utc::now().date_naive();
+    if payload.maturity_date <= today {
+        return Err(Error::BadRequest(
+            "maturity_date must be in the future".into(),
+        ));
+    }
+    let face_value = BigDecimal::from_f64(payload.face_value)
+        .ok_or_else(|| Error::BadRequest("Invalid face value".into()))?
+        .with_scale_round(2, RoundingMode::HalfUp);
+    let coupon_rate_percent = BigDecimal::from_f64(payload.coupon_rate_percent)
+        .ok_or_else(|| Error::BadRequest("Invalid coupon rate".into()))?
+        .with_scale_round(4, RoundingMode::HalfUp);
+    let next_coupon_date =
+        bonds::next_coupon_after(payload.maturity_date, payload.coupons_per_year, today);
+
+    let bond = BondRepository::new(&state.pg_pool)
+        .upsert_bond(
+            &ticker,
+            face_value,
+            coupon_rate_percent,
+            payload.coupons_per_year,
+            payload.maturity_date,
+            next_coupon_date,
+        )
+        .await?
+        .ok_or_else(|| Error::Conflict(format!("{} has been redeemed", ticker)))?;
+
+    tracing::info!("Bond terms set by admin: {:?}", bond);
+
+    Ok(Json(bond.into()))
+}
+
+/// Set or correct the price of a listed instrument
+///
+/// The price is cached, published to subscribers and folded into the
+/// candles exactly like a feed price, bypassing the price bands and
+/// volatility halts. The feed overwrites it with its next update.
+#[utoipa::path(
+    post,
+    path = "/prices/{ticker}",
+    tag = "admin",
+    params(("ticker" = String, Path, description = "Instrument symbol")),
+    request_body = OverridePriceRequest,
+    responses(
+        (status = 200, description = "Stored price", body = PriceOverrideResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 404, description = "Instrument not listed", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn override_price(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(ticker): Path<String>,
+    Json(payload): Json<OverridePriceRequest>,
+) -> Result<Json<PriceOverrideResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let ticker = ticker.trim().to_uppercase();
+    if InstrumentRepository::new(&state.pg_pool)
+        .get_instrument(&ticker)
+        .await?
+        .is_none()
+    {
+        return Err(Error::NotFound);
+    }
+
+    let mut conn = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let timestamp = Utc::now();
+    price_updater::store_price(&state, &mut conn, &ticker, payload.price, timestamp).await?;
+
+    tracing::info!("Price of {} set to {} by admin", ticker, payload.price);
+
+    Ok(Json(PriceOverrideResponse {
+        ticker,
+        price: payload.price,
+        timestamp,
+    }))
+}
+
+/// Load historical candles of a listed instrument from a CSV upload
+///
+/// The body is CSV with a `timestamp,open,high,low,close` header and one
+/// candle of `interval` (default `1d`) per line. Timestamps are RFC 3339 or
+/// dates, and must start a bucket of the interval in the past. Candles of the
+/// same buckets are replaced.
+#[utoipa::path(
+    post,
+    path = "/prices/{ticker}/candles",
+    tag = "admin",
+    params(("ticker" = String, Path, description = "Instrument symbol"), BackfillQuery),
+    request_body(content = String, content_type = "text/csv", description = "`timestamp,open,high,low,close` header and one candle per line"),
+    responses(
+        (status = 200, description = "Loaded candles", body = BackfillResponse),
+        (status = 400, description = "Malformed CSV", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 404, description = "Instrument not listed", body = ErrorResponse),
+        (status = 413, description = "Request body too large", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn backfill_candles(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(ticker): Path<String>,
+    Query(query): Query<BackfillQuery>,
+    body: String,
+) -> Result<Json<BackfillResponse>> {
+    let ticker = ticker.trim().to_uppercase();
+    if InstrumentRepository::new(&state.pg_pool)
+        .get_instrument(&ticker)
+        .await?
+        .is_none()
+    {
+        return Err(Error::NotFound);
+    }
+
+    let interval = query.interval.unwrap_or(CandleInterval::OneDay);
+    let candles = parse_candles_csv(&body, interval)?;
+    let loaded = PriceCandleRepository::new(&state.pg_pool)
+        .backfill(&ticker, interval, &candles)
+        .await?;
+
+    tracing::info!(
+        "Backfilled {} {} candles of {} by admin",
+        loaded,
+        interval.as_str(),
+        ticker
+    );
+
+    Ok(Json(BackfillResponse {
+        ticker,
+        interval,
+        candles: loaded,
+    }))
+}
+
+/// Candles of a backfill upload, with errors naming the offending line
+fn parse_candles_csv(body: &str, interval: CandleInterval) -> Result<Vec<PriceCandle>> {
+    let mut lines = body
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let header: Vec<String> = match lines.next() {
+        Some((_, header)) => header
+            .split(',')
+            .map(|column| column.trim().to_lowercase())
+            .collect(),
+        None => return Err(Error::BadRequest("CSV upload is empty".into())),
+    };
+    if header != ["timestamp", "open", "high", "low", "close"] {
+        return Err(Error::BadRequest(
+            "CSV header must be timestamp,open,high,low,close".into(),
+        ));
+    }
+
+    let now = Utc::now();
+    let mut candles = Vec::new();
+    for (number, line) in lines {
+        if candles.len() == MAX_BACKFILL_CANDLES {
+            return Err(Error::BadRequest(format!(
+                "CSV upload has more than {} candles",
+                MAX_BACKFILL_CANDLES
+            )));
+        }
+        let invalid = |reason: &str| Error::BadRequest(format!("Line {}: {}", number, reason));
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [timestamp, open, high, low, close] = fields[..] else {
+            return Err(invalid("expected 5 columns"));
+        };
+
+        let bucket_start = DateTime::parse_from_rfc3339(timestamp)
+            .map(|at| at.with_timezone(&Utc))
+            .or_else(|_| {
+                NaiveDate::parse_from_str(timestamp, "%Y-%m-%d")
+                    .map(|date| date.and_time(chrono::NaiveTime::MIN).and_utc())
+            })
+            .map_err(|_| invalid("timestamp must be RFC 3339 or YYYY-MM-DD"))?;
+        if interval.bucket_start(bucket_start) != bucket_start {
+            return Err(invalid(&format!(
+                "timestamp does not start a {} candle",
+                interval.as_str()
+            )));
+        }
+        if bucket_start > now {
+            return Err(invalid("timestamp is in the future"));
+        }
+
+        let price = |value: &str, column: &str| {
+            value
+                .parse::<BigDecimal>()
+                .ok()
+                .filter(|price| *price > BigDecimal::zero())
+                .ok_or_else(|| invalid(&format!("{} must be a positive number", column)))
+        };
+        let open = price(open, "open")?;
+        let high = price(high, "high")?;
+        let low = price(low, "low")?;
+        let close = price(close, "close")?;
+        if low > high || open < low || open > high || close < low || close > high {
+            return Err(invalid("open and close must lie between low and high"));
+        }
+
+        candles.push(PriceCandle {
+            bucket_start,
+            open,
+            high,
+            low,
+            close,
+            tick_count: 1,
+            volume: 0,
+        });
+    }
+
+    if candles.is_empty() {
+        return Err(Error::BadRequest("CSV upload has no candles".into()));
+    }
+    Ok(candles)
+}
+
+/// List the configured liquidity profiles
+///
+/// Tickers without a profile trade with the default liquidity parameters.
+#[utoipa::path(
+    get,
+    path = "/liquidity",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Liquidity profiles", body = Vec<LiquidityProfileResponse>),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn get_liquidity_profiles(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<LiquidityProfileResponse>>> {
+    let profiles = LiquidityProfileRepository::new(&state.pg_pool)
+        .get_profiles()
+        .await?;
+
+    Ok(Json(profiles.into_iter().map(Into::into).collect()))
+}
+
+/// Create or replace the liquidity profile of a ticker
+#[utoipa::path(
+    put,
+    path = "/liquidity/{ticker}",
+    tag = "admin",
+    params(("ticker" = String, Path, description = "Instrument symbol")),
+    request_body = UpsertLiquidityProfileRequest,
+    responses(
+        (status = 200, description = "Stored profile", body = LiquidityProfileResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn upsert_liquidity_profile(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(ticker): Path<String>,
+    Json(payload): Json<UpsertLiquidityProfileRequest>,
+) -> Result<Json<LiquidityProfileResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let ticker = ticker.trim().to_uppercase();
+    if ticker.is_empty() || ticker.len() > 10 {
+        return Err(Error::BadRequest("Invalid ticker".into()));
+    }
+
+    let profile = LiquidityProfileRepository::new(&state.pg_pool)
+        .upsert_profile(
+            &ticker,
+            payload.depth,
+            payload.spread_bps,
+            payload.resilience,
+        )
+        .await?;
+
+    tracing::info!("Liquidity profile updated by admin: {:?}", profile);
+
+    Ok(Json(profile.into()))
+}
+
+/// Remove the liquidity profile of a ticker, reverting it to the defaults
+#[utoipa::path(
+    delete,
+    path = "/liquidity/{ticker}",
+    tag = "admin",
+    params(("ticker" = String, Path, description = "Instrument symbol")),
+    responses(
+        (status = 200, description = "Profile deleted", body = String),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 404, description = "No profile for the ticker", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn delete_liquidity_profile(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(ticker): Path<String>,
+) -> Result<Json<&'static str>> {
+    let deleted = LiquidityProfileRepository::new(&state.pg_pool)
+        .delete_profile(&ticker.trim().to_uppercase())
+        .await?;
+
+    if !deleted {
+        return Err(Error::NotFound);
+    }
+
+    Ok(Json("Liquidity profile deleted"))
+}
+
+/// List the stored feature flags
+///
+/// Features without a flag use their built-in default.
+#[utoipa::path(
+    get,
+    path = "/feature-flags",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Feature flags", body = Vec<FeatureFlagResponse>),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn get_feature_flags(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<FeatureFlagResponse>>> {
+    let flags = FeatureFlagRepository::new(&state.pg_pool)
+        .get_flags()
+        .await?;
+    let user_ids: Vec<i32> = flags
+        .iter()
+        .flat_map(|flag| flag.user_ids.iter().copied())
+        .collect();
+    let public_ids = state.repos.users.get_public_ids(&user_ids).await?;
+
+    Ok(Json(
+        flags
+            .into_iter()
+            .map(|flag| FeatureFlagResponse::new(flag, &public_ids))
+            .collect(),
+    ))
+}
+
+/// Create or replace a feature flag
+///
+/// An enabled flag is on for the listed users and for `rollout_percent`
+/// percent of everyone else; a disabled flag is off for everyone.
+#[utoipa::path(
+    put,
+    path = "/feature-flags/{name}",
+    tag = "admin",
+    params(("name" = String, Path, description = "Flag name, lowercase letters, digits and underscores")),
+    request_body = UpsertFeatureFlagRequest,
+    responses(
+        (status = 200, description = "Stored flag", body = FeatureFlagResponse),
+        (status = 400, description = "Validation error or unknown user", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn upsert_feature_flag(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<UpsertFeatureFlagRequest>,
+) -> Result<Json<FeatureFlagResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let valid_name = !name.is_empty()
+        && name.len() <= 50
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_');
+    if !valid_name {
+        return Err(Error::BadRequest("Invalid feature flag name".into()));
+    }
+
+    let mut public_ids = payload.user_ids;
+    public_ids.sort_unstable();
+    public_ids.dedup();
+    let users = &state.repos.users;
+    let user_ids = users.get_ids_by_public_ids(&public_ids).await?;
+    if user_ids.len() != public_ids.len() {
+        return Err(Error::BadRequest("Unknown user in `user_ids`".into()));
+    }
+
+    let flag = FeatureFlagRepository::new(&state.pg_pool)
+        .upsert_flag(
+            &name,
+            payload.description.as_deref().unwrap_or_default(),
+            payload.enabled,
+            payload.rollout_percent,
+            &user_ids,
+        )
+        .await?;
+    feature_flags::invalidate(&state).await;
+
+    tracing::info!("Feature flag updated by admin: {:?}", flag);
+    let public_ids = users.get_public_ids(&flag.user_ids).await?;
+
+    Ok(Json(FeatureFlagResponse::new(flag, &public_ids)))
+}
+
+/// Remove a feature flag, reverting the feature to its default
+#[utoipa::path(
+    delete,
+    path = "/feature-flags/{name}",
+    tag = "admin",
+    params(("name" = String, Path, description = "Flag name")),
+    responses(
+        (status = 200, description = "Flag deleted", body = String),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 404, description = "No flag of the name", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn delete_feature_flag(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<&'static str>> {
+    let deleted = FeatureFlagRepository::new(&state.pg_pool)
+        .delete_flag(&name)
+        .await?;
+
+    if !deleted {
+        return Err(Error::NotFound);
+    }
+    feature_flags::invalidate(&state).await;
+
+    Ok(Json("Feature flag deleted"))
+}
+
+/// List announced, recorded and paid dividends, most recent ex-date first
+#[utoipa::path(
+    get,
+    path = "/dividends",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Dividends", body = Vec<DividendResponse>),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn get_dividends(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<DividendResponse>>> {
+    let dividends = DividendRepository::new(&state.pg_pool)
+        .get_dividends()
+        .await?;
+
+    Ok(Json(dividends.into_iter().map(Into::into).collect()))
+}
+
+/// Announce a dividend
+///
+/// Holders at the start of `ex_date` receive `amount_per_share` (rounded to
+/// cents) per share on `pay_date`.
+#[utoipa::path(
+    post,
+    path = "/dividends",
+    tag = "admin",
+    request_body = CreateDividendRequest,
+    responses(
+        (status = 200, description = "Announced dividend", body = DividendResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn create_dividend(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateDividendRequest>,
+) -> Result<Json<DividendResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    if payload.pay_date < payload.ex_date {
+        return Err(Error::BadRequest(
+            "Pay date must not be before the ex-date".into(),
+        ));
+    }
+    if payload.ex_date < Utc::now().date_naive() {
+        return Err(Error::BadRequest("Ex-date must not be in the past".into()));
+    }
+
+    let amount_per_share = BigDecimal::from_f64(payload.amount_per_share)
+        .ok_or_else(|| Error::BadRequest("Invalid amount format".into()))?
+        .with_scale_round(2, RoundingMode::HalfUp);
+
+    let dividend = DividendRepository::new(&state.pg_pool)
+        .create_dividend(
+            &payload.ticker.trim().to_uppercase(),
+            payload.ex_date,
+            payload.pay_date,
+            amount_per_share,
+        )
+        .await?;
+
+    tracing::info!("Dividend announced by admin: {:?}", dividend);
+
+    Ok(Json(dividend.into()))
+}
+
+/// Cancel a dividend whose ex-date has not been processed yet
+#[utoipa::path(
+    delete,
+    path = "/dividends/{id}",
+    tag = "admin",
+    params(("id" = i32, Path, description = "Dividend ID")),
+    responses(
+        (status = 200, description = "Dividend cancelled", body = String),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 409, description = "Dividend not found or holders already recorded", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn delete_dividend(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<&'static str>> {
+    let deleted = DividendRepository::new(&state.pg_pool)
+        .delete_announced(id)
+        .await?;
+
+    if !deleted {
+        return Err(Error::Conflict(
+            "Dividend not found or holders already recorded".into(),
+        ));
+    }
+
+    Ok(Json("Dividend cancelled"))
+}
+
+/// List an option contract on a listed underlying
+///
+/// The contract covers 100 shares and is cash-settled at the underlying's
+/// price once `expires_at` passes.
+#[utoipa::path(
+    post,
+    path = "/options",
+    tag = "admin",
+    request_body = CreateOptionContractRequest,
+    responses(
+        (status = 200, description = "Listed contract", body = OptionContractResponse),
+        (status = 400, description = "Validation error, unknown underlying or past expiry", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 409, description = "A contract with the same terms is listed", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn create_option_contract(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateOptionContractRequest>,
+) -> Result<Json<OptionContractResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let underlying = payload.underlying.trim().to_uppercase();
+    InstrumentRepository::new(&state.pg_pool)
+        .get_instrument(&underlying)
+        .await?
+        .ok_or_else(|| Error::BadRequest(format!("Unknown ticker {}", underlying)))?;
+    let kind = payload.kind.trim().to_lowercase();
+    if !options::KINDS.contains(&kind.as_str()) {
+        return Err(Error::BadRequest("kind must be call or put".into()));
+    }
+    if payload.expires_at <= Utc::now() {
+        return Err(Error::BadRequest("expires_at must be in the future".into()));
+    }
+    let strike = BigDecimal::from_f64(payload.strike)
+        .ok_or_else(|| Error::BadRequest("Invalid strike format".into()))?
+        .with_scale_round(4, RoundingMode::HalfUp);
+
+    let contract = OptionRepository::new(&state.pg_pool)
+        .create_contract(&underlying, &kind, strike, payload.expires_at)
+        .await?
+        .ok_or_else(|| Error::Conflict("A contract with these terms is listed".into()))?;
+
+    tracing::info!("Option contract listed by admin: {:?}", contract);
+
+    Ok(Json(contract.into()))
+}
+
+/// Delist an option contract nobody holds
+#[utoipa::path(
+    delete,
+    path = "/options/{id}",
+    tag = "admin",
+    params(("id" = i32, Path, description = "Option contract ID")),
+    responses(
+        (status = 200, description = "Contract delisted", body = String),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 409, description = "Contract not found or held", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn delete_option_contract(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<&'static str>> {
+    let deleted = OptionRepository::new(&state.pg_pool)
+        .delete_unheld_contract(id)
+        .await?;
+
+    if !deleted {
+        return Err(Error::Conflict("Contract not found or held".into()));
+    }
+
+    Ok(Json("Contract delisted"))
+}
+
+/// Schedule a trading competition
+///
+/// Users can join until `ends_at` and trade their competition portfolio,
+/// funded with `starting_balance`, from `starts_at` on, under the rules of
+/// `difficulty` whatever their own setting. With a `clock_speed` above 1 the
+/// competition's clock runs that much faster than real time, and dividends
+/// reach entrants on its simulated dates. Standings are frozen within a
+/// minute of the end.
+#[utoipa::path(
+    post,
+    path = "/competitions",
+    tag = "admin",
+    request_body = CreateCompetitionRequest,
+    responses(
+        (status = 200, description = "Scheduled competition", body = CompetitionResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn create_competition(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateCompetitionRequest>,
+) -> Result<Json<CompetitionResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(Error::BadRequest(
+            "Competition name must not be blank".into(),
+        ));
+    }
+    if payload.ends_at <= payload.starts_at {
+        return Err(Error::BadRequest("End must be after the start".into()));
+    }
+    if payload.ends_at <= Utc::now() {
+        return Err(Error::BadRequest("End must be in the future".into()));
+    }
+
+    let starting_balance = BigDecimal::from_f64(payload.starting_balance)
+        .ok_or_else(|| Error::BadRequest("Invalid amount format".into()))?
+        .with_scale_round(2, RoundingMode::HalfUp);
+
+    let competition = CompetitionRepository::new(&state.pg_pool)
+        .create_competition(
+            name,
+            payload.description.as_deref().unwrap_or("").trim(),
+            payload.starts_at,
+            payload.ends_at,
+            starting_balance,
+            payload.difficulty.unwrap_or_default(),
+            payload.clock_speed.unwrap_or(1.0),
+        )
+        .await?;
+
+    tracing::info!("Competition scheduled by admin: {:?}", competition);
+
+    Ok(Json(competition.into()))
+}
+
+/// Cancel a competition that has not started, deleting its entrants' portfolios
+#[utoipa::path(
+    delete,
+    path = "/competitions/{id}",
+    tag = "admin",
+    params(("id" = i32, Path, description = "Competition ID")),
+    responses(
+        (status = 200, description = "Competition cancelled", body = String),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 409, description = "Competition not found or already started", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn delete_competition(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<&'static str>> {
+    let deleted = CompetitionRepository::new(&state.pg_pool)
+        .delete_upcoming(id)
+        .await?;
+
+    if !deleted {
+        return Err(Error::Conflict(
+            "Competition not found or already started".into(),
+        ));
+    }
+
+    Ok(Json("Competition cancelled"))
+}
+
+/// List scheduled and applied corporate actions, most recent first
+#[utoipa::path(
+    get,
+    path = "/corporate-actions",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Corporate actions", body = Vec<CorporateActionResponse>),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn get_corporate_actions(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<CorporateActionResponse>>> {
+    let actions = CorporateActionRepository::new(&state.pg_pool)
+        .get_actions()
+        .await?;
+
+    Ok(Json(actions.into_iter().map(Into::into).collect()))
+}
+
+/// Schedule a stock split or symbol change
+///
+/// The action is applied at the start of `effective_date`. Splits need
+/// `ratio_from` and `ratio_to`, symbol changes need `new_ticker`.
+#[utoipa::path(
+    post,
+    path = "/corporate-actions",
+    tag = "admin",
+    request_body = CreateCorporateActionRequest,
+    responses(
+        (status = 200, description = "Scheduled action", body = CorporateActionResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn create_corporate_action(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateCorporateActionRequest>,
+) -> Result<Json<CorporateActionResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    if payload.effective_date < Utc::now().date_naive() {
+        return Err(Error::BadRequest(
+            "Effective date must not be in the past".into(),
+        ));
+    }
+
+    let ticker = payload.ticker.trim().to_uppercase();
+    let repository = CorporateActionRepository::new(&state.pg_pool);
+    let action = match payload.action_type.as_str() {
+        "split" => {
+            let (Some(ratio_from), Some(ratio_to)) = (payload.ratio_from, payload.ratio_to) else {
+                return Err(Error::BadRequest(
+                    "Splits require ratio_from and ratio_to".into(),
+                ));
+            };
+            if ratio_from == ratio_to {
+                return Err(Error::BadRequest(
+                    "Split ratio must change the share count".into(),
+                ));
+            }
+            repository
+                .create_split(&ticker, payload.effective_date, ratio_from, ratio_to)
+                .await?
+        }
+        "symbol_change" => {
+            let new_ticker = payload
+                .new_ticker
+                .as_deref()
+                .map(|t| t.trim().to_uppercase())
+                .filter(|t| !t.is_empty())
+                .ok_or_else(|| Error::BadRequest("Symbol changes require new_ticker".into()))?;
+            if new_ticker == ticker {
+                return Err(Error::BadRequest(
+                    "New ticker must differ from the current ticker".into(),
+                ));
+            }
+            repository
+                .create_symbol_change(&ticker, payload.effective_date, &new_ticker)
+                .await?
+        }
+        _ => {
+            return Err(Error::BadRequest(
+                "action_type must be split or symbol_change".into(),
+            ));
+        }
+    };
+
+    tracing::info!("Corporate action scheduled by admin: {:?}", action);
+
+    Ok(Json(action.into()))
+}
+
+/// Cancel a corporate action that has not been applied yet
+#[utoipa::path(
+    delete,
+    path = "/corporate-actions/{id}",
+    tag = "admin",
+    params(("id" = i32, Path, description = "Corporate action ID")),
+    responses(
+        (status = 200, description = "Action cancelled", body = String),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 409, description = "Action not found or already applied", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn delete_corporate_action(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<&'static str>> {
+    let deleted = CorporateActionRepository::new(&state.pg_pool)
+        .delete_pending(id)
+        .await?;
+
+    if !deleted {
+        return Err(Error::Conflict(
+            "Corporate action not found or already applied".into(),
+        ));
+    }
+
+    Ok(Json("Corporate action cancelled"))
+}
+
+/// List scheduled and published news, latest publish time first
+#[utoipa::path(
+    get,
+    path = "/news",
+    tag = "admin",
+    responses(
+        (status = 200, description = "News", body = Vec<NewsResponse>),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn get_news(_admin: AdminKey, state: Extension<AppState>) -> Result<Json<Vec<NewsResponse>>> {
+    let news = NewsRepository::new(&state.pg_pool).get_news().await?;
+
+    Ok(Json(news.into_iter().map(Into::into).collect()))
+}
+
+/// Schedule a news event for a ticker
+///
+/// The news is published at `publish_at` (default now). With the synthetic
+/// price provider the ticker's price then jumps by `price_impact_percent`,
+/// which defaults by event type, and its volatility is multiplied by
+/// `volatility_multiplier` for `volatility_duration_secs`.
+#[utoipa::path(
+    post,
+    path = "/news",
+    tag = "admin",
+    request_body = CreateNewsRequest,
+    responses(
+        (status = 200, description = "Scheduled news", body = NewsResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn create_news(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateNewsRequest>,
+) -> Result<Json<NewsResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let default_impact = match payload.event_type.as_str() {
+        "earnings_beat" => 5.0,
+        "earnings_miss" => -5.0,
+        "upgrade" => 3.0,
+        "downgrade" => -3.0,
+        "other" => 0.0,
+        _ => {
+            return Err(Error::BadRequest(
+                "event_type must be earnings_beat, earnings_miss, upgrade, downgrade or other"
+                    .into(),
+            ));
+        }
+    };
+
+    let now = Utc::now();
+    let publish_at = payload.publish_at.unwrap_or(now);
+    if publish_at < now - TimeDelta::minutes(1) {
+        return Err(Error::BadRequest(
+            "publish_at must not be in the past".into(),
+        ));
+    }
+
+    let news = NewsRepository::new(&state.pg_pool)
+        .create_news(&NewsDetails {
+            ticker: payload.ticker.trim().to_uppercase(),
+            event_type: payload.event_type,
+            headline: payload.headline.trim().to_string(),
+            price_impact_percent: payload.price_impact_percent.unwrap_or(default_impact),
+            volatility_multiplier: payload.volatility_multiplier.unwrap_or(1.0),
+            volatility_duration_secs: payload.volatility_duration_secs.unwrap_or(0),
+            publish_at,
+        })
+        .await?;
+
+    tracing::info!("News scheduled by admin: {:?}", news);
+
+    Ok(Json(news.into()))
+}
+
+/// Cancel news that has not been published yet
+#[utoipa::path(
+    delete,
+    path = "/news/{id}",
+    tag = "admin",
+    params(("id" = i32, Path, description = "News ID")),
+    responses(
+        (status = 200, description = "News cancelled", body = String),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 409, description = "News not found or already published", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn delete_news(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<&'static str>> {
+    let deleted = NewsRepository::new(&state.pg_pool)
+        .delete_scheduled(id)
+        .await?;
+
+    if !deleted {
+        return Err(Error::Conflict(
+            "News not found or already published".into(),
+        ));
+    }
+
+    Ok(Json("News cancelled"))
+}
+
+/// List announcements, latest first
+#[utoipa::path(
+    get,
+    path = "/announcements",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Announcements", body = Vec<AnnouncementResponse>),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn get_announcements(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<AnnouncementResponse>>> {
+    let announcements = AnnouncementRepository::new(&state.pg_pool)
+        .get_announcements()
+        .await?;
+
+    Ok(Json(announcements.into_iter().map(Into::into).collect()))
+}
+
+/// Broadcast an announcement to every WebSocket client on the `system` channel
+///
+/// The announcement is pushed to current subscribers right away and sent to
+/// every later subscriber until `expires_at`, or until deleted when unset.
+#[utoipa::path(
+    post,
+    path = "/announcements",
+    tag = "admin",
+    request_body = CreateAnnouncementRequest,
+    responses(
+        (status = 200, description = "Broadcast announcement", body = AnnouncementResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn create_announcement(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateAnnouncementRequest>,
+) -> Result<Json<AnnouncementResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let severity = payload.severity.as_deref().unwrap_or("info");
+    if !matches!(severity, "info" | "warning" | "critical") {
+        return Err(Error::BadRequest(
+            "severity must be info, warning or critical".into(),
+        ));
+    }
+    if payload
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        return Err(Error::BadRequest("expires_at must be in the future".into()));
+    }
+    let message = payload.message.trim();
+    if message.is_empty() {
+        return Err(Error::BadRequest("message must not be blank".into()));
+    }
+
+    let announcement = AnnouncementRepository::new(&state.pg_pool)
+        .create_announcement(message, severity, payload.expires_at)
+        .await?;
+    announcements::publish(&state, &announcement).await;
+
+    tracing::info!("Announcement broadcast by admin: {:?}", announcement);
+
+    Ok(Json(announcement.into()))
+}
+
+/// Withdraw an announcement so later subscribers no longer receive it
+#[utoipa::path(
+    delete,
+    path = "/announcements/{id}",
+    tag = "admin",
+    params(("id" = i32, Path, description = "Announcement ID")),
+    responses(
+        (status = 200, description = "Announcement deleted", body = String),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 404, description = "Announcement not found", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn delete_announcement(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<&'static str>> {
+    let deleted = AnnouncementRepository::new(&state.pg_pool)
+        .delete_announcement(id)
+        .await?;
+
+    if !deleted {
+        return Err(Error::NotFound);
+    }
+
+    Ok(Json("Announcement deleted"))
+}
+
+/// Change the role of a user
+///
+/// The new role takes effect when the user next logs in.
+#[utoipa::path(
+    put,
+    path = "/users/{id}/role",
+    tag = "admin",
+    params(("id" = Uuid, Path, description = "User ID")),
+    request_body = UpdateRoleRequest,
+    responses(
+        (status = 200, description = "User with the new role", body = UserRoleResponse),
+        (status = 400, description = "Unknown role", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn update_user_role(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateRoleRequest>,
+) -> Result<Json<UserRoleResponse>> {
+    let role: Role = payload
+        .role
+        .parse()
+        .map_err(|_| Error::BadRequest("role must be user, moderator or admin".into()))?;
+
+    let users = &state.repos.users;
+    let user = users
+        .get_user_by_public_id(id)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let user = users
+        .set_role(user.id, role.as_str())
+        .await?
+        .ok_or(Error::NotFound)?;
+    user_cache::invalidate(&state, user.id).await;
+
+    tracing::info!("Role of user ID {} set to {} by admin", user.id, user.role);
+
+    Ok(Json(user.into()))
+}
+
+/// List the server-managed bot traders
+#[utoipa::path(
+    get,
+    path = "/bots",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Bots, oldest first", body = Vec<BotResponse>),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn get_bots(_admin: AdminKey, state: Extension<AppState>) -> Result<Json<Vec<BotResponse>>> {
+    let bots = BotRepository::new(&state.pg_pool).get_bots().await?;
+
+    Ok(Json(bots.into_iter().map(Into::into).collect()))
+}
+
+/// Add a bot trader
+///
+/// The bot gets its own account funded with the starting balance and
+/// trades from the next round on.
+#[utoipa::path(
+    post,
+    path = "/bots",
+    tag = "admin",
+    request_body = CreateBotRequest,
+    responses(
+        (status = 200, description = "Created bot", body = BotResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn create_bot(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateBotRequest>,
+) -> Result<Json<BotResponse>> {
+    let bot = bots::add_bot(&state, payload.strategy).await?;
+
+    tracing::info!("Bot created by admin: {:?}", bot);
+
+    Ok(Json(bot.into()))
+}
+
+/// Pause or resume a bot trader
+#[utoipa::path(
+    patch,
+    path = "/bots/{id}",
+    tag = "admin",
+    params(("id" = i32, Path, description = "Bot ID")),
+    request_body = UpdateBotRequest,
+    responses(
+        (status = 200, description = "Updated bot", body = BotResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 404, description = "Bot not found", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn update_bot(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+    Json(payload): Json<UpdateBotRequest>,
+) -> Result<Json<BotResponse>> {
+    let bot = BotRepository::new(&state.pg_pool)
+        .set_active(id, payload.active)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    tracing::info!("Bot ID {} set active={} by admin", bot.id, bot.active);
+
+    Ok(Json(bot.into()))
+}
+
+/// List the uploaded historical replays, latest first
+#[utoipa::path(
+    get,
+    path = "/replays",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Replays", body = Vec<ReplayResponse>),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn get_replays(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<ReplayResponse>>> {
+    let replays = ReplayRepository::new(&state.pg_pool).get_replays().await?;
+
+    Ok(Json(replays.into_iter().map(Into::into).collect()))
+}
+
+/// Upload a historical tick dataset to replay
+///
+/// The body is CSV with a `timestamp,ticker,price` header and one tick per
+/// line, such as the trades of a flash crash. Timestamps are RFC 3339 and the
+/// tickers must be listed. Once started, the replay publishes the ticks in
+/// chronological order, `speed` historical seconds per real second.
+#[utoipa::path(
+    post,
+    path = "/replays",
+    tag = "admin",
+    params(CreateReplayQuery),
+    request_body(content = String, content_type = "text/csv", description = "`timestamp,ticker,price` header and one tick per line"),
+    responses(
+        (status = 200, description = "Uploaded replay", body = ReplayResponse),
+        (status = 400, description = "Malformed CSV or invalid parameters", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 413, description = "Request body too large", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn create_replay(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Query(query): Query<CreateReplayQuery>,
+    body: String,
+) -> Result<Json<ReplayResponse>> {
+    query
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let ticks = parse_replay_csv(&body)?;
+    let listed = InstrumentRepository::new(&state.pg_pool)
+        .get_instruments()
+        .await?;
+    if let Some(tick) = ticks.iter().find(|tick| {
+        !listed
+            .iter()
+            .any(|instrument| instrument.ticker == tick.ticker)
+    }) {
+        return Err(Error::BadRequest(format!("{} is not listed", tick.ticker)));
+    }
+
+    let replay = ReplayRepository::new(&state.pg_pool)
+        .create_replay(query.name.trim(), query.speed, &ticks)
+        .await?;
+
+    tracing::info!(
+        "Replay ID {} ({}) with {} ticks uploaded by admin",
+        replay.id,
+        replay.name,
+        replay.tick_count
+    );
+
+    Ok(Json(replay.into()))
+}
+
+/// Ticks of a replay upload in chronological order, with errors naming the
+/// offending line
+fn parse_replay_csv(body: &str) -> Result<Vec<ReplayTick>> {
+    let mut lines = body
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let header: Vec<String> = match lines.next() {
+        Some((_, header)) => header
+            .split(',')
+            .map(|column| column.trim().to_lowercase())
+            .collect(),
+        None => return Err(Error::BadRequest("CSV upload is empty".into())),
+    };
+    if header != ["timestamp", "ticker", "price"] {
+        return Err(Error::BadRequest(
+            "CSV header must be timestamp,ticker,price".into(),
+        ));
+    }
+
+    let mut ticks = Vec::new();
+    for (number, line) in lines {
+        if ticks.len() == MAX_REPLAY_TICKS {
+            return Err(Error::BadRequest(format!(
+                "CSV upload has more than {} ticks",
+                MAX_REPLAY_TICKS
+            )));
+        }
+        let invalid = |reason: &str| Error::BadRequest(format!("Line {}: {}", number, reason));
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [timestamp, ticker, price] = fields[..] else {
+            return Err(invalid("expected 3 columns"));
+        };
+
+        let at = DateTime::parse_from_rfc3339(timestamp)
+            .map(|at| at.with_timezone(&Utc))
+            .map_err(|_| invalid("timestamp must be RFC 3339"))?;
+        if ticker.is_empty() {
+            return Err(invalid("ticker is empty"));
+        }
+        let price = price
+            .parse::<f64>()
+            .ok()
+            .filter(|price| price.is_finite() && *price > 0.0)
+            .ok_or_else(|| invalid("price must be a positive number"))?;
+
+        ticks.push(ReplayTick {
+            seq: 0,
+            at,
+            ticker: ticker.to_uppercase(),
+            price,
+        });
+    }
+
+    // Ticks of the same moment keep their upload order
+    ticks.sort_by_key(|tick| tick.at);
+    for (index, tick) in ticks.iter_mut().enumerate() {
+        tick.seq = index as i32 + 1;
+    }
+
+    Ok(ticks)
+}
+
+/// Start replaying a dataset from its first tick
+///
+/// The tickers of the replay ignore the live price feed until it finishes or
+/// is stopped. A finished replay starts over.
+#[utoipa::path(
+    post,
+    path = "/replays/{id}/start",
+    tag = "admin",
+    params(("id" = i32, Path, description = "Replay ID")),
+    responses(
+        (status = 200, description = "Started replay", body = ReplayResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 409, description = "Replay not found or already running", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn start_replay(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<ReplayResponse>> {
+    let replay = ReplayRepository::new(&state.pg_pool)
+        .start(id)
+        .await?
+        .ok_or_else(|| Error::Conflict("Replay not found or already running".into()))?;
+
+    tracing::info!("Replay ID {} ({}) started by admin", replay.id, replay.name);
+
+    Ok(Json(replay.into()))
+}
+
+/// Stop a running replay and hand its tickers back to the live feed
+#[utoipa::path(
+    post,
+    path = "/replays/{id}/stop",
+    tag = "admin",
+    params(("id" = i32, Path, description = "Replay ID")),
+    responses(
+        (status = 200, description = "Stopped replay", body = ReplayResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 409, description = "Replay not found or not running", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn stop_replay(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<ReplayResponse>> {
+    let replay = replay::stop(&state, id)
+        .await?
+        .ok_or_else(|| Error::Conflict("Replay not found or not running".into()))?;
+
+    tracing::info!("Replay ID {} ({}) stopped by admin", replay.id, replay.name);
+
+    Ok(Json(replay.into()))
+}
+
+/// Delete a replay that is not running
+#[utoipa::path(
+    delete,
+    path = "/replays/{id}",
+    tag = "admin",
+    params(("id" = i32, Path, description = "Replay ID")),
+    responses(
+        (status = 200, description = "Replay deleted", body = String),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+        (status = 409, description = "Replay not found or running", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn delete_replay(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<&'static str>> {
+    let deleted = ReplayRepository::new(&state.pg_pool)
+        .delete_replay(id)
+        .await?;
+
+    if !deleted {
+        return Err(Error::Conflict("Replay not found or running".into()));
+    }
+
+    Ok(Json("Replay deleted"))
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct UpdateMatchingConfigRequest {
+    #[validate(range(min = 0.01, max = 100.0))]
+    price_band_percent: f64,
+    #[validate(range(min = 1, max = 1_000_000))]
+    max_order_quantity: i32,
+    #[validate(range(min = 0.01, max = 100.0))]
+    volatility_halt_percent: f64,
+    #[validate(range(min = 1, max = 86_400))]
+    halt_duration_secs: i32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct MatchingConfigResponse {
+    price_band_percent: f64,
+    max_order_quantity: i32,
+    volatility_halt_percent: f64,
+    halt_duration_secs: i32,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreateInstrumentRequest {
+    #[validate(length(min = 1, max = 10))]
+    ticker: String,
+    #[serde(flatten)]
+    #[validate(nested)]
+    details: InstrumentDetailsRequest,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct InstrumentDetailsRequest {
+    #[validate(length(min = 1, max = 255))]
+    name: String,
+    #[validate(length(min = 1, max = 100))]
+    sector: Option<String>,
+    /// `equity`, `etf`, `bond`, `commodity`, `crypto` or `index`; defaults to `equity`
+    asset_class: Option<String>,
+    /// Defaults to 0.01
+    #[validate(range(min = 0.0000000001, max = 1_000_000.0))]
+    tick_size: Option<f64>,
+    /// Defaults to 1
+    #[validate(range(min = 1, max = 1_000_000))]
+    lot_size: Option<i32>,
+    /// Defaults to true
+    active: Option<bool>,
+}
+
+impl InstrumentDetailsRequest {
+    fn into_details(self) -> Result<InstrumentDetails> {
+        let asset_class = self.asset_class.unwrap_or_else(|| "equity".to_string());
+        if !instruments::ASSET_CLASSES.contains(&asset_class.as_str()) {
+            return Err(Error::BadRequest(format!(
+                "asset_class must be one of {}",
+                instruments::ASSET_CLASSES.join(", ")
+            )));
+        }
+        let tick_size = match self.tick_size {
+            Some(tick_size) => BigDecimal::from_f64(tick_size)
+                .ok_or_else(|| Error::BadRequest("Invalid tick size".into()))?,
+            None => BigDecimal::new(1.into(), 2),
+        };
+
+        Ok(InstrumentDetails {
+            name: self.name,
+            sector: self.sector,
+            asset_class,
+            tick_size,
+            lot_size: self.lot_size.unwrap_or(1),
+            active: self.active.unwrap_or(true),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct InstrumentResponse {
+    ticker: String,
+    name: String,
+    sector: Option<String>,
+    asset_class: String,
+    tick_size: BigDecimal,
+    lot_size: i32,
+    active: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<Instrument> for InstrumentResponse {
+    fn from(instrument: Instrument) -> Self {
+        InstrumentResponse {
+            ticker: instrument.ticker,
+            name: instrument.name,
+            sector: instrument.sector,
+            asset_class: instrument.asset_class,
+            tick_size: instrument.tick_size,
+            lot_size: instrument.lot_size,
+            active: instrument.active,
+            created_at: instrument.created_at,
+            updated_at: instrument.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct UpsertLiquidityProfileRequest {
+    #[validate(range(min = 1, max = 100_000_000))]
+    depth: i32,
+    #[validate(range(min = 0.0, max = 10_000.0))]
+    spread_bps: f64,
+    #[validate(range(min = 0.0001, max = 1.0))]
+    resilience: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct LiquidityProfileResponse {
+    ticker: String,
+    depth: i32,
+    spread_bps: f64,
+    resilience: f64,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct UpsertFeatureFlagRequest {
+    #[validate(length(max = 255))]
+    description: Option<String>,
+    enabled: bool,
+    #[serde(default)]
+    #[validate(range(min = 0, max = 100))]
+    rollout_percent: i16,
+    /// Users the feature is on for regardless of `rollout_percent`
+    #[serde(default)]
+    #[validate(length(max = 10_000))]
+    user_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct FeatureFlagResponse {
+    name: String,
+    description: String,
+    enabled: bool,
+    rollout_percent: i16,
+    user_ids: Vec<Uuid>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreateDividendRequest {
+    #[validate(length(min = 1, max = 10))]
+    ticker: String,
+    ex_date: NaiveDate,
+    pay_date: NaiveDate,
+    #[validate(range(min = 0.01, max = 10_000.0))]
+    amount_per_share: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DividendResponse {
+    id: i32,
+    ticker: String,
+    ex_date: NaiveDate,
+    pay_date: NaiveDate,
+    amount_per_share: BigDecimal,
+    status: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct BasketRequest {
+    #[validate(length(min = 1, max = "MAX_CONSTITUENTS"), nested)]
+    constituents: Vec<BasketConstituentRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+struct BasketConstituentRequest {
+    #[validate(length(min = 1, max = 10))]
+    ticker: String,
+    /// Units of the constituent in one unit of the basket
+    #[validate(range(min = 0.0000000001, max = 1_000_000.0))]
+    weight: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct BasketConstituentResponse {
+    ticker: String,
+    weight: BigDecimal,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct BondTermsRequest {
+    /// Amount repaid per bond at maturity
+    #[validate(range(min = 0.01, max = 1_000_000.0))]
+    face_value: f64,
+    /// Annual coupon in percent of the face value; 0 for a zero-coupon bond
+    #[validate(range(min = 0.0, max = 100.0))]
+    coupon_rate_percent: f64,
+    /// 1, 2, 4 or 12
+    coupons_per_year: i32,
+    maturity_date: NaiveDate,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct BondTermsResponse {
+    ticker: String,
+    face_value: BigDecimal,
+    coupon_rate_percent: BigDecimal,
+    coupons_per_year: i32,
+    maturity_date: NaiveDate,
+    next_coupon_date: NaiveDate,
+    redeemed_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreateOptionContractRequest {
+    #[validate(length(min = 1, max = 10))]
+    underlying: String,
+    /// `call` or `put`
+    kind: String,
+    #[validate(range(min = 0.01, max = 10_000_000.0))]
+    strike: f64,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct OptionContractResponse {
+    id: i32,
+    underlying: String,
+    kind: String,
+    strike: BigDecimal,
+    expires_at: DateTime<Utc>,
+    /// Underlying price the contract settled at, once expired
+    settlement_price: Option<BigDecimal>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreateCompetitionRequest {
+    #[validate(length(min = 1, max = 50))]
+    name: String,
+    #[validate(length(max = 1000))]
+    description: Option<String>,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    #[validate(range(min = 100.0, max = 10_000_000.0))]
+    starting_balance: f64,
+    /// Rules every entrant trades under, `realistic` when omitted
+    difficulty: Option<Difficulty>,
+    /// Simulated seconds per real second, 1 (real time) when omitted
+    #[validate(range(min = 1.0, max = 1440.0))]
+    clock_speed: Option<f64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(as = AdminCompetitionResponse)]
+struct CompetitionResponse {
+    id: i32,
+    name: String,
+    description: String,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    starting_balance: BigDecimal,
+    difficulty: String,
+    clock_speed: f64,
+    finalized_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreateCorporateActionRequest {
+    #[validate(length(min = 1, max = 10))]
+    ticker: String,
+    /// `split` or `symbol_change`
+    action_type: String,
+    effective_date: NaiveDate,
+    #[validate(range(min = 1, max = 1000))]
+    ratio_from: Option<i32>,
+    #[validate(range(min = 1, max = 1000))]
+    ratio_to: Option<i32>,
+    #[validate(length(min = 1, max = 10))]
+    new_ticker: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreateNewsRequest {
+    #[validate(length(min = 1, max = 10))]
+    ticker: String,
+    /// `earnings_beat`, `earnings_miss`, `upgrade`, `downgrade` or `other`
+    event_type: String,
+    #[validate(length(min = 1, max = 200))]
+    headline: String,
+    #[validate(range(min = -90.0, max = 100.0))]
+    price_impact_percent: Option<f64>,
+    #[validate(range(min = 0.1, max = 10.0))]
+    volatility_multiplier: Option<f64>,
+    #[validate(range(min = 0, max = 604_800))]
+    volatility_duration_secs: Option<i32>,
+    publish_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreateAnnouncementRequest {
+    #[validate(length(min = 1, max = 500))]
+    message: String,
+    /// `info` (default), `warning` or `critical`
+    severity: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct UpdateRoleRequest {
+    /// `user`, `moderator` or `admin`
+    role: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct CreateBotRequest {
+    strategy: BotStrategy,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct UpdateBotRequest {
+    /// Paused bots keep their account and holdings but stop trading
+    active: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(as = AdminBotResponse)]
+struct BotResponse {
+    id: i32,
+    user_id: Uuid,
+    display_name: Option<String>,
+    /// `momentum`, `mean_reversion` or `random`
+    strategy: String,
+    active: bool,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(as = AdminReplayResponse)]
+struct ReplayResponse {
+    id: i32,
+    name: String,
+    /// Historical seconds replayed per real second
+    speed: f64,
+    /// `ready`, `running` or `finished`
+    status: String,
+    tick_count: i32,
+    /// Ticks published so far in the current run
+    position: i32,
+    data_from: DateTime<Utc>,
+    data_to: DateTime<Utc>,
+    /// Historical time the replay has reached, while running
+    replay_time: Option<DateTime<Utc>>,
+    started_at: Option<DateTime<Utc>>,
+    finished_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct UserRoleResponse {
+    id: Uuid,
+    email: String,
+    role: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct AnnouncementResponse {
+    id: i32,
+    message: String,
+    severity: String,
+    expires_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(as = AdminNewsResponse)]
+struct NewsResponse {
+    id: i32,
+    ticker: String,
+    event_type: String,
+    headline: String,
+    price_impact_percent: f64,
+    volatility_multiplier: f64,
+    volatility_duration_secs: i32,
+    publish_at: DateTime<Utc>,
+    applied_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct CorporateActionResponse {
+    id: i32,
+    ticker: String,
+    action_type: String,
+    effective_date: NaiveDate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ratio_from: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ratio_to: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_ticker: Option<String>,
+    status: String,
+    applied_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<CorporateAction> for CorporateActionResponse {
+    fn from(action: CorporateAction) -> Self {
+        CorporateActionResponse {
+            id: action.id,
+            ticker: action.ticker,
+            action_type: action.action_type,
+            effective_date: action.effective_date,
+            ratio_from: action.ratio_from,
+            ratio_to: action.ratio_to,
+            new_ticker: action.new_ticker,
+            status: action.status,
+            applied_at: action.applied_at,
+            created_at: action.created_at,
+        }
+    }
+}
+
+impl From<User> for UserRoleResponse {
+    fn from(user: User) -> Self {
+        UserRoleResponse {
+            id: user.public_id,
+            email: user.email,
+            role: user.role,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+impl From<Announcement> for AnnouncementResponse {
+    fn from(announcement: Announcement) -> Self {
+        AnnouncementResponse {
+            id: announcement.id,
+            message: announcement.message,
+            severity: announcement.severity,
+            expires_at: announcement.expires_at,
+            created_at: announcement.created_at,
+        }
+    }
+}
+
+impl From<NewsEvent> for NewsResponse {
+    fn from(news: NewsEvent) -> Self {
+        NewsResponse {
+            id: news.id,
+            ticker: news.ticker,
+            event_type: news.event_type,
+            headline: news.headline,
+            price_impact_percent: news.price_impact_percent,
+            volatility_multiplier: news.volatility_multiplier,
+            volatility_duration_secs: news.volatility_duration_secs,
+            publish_at: news.publish_at,
+            applied_at: news.applied_at,
+            created_at: news.created_at,
+        }
+    }
+}
+
+impl From<Dividend> for DividendResponse {
+    fn from(dividend: Dividend) -> Self {
+        DividendResponse {
+            id: dividend.id,
+            ticker: dividend.ticker,
+            ex_date: dividend.ex_date,
+            pay_date: dividend.pay_date,
+            amount_per_share: dividend.amount_per_share,
+            status: dividend.status,
+            created_at: dividend.created_at,
+        }
+    }
+}
+
+impl From<BasketConstituent> for BasketConstituentResponse {
+    fn from(constituent: BasketConstituent) -> Self {
+        BasketConstituentResponse {
+            ticker: constituent.ticker,
+            weight: constituent.weight,
+        }
+    }
+}
+
+impl From<Bond> for BondTermsResponse {
+    fn from(bond: Bond) -> Self {
+        BondTermsResponse {
+            ticker: bond.ticker,
+            face_value: bond.face_value,
+            coupon_rate_percent: bond.coupon_rate_percent,
+            coupons_per_year: bond.coupons_per_year,
+            maturity_date: bond.maturity_date,
+            next_coupon_date: bond.next_coupon_date,
+            redeemed_at: bond.redeemed_at,
+            created_at: bond.created_at,
+            updated_at: bond.updated_at,
+        }
+    }
+}
+
+impl From<OptionContract> for OptionContractResponse {
+    fn from(contract: OptionContract) -> Self {
+        OptionContractResponse {
+            id: contract.id,
+            underlying: contract.underlying,
+            kind: contract.kind,
+            strike: contract.strike,
+            expires_at: contract.expires_at,
+            settlement_price: contract.settlement_price,
+            created_at: contract.created_at,
+        }
+    }
+}
+
+impl From<Replay> for ReplayResponse {
+    fn from(r: Replay) -> Self {
+        let replay_time =
+            (r.status == "running").then(|| replay::replay_time(&r, Utc::now()).min(r.data_to));
+        ReplayResponse {
+            id: r.id,
+            name: r.name,
+            speed: r.speed,
+            status: r.status,
+            tick_count: r.tick_count,
+            position: r.position,
+            data_from: r.data_from,
+            data_to: r.data_to,
+            replay_time,
+            started_at: r.started_at,
+            finished_at: r.finished_at,
+            created_at: r.created_at,
+        }
+    }
+}
+
+impl From<Bot> for BotResponse {
+    fn from(bot: Bot) -> Self {
+        BotResponse {
+            id: bot.id,
+            user_id: bot.user_public_id,
+            display_name: bot.display_name,
+            strategy: bot.strategy,
+            active: bot.active,
+            created_at: bot.created_at,
+        }
+    }
+}
+
+impl From<Competition> for CompetitionResponse {
+    fn from(competition: Competition) -> Self {
+        CompetitionResponse {
+            id: competition.id,
+            name: competition.name,
+            description: competition.description,
+            starts_at: competition.starts_at,
+            ends_at: competition.ends_at,
+            starting_balance: competition.starting_balance,
+            difficulty: competition.difficulty,
+            clock_speed: competition.clock_speed,
+            finalized_at: competition.finalized_at,
+            created_at: competition.created_at,
+        }
+    }
+}
+
+impl From<LiquidityProfile> for LiquidityProfileResponse {
+    fn from(profile: LiquidityProfile) -> Self {
+        LiquidityProfileResponse {
+            ticker: profile.ticker,
+            depth: profile.depth,
+            spread_bps: profile.spread_bps,
+            resilience: profile.resilience,
+            updated_at: profile.updated_at,
+        }
+    }
+}
+
+impl FeatureFlagResponse {
+    /// `flag` naming its users by the public IDs of `public_ids`; deleted
+    /// users are left out
+    fn new(flag: FeatureFlag, public_ids: &HashMap<i32, Uuid>) -> Self {
+        FeatureFlagResponse {
+            name: flag.name,
+            description: flag.description,
+            enabled: flag.enabled,
+            rollout_percent: flag.rollout_percent,
+            user_ids: flag
+                .user_ids
+                .iter()
+                .filter_map(|user_id| public_ids.get(user_id).copied())
+                .collect(),
+            created_at: flag.created_at,
+            updated_at: flag.updated_at,
+        }
+    }
+}
+
+impl From<MatchingConfig> for MatchingConfigResponse {
+    fn from(config: MatchingConfig) -> Self {
+        MatchingConfigResponse {
+            price_band_percent: config.price_band_percent,
+            max_order_quantity: config.max_order_quantity,
+            volatility_halt_percent: config.volatility_halt_percent,
+            halt_duration_secs: config.halt_duration_secs,
+            updated_at: config.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct OverridePriceRequest {
+    #[validate(range(min = 0.0000000001, max = 1_000_000_000.0))]
+    price: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct PriceOverrideResponse {
+    ticker: String,
+    price: f64,
+    timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct BackfillQuery {
+    interval: Option<CandleInterval>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct CreateReplayQuery {
+    #[validate(length(min = 1, max = 100))]
+    name: String,
+    /// Historical seconds replayed per real second
+    #[validate(range(min = 0.1, max = 1000.0))]
+    speed: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct BackfillResponse {
+    ticker: String,
+    interval: CandleInterval,
+    candles: u64,
+}
+
+/// Get system-wide statistics
+///
+/// Counts come from Postgres and Redis and cover every instance; the price
+/// feed's state is that of the instance answering. Trading statistics cover
+/// the current UTC day.
+#[utoipa::path(
+    get,
+    path = "/stats",
+    tag = "admin",
+    responses(
+        (status = 200, description = "System-wide statistics", body = StatsResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn get_stats(_admin: AdminKey, state: Extension<AppState>) -> Result<Json<StatsResponse>> {
+    let now = Utc::now();
+    let day_start = now.date_naive().and_time(chrono::NaiveTime::MIN);
+
+    let registered_users = state.repos.users.count_users().await?;
+    let volumes = state
+        .repos
+        .transactions
+        .get_most_traded(day_start, i64::MAX)
+        .await?;
+    let active_sessions = sessions::count(&state).await?;
+    let ws_connections = limits::count_all(&state).await?;
+    let feed = price_updater::health(&state);
+
+    Ok(Json(StatsResponse {
+        generated_at: now,
+        registered_users,
+        active_sessions,
+        trades_today: volumes.iter().map(|volume| volume.trades).sum(),
+        open_orders: 0,
+        ws_connections,
+        volume_by_ticker: volumes.into_iter().map(Into::into).collect(),
+        feed: FeedStatsResponse {
+            status: feed.status,
+            last_update_at: feed.last_update_at,
+            lag_ms: feed
+                .last_update_at
+                .map(|at| (now - at).num_milliseconds().max(0)),
+        },
+    }))
+}
+
+/// Archive the transactions executed before a date
+///
+/// Moves them out of the history listed by `GET /transactions` into the
+/// monthly archive served by `GET /transactions/archived`, as the archive
+/// worker does daily when `TRANSACTION_ARCHIVE_AFTER_DAYS` is set. Dates
+/// are UTC; the date itself is not archived.
+#[utoipa::path(
+    post,
+    path = "/transactions/archive",
+    tag = "admin",
+    request_body = ArchiveTransactionsRequest,
+    responses(
+        (status = 200, description = "Transactions archived", body = ArchiveTransactionsResponse),
+        (status = 400, description = "Date in the future", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid admin key or token", body = ErrorResponse),
+        (status = 403, description = "Token of a non-admin user", body = ErrorResponse),
+    ),
+    security(("adminKey" = []), ("bearerAuth" = []))
+)]
+async fn archive_transactions(
+    _admin: AdminKey,
+    state: Extension<AppState>,
+    Json(payload): Json<ArchiveTransactionsRequest>,
+) -> Result<Json<ArchiveTransactionsResponse>> {
+    if payload.before > Utc::now().date_naive() {
+        return Err(Error::BadRequest(
+            "`before` must not be in the future".into(),
+        ));
+    }
+
+    let cutoff = payload.before.and_time(chrono::NaiveTime::MIN);
+    let archived = archive::archive_before(&state, cutoff).await?;
+
+    tracing::info!(
+        "Archived {} transactions before {} by admin",
+        archived,
+        payload.before
+    );
+
+    Ok(Json(ArchiveTransactionsResponse {
+        before: payload.before,
+        archived,
+    }))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct ArchiveTransactionsRequest {
+    /// First day left in the hot history
+    before: NaiveDate,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ArchiveTransactionsResponse {
+    before: NaiveDate,
+    /// Transactions moved to the archive
+    archived: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct StatsResponse {
+    generated_at: DateTime<Utc>,
+    registered_users: i64,
+    /// Access tokens issued and neither expired nor revoked
+    active_sessions: u64,
+    trades_today: i64,
+    /// Orders are filled when placed, so none are ever left open
+    open_orders: u64,
+    ws_connections: u64,
+    /// Shares bought and sold today per ticker, most traded first
+    volume_by_ticker: Vec<TickerVolumeResponse>,
+    feed: FeedStatsResponse,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct TickerVolumeResponse {
+    ticker: String,
+    volume: i64,
+    trades: i64,
+}
+
+impl From<TradedVolume> for TickerVolumeResponse {
+    fn from(volume: TradedVolume) -> Self {
+        TickerVolumeResponse {
+            ticker: volume.ticker,
+            volume: volume.volume,
+            trades: volume.trades,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct FeedStatsResponse {
+    status: FeedStatus,
+    last_update_at: Option<DateTime<Utc>>,
+    /// Milliseconds since the last price update
+    lag_ms: Option<i64>,
+}
@@ -0,0 +1,459 @@
+use std::net::SocketAddr;
+
+use axum::{Extension, Router, extract::ConnectInfo, routing::post};
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode};
+use serde::{Deserialize, Serialize};
+use utoipa::{OpenApi, ToSchema};
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::{
+        email_change::{self, PendingEmailChange},
+        jwt::Claims,
+        lockout,
+        password::{
+            check_password_strength, hash_password, verify_dummy_password, verify_password,
+        },
+        revocation, sessions,
+    },
+    models::{portfolio::DEFAULT_PORTFOLIO_NAME, user::User},
+    repository::portfolio_repository::PortfolioRepository,
+    services::{
+        event_stream::DomainEvent,
+        mailer::{self, Mail},
+        user_cache,
+    },
+    timing::Json,
+};
+
+/// Times a confirmed email change is retried after a concurrent change of
+/// the account
+const EMAIL_UPDATE_ATTEMPTS: usize = 3;
+
+#[derive(OpenApi)]
+#[openapi(paths(login, logout, register, change_password, change_email, confirm_email))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/login", post(login))
+        .route("/logout", post(logout))
+        .route("/register", post(register))
+        .route("/change-password", post(change_password))
+        .route("/change-email", post(change_email))
+        .route("/confirm-email", post(confirm_email))
+}
+
+/// Exchange credentials for an access token
+///
+/// Unknown emails and wrong passwords get the same `401`, and repeated
+/// failures are throttled with `429` per email and per client IP (see
+/// [`lockout`]).
+#[utoipa::path(
+    post,
+    path = "/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Access token", body = LoginResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Invalid email or password", body = ErrorResponse),
+        (status = 429, description = "Too many failed attempts", body = ErrorResponse),
+    )
+)]
+async fn login(
+    db: Extension<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(payload): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    // An unreachable Redis must not stop everyone from logging in
+    match lockout::retry_after(&db, &payload.email, addr.ip()).await {
+        Ok(Some(retry_after)) => {
+            tracing::warn!("Throttled login attempt from {}", addr.ip());
+            return Err(Error::TooManyRequests(retry_after));
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to check login throttling: {}", e),
+    }
+
+    let repository = &db.repos.users;
+
+    let user = repository.get_user_by_email(&payload.email).await?;
+    let is_valid = match &user {
+        Some(user) => verify_password(&payload.password, &user.password)?,
+        None => {
+            verify_dummy_password(&payload.password);
+            false
+        }
+    };
+
+    let user = match user {
+        Some(user) if is_valid => user,
+        user => {
+            match user {
+                Some(user) => tracing::warn!("Failed login attempt for user ID: {}", user.id),
+                None => tracing::warn!("Login attempt with non-existent email: {}", payload.email),
+            }
+            if let Err(e) = lockout::record_failure(&db, &payload.email, addr.ip()).await {
+                tracing::warn!("Failed to record failed login: {}", e);
+            }
+            return Err(Error::LoginFailed);
+        }
+    };
+
+    if let Err(e) = lockout::clear(&db, &payload.email).await {
+        tracing::warn!("Failed to clear failed logins: {}", e);
+    }
+    if let Err(e) = db.repos.users.record_login(user.id).await {
+        tracing::warn!("Failed to record login of user {}: {}", user.id, e);
+    }
+
+    tracing::info!("Successful login for user ID: {}", user.id);
+
+    issue_token(&db, &user).await.map(Json)
+}
+
+/// Sign an access token for `user` with its current role
+async fn issue_token(db: &AppState, user: &User) -> Result<LoginResponse> {
+    let role = user.role.parse().map_err(|e| {
+        tracing::error!("User ID {} has an invalid role: {}", user.id, e);
+        Error::InternalServerError
+    })?;
+    let (token, claims) = db
+        .auth
+        .create_token(user.id, role)
+        .map_err(|_| Error::InternalServerError)?;
+    sessions::record(db, &claims).await;
+
+    Ok(LoginResponse {
+        access_token: token,
+        token_type: "Bearer".into(),
+    })
+}
+
+/// Create an account with a default portfolio of starting cash
+///
+/// The portfolio holds `STARTING_BALANCE`, $1000 unless configured otherwise.
+#[utoipa::path(
+    post,
+    path = "/register",
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses(
+        (status = 200, description = "Account created", body = String),
+        (status = 400, description = "Validation error or weak password", body = ErrorResponse),
+        (status = 409, description = "Email already exists", body = ErrorResponse),
+    )
+)]
+async fn register(
+    db: Extension<AppState>,
+    Json(payload): Json<RegisterRequest>,
+) -> Result<Json<&'static str>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    check_password_strength(&db.config, &payload.password, &payload.email)?;
+
+    let repository = &db.repos.users;
+
+    let user_exists = repository.get_user_by_email(&payload.email).await?;
+    if user_exists.is_some() {
+        return Err(Error::Conflict("Email already exists".into()));
+    }
+
+    let hashed_password = hash_password(&payload.password)?;
+    let starting_balance = BigDecimal::from_f64(db.config.starting_balance)
+        .ok_or(Error::InternalServerError)?
+        .with_scale_round(2, RoundingMode::HalfUp);
+
+    let user = repository
+        .create_user(&payload.email, &hashed_password)
+        .await?;
+    PortfolioRepository::new(&db.pg_pool)
+        .create_portfolio(user.id, DEFAULT_PORTFOLIO_NAME, starting_balance, true)
+        .await?;
+    db.event_stream
+        .emit(DomainEvent::UserRegistered { user_id: user.id });
+
+    Ok(Json("User registered successfully"))
+}
+
+/// Revoke the access token the request was made with
+///
+/// Other tokens of the user, e.g. from other devices, stay valid.
+#[utoipa::path(
+    post,
+    path = "/logout",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Token revoked", body = String),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn logout(state: Extension<AppState>, claims: Claims) -> Result<Json<&'static str>> {
+    revocation::revoke(&state, &claims).await?;
+
+    tracing::info!("User ID {} logged out", claims.user_id);
+
+    Ok(Json("Logged out successfully"))
+}
+
+/// Replace the password of the account, signing out every session
+///
+/// The token of the request is revoked along with the others, so the
+/// response carries a fresh one for the session making the change.
+#[utoipa::path(
+    post,
+    path = "/change-password",
+    tag = "auth",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "Fresh access token", body = LoginResponse),
+        (status = 400, description = "Validation error, wrong current password or weak new password", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 409, description = "Account changed by another request", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn change_password(
+    db: Extension<AppState>,
+    claims: Claims,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<Json<LoginResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let repository = &db.repos.users;
+    let user = repository
+        .get_user_by_id(claims.user_id)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    if !verify_password(&payload.current_password, &user.password)? {
+        tracing::warn!(
+            "Wrong current password to change that of user ID: {}",
+            user.id
+        );
+        return Err(Error::BadRequest("Current password is incorrect".into()));
+    }
+    check_password_strength(&db.config, &payload.new_password, &user.email)?;
+
+    let hashed_password = hash_password(&payload.new_password)?;
+    let user = repository
+        .set_password(user.id, user.version, &hashed_password)
+        .await?
+        .ok_or_else(account_changed)?;
+    user_cache::invalidate(&db, user.id).await;
+    revocation::revoke_all(&db, user.id).await?;
+
+    tracing::info!("User ID {} changed their password", user.id);
+
+    issue_token(&db, &user).await.map(Json)
+}
+
+/// Start moving the account to another email
+///
+/// Mails a confirmation token to the new address; the email changes once it
+/// is posted to `/auth/confirm-email`.
+#[utoipa::path(
+    post,
+    path = "/change-email",
+    tag = "auth",
+    request_body = ChangeEmailRequest,
+    responses(
+        (status = 200, description = "Confirmation mailed", body = String),
+        (status = 400, description = "Validation error or wrong password", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 409, description = "Email already exists", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn change_email(
+    db: Extension<AppState>,
+    claims: Claims,
+    Json(payload): Json<ChangeEmailRequest>,
+) -> Result<Json<&'static str>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let repository = &db.repos.users;
+    let user = repository
+        .get_user_by_id(claims.user_id)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    if !verify_password(&payload.password, &user.password)? {
+        tracing::warn!("Wrong password to change the email of user ID: {}", user.id);
+        return Err(Error::BadRequest("Password is incorrect".into()));
+    }
+    if payload.new_email == user.email {
+        return Err(Error::BadRequest("New email is the current email".into()));
+    }
+    if repository
+        .get_user_by_email(&payload.new_email)
+        .await?
+        .is_some()
+    {
+        return Err(Error::Conflict("Email already exists".into()));
+    }
+
+    let token = email_change::request(
+        &db,
+        &PendingEmailChange {
+            user_id: user.id,
+            new_email: payload.new_email.clone(),
+        },
+    )
+    .await?;
+    mailer::send(
+        &db,
+        &Mail {
+            to: &payload.new_email,
+            subject: "Confirm your new email address",
+            body: format!(
+                "Confirm the change of your account's email to this address by posting \
+                 {{\"token\": \"{}\"}} to /auth/confirm-email within {} hours.",
+                token,
+                email_change::CONFIRMATION_TTL_SECS / 3600
+            ),
+        },
+    )
+    .await?;
+
+    tracing::info!("User ID {} requested an email change", user.id);
+
+    Ok(Json("Confirmation sent to the new email address"))
+}
+
+/// Finish an email change with the token mailed to the new address
+#[utoipa::path(
+    post,
+    path = "/confirm-email",
+    tag = "auth",
+    request_body = ConfirmEmailRequest,
+    responses(
+        (status = 200, description = "Email changed", body = String),
+        (status = 400, description = "Validation error or invalid token", body = ErrorResponse),
+        (status = 409, description = "Email already exists or account kept changing", body = ErrorResponse),
+    )
+)]
+async fn confirm_email(
+    db: Extension<AppState>,
+    Json(payload): Json<ConfirmEmailRequest>,
+) -> Result<Json<&'static str>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let change = email_change::take(&db, &payload.token)
+        .await?
+        .ok_or_else(|| Error::BadRequest("Invalid or expired token".into()))?;
+
+    let repository = &db.repos.users;
+    if repository
+        .get_user_by_email(&change.new_email)
+        .await?
+        .is_some()
+    {
+        return Err(Error::Conflict("Email already exists".into()));
+    }
+    // The token is spent, so a concurrent change of the account is retried
+    // rather than failing the confirmation
+    let mut old_email = None;
+    for _ in 0..EMAIL_UPDATE_ATTEMPTS {
+        let user = repository
+            .get_user_by_id(change.user_id)
+            .await?
+            .ok_or(Error::NotFound)?;
+        if repository
+            .set_email(user.id, user.version, &change.new_email)
+            .await?
+            .is_some()
+        {
+            old_email = Some(user.email);
+            break;
+        }
+    }
+    let old_email = old_email.ok_or_else(account_changed)?;
+    user_cache::invalidate(&db, change.user_id).await;
+
+    tracing::info!("User ID {} changed their email", change.user_id);
+
+    // The change is done, so failing to tell the old address must not undo it
+    let notice = Mail {
+        to: &old_email,
+        subject: "Your email address was changed",
+        body: format!(
+            "Your account now signs in with {}. If you did not make this change, \
+             contact support.",
+            change.new_email
+        ),
+    };
+    if let Err(e) = mailer::send(&db, &notice).await {
+        tracing::warn!(
+            "Failed to notify the old email of user ID {}: {}",
+            change.user_id,
+            e
+        );
+    }
+
+    Ok(Json("Email changed successfully"))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct LoginResponse {
+    access_token: String,
+    token_type: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct LoginRequest {
+    #[validate(email, length(min = 3, max = 255))]
+    email: String,
+    #[validate(length(min = 8, max = 128))]
+    password: String,
+}
+
+/// Error of an update that lost the race with another change of the account
+fn account_changed() -> Error {
+    Error::Conflict("Account changed by another request, try again".into())
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct RegisterRequest {
+    #[validate(email, length(min = 3, max = 255))]
+    email: String,
+    #[validate(length(min = 8, max = 128))]
+    password: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct ChangePasswordRequest {
+    #[validate(length(min = 8, max = 128))]
+    current_password: String,
+    #[validate(length(min = 8, max = 128))]
+    new_password: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct ChangeEmailRequest {
+    #[validate(email, length(min = 3, max = 255))]
+    new_email: String,
+    #[validate(length(min = 8, max = 128))]
+    password: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct ConfirmEmailRequest {
+    #[validate(length(min = 1, max = 64))]
+    token: String,
+}
@@ -0,0 +1,337 @@
+use axum::{
+    Extension, Router,
+    extract::Query,
+    http::{HeaderMap, HeaderValue},
+    routing::{get, post},
+};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    AppState, ErrorResponse, Result,
+    auth::portfolio::SelectedPortfolio,
+    models::{cash_movement::CashMovement, ledger_entry::LedgerEntry},
+    repository::{
+        cash_movement_repository::CashMovementRepository, ledger_repository::LedgerRepository,
+    },
+    services::balance,
+    timing::Json,
+};
+
+/// Default number of ledger entries or cash movements per page
+const DEFAULT_PAGE_SIZE: i64 = 50;
+/// Largest page a client may request
+const MAX_PAGE_SIZE: i64 = 200;
+
+#[derive(OpenApi)]
+#[openapi(paths(get_balance, deposit, withdraw, get_history, get_ledger))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/", get(get_balance))
+        .route("/deposit", post(deposit))
+        .route("/withdraw", post(withdraw))
+        .route("/history", get(get_history))
+        .route("/ledger", get(get_ledger))
+}
+
+/// Get the cash balance of the selected portfolio as a decimal string
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "balance",
+    params(SelectedPortfolio),
+    responses(
+        (status = 200, description = "Cash balance", body = BigDecimal),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_balance(SelectedPortfolio(portfolio): SelectedPortfolio) -> Result<Json<BigDecimal>> {
+    Ok(Json(portfolio.balance))
+}
+
+/// Deposit cash into the selected portfolio, returning the recorded movement
+///
+/// The amount is a decimal string with at most two decimal places. Numeric
+/// amounts are still accepted but deprecated: responses to them carry a
+/// `Deprecation` header.
+#[utoipa::path(
+    post,
+    path = "/deposit",
+    tag = "balance",
+    params(SelectedPortfolio),
+    request_body = DepositRequest,
+    responses(
+        (status = 200, description = "Deposit made", body = CashMovementResponse),
+        (status = 400, description = "Validation error, competition or class portfolio", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn deposit(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    db: Extension<AppState>,
+    Json(payload): Json<DepositRequest>,
+) -> Result<(HeaderMap, Json<CashMovementResponse>)> {
+    let movement = balance::deposit(&db, &portfolio, &payload.amount.parse()?).await?;
+
+    Ok((payload.amount.headers(), Json(movement.into())))
+}
+
+/// Withdraw cash from the selected portfolio, returning the recorded movement
+///
+/// Swept cash is redeemed from the money market first when the balance alone
+/// does not cover the amount. Amounts are given as for deposits.
+#[utoipa::path(
+    post,
+    path = "/withdraw",
+    tag = "balance",
+    params(SelectedPortfolio),
+    request_body = WithdrawRequest,
+    responses(
+        (status = 200, description = "Withdrawal made", body = CashMovementResponse),
+        (status = 400, description = "Validation error, insufficient funds, competition or class portfolio", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn withdraw(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    db: Extension<AppState>,
+    Json(payload): Json<WithdrawRequest>,
+) -> Result<(HeaderMap, Json<CashMovementResponse>)> {
+    let movement = balance::withdraw(&db, &portfolio, &payload.amount.parse()?).await?;
+
+    Ok((payload.amount.headers(), Json(movement.into())))
+}
+
+/// Get the deposits and withdrawals of the selected portfolio, newest first
+///
+/// Pass the returned `next_cursor` as `before` to fetch the following page;
+/// it is absent on the last page.
+#[utoipa::path(
+    get,
+    path = "/history",
+    tag = "balance",
+    params(SelectedPortfolio, HistoryQuery),
+    responses(
+        (status = 200, description = "One page of deposits and withdrawals", body = CashHistoryResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_history(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    db: Extension<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<CashHistoryResponse>> {
+    query
+        .validate()
+        .map_err(|e| crate::Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let movements = CashMovementRepository::new(&db.pg_pool)
+        .get_movements_page(portfolio.id, query.before, limit)
+        .await?;
+
+    let next_cursor = if movements.len() as i64 == limit {
+        movements.last().map(|movement| movement.id)
+    } else {
+        None
+    };
+
+    Ok(Json(CashHistoryResponse {
+        movements: movements.into_iter().map(Into::into).collect(),
+        next_cursor,
+    }))
+}
+
+/// Get the ledger of the selected portfolio, newest entry first
+///
+/// Every change to the cash balance is an entry of the ledger, and the
+/// `balance_after` of the newest entry is the current balance. Pass the
+/// returned `next_cursor` as `before` to fetch the following page; it is
+/// absent on the last page.
+#[utoipa::path(
+    get,
+    path = "/ledger",
+    tag = "balance",
+    params(SelectedPortfolio, LedgerQuery),
+    responses(
+        (status = 200, description = "One page of ledger entries", body = LedgerPageResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_ledger(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    db: Extension<AppState>,
+    Query(query): Query<LedgerQuery>,
+) -> Result<Json<LedgerPageResponse>> {
+    query
+        .validate()
+        .map_err(|e| crate::Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let entries = LedgerRepository::new(&db.pg_pool)
+        .get_entries_page(portfolio.id, query.before, limit)
+        .await?;
+
+    let next_cursor = if entries.len() as i64 == limit {
+        entries.last().map(|entry| entry.id)
+    } else {
+        None
+    };
+
+    Ok(Json(LedgerPageResponse {
+        entries: entries.into_iter().map(Into::into).collect(),
+        next_cursor,
+    }))
+}
+
+/// An amount of cash, as a decimal string such as `"1000.50"`; numbers are
+/// deprecated
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(untagged)]
+enum CashAmount {
+    Decimal(String),
+    Number(f64),
+}
+
+impl CashAmount {
+    /// The amount as given; [`balance`] checks its bounds
+    fn parse(&self) -> Result<BigDecimal> {
+        let text = match self {
+            CashAmount::Decimal(text) => text.trim().to_string(),
+            CashAmount::Number(number) => number.to_string(),
+        };
+
+        text.parse()
+            .map_err(|_| crate::Error::BadRequest("Invalid amount format".into()))
+    }
+
+    /// `Deprecation` header for numeric amounts
+    fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let CashAmount::Number(_) = self {
+            headers.insert("deprecation", HeaderValue::from_static("true"));
+        }
+        headers
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct DepositRequest {
+    amount: CashAmount,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct WithdrawRequest {
+    amount: CashAmount,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct CashMovementResponse {
+    id: i32,
+    portfolio_id: i32,
+    /// `deposit` or `withdrawal`
+    movement_type: String,
+    amount: BigDecimal,
+    /// Cash balance once the movement was posted
+    balance_after: BigDecimal,
+    created_at: DateTime<Utc>,
+}
+
+impl From<CashMovement> for CashMovementResponse {
+    fn from(movement: CashMovement) -> Self {
+        CashMovementResponse {
+            id: movement.id,
+            portfolio_id: movement.portfolio_id,
+            movement_type: movement.movement_type,
+            amount: movement.amount,
+            balance_after: movement.balance_after,
+            created_at: movement.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct HistoryQuery {
+    /// Cursor from a previous page
+    before: Option<i32>,
+    #[validate(range(min = 1, max = "MAX_PAGE_SIZE"))]
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct CashHistoryResponse {
+    movements: Vec<CashMovementResponse>,
+    /// Cursor of the next page, absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct LedgerQuery {
+    /// Cursor from a previous page
+    before: Option<i64>,
+    #[validate(range(min = 1, max = "MAX_PAGE_SIZE"))]
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct LedgerPageResponse {
+    entries: Vec<LedgerEntryResponse>,
+    /// Cursor of the next page, absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct LedgerEntryResponse {
+    id: i64,
+    portfolio_id: i32,
+    /// `opening`, `deposit`, `withdrawal`, `allowance`, `transfer`, `buy`,
+    /// `sell`, `fee`, `dividend`, `coupon`, `redemption`, `loan`, `sweep`,
+    /// `premium`, `margin`, `settlement` or `cash_in_lieu`
+    entry_type: String,
+    /// Positive amounts credit the cash, negative ones debit it
+    amount: BigDecimal,
+    /// Cash balance once the entry was posted
+    balance_after: BigDecimal,
+    /// Transaction the entry settles, if any
+    transaction_id: Option<Uuid>,
+    /// Other portfolio of a transfer
+    counter_portfolio_id: Option<i32>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<LedgerEntry> for LedgerEntryResponse {
+    fn from(entry: LedgerEntry) -> Self {
+        LedgerEntryResponse {
+            id: entry.id,
+            portfolio_id: entry.portfolio_id,
+            entry_type: entry.entry_type,
+            amount: entry.amount,
+            balance_after: entry.balance_after,
+            transaction_id: entry.transaction_id,
+            counter_portfolio_id: entry.counter_portfolio_id,
+            created_at: entry.created_at,
+        }
+    }
+}
@@ -0,0 +1,490 @@
+use std::collections::HashMap;
+
+use axum::{
+    Extension, Router,
+    extract::Path,
+    routing::{get, post},
+};
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{OpenApi, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::jwt::Claims,
+    models::class::{Class, ClassMember, ClassRole},
+    repository::class_repository::ClassRepository,
+    services::{classes, portfolio},
+    timing::Json,
+};
+
+/// Most tickers a class may allow
+const MAX_ALLOWED_TICKERS: u64 = 500;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_classes,
+    create_class,
+    join_class,
+    get_class,
+    update_class,
+    reset_invite_code,
+    get_dashboard
+))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/", get(get_classes).post(create_class))
+        .route("/join", post(join_class))
+        .route("/{id}", get(get_class).patch(update_class))
+        .route("/{id}/invite-code", post(reset_invite_code))
+        .route("/{id}/dashboard", get(get_dashboard))
+}
+
+/// List the classes the authenticated user teaches or attends
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "classes",
+    responses(
+        (status = 200, description = "Classes, latest joined first", body = Vec<ClassResponse>),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_classes(
+    claims: Claims,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<ClassResponse>>> {
+    let classes = ClassRepository::new(&state.pg_pool)
+        .get_classes_by_user(claims.user_id)
+        .await?;
+
+    Ok(Json(
+        classes
+            .into_iter()
+            .map(|(class, member)| ClassResponse::new(class, &member))
+            .collect(),
+    ))
+}
+
+/// Create a class taught by the authenticated user
+///
+/// Students join with the returned `invite_code` and get a class portfolio
+/// funded with `starting_balance`. With `allowed_tickers` they can only buy
+/// those tickers; leave it out or empty to allow every ticker.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "classes",
+    request_body = CreateClassRequest,
+    responses(
+        (status = 200, description = "Class created", body = ClassResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn create_class(
+    claims: Claims,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateClassRequest>,
+) -> Result<Json<ClassResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let name = class_name(&payload.name)?;
+    let starting_balance = amount(payload.starting_balance)?;
+    let allowed_tickers = payload
+        .allowed_tickers
+        .as_deref()
+        .and_then(normalize_tickers);
+
+    let repository = ClassRepository::new(&state.pg_pool);
+    let class = repository
+        .create_class(
+            claims.user_id,
+            name,
+            starting_balance,
+            allowed_tickers.as_deref(),
+            &classes::generate_invite_code(),
+        )
+        .await?;
+    let member = repository
+        .get_member(class.id, claims.user_id)
+        .await?
+        .ok_or(Error::InternalServerError)?;
+
+    Ok(Json(ClassResponse::new(class, &member)))
+}
+
+/// Join a class as a student with the invite code from its instructor
+///
+/// Creates a class portfolio named after the class and funded with its
+/// starting balance; select it with `X-Portfolio-Id` to trade. Cash cannot
+/// be deposited, withdrawn or transferred in or out of it.
+#[utoipa::path(
+    post,
+    path = "/join",
+    tag = "classes",
+    request_body = JoinClassRequest,
+    responses(
+        (status = 200, description = "Class joined", body = ClassResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "No class has this invite code", body = ErrorResponse),
+        (status = 409, description = "Already a member, or a portfolio of the class's name exists", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn join_class(
+    claims: Claims,
+    state: Extension<AppState>,
+    Json(payload): Json<JoinClassRequest>,
+) -> Result<Json<ClassResponse>> {
+    let repository = ClassRepository::new(&state.pg_pool);
+    let class = repository
+        .get_class_by_invite_code(&payload.invite_code.trim().to_uppercase())
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    if repository
+        .get_member(class.id, claims.user_id)
+        .await?
+        .is_some()
+    {
+        return Err(Error::Conflict("Already a member of this class".into()));
+    }
+    repository
+        .join(&class, claims.user_id, &class.name)
+        .await?
+        .ok_or_else(|| {
+            Error::Conflict(format!("A portfolio named {} already exists", class.name))
+        })?;
+    let member = repository
+        .get_member(class.id, claims.user_id)
+        .await?
+        .ok_or(Error::InternalServerError)?;
+
+    Ok(Json(ClassResponse::new(class, &member)))
+}
+
+/// Get a class the authenticated user is a member of
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "classes",
+    params(("id" = i32, Path, description = "Class ID")),
+    responses(
+        (status = 200, description = "Class", body = ClassResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Class not found or not a member", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_class(
+    claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<ClassResponse>> {
+    let (class, member) = membership(&state, id, claims.user_id).await?;
+
+    Ok(Json(ClassResponse::new(class, &member)))
+}
+
+/// Change the rules of a class
+///
+/// Only the fields present change. A new starting balance applies to
+/// students who join afterwards. An empty `allowed_tickers` allows every
+/// ticker; students can always sell what they hold. Instructors only.
+#[utoipa::path(
+    patch,
+    path = "/{id}",
+    tag = "classes",
+    params(("id" = i32, Path, description = "Class ID")),
+    request_body = UpdateClassRequest,
+    responses(
+        (status = 200, description = "Class updated", body = ClassResponse),
+        (status = 400, description = "Validation error or nothing to update", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 403, description = "Not an instructor of the class", body = ErrorResponse),
+        (status = 404, description = "Class not found or not a member", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn update_class(
+    claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+    Json(payload): Json<UpdateClassRequest>,
+) -> Result<Json<ClassResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+    if payload.name.is_none()
+        && payload.starting_balance.is_none()
+        && payload.allowed_tickers.is_none()
+    {
+        return Err(Error::BadRequest("No class fields to update".into()));
+    }
+
+    let (class, member) = instructor(&state, id, claims.user_id).await?;
+    let name = match &payload.name {
+        Some(name) => class_name(name)?,
+        None => class.name.as_str(),
+    };
+    let starting_balance = match payload.starting_balance {
+        Some(starting_balance) => amount(starting_balance)?,
+        None => class.starting_balance.clone(),
+    };
+    let allowed_tickers = match &payload.allowed_tickers {
+        Some(tickers) => normalize_tickers(tickers),
+        None => class.allowed_tickers.clone(),
+    };
+
+    let class = ClassRepository::new(&state.pg_pool)
+        .update_class(id, name, starting_balance, allowed_tickers.as_deref())
+        .await?;
+
+    Ok(Json(ClassResponse::new(class, &member)))
+}
+
+/// Replace the invite code of a class
+///
+/// The old code stops working; students who joined stay in the class.
+/// Instructors only.
+#[utoipa::path(
+    post,
+    path = "/{id}/invite-code",
+    tag = "classes",
+    params(("id" = i32, Path, description = "Class ID")),
+    responses(
+        (status = 200, description = "Class with its new invite code", body = ClassResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 403, description = "Not an instructor of the class", body = ErrorResponse),
+        (status = 404, description = "Class not found or not a member", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn reset_invite_code(
+    claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<ClassResponse>> {
+    let (_, member) = instructor(&state, id, claims.user_id).await?;
+
+    let class = ClassRepository::new(&state.pg_pool)
+        .set_invite_code(id, &classes::generate_invite_code())
+        .await?;
+
+    Ok(Json(ClassResponse::new(class, &member)))
+}
+
+/// Get the class dashboard of every student's portfolio
+///
+/// Students are valued at the latest prices and listed by equity, highest
+/// first. Instructors only.
+#[utoipa::path(
+    get,
+    path = "/{id}/dashboard",
+    tag = "classes",
+    params(("id" = i32, Path, description = "Class ID")),
+    responses(
+        (status = 200, description = "Students and their portfolios", body = DashboardResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 403, description = "Not an instructor of the class", body = ErrorResponse),
+        (status = 404, description = "Class not found or not a member", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_dashboard(
+    claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<DashboardResponse>> {
+    let (class, _) = instructor(&state, id, claims.user_id).await?;
+
+    let repository = ClassRepository::new(&state.pg_pool);
+    let students = repository.get_students(id).await?;
+    let mut portfolios: HashMap<i32, _> = repository
+        .get_portfolios(id)
+        .await?
+        .into_iter()
+        .map(|portfolio| (portfolio.id, portfolio))
+        .collect();
+
+    let mut entries = Vec::with_capacity(students.len());
+    for student in students {
+        let Some(student_portfolio) = portfolios.remove(&student.portfolio_id) else {
+            continue;
+        };
+        let valuation = portfolio::value_portfolio(&state, &student_portfolio).await?;
+        entries.push(StudentEntry {
+            user_id: student.user_id,
+            email: student.email,
+            display_name: student.display_name,
+            portfolio_id: student.portfolio_id,
+            return_percent: portfolio::percent_of(
+                &(&valuation.equity - &student.starting_balance),
+                &student.starting_balance,
+            ),
+            cash: valuation.cash,
+            market_value: valuation.market_value,
+            equity: valuation.equity,
+            positions: valuation.positions.len(),
+            joined_at: student.joined_at,
+        });
+    }
+    // Students are listed by when they joined, so ties keep that order
+    entries.sort_by(|a, b| b.equity.cmp(&a.equity));
+
+    Ok(Json(DashboardResponse {
+        class_id: class.id,
+        name: class.name,
+        students: entries,
+    }))
+}
+
+/// The class and the user's membership, `404` for non-members
+async fn membership(state: &AppState, class_id: i32, user_id: i32) -> Result<(Class, ClassMember)> {
+    let repository = ClassRepository::new(&state.pg_pool);
+    let member = repository
+        .get_member(class_id, user_id)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let class = repository
+        .get_class(class_id)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    Ok((class, member))
+}
+
+/// Like [`membership`], but `403` for students
+async fn instructor(state: &AppState, class_id: i32, user_id: i32) -> Result<(Class, ClassMember)> {
+    let (class, member) = membership(state, class_id, user_id).await?;
+    if member.role != ClassRole::Instructor.as_str() {
+        return Err(Error::Forbidden);
+    }
+
+    Ok((class, member))
+}
+
+fn class_name(name: &str) -> Result<&str> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::BadRequest("Class name must not be blank".into()));
+    }
+
+    Ok(name)
+}
+
+fn amount(value: f64) -> Result<BigDecimal> {
+    Ok(BigDecimal::from_f64(value)
+        .ok_or_else(|| Error::BadRequest("Invalid amount format".into()))?
+        .with_scale_round(2, RoundingMode::HalfUp))
+}
+
+/// Upper-cased, deduplicated tickers, `None` to allow every ticker
+fn normalize_tickers(tickers: &[String]) -> Option<Vec<String>> {
+    let mut tickers: Vec<String> = tickers
+        .iter()
+        .map(|ticker| ticker.trim().to_uppercase())
+        .filter(|ticker| !ticker.is_empty())
+        .collect();
+    tickers.sort();
+    tickers.dedup();
+
+    Some(tickers).filter(|tickers| !tickers.is_empty())
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreateClassRequest {
+    #[validate(length(min = 1, max = 50))]
+    name: String,
+    #[validate(range(min = 100.0, max = 10_000_000.0))]
+    starting_balance: f64,
+    /// Tickers students may buy; every ticker when absent or empty
+    #[validate(length(max = "MAX_ALLOWED_TICKERS"))]
+    allowed_tickers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct UpdateClassRequest {
+    #[validate(length(min = 1, max = 50))]
+    name: Option<String>,
+    #[validate(range(min = 100.0, max = 10_000_000.0))]
+    starting_balance: Option<f64>,
+    /// Replaces the list; empty allows every ticker
+    #[validate(length(max = "MAX_ALLOWED_TICKERS"))]
+    allowed_tickers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct JoinClassRequest {
+    invite_code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(as = Class)]
+struct ClassResponse {
+    id: i32,
+    name: String,
+    starting_balance: BigDecimal,
+    /// Tickers students may buy, `null` for every ticker
+    allowed_tickers: Option<Vec<String>>,
+    /// The caller's role in the class
+    role: ClassRole,
+    /// The caller's class portfolio, `null` for instructors
+    portfolio_id: Option<i32>,
+    /// Shown to instructors only
+    invite_code: Option<String>,
+    created_at: DateTime<Utc>,
+    /// When the caller joined, or created the class
+    joined_at: DateTime<Utc>,
+}
+
+impl ClassResponse {
+    fn new(class: Class, member: &ClassMember) -> Self {
+        let role = member.role.parse().unwrap_or(ClassRole::Student);
+        ClassResponse {
+            id: class.id,
+            name: class.name,
+            starting_balance: class.starting_balance,
+            allowed_tickers: class.allowed_tickers,
+            role,
+            portfolio_id: member.portfolio_id,
+            invite_code: (role == ClassRole::Instructor).then_some(class.invite_code),
+            created_at: class.created_at,
+            joined_at: member.joined_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DashboardResponse {
+    class_id: i32,
+    name: String,
+    /// Highest equity first
+    students: Vec<StudentEntry>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct StudentEntry {
+    user_id: Uuid,
+    email: String,
+    display_name: Option<String>,
+    portfolio_id: i32,
+    cash: BigDecimal,
+    market_value: BigDecimal,
+    equity: BigDecimal,
+    /// Gain on the starting balance the student joined with, in percent
+    return_percent: BigDecimal,
+    /// Number of open positions
+    positions: usize,
+    joined_at: DateTime<Utc>,
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use axum::{
+    Extension, Router,
+    extract::{Path, Query},
+    routing::{get, post},
+};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::jwt::Claims,
+    models::competition::Competition,
+    repository::{
+        competition_repository::CompetitionRepository,
+        user_settings_repository::UserSettingsRepository,
+    },
+    services::competitions::{self, Standing, Status},
+    timing::Json,
+};
+
+/// Standings returned when no limit is given
+const DEFAULT_STANDINGS_LIMIT: usize = 50;
+/// Most standings a request may ask for
+const MAX_STANDINGS_LIMIT: usize = 500;
+
+#[derive(OpenApi)]
+#[openapi(paths(get_competitions, join_competition, get_standings))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/", get(get_competitions))
+        .route("/{id}/join", post(join_competition))
+        .route("/{id}/standings", get(get_standings))
+}
+
+/// List the trading competitions, latest start first
+///
+/// `portfolio_id` is the caller's competition portfolio in the competitions
+/// they joined.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "competitions",
+    responses(
+        (status = 200, description = "Competitions", body = Vec<CompetitionResponse>),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_competitions(
+    claims: Claims,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<CompetitionResponse>>> {
+    let repository = CompetitionRepository::new(&state.pg_pool);
+    let competitions = repository.get_competitions().await?;
+    let portfolios: HashMap<i32, i32> = repository
+        .get_entries_by_user(claims.user_id)
+        .await?
+        .into_iter()
+        .map(|entry| (entry.competition_id, entry.portfolio_id))
+        .collect();
+
+    let now = Utc::now();
+    Ok(Json(
+        competitions
+            .into_iter()
+            .map(|competition| {
+                let portfolio_id = portfolios.get(&competition.id).copied();
+                CompetitionResponse::new(competition, portfolio_id, now)
+            })
+            .collect(),
+    ))
+}
+
+/// Join a competition that has not ended
+///
+/// Creates a competition portfolio named after the competition and funded
+/// with its starting balance; select it with `X-Portfolio-Id` to trade. It
+/// can only trade while the competition is running, and cash cannot be
+/// deposited, withdrawn or transferred in or out of it.
+#[utoipa::path(
+    post,
+    path = "/{id}/join",
+    tag = "competitions",
+    params(("id" = i32, Path, description = "Competition ID")),
+    responses(
+        (status = 200, description = "Competition joined", body = CompetitionResponse),
+        (status = 400, description = "The competition has ended", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Competition not found", body = ErrorResponse),
+        (status = 409, description = "Already joined, or a portfolio of the competition's name exists", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn join_competition(
+    claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<CompetitionResponse>> {
+    let repository = CompetitionRepository::new(&state.pg_pool);
+    let competition = repository
+        .get_competition(id)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    let now = Utc::now();
+    if competitions::status(&competition, now) == Status::Ended {
+        return Err(Error::BadRequest("The competition has ended".into()));
+    }
+    if repository.get_entry(id, claims.user_id).await?.is_some() {
+        return Err(Error::Conflict("Already joined this competition".into()));
+    }
+
+    let portfolio = repository
+        .join(&competition, claims.user_id, &competition.name)
+        .await?
+        .ok_or_else(|| {
+            Error::Conflict(format!(
+                "A portfolio named {} already exists",
+                competition.name
+            ))
+        })?;
+
+    Ok(Json(CompetitionResponse::new(
+        competition,
+        Some(portfolio.id),
+        now,
+    )))
+}
+
+/// Get the standings of a competition
+///
+/// Entrants are ranked by the equity of their competition portfolio, valued
+/// at the latest prices while the competition runs. Once it has ended the
+/// standings are frozen and `final` is true. Entrants show up by their
+/// display name, if they set one; `you` is the caller's own place, `null`
+/// when they did not join.
+#[utoipa::path(
+    get,
+    path = "/{id}/standings",
+    tag = "competitions",
+    params(("id" = i32, Path, description = "Competition ID"), StandingsQuery),
+    responses(
+        (status = 200, description = "Standings, best first", body = StandingsResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Competition not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_standings(
+    claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+    Query(query): Query<StandingsQuery>,
+) -> Result<Json<StandingsResponse>> {
+    query
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let competition = CompetitionRepository::new(&state.pg_pool)
+        .get_competition(id)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    let standings = competitions::standings(&state, &competition).await?;
+    let you = standings
+        .iter()
+        .find(|standing| standing.user_id == claims.user_id)
+        .cloned();
+    let limit = query.limit.unwrap_or(DEFAULT_STANDINGS_LIMIT);
+
+    let mut user_ids: Vec<i32> = standings.iter().take(limit).map(|s| s.user_id).collect();
+    user_ids.push(claims.user_id);
+    let names = UserSettingsRepository::new(&state.pg_pool)
+        .get_display_names(&user_ids)
+        .await?;
+
+    Ok(Json(StandingsResponse {
+        competition_id: competition.id,
+        status: competitions::status(&competition, Utc::now()),
+        r#final: competition.finalized_at.is_some(),
+        entrants: standings.len(),
+        standings: standings
+            .into_iter()
+            .take(limit)
+            .map(|standing| StandingEntry::new(standing, &names, claims.user_id))
+            .collect(),
+        you: you.map(|standing| StandingEntry::new(standing, &names, claims.user_id)),
+    }))
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct StandingsQuery {
+    #[validate(range(min = 1, max = "MAX_STANDINGS_LIMIT"))]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(as = Competition)]
+struct CompetitionResponse {
+    id: i32,
+    name: String,
+    description: String,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+    starting_balance: BigDecimal,
+    /// `beginner` or `realistic`, the rules every entrant trades under
+    difficulty: String,
+    /// Simulated seconds per real second, e.g. 24 for a trading day per hour
+    clock_speed: f64,
+    /// Current time on the competition's clock
+    sim_time: DateTime<Utc>,
+    status: Status,
+    /// Whether the final standings are recorded
+    r#final: bool,
+    /// The caller's competition portfolio, `null` when they did not join
+    portfolio_id: Option<i32>,
+}
+
+impl CompetitionResponse {
+    fn new(competition: Competition, portfolio_id: Option<i32>, now: DateTime<Utc>) -> Self {
+        CompetitionResponse {
+            status: competitions::status(&competition, now),
+            sim_time: competitions::sim_time(&competition, now),
+            r#final: competition.finalized_at.is_some(),
+            id: competition.id,
+            name: competition.name,
+            description: competition.description,
+            starts_at: competition.starts_at,
+            ends_at: competition.ends_at,
+            starting_balance: competition.starting_balance,
+            difficulty: competition.difficulty,
+            clock_speed: competition.clock_speed,
+            portfolio_id,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct StandingsResponse {
+    competition_id: i32,
+    status: Status,
+    /// Whether the standings are frozen
+    r#final: bool,
+    /// Number of users ranked
+    entrants: usize,
+    standings: Vec<StandingEntry>,
+    you: Option<StandingEntry>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct StandingEntry {
+    rank: i32,
+    /// `null` for users without a display name
+    display_name: Option<String>,
+    /// Equity of the competition portfolio
+    equity: BigDecimal,
+    /// Gain on the starting balance in percent
+    return_percent: BigDecimal,
+    /// Whether this is the caller
+    is_you: bool,
+}
+
+impl StandingEntry {
+    fn new(standing: Standing, names: &HashMap<i32, String>, caller_id: i32) -> Self {
+        StandingEntry {
+            rank: standing.rank,
+            display_name: names.get(&standing.user_id).cloned(),
+            equity: standing.equity,
+            return_percent: standing.return_percent,
+            is_you: standing.user_id == caller_id,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use axum::{Extension, Router, extract::Query, routing::get};
+use bigdecimal::BigDecimal;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result, auth::jwt::Claims, models::follow::FeedTrade,
+    repository::follow_repository::FollowRepository, timing::Json,
+};
+
+/// Default number of trades per page
+const DEFAULT_PAGE_SIZE: i64 = 50;
+/// Largest page a client may request
+const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(OpenApi)]
+#[openapi(paths(get_feed))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new().route("/", get(get_feed))
+}
+
+/// Get the trades of the users the caller follows, newest first
+///
+/// Only buys and sells in public portfolios are listed, each once its
+/// trader's delay has passed. `quantity` is `null` for traders who hide
+/// quantities. Pass the returned `next_cursor` as `before` to fetch the
+/// following page; it is absent on the last page.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "feed",
+    params(FeedQuery),
+    responses(
+        (status = 200, description = "One page of trades", body = FeedResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_feed(
+    claims: Claims,
+    state: Extension<AppState>,
+    Query(query): Query<FeedQuery>,
+) -> Result<Json<FeedResponse>> {
+    query
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let trades = FollowRepository::new(&state.pg_pool)
+        .get_feed(claims.user_id, query.before, limit)
+        .await?;
+
+    let next_cursor = if trades.len() as i64 == limit {
+        trades.last().map(|trade| trade.transaction_id)
+    } else {
+        None
+    };
+
+    Ok(Json(FeedResponse {
+        trades: trades.into_iter().map(Into::into).collect(),
+        next_cursor,
+    }))
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FeedQuery {
+    /// Cursor from a previous page
+    before: Option<Uuid>,
+    #[validate(range(min = 1, max = "MAX_PAGE_SIZE"))]
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct FeedResponse {
+    trades: Vec<FeedItem>,
+    /// Cursor of the next page, absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct FeedItem {
+    transaction_id: Uuid,
+    user_id: Uuid,
+    /// `null` for users without a display name
+    display_name: Option<String>,
+    portfolio_id: i32,
+    portfolio_name: String,
+    ticker: String,
+    /// `buy` or `sell`
+    transaction_type: String,
+    /// `null` when the trader hides quantities
+    quantity: Option<i32>,
+    price: BigDecimal,
+    created_at: NaiveDateTime,
+}
+
+impl From<FeedTrade> for FeedItem {
+    fn from(trade: FeedTrade) -> Self {
+        FeedItem {
+            transaction_id: trade.transaction_id,
+            user_id: trade.user_id,
+            display_name: trade.display_name,
+            portfolio_id: trade.portfolio_id,
+            portfolio_name: trade.portfolio_name,
+            ticker: trade.ticker,
+            transaction_type: trade.transaction_type,
+            quantity: (!trade.hide_quantity).then_some(trade.quantity),
+            price: trade.price,
+            created_at: trade.created_at,
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use axum::{Extension, Router, extract::Query, routing::get};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::portfolio::SelectedPortfolio,
+    models::holding::ClosedPosition,
+    services::{
+        bonds,
+        portfolio::{self, PositionValuation},
+        price_cache::PriceCache,
+    },
+    timing::Json,
+};
+
+/// Default number of closed positions per page
+const DEFAULT_PAGE_SIZE: i64 = 50;
+/// Largest page a client may request
+const MAX_PAGE_SIZE: i64 = 200;
+
+#[derive(OpenApi)]
+#[openapi(paths(get_holdings, get_closed_positions))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/", get(get_holdings))
+        .route("/closed", get(get_closed_positions))
+}
+
+/// Get the open holdings of the selected portfolio
+///
+/// Each row is valued at the latest cached price, fetched for all tickers in
+/// one round trip. Holdings without a cached price are valued at their
+/// average price. Bonds add the interest accrued since their last coupon to
+/// their market value. `percent_of_portfolio` is the row's share of the market
+/// value of all holdings.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "portfolio",
+    params(SelectedPortfolio),
+    responses(
+        (status = 200, description = "Holdings valued at the latest prices", body = Vec<HoldingResponse>),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_holdings(
+    SelectedPortfolio(selected): SelectedPortfolio,
+    db: Extension<AppState>,
+) -> Result<Json<Vec<HoldingResponse>>> {
+    // Read from the primary, so a trade's position shows right after it
+    let holdings_repository = &db.repos.holdings;
+
+    let holdings = holdings_repository
+        .get_holdings_by_portfolio(selected.id)
+        .await?;
+
+    let ids: Vec<Uuid> = holdings.iter().map(|h| h.public_id).collect();
+    let tickers: Vec<String> = holdings.iter().map(|h| h.ticker.clone()).collect();
+    let prices = PriceCache::new(&db.redis_pool).prices(&tickers).await?;
+    let accrued = bonds::accrued_interest_by_ticker(&db, &tickers).await?;
+
+    let zero = || BigDecimal::from(0);
+    let valuation = portfolio::valuate(
+        selected.balance,
+        zero(),
+        zero(),
+        holdings,
+        &prices,
+        &accrued,
+    );
+
+    let response: Vec<HoldingResponse> = ids
+        .into_iter()
+        .zip(valuation.positions)
+        .map(|(id, position)| HoldingResponse::new(id, position, &valuation.market_value))
+        .collect();
+
+    Ok(Json(response))
+}
+
+/// Get the closed positions of the selected portfolio, newest first
+///
+/// A position closes when its last share is sold, redeemed or split away;
+/// buying the ticker again opens a new position. `realized_gain` sums the
+/// gains realized by the sells while the position was open. Pass the
+/// returned `next_cursor` as `before` to fetch the following page; it is
+/// absent on the last page.
+#[utoipa::path(
+    get,
+    path = "/closed",
+    tag = "portfolio",
+    params(SelectedPortfolio, ClosedPositionsQuery),
+    responses(
+        (status = 200, description = "One page of closed positions", body = ClosedPositionsResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_closed_positions(
+    SelectedPortfolio(selected): SelectedPortfolio,
+    db: Extension<AppState>,
+    Query(query): Query<ClosedPositionsQuery>,
+) -> Result<Json<ClosedPositionsResponse>> {
+    query
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let positions = db
+        .read_repos
+        .holdings
+        .get_closed_positions_page(selected.id, query.before, limit)
+        .await?;
+
+    let next_cursor = if positions.len() as i64 == limit {
+        positions.last().map(|position| position.public_id)
+    } else {
+        None
+    };
+
+    Ok(Json(ClosedPositionsResponse {
+        positions: positions.into_iter().map(Into::into).collect(),
+        next_cursor,
+    }))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct HoldingResponse {
+    id: Uuid,
+    ticker: String,
+    quantity: i32,
+    average_price: BigDecimal,
+    current_price: Option<BigDecimal>,
+    /// Interest accrued on a bond since its last coupon, included in the market value
+    accrued_interest: BigDecimal,
+    market_value: BigDecimal,
+    unrealized_pnl: BigDecimal,
+    percent_of_portfolio: BigDecimal,
+    /// When the position was opened
+    created_at: DateTime<Utc>,
+    /// Last trade or corporate action that changed the position
+    updated_at: DateTime<Utc>,
+}
+
+impl HoldingResponse {
+    fn new(id: Uuid, position: PositionValuation, total_market_value: &BigDecimal) -> Self {
+        HoldingResponse {
+            id,
+            percent_of_portfolio: portfolio::percent_of(&position.market_value, total_market_value),
+            ticker: position.ticker,
+            quantity: position.quantity,
+            average_price: position.average_price,
+            current_price: position.current_price,
+            accrued_interest: position.accrued_interest,
+            market_value: position.market_value,
+            unrealized_pnl: position.unrealized_pnl,
+            created_at: position.created_at,
+            updated_at: position.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ClosedPositionsQuery {
+    /// Cursor from a previous page
+    before: Option<Uuid>,
+    #[validate(range(min = 1, max = "MAX_PAGE_SIZE"))]
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ClosedPositionsResponse {
+    positions: Vec<ClosedPositionResponse>,
+    /// Cursor of the next page, absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ClosedPositionResponse {
+    id: Uuid,
+    ticker: String,
+    opened_at: DateTime<Utc>,
+    closed_at: DateTime<Utc>,
+    /// Gain realized by the sells while the position was open
+    realized_gain: BigDecimal,
+}
+
+impl From<ClosedPosition> for ClosedPositionResponse {
+    fn from(position: ClosedPosition) -> Self {
+        ClosedPositionResponse {
+            id: position.public_id,
+            ticker: position.ticker,
+            opened_at: position.created_at,
+            closed_at: position.closed_at,
+            realized_gain: position.realized_gain,
+        }
+    }
+}
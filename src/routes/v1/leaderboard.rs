@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use axum::{Extension, Router, extract::Query, routing::get};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::jwt::Claims,
+    repository::user_settings_repository::UserSettingsRepository,
+    services::leaderboard::{self, Period, Ranking},
+    timing::Json,
+};
+
+/// Entries returned when no limit is given
+const DEFAULT_LEADERBOARD_LIMIT: usize = 10;
+/// Most entries a request may ask for
+const MAX_LEADERBOARD_LIMIT: usize = 100;
+
+#[derive(OpenApi)]
+#[openapi(paths(get_leaderboard))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new().route("/", get(get_leaderboard))
+}
+
+/// Get the users with the best returns over a period
+///
+/// Users are ranked by the time-weighted return of their account, so
+/// deposits and withdrawals do not move them. Rankings are refreshed with
+/// the daily snapshots. Users show up by their display name, if they set
+/// one, and can leave the leaderboard with `leaderboard_opt_out` on
+/// `PATCH /me`. `you` is the caller's own place, `null` when unranked.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "leaderboard",
+    params(LeaderboardQuery),
+    responses(
+        (status = 200, description = "Best returns of the period", body = LeaderboardResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_leaderboard(
+    claims: Claims,
+    state: Extension<AppState>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Result<Json<LeaderboardResponse>> {
+    query
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+    let period = query.period.unwrap_or_default();
+
+    let rankings = leaderboard::top(
+        &state,
+        period,
+        query.limit.unwrap_or(DEFAULT_LEADERBOARD_LIMIT),
+    )
+    .await?;
+    let you = leaderboard::rank_of(&state, period, claims.user_id).await?;
+
+    let mut user_ids: Vec<i32> = rankings.iter().map(|r| r.user_id).collect();
+    user_ids.push(claims.user_id);
+    let names = UserSettingsRepository::new(&state.pg_pool)
+        .get_display_names(&user_ids)
+        .await?;
+
+    Ok(Json(LeaderboardResponse {
+        period,
+        entries: rankings
+            .into_iter()
+            .map(|ranking| LeaderboardEntry::new(ranking, &names, claims.user_id))
+            .collect(),
+        you: you.map(|ranking| LeaderboardEntry::new(ranking, &names, claims.user_id)),
+    }))
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct LeaderboardQuery {
+    /// `day`, `week` (default) or `all`
+    period: Option<Period>,
+    #[validate(range(min = 1, max = "MAX_LEADERBOARD_LIMIT"))]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct LeaderboardResponse {
+    period: Period,
+    entries: Vec<LeaderboardEntry>,
+    you: Option<LeaderboardEntry>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct LeaderboardEntry {
+    rank: u64,
+    /// `null` for users without a display name
+    display_name: Option<String>,
+    /// Fraction, 0.05 = 5%
+    time_weighted_return: f64,
+    /// Whether this is the caller
+    is_you: bool,
+}
+
+impl LeaderboardEntry {
+    fn new(ranking: Ranking, names: &HashMap<i32, String>, caller_id: i32) -> Self {
+        LeaderboardEntry {
+            rank: ranking.rank,
+            display_name: names.get(&ranking.user_id).cloned(),
+            time_weighted_return: ranking.time_weighted_return,
+            is_you: ranking.user_id == caller_id,
+        }
+    }
+}
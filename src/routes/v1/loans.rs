@@ -0,0 +1,213 @@
+use axum::{
+    Extension, Router,
+    extract::Path,
+    routing::{get, post},
+};
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{OpenApi, ToSchema};
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::{jwt::Claims, portfolio::SelectedPortfolio},
+    repository::loan_repository::LoanRepository,
+    services::{
+        classes, competitions, feature_flags,
+        loans::{self, LoanValuation},
+    },
+    timing::Json,
+};
+
+#[derive(OpenApi)]
+#[openapi(paths(get_loans, create_loan, repay_loan))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/", get(get_loans).post(create_loan))
+        .route("/{id}/repay", post(repay_loan))
+}
+
+/// List the selected portfolio's loans, newest first
+///
+/// Open loans report their collateral's current value and loan-to-value.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "loans",
+    params(SelectedPortfolio),
+    responses(
+        (status = 200, description = "Loans of the portfolio", body = Vec<LoanResponse>),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_loans(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<LoanResponse>>> {
+    let loans = LoanRepository::new(&state.pg_pool)
+        .get_loans_by_portfolio(portfolio.id)
+        .await?;
+    let valuations = loans::value_loans(&state, loans).await?;
+
+    Ok(Json(valuations.into_iter().map(Into::into).collect()))
+}
+
+/// Borrow cash against holdings pledged from the selected portfolio
+///
+/// The amount may be at most `LOAN_MAX_LTV_PERCENT` of the collateral's
+/// current market value. Pledged shares cannot be sold until the loan is
+/// repaid and are liquidated when the loan-to-value reaches
+/// `LOAN_MARGIN_CALL_LTV_PERCENT`. Requires the `margin_trading` feature;
+/// competition and class portfolios cannot borrow.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "loans",
+    params(SelectedPortfolio),
+    request_body = CreateLoanRequest,
+    responses(
+        (status = 200, description = "Loan taken", body = LoanResponse),
+        (status = 400, description = "Validation error, insufficient collateral, no price, competition or class portfolio", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 403, description = "Margin trading is not enabled for the user", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn create_loan(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateLoanRequest>,
+) -> Result<Json<LoanResponse>> {
+    feature_flags::require(&state, feature_flags::MARGIN_TRADING, portfolio.user_id).await?;
+    competitions::check_cash_movement(&portfolio)?;
+    classes::check_cash_movement(&portfolio)?;
+
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let amount = BigDecimal::from_f64(payload.amount)
+        .ok_or_else(|| Error::BadRequest("Invalid amount format".into()))?
+        .with_scale_round(2, RoundingMode::HalfUp);
+    let collateral: Vec<(String, i32)> = payload
+        .collateral
+        .iter()
+        .map(|c| (c.ticker.trim().to_uppercase(), c.quantity))
+        .collect();
+
+    let valuation = loans::borrow(&state, &portfolio, amount, &collateral).await?;
+
+    Ok(Json(valuation.into()))
+}
+
+/// Repay part or all of a loan from the cash of the portfolio it belongs to
+///
+/// Paying the full outstanding amount closes the loan and releases the
+/// collateral; overpayments are not charged.
+#[utoipa::path(
+    post,
+    path = "/{id}/repay",
+    tag = "loans",
+    params(("id" = i32, Path, description = "Loan ID")),
+    request_body = RepayLoanRequest,
+    responses(
+        (status = 200, description = "Loan after the repayment", body = LoanResponse),
+        (status = 400, description = "Validation error, closed loan or insufficient funds", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Loan not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn repay_loan(
+    claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+    Json(payload): Json<RepayLoanRequest>,
+) -> Result<Json<LoanResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let amount = BigDecimal::from_f64(payload.amount)
+        .ok_or_else(|| Error::BadRequest("Invalid amount format".into()))?
+        .with_scale_round(2, RoundingMode::HalfUp);
+
+    let loan = loans::repay(&state, claims.user_id, id, amount).await?;
+    let valuation = loans::value_loans(&state, vec![loan]).await?.remove(0);
+
+    Ok(Json(valuation.into()))
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreateLoanRequest {
+    #[validate(range(min = 0.01, max = 1_000_000.0))]
+    amount: f64,
+    #[validate(length(min = 1, max = 10), nested)]
+    collateral: Vec<CollateralRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+struct CollateralRequest {
+    #[validate(length(min = 1, max = 10))]
+    ticker: String,
+    #[validate(range(min = 1, max = 1_000_000))]
+    quantity: i32,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct RepayLoanRequest {
+    #[validate(range(min = 0.01, max = 1_000_000.0))]
+    amount: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct LoanResponse {
+    id: i32,
+    principal: BigDecimal,
+    outstanding: BigDecimal,
+    interest_rate_percent: f64,
+    status: String,
+    collateral: Vec<CollateralResponse>,
+    collateral_value: BigDecimal,
+    /// `None` while a collateral price is unavailable or nothing is pledged
+    ltv_percent: Option<f64>,
+    created_at: DateTime<Utc>,
+    closed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct CollateralResponse {
+    ticker: String,
+    quantity: i32,
+}
+
+impl From<LoanValuation> for LoanResponse {
+    fn from(valuation: LoanValuation) -> Self {
+        let loan = valuation.loan;
+        LoanResponse {
+            id: loan.id,
+            principal: loan.principal,
+            outstanding: loan.outstanding.with_scale_round(2, RoundingMode::Up),
+            interest_rate_percent: loan.interest_rate_percent,
+            status: loan.status,
+            collateral: valuation
+                .collateral
+                .into_iter()
+                .map(|c| CollateralResponse {
+                    ticker: c.ticker,
+                    quantity: c.quantity,
+                })
+                .collect(),
+            collateral_value: valuation.collateral_value,
+            ltv_percent: valuation.ltv_percent,
+            created_at: loan.created_at,
+            closed_at: loan.closed_at,
+        }
+    }
+}
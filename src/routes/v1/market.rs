@@ -0,0 +1,650 @@
+use std::collections::HashSet;
+
+use axum::{
+    Extension, Router,
+    extract::{Path, Query},
+    routing::{get, post},
+};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    models::{
+        bond::Bond,
+        instrument::Instrument,
+        news_event::NewsEvent,
+        price_candle::{CandleInterval, PriceCandle},
+        transaction::TradedVolume,
+    },
+    repository::{
+        basket_repository::BasketRepository, bond_repository::BondRepository,
+        instrument_repository::InstrumentRepository, news_repository::NewsRepository,
+        price_candle_repository::PriceCandleRepository,
+    },
+    services::{
+        baskets, bonds,
+        liquidity::{self, MarketDepth},
+        movers::{self, MarketMovers, Mover},
+        quotes::{self, Quote},
+    },
+    timing::Json,
+};
+
+/// Candles returned when `from` is omitted
+const DEFAULT_CANDLES: i64 = 200;
+/// Most candles a single request may span
+const MAX_CANDLES: i64 = 1000;
+/// Search results returned when `limit` is omitted
+const DEFAULT_SEARCH_LIMIT: i64 = 10;
+/// Most search results a client may request
+const MAX_SEARCH_LIMIT: i64 = 50;
+/// Most tickers a batch quote request may ask for
+const MAX_BATCH_QUOTES: u64 = 100;
+/// Entries per movers list when `limit` is omitted
+const DEFAULT_MOVERS_LIMIT: usize = 5;
+/// Most entries per movers list a client may request
+const MAX_MOVERS_LIMIT: usize = 50;
+/// Most book levels per side a client may request
+const MAX_DEPTH_LEVELS: usize = 50;
+/// News items returned when `limit` is omitted
+const DEFAULT_NEWS_LIMIT: i64 = 20;
+/// Most news items a client may request
+const MAX_NEWS_LIMIT: i64 = 100;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_candles,
+    search_instruments,
+    get_quote,
+    get_quotes,
+    get_movers,
+    get_depth,
+    get_news,
+    get_bond,
+    get_basket
+))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/candles/{ticker}", get(get_candles))
+        .route("/search", get(search_instruments))
+        .route("/quote/{ticker}", get(get_quote))
+        .route("/quotes", post(get_quotes))
+        .route("/movers", get(get_movers))
+        .route("/depth/{ticker}", get(get_depth))
+        .route("/news", get(get_news))
+        .route("/bonds/{ticker}", get(get_bond))
+        .route("/baskets/{ticker}", get(get_basket))
+}
+
+/// Get OHLCV candles of a ticker for charting
+///
+/// Returns the candles of `interval` (`1m`, `5m`, `1h` or `1d`, default `1m`)
+/// starting between `from` and `to` (inclusive, RFC 3339). `to` defaults to
+/// now and `from` to 200 intervals before `to`; a range may span at most 1000
+/// intervals. Buckets without price updates have no candle.
+#[utoipa::path(
+    get,
+    path = "/candles/{ticker}",
+    tag = "market",
+    params(("ticker" = String, Path, description = "Instrument symbol"), CandlesQuery),
+    responses(
+        (status = 200, description = "Candles of the range", body = Vec<CandleResponse>),
+        (status = 400, description = "Invalid ticker or range", body = ErrorResponse),
+    )
+)]
+async fn get_candles(
+    Path(ticker): Path<String>,
+    state: Extension<AppState>,
+    Query(query): Query<CandlesQuery>,
+) -> Result<Json<Vec<CandleResponse>>> {
+    if ticker.is_empty() || ticker.len() > 10 {
+        return Err(Error::BadRequest(
+            "Validation error: ticker must be 1 to 10 characters".to_string(),
+        ));
+    }
+
+    let interval = query.interval.unwrap_or(CandleInterval::OneMinute);
+    let width = TimeDelta::seconds(interval.seconds());
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - width * DEFAULT_CANDLES as i32);
+    if from > to {
+        return Err(Error::BadRequest(
+            "`from` must not be after `to`".to_string(),
+        ));
+    }
+    if (to - from).num_seconds() / interval.seconds() > MAX_CANDLES {
+        return Err(Error::BadRequest(format!(
+            "Range spans more than {} candles",
+            MAX_CANDLES
+        )));
+    }
+
+    let candles = PriceCandleRepository::new(&state.pg_read_pool)
+        .get_candles(&ticker, interval, from, to)
+        .await?;
+
+    Ok(Json(candles.into_iter().map(Into::into).collect()))
+}
+
+/// Search active instruments by symbol or name
+///
+/// Matches symbol and name prefixes, words inside names and, to tolerate
+/// typos, similar symbols and names. Exact symbols rank first, followed by
+/// symbol prefixes, name prefixes, word matches and fuzzy matches.
+#[utoipa::path(
+    get,
+    path = "/search",
+    tag = "market",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Matching instruments, best first", body = Vec<SearchResultResponse>),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+    )
+)]
+async fn search_instruments(
+    state: Extension<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchResultResponse>>> {
+    query
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Err(Error::BadRequest(
+            "Validation error: q must not be blank".into(),
+        ));
+    }
+
+    let instruments = InstrumentRepository::new(&state.pg_pool)
+        .search(q, query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+        .await?;
+
+    Ok(Json(instruments.into_iter().map(Into::into).collect()))
+}
+
+/// Get the quote of a single ticker
+///
+/// Returns the last price with its timestamp, the change against the previous
+/// day's close and the day's range. Prices come from the cache, falling back
+/// to the latest daily candle when the cache has none.
+#[utoipa::path(
+    get,
+    path = "/quote/{ticker}",
+    tag = "market",
+    params(("ticker" = String, Path, description = "Instrument symbol")),
+    responses(
+        (status = 200, description = "Quote of the ticker", body = QuoteResponse),
+        (status = 404, description = "No price known for the ticker", body = ErrorResponse),
+    )
+)]
+async fn get_quote(
+    Path(ticker): Path<String>,
+    state: Extension<AppState>,
+) -> Result<Json<QuoteResponse>> {
+    let quote = quotes::quotes(&state, &[ticker])
+        .await?
+        .pop()
+        .ok_or(Error::NotFound)?;
+
+    Ok(Json(quote.into()))
+}
+
+/// Get the quotes of several tickers at once
+///
+/// Quotes are returned in request order; tickers without any known price are
+/// left out.
+#[utoipa::path(
+    post,
+    path = "/quotes",
+    tag = "market",
+    request_body = QuotesRequest,
+    responses(
+        (status = 200, description = "Quotes of the tickers with a price", body = Vec<QuoteResponse>),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+    )
+)]
+async fn get_quotes(
+    state: Extension<AppState>,
+    Json(payload): Json<QuotesRequest>,
+) -> Result<Json<Vec<QuoteResponse>>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let mut tickers = payload.tickers;
+    let mut seen = HashSet::new();
+    tickers.retain(|ticker| seen.insert(ticker.clone()));
+
+    let quotes = quotes::quotes(&state, &tickers).await?;
+
+    Ok(Json(quotes.into_iter().map(Into::into).collect()))
+}
+
+/// Get today's top gainers, losers and most traded tickers
+///
+/// Gainers and losers are ranked by their percentage change since the
+/// previous day's close; most traded tickers by the shares bought and sold in
+/// the simulator today (UTC).
+#[utoipa::path(
+    get,
+    path = "/movers",
+    tag = "market",
+    params(MoversQuery),
+    responses(
+        (status = 200, description = "Today's movers", body = MoversResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+    )
+)]
+async fn get_movers(
+    state: Extension<AppState>,
+    Query(query): Query<MoversQuery>,
+) -> Result<Json<MoversResponse>> {
+    query
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let movers = movers::movers(&state, query.limit.unwrap_or(DEFAULT_MOVERS_LIMIT)).await?;
+
+    Ok(Json(movers.into()))
+}
+
+/// Get the synthetic order book of a ticker
+///
+/// Returns up to `levels` (default 10, at most 50) price levels per side
+/// around the last price, thinned by recent market orders. Market orders
+/// execute against this book. Returns `404` for tickers without any price.
+#[utoipa::path(
+    get,
+    path = "/depth/{ticker}",
+    tag = "market",
+    params(("ticker" = String, Path, description = "Instrument symbol"), DepthQuery),
+    responses(
+        (status = 200, description = "Order book of the ticker", body = MarketDepth),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 404, description = "No price known for the ticker", body = ErrorResponse),
+    )
+)]
+async fn get_depth(
+    Path(ticker): Path<String>,
+    state: Extension<AppState>,
+    Query(query): Query<DepthQuery>,
+) -> Result<Json<MarketDepth>> {
+    query
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let depth = liquidity::market_depth(
+        &state,
+        &ticker,
+        query.levels.unwrap_or(liquidity::DEFAULT_BOOK_LEVELS),
+    )
+    .await?
+    .ok_or(Error::NotFound)?;
+
+    Ok(Json(depth))
+}
+
+/// Get published market news, latest first
+///
+/// Returns up to `limit` (default 20, at most 100) news items, optionally only
+/// those of `ticker`. Scheduled news stays hidden until it is published.
+#[utoipa::path(
+    get,
+    path = "/news",
+    tag = "market",
+    params(NewsQuery),
+    responses(
+        (status = 200, description = "Published news", body = Vec<NewsResponse>),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+    )
+)]
+async fn get_news(
+    state: Extension<AppState>,
+    Query(query): Query<NewsQuery>,
+) -> Result<Json<Vec<NewsResponse>>> {
+    query
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let ticker = query.ticker.map(|ticker| ticker.trim().to_uppercase());
+    let news = NewsRepository::new(&state.pg_pool)
+        .get_published(ticker.as_deref(), query.limit.unwrap_or(DEFAULT_NEWS_LIMIT))
+        .await?;
+
+    Ok(Json(news.into_iter().map(Into::into).collect()))
+}
+
+/// Get the terms of a bond
+///
+/// Returns the face value, coupon schedule and maturity of a bond instrument,
+/// with the coupon paid per bond and the interest accrued per bond since the
+/// last coupon date. Prices of bonds are quoted without accrued interest.
+#[utoipa::path(
+    get,
+    path = "/bonds/{ticker}",
+    tag = "market",
+    params(("ticker" = String, Path, description = "Bond symbol")),
+    responses(
+        (status = 200, description = "Terms of the bond", body = BondResponse),
+        (status = 404, description = "No bond with this ticker", body = ErrorResponse),
+    )
+)]
+async fn get_bond(
+    Path(ticker): Path<String>,
+    state: Extension<AppState>,
+) -> Result<Json<BondResponse>> {
+    let bond = BondRepository::new(&state.pg_pool)
+        .get_bond(&ticker.trim().to_uppercase())
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    Ok(Json(bond.into()))
+}
+
+/// Get the constituents of an index or ETF
+///
+/// Returns the weighted basket an index or ETF is priced from, with the price
+/// it derives from the latest prices of the constituents. A weight is the
+/// number of units of the constituent in one unit of the basket. The price is
+/// absent until every constituent has one.
+#[utoipa::path(
+    get,
+    path = "/baskets/{ticker}",
+    tag = "market",
+    params(("ticker" = String, Path, description = "Index or ETF symbol")),
+    responses(
+        (status = 200, description = "Constituents of the basket", body = BasketResponse),
+        (status = 404, description = "No basket with this ticker", body = ErrorResponse),
+    )
+)]
+async fn get_basket(
+    Path(ticker): Path<String>,
+    state: Extension<AppState>,
+) -> Result<Json<BasketResponse>> {
+    let ticker = ticker.trim().to_uppercase();
+    let constituents: Vec<(String, BigDecimal)> = BasketRepository::new(&state.pg_pool)
+        .get_constituents(&ticker)
+        .await?
+        .into_iter()
+        .map(|constituent| (constituent.ticker, constituent.weight))
+        .collect();
+    if constituents.is_empty() {
+        return Err(Error::NotFound);
+    }
+    let price = baskets::basket_price(&state, &constituents).await?;
+
+    Ok(Json(BasketResponse {
+        ticker,
+        price,
+        constituents: constituents
+            .into_iter()
+            .map(|(ticker, weight)| BasketConstituentResponse { ticker, weight })
+            .collect(),
+    }))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct CandlesQuery {
+    interval: Option<CandleInterval>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct QuotesRequest {
+    #[validate(length(min = 1, max = "MAX_BATCH_QUOTES"))]
+    tickers: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct QuoteResponse {
+    ticker: String,
+    price: BigDecimal,
+    timestamp: Option<DateTime<Utc>>,
+    previous_close: Option<BigDecimal>,
+    change: Option<BigDecimal>,
+    change_percent: Option<BigDecimal>,
+    day_high: BigDecimal,
+    day_low: BigDecimal,
+    /// `cache` or `database`
+    source: &'static str,
+    stale: bool,
+}
+
+impl From<Quote> for QuoteResponse {
+    fn from(quote: Quote) -> Self {
+        QuoteResponse {
+            ticker: quote.ticker,
+            price: quote.price,
+            timestamp: quote.timestamp,
+            previous_close: quote.previous_close,
+            change: quote.change,
+            change_percent: quote.change_percent,
+            day_high: quote.day_high,
+            day_low: quote.day_low,
+            source: quote.source.as_str(),
+            stale: quote.stale,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct MoversQuery {
+    #[validate(range(min = 1, max = "MAX_MOVERS_LIMIT"))]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DepthQuery {
+    #[validate(range(min = 1, max = "MAX_DEPTH_LEVELS"))]
+    levels: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct NewsQuery {
+    #[validate(length(min = 1, max = 10))]
+    ticker: Option<String>,
+    #[validate(range(min = 1, max = "MAX_NEWS_LIMIT"))]
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct NewsResponse {
+    id: i32,
+    ticker: String,
+    /// `earnings_beat`, `earnings_miss`, `upgrade`, `downgrade` or `other`
+    event_type: String,
+    headline: String,
+    published_at: DateTime<Utc>,
+}
+
+impl From<NewsEvent> for NewsResponse {
+    fn from(news: NewsEvent) -> Self {
+        NewsResponse {
+            id: news.id,
+            ticker: news.ticker,
+            event_type: news.event_type,
+            headline: news.headline,
+            published_at: news.publish_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct MoversResponse {
+    date: NaiveDate,
+    gainers: Vec<MoverResponse>,
+    losers: Vec<MoverResponse>,
+    most_traded: Vec<TradedVolumeResponse>,
+}
+
+impl From<MarketMovers> for MoversResponse {
+    fn from(movers: MarketMovers) -> Self {
+        MoversResponse {
+            date: movers.day.date_naive(),
+            gainers: movers.gainers.into_iter().map(Into::into).collect(),
+            losers: movers.losers.into_iter().map(Into::into).collect(),
+            most_traded: movers.most_traded.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct MoverResponse {
+    ticker: String,
+    price: BigDecimal,
+    previous_close: BigDecimal,
+    change: BigDecimal,
+    change_percent: BigDecimal,
+}
+
+impl From<Mover> for MoverResponse {
+    fn from(mover: Mover) -> Self {
+        MoverResponse {
+            ticker: mover.ticker,
+            price: mover.price,
+            previous_close: mover.previous_close,
+            change: mover.change,
+            change_percent: mover.change_percent,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct TradedVolumeResponse {
+    ticker: String,
+    volume: i64,
+    trades: i64,
+}
+
+impl From<TradedVolume> for TradedVolumeResponse {
+    fn from(traded: TradedVolume) -> Self {
+        TradedVolumeResponse {
+            ticker: traded.ticker,
+            volume: traded.volume,
+            trades: traded.trades,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SearchQuery {
+    #[validate(length(min = 1, max = 50))]
+    q: String,
+    #[validate(range(min = 1, max = "MAX_SEARCH_LIMIT"))]
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct SearchResultResponse {
+    ticker: String,
+    name: String,
+    sector: Option<String>,
+    asset_class: String,
+    tick_size: BigDecimal,
+    lot_size: i32,
+}
+
+impl From<Instrument> for SearchResultResponse {
+    fn from(instrument: Instrument) -> Self {
+        SearchResultResponse {
+            ticker: instrument.ticker,
+            name: instrument.name,
+            sector: instrument.sector,
+            asset_class: instrument.asset_class,
+            tick_size: instrument.tick_size,
+            lot_size: instrument.lot_size,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct CandleResponse {
+    time: DateTime<Utc>,
+    open: BigDecimal,
+    high: BigDecimal,
+    low: BigDecimal,
+    close: BigDecimal,
+    volume: i64,
+    ticks: i32,
+}
+
+impl From<PriceCandle> for CandleResponse {
+    fn from(candle: PriceCandle) -> Self {
+        CandleResponse {
+            time: candle.bucket_start,
+            open: candle.open,
+            high: candle.high,
+            low: candle.low,
+            close: candle.close,
+            volume: candle.volume,
+            ticks: candle.tick_count,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct BondResponse {
+    ticker: String,
+    /// Amount repaid per bond at maturity
+    face_value: BigDecimal,
+    /// Annual coupon in percent of the face value
+    coupon_rate_percent: BigDecimal,
+    coupons_per_year: i32,
+    maturity_date: NaiveDate,
+    /// Absent once the bond has been redeemed
+    next_coupon_date: Option<NaiveDate>,
+    /// Coupon paid per bond on each coupon date
+    coupon: BigDecimal,
+    /// Interest accrued per bond since the last coupon date
+    accrued_interest: BigDecimal,
+    redeemed_at: Option<DateTime<Utc>>,
+}
+
+impl From<Bond> for BondResponse {
+    fn from(bond: Bond) -> Self {
+        let outstanding = bond.redeemed_at.is_none();
+        BondResponse {
+            coupon: bonds::coupon_per_bond(&bond),
+            accrued_interest: if outstanding {
+                bonds::accrued_interest(&bond, Utc::now().date_naive())
+            } else {
+                BigDecimal::from(0)
+            },
+            next_coupon_date: outstanding.then_some(bond.next_coupon_date),
+            ticker: bond.ticker,
+            face_value: bond.face_value,
+            coupon_rate_percent: bond.coupon_rate_percent,
+            coupons_per_year: bond.coupons_per_year,
+            maturity_date: bond.maturity_date,
+            redeemed_at: bond.redeemed_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct BasketResponse {
+    ticker: String,
+    /// Price derived from the constituents, absent until all have a price
+    price: Option<BigDecimal>,
+    constituents: Vec<BasketConstituentResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct BasketConstituentResponse {
+    ticker: String,
+    /// Units of the constituent in one unit of the basket
+    weight: BigDecimal,
+}
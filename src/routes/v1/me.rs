@@ -0,0 +1,523 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    Extension, Router,
+    extract::{DefaultBodyLimit, Path},
+    routing::{delete, get},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{OpenApi, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::{api_key, jwt::Claims},
+    models::{
+        api_key::ApiKey,
+        user::User,
+        user_settings::{CostBasisMethod, UserSettings},
+    },
+    repository::{
+        api_key_repository::ApiKeyRepository, user_settings_repository::UserSettingsRepository,
+    },
+    services::{feature_flags, leaderboard, user_cache},
+    timing::Json,
+};
+
+/// Base currencies amounts can be reported in
+///
+/// Prices and balances are kept in US dollars; other currencies need
+/// exchange rates the server does not have yet.
+const SUPPORTED_CURRENCIES: &[&str] = &["USD"];
+/// Largest accepted `ui_settings` document, in bytes of JSON
+const MAX_UI_SETTINGS_SIZE: usize = 16 * 1024;
+/// Largest accepted profile update, leaving room around `ui_settings`
+const MAX_REQUEST_SIZE: usize = 2 * MAX_UI_SETTINGS_SIZE;
+/// Minutes a trade is held back from followers unless the user chose otherwise
+const DEFAULT_TRADE_DELAY_MINUTES: i32 = 15;
+/// Longest trade delay, a week
+const MAX_TRADE_DELAY_MINUTES: i32 = 7 * 24 * 60;
+/// Most API keys a user may hold
+const MAX_API_KEYS: usize = 10;
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_profile,
+    update_profile,
+    get_features,
+    list_api_keys,
+    create_api_key,
+    delete_api_key
+))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/", get(get_profile).patch(update_profile))
+        .route("/features", get(get_features))
+        .route("/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/api-keys/{id}", delete(delete_api_key))
+        .layer(DefaultBodyLimit::max(MAX_REQUEST_SIZE))
+}
+
+/// Get the authenticated user's profile
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "me",
+    responses(
+        (status = 200, description = "Profile of the user", body = ProfileResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_profile(claims: Claims, db: Extension<AppState>) -> Result<Json<ProfileResponse>> {
+    let settings_repository = UserSettingsRepository::new(&db.pg_pool);
+
+    let user = user_cache::get_user(&db, claims.user_id).await?;
+    let user = user.ok_or(Error::Unauthorized)?;
+
+    let settings = settings_repository.get_settings(user.id).await?;
+    let cost_basis_method = settings_repository.get_cost_basis_method(user.id).await?;
+
+    Ok(Json(ProfileResponse::new(
+        user,
+        settings,
+        cost_basis_method,
+    )))
+}
+
+/// List the features and whether each is on for the authenticated user
+///
+/// Lets clients hide what the user cannot use; the server checks the flags
+/// again on use.
+#[utoipa::path(
+    get,
+    path = "/features",
+    tag = "me",
+    responses(
+        (status = 200, description = "Feature names and whether each is on", body = BTreeMap<String, bool>),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_features(
+    claims: Claims,
+    state: Extension<AppState>,
+) -> Result<Json<BTreeMap<String, bool>>> {
+    let features = feature_flags::enabled_for(&state, claims.user_id).await?;
+
+    Ok(Json(features))
+}
+
+/// List the authenticated user's API keys
+#[utoipa::path(
+    get,
+    path = "/api-keys",
+    tag = "me",
+    responses(
+        (status = 200, description = "API keys of the user, without the keys themselves", body = Vec<ApiKeyResponse>),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn list_api_keys(
+    claims: Claims,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<ApiKeyResponse>>> {
+    let keys = ApiKeyRepository::new(&state.pg_pool)
+        .get_api_keys_by_user(claims.user_id)
+        .await?;
+
+    Ok(Json(keys.into_iter().map(Into::into).collect()))
+}
+
+/// Create an API key for programs trading on the user's behalf
+///
+/// Keys authenticate calls to the gRPC trading API with the full rights of
+/// the user until deleted. The key is only returned here; store it safely.
+#[utoipa::path(
+    post,
+    path = "/api-keys",
+    tag = "me",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 200, description = "Created key, shown only this once", body = CreatedApiKeyResponse),
+        (status = 400, description = "Validation error or too many keys", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn create_api_key(
+    claims: Claims,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<Json<CreatedApiKeyResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let repository = ApiKeyRepository::new(&state.pg_pool);
+    if repository.get_api_keys_by_user(claims.user_id).await?.len() >= MAX_API_KEYS {
+        return Err(Error::BadRequest(format!(
+            "At most {} API keys per user",
+            MAX_API_KEYS
+        )));
+    }
+
+    let key = api_key::generate();
+    let created = repository
+        .create_api_key(claims.user_id, payload.name.trim(), &api_key::hash(&key))
+        .await?;
+    tracing::info!("User ID {} created API key {}", claims.user_id, created.id);
+
+    Ok(Json(CreatedApiKeyResponse {
+        key,
+        api_key: created.into(),
+    }))
+}
+
+/// Delete an API key, rejecting its further use at once
+#[utoipa::path(
+    delete,
+    path = "/api-keys/{id}",
+    tag = "me",
+    params(("id" = i32, Path, description = "API key ID")),
+    responses(
+        (status = 200, description = "API key deleted", body = String),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "No such key of the user", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn delete_api_key(
+    claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<&'static str>> {
+    if !ApiKeyRepository::new(&state.pg_pool)
+        .delete_api_key(id, claims.user_id)
+        .await?
+    {
+        return Err(Error::NotFound);
+    }
+    tracing::info!("User ID {} deleted API key {}", claims.user_id, id);
+
+    Ok(Json("API key deleted"))
+}
+
+/// Update the authenticated user's profile
+///
+/// Only the fields present in the request are changed, and only the given
+/// notification kinds. An empty display name clears it. `ui_settings` is
+/// replaced as a whole and must be a JSON object. `leaderboard_opt_out`
+/// takes the user off the leaderboard, or back on, at once. `privacy`
+/// controls how followers see the trades of the user's public portfolios.
+#[utoipa::path(
+    patch,
+    path = "/",
+    tag = "me",
+    request_body = UpdateProfileRequest,
+    responses(
+        (status = 200, description = "Updated profile", body = ProfileResponse),
+        (status = 400, description = "Validation error or nothing to update", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 413, description = "Request body too large", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn update_profile(
+    claims: Claims,
+    db: Extension<AppState>,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> Result<Json<ProfileResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let settings_repository = UserSettingsRepository::new(&db.pg_pool);
+
+    let user = user_cache::get_user(&db, claims.user_id).await?;
+    let user = user.ok_or(Error::Unauthorized)?;
+
+    if payload.display_name.is_none()
+        && payload.base_currency.is_none()
+        && payload.cost_basis_method.is_none()
+        && payload.notifications.is_none()
+        && payload.ui_settings.is_none()
+        && payload.leaderboard_opt_out.is_none()
+        && payload.privacy.is_none()
+    {
+        return Err(Error::BadRequest("No profile fields to update".into()));
+    }
+
+    if let Some(currency) = &payload.base_currency {
+        if !SUPPORTED_CURRENCIES.contains(&currency.as_str()) {
+            return Err(Error::BadRequest(format!(
+                "Unsupported base currency: {}",
+                currency
+            )));
+        }
+    }
+    if let Some(ui_settings) = &payload.ui_settings {
+        if !ui_settings.is_object() {
+            return Err(Error::BadRequest("ui_settings must be an object".into()));
+        }
+        if ui_settings.to_string().len() > MAX_UI_SETTINGS_SIZE {
+            return Err(Error::BadRequest("ui_settings is too large".into()));
+        }
+    }
+
+    if let Some(display_name) = &payload.display_name {
+        let display_name = display_name.trim();
+        settings_repository
+            .set_display_name(user.id, Some(display_name).filter(|n| !n.is_empty()))
+            .await?;
+    }
+
+    if let Some(currency) = &payload.base_currency {
+        settings_repository
+            .set_base_currency(user.id, currency)
+            .await?;
+    }
+
+    if let Some(cost_basis_method) = payload.cost_basis_method {
+        settings_repository
+            .set_cost_basis_method(user.id, cost_basis_method)
+            .await?;
+    }
+
+    if let Some(notifications) = &payload.notifications {
+        let current = settings_repository
+            .get_settings(user.id)
+            .await?
+            .map(|s| Notifications::from(&s))
+            .unwrap_or_default();
+        settings_repository
+            .set_notifications(
+                user.id,
+                notifications.order_fills.unwrap_or(current.order_fills),
+                notifications.margin_calls.unwrap_or(current.margin_calls),
+                notifications.deposits.unwrap_or(current.deposits),
+            )
+            .await?;
+    }
+
+    if let Some(ui_settings) = &payload.ui_settings {
+        settings_repository
+            .set_ui_settings(user.id, ui_settings)
+            .await?;
+    }
+
+    if let Some(opt_out) = payload.leaderboard_opt_out {
+        settings_repository
+            .set_leaderboard_opt_out(user.id, opt_out)
+            .await?;
+        // Otherwise the rankings would only follow at the next daily rebuild
+        let result = if opt_out {
+            leaderboard::remove_user(&db, user.id).await
+        } else {
+            leaderboard::refresh_user(&db, user.id, Utc::now().date_naive()).await
+        };
+        if let Err(e) = result {
+            tracing::warn!(
+                "Failed to update the leaderboard for user {}: {}",
+                user.id,
+                e
+            );
+        }
+    }
+
+    if let Some(privacy) = &payload.privacy {
+        let current = settings_repository
+            .get_settings(user.id)
+            .await?
+            .map(|s| Privacy::from(&s))
+            .unwrap_or_default();
+        settings_repository
+            .set_trade_privacy(
+                user.id,
+                privacy
+                    .trade_delay_minutes
+                    .unwrap_or(current.trade_delay_minutes),
+                privacy.hide_quantities.unwrap_or(current.hide_quantities),
+            )
+            .await?;
+    }
+
+    let settings = settings_repository.get_settings(user.id).await?;
+    let cost_basis_method = settings_repository.get_cost_basis_method(user.id).await?;
+
+    Ok(Json(ProfileResponse::new(
+        user,
+        settings,
+        cost_basis_method,
+    )))
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct UpdateProfileRequest {
+    #[validate(length(max = 50))]
+    display_name: Option<String>,
+    #[validate(length(equal = 3))]
+    base_currency: Option<String>,
+    cost_basis_method: Option<CostBasisMethod>,
+    notifications: Option<UpdateNotificationsRequest>,
+    ui_settings: Option<serde_json::Value>,
+    /// Leave the leaderboard, or join it again with `false`
+    leaderboard_opt_out: Option<bool>,
+    #[validate(nested)]
+    privacy: Option<UpdatePrivacyRequest>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct UpdateNotificationsRequest {
+    order_fills: Option<bool>,
+    margin_calls: Option<bool>,
+    deposits: Option<bool>,
+}
+
+/// Account events pushed to the user's WebSocket connections
+#[derive(Debug, Serialize, ToSchema)]
+struct Notifications {
+    order_fills: bool,
+    margin_calls: bool,
+    deposits: bool,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Notifications {
+            order_fills: true,
+            margin_calls: true,
+            deposits: true,
+        }
+    }
+}
+
+impl From<&UserSettings> for Notifications {
+    fn from(settings: &UserSettings) -> Self {
+        Notifications {
+            order_fills: settings.notify_order_fills,
+            margin_calls: settings.notify_margin_calls,
+            deposits: settings.notify_deposits,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct UpdatePrivacyRequest {
+    #[validate(range(min = 0, max = "MAX_TRADE_DELAY_MINUTES"))]
+    trade_delay_minutes: Option<i32>,
+    hide_quantities: Option<bool>,
+}
+
+/// How followers see the trades of the user's public portfolios
+#[derive(Debug, Serialize, ToSchema)]
+struct Privacy {
+    /// Minutes before a trade shows up in followers' feeds
+    trade_delay_minutes: i32,
+    /// Whether followers see trades and positions without quantities
+    hide_quantities: bool,
+}
+
+impl Default for Privacy {
+    fn default() -> Self {
+        Privacy {
+            trade_delay_minutes: DEFAULT_TRADE_DELAY_MINUTES,
+            hide_quantities: false,
+        }
+    }
+}
+
+impl From<&UserSettings> for Privacy {
+    fn from(settings: &UserSettings) -> Self {
+        Privacy {
+            trade_delay_minutes: settings.trade_delay_minutes,
+            hide_quantities: settings.hide_trade_quantities,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreateApiKeyRequest {
+    /// What the key is for, e.g. the program using it
+    #[validate(length(min = 1, max = 100))]
+    name: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ApiKeyResponse {
+    id: i32,
+    name: String,
+    created_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(api_key: ApiKey) -> Self {
+        ApiKeyResponse {
+            id: api_key.id,
+            name: api_key.name,
+            created_at: api_key.created_at,
+            last_used_at: api_key.last_used_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct CreatedApiKeyResponse {
+    /// The key to send as `x-api-key`; it cannot be retrieved again
+    key: String,
+    #[serde(flatten)]
+    api_key: ApiKeyResponse,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ProfileResponse {
+    id: Uuid,
+    email: String,
+    role: String,
+    display_name: Option<String>,
+    base_currency: String,
+    cost_basis_method: CostBasisMethod,
+    notifications: Notifications,
+    ui_settings: serde_json::Value,
+    leaderboard_opt_out: bool,
+    privacy: Privacy,
+    /// When the account was registered
+    created_at: DateTime<Utc>,
+    /// Last change of the account or its settings
+    updated_at: DateTime<Utc>,
+}
+
+impl ProfileResponse {
+    /// Profile of `user`, with the defaults for settings never saved
+    fn new(user: User, settings: Option<UserSettings>, cost_basis_method: CostBasisMethod) -> Self {
+        let notifications = settings
+            .as_ref()
+            .map(Notifications::from)
+            .unwrap_or_default();
+        ProfileResponse {
+            id: user.public_id,
+            email: user.email,
+            role: user.role,
+            display_name: settings.as_ref().and_then(|s| s.display_name.clone()),
+            base_currency: settings.as_ref().map_or_else(
+                || SUPPORTED_CURRENCIES[0].to_string(),
+                |s| s.base_currency.clone(),
+            ),
+            cost_basis_method,
+            notifications,
+            ui_settings: settings
+                .as_ref()
+                .map_or_else(|| serde_json::json!({}), |s| s.ui_settings.clone()),
+            leaderboard_opt_out: settings.as_ref().is_some_and(|s| s.leaderboard_opt_out),
+            privacy: settings.as_ref().map(Privacy::from).unwrap_or_default(),
+            created_at: user.created_at,
+            updated_at: settings.map_or(user.updated_at, |s| s.updated_at.max(user.updated_at)),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! # API Version 1
+//!
+//! Route modules of `/api/v1`, each with its handlers and the request and
+//! response types they exchange.
+
+use axum::Router;
+use utoipa::OpenApi;
+
+mod admin;
+mod auth;
+mod balance;
+mod classes;
+mod competitions;
+mod feed;
+mod holdings;
+mod leaderboard;
+mod loans;
+mod market;
+mod me;
+mod options;
+mod portfolio;
+mod portfolios;
+mod reports;
+mod settings;
+mod transactions;
+mod users;
+mod webhooks;
+
+/// Every route but the admin API
+pub fn routes() -> Router {
+    Router::new()
+        .nest("/auth", auth::routes())
+        .nest("/balance", balance::routes())
+        .nest("/classes", classes::routes())
+        .nest("/competitions", competitions::routes())
+        .nest("/feed", feed::routes())
+        .nest("/transactions", transactions::routes())
+        .nest("/holdings", holdings::routes())
+        .nest("/leaderboard", leaderboard::routes())
+        .nest("/loans", loans::routes())
+        .nest("/market", market::routes())
+        .nest("/me", me::routes())
+        .nest("/options", options::routes())
+        .nest("/portfolio", portfolio::routes())
+        .nest("/portfolios", portfolios::routes())
+        .nest("/reports", reports::routes())
+        .nest("/settings", settings::routes())
+        .nest("/users", users::routes())
+        .nest("/webhooks", webhooks::routes())
+}
+
+/// The admin API
+pub fn admin_routes() -> Router {
+    Router::new().nest("/admin", admin::routes())
+}
+
+/// OpenAPI description of [`routes`] and [`admin_routes`], nested under the
+/// same prefixes
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    [
+        ("/admin", admin::ApiDoc::openapi()),
+        ("/auth", auth::ApiDoc::openapi()),
+        ("/balance", balance::ApiDoc::openapi()),
+        ("/classes", classes::ApiDoc::openapi()),
+        ("/competitions", competitions::ApiDoc::openapi()),
+        ("/feed", feed::ApiDoc::openapi()),
+        ("/transactions", transactions::ApiDoc::openapi()),
+        ("/holdings", holdings::ApiDoc::openapi()),
+        ("/leaderboard", leaderboard::ApiDoc::openapi()),
+        ("/loans", loans::ApiDoc::openapi()),
+        ("/market", market::ApiDoc::openapi()),
+        ("/me", me::ApiDoc::openapi()),
+        ("/options", options::ApiDoc::openapi()),
+        ("/portfolio", portfolio::ApiDoc::openapi()),
+        ("/portfolios", portfolios::ApiDoc::openapi()),
+        ("/reports", reports::ApiDoc::openapi()),
+        ("/settings", settings::ApiDoc::openapi()),
+        ("/users", users::ApiDoc::openapi()),
+        ("/webhooks", webhooks::ApiDoc::openapi()),
+    ]
+    .into_iter()
+    .fold(
+        utoipa::openapi::OpenApiBuilder::new().build(),
+        |spec, (prefix, module)| {
+            // A module's `/` is the prefix itself, as in the router
+            spec.nest_with_path_composer(prefix, module, |prefix, path| match path {
+                "/" => prefix.to_string(),
+                _ => format!("{}{}", prefix, path),
+            })
+        },
+    )
+}
@@ -0,0 +1,291 @@
+use axum::{
+    Extension, Router,
+    extract::Path,
+    routing::{get, post},
+};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{OpenApi, ToSchema};
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::portfolio::SelectedPortfolio,
+    models::option::OptionTrade,
+    repository::option_repository::OptionRepository,
+    services::{
+        options::{self, OptionPositionValuation},
+        price_cache::PriceCache,
+    },
+    timing::Json,
+};
+
+#[derive(OpenApi)]
+#[openapi(paths(get_chain, get_positions, buy_option, sell_option))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/chain/{ticker}", get(get_chain))
+        .route("/positions", get(get_positions))
+        .route("/buy", post(buy_option))
+        .route("/sell", post(sell_option))
+}
+
+/// Get the option chain of an underlying
+///
+/// Lists the unexpired contracts on the ticker by expiry and strike, with
+/// their Black-Scholes price per share at the underlying's latest price.
+/// Prices are absent while the underlying has no price.
+#[utoipa::path(
+    get,
+    path = "/chain/{ticker}",
+    tag = "options",
+    params(("ticker" = String, Path, description = "Symbol of the underlying")),
+    responses(
+        (status = 200, description = "Contracts on the underlying", body = OptionChainResponse),
+    )
+)]
+async fn get_chain(
+    Path(ticker): Path<String>,
+    state: Extension<AppState>,
+) -> Result<Json<OptionChainResponse>> {
+    let ticker = ticker.trim().to_uppercase();
+    let contracts = OptionRepository::new(&state.pg_pool)
+        .get_chain(&ticker)
+        .await?;
+    let underlying_price = PriceCache::new(&state.redis_pool)
+        .prices(std::slice::from_ref(&ticker))
+        .await?
+        .remove(&ticker);
+
+    let now = Utc::now();
+    let contracts = contracts
+        .into_iter()
+        .map(|contract| {
+            let quote = underlying_price.as_ref().map(|spot| {
+                options::quote(
+                    &state.config,
+                    &contract.kind,
+                    &contract.strike,
+                    contract.expires_at,
+                    spot,
+                    now,
+                )
+            });
+            OptionContractResponse {
+                id: contract.id,
+                kind: contract.kind,
+                strike: contract.strike,
+                expires_at: contract.expires_at,
+                price: quote.as_ref().map(|quote| quote.price.clone()),
+                delta: quote.map(|quote| quote.delta),
+            }
+        })
+        .collect();
+
+    Ok(Json(OptionChainResponse {
+        underlying: ticker,
+        underlying_price,
+        multiplier: options::MULTIPLIER,
+        contracts,
+    }))
+}
+
+/// List the option positions of the selected portfolio
+///
+/// Positions are valued at the model price of their contracts; short
+/// positions have a negative quantity and value, and report the margin they
+/// hold.
+#[utoipa::path(
+    get,
+    path = "/positions",
+    tag = "options",
+    params(SelectedPortfolio),
+    responses(
+        (status = 200, description = "Option positions of the portfolio", body = Vec<OptionPositionResponse>),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_positions(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<OptionPositionResponse>>> {
+    let positions = OptionRepository::new(&state.pg_pool)
+        .get_positions_by_portfolio(portfolio.id)
+        .await?;
+    let valuations = options::value_positions(&state, positions).await?;
+
+    Ok(Json(valuations.into_iter().map(Into::into).collect()))
+}
+
+/// Buy option contracts into the selected portfolio
+///
+/// Fills at the contract's model price plus the commission of the
+/// underlying. Buying contracts the portfolio is short closes the short
+/// position, releasing its margin.
+#[utoipa::path(
+    post,
+    path = "/buy",
+    tag = "options",
+    params(SelectedPortfolio),
+    request_body = OptionOrderRequest,
+    responses(
+        (status = 200, description = "Executed option trade", body = OptionTradeResponse),
+        (status = 400, description = "Validation error, unknown or expired contract, no price, insufficient balance, or a competition or class portfolio", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn buy_option(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    state: Extension<AppState>,
+    Json(payload): Json<OptionOrderRequest>,
+) -> Result<Json<OptionTradeResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let trade = options::buy(&state, &portfolio, payload.contract_id, payload.quantity).await?;
+
+    Ok(Json(trade.into()))
+}
+
+/// Sell option contracts out of the selected portfolio
+///
+/// Fills at the contract's model price less the commission of the
+/// underlying. Selling contracts the portfolio holds closes the long
+/// position; otherwise the contracts are written, which requires the
+/// `margin_trading` feature and holds their margin requirement out of cash.
+#[utoipa::path(
+    post,
+    path = "/sell",
+    tag = "options",
+    params(SelectedPortfolio),
+    request_body = OptionOrderRequest,
+    responses(
+        (status = 200, description = "Executed option trade", body = OptionTradeResponse),
+        (status = 400, description = "Validation error, unknown or expired contract, no price, insufficient balance for the margin, or a competition or class portfolio", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 403, description = "Writing contracts without the margin trading feature", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn sell_option(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    state: Extension<AppState>,
+    Json(payload): Json<OptionOrderRequest>,
+) -> Result<Json<OptionTradeResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let trade = options::sell(&state, &portfolio, payload.contract_id, payload.quantity).await?;
+
+    Ok(Json(trade.into()))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct OptionChainResponse {
+    underlying: String,
+    /// Latest price of the underlying, absent while it has none
+    underlying_price: Option<BigDecimal>,
+    /// Shares covered by one contract
+    multiplier: i32,
+    contracts: Vec<OptionContractResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct OptionContractResponse {
+    id: i32,
+    /// `call` or `put`
+    kind: String,
+    strike: BigDecimal,
+    expires_at: DateTime<Utc>,
+    /// Model premium per share
+    price: Option<BigDecimal>,
+    /// Change of the premium per unit change of the underlying
+    delta: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct OptionOrderRequest {
+    contract_id: i32,
+    /// Number of contracts
+    #[validate(range(min = 1, max = 1000))]
+    quantity: i32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct OptionTradeResponse {
+    id: i32,
+    contract_id: i32,
+    /// `buy` or `sell`
+    trade_type: String,
+    /// Number of contracts
+    quantity: i32,
+    /// Premium per share
+    price: BigDecimal,
+    /// Commission paid on top of the premium
+    fee: BigDecimal,
+    created_at: DateTime<Utc>,
+}
+
+impl From<OptionTrade> for OptionTradeResponse {
+    fn from(trade: OptionTrade) -> Self {
+        OptionTradeResponse {
+            id: trade.id,
+            contract_id: trade.contract_id,
+            trade_type: trade.trade_type,
+            quantity: trade.quantity,
+            price: trade.price,
+            fee: trade.fee,
+            created_at: trade.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct OptionPositionResponse {
+    contract_id: i32,
+    underlying: String,
+    /// `call` or `put`
+    kind: String,
+    strike: BigDecimal,
+    expires_at: DateTime<Utc>,
+    /// Number of contracts, negative for short positions
+    quantity: i32,
+    /// Average premium per share paid or received
+    average_premium: BigDecimal,
+    /// Model premium per share, absent while the underlying has no price
+    current_price: Option<BigDecimal>,
+    /// Value of the contracts, negative for short positions
+    market_value: BigDecimal,
+    unrealized_pnl: BigDecimal,
+    /// Cash held against a short position
+    margin: BigDecimal,
+}
+
+impl From<OptionPositionValuation> for OptionPositionResponse {
+    fn from(valuation: OptionPositionValuation) -> Self {
+        let position = valuation.position;
+        OptionPositionResponse {
+            contract_id: position.contract_id,
+            underlying: position.underlying,
+            kind: position.kind,
+            strike: position.strike,
+            expires_at: position.expires_at,
+            quantity: position.quantity,
+            average_premium: position.average_premium,
+            current_price: valuation.mark,
+            market_value: valuation.market_value,
+            unrealized_pnl: valuation.unrealized_pnl,
+            margin: position.margin,
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+
+use axum::{Extension, Router, extract::Query, routing::get};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::{jwt::Claims, portfolio::SelectedPortfolio},
+    models::portfolio_snapshot::PortfolioSnapshot,
+    repository::{
+        portfolio_snapshot_repository::PortfolioSnapshotRepository,
+        user_settings_repository::UserSettingsRepository,
+    },
+    services::{
+        metrics::{self, BenchmarkComparison, BenchmarkPoint, PerformanceMetrics},
+        portfolio::{self, PortfolioValuation, PositionValuation},
+    },
+    timing::Json,
+};
+
+/// Range covered by `/portfolio/history` and `/portfolio/metrics` when `from` is omitted
+const DEFAULT_HISTORY_DAYS: u64 = 365;
+
+#[derive(OpenApi)]
+#[openapi(paths(get_portfolio, get_portfolio_history, get_portfolio_metrics))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/", get(get_portfolio))
+        .route("/history", get(get_portfolio_history))
+        .route("/metrics", get(get_portfolio_metrics))
+}
+
+/// Get the valuation of the selected portfolio
+///
+/// Joins holdings with the latest cached prices and returns cash, market
+/// value, unrealized P&L per position and overall portfolio equity.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "portfolio",
+    params(SelectedPortfolio),
+    responses(
+        (status = 200, description = "Valuation of the portfolio", body = PortfolioResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_portfolio(
+    SelectedPortfolio(selected): SelectedPortfolio,
+    state: Extension<AppState>,
+) -> Result<Json<PortfolioResponse>> {
+    let valuation = portfolio::value_portfolio(&state, &selected).await?;
+
+    Ok(Json(valuation.into()))
+}
+
+/// Get the daily equity history of the authenticated user's whole account
+///
+/// Returns one snapshot per day between `from` and `to` (inclusive, `YYYY-MM-DD`).
+/// `to` defaults to today and `from` to a year before `to`. With a benchmark,
+/// days with a benchmark price also carry it and the cumulative returns of
+/// the account and the benchmark since the first such day.
+#[utoipa::path(
+    get,
+    path = "/history",
+    tag = "portfolio",
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "Daily snapshots of the account", body = Vec<SnapshotResponse>),
+        (status = 400, description = "Invalid range", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_portfolio_history(
+    claims: Claims,
+    state: Extension<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<SnapshotResponse>>> {
+    let (from, to) = query.range()?;
+
+    let snapshots = PortfolioSnapshotRepository::new(&state.pg_read_pool)
+        .get_snapshots(claims.user_id, from, to)
+        .await?;
+    let mut benchmark = match query.benchmark(&state, claims.user_id).await? {
+        Some(ticker) => {
+            metrics::benchmark_history(&state, claims.user_id, &snapshots, &ticker).await?
+        }
+        None => HashMap::new(),
+    };
+
+    Ok(Json(
+        snapshots
+            .into_iter()
+            .map(|snapshot| {
+                let point = benchmark.remove(&snapshot.snapshot_date);
+                SnapshotResponse::new(snapshot, point)
+            })
+            .collect(),
+    ))
+}
+
+/// Get performance statistics of the authenticated user's account
+///
+/// Computes time-weighted return, annualized volatility, Sharpe ratio and
+/// maximum drawdown from the daily snapshots in the range, which defaults like
+/// `/portfolio/history`. Returns and risk figures are fractions (0.05 = 5%).
+/// With a benchmark, adds its return, tracking difference and error, beta
+/// and alpha over the days it has prices for.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "portfolio",
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "Performance statistics of the account", body = MetricsResponse),
+        (status = 400, description = "Invalid range", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_portfolio_metrics(
+    claims: Claims,
+    state: Extension<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<MetricsResponse>> {
+    let (from, to) = query.range()?;
+    let benchmark = query.benchmark(&state, claims.user_id).await?;
+
+    let metrics = metrics::performance(&state, claims.user_id, from, to, benchmark.as_deref())
+        .await?
+        .ok_or_else(|| {
+            Error::BadRequest("At least two daily snapshots are required in the range".into())
+        })?;
+
+    Ok(Json(metrics.into()))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct HistoryQuery {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    /// Overrides the benchmark from the settings; empty for none
+    benchmark: Option<String>,
+}
+
+impl HistoryQuery {
+    /// Requested range with defaults applied
+    fn range(&self) -> Result<(NaiveDate, NaiveDate)> {
+        let to = self.to.unwrap_or_else(|| Utc::now().date_naive());
+        let from = self.from.unwrap_or_else(|| {
+            to.checked_sub_days(Days::new(DEFAULT_HISTORY_DAYS))
+                .unwrap_or(NaiveDate::MIN)
+        });
+
+        if from > to {
+            return Err(Error::BadRequest("`from` must not be after `to`".into()));
+        }
+
+        Ok((from, to))
+    }
+
+    /// Requested benchmark, falling back to the user's setting
+    async fn benchmark(&self, state: &AppState, user_id: i32) -> Result<Option<String>> {
+        let ticker = match &self.benchmark {
+            Some(ticker) => Some(ticker.clone()),
+            None => UserSettingsRepository::new(&state.pg_pool)
+                .get_settings(user_id)
+                .await?
+                .and_then(|settings| settings.benchmark_ticker),
+        };
+
+        Ok(ticker.filter(|ticker| !ticker.is_empty()))
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct MetricsResponse {
+    from: NaiveDate,
+    to: NaiveDate,
+    periods: usize,
+    time_weighted_return: f64,
+    annualized_return: f64,
+    annualized_volatility: Option<f64>,
+    sharpe_ratio: Option<f64>,
+    max_drawdown: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    benchmark: Option<BenchmarkResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct BenchmarkResponse {
+    ticker: String,
+    from: NaiveDate,
+    to: NaiveDate,
+    periods: usize,
+    portfolio_return: f64,
+    benchmark_return: f64,
+    tracking_difference: f64,
+    tracking_error: Option<f64>,
+    beta: Option<f64>,
+    alpha: Option<f64>,
+}
+
+impl From<BenchmarkComparison> for BenchmarkResponse {
+    fn from(comparison: BenchmarkComparison) -> Self {
+        BenchmarkResponse {
+            ticker: comparison.ticker,
+            from: comparison.from,
+            to: comparison.to,
+            periods: comparison.periods,
+            portfolio_return: comparison.portfolio_return,
+            benchmark_return: comparison.benchmark_return,
+            tracking_difference: comparison.tracking_difference,
+            tracking_error: comparison.tracking_error,
+            beta: comparison.beta,
+            alpha: comparison.alpha,
+        }
+    }
+}
+
+impl From<PerformanceMetrics> for MetricsResponse {
+    fn from(metrics: PerformanceMetrics) -> Self {
+        MetricsResponse {
+            from: metrics.from,
+            to: metrics.to,
+            periods: metrics.periods,
+            time_weighted_return: metrics.time_weighted_return,
+            annualized_return: metrics.annualized_return,
+            annualized_volatility: metrics.annualized_volatility,
+            sharpe_ratio: metrics.sharpe_ratio,
+            max_drawdown: metrics.max_drawdown,
+            benchmark: metrics.benchmark.map(Into::into),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct SnapshotResponse {
+    date: NaiveDate,
+    cash: BigDecimal,
+    market_value: BigDecimal,
+    equity: BigDecimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    benchmark_price: Option<BigDecimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cumulative_return: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    benchmark_cumulative_return: Option<f64>,
+}
+
+impl SnapshotResponse {
+    fn new(snapshot: PortfolioSnapshot, benchmark: Option<BenchmarkPoint>) -> Self {
+        SnapshotResponse {
+            date: snapshot.snapshot_date,
+            cash: snapshot.cash,
+            market_value: snapshot.market_value,
+            equity: snapshot.equity,
+            cumulative_return: benchmark.as_ref().map(|b| b.cumulative_return),
+            benchmark_cumulative_return: benchmark.as_ref().map(|b| b.benchmark_cumulative_return),
+            benchmark_price: benchmark.map(|b| b.price),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct PortfolioResponse {
+    cash: BigDecimal,
+    money_market: BigDecimal,
+    loans: BigDecimal,
+    /// Model value of option positions plus the margin they hold
+    options: BigDecimal,
+    market_value: BigDecimal,
+    cost_basis: BigDecimal,
+    unrealized_pnl: BigDecimal,
+    unrealized_pnl_percent: BigDecimal,
+    equity: BigDecimal,
+    positions: Vec<PositionResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct PositionResponse {
+    ticker: String,
+    quantity: i32,
+    average_price: BigDecimal,
+    current_price: Option<BigDecimal>,
+    /// Interest accrued on a bond since its last coupon, included in the market value
+    accrued_interest: BigDecimal,
+    cost_basis: BigDecimal,
+    market_value: BigDecimal,
+    unrealized_pnl: BigDecimal,
+    unrealized_pnl_percent: BigDecimal,
+    /// When the position was opened
+    created_at: DateTime<Utc>,
+    /// Last trade or corporate action that changed the position
+    updated_at: DateTime<Utc>,
+}
+
+impl From<PortfolioValuation> for PortfolioResponse {
+    fn from(valuation: PortfolioValuation) -> Self {
+        PortfolioResponse {
+            unrealized_pnl_percent: portfolio::percent_of(
+                &valuation.unrealized_pnl,
+                &valuation.cost_basis,
+            ),
+            cash: valuation.cash,
+            money_market: valuation.money_market,
+            loans: valuation.loans,
+            options: valuation.options,
+            market_value: valuation.market_value,
+            cost_basis: valuation.cost_basis,
+            unrealized_pnl: valuation.unrealized_pnl,
+            equity: valuation.equity,
+            positions: valuation.positions.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<PositionValuation> for PositionResponse {
+    fn from(position: PositionValuation) -> Self {
+        PositionResponse {
+            ticker: position.ticker,
+            quantity: position.quantity,
+            average_price: position.average_price,
+            current_price: position.current_price,
+            accrued_interest: position.accrued_interest,
+            cost_basis: position.cost_basis,
+            market_value: position.market_value,
+            unrealized_pnl: position.unrealized_pnl,
+            unrealized_pnl_percent: position.unrealized_pnl_percent,
+            created_at: position.created_at,
+            updated_at: position.updated_at,
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use axum::{
+    Extension, Router,
+    extract::Path,
+    routing::{get, patch, post},
+};
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{OpenApi, ToSchema};
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::jwt::Claims,
+    models::portfolio::Portfolio,
+    repository::{ledger_repository::LedgerRepository, portfolio_repository::PortfolioRepository},
+    services::{classes, competitions, sweep},
+    timing::Json,
+};
+
+#[derive(OpenApi)]
+#[openapi(paths(get_portfolios, create_portfolio, update_portfolio, transfer))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/", get(get_portfolios).post(create_portfolio))
+        .route("/{id}", patch(update_portfolio))
+        .route("/transfer", post(transfer))
+}
+
+/// List the authenticated user's portfolios, default first
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "portfolios",
+    responses(
+        (status = 200, description = "Portfolios of the user", body = Vec<PortfolioResponse>),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_portfolios(
+    claims: Claims,
+    db: Extension<AppState>,
+) -> Result<Json<Vec<PortfolioResponse>>> {
+    let portfolios = PortfolioRepository::new(&db.pg_pool)
+        .get_portfolios_by_user(claims.user_id)
+        .await?;
+
+    Ok(Json(portfolios.into_iter().map(Into::into).collect()))
+}
+
+/// Create an empty portfolio
+///
+/// Fund it with a deposit selecting it through `X-Portfolio-Id` or with a
+/// transfer from another portfolio.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "portfolios",
+    request_body = CreatePortfolioRequest,
+    responses(
+        (status = 200, description = "Portfolio created", body = PortfolioResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 409, description = "Portfolio name already exists", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn create_portfolio(
+    claims: Claims,
+    db: Extension<AppState>,
+    Json(payload): Json<CreatePortfolioRequest>,
+) -> Result<Json<PortfolioResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(Error::BadRequest("Portfolio name must not be blank".into()));
+    }
+
+    let repository = PortfolioRepository::new(&db.pg_pool);
+    let portfolios = repository.get_portfolios_by_user(claims.user_id).await?;
+    if portfolios.iter().any(|p| p.name.eq_ignore_ascii_case(name)) {
+        return Err(Error::Conflict("Portfolio name already exists".into()));
+    }
+
+    let portfolio = repository
+        .create_portfolio(claims.user_id, name, BigDecimal::from(0), false)
+        .await?;
+
+    Ok(Json(portfolio.into()))
+}
+
+/// Update one of the authenticated user's portfolios
+///
+/// A public portfolio is listed on the user's profile, and its trades show
+/// up in their followers' feeds after the user's trade delay.
+#[utoipa::path(
+    patch,
+    path = "/{id}",
+    tag = "portfolios",
+    params(("id" = i32, Path, description = "Portfolio ID")),
+    request_body = UpdatePortfolioRequest,
+    responses(
+        (status = 200, description = "Portfolio updated", body = PortfolioResponse),
+        (status = 400, description = "Nothing to update", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn update_portfolio(
+    claims: Claims,
+    db: Extension<AppState>,
+    Path(id): Path<i32>,
+    Json(payload): Json<UpdatePortfolioRequest>,
+) -> Result<Json<PortfolioResponse>> {
+    let Some(is_public) = payload.is_public else {
+        return Err(Error::BadRequest("Nothing to update".into()));
+    };
+
+    let repository = PortfolioRepository::new(&db.pg_pool);
+    repository
+        .get_portfolio(id)
+        .await?
+        .filter(|portfolio| portfolio.user_id == claims.user_id)
+        .ok_or(Error::NotFound)?;
+    let portfolio = repository.set_public(id, is_public).await?;
+
+    Ok(Json(portfolio.into()))
+}
+
+/// Move cash between two of the authenticated user's portfolios
+///
+/// Transfers are not deposits or withdrawals, so they do not affect the
+/// account's performance metrics. Competition and class portfolios cannot
+/// take part.
+#[utoipa::path(
+    post,
+    path = "/transfer",
+    tag = "portfolios",
+    request_body = TransferRequest,
+    responses(
+        (status = 200, description = "Cash transferred", body = String),
+        (status = 400, description = "Validation error, insufficient funds, competition or class portfolio", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn transfer(
+    claims: Claims,
+    db: Extension<AppState>,
+    Json(payload): Json<TransferRequest>,
+) -> Result<Json<&'static str>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    if payload.from_portfolio_id == payload.to_portfolio_id {
+        return Err(Error::BadRequest(
+            "Source and destination portfolios must differ".into(),
+        ));
+    }
+
+    let repository = PortfolioRepository::new(&db.pg_pool);
+    let mut owned = Vec::with_capacity(2);
+    for portfolio_id in [payload.from_portfolio_id, payload.to_portfolio_id] {
+        let portfolio = repository
+            .get_portfolio(portfolio_id)
+            .await?
+            .filter(|portfolio| portfolio.user_id == claims.user_id)
+            .ok_or(Error::NotFound)?;
+        competitions::check_cash_movement(&portfolio)?;
+        classes::check_cash_movement(&portfolio)?;
+        owned.push(portfolio);
+    }
+    let (from, to) = (&owned[0], &owned[1]);
+
+    let amount = BigDecimal::from_f64(payload.amount)
+        .ok_or_else(|| Error::BadRequest("Invalid amount format".into()))?
+        .with_scale_round(2, RoundingMode::HalfUp);
+    sweep::sweep_out(&db, from, &amount).await?;
+    if !LedgerRepository::new(&db.pg_pool)
+        .transfer(from.id, to.id, amount)
+        .await?
+    {
+        return Err(Error::BadRequest("Insufficient funds".into()));
+    }
+
+    Ok(Json("Transfer successful"))
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreatePortfolioRequest {
+    #[validate(length(min = 1, max = 50))]
+    name: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct UpdatePortfolioRequest {
+    /// Share the portfolio with followers
+    is_public: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct TransferRequest {
+    from_portfolio_id: i32,
+    to_portfolio_id: i32,
+    #[validate(range(min = 0.01, max = 1_000_000.0))]
+    amount: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(as = PortfolioInfo)]
+struct PortfolioResponse {
+    id: i32,
+    name: String,
+    cash: BigDecimal,
+    is_default: bool,
+    /// Competition the portfolio was entered in, `null` for regular portfolios
+    competition_id: Option<i32>,
+    /// Class the portfolio belongs to, `null` for regular portfolios
+    class_id: Option<i32>,
+    /// Whether followers can see the portfolio and its trades
+    is_public: bool,
+    created_at: DateTime<Utc>,
+}
+
+impl From<Portfolio> for PortfolioResponse {
+    fn from(portfolio: Portfolio) -> Self {
+        PortfolioResponse {
+            id: portfolio.id,
+            name: portfolio.name,
+            cash: portfolio.balance,
+            is_default: portfolio.is_default,
+            competition_id: portfolio.competition_id,
+            class_id: portfolio.class_id,
+            is_public: portfolio.is_public,
+            created_at: portfolio.created_at,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use axum::{Extension, Router, extract::Query, routing::get};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::jwt::Claims,
+    services::tax_report::{self, ClassifiedGain, RealizedGainsReport},
+    timing::Json,
+};
+
+#[derive(OpenApi)]
+#[openapi(paths(get_realized_gains))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new().route("/realized-gains", get(get_realized_gains))
+}
+
+/// Get the realized gains report for a calendar year
+///
+/// Lists every lot slice sold during the year across all of the user's
+/// portfolios, classified as short- or long-term, with yearly totals. The
+/// year defaults to the current one.
+#[utoipa::path(
+    get,
+    path = "/realized-gains",
+    tag = "reports",
+    params(RealizedGainsQuery),
+    responses(
+        (status = 200, description = "Realized gains of the year", body = RealizedGainsResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_realized_gains(
+    claims: Claims,
+    state: Extension<AppState>,
+    Query(query): Query<RealizedGainsQuery>,
+) -> Result<Json<RealizedGainsResponse>> {
+    query
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let year = query.year.unwrap_or_else(|| Utc::now().year());
+    let report = tax_report::realized_gains(&state, claims.user_id, year).await?;
+
+    Ok(Json(report.into()))
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct RealizedGainsQuery {
+    #[validate(range(min = 1970, max = 9999))]
+    year: Option<i32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct RealizedGainsResponse {
+    year: i32,
+    proceeds: BigDecimal,
+    cost_basis: BigDecimal,
+    short_term_gain: BigDecimal,
+    long_term_gain: BigDecimal,
+    /// Gains on shares without an acquisition date
+    unknown_term_gain: BigDecimal,
+    total_gain: BigDecimal,
+    lots: Vec<RealizedLotResponse>,
+}
+
+impl From<RealizedGainsReport> for RealizedGainsResponse {
+    fn from(report: RealizedGainsReport) -> Self {
+        RealizedGainsResponse {
+            year: report.year,
+            proceeds: report.proceeds,
+            cost_basis: report.cost_basis,
+            short_term_gain: report.short_term_gain,
+            long_term_gain: report.long_term_gain,
+            unknown_term_gain: report.unknown_term_gain,
+            total_gain: report.total_gain,
+            lots: report.lots.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct RealizedLotResponse {
+    id: i32,
+    ticker: String,
+    sell_transaction_id: Uuid,
+    lot_id: Option<i32>,
+    quantity: i32,
+    acquired_at: Option<DateTime<Utc>>,
+    realized_at: DateTime<Utc>,
+    cost_basis: BigDecimal,
+    proceeds: BigDecimal,
+    gain: BigDecimal,
+    cost_basis_method: String,
+    holding_period: &'static str,
+}
+
+impl From<ClassifiedGain> for RealizedLotResponse {
+    fn from(classified: ClassifiedGain) -> Self {
+        let gain = classified.gain;
+        RealizedLotResponse {
+            id: gain.id,
+            ticker: gain.ticker,
+            sell_transaction_id: gain.sell_transaction_id,
+            lot_id: gain.lot_id,
+            quantity: gain.quantity,
+            acquired_at: gain.acquired_at,
+            realized_at: gain.realized_at,
+            cost_basis: gain.cost_basis,
+            proceeds: gain.proceeds,
+            gain: gain.gain,
+            cost_basis_method: gain.cost_basis_method,
+            holding_period: classified.holding_period.as_str(),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use axum::{Extension, Router, routing::get};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{OpenApi, ToSchema};
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::jwt::Claims,
+    models::{difficulty::Difficulty, user_settings::CostBasisMethod},
+    repository::user_settings_repository::UserSettingsRepository,
+    services::{sweep, user_cache},
+    timing::Json,
+};
+
+#[derive(OpenApi)]
+#[openapi(paths(get_settings, update_settings))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new().route("/", get(get_settings).patch(update_settings))
+}
+
+/// Get the authenticated user's settings
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "settings",
+    responses(
+        (status = 200, description = "Settings of the user", body = SettingsResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_settings(claims: Claims, db: Extension<AppState>) -> Result<Json<SettingsResponse>> {
+    let settings_repository = UserSettingsRepository::new(&db.pg_pool);
+
+    let user = user_cache::get_user(&db, claims.user_id).await?;
+    let user = user.ok_or(crate::Error::Unauthorized)?;
+
+    let settings = settings_repository.get_settings(user.id).await?;
+    let cost_basis_method = settings_repository.get_cost_basis_method(user.id).await?;
+    let difficulty = settings_repository.get_difficulty(user.id).await?;
+
+    Ok(Json(SettingsResponse {
+        cost_basis_method,
+        cash_sweep_enabled: settings.as_ref().is_some_and(|s| s.cash_sweep_enabled),
+        drip_enabled: settings.as_ref().is_some_and(|s| s.drip_enabled),
+        benchmark_ticker: settings.as_ref().and_then(|s| s.benchmark_ticker.clone()),
+        difficulty,
+        updated_at: settings.map(|s| s.updated_at),
+    }))
+}
+
+/// Update the authenticated user's settings
+///
+/// Only the fields present in the request are changed. A new cost-basis
+/// method applies to sells made after the change; already realized gains keep
+/// the method they were computed with. Disabling the cash sweep moves the
+/// whole money market balance back into cash. With DRIP enabled, dividends
+/// are reinvested into whole shares of the paying stock. The benchmark ticker
+/// is compared against in the portfolio history and metrics; an empty string
+/// clears it. The difficulty applies to orders and loans placed after the
+/// change, except in competition portfolios, which follow the competition's.
+#[utoipa::path(
+    patch,
+    path = "/",
+    tag = "settings",
+    request_body = UpdateSettingsRequest,
+    responses(
+        (status = 200, description = "Updated settings", body = SettingsResponse),
+        (status = 400, description = "Validation error or nothing to update", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn update_settings(
+    claims: Claims,
+    db: Extension<AppState>,
+    Json(payload): Json<UpdateSettingsRequest>,
+) -> Result<Json<SettingsResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let settings_repository = UserSettingsRepository::new(&db.pg_pool);
+
+    let user = user_cache::get_user(&db, claims.user_id).await?;
+    let user = user.ok_or(crate::Error::Unauthorized)?;
+
+    if payload.cost_basis_method.is_none()
+        && payload.cash_sweep_enabled.is_none()
+        && payload.drip_enabled.is_none()
+        && payload.benchmark_ticker.is_none()
+        && payload.difficulty.is_none()
+    {
+        return Err(Error::BadRequest("No settings to update".into()));
+    }
+
+    if let Some(cost_basis_method) = payload.cost_basis_method {
+        settings_repository
+            .set_cost_basis_method(user.id, cost_basis_method)
+            .await?;
+    }
+
+    if let Some(enabled) = payload.cash_sweep_enabled {
+        settings_repository
+            .set_cash_sweep_enabled(user.id, enabled)
+            .await?;
+        if !enabled {
+            sweep::sweep_all_out(&db, user.id).await?;
+        }
+    }
+
+    if let Some(enabled) = payload.drip_enabled {
+        settings_repository
+            .set_drip_enabled(user.id, enabled)
+            .await?;
+    }
+
+    if let Some(ticker) = &payload.benchmark_ticker {
+        settings_repository
+            .set_benchmark_ticker(user.id, Some(ticker.as_str()).filter(|t| !t.is_empty()))
+            .await?;
+    }
+
+    if let Some(difficulty) = payload.difficulty {
+        settings_repository
+            .set_difficulty(user.id, difficulty)
+            .await?;
+    }
+
+    let settings = settings_repository
+        .get_settings(user.id)
+        .await?
+        .ok_or(Error::InternalServerError)?;
+
+    Ok(Json(SettingsResponse {
+        cost_basis_method: settings_repository.get_cost_basis_method(user.id).await?,
+        cash_sweep_enabled: settings.cash_sweep_enabled,
+        drip_enabled: settings.drip_enabled,
+        benchmark_ticker: settings.benchmark_ticker,
+        difficulty: settings_repository.get_difficulty(user.id).await?,
+        updated_at: Some(settings.updated_at),
+    }))
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct UpdateSettingsRequest {
+    cost_basis_method: Option<CostBasisMethod>,
+    cash_sweep_enabled: Option<bool>,
+    drip_enabled: Option<bool>,
+    #[validate(length(max = 10))]
+    benchmark_ticker: Option<String>,
+    difficulty: Option<Difficulty>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct SettingsResponse {
+    cost_basis_method: CostBasisMethod,
+    cash_sweep_enabled: bool,
+    drip_enabled: bool,
+    benchmark_ticker: Option<String>,
+    difficulty: Difficulty,
+    updated_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,442 @@
+use axum::{
+    Extension, Router,
+    extract::{DefaultBodyLimit, Multipart, Query},
+    routing::{get, post},
+};
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::portfolio::SelectedPortfolio,
+    models::transaction::Transaction,
+    repository::transaction_repository::TransactionFilter,
+    services::{
+        trading,
+        transaction_import::{self, ImportParser, ImportReport, MAX_IMPORT_ROWS, MAX_LINE_LEN},
+    },
+    timing::Json,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        get_transactions,
+        get_archived_transactions,
+        create_buy_transaction,
+        create_sell_transaction,
+        import_transactions
+    ),
+    // Only referenced by query parameters, which utoipa does not collect
+    components(schemas(TransactionType, SortOrder))
+)]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/", get(get_transactions))
+        .route("/archived", get(get_archived_transactions))
+        .route("/buy", post(create_buy_transaction))
+        .route("/sell", post(create_sell_transaction))
+        .route(
+            "/import",
+            post(import_transactions).layer(DefaultBodyLimit::max(MAX_IMPORT_SIZE)),
+        )
+}
+
+/// Default number of transactions per page
+const DEFAULT_PAGE_SIZE: i64 = 50;
+/// Largest page a client may request
+const MAX_PAGE_SIZE: i64 = 200;
+/// Largest accepted import upload, in bytes: the header and the most trades
+/// at the longest lines, with room for the multipart framing
+const MAX_IMPORT_SIZE: usize = (MAX_IMPORT_ROWS + 1) * (MAX_LINE_LEN + 2) + 64 * 1024;
+
+/// Get the transaction history of the selected portfolio
+///
+/// Returns one page of buy, sell, dividend, coupon and redemption transactions
+/// matching the query filters, newest first unless `order=asc` is given. Pass the returned
+/// `next_cursor` as `cursor` to fetch the following page; it is absent on the
+/// last page.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "transactions",
+    params(SelectedPortfolio, TransactionsQuery),
+    responses(
+        (status = 200, description = "One page of transactions", body = TransactionPageResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_transactions(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    db: Extension<AppState>,
+    Query(query): Query<TransactionsQuery>,
+) -> Result<Json<TransactionPageResponse>> {
+    let filter = query.filter()?;
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let newest_first = query.order == SortOrder::Desc;
+
+    // Fetch one extra row to learn whether another page follows
+    let transactions = db
+        .read_repos
+        .transactions
+        .get_transactions_page(portfolio.id, &filter, query.cursor, newest_first, limit + 1)
+        .await?;
+
+    Ok(Json(TransactionPageResponse::new(transactions, limit)))
+}
+
+/// Get the archived transaction history of the selected portfolio
+///
+/// Transactions older than the deployment's archive cutoff are no longer
+/// listed by `GET /transactions`; this lists them with the same filters and
+/// paging, newest first unless `order=asc` is given. Nothing is archived
+/// unless the deployment enables archiving.
+#[utoipa::path(
+    get,
+    path = "/archived",
+    tag = "transactions",
+    params(SelectedPortfolio, TransactionsQuery),
+    responses(
+        (status = 200, description = "One page of archived transactions", body = TransactionPageResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_archived_transactions(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    db: Extension<AppState>,
+    Query(query): Query<TransactionsQuery>,
+) -> Result<Json<TransactionPageResponse>> {
+    let filter = query.filter()?;
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let newest_first = query.order == SortOrder::Desc;
+
+    let transactions = db
+        .read_repos
+        .transactions
+        .get_archived_page(portfolio.id, &filter, query.cursor, newest_first, limit + 1)
+        .await?;
+
+    Ok(Json(TransactionPageResponse::new(transactions, limit)))
+}
+
+/// Create a buy transaction
+///
+/// Creates a new buy transaction in the selected portfolio.
+/// This operation:
+/// 1. Validates the ticker is listed and active, the portfolio has sufficient
+///    balance, for a competition portfolio, the competition is running and,
+///    for a class portfolio, the class allows the ticker
+/// 2. Creates a transaction record
+/// 3. Updates or creates a holding record
+/// 4. Opens a tax lot for cost-basis tracking
+/// 5. Updates the portfolio's balance (deducting the cost)
+///
+/// Steps 3 to 5 are booked in one database transaction, and the transaction
+/// record is removed again when they fail.
+#[utoipa::path(
+    post,
+    path = "/buy",
+    tag = "transactions",
+    params(SelectedPortfolio),
+    request_body = CreateBuyTransactionRequest,
+    responses(
+        (status = 200, description = "Executed transaction", body = TransactionResponse),
+        (status = 400, description = "Validation error, unknown ticker, insufficient balance, competition not running or ticker not allowed in the class", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn create_buy_transaction(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateBuyTransactionRequest>,
+) -> Result<Json<TransactionResponse>> {
+    payload
+        .validate()
+        .map_err(|e| crate::Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let transaction = trading::buy(&state, &portfolio, &payload.ticker, payload.quantity).await?;
+
+    Ok(Json(transaction.into()))
+}
+
+/// Create a sell transaction
+///
+/// Creates a new sell transaction in the selected portfolio.
+/// This operation:
+/// 1. Validates the ticker is listed, the portfolio has sufficient holdings
+///    and, for a competition portfolio, the competition is running
+/// 2. Creates a transaction record
+/// 3. Updates the holding quantity
+/// 4. Consumes tax lots according to the user's cost-basis method and
+///    records the realized gain
+/// 5. Updates the portfolio's balance (adding the proceeds)
+///
+/// Steps 3 to 5 are booked in one database transaction, and the transaction
+/// record is removed again when they fail.
+#[utoipa::path(
+    post,
+    path = "/sell",
+    tag = "transactions",
+    params(SelectedPortfolio),
+    request_body = CreateSellTransactionRequest,
+    responses(
+        (status = 200, description = "Executed transaction", body = TransactionResponse),
+        (status = 400, description = "Validation error, unknown ticker, insufficient holdings or competition not running", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn create_sell_transaction(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateSellTransactionRequest>,
+) -> Result<Json<TransactionResponse>> {
+    payload
+        .validate()
+        .map_err(|e| crate::Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let (transaction, realized_gain) =
+        trading::sell(&state, &portfolio, &payload.ticker, payload.quantity).await?;
+
+    let response = TransactionResponse {
+        realized_gain: Some(realized_gain),
+        ..transaction.into()
+    };
+
+    Ok(Json(response))
+}
+
+/// Import a trade history from CSV into the selected portfolio
+///
+/// Upload the file as the `file` field of a `multipart/form-data` body, one
+/// trade per line under the header `date,ticker,side,quantity,price[,fee]`.
+/// `date` is RFC 3339 or `YYYY-MM-DD`, `side` is `buy` or `sell`, and `fee`
+/// defaults to zero.
+///
+/// Every line is checked before anything is imported: the report lists the
+/// invalid lines and nothing is imported unless there are none. Valid trades
+/// are then booked in date order as if executed at the given price on that
+/// date, paying from and into the cash balance, rebuilding holdings and tax
+/// lots and realizing gains under the user's cost-basis method. Only
+/// portfolios without transactions can import, and not competition or class
+/// portfolios.
+#[utoipa::path(
+    post,
+    path = "/import",
+    tag = "transactions",
+    params(SelectedPortfolio),
+    request_body(content = ImportUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Import report; `imported` is zero when any line is invalid", body = ImportReport),
+        (status = 400, description = "Malformed upload, bad header, too many trades, or a competition or class portfolio", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Selected portfolio not found", body = ErrorResponse),
+        (status = 409, description = "The portfolio already has transactions", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn import_transactions(
+    SelectedPortfolio(portfolio): SelectedPortfolio,
+    state: Extension<AppState>,
+    mut multipart: Multipart,
+) -> Result<Json<ImportReport>> {
+    let invalid_upload = |e: axum::extract::multipart::MultipartError| {
+        Error::BadRequest(format!("Invalid multipart upload: {}", e.body_text()))
+    };
+
+    let mut parser = None;
+    while let Some(mut field) = multipart.next_field().await.map_err(invalid_upload)? {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let mut csv = ImportParser::new();
+        while let Some(chunk) = field.chunk().await.map_err(invalid_upload)? {
+            csv.push(&chunk)?;
+        }
+        parser = Some(csv);
+    }
+    let parser = parser.ok_or_else(|| Error::BadRequest("Missing `file` field".into()))?;
+
+    let (rows, mut report) = parser.finish()?;
+    transaction_import::import(&state, &portfolio, rows, &mut report).await?;
+
+    Ok(Json(report))
+}
+
+/// Multipart body of a transaction import, only describing it in the OpenAPI
+/// document since the handler reads the upload as a stream
+#[derive(ToSchema)]
+struct ImportUpload {
+    /// CSV file of trades
+    #[schema(value_type = String, format = Binary)]
+    #[allow(dead_code)]
+    file: Vec<u8>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct TransactionsQuery {
+    /// `next_cursor` of the previous page
+    cursor: Option<Uuid>,
+    #[validate(range(min = 1, max = "MAX_PAGE_SIZE"))]
+    limit: Option<i64>,
+    #[validate(length(min = 1, max = 10))]
+    ticker: Option<String>,
+    #[serde(rename = "type")]
+    transaction_type: Option<TransactionType>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    min_price: Option<BigDecimal>,
+    max_price: Option<BigDecimal>,
+    #[serde(default)]
+    order: SortOrder,
+}
+
+impl TransactionsQuery {
+    /// Validate the query and build the filter it describes
+    fn filter(&self) -> Result<TransactionFilter> {
+        self.validate()
+            .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+        if let (Some(from), Some(to)) = (self.from, self.to)
+            && from > to
+        {
+            return Err(Error::BadRequest("`from` must not be after `to`".into()));
+        }
+        if let (Some(min), Some(max)) = (&self.min_price, &self.max_price)
+            && min > max
+        {
+            return Err(Error::BadRequest(
+                "`min_price` must not exceed `max_price`".into(),
+            ));
+        }
+
+        Ok(TransactionFilter {
+            ticker: self.ticker.clone(),
+            transaction_type: self.transaction_type.map(|t| t.as_str().to_string()),
+            from: self.from,
+            to: self.to,
+            min_price: self.min_price.clone(),
+            max_price: self.max_price.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum TransactionType {
+    Buy,
+    Sell,
+    Dividend,
+    Coupon,
+    Redemption,
+}
+
+impl TransactionType {
+    fn as_str(self) -> &'static str {
+        match self {
+            TransactionType::Buy => "buy",
+            TransactionType::Sell => "sell",
+            TransactionType::Dividend => "dividend",
+            TransactionType::Coupon => "coupon",
+            TransactionType::Redemption => "redemption",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreateBuyTransactionRequest {
+    #[validate(length(min = 1, max = 10))]
+    ticker: String,
+    #[validate(range(min = 1, max = 10000))]
+    quantity: i32,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreateSellTransactionRequest {
+    #[validate(length(min = 1, max = 10))]
+    ticker: String,
+    #[validate(range(min = 1, max = 10000))]
+    quantity: i32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct TransactionResponse {
+    id: Uuid,
+    ticker: String,
+    quantity: i32,
+    price: BigDecimal,
+    /// Commission paid on top of the price
+    fee: BigDecimal,
+    transaction_type: String,
+    created_at: NaiveDateTime,
+    /// Differs from `created_at` only for backdated seed trades
+    updated_at: NaiveDateTime,
+    /// Gain realized by a sell under the user's cost-basis method
+    #[serde(skip_serializing_if = "Option::is_none")]
+    realized_gain: Option<BigDecimal>,
+}
+
+impl From<Transaction> for TransactionResponse {
+    fn from(transaction: Transaction) -> Self {
+        TransactionResponse {
+            id: transaction.public_id,
+            ticker: transaction.ticker,
+            quantity: transaction.quantity,
+            price: transaction.price,
+            fee: transaction.fee,
+            transaction_type: transaction.transaction_type,
+            created_at: transaction.created_at,
+            updated_at: transaction.updated_at,
+            realized_gain: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct TransactionPageResponse {
+    transactions: Vec<TransactionResponse>,
+    /// Cursor of the next page, absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<Uuid>,
+}
+
+impl TransactionPageResponse {
+    /// Page of up to `limit` of `transactions`, fetched with one extra row
+    /// telling whether another page follows
+    fn new(mut transactions: Vec<Transaction>, limit: i64) -> Self {
+        let next_cursor = if transactions.len() as i64 > limit {
+            transactions.truncate(limit as usize);
+            transactions.last().map(|tx| tx.public_id)
+        } else {
+            None
+        };
+
+        TransactionPageResponse {
+            transactions: transactions.into_iter().map(Into::into).collect(),
+            next_cursor,
+        }
+    }
+}
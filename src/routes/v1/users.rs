@@ -0,0 +1,327 @@
+use axum::{
+    Extension, Router,
+    extract::Path,
+    routing::{get, put},
+};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::{OpenApi, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::jwt::Claims,
+    models::{follow::FollowedUser, user::User},
+    repository::{
+        follow_repository::FollowRepository, portfolio_repository::PortfolioRepository,
+        user_settings_repository::UserSettingsRepository,
+    },
+    services::portfolio,
+    timing::Json,
+};
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_user,
+    follow,
+    unfollow,
+    get_followers,
+    get_following,
+    get_public_portfolio
+))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/{id}", get(get_user))
+        .route("/{id}/follow", put(follow).delete(unfollow))
+        .route("/{id}/followers", get(get_followers))
+        .route("/{id}/following", get(get_following))
+        .route("/{id}/portfolios/{portfolio_id}", get(get_public_portfolio))
+}
+
+/// Get the public profile of a user
+///
+/// Lists the user's public portfolios along with their follower counts.
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Public profile", body = PublicProfileResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_user(
+    claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PublicProfileResponse>> {
+    let user = find_user(&state, id).await?;
+
+    let follows = FollowRepository::new(&state.pg_pool);
+    let (followers, following) = follows.get_counts(user.id).await?;
+    let you_follow = follows.is_following(claims.user_id, user.id).await?;
+    let settings = UserSettingsRepository::new(&state.pg_pool)
+        .get_settings(user.id)
+        .await?;
+    let portfolios = PortfolioRepository::new(&state.pg_pool)
+        .get_public_portfolios(user.id)
+        .await?;
+
+    Ok(Json(PublicProfileResponse {
+        id,
+        display_name: settings.and_then(|s| s.display_name),
+        followers,
+        following,
+        you_follow,
+        portfolios: portfolios
+            .into_iter()
+            .map(|portfolio| PublicPortfolioSummary {
+                id: portfolio.id,
+                name: portfolio.name,
+            })
+            .collect(),
+    }))
+}
+
+/// Follow a user
+///
+/// Trades in the user's public portfolios show up in the caller's feed.
+/// Following a user again changes nothing.
+#[utoipa::path(
+    put,
+    path = "/{id}/follow",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "User followed", body = String),
+        (status = 400, description = "Users cannot follow themselves", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn follow(
+    claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<&'static str>> {
+    let user = find_user(&state, id).await?;
+    if user.id == claims.user_id {
+        return Err(Error::BadRequest("You cannot follow yourself".into()));
+    }
+
+    FollowRepository::new(&state.pg_pool)
+        .follow(claims.user_id, user.id)
+        .await?;
+
+    Ok(Json("Following"))
+}
+
+/// Stop following a user
+#[utoipa::path(
+    delete,
+    path = "/{id}/follow",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "User unfollowed", body = String),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "Not following the user", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn unfollow(
+    claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<&'static str>> {
+    let user = find_user(&state, id).await?;
+    if !FollowRepository::new(&state.pg_pool)
+        .unfollow(claims.user_id, user.id)
+        .await?
+    {
+        return Err(Error::NotFound);
+    }
+
+    Ok(Json("Unfollowed"))
+}
+
+/// List the users following a user, latest first
+#[utoipa::path(
+    get,
+    path = "/{id}/followers",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Followers", body = Vec<FollowResponse>),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_followers(
+    _claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<FollowResponse>>> {
+    let user = find_user(&state, id).await?;
+
+    let followers = FollowRepository::new(&state.pg_pool)
+        .get_followers(user.id)
+        .await?;
+
+    Ok(Json(followers.into_iter().map(Into::into).collect()))
+}
+
+/// List the users a user follows, latest first
+#[utoipa::path(
+    get,
+    path = "/{id}/following",
+    tag = "users",
+    params(("id" = Uuid, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Followed users", body = Vec<FollowResponse>),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_following(
+    _claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<FollowResponse>>> {
+    let user = find_user(&state, id).await?;
+
+    let following = FollowRepository::new(&state.pg_pool)
+        .get_following(user.id)
+        .await?;
+
+    Ok(Json(following.into_iter().map(Into::into).collect()))
+}
+
+/// Get the positions of a user's public portfolio
+///
+/// Positions are shown as their weight in the portfolio's equity, at the
+/// latest prices; cash amounts are never shown. Quantities are `null` when
+/// the user hides them. Unlike trades in the feed, positions are current.
+#[utoipa::path(
+    get,
+    path = "/{id}/portfolios/{portfolio_id}",
+    tag = "users",
+    params(
+        ("id" = Uuid, Path, description = "User ID"),
+        ("portfolio_id" = i32, Path, description = "Portfolio ID"),
+    ),
+    responses(
+        (status = 200, description = "Public portfolio", body = PublicPortfolioResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "No such public portfolio", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn get_public_portfolio(
+    _claims: Claims,
+    state: Extension<AppState>,
+    Path((id, portfolio_id)): Path<(Uuid, i32)>,
+) -> Result<Json<PublicPortfolioResponse>> {
+    let user = find_user(&state, id).await?;
+    let portfolio = PortfolioRepository::new(&state.pg_pool)
+        .get_portfolio(portfolio_id)
+        .await?
+        .filter(|portfolio| portfolio.user_id == user.id && portfolio.is_public)
+        .ok_or(Error::NotFound)?;
+    let hide_quantities = UserSettingsRepository::new(&state.pg_pool)
+        .get_settings(user.id)
+        .await?
+        .is_some_and(|s| s.hide_trade_quantities);
+
+    let valuation = portfolio::value_portfolio(&state, &portfolio).await?;
+    let positions = valuation
+        .positions
+        .into_iter()
+        .map(|position| PublicPosition {
+            weight_percent: portfolio::percent_of(&position.market_value, &valuation.equity),
+            quantity: (!hide_quantities).then_some(position.quantity),
+            unrealized_pnl_percent: position.unrealized_pnl_percent,
+            ticker: position.ticker,
+        })
+        .collect();
+
+    Ok(Json(PublicPortfolioResponse {
+        id: portfolio.id,
+        user_id: user.public_id,
+        name: portfolio.name,
+        positions,
+    }))
+}
+
+/// The user with public ID `id`
+async fn find_user(state: &AppState, id: Uuid) -> Result<User> {
+    state
+        .repos
+        .users
+        .get_user_by_public_id(id)
+        .await?
+        .ok_or(Error::NotFound)
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct PublicProfileResponse {
+    id: Uuid,
+    /// `null` for users without a display name
+    display_name: Option<String>,
+    followers: i64,
+    following: i64,
+    /// Whether the caller follows the user
+    you_follow: bool,
+    portfolios: Vec<PublicPortfolioSummary>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct PublicPortfolioSummary {
+    id: i32,
+    name: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct FollowResponse {
+    user_id: Uuid,
+    /// `null` for users without a display name
+    display_name: Option<String>,
+    followed_at: DateTime<Utc>,
+}
+
+impl From<FollowedUser> for FollowResponse {
+    fn from(user: FollowedUser) -> Self {
+        FollowResponse {
+            user_id: user.user_id,
+            display_name: user.display_name,
+            followed_at: user.followed_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct PublicPortfolioResponse {
+    id: i32,
+    user_id: Uuid,
+    name: String,
+    positions: Vec<PublicPosition>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct PublicPosition {
+    ticker: String,
+    /// `null` when the owner hides quantities
+    quantity: Option<i32>,
+    /// Share of the portfolio's equity in percent
+    weight_percent: BigDecimal,
+    unrealized_pnl_percent: BigDecimal,
+}
@@ -0,0 +1,301 @@
+use axum::{
+    Extension, Router,
+    extract::{Path, Query},
+    routing::{delete, get},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use validator::Validate;
+
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::jwt::Claims,
+    models::webhook::{Webhook, WebhookDelivery},
+    repository::webhook_repository::WebhookRepository,
+    services::webhooks,
+    timing::Json,
+    ws::messages::AccountEvent,
+};
+
+/// Most webhooks a user may register
+const MAX_WEBHOOKS: usize = 5;
+/// Default number of deliveries per page
+const DEFAULT_PAGE_SIZE: i64 = 50;
+/// Largest page a client may request
+const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(OpenApi)]
+#[openapi(paths(list_webhooks, create_webhook, delete_webhook, list_deliveries))]
+pub struct ApiDoc;
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/", get(list_webhooks).post(create_webhook))
+        .route("/{id}", delete(delete_webhook))
+        .route("/{id}/deliveries", get(list_deliveries))
+}
+
+/// List the authenticated user's webhooks
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "Webhooks of the user, without their secrets", body = Vec<WebhookResponse>),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn list_webhooks(
+    claims: Claims,
+    state: Extension<AppState>,
+) -> Result<Json<Vec<WebhookResponse>>> {
+    let webhooks = WebhookRepository::new(&state.pg_pool)
+        .get_webhooks_by_user(claims.user_id)
+        .await?;
+
+    Ok(Json(webhooks.into_iter().map(Into::into).collect()))
+}
+
+/// Register a URL the user's account events are posted to
+///
+/// Every event of the given kinds is posted as JSON and signed with the
+/// returned secret; see the README for verifying signatures. The secret is
+/// only returned here. The URL must be `https` and point at a public host.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 200, description = "Registered webhook with its signing secret", body = CreatedWebhookResponse),
+        (status = 400, description = "Validation error or too many webhooks", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn create_webhook(
+    claims: Claims,
+    state: Extension<AppState>,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> Result<Json<CreatedWebhookResponse>> {
+    payload
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let url = Url::parse(payload.url.trim())
+        .map_err(|_| Error::BadRequest("url must be an absolute URL".to_string()))?;
+    webhooks::check_url(&url, state.config.webhook_allow_insecure)?;
+    let mut events = Vec::with_capacity(payload.events.len());
+    for event in payload.events {
+        if !AccountEvent::KINDS.contains(&event.as_str()) {
+            return Err(Error::BadRequest(format!(
+                "Unknown event '{}', expected one of {}",
+                event,
+                AccountEvent::KINDS.join(", ")
+            )));
+        }
+        if !events.contains(&event) {
+            events.push(event);
+        }
+    }
+
+    let repository = WebhookRepository::new(&state.pg_pool);
+    if repository.get_webhooks_by_user(claims.user_id).await?.len() >= MAX_WEBHOOKS {
+        return Err(Error::BadRequest(format!(
+            "At most {} webhooks per user",
+            MAX_WEBHOOKS
+        )));
+    }
+
+    let created = repository
+        .create_webhook(
+            claims.user_id,
+            url.as_str(),
+            &webhooks::generate_secret(),
+            &events,
+        )
+        .await?;
+    tracing::info!(
+        "User ID {} registered webhook {}",
+        claims.user_id,
+        created.id
+    );
+
+    Ok(Json(CreatedWebhookResponse {
+        secret: created.secret.clone(),
+        webhook: created.into(),
+    }))
+}
+
+/// Delete a webhook with its delivery log, stopping deliveries at once
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "webhooks",
+    params(("id" = i32, Path, description = "Webhook ID")),
+    responses(
+        (status = 200, description = "Webhook deleted", body = String),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "No such webhook of the user", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn delete_webhook(
+    claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+) -> Result<Json<&'static str>> {
+    if !WebhookRepository::new(&state.pg_pool)
+        .delete_webhook(id, claims.user_id)
+        .await?
+    {
+        return Err(Error::NotFound);
+    }
+    tracing::info!("User ID {} deleted webhook {}", claims.user_id, id);
+
+    Ok(Json("Webhook deleted"))
+}
+
+/// Get the delivery log of a webhook, newest first
+///
+/// Lists the events posted or still to be posted, with the outcome of their
+/// latest attempt. Delivered and failed events are kept for 30 days. Pass
+/// the returned `next_cursor` as `before` to fetch the following page; it is
+/// absent on the last page.
+#[utoipa::path(
+    get,
+    path = "/{id}/deliveries",
+    tag = "webhooks",
+    params(("id" = i32, Path, description = "Webhook ID"), DeliveriesQuery),
+    responses(
+        (status = 200, description = "One page of deliveries", body = DeliveriesResponse),
+        (status = 400, description = "Validation error", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+        (status = 404, description = "No such webhook of the user", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+async fn list_deliveries(
+    claims: Claims,
+    state: Extension<AppState>,
+    Path(id): Path<i32>,
+    Query(query): Query<DeliveriesQuery>,
+) -> Result<Json<DeliveriesResponse>> {
+    query
+        .validate()
+        .map_err(|e| Error::BadRequest(format!("Validation error: {}", e)))?;
+
+    let repository = WebhookRepository::new(&state.pg_pool);
+    repository
+        .get_webhook(id, claims.user_id)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let deliveries = repository.get_deliveries(id, query.before, limit).await?;
+
+    let next_cursor = if deliveries.len() as i64 == limit {
+        deliveries.last().map(|delivery| delivery.id)
+    } else {
+        None
+    };
+
+    Ok(Json(DeliveriesResponse {
+        deliveries: deliveries.into_iter().map(Into::into).collect(),
+        next_cursor,
+    }))
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+struct CreateWebhookRequest {
+    /// `http` or `https` URL events are posted to
+    #[validate(length(min = 1, max = 2048))]
+    url: String,
+    /// Kinds of events posted: `order_filled`, `margin_call` or `deposit_settled`
+    #[validate(length(min = 1, max = 10))]
+    events: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct WebhookResponse {
+    id: i32,
+    url: String,
+    events: Vec<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<Webhook> for WebhookResponse {
+    fn from(webhook: Webhook) -> Self {
+        WebhookResponse {
+            id: webhook.id,
+            url: webhook.url,
+            events: webhook.events,
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct CreatedWebhookResponse {
+    /// Key of the `X-Webhook-Signature` HMAC; it cannot be retrieved again
+    secret: String,
+    #[serde(flatten)]
+    webhook: WebhookResponse,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DeliveriesQuery {
+    /// Cursor from a previous page
+    before: Option<i64>,
+    #[validate(range(min = 1, max = "MAX_PAGE_SIZE"))]
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DeliveriesResponse {
+    deliveries: Vec<DeliveryResponse>,
+    /// Cursor of the next page, absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DeliveryResponse {
+    id: i64,
+    event: String,
+    /// The JSON body posted
+    payload: serde_json::Value,
+    /// `pending`, `delivered` or `failed` once every attempt failed
+    status: String,
+    attempts: i32,
+    /// When a pending delivery is attempted next
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_attempt_at: Option<DateTime<Utc>>,
+    /// HTTP status the receiver answered the latest attempt with
+    response_status: Option<i32>,
+    /// Why the latest attempt failed
+    error: Option<String>,
+    created_at: DateTime<Utc>,
+    delivered_at: Option<DateTime<Utc>>,
+}
+
+impl From<WebhookDelivery> for DeliveryResponse {
+    fn from(delivery: WebhookDelivery) -> Self {
+        DeliveryResponse {
+            id: delivery.id,
+            event: delivery.event,
+            payload: delivery.payload,
+            next_attempt_at: (delivery.status == "pending").then_some(delivery.next_attempt_at),
+            status: delivery.status,
+            attempts: delivery.attempts,
+            response_status: delivery.response_status,
+            error: delivery.error,
+            created_at: delivery.created_at,
+            delivered_at: delivery.delivered_at,
+        }
+    }
+}
@@ -0,0 +1,12 @@
+//! # API Schema
+//!
+//! Raw schema documents describing the public interfaces of the simulator.
+//! The same files are published to dependent build scripts through the
+//! `DEP_STOCK_EXCHANGE_SIM_CORE_PROTO_DIR` and `DEP_STOCK_EXCHANGE_SIM_CORE_OPENAPI`
+//! environment variables.
+
+/// OpenAPI 3 document for the REST API
+pub const OPENAPI_JSON: &str = include_str!("../api/openapi.json");
+
+/// Protobuf definition of the price feed service
+pub const PRICEFEED_PROTO: &str = include_str!("../proto/pricefeed.proto");
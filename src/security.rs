@@ -0,0 +1,116 @@
+//! # Security Middleware
+//!
+//! Layers wrapping every HTTP response:
+//!
+//! - [`security_headers`] adds `X-Content-Type-Options`, `X-Frame-Options`
+//!   and `Referrer-Policy`, plus `Strict-Transport-Security` when
+//!   `HSTS_MAX_AGE_SECS` is set and `Content-Security-Policy` unless
+//!   `CONTENT_SECURITY_POLICY` is empty
+//! - [`request_timeout`] answers `408` when a handler takes longer than
+//!   `REQUEST_TIMEOUT_SECS`, dropping its work
+//! - [`body_limit`] answers bodies over their limit with `413` and the usual
+//!   JSON error
+//!
+//! The body limit is `MAX_REQUEST_SIZE`, set as the `DefaultBodyLimit` of
+//! the whole router and enforced by the extractors reading the body. Routes
+//! that need another limit, like imports of large files, override it with a
+//! `DefaultBodyLimit` layer of their own.
+//!
+//! WebSocket connections are only timed until the upgrade, since the socket
+//! itself is served outside of the request.
+
+use std::{sync::Arc, time::Duration};
+
+use crate::{Error, config::Config, errors::ErrorResponse};
+use axum::{
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+/// Values of the headers added to every response
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders {
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl SecurityHeaders {
+    /// Headers configured by `HSTS_MAX_AGE_SECS` and `CONTENT_SECURITY_POLICY`
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let mut headers = vec![
+            (
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            ),
+            (header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY")),
+            (
+                header::REFERRER_POLICY,
+                HeaderValue::from_static("no-referrer"),
+            ),
+        ];
+        if config.hsts_max_age_secs > 0 {
+            headers.push((
+                header::STRICT_TRANSPORT_SECURITY,
+                HeaderValue::from_str(&format!(
+                    "max-age={}; includeSubDomains",
+                    config.hsts_max_age_secs
+                ))?,
+            ));
+        }
+        if !config.content_security_policy.is_empty() {
+            headers.push((
+                header::CONTENT_SECURITY_POLICY,
+                HeaderValue::from_str(&config.content_security_policy)
+                    .map_err(|_| anyhow::anyhow!("Invalid CONTENT_SECURITY_POLICY"))?,
+            ));
+        }
+
+        Ok(SecurityHeaders { headers })
+    }
+}
+
+/// Middleware adding the [`SecurityHeaders`] to every response
+///
+/// Headers a route set itself, like the relaxed policy of the API docs, are
+/// kept.
+pub async fn security_headers(
+    State(security): State<Arc<SecurityHeaders>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    for (name, value) in &security.headers {
+        if !response.headers().contains_key(name) {
+            response.headers_mut().insert(name, value.clone());
+        }
+    }
+    response
+}
+
+/// Middleware cutting requests off after `timeout`
+pub async fn request_timeout(
+    State(timeout): State<Duration>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            tracing::warn!("Request to {} timed out after {:?}", path, timeout);
+            let body = axum::Json(ErrorResponse::new("Request timed out"));
+            (StatusCode::REQUEST_TIMEOUT, body).into_response()
+        }
+    }
+}
+
+/// Middleware turning the `413` of extractors into the JSON error envelope
+pub async fn body_limit(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE {
+        return response;
+    }
+    tracing::warn!("Rejected request body over its size limit");
+    Error::PayloadTooLarge.into_response()
+}
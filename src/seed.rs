@@ -0,0 +1,536 @@
+//! # Demo Data
+//!
+//! `stock-exchange-sim-core seed` fills an empty database with data to build
+//! frontends against and to run classroom demos with:
+//!
+//! - a catalog of [`INSTRUMENTS`] with a year of synthetic daily candles,
+//!   whose last close is cached as the current price so live prices continue
+//!   from it
+//! - the demo accounts of [`PERSONAS`], each with its own trading style,
+//!   a year of backdated transactions and daily equity snapshots
+//!
+//! Every account signs in with [`DEMO_PASSWORD`]. Prices and trades come from
+//! a fixed random seed, so every seeded database looks the same. Databases
+//! that already have users are refused, so demo accounts with a published
+//! password never end up next to real ones.
+
+use std::collections::{BTreeMap, HashMap};
+
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode, ToPrimitive};
+use chrono::{Days, NaiveDate, NaiveTime, TimeDelta, Utc};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_distr::StandardNormal;
+
+use crate::{
+    AppState, Error, Result,
+    auth::password::hash_password,
+    models::{
+        instrument::InstrumentDetails,
+        price_candle::{CandleInterval, PriceCandle},
+        user::Role,
+        user_settings::CostBasisMethod,
+    },
+    repository::{
+        benchmark_price_repository::BenchmarkPriceRepository,
+        instrument_repository::InstrumentRepository, ledger_repository::TradePosting,
+        portfolio_repository::PortfolioRepository,
+        portfolio_snapshot_repository::PortfolioSnapshotRepository,
+        price_candle_repository::PriceCandleRepository,
+        user_settings_repository::UserSettingsRepository,
+    },
+    services::{positions, price_updater},
+};
+
+/// Password of every demo account
+pub const DEMO_PASSWORD: &str = "demo-trader-2025";
+/// Seed of the random prices and trades
+const SEED: u64 = 1607;
+/// Days of history before today
+const HISTORY_DAYS: u64 = 365;
+/// Price moves simulated per day to shape each candle
+const STEPS_PER_DAY: usize = 8;
+/// Annualized drift of every demo price
+const DRIFT: f64 = 0.08;
+/// Name of the portfolio every demo account trades in
+const PORTFOLIO_NAME: &str = "Main";
+/// Ticker demo accounts compare their performance against
+const BENCHMARK: &str = "SPY";
+
+/// A listed demo instrument and how its price moves
+struct DemoInstrument {
+    ticker: &'static str,
+    name: &'static str,
+    sector: Option<&'static str>,
+    asset_class: &'static str,
+    start_price: f64,
+    /// Annualized volatility
+    volatility: f64,
+}
+
+const INSTRUMENTS: &[DemoInstrument] = &[
+    DemoInstrument {
+        ticker: "AAPL",
+        name: "Apple Inc.",
+        sector: Some("Technology"),
+        asset_class: "equity",
+        start_price: 190.0,
+        volatility: 0.25,
+    },
+    DemoInstrument {
+        ticker: "MSFT",
+        name: "Microsoft Corporation",
+        sector: Some("Technology"),
+        asset_class: "equity",
+        start_price: 410.0,
+        volatility: 0.22,
+    },
+    DemoInstrument {
+        ticker: "GOOGL",
+        name: "Alphabet Inc.",
+        sector: Some("Communication Services"),
+        asset_class: "equity",
+        start_price: 150.0,
+        volatility: 0.28,
+    },
+    DemoInstrument {
+        ticker: "AMZN",
+        name: "Amazon.com Inc.",
+        sector: Some("Consumer Discretionary"),
+        asset_class: "equity",
+        start_price: 180.0,
+        volatility: 0.30,
+    },
+    DemoInstrument {
+        ticker: "NVDA",
+        name: "NVIDIA Corporation",
+        sector: Some("Technology"),
+        asset_class: "equity",
+        start_price: 120.0,
+        volatility: 0.50,
+    },
+    DemoInstrument {
+        ticker: "TSLA",
+        name: "Tesla Inc.",
+        sector: Some("Consumer Discretionary"),
+        asset_class: "equity",
+        start_price: 200.0,
+        volatility: 0.60,
+    },
+    DemoInstrument {
+        ticker: "JPM",
+        name: "JPMorgan Chase & Co.",
+        sector: Some("Financials"),
+        asset_class: "equity",
+        start_price: 200.0,
+        volatility: 0.20,
+    },
+    DemoInstrument {
+        ticker: "XOM",
+        name: "Exxon Mobil Corporation",
+        sector: Some("Energy"),
+        asset_class: "equity",
+        start_price: 110.0,
+        volatility: 0.22,
+    },
+    DemoInstrument {
+        ticker: "KO",
+        name: "The Coca-Cola Company",
+        sector: Some("Consumer Staples"),
+        asset_class: "equity",
+        start_price: 62.0,
+        volatility: 0.14,
+    },
+    DemoInstrument {
+        ticker: "SPY",
+        name: "SPDR S&P 500 ETF Trust",
+        sector: None,
+        asset_class: "etf",
+        start_price: 520.0,
+        volatility: 0.15,
+    },
+];
+
+/// How a demo account trades over the year
+enum Style {
+    /// Buys the same shares at the start, a third and two thirds into the
+    /// year, then holds
+    BuyAndHold(&'static [(&'static str, i32)]),
+    /// Buys or sells one of the tickers every few days
+    Active(&'static [&'static str]),
+    /// Never trades
+    Cash,
+}
+
+/// A demo account
+struct Persona {
+    email: &'static str,
+    display_name: &'static str,
+    role: Role,
+    cash: i32,
+    cost_basis_method: CostBasisMethod,
+    style: Style,
+}
+
+const PERSONAS: &[Persona] = &[
+    Persona {
+        email: "alice@demo.local",
+        display_name: "Alice (long-term investor)",
+        role: Role::User,
+        cash: 100_000,
+        cost_basis_method: CostBasisMethod::Fifo,
+        style: Style::BuyAndHold(&[("SPY", 30), ("AAPL", 25), ("MSFT", 15), ("KO", 60)]),
+    },
+    Persona {
+        email: "bob@demo.local",
+        display_name: "Bob (day trader)",
+        role: Role::User,
+        cash: 50_000,
+        cost_basis_method: CostBasisMethod::Average,
+        style: Style::Active(&["TSLA", "NVDA", "AMZN", "GOOGL"]),
+    },
+    Persona {
+        email: "carol@demo.local",
+        display_name: "Carol (value investor)",
+        role: Role::User,
+        cash: 75_000,
+        cost_basis_method: CostBasisMethod::Lifo,
+        style: Style::BuyAndHold(&[("JPM", 40), ("XOM", 60), ("KO", 100)]),
+    },
+    Persona {
+        email: "dave@demo.local",
+        display_name: "Dave (new account)",
+        role: Role::User,
+        cash: 1_000,
+        cost_basis_method: CostBasisMethod::Average,
+        style: Style::Cash,
+    },
+    Persona {
+        email: "admin@demo.local",
+        display_name: "Demo admin",
+        role: Role::Admin,
+        cash: 1_000,
+        cost_basis_method: CostBasisMethod::Average,
+        style: Style::Cash,
+    },
+];
+
+/// A trade of a demo account at the close of a day of the history
+struct Trade {
+    day: usize,
+    ticker: &'static str,
+    /// Positive to buy, negative to sell
+    quantity: i32,
+}
+
+/// Fill an empty database with the demo catalog, prices and accounts
+pub async fn run(state: &AppState) -> Result<()> {
+    if state.repos.users.count_users().await? > 0 {
+        return Err(Error::Conflict(
+            "Refusing to seed a database that already has users".into(),
+        ));
+    }
+
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let today = Utc::now().date_naive();
+    let first_day = today - Days::new(HISTORY_DAYS);
+    let days: Vec<NaiveDate> = first_day
+        .iter_days()
+        .take_while(|day| *day < today)
+        .collect();
+
+    let mut closes = HashMap::new();
+    for instrument in INSTRUMENTS {
+        let candles = seed_instrument(state, instrument, &days, &mut rng).await?;
+        closes.insert(
+            instrument.ticker,
+            candles.into_iter().map(|c| c.close).collect::<Vec<_>>(),
+        );
+    }
+    tracing::info!(
+        "Seeded {} instruments with {} days of candles",
+        INSTRUMENTS.len(),
+        days.len()
+    );
+
+    for persona in PERSONAS {
+        let trades = plan_trades(persona, &closes, days.len(), &mut rng);
+        seed_persona(state, persona, &trades, &closes, &days).await?;
+        tracing::info!(
+            "Seeded demo account {} with {} trades",
+            persona.email,
+            trades.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// List an instrument and backfill its daily candles, returning them
+///
+/// The last close becomes the current price, and the benchmark's closes are
+/// recorded as its daily benchmark prices.
+async fn seed_instrument(
+    state: &AppState,
+    instrument: &DemoInstrument,
+    days: &[NaiveDate],
+    rng: &mut StdRng,
+) -> Result<Vec<PriceCandle>> {
+    InstrumentRepository::new(&state.pg_pool)
+        .create_instrument(
+            instrument.ticker,
+            &InstrumentDetails {
+                name: instrument.name.to_string(),
+                sector: instrument.sector.map(str::to_string),
+                asset_class: instrument.asset_class.to_string(),
+                tick_size: BigDecimal::new(1.into(), 2),
+                lot_size: 1,
+                active: true,
+            },
+        )
+        .await?;
+
+    let candles = simulate_candles(instrument, days, rng);
+    PriceCandleRepository::new(&state.pg_pool)
+        .backfill(instrument.ticker, CandleInterval::OneDay, &candles)
+        .await?;
+
+    if instrument.ticker == BENCHMARK {
+        let repository = BenchmarkPriceRepository::new(&state.pg_pool);
+        for (day, candle) in days.iter().zip(&candles) {
+            repository
+                .upsert_price(instrument.ticker, *day, candle.close.clone())
+                .await?;
+        }
+    }
+
+    if let Some(last) = candles.last().and_then(|c| c.close.to_f64()) {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        price_updater::store_price(state, &mut conn, instrument.ticker, last, Utc::now()).await?;
+    }
+
+    Ok(candles)
+}
+
+/// Daily candles of a geometric Brownian motion, one per day
+fn simulate_candles(
+    instrument: &DemoInstrument,
+    days: &[NaiveDate],
+    rng: &mut StdRng,
+) -> Vec<PriceCandle> {
+    let step_years = 1.0 / 365.25 / STEPS_PER_DAY as f64;
+    let drift = (DRIFT - instrument.volatility.powi(2) / 2.0) * step_years;
+    let shock = instrument.volatility * step_years.sqrt();
+
+    let mut price = instrument.start_price;
+    days.iter()
+        .map(|day| {
+            let open = price;
+            let (mut high, mut low) = (open, open);
+            for _ in 0..STEPS_PER_DAY {
+                let z: f64 = rng.sample(StandardNormal);
+                price *= (drift + shock * z).exp();
+                high = high.max(price);
+                low = low.min(price);
+            }
+            PriceCandle {
+                bucket_start: day.and_time(NaiveTime::MIN).and_utc(),
+                open: cents(open),
+                high: cents(high),
+                low: cents(low),
+                close: cents(price),
+                tick_count: STEPS_PER_DAY as i32,
+                volume: 0,
+            }
+        })
+        .collect()
+}
+
+/// Trades of a persona over `days` days, in order
+fn plan_trades(
+    persona: &Persona,
+    closes: &HashMap<&'static str, Vec<BigDecimal>>,
+    days: usize,
+    rng: &mut StdRng,
+) -> Vec<Trade> {
+    match persona.style {
+        Style::BuyAndHold(positions) => [0, days / 3, days * 2 / 3]
+            .into_iter()
+            .flat_map(|day| {
+                positions.iter().map(move |(ticker, quantity)| Trade {
+                    day,
+                    ticker,
+                    quantity: *quantity,
+                })
+            })
+            .collect(),
+        Style::Active(tickers) => {
+            let mut trades = Vec::new();
+            let mut cash = BigDecimal::from(persona.cash);
+            let mut held: BTreeMap<&'static str, i32> = BTreeMap::new();
+            let mut day = 0;
+            while day < days {
+                let ticker = tickers[rng.random_range(0..tickers.len())];
+                let price = &closes[ticker][day];
+                let shares = held.get(ticker).copied().unwrap_or(0);
+                if shares > 0 && rng.random_bool(0.4) {
+                    let quantity = rng.random_range(1..=shares);
+                    cash += price * quantity;
+                    held.insert(ticker, shares - quantity);
+                    trades.push(Trade {
+                        day,
+                        ticker,
+                        quantity: -quantity,
+                    });
+                } else {
+                    // Spend up to a tenth of the cash on each buy
+                    let budget: BigDecimal = &cash / (price * 10);
+                    let budget = budget.to_i32().unwrap_or(0);
+                    if budget > 0 {
+                        let quantity = rng.random_range(1..=budget);
+                        cash -= price * quantity;
+                        *held.entry(ticker).or_default() += quantity;
+                        trades.push(Trade {
+                            day,
+                            ticker,
+                            quantity,
+                        });
+                    }
+                }
+                day += rng.random_range(2..=6);
+            }
+            trades
+        }
+        Style::Cash => Vec::new(),
+    }
+}
+
+/// Create a persona's account, execute its trades and record its snapshots
+async fn seed_persona(
+    state: &AppState,
+    persona: &Persona,
+    trades: &[Trade],
+    closes: &HashMap<&'static str, Vec<BigDecimal>>,
+    days: &[NaiveDate],
+) -> Result<()> {
+    let users = &state.repos.users;
+    let user = users
+        .create_user(persona.email, &hash_password(DEMO_PASSWORD)?)
+        .await?;
+    if persona.role != Role::User {
+        users.set_role(user.id, persona.role.as_str()).await?;
+    }
+
+    let settings = UserSettingsRepository::new(&state.pg_pool);
+    settings
+        .set_display_name(user.id, Some(persona.display_name))
+        .await?;
+    settings
+        .set_cost_basis_method(user.id, persona.cost_basis_method)
+        .await?;
+    settings
+        .set_benchmark_ticker(user.id, Some(BENCHMARK))
+        .await?;
+
+    let portfolios = PortfolioRepository::new(&state.pg_pool);
+    let portfolio = portfolios
+        .create_portfolio(
+            user.id,
+            PORTFOLIO_NAME,
+            BigDecimal::from(persona.cash),
+            true,
+        )
+        .await?;
+
+    let transactions = &state.repos.transactions;
+    let holdings = &state.repos.holdings;
+    let snapshots = PortfolioSnapshotRepository::new(&state.pg_pool);
+    let mut cash = portfolio.balance;
+    let mut held: BTreeMap<&'static str, i32> = BTreeMap::new();
+    let mut trades = trades.iter().peekable();
+
+    for (index, day) in days.iter().enumerate() {
+        while let Some(trade) = trades.next_if(|trade| trade.day == index) {
+            let price = &closes[trade.ticker][index];
+            let cost = price * trade.quantity;
+            let shares = held.get(trade.ticker).copied().unwrap_or(0);
+            if cost > cash || shares + trade.quantity < 0 {
+                tracing::debug!("Skipping unaffordable demo trade of {}", trade.ticker);
+                continue;
+            }
+
+            let side = if trade.quantity > 0 { "buy" } else { "sell" };
+            let quantity = trade.quantity.abs();
+            let transaction = transactions
+                .create_transaction(
+                    user.id,
+                    portfolio.id,
+                    trade.ticker,
+                    quantity,
+                    price.clone(),
+                    side,
+                    BigDecimal::from(0),
+                )
+                .await?;
+            cash -= &cost;
+            let posting = TradePosting {
+                entry_type: side,
+                value: -cost,
+                fee: BigDecimal::from(0),
+            };
+
+            if trade.quantity > 0 {
+                positions::add_shares(
+                    state,
+                    user.id,
+                    portfolio.id,
+                    trade.ticker,
+                    quantity,
+                    price,
+                    transaction.id,
+                    posting,
+                )
+                .await?;
+            } else {
+                let holding = holdings
+                    .get_holding_by_portfolio_and_ticker(portfolio.id, trade.ticker)
+                    .await?
+                    .ok_or(Error::NotFound)?;
+                positions::remove_shares(state, holding, quantity, price, transaction.id, posting)
+                    .await?;
+            }
+            *held.entry(trade.ticker).or_default() += trade.quantity;
+
+            // Trades execute at the close, shortly before midnight UTC
+            let at = day.and_time(NaiveTime::MIN).and_utc() + TimeDelta::hours(21);
+            transactions.backdate(transaction.id, at).await?;
+        }
+
+        let market_value = held
+            .iter()
+            .fold(BigDecimal::from(0), |total, (ticker, quantity)| {
+                total + &closes[ticker][index] * *quantity
+            });
+        snapshots
+            .upsert_snapshot(
+                user.id,
+                *day,
+                cash.clone(),
+                market_value.clone(),
+                &cash + market_value,
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// `price` rounded to cents
+fn cents(price: f64) -> BigDecimal {
+    BigDecimal::from_f64(price)
+        .unwrap_or_default()
+        .with_scale_round(2, RoundingMode::HalfUp)
+}
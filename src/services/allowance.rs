@@ -0,0 +1,71 @@
+//! # Weekly Allowance
+//!
+//! With `WEEKLY_ALLOWANCE` set, every account that logged in within the last
+//! `ALLOWANCE_ACTIVE_DAYS` is credited that much virtual cash once per week
+//! (Monday to Sunday, UTC), into its default portfolio. The worker checks at
+//! startup and after every UTC midnight; payments are keyed by user and week,
+//! so an account that becomes active mid-week is paid at the next check and
+//! restarts or several instances never pay twice. Allowances count as
+//! deposits, so they do not inflate returns.
+
+use std::sync::Arc;
+
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode};
+use chrono::{Datelike, Days, NaiveDate, TimeDelta, Utc};
+
+use crate::{
+    AppState, Error, Result,
+    repository::allowance_repository::AllowanceRepository,
+    services::snapshots,
+    ws::{events, messages::AccountEvent},
+};
+
+/// Pay the week's allowance at startup and after every UTC midnight
+pub async fn allowance_worker(state: Arc<AppState>) -> Result<()> {
+    if state.config.weekly_allowance <= 0.0 {
+        return Ok(());
+    }
+
+    loop {
+        let today = Utc::now().date_naive();
+        match pay(&state, today).await {
+            Ok(count) if count > 0 => tracing::info!("Paid {} weekly allowances", count),
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to pay weekly allowances: {}", e),
+        }
+
+        tokio::time::sleep(snapshots::until_next_day()).await;
+    }
+}
+
+/// Credit the allowance of the week containing `today` to active accounts
+/// not paid for it yet, returning how many were paid
+pub async fn pay(state: &AppState, today: NaiveDate) -> Result<usize> {
+    let amount = BigDecimal::from_f64(state.config.weekly_allowance)
+        .ok_or(Error::InternalServerError)?
+        .with_scale_round(2, RoundingMode::HalfUp);
+    let active_since = Utc::now() - TimeDelta::days(i64::from(state.config.allowance_active_days));
+
+    let payments = AllowanceRepository::new(&state.pg_pool)
+        .pay_allowances(week_start(today), amount, active_since)
+        .await?;
+
+    let count = payments.len();
+    for payment in payments {
+        let event = AccountEvent::DepositSettled {
+            portfolio_id: payment.portfolio_id,
+            amount: payment.amount,
+            balance: payment.balance,
+        };
+        events::publish(state, payment.user_id, event).await;
+    }
+
+    Ok(count)
+}
+
+/// Monday of the week containing `date`
+fn week_start(date: NaiveDate) -> NaiveDate {
+    let since_monday = u64::from(date.weekday().num_days_from_monday());
+    date.checked_sub_days(Days::new(since_monday))
+        .unwrap_or(date)
+}
@@ -0,0 +1,58 @@
+//! # Transaction Archive
+//!
+//! With `TRANSACTION_ARCHIVE_AFTER_DAYS` set, transactions executed longer
+//! ago than that move out of the table trades are written to and into the
+//! archive, one partition per month of execution. The worker archives at
+//! startup and after every UTC midnight, in batches so no single statement
+//! holds locks on the history for long; admins can archive up to any date on
+//! demand.
+//!
+//! Archived transactions keep their IDs. `GET /transactions` lists the hot
+//! table and `GET /transactions/archived` the archive; realized gains, the
+//! ledger, candle volumes and order lookups read both.
+
+use std::sync::Arc;
+
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+
+use crate::{AppState, Result, services::snapshots};
+
+/// Transactions moved per statement
+const ARCHIVE_BATCH_SIZE: i32 = 1000;
+
+/// Archive transactions past the configured age at startup and after every
+/// UTC midnight
+pub async fn archive_worker(state: Arc<AppState>) -> Result<()> {
+    let after_days = state.config.transaction_archive_after_days;
+    if after_days == 0 {
+        return Ok(());
+    }
+
+    loop {
+        let cutoff = Utc::now().naive_utc() - TimeDelta::days(i64::from(after_days));
+        match archive_before(&state, cutoff).await {
+            Ok(count) if count > 0 => tracing::info!("Archived {} transactions", count),
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to archive transactions: {}", e),
+        }
+
+        tokio::time::sleep(snapshots::until_next_day()).await;
+    }
+}
+
+/// Move every transaction executed before `cutoff` to the archive, returning
+/// how many moved
+pub async fn archive_before(state: &AppState, cutoff: NaiveDateTime) -> Result<u64> {
+    let repository = &state.repos.transactions;
+    let mut archived = 0;
+
+    loop {
+        let moved = repository
+            .archive_before(cutoff, ARCHIVE_BATCH_SIZE)
+            .await?;
+        archived += moved as u64;
+        if moved < ARCHIVE_BATCH_SIZE {
+            return Ok(archived);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! # Balance
+//!
+//! Deposits and withdrawals of cash, shared by the balance endpoints and any
+//! other frontend. Amounts are checked here rather than by the caller, so
+//! every way in accepts the same amounts. Competition and class portfolios
+//! cannot move cash, and withdrawals redeem swept cash from the money market
+//! when the balance alone falls short.
+
+use bigdecimal::{BigDecimal, Zero};
+
+use crate::{
+    AppState, Error, Result,
+    models::{cash_movement::CashMovement, portfolio::Portfolio},
+    repository::{
+        cash_flow_repository::CashFlowRepository, cash_movement_repository::CashMovementRepository,
+        ledger_repository,
+    },
+    services::{classes, competitions, sweep},
+    ws::{events, messages::AccountEvent},
+};
+
+/// Largest amount deposited or withdrawn at once
+pub const MAX_AMOUNT: i64 = 1_000_000;
+/// Decimal places an amount may have
+pub const AMOUNT_SCALE: i64 = 2;
+
+/// `amount` at [`AMOUNT_SCALE`], if it is between 0.01 and [`MAX_AMOUNT`]
+/// with at most that many decimal places
+pub fn cash_amount(amount: &BigDecimal) -> Result<BigDecimal> {
+    if amount.normalized().fractional_digit_count() > AMOUNT_SCALE {
+        return Err(Error::BadRequest(format!(
+            "Amount must have at most {} decimal places",
+            AMOUNT_SCALE
+        )));
+    }
+    let max_amount = BigDecimal::from(MAX_AMOUNT);
+    if *amount <= BigDecimal::zero() || *amount > max_amount {
+        return Err(Error::BadRequest(format!(
+            "Amount must be between 0.01 and {}",
+            MAX_AMOUNT
+        )));
+    }
+
+    Ok(amount.with_scale(AMOUNT_SCALE))
+}
+
+/// Check that `available` cash covers a debit of `amount`
+pub fn check_funds(available: &BigDecimal, amount: &BigDecimal) -> Result<()> {
+    if available < amount {
+        return Err(ledger_repository::insufficient_funds());
+    }
+
+    Ok(())
+}
+
+/// Deposit `amount` into `portfolio`, returning the recorded movement
+pub async fn deposit(
+    state: &AppState,
+    portfolio: &Portfolio,
+    amount: &BigDecimal,
+) -> Result<CashMovement> {
+    let amount = cash_amount(amount)?;
+    competitions::check_cash_movement(portfolio)?;
+    classes::check_cash_movement(portfolio)?;
+
+    let movement = CashMovementRepository::new(&state.pg_pool)
+        .record(portfolio.id, "deposit", amount.clone())
+        .await?;
+    CashFlowRepository::new(&state.pg_pool)
+        .record_flow(portfolio.user_id, amount.clone())
+        .await?;
+    let event = AccountEvent::DepositSettled {
+        portfolio_id: portfolio.id,
+        amount,
+        balance: movement.balance_after.clone(),
+    };
+    events::publish(state, portfolio.user_id, event).await;
+
+    Ok(movement)
+}
+
+/// Withdraw `amount` from `portfolio`, returning the recorded movement
+pub async fn withdraw(
+    state: &AppState,
+    portfolio: &Portfolio,
+    amount: &BigDecimal,
+) -> Result<CashMovement> {
+    let amount = cash_amount(amount)?;
+    competitions::check_cash_movement(portfolio)?;
+    classes::check_cash_movement(portfolio)?;
+
+    let balance = sweep::sweep_out(state, portfolio, &amount).await?;
+    check_funds(&balance, &amount)?;
+    let movement = CashMovementRepository::new(&state.pg_pool)
+        .record(portfolio.id, "withdrawal", amount.clone())
+        .await?;
+    CashFlowRepository::new(&state.pg_pool)
+        .record_flow(portfolio.user_id, -amount)
+        .await?;
+
+    Ok(movement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(text: &str) -> Result<BigDecimal> {
+        cash_amount(&text.parse().unwrap())
+    }
+
+    #[test]
+    fn amounts_are_kept_to_cents() {
+        assert_eq!(amount("12.5").unwrap().to_string(), "12.50");
+        assert_eq!(amount("0.01").unwrap().to_string(), "0.01");
+        assert_eq!(amount("1000000").unwrap().to_string(), "1000000.00");
+        // Trailing zeros do not count as decimal places
+        assert_eq!(amount("3.1000").unwrap().to_string(), "3.10");
+
+        for rejected in ["0", "-5", "0.001", "1000000.01"] {
+            assert!(
+                matches!(amount(rejected), Err(Error::BadRequest(_))),
+                "{rejected}"
+            );
+        }
+    }
+
+    #[test]
+    fn debits_need_covering_cash() {
+        let available = BigDecimal::from(100);
+        assert!(check_funds(&available, &BigDecimal::from(100)).is_ok());
+        assert!(check_funds(&available, &"100.01".parse().unwrap()).is_err());
+    }
+}
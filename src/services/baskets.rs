@@ -0,0 +1,257 @@
+//! # Baskets
+//!
+//! Index and ETF instruments may be priced from a weighted basket of
+//! constituent tickers instead of by the price feed. A basket's price is the
+//! sum of its constituents' latest prices times their weights, so a weight is
+//! the number of units of the constituent in one unit of the basket.
+//!
+//! The basket worker listens on the price channel of every ticker and,
+//! whenever a constituent ticks, reprices the baskets holding it and stores
+//! the price like a feed price: cached, published to subscribers, streamed
+//! as a domain event and folded into candles. A basket is only priced once
+//! every constituent has a price, and baskets cannot hold other baskets.
+//! The price updater leaves baskets out of the tickers it streams from the
+//! provider.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use bigdecimal::{BigDecimal, RoundingMode, ToPrimitive};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use redis::AsyncCommands;
+
+use crate::{
+    AppState, Error, Result,
+    models::basket::BasketConstituent,
+    repository::{
+        basket_repository::BasketRepository, instrument_repository::InstrumentRepository,
+    },
+    services::{
+        instruments,
+        price_cache::PriceCache,
+        price_updater::{self, PRICE_CHANNEL_PATTERN, PriceMessage},
+        replay,
+    },
+};
+
+/// Asset classes that may be priced from a basket
+pub const BASKET_CLASSES: [&str; 2] = ["etf", instruments::INDEX];
+/// Delay before resubscribing after the pub/sub connection drops
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+/// Decimal places of basket prices
+const PRICE_SCALE: i64 = 4;
+
+/// Constituents of every basket, indexed both ways
+#[derive(Debug, Default)]
+struct Baskets {
+    constituents: HashMap<String, Vec<(String, BigDecimal)>>,
+    /// Baskets holding each constituent
+    holders: HashMap<String, Vec<String>>,
+}
+
+impl Baskets {
+    async fn load(state: &AppState) -> Result<Self> {
+        let mut baskets = Baskets::default();
+        for constituent in BasketRepository::new(&state.pg_pool).get_all().await? {
+            baskets
+                .holders
+                .entry(constituent.ticker.clone())
+                .or_default()
+                .push(constituent.basket.clone());
+            baskets
+                .constituents
+                .entry(constituent.basket)
+                .or_default()
+                .push((constituent.ticker, constituent.weight));
+        }
+
+        Ok(baskets)
+    }
+}
+
+/// Replace the constituents of the index or ETF `basket`
+///
+/// Constituents must be listed, distinct and not baskets themselves, and a
+/// basket cannot be a constituent of another one.
+pub async fn set_basket(
+    state: &AppState,
+    basket: &str,
+    constituents: Vec<(String, BigDecimal)>,
+) -> Result<Vec<BasketConstituent>> {
+    let instrument_repository = InstrumentRepository::new(&state.pg_pool);
+    let basket_repository = BasketRepository::new(&state.pg_pool);
+
+    let instrument = instrument_repository
+        .get_instrument(basket)
+        .await?
+        .ok_or(Error::NotFound)?;
+    if !BASKET_CLASSES.contains(&instrument.asset_class.as_str()) {
+        return Err(Error::BadRequest(format!(
+            "{} is not an index or ETF",
+            basket
+        )));
+    }
+    if basket_repository.is_constituent(basket).await? {
+        return Err(Error::BadRequest(format!(
+            "{} is held in another basket",
+            basket
+        )));
+    }
+
+    let mut seen = Vec::with_capacity(constituents.len());
+    for (ticker, _) in &constituents {
+        if ticker == basket || seen.contains(ticker) {
+            return Err(Error::BadRequest(format!(
+                "{} is listed more than once",
+                ticker
+            )));
+        }
+        if instrument_repository
+            .get_instrument(ticker)
+            .await?
+            .is_none()
+        {
+            return Err(Error::BadRequest(format!("Unknown ticker {}", ticker)));
+        }
+        if !basket_repository.get_constituents(ticker).await?.is_empty() {
+            return Err(Error::BadRequest(format!(
+                "{} is a basket and cannot be a constituent",
+                ticker
+            )));
+        }
+        seen.push(ticker.clone());
+    }
+
+    let replaced = basket_repository
+        .replace_basket(basket, &constituents)
+        .await?;
+    // Stops the feed for the basket and reloads the basket workers
+    instruments::announce_catalog_change(state).await;
+
+    Ok(replaced)
+}
+
+/// Price of `basket` at the cached prices of its constituents, `None` until
+/// every constituent has one
+pub async fn basket_price(
+    state: &AppState,
+    constituents: &[(String, BigDecimal)],
+) -> Result<Option<BigDecimal>> {
+    let tickers: Vec<String> = constituents
+        .iter()
+        .map(|(ticker, _)| ticker.clone())
+        .collect();
+    let prices = PriceCache::new(&state.redis_pool).prices(&tickers).await?;
+
+    let mut value = BigDecimal::from(0);
+    for (ticker, weight) in constituents {
+        let Some(price) = prices.get(ticker) else {
+            return Ok(None);
+        };
+        value += price * weight;
+    }
+
+    Ok(Some(
+        value.with_scale_round(PRICE_SCALE, RoundingMode::HalfUp),
+    ))
+}
+
+/// Reprice baskets whenever one of their constituents ticks, resubscribing
+/// whenever the pub/sub connection drops
+pub async fn basket_worker(state: Arc<AppState>) -> Result<()> {
+    loop {
+        if let Err(e) = price_baskets(&state).await {
+            tracing::error!("Basket pricing stopped: {}", e);
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn price_baskets(state: &AppState) -> Result<()> {
+    let client = redis::Client::open(state.config.redis_url.as_str())
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let mut pubsub = client
+        .get_async_pubsub()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    pubsub
+        .psubscribe(PRICE_CHANNEL_PATTERN)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    pubsub
+        .subscribe(instruments::CATALOG_CHANNEL)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    let mut baskets = Baskets::load(state).await?;
+    reprice_all(state, &baskets).await;
+
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        if message.get_channel_name() == instruments::CATALOG_CHANNEL {
+            baskets = Baskets::load(state).await?;
+            reprice_all(state, &baskets).await;
+            continue;
+        }
+
+        let Ok(payload) = message.get_payload::<String>() else {
+            continue;
+        };
+        let Ok(tick) = serde_json::from_str::<PriceMessage>(&payload) else {
+            continue;
+        };
+        let Some(holders) = baskets.holders.get(&tick.ticker) else {
+            continue;
+        };
+        for basket in holders {
+            if let Err(e) = reprice(state, &baskets, basket, tick.timestamp).await {
+                tracing::warn!("Failed to price basket {}: {}", basket, e);
+            }
+        }
+    }
+
+    Err(Error::RedisError("Price subscription closed".into()))
+}
+
+/// Price every basket whose constituents all have a price
+async fn reprice_all(state: &AppState, baskets: &Baskets) {
+    let now = Utc::now();
+    for basket in baskets.constituents.keys() {
+        if let Err(e) = reprice(state, baskets, basket, now).await {
+            tracing::warn!("Failed to price basket {}: {}", basket, e);
+        }
+    }
+}
+
+/// Store the price of `basket` as of `at`, unless a replay drives it
+async fn reprice(
+    state: &AppState,
+    baskets: &Baskets,
+    basket: &str,
+    at: DateTime<Utc>,
+) -> Result<()> {
+    let Some(constituents) = baskets.constituents.get(basket) else {
+        return Ok(());
+    };
+    let Some(price) = basket_price(state, constituents)
+        .await?
+        .and_then(|price| price.to_f64())
+    else {
+        return Ok(());
+    };
+
+    let mut conn = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let replaying: bool = conn
+        .exists(replay::replay_key(basket))
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    if replaying {
+        return Ok(());
+    }
+
+    price_updater::store_price(state, &mut conn, basket, price, at).await
+}
@@ -0,0 +1,276 @@
+//! # Bonds
+//!
+//! Instruments of the `bond` asset class carry terms set by admins: a face
+//! value, an annual coupon rate paid 1, 2, 4 or 12 times a year, and a
+//! maturity date. Coupon dates step back from the maturity date, so the last
+//! coupon is paid together with the face value. One unit of the ticker is one
+//! bond, and the price feed quotes its clean price, without accrued interest.
+//!
+//! Valuations add the interest accrued since the last coupon date to bond
+//! positions, pro rata over the days of the coupon period. At the start of
+//! every coupon date the worker credits the coupon of every bond held as cash
+//! to the portfolio holding it, as a `coupon` transaction. On the maturity
+//! date positions are redeemed at face value as `redemption` transactions,
+//! realizing gains against their tax lots like a sale, and the bond is
+//! delisted; proceeds of bonds pledged to a loan repay the loan first.
+//!
+//! Coupons follow the calendar, in competition portfolios as well.
+
+use std::{collections::HashMap, sync::Arc};
+
+use bigdecimal::{BigDecimal, RoundingMode, Zero};
+use chrono::{Months, NaiveDate, Utc};
+
+use crate::{
+    AppState, Result,
+    models::{bond::Bond, holding::Holding},
+    repository::{
+        bond_repository::BondRepository,
+        ledger_repository::{LedgerRepository, TradePosting},
+        loan_repository::LoanRepository,
+    },
+    services::{positions, snapshots},
+};
+
+/// Coupons a year a bond may pay
+pub const COUPON_FREQUENCIES: [i32; 4] = [1, 2, 4, 12];
+
+/// Pay due coupons and redeem matured bonds at startup and after every UTC
+/// midnight
+pub async fn bond_worker(state: Arc<AppState>) -> Result<()> {
+    loop {
+        let today = Utc::now().date_naive();
+        if let Err(e) = process(&state, today).await {
+            tracing::error!("Failed to process bonds: {}", e);
+        }
+
+        tokio::time::sleep(snapshots::until_next_day()).await;
+    }
+}
+
+/// Pay every coupon due on or before `today`, redeeming bonds that matured
+pub async fn process(state: &AppState, today: NaiveDate) -> Result<()> {
+    let bonds = BondRepository::new(&state.pg_pool).get_due(today).await?;
+
+    for bond in bonds {
+        let ticker = bond.ticker.clone();
+        if let Err(e) = settle(state, bond, today).await {
+            tracing::error!("Failed to pay coupon of bond {}: {}", ticker, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Coupon dates of `maturity_date`'s schedule, latest first
+fn coupon_dates(
+    maturity_date: NaiveDate,
+    coupons_per_year: i32,
+) -> impl Iterator<Item = NaiveDate> {
+    let step = (12 / coupons_per_year.clamp(1, 12)) as u32;
+    (0..).map_while(move |period| maturity_date.checked_sub_months(Months::new(step * period)))
+}
+
+/// Earliest coupon date after `date`, or the maturity date once past it
+pub fn next_coupon_after(
+    maturity_date: NaiveDate,
+    coupons_per_year: i32,
+    date: NaiveDate,
+) -> NaiveDate {
+    coupon_dates(maturity_date, coupons_per_year)
+        .take_while(|coupon| *coupon > date)
+        .last()
+        .unwrap_or(maturity_date)
+}
+
+/// Coupon paid per bond, rounded to cents
+pub fn coupon_per_bond(bond: &Bond) -> BigDecimal {
+    (&bond.face_value * &bond.coupon_rate_percent / BigDecimal::from(100 * bond.coupons_per_year))
+        .with_scale_round(2, RoundingMode::HalfUp)
+}
+
+/// Interest accrued per bond since the last coupon date
+pub fn accrued_interest(bond: &Bond, today: NaiveDate) -> BigDecimal {
+    let next = bond.next_coupon_date;
+    let Some(previous) =
+        coupon_dates(bond.maturity_date, bond.coupons_per_year).find(|coupon| *coupon < next)
+    else {
+        return BigDecimal::from(0);
+    };
+    let period = (next - previous).num_days().max(1);
+    let elapsed = (today - previous).num_days().clamp(0, period);
+
+    (coupon_per_bond(bond) * BigDecimal::from(elapsed) / BigDecimal::from(period))
+        .with_scale_round(4, RoundingMode::HalfUp)
+}
+
+/// Interest accrued per bond today for the outstanding bonds among `tickers`
+pub async fn accrued_interest_by_ticker(
+    state: &AppState,
+    tickers: &[String],
+) -> Result<HashMap<String, BigDecimal>> {
+    if tickers.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let today = Utc::now().date_naive();
+    let bonds = BondRepository::new(&state.pg_pool)
+        .get_outstanding(tickers)
+        .await?;
+
+    Ok(bonds
+        .into_iter()
+        .map(|bond| {
+            let accrued = accrued_interest(&bond, today);
+            (bond.ticker, accrued)
+        })
+        .collect())
+}
+
+/// Pay the coupons of `bond` due by `today` one date at a time, redeeming it
+/// at maturity
+async fn settle(state: &AppState, mut bond: Bond, today: NaiveDate) -> Result<()> {
+    let repository = BondRepository::new(&state.pg_pool);
+
+    while bond.next_coupon_date <= today {
+        let due = bond.next_coupon_date;
+        if due >= bond.maturity_date {
+            // Claimed before paying; a failure here needs manual follow-up
+            if repository.claim_redemption(&bond.ticker).await? {
+                pay_coupon(state, &bond).await?;
+                redeem(state, &bond).await?;
+                tracing::info!("Redeemed bond {}", bond.ticker);
+            }
+            return Ok(());
+        }
+
+        let next = next_coupon_after(bond.maturity_date, bond.coupons_per_year, due);
+        if !repository.claim_coupon(&bond.ticker, due, next).await? {
+            return Ok(());
+        }
+        pay_coupon(state, &bond).await?;
+        tracing::info!("Paid {} coupon of bond {}", due, bond.ticker);
+        bond.next_coupon_date = next;
+    }
+
+    Ok(())
+}
+
+/// Credit one coupon to every portfolio holding `bond`
+async fn pay_coupon(state: &AppState, bond: &Bond) -> Result<()> {
+    let coupon = coupon_per_bond(bond);
+    if coupon <= BigDecimal::zero() {
+        return Ok(());
+    }
+
+    for holding in held(state, &bond.ticker).await? {
+        let transaction = state
+            .repos
+            .transactions
+            .create_transaction(
+                holding.user_id,
+                holding.portfolio_id,
+                &bond.ticker,
+                holding.quantity,
+                coupon.clone(),
+                "coupon",
+                BigDecimal::from(0),
+            )
+            .await?;
+        LedgerRepository::new(&state.pg_pool)
+            .post(
+                holding.portfolio_id,
+                "coupon",
+                &coupon * BigDecimal::from(holding.quantity),
+                Some(transaction.id),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Repay every position in `bond` at face value
+async fn redeem(state: &AppState, bond: &Bond) -> Result<()> {
+    let loan_repository = LoanRepository::new(&state.pg_pool);
+    let ledger_repository = LedgerRepository::new(&state.pg_pool);
+    let face_value = bond.face_value.with_scale_round(2, RoundingMode::HalfUp);
+
+    for holding in held(state, &bond.ticker).await? {
+        let transaction = state
+            .repos
+            .transactions
+            .create_transaction(
+                holding.user_id,
+                holding.portfolio_id,
+                &bond.ticker,
+                holding.quantity,
+                face_value.clone(),
+                "redemption",
+                BigDecimal::from(0),
+            )
+            .await?;
+        let portfolio_id = holding.portfolio_id;
+        let quantity = holding.quantity;
+        let posting = TradePosting {
+            entry_type: "redemption",
+            value: &face_value * BigDecimal::from(quantity),
+            fee: BigDecimal::from(0),
+        };
+        positions::remove_shares(
+            state,
+            holding,
+            quantity,
+            &face_value,
+            transaction.id,
+            posting,
+        )
+        .await?;
+
+        // Bonds pledged to open loans repay those loans out of their proceeds
+        let loans: Vec<_> = loan_repository
+            .get_loans_by_portfolio(portfolio_id)
+            .await?
+            .into_iter()
+            .filter(|loan| loan.status == "open")
+            .collect();
+        let loan_ids: Vec<i32> = loans.iter().map(|loan| loan.id).collect();
+        for pledge in loan_repository.get_collateral(&loan_ids).await? {
+            if pledge.ticker != bond.ticker || pledge.quantity <= 0 {
+                continue;
+            }
+            loan_repository
+                .reduce_collateral(pledge.loan_id, &pledge.ticker, pledge.quantity)
+                .await?;
+            let Some(loan) = loans.iter().find(|loan| loan.id == pledge.loan_id) else {
+                continue;
+            };
+            let pledged_value = &face_value * BigDecimal::from(pledge.quantity);
+            let repayment = pledged_value.min(loan.outstanding.clone());
+            if repayment > BigDecimal::zero()
+                && loan_repository
+                    .repay(loan.id, repayment.clone())
+                    .await?
+                    .is_some()
+            {
+                ledger_repository
+                    .post(portfolio_id, "loan", -repayment, Some(transaction.id))
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Positions in `ticker` with bonds left
+async fn held(state: &AppState, ticker: &str) -> Result<Vec<Holding>> {
+    Ok(state
+        .repos
+        .holdings
+        .get_holdings_by_ticker(ticker)
+        .await?
+        .into_iter()
+        .filter(|holding| holding.quantity > 0)
+        .collect())
+}
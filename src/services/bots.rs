@@ -0,0 +1,396 @@
+//! # Bot Traders
+//!
+//! Server-managed bot accounts trade on their own, so a fresh deployment has
+//! market activity, a populated leaderboard and realistic transaction
+//! volume. `BOT_COUNT` bots are created at startup, each with a regular
+//! account named after its strategy, and admins can add, pause and resume
+//! bots.
+//!
+//! Every instance listens to the price stream of every listed ticker and
+//! keeps a short window of recent prices. Once per trading round one
+//! instance wins the round and lets each active bot's strategy pick at most
+//! one order from those windows. Orders go through the same checks and
+//! execution as user orders: buys spend a fraction of the bot's cash in
+//! whole lots, sells close the whole position.
+//!
+//! Strategies are pluggable through [`Strategy`]:
+//!
+//! - `momentum` buys tickers that rose over the window and sells held
+//!   tickers that fell
+//! - `mean_reversion` buys tickers trading below their window average and
+//!   sells held tickers trading above it
+//! - `random` now and then buys or sells something at random
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode, ToPrimitive};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use redis::{AsyncCommands, ExistenceCheck, SetExpiry, SetOptions};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+use uuid::Uuid;
+
+use crate::{
+    AppState, Error, Result,
+    auth::password::hash_password,
+    models::{
+        bot::{Bot, BotStrategy},
+        portfolio::DEFAULT_PORTFOLIO_NAME,
+    },
+    repository::{
+        bot_repository::BotRepository, instrument_repository::InstrumentRepository,
+        portfolio_repository::PortfolioRepository,
+    },
+    services::{price_updater::PriceMessage, trading},
+};
+
+/// Recent prices kept per ticker
+const PRICE_WINDOW: usize = 20;
+/// Relative move over the window, or away from its average, that makes a signal
+const SIGNAL_THRESHOLD: f64 = 0.01;
+/// Share of its cash a bot spends on one buy
+const ORDER_CASH_FRACTION: f64 = 0.1;
+/// Chance that a random bot trades in a round
+const RANDOM_TRADE_PROBABILITY: f64 = 0.3;
+/// Largest order a bot places, the same as for users
+const MAX_ORDER_QUANTITY: i32 = 10_000;
+/// Bot emails use a reserved domain, so no one can receive their mail
+const BOT_EMAIL_DOMAIN: &str = "bots.invalid";
+
+/// An order picked by a strategy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Order {
+    Buy(String),
+    Sell(String),
+}
+
+/// Recent prices of every ticker, oldest first
+#[derive(Debug, Default)]
+pub struct PriceHistory {
+    windows: HashMap<String, VecDeque<f64>>,
+}
+
+impl PriceHistory {
+    pub fn record(&mut self, ticker: &str, price: f64) {
+        let window = self.windows.entry(ticker.to_string()).or_default();
+        if window.len() == PRICE_WINDOW {
+            window.pop_front();
+        }
+        window.push_back(price);
+    }
+
+    /// The window of `ticker`, once it is full
+    pub fn window(&self, ticker: &str) -> Option<&VecDeque<f64>> {
+        self.windows
+            .get(ticker)
+            .filter(|window| window.len() == PRICE_WINDOW)
+    }
+
+    pub fn latest(&self, ticker: &str) -> Option<f64> {
+        self.windows.get(ticker)?.back().copied()
+    }
+
+    pub fn tickers(&self) -> impl Iterator<Item = &str> {
+        self.windows.keys().map(String::as_str)
+    }
+
+    fn forget(&mut self, ticker: &str) {
+        self.windows.remove(ticker);
+    }
+}
+
+/// A way of picking trades
+pub trait Strategy: Send + Sync {
+    /// Pick at most one order from recent prices and the tickers the bot holds
+    fn decide(&self, prices: &PriceHistory, held: &[String], rng: &mut StdRng) -> Option<Order>;
+}
+
+/// The strategy of a kind
+pub fn strategy(kind: BotStrategy) -> Box<dyn Strategy> {
+    match kind {
+        BotStrategy::Momentum => Box::new(Momentum),
+        BotStrategy::MeanReversion => Box::new(MeanReversion),
+        BotStrategy::Random => Box::new(RandomTrader),
+    }
+}
+
+/// Follows the trend over the window
+struct Momentum;
+
+impl Strategy for Momentum {
+    fn decide(&self, prices: &PriceHistory, held: &[String], rng: &mut StdRng) -> Option<Order> {
+        pick_signal(prices, held, rng, |window| {
+            let first = window.front()?;
+            let last = window.back()?;
+            Some(last / first - 1.0)
+        })
+    }
+}
+
+/// Bets on prices returning to their window average
+struct MeanReversion;
+
+impl Strategy for MeanReversion {
+    fn decide(&self, prices: &PriceHistory, held: &[String], rng: &mut StdRng) -> Option<Order> {
+        pick_signal(prices, held, rng, |window| {
+            let average = window.iter().sum::<f64>() / window.len() as f64;
+            let last = window.back()?;
+            Some(average / last - 1.0)
+        })
+    }
+}
+
+/// Trades now and then, ignoring prices
+struct RandomTrader;
+
+impl Strategy for RandomTrader {
+    fn decide(&self, prices: &PriceHistory, held: &[String], rng: &mut StdRng) -> Option<Order> {
+        if !rng.random_bool(RANDOM_TRADE_PROBABILITY) {
+            return None;
+        }
+
+        if !held.is_empty() && rng.random_bool(0.5) {
+            return held.choose(rng).cloned().map(Order::Sell);
+        }
+        let tickers: Vec<&str> = prices.tickers().collect();
+        tickers
+            .choose(rng)
+            .map(|ticker| Order::Buy(ticker.to_string()))
+    }
+}
+
+/// A random order among the tickers whose `score` passes the threshold:
+/// buys above it and sells of held tickers below its negative
+fn pick_signal(
+    prices: &PriceHistory,
+    held: &[String],
+    rng: &mut StdRng,
+    score: impl Fn(&VecDeque<f64>) -> Option<f64>,
+) -> Option<Order> {
+    let mut orders = Vec::new();
+    for ticker in prices.tickers() {
+        let Some(score) = prices.window(ticker).and_then(&score) else {
+            continue;
+        };
+        if score > SIGNAL_THRESHOLD {
+            orders.push(Order::Buy(ticker.to_string()));
+        } else if score < -SIGNAL_THRESHOLD && held.iter().any(|t| t == ticker) {
+            orders.push(Order::Sell(ticker.to_string()));
+        }
+    }
+
+    orders.choose(rng).cloned()
+}
+
+/// Create the configured bots, then run a trading round every
+/// `BOT_TRADE_INTERVAL_SECS`
+pub async fn bot_worker(state: Arc<AppState>) -> Result<()> {
+    if let Err(e) = provision(&state).await {
+        tracing::error!("Failed to create bot accounts: {}", e);
+    }
+
+    let interval_secs = state.config.bot_trade_interval_secs;
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut receivers = HashMap::new();
+    let mut prices = PriceHistory::default();
+    let mut rng = StdRng::from_os_rng();
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = follow_prices(&state, &mut receivers, &mut prices).await {
+            tracing::error!("Failed to follow prices for bots: {}", e);
+            continue;
+        }
+        match claim_round(&state, interval_secs).await {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                tracing::error!("Failed to claim bot trading round: {}", e);
+                continue;
+            }
+        }
+        if let Err(e) = trade_round(&state, &prices, &mut rng).await {
+            tracing::error!("Bot trading round failed: {}", e);
+        }
+    }
+}
+
+/// Create a bot with `strategy`, funded like a new account
+pub async fn add_bot(state: &AppState, strategy: BotStrategy) -> Result<Bot> {
+    let email = format!("bot-{}@{}", Uuid::new_v4().simple(), BOT_EMAIL_DOMAIN);
+    create_bot(state, &email, strategy)
+        .await?
+        .ok_or(Error::InternalServerError)
+}
+
+/// Create bots until there are `BOT_COUNT`, taking turns between strategies
+///
+/// Configured bots have numbered emails, so instances starting together
+/// do not create them twice.
+async fn provision(state: &AppState) -> Result<()> {
+    let existing = BotRepository::new(&state.pg_pool).get_bots().await?.len() as u32;
+    for n in existing + 1..=state.config.bot_count {
+        let strategy = BotStrategy::ALL[(n as usize - 1) % BotStrategy::ALL.len()];
+        let email = format!("bot-{}@{}", n, BOT_EMAIL_DOMAIN);
+        if let Some(bot) = create_bot(state, &email, strategy).await? {
+            tracing::info!("Created bot {} trading {}", bot.id, strategy.as_str());
+        }
+    }
+
+    Ok(())
+}
+
+async fn create_bot(state: &AppState, email: &str, strategy: BotStrategy) -> Result<Option<Bot>> {
+    // Nobody knows the password, so bots cannot be logged into
+    let password = hash_password(&Uuid::new_v4().to_string())?;
+    let starting_balance = BigDecimal::from_f64(state.config.starting_balance)
+        .ok_or(Error::InternalServerError)?
+        .with_scale_round(2, RoundingMode::HalfUp);
+
+    BotRepository::new(&state.pg_pool)
+        .create_bot(
+            email,
+            &password,
+            strategy,
+            DEFAULT_PORTFOLIO_NAME,
+            starting_balance,
+        )
+        .await
+}
+
+/// Subscribe to the prices of newly listed tickers and record every update
+/// received since the last round
+async fn follow_prices(
+    state: &AppState,
+    receivers: &mut HashMap<String, broadcast::Receiver<Arc<PriceMessage>>>,
+    prices: &mut PriceHistory,
+) -> Result<()> {
+    let tickers = InstrumentRepository::new(&state.pg_pool)
+        .get_tickers()
+        .await?;
+    receivers.retain(|ticker, _| tickers.contains(ticker));
+    let delisted: Vec<String> = prices
+        .tickers()
+        .filter(|ticker| !receivers.contains_key(*ticker))
+        .map(str::to_string)
+        .collect();
+    for ticker in delisted {
+        prices.forget(&ticker);
+    }
+    for ticker in tickers {
+        receivers
+            .entry(ticker)
+            .or_insert_with_key(|ticker| state.price_fanout.subscribe(ticker));
+    }
+
+    for receiver in receivers.values_mut() {
+        loop {
+            match receiver.try_recv() {
+                Ok(message) => prices.record(&message.ticker, message.price),
+                // Skipped updates only thin out the window
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Let every active bot place at most one order
+async fn trade_round(state: &AppState, prices: &PriceHistory, rng: &mut StdRng) -> Result<()> {
+    let bots = BotRepository::new(&state.pg_pool).get_bots().await?;
+    for bot in bots.into_iter().filter(|bot| bot.active) {
+        let kind: BotStrategy = match bot.strategy.parse() {
+            Ok(kind) => kind,
+            Err(e) => {
+                tracing::warn!("Skipping bot {}: {}", bot.id, e);
+                continue;
+            }
+        };
+        // Rejected orders, e.g. on halted tickers, are normal market life
+        if let Err(e) = trade(state, &bot, kind, prices, rng).await {
+            tracing::debug!("Bot {} did not trade: {}", bot.id, e);
+        }
+    }
+
+    Ok(())
+}
+
+async fn trade(
+    state: &AppState,
+    bot: &Bot,
+    kind: BotStrategy,
+    prices: &PriceHistory,
+    rng: &mut StdRng,
+) -> Result<()> {
+    let portfolio = PortfolioRepository::new(&state.pg_pool)
+        .get_default_portfolio(bot.user_id)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let holdings = state
+        .repos
+        .holdings
+        .get_holdings_by_portfolio(portfolio.id)
+        .await?;
+    let held: Vec<String> = holdings
+        .iter()
+        .filter(|holding| holding.quantity > 0)
+        .map(|holding| holding.ticker.clone())
+        .collect();
+
+    match strategy(kind).decide(prices, &held, rng) {
+        Some(Order::Buy(ticker)) => {
+            let Some(price) = prices.latest(&ticker) else {
+                return Ok(());
+            };
+            let lot_size = InstrumentRepository::new(&state.pg_pool)
+                .get_instrument(&ticker)
+                .await?
+                .ok_or(Error::NotFound)?
+                .lot_size
+                .max(1);
+            let budget = portfolio.balance.to_f64().unwrap_or(0.0) * ORDER_CASH_FRACTION;
+            let shares = ((budget / price) as i32).min(MAX_ORDER_QUANTITY);
+            let quantity = shares - shares % lot_size;
+            if quantity > 0 {
+                trading::buy(state, &portfolio, &ticker, quantity).await?;
+            }
+        }
+        Some(Order::Sell(ticker)) => {
+            let quantity = holdings
+                .iter()
+                .find(|holding| holding.ticker == ticker)
+                .map_or(0, |holding| holding.quantity.min(MAX_ORDER_QUANTITY));
+            if quantity > 0 {
+                trading::sell(state, &portfolio, &ticker, quantity).await?;
+            }
+        }
+        None => {}
+    }
+
+    Ok(())
+}
+
+/// Claim the current trading round, returning false if another instance did
+async fn claim_round(state: &AppState, interval_secs: u64) -> Result<bool> {
+    let options = SetOptions::default()
+        .conditional_set(ExistenceCheck::NX)
+        .with_expiration(SetExpiry::EX(interval_secs - 1));
+
+    let claimed: Option<String> = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?
+        .set_options("bots:round", 1, options)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    Ok(claimed.is_some())
+}
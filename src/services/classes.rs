@@ -0,0 +1,68 @@
+//! # Classes
+//!
+//! Instructors run classes for their students. Any user can create a class
+//! and becomes its instructor; students join with the class's invite code,
+//! which the instructor shares and can replace to stop further joins.
+//! Joining gives the student a portfolio of its own, named after the class
+//! and funded with the class's starting cash. Like competition portfolios,
+//! class portfolios are isolated from the rest of the account: cash cannot
+//! be moved in or out of them, the money market does not fund their trades
+//! and they are left out of the account's equity.
+//!
+//! A class may restrict the tickers its students buy. Selling is always
+//! allowed, so students are never stuck in a position the instructor has
+//! taken off the list. Instructors see every student's portfolio on the
+//! class dashboard.
+
+use uuid::Uuid;
+
+use crate::{
+    AppState, Error, Result, models::portfolio::Portfolio,
+    repository::class_repository::ClassRepository, services::liquidity::Side,
+};
+
+/// Characters in an invite code
+const INVITE_CODE_LENGTH: usize = 10;
+
+/// A fresh random invite code
+pub fn generate_invite_code() -> String {
+    Uuid::new_v4().simple().to_string()[..INVITE_CODE_LENGTH].to_uppercase()
+}
+
+/// Reject buys of tickers a class portfolio's class does not allow
+pub async fn check_trading(
+    state: &AppState,
+    portfolio: &Portfolio,
+    ticker: &str,
+    side: Side,
+) -> Result<()> {
+    let Some(class_id) = portfolio.class_id else {
+        return Ok(());
+    };
+    if side == Side::Sell {
+        return Ok(());
+    }
+
+    let class = ClassRepository::new(&state.pg_pool)
+        .get_class(class_id)
+        .await?
+        .ok_or(Error::NotFound)?;
+    match class.allowed_tickers {
+        Some(allowed) if !allowed.iter().any(|t| t == ticker) => Err(Error::BadRequest(format!(
+            "{} is not allowed in this class",
+            ticker
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Reject moving cash in or out of a class portfolio
+pub fn check_cash_movement(portfolio: &Portfolio) -> Result<()> {
+    if portfolio.class_id.is_some() {
+        return Err(Error::BadRequest(
+            "Cash cannot be moved in or out of a class portfolio".into(),
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,63 @@
+//! # Simulation Clock
+//!
+//! Time-based rules ask a [`SimClock`] for the current time instead of
+//! reading the wall clock, so a game session can run faster than real time.
+//! A clock maps real time onto simulated time: from its origin on, every
+//! real second advances it by `speed` simulated seconds. The wall clock is
+//! the clock with speed 1.
+//!
+//! Competitions run on a clock starting at `starts_at`; with a
+//! `clock_speed` of 24 a competition covers one trading day per real hour,
+//! and dividends reach its entrants' portfolios on their simulated ex- and
+//! pay dates. Everything else follows the wall clock.
+
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+
+use crate::models::competition::Competition;
+
+/// Maps real time onto simulated time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimClock {
+    /// Real time at which simulated and real time coincide
+    origin: DateTime<Utc>,
+    /// Simulated seconds per real second
+    speed: f64,
+}
+
+impl SimClock {
+    pub fn new(origin: DateTime<Utc>, speed: f64) -> Self {
+        SimClock { origin, speed }
+    }
+
+    /// The real-time clock
+    pub fn wall() -> Self {
+        SimClock::new(DateTime::UNIX_EPOCH, 1.0)
+    }
+
+    /// The clock of a competition, starting at its start
+    pub fn for_competition(competition: &Competition) -> Self {
+        SimClock::new(competition.starts_at, competition.clock_speed)
+    }
+
+    pub fn is_accelerated(&self) -> bool {
+        self.speed != 1.0
+    }
+
+    /// Simulated time at the real time `real`
+    pub fn at(&self, real: DateTime<Utc>) -> DateTime<Utc> {
+        if !self.is_accelerated() {
+            return real;
+        }
+        let elapsed = (real - self.origin).num_milliseconds() as f64 * self.speed;
+        self.origin + TimeDelta::milliseconds(elapsed as i64)
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.at(Utc::now())
+    }
+
+    /// The simulated UTC date
+    pub fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+}
@@ -0,0 +1,229 @@
+//! # Trading Competitions
+//!
+//! Admins schedule competitions with a start, an end and a starting balance.
+//! Joining one gives the user a portfolio of its own, named after the
+//! competition and funded with the starting balance. Competition portfolios
+//! are isolated from the rest of the account: cash cannot be deposited,
+//! withdrawn, transferred or borrowed into them, the money market does not
+//! fund their trades, and they are left out of the account's equity. They
+//! can only trade while the competition is running.
+//!
+//! Entrants are ranked by the equity of their competition portfolio. During
+//! the event standings are valued live at the latest prices; once it ends the
+//! competition worker records the final equity and rank of every entry, and
+//! those frozen standings are served from then on.
+//!
+//! A competition may run on an accelerated [`SimClock`], e.g. one trading
+//! day per real hour. Its start and end stay in real time, while dividends
+//! reach entrants on the simulated dates; the worker applies them once a
+//! minute.
+
+use std::{sync::Arc, time::Duration};
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    AppState, Error, Result,
+    models::{competition::Competition, portfolio::Portfolio},
+    repository::competition_repository::CompetitionRepository,
+    services::{clock::SimClock, dividends, portfolio},
+};
+
+/// How often ended competitions are looked for
+pub const FINALIZE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Where a competition is in its schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// Open for entries, not trading yet
+    Upcoming,
+    /// Open for entries and trading
+    Running,
+    /// Over, with its standings frozen once finalized
+    Ended,
+}
+
+pub fn status(competition: &Competition, now: DateTime<Utc>) -> Status {
+    if now < competition.starts_at {
+        Status::Upcoming
+    } else if now < competition.ends_at {
+        Status::Running
+    } else {
+        Status::Ended
+    }
+}
+
+/// Simulated time of a competition at the real time `now`, standing still
+/// before its start and after its end
+pub fn sim_time(competition: &Competition, now: DateTime<Utc>) -> DateTime<Utc> {
+    let now = now.clamp(competition.starts_at, competition.ends_at);
+    SimClock::for_competition(competition).at(now)
+}
+
+/// An entrant's place in a competition
+#[derive(Debug, Clone)]
+pub struct Standing {
+    /// 1 for the highest equity
+    pub rank: i32,
+    pub user_id: i32,
+    pub portfolio_id: i32,
+    pub equity: BigDecimal,
+    /// Gain on the starting balance in percent
+    pub return_percent: BigDecimal,
+}
+
+/// Apply the dividends of accelerated competitions and record the final
+/// standings of ended competitions once a minute
+pub async fn competition_worker(state: Arc<AppState>) -> Result<()> {
+    let mut interval = tokio::time::interval(FINALIZE_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+
+        match CompetitionRepository::new(&state.pg_pool)
+            .get_running_accelerated()
+            .await
+        {
+            Ok(running) => {
+                for competition in running {
+                    if let Err(e) = dividends::process_competition(&state, &competition).await {
+                        tracing::error!(
+                            "Failed to process dividends of competition {}: {}",
+                            competition.id,
+                            e
+                        );
+                    }
+                }
+            }
+            Err(e) => tracing::error!("Failed to look up accelerated competitions: {}", e),
+        }
+
+        let due = match CompetitionRepository::new(&state.pg_pool)
+            .get_due_for_finalization()
+            .await
+        {
+            Ok(due) => due,
+            Err(e) => {
+                tracing::error!("Failed to look up ended competitions: {}", e);
+                continue;
+            }
+        };
+        for competition in due {
+            match finalize(&state, &competition).await {
+                Ok(true) => tracing::info!("Finalized competition {}", competition.id),
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::error!("Failed to finalize competition {}: {}", competition.id, e)
+                }
+            }
+        }
+    }
+}
+
+/// Freeze the standings of an ended competition at the latest prices
+///
+/// Returns false when they were already frozen.
+pub async fn finalize(state: &AppState, competition: &Competition) -> Result<bool> {
+    let standings = live_standings(state, competition).await?;
+    let recorded: Vec<(i32, BigDecimal, i32)> = standings
+        .into_iter()
+        .map(|s| (s.portfolio_id, s.equity, s.rank))
+        .collect();
+
+    CompetitionRepository::new(&state.pg_pool)
+        .finalize(competition.id, &recorded)
+        .await
+}
+
+/// Standings of a competition, frozen once it is finalized
+pub async fn standings(state: &AppState, competition: &Competition) -> Result<Vec<Standing>> {
+    if competition.finalized_at.is_none() {
+        return live_standings(state, competition).await;
+    }
+
+    let entries = CompetitionRepository::new(&state.pg_pool)
+        .get_final_entries(competition.id)
+        .await?;
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| {
+            let equity = entry.final_equity?;
+            Some(Standing {
+                rank: entry.final_rank?,
+                user_id: entry.user_id,
+                portfolio_id: entry.portfolio_id,
+                return_percent: return_percent(&equity, &competition.starting_balance),
+                equity,
+            })
+        })
+        .collect())
+}
+
+/// Rank every entrant by the equity of their portfolio at the latest prices
+///
+/// Equal equity is ranked by who joined first.
+async fn live_standings(state: &AppState, competition: &Competition) -> Result<Vec<Standing>> {
+    let portfolios = CompetitionRepository::new(&state.pg_pool)
+        .get_portfolios(competition.id)
+        .await?;
+
+    let mut valued = Vec::with_capacity(portfolios.len());
+    for portfolio in portfolios {
+        let valuation = portfolio::value_portfolio(state, &portfolio).await?;
+        valued.push((portfolio, valuation.equity));
+    }
+    // Portfolios are listed by ID, so the stable sort keeps earlier entrants ahead
+    valued.sort_by(|a, b| b.1.cmp(&a.1));
+
+    Ok(valued
+        .into_iter()
+        .zip(1..)
+        .map(|((portfolio, equity), rank)| Standing {
+            rank,
+            user_id: portfolio.user_id,
+            portfolio_id: portfolio.id,
+            return_percent: return_percent(&equity, &competition.starting_balance),
+            equity,
+        })
+        .collect())
+}
+
+fn return_percent(equity: &BigDecimal, starting_balance: &BigDecimal) -> BigDecimal {
+    portfolio::percent_of(&(equity - starting_balance), starting_balance)
+}
+
+/// Reject trades in a competition portfolio outside its competition
+pub async fn check_trading(state: &AppState, portfolio: &Portfolio) -> Result<()> {
+    let Some(competition_id) = portfolio.competition_id else {
+        return Ok(());
+    };
+
+    let competition = CompetitionRepository::new(&state.pg_pool)
+        .get_competition(competition_id)
+        .await?
+        .ok_or(Error::NotFound)?;
+    match status(&competition, Utc::now()) {
+        Status::Running => Ok(()),
+        Status::Upcoming => Err(Error::BadRequest(
+            "The competition has not started yet".into(),
+        )),
+        Status::Ended => Err(Error::BadRequest("The competition has ended".into())),
+    }
+}
+
+/// Reject moving cash in or out of a competition portfolio
+pub fn check_cash_movement(portfolio: &Portfolio) -> Result<()> {
+    if portfolio.competition_id.is_some() {
+        return Err(Error::BadRequest(
+            "Cash cannot be moved in or out of a competition portfolio".into(),
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! # Corporate Actions
+//!
+//! Admins schedule stock splits and symbol changes with an effective date; the
+//! worker applies them at the start of that day.
+//!
+//! A split turns every `ratio_from` shares into `ratio_to` shares (2-for-1 is
+//! `ratio_from = 1, ratio_to = 2`, a 1-for-10 reverse split is `10, 1`).
+//! Holdings and open tax lots are restated so cost basis is unchanged, and
+//! fractional shares left over are paid out as cash at the post-split average
+//! price, so no gain is realized. Shares pledged to loans are split as well,
+//! and the candles and benchmark prices stored before the effective date are
+//! restated at the split ratio, so charts and returns do not jump at it. A
+//! symbol change moves positions, lots, loan collateral, liquidity profiles,
+//! the catalog listing and upcoming dividends and actions to the new ticker;
+//! past transactions keep the ticker they traded under.
+//!
+//! Prices come from the external feed, which is expected to publish
+//! split-adjusted prices under the new ticker from the effective date on.
+
+use std::sync::Arc;
+
+use bigdecimal::{BigDecimal, RoundingMode};
+use chrono::{NaiveDate, Utc};
+
+use crate::{
+    AppState, Error, Result,
+    models::corporate_action::CorporateAction,
+    repository::corporate_action_repository::CorporateActionRepository,
+    services::{instruments, snapshots},
+};
+
+/// Decimal places stored for average and lot prices
+const PRICE_SCALE: i64 = 10;
+
+/// A position restated for a split
+pub struct SplitPosition {
+    pub quantity: i32,
+    pub average_price: BigDecimal,
+    /// Open shares and price of each of the position's open lots, in order
+    pub lots: Vec<(i32, BigDecimal)>,
+    /// Fractional share left over, paid out at the split average price
+    pub cash_in_lieu: BigDecimal,
+}
+
+/// Apply due corporate actions at startup and after every UTC midnight
+pub async fn corporate_action_worker(state: Arc<AppState>) -> Result<()> {
+    loop {
+        let today = Utc::now().date_naive();
+        if let Err(e) = process(&state, today).await {
+            tracing::error!("Failed to process corporate actions: {}", e);
+        }
+
+        tokio::time::sleep(snapshots::until_next_day()).await;
+    }
+}
+
+/// Apply every pending action whose effective date has arrived
+pub async fn process(state: &AppState, today: NaiveDate) -> Result<()> {
+    let actions = CorporateActionRepository::new(&state.pg_pool)
+        .claim_due(today)
+        .await?;
+
+    for action in &actions {
+        // Actions are claimed before applying; a failure here needs manual follow-up
+        match apply(state, action).await {
+            Ok(()) => tracing::info!("Applied corporate action: {:?}", action),
+            Err(e) => tracing::error!("Failed to apply corporate action {}: {}", action.id, e),
+        }
+    }
+
+    Ok(())
+}
+
+async fn apply(state: &AppState, action: &CorporateAction) -> Result<()> {
+    match (
+        action.action_type.as_str(),
+        action.ratio_from,
+        action.ratio_to,
+        action.new_ticker.as_deref(),
+    ) {
+        ("split", Some(ratio_from), Some(ratio_to), _) => {
+            apply_split(state, action, ratio_from, ratio_to).await
+        }
+        ("symbol_change", _, _, Some(new_ticker)) => {
+            CorporateActionRepository::new(&state.pg_pool)
+                .rename_ticker(&action.ticker, new_ticker)
+                .await?;
+            instruments::announce_catalog_change(state).await;
+            Ok(())
+        }
+        _ => Err(Error::BadRequest(format!(
+            "Malformed corporate action {}",
+            action.id
+        ))),
+    }
+}
+
+/// Restate every position in the action's ticker for a
+/// `ratio_from`-to-`ratio_to` split
+async fn apply_split(
+    state: &AppState,
+    action: &CorporateAction,
+    ratio_from: i32,
+    ratio_to: i32,
+) -> Result<()> {
+    let adjust_price = |price: &BigDecimal| {
+        (price * BigDecimal::from(ratio_from) / BigDecimal::from(ratio_to))
+            .with_scale_round(PRICE_SCALE, RoundingMode::HalfUp)
+    };
+
+    CorporateActionRepository::new(&state.pg_pool)
+        .split_ticker(
+            &action.ticker,
+            action.effective_date,
+            ratio_from,
+            ratio_to,
+            |holding, lots| {
+                let (quantity, fraction) = split_quantity(holding.quantity, ratio_from, ratio_to)?;
+                let average_price = adjust_price(&holding.average_price);
+                let remaining: Vec<i32> = lots.iter().map(|lot| lot.remaining_quantity).collect();
+                let lots = split_lots(&remaining, quantity, ratio_from, ratio_to)?
+                    .into_iter()
+                    .zip(lots)
+                    .map(|(shares, lot)| (shares, adjust_price(&lot.price)))
+                    .collect();
+                let cash_in_lieu =
+                    (fraction * &average_price).with_scale_round(2, RoundingMode::HalfUp);
+
+                Ok(SplitPosition {
+                    quantity,
+                    average_price,
+                    lots,
+                    cash_in_lieu,
+                })
+            },
+        )
+        .await
+}
+
+/// Whole shares after a split and the fractional share left over
+pub fn split_quantity(quantity: i32, ratio_from: i32, ratio_to: i32) -> Result<(i32, BigDecimal)> {
+    let scaled = i64::from(quantity) * i64::from(ratio_to);
+    let whole = i32::try_from(scaled / i64::from(ratio_from))
+        .map_err(|_| Error::BadRequest("Split quantity out of range".into()))?;
+    let fraction = BigDecimal::from(scaled % i64::from(ratio_from)) / BigDecimal::from(ratio_from);
+
+    Ok((whole, fraction))
+}
+
+/// Split each lot's open shares, handing the shares lost to per-lot rounding
+/// back to the oldest lots so the lots add up to the split position
+pub fn split_lots(
+    remaining: &[i32],
+    quantity: i32,
+    ratio_from: i32,
+    ratio_to: i32,
+) -> Result<Vec<i32>> {
+    let mut lots = remaining
+        .iter()
+        .map(|shares| split_quantity(*shares, ratio_from, ratio_to).map(|(whole, _)| whole))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut leftover = quantity - lots.iter().sum::<i32>();
+    for lot in lots.iter_mut() {
+        if leftover <= 0 {
+            break;
+        }
+        *lot += 1;
+        leftover -= 1;
+    }
+
+    Ok(lots)
+}
@@ -0,0 +1,297 @@
+//! # Cost Basis Accounting
+//!
+//! Pure lot-selection logic used when a position is sold. Lots are consumed
+//! according to the user's cost-basis method and each consumed slice yields a
+//! realized gain record.
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+
+use crate::models::{tax_lot::TaxLot, user_settings::CostBasisMethod};
+
+/// A slice of a sell matched against a single lot
+#[derive(Debug, Clone, PartialEq)]
+pub struct RealizedLot {
+    /// Lot the shares came from; `None` for shares not covered by any lot
+    pub lot_id: Option<i32>,
+    pub acquired_at: Option<DateTime<Utc>>,
+    pub quantity: i32,
+    /// Total cost of the sold shares
+    pub cost_basis: BigDecimal,
+    /// Total sale proceeds of the sold shares
+    pub proceeds: BigDecimal,
+    pub gain: BigDecimal,
+}
+
+/// Match a sell of `quantity` shares at `sale_price` against the open lots
+///
+/// `lots` must be ordered from oldest to newest. FIFO consumes the oldest lots
+/// first and LIFO the newest; both use each lot's own price as cost. The
+/// average method consumes lots oldest first but values every share at the
+/// position's `average_price`. Shares not covered by any lot (positions opened
+/// before lot tracking) are valued at `average_price` too.
+pub fn realize(
+    method: CostBasisMethod,
+    lots: &[TaxLot],
+    quantity: i32,
+    average_price: &BigDecimal,
+    sale_price: &BigDecimal,
+) -> Vec<RealizedLot> {
+    let ordered: Vec<&TaxLot> = match method {
+        CostBasisMethod::Lifo => lots.iter().rev().collect(),
+        CostBasisMethod::Fifo | CostBasisMethod::Average => lots.iter().collect(),
+    };
+
+    let mut remaining = quantity;
+    let mut realized = Vec::new();
+
+    for lot in ordered {
+        if remaining == 0 {
+            break;
+        }
+        let taken = remaining.min(lot.remaining_quantity);
+        if taken <= 0 {
+            continue;
+        }
+        let cost_per_share = match method {
+            CostBasisMethod::Average => average_price,
+            CostBasisMethod::Fifo | CostBasisMethod::Lifo => &lot.price,
+        };
+        realized.push(realized_lot(
+            Some(lot.id),
+            Some(lot.acquired_at),
+            taken,
+            cost_per_share,
+            sale_price,
+        ));
+        remaining -= taken;
+    }
+
+    if remaining > 0 {
+        realized.push(realized_lot(
+            None,
+            None,
+            remaining,
+            average_price,
+            sale_price,
+        ));
+    }
+
+    realized
+}
+
+/// Weighted average price of the lots left open after a sell
+///
+/// Returns `None` when no shares remain in any lot.
+pub fn remaining_average(lots: &[TaxLot], realized: &[RealizedLot]) -> Option<BigDecimal> {
+    let mut total_quantity = 0;
+    let mut total_cost = BigDecimal::from(0);
+
+    for lot in lots {
+        let sold: i32 = realized
+            .iter()
+            .filter(|r| r.lot_id == Some(lot.id))
+            .map(|r| r.quantity)
+            .sum();
+        let left = lot.remaining_quantity - sold;
+        if left > 0 {
+            total_quantity += left;
+            total_cost += &lot.price * BigDecimal::from(left);
+        }
+    }
+
+    (total_quantity > 0).then(|| total_cost / BigDecimal::from(total_quantity))
+}
+
+fn realized_lot(
+    lot_id: Option<i32>,
+    acquired_at: Option<DateTime<Utc>>,
+    quantity: i32,
+    cost_per_share: &BigDecimal,
+    sale_price: &BigDecimal,
+) -> RealizedLot {
+    let cost_basis = cost_per_share * BigDecimal::from(quantity);
+    let proceeds = sale_price * BigDecimal::from(quantity);
+    let gain = &proceeds - &cost_basis;
+
+    RealizedLot {
+        lot_id,
+        acquired_at,
+        quantity,
+        cost_basis,
+        proceeds,
+        gain,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::services::positions::average_after_buy;
+
+    /// Prices in whole cents, as they are stored
+    fn price() -> impl Strategy<Value = BigDecimal> {
+        (1i64..1_000_000).prop_map(|cents| BigDecimal::new(cents.into(), 2))
+    }
+
+    fn method() -> impl Strategy<Value = CostBasisMethod> {
+        prop_oneof![
+            Just(CostBasisMethod::Fifo),
+            Just(CostBasisMethod::Lifo),
+            Just(CostBasisMethod::Average),
+        ]
+    }
+
+    /// Buys opening a position, oldest first, and a sell of at most `extra`
+    /// shares more than they bought
+    fn sale(extra: i32) -> impl Strategy<Value = (Vec<(i32, BigDecimal)>, i32)> {
+        prop::collection::vec((1..500i32, price()), 1..8).prop_flat_map(move |buys| {
+            let held: i32 = buys.iter().map(|(quantity, _)| quantity).sum();
+            (Just(buys), 1..=held + extra)
+        })
+    }
+
+    /// The open lots and average price left by `buys`
+    fn position(buys: &[(i32, BigDecimal)]) -> (Vec<TaxLot>, BigDecimal) {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let lots = buys
+            .iter()
+            .enumerate()
+            .map(|(i, (quantity, price))| TaxLot {
+                id: i as i32 + 1,
+                remaining_quantity: *quantity,
+                price: price.clone(),
+                acquired_at: start + chrono::Duration::days(i as i64),
+            })
+            .collect();
+
+        let (first_quantity, first_price) = &buys[0];
+        let (_, average) = buys[1..].iter().fold(
+            (*first_quantity, first_price.clone()),
+            |(held, average), (quantity, price)| {
+                let average = average_after_buy(held, &average, *quantity, price);
+                (held + quantity, average)
+            },
+        );
+
+        (lots, average)
+    }
+
+    /// Equal up to the rounding of divisions
+    fn close(a: &BigDecimal, b: &BigDecimal) -> bool {
+        (a - b).abs() < BigDecimal::new(1.into(), 6)
+    }
+
+    proptest! {
+        #[test]
+        fn sells_never_take_more_than_a_lot_holds(
+            method in method(),
+            (buys, quantity) in sale(100),
+            sale_price in price(),
+        ) {
+            let (lots, average) = position(&buys);
+            let held: i32 = lots.iter().map(|lot| lot.remaining_quantity).sum();
+            let realized = realize(method, &lots, quantity, &average, &sale_price);
+
+            prop_assert_eq!(realized.iter().map(|r| r.quantity).sum::<i32>(), quantity);
+            prop_assert!(realized.iter().all(|r| r.quantity > 0));
+            for lot in &lots {
+                let sold: i32 = realized
+                    .iter()
+                    .filter(|r| r.lot_id == Some(lot.id))
+                    .map(|r| r.quantity)
+                    .sum();
+                prop_assert!(sold <= lot.remaining_quantity);
+            }
+
+            // Only shares beyond the open lots are left uncovered
+            let uncovered: i32 = realized
+                .iter()
+                .filter(|r| r.lot_id.is_none())
+                .map(|r| r.quantity)
+                .sum();
+            prop_assert_eq!(uncovered, (quantity - held).max(0));
+        }
+
+        #[test]
+        fn gains_are_proceeds_less_cost(
+            method in method(),
+            (buys, quantity) in sale(100),
+            sale_price in price(),
+        ) {
+            let (lots, average) = position(&buys);
+            for r in realize(method, &lots, quantity, &average, &sale_price) {
+                prop_assert_eq!(&r.proceeds, &(&sale_price * BigDecimal::from(r.quantity)));
+                prop_assert_eq!(&r.gain, &(&r.proceeds - &r.cost_basis));
+            }
+        }
+
+        #[test]
+        fn lots_are_consumed_in_method_order(
+            method in method(),
+            (buys, quantity) in sale(0),
+            sale_price in price(),
+        ) {
+            let (lots, average) = position(&buys);
+            let realized = realize(method, &lots, quantity, &average, &sale_price);
+
+            let ids: Vec<i32> = realized.iter().filter_map(|r| r.lot_id).collect();
+            let expected: Vec<i32> = match method {
+                CostBasisMethod::Lifo => (1..=lots.len() as i32).rev().take(ids.len()).collect(),
+                CostBasisMethod::Fifo | CostBasisMethod::Average => {
+                    (1..=ids.len() as i32).collect()
+                }
+            };
+            prop_assert_eq!(&ids, &expected);
+            // Every lot but the last one touched is used up
+            for r in &realized[..realized.len() - 1] {
+                let lot = &lots[r.lot_id.unwrap() as usize - 1];
+                prop_assert_eq!(r.quantity, lot.remaining_quantity);
+            }
+        }
+
+        #[test]
+        fn realized_and_unrealized_gains_reconcile_with_cash_flows(
+            method in method(),
+            (buys, quantity) in sale(0),
+            sale_price in price(),
+            mark in price(),
+        ) {
+            let (lots, average) = position(&buys);
+            let held: i32 = lots.iter().map(|lot| lot.remaining_quantity).sum();
+            let realized = realize(method, &lots, quantity, &average, &sale_price);
+
+            // The remaining position is valued the way remove_shares values it
+            let remaining_price = match method {
+                CostBasisMethod::Average => average.clone(),
+                CostBasisMethod::Fifo | CostBasisMethod::Lifo => {
+                    remaining_average(&lots, &realized).unwrap_or_else(|| average.clone())
+                }
+            };
+            let left = BigDecimal::from(held - quantity);
+            let realized_gain = realized
+                .iter()
+                .fold(BigDecimal::from(0), |total, r| total + &r.gain);
+            let unrealized_gain = (&mark - &remaining_price) * &left;
+
+            let paid = buys
+                .iter()
+                .fold(BigDecimal::from(0), |total, (quantity, price)| {
+                    total + price * BigDecimal::from(*quantity)
+                });
+            let received = &sale_price * BigDecimal::from(quantity);
+            let net = received + &mark * &left - paid;
+
+            prop_assert!(
+                close(&(&realized_gain + &unrealized_gain), &net),
+                "realized {} + unrealized {} != {}",
+                realized_gain,
+                unrealized_gain,
+                net
+            );
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! # Dividends
+//!
+//! Admins announce dividends with an ex-date, a pay date and an amount per
+//! share. At the start of the ex-date the worker records every holder of the
+//! ticker and their share count per portfolio; on the pay date the recorded
+//! amounts are credited as cash to the portfolio holding the shares and appear
+//! as `dividend` transactions. Users with DRIP
+//! enabled have each payment reinvested into whole shares at the current
+//! price, without spread or slippage; any remainder stays in cash.
+//!
+//! Portfolios of accelerated competitions follow the competition's
+//! [`SimClock`] instead: the competition worker records their holders and
+//! pays them once the simulated date reaches the ex- and pay dates.
+
+use std::sync::Arc;
+
+use bigdecimal::{BigDecimal, RoundingMode, ToPrimitive, Zero};
+use chrono::NaiveDate;
+
+use crate::{
+    AppState, Result,
+    models::{competition::Competition, dividend::DuePayment},
+    repository::{
+        dividend_repository::DividendRepository,
+        ledger_repository::{LedgerRepository, TradePosting},
+        user_settings_repository::UserSettingsRepository,
+    },
+    services::{clock::SimClock, positions, price_cache::PriceCache, snapshots},
+};
+
+/// Process dividends at startup and after every UTC midnight
+pub async fn dividend_worker(state: Arc<AppState>) -> Result<()> {
+    loop {
+        let today = SimClock::wall().today();
+        if let Err(e) = process(&state, today).await {
+            tracing::error!("Failed to process dividends: {}", e);
+        }
+
+        tokio::time::sleep(snapshots::until_next_day()).await;
+    }
+}
+
+/// Record holders of dividends going ex and pay dividends that are due
+pub async fn process(state: &AppState, today: NaiveDate) -> Result<()> {
+    let repository = DividendRepository::new(&state.pg_pool);
+
+    let recorded = repository.record_holders(today).await?;
+    if recorded > 0 {
+        tracing::info!("Recorded {} dividend entitlements", recorded);
+    }
+
+    let payments = repository.claim_due_payments(today).await?;
+    for payment in &payments {
+        // Payments are claimed before crediting; a failure here needs manual follow-up
+        if let Err(e) = pay(state, payment).await {
+            tracing::error!(
+                "Failed to pay dividend payment {} to user {}: {}",
+                payment.id,
+                payment.user_id,
+                e
+            );
+        }
+    }
+
+    let paid = repository.mark_paid(today).await?;
+    if paid > 0 {
+        tracing::info!("Paid {} dividends ({} payments)", paid, payments.len());
+    }
+
+    Ok(())
+}
+
+/// Record and pay the dividends of a competition's portfolios up to the
+/// simulated date of its clock
+pub async fn process_competition(state: &AppState, competition: &Competition) -> Result<()> {
+    let repository = DividendRepository::new(&state.pg_pool);
+    let today = SimClock::for_competition(competition).today();
+
+    let recorded = repository
+        .record_competition_holders(competition.id, today)
+        .await?;
+    if recorded > 0 {
+        tracing::info!(
+            "Recorded {} dividend entitlements in competition {}",
+            recorded,
+            competition.id
+        );
+    }
+
+    let payments = repository
+        .claim_competition_payments(competition.id, today)
+        .await?;
+    for payment in &payments {
+        if let Err(e) = pay(state, payment).await {
+            tracing::error!(
+                "Failed to pay dividend payment {} to user {}: {}",
+                payment.id,
+                payment.user_id,
+                e
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Credit a claimed payment and reinvest it for DRIP users
+async fn pay(state: &AppState, payment: &DuePayment) -> Result<()> {
+    let transaction = state
+        .repos
+        .transactions
+        .create_transaction(
+            payment.user_id,
+            payment.portfolio_id,
+            &payment.ticker,
+            payment.quantity,
+            payment.amount_per_share.clone(),
+            "dividend",
+            BigDecimal::from(0),
+        )
+        .await?;
+    LedgerRepository::new(&state.pg_pool)
+        .post(
+            payment.portfolio_id,
+            "dividend",
+            payment.amount.clone(),
+            Some(transaction.id),
+        )
+        .await?;
+    DividendRepository::new(&state.pg_pool)
+        .set_payment_transaction(payment.id, transaction.id)
+        .await?;
+
+    let drip_enabled = UserSettingsRepository::new(&state.pg_pool)
+        .get_settings(payment.user_id)
+        .await?
+        .is_some_and(|settings| settings.drip_enabled);
+    if drip_enabled {
+        reinvest(state, payment).await?;
+    }
+
+    Ok(())
+}
+
+/// Buy as many whole shares of the paying ticker as the payment covers at the current price
+async fn reinvest(state: &AppState, payment: &DuePayment) -> Result<()> {
+    let ticker = payment.ticker.as_str();
+    let prices = PriceCache::new(&state.redis_pool)
+        .prices(&[ticker.to_string()])
+        .await?;
+    let price = match prices.get(ticker) {
+        Some(price) if *price > BigDecimal::zero() => {
+            price.with_scale_round(2, RoundingMode::HalfUp)
+        }
+        _ => {
+            tracing::warn!(
+                "No price for {}, dividend for user {} kept as cash",
+                ticker,
+                payment.user_id
+            );
+            return Ok(());
+        }
+    };
+
+    let quantity = (&payment.amount / &price)
+        .with_scale_round(0, RoundingMode::Down)
+        .to_i32()
+        .unwrap_or(0);
+    if quantity < 1 {
+        return Ok(());
+    }
+
+    let cost = &price * BigDecimal::from(quantity);
+    let transaction = state
+        .repos
+        .transactions
+        .create_transaction(
+            payment.user_id,
+            payment.portfolio_id,
+            ticker,
+            quantity,
+            price.clone(),
+            "buy",
+            BigDecimal::from(0),
+        )
+        .await?;
+    positions::add_shares(
+        state,
+        payment.user_id,
+        payment.portfolio_id,
+        ticker,
+        quantity,
+        &price,
+        transaction.id,
+        TradePosting {
+            entry_type: "buy",
+            value: -cost,
+            fee: BigDecimal::from(0),
+        },
+    )
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,504 @@
+//! # Event Streaming
+//!
+//! Normalized domain events for analytics pipelines and other services,
+//! streamed to the message broker chosen with `EVENT_BROKER`:
+//!
+//! - `user.registered` when an account is created with `POST /auth/register`
+//! - `order.placed` for every buy or sell submitted through any API, by bots
+//!   or by margin calls, before it is checked; orders that are refused never
+//!   get a `trade.executed`
+//! - `trade.executed` when an order is filled, with the `order_id` of its
+//!   `order.placed`
+//! - `price.tick` for every price stored, whether from the feed, a replay or
+//!   an admin override
+//!
+//! Every event is a JSON envelope with its `id`, `type`, envelope `version`,
+//! `occurred_at`, the `request_id` that caused it if any, and its `data`. It
+//! is published under `{EVENT_SUBJECT_PREFIX}.{type}`, e.g.
+//! `sim.trade.executed`:
+//!
+//! - `nats` publishes to that subject on the NATS server at
+//!   `EVENT_BROKER_URL` (`nats://[user:password@|token@]host:port`, without
+//!   TLS), where JetStream or a bridge to Kafka can capture it
+//! - `redis` appends it as the `payload` field to the Redis stream of that
+//!   name, capped at about [`REDIS_STREAM_MAX_LEN`] entries, for consumer
+//!   groups to read
+//!
+//! Emitting never waits for the broker: events are queued for a publisher
+//! task and dropped with a warning while the queue is full or the broker is
+//! unreachable, so events are streamed at most once. Each instance streams
+//! the events that happen on it.
+
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
+use serde::Serialize;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter},
+    net::{TcpStream, tcp::OwnedWriteHalf},
+    sync::mpsc,
+    task::JoinHandle,
+};
+use url::Url;
+use uuid::Uuid;
+
+use crate::{AppState, Result, config::Config};
+
+/// Version of the envelope, raised when its fields change incompatibly
+const ENVELOPE_VERSION: u32 = 1;
+/// Events waiting for the publisher before new ones are dropped
+const QUEUE_CAPACITY: usize = 10_000;
+/// Entries kept per Redis stream, approximately
+pub const REDIS_STREAM_MAX_LEN: usize = 100_000;
+/// Port of NATS URLs without one
+const NATS_DEFAULT_PORT: u16 = 4222;
+/// Time to connect to the broker and complete its handshake
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Wait after a failed publish, doubled on every failure in a row
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Longest wait after a failed publish
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Something that happened in the simulator
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum DomainEvent {
+    UserRegistered {
+        user_id: i32,
+    },
+    OrderPlaced {
+        order_id: Uuid,
+        user_id: i32,
+        portfolio_id: i32,
+        ticker: String,
+        /// `buy` or `sell`
+        side: &'static str,
+        quantity: i32,
+    },
+    TradeExecuted {
+        order_id: Uuid,
+        /// Public ID of the transaction, as the API shows it
+        transaction_id: Uuid,
+        user_id: i32,
+        portfolio_id: i32,
+        ticker: String,
+        /// `buy` or `sell`
+        side: String,
+        quantity: i32,
+        price: BigDecimal,
+        fee: BigDecimal,
+    },
+    PriceTick {
+        ticker: String,
+        price: f64,
+        timestamp: DateTime<Utc>,
+    },
+}
+
+impl DomainEvent {
+    /// The event's `type`, the last part of its subject
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DomainEvent::UserRegistered { .. } => "user.registered",
+            DomainEvent::OrderPlaced { .. } => "order.placed",
+            DomainEvent::TradeExecuted { .. } => "trade.executed",
+            DomainEvent::PriceTick { .. } => "price.tick",
+        }
+    }
+}
+
+/// An event as published
+#[derive(Debug, Serialize)]
+struct Envelope<'a> {
+    id: Uuid,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    version: u32,
+    occurred_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    data: &'a DomainEvent,
+}
+
+/// An encoded event waiting for the publisher
+struct Message {
+    subject: String,
+    payload: String,
+}
+
+/// Queue of events for the publisher, a no-op without a broker
+pub struct EventStream {
+    prefix: String,
+    sender: Option<mpsc::Sender<Message>>,
+    receiver: Mutex<Option<mpsc::Receiver<Message>>>,
+    dropped: AtomicU64,
+}
+
+impl EventStream {
+    pub fn from_config(config: &Config) -> Self {
+        let (sender, receiver) = match config.event_broker.as_str() {
+            "none" => (None, None),
+            _ => {
+                let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+                (Some(sender), Some(receiver))
+            }
+        };
+
+        EventStream {
+            prefix: config.event_subject_prefix.clone(),
+            sender,
+            receiver: Mutex::new(receiver),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Queue `event` for the broker without waiting
+    pub fn emit(&self, event: DomainEvent) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+
+        let envelope = Envelope {
+            id: Uuid::new_v4(),
+            kind: event.kind(),
+            version: ENVELOPE_VERSION,
+            occurred_at: Utc::now(),
+            request_id: crate::request_id::current(),
+            data: &event,
+        };
+        let payload = match serde_json::to_string(&envelope) {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::error!("Failed to encode {} event: {}", event.kind(), e);
+                return;
+            }
+        };
+        let message = Message {
+            subject: format!("{}.{}", self.prefix, event.kind()),
+            payload,
+        };
+
+        if sender.try_send(message).is_err() {
+            // Warn once per thousand, the queue stays full while the broker is down
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed);
+            if dropped % 1000 == 0 {
+                tracing::warn!(
+                    "Event stream queue is full, {} events dropped so far",
+                    dropped + 1
+                );
+            }
+        }
+    }
+}
+
+/// Publish queued events to the configured broker until the server stops
+pub async fn publisher(state: Arc<AppState>) -> Result<()> {
+    let receiver = state
+        .event_stream
+        .receiver
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .take();
+    let Some(mut receiver) = receiver else {
+        return Ok(());
+    };
+
+    let config = &state.config;
+    let mut broker: Box<dyn Broker> = match config.event_broker.as_str() {
+        "nats" => Box::new(NatsBroker::new(
+            config.event_broker_url.as_deref().unwrap_or_default(),
+        )),
+        _ => Box::new(RedisBroker::new(
+            config
+                .event_broker_url
+                .as_deref()
+                .unwrap_or(&config.redis_url),
+        )),
+    };
+    tracing::info!("Streaming domain events to {}", broker.name());
+
+    let mut delay = RETRY_DELAY;
+    while let Some(message) = receiver.recv().await {
+        match broker.publish(&message.subject, &message.payload).await {
+            Ok(()) => delay = RETRY_DELAY,
+            Err(e) => {
+                tracing::warn!(
+                    "Dropping event on {}, {} failed: {}",
+                    message.subject,
+                    broker.name(),
+                    e
+                );
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A message broker events are published to
+trait Broker: Send {
+    /// Short name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Publish `payload` to `subject`, connecting first when not connected
+    fn publish<'a>(
+        &'a mut self,
+        subject: &'a str,
+        payload: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+/// Appends events to Redis streams named after their subject
+struct RedisBroker {
+    url: String,
+    connection: Option<redis::aio::MultiplexedConnection>,
+}
+
+impl RedisBroker {
+    fn new(url: &str) -> Self {
+        RedisBroker {
+            url: url.to_string(),
+            connection: None,
+        }
+    }
+
+    async fn append(&mut self, stream: &str, payload: &str) -> anyhow::Result<()> {
+        let connection = match &mut self.connection {
+            Some(connection) => connection,
+            None => {
+                let client = redis::Client::open(self.url.as_str())?;
+                let connection = tokio::time::timeout(
+                    CONNECT_TIMEOUT,
+                    client.get_multiplexed_async_connection(),
+                )
+                .await
+                .map_err(|_| anyhow!("timed out connecting"))??;
+                self.connection.insert(connection)
+            }
+        };
+
+        let result = redis::cmd("XADD")
+            .arg(stream)
+            .arg("MAXLEN")
+            .arg("~")
+            .arg(REDIS_STREAM_MAX_LEN)
+            .arg("*")
+            .arg("payload")
+            .arg(payload)
+            .query_async::<String>(connection)
+            .await;
+        if result.is_err() {
+            self.connection = None;
+        }
+        result?;
+
+        Ok(())
+    }
+}
+
+impl Broker for RedisBroker {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    fn publish<'a>(
+        &'a mut self,
+        subject: &'a str,
+        payload: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(self.append(subject, payload))
+    }
+}
+
+/// Publishes events with the core NATS protocol, at most once
+struct NatsBroker {
+    url: String,
+    connection: Option<NatsConnection>,
+}
+
+/// An open NATS connection whose server pings are answered in the background
+struct NatsConnection {
+    writer: Arc<tokio::sync::Mutex<BufWriter<OwnedWriteHalf>>>,
+    closed: Arc<AtomicBool>,
+    reader: JoinHandle<()>,
+}
+
+impl Drop for NatsConnection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+impl NatsBroker {
+    fn new(url: &str) -> Self {
+        NatsBroker {
+            url: url.to_string(),
+            connection: None,
+        }
+    }
+
+    async fn send(&mut self, subject: &str, payload: &str) -> anyhow::Result<()> {
+        if self
+            .connection
+            .as_ref()
+            .is_some_and(|connection| connection.closed.load(Ordering::Relaxed))
+        {
+            self.connection = None;
+        }
+        let connection = match &self.connection {
+            Some(connection) => connection,
+            None => {
+                let connection = tokio::time::timeout(CONNECT_TIMEOUT, nats_connect(&self.url))
+                    .await
+                    .map_err(|_| anyhow!("timed out connecting"))??;
+                self.connection.insert(connection)
+            }
+        };
+
+        let result = async {
+            let mut writer = connection.writer.lock().await;
+            writer
+                .write_all(format!("PUB {} {}\r\n", subject, payload.len()).as_bytes())
+                .await?;
+            writer.write_all(payload.as_bytes()).await?;
+            writer.write_all(b"\r\n").await?;
+            writer.flush().await
+        }
+        .await;
+        if result.is_err() {
+            self.connection = None;
+        }
+        result?;
+
+        Ok(())
+    }
+}
+
+impl Broker for NatsBroker {
+    fn name(&self) -> &'static str {
+        "nats"
+    }
+
+    fn publish<'a>(
+        &'a mut self,
+        subject: &'a str,
+        payload: &'a str,
+    ) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(self.send(subject, payload))
+    }
+}
+
+/// Connect to the NATS server at `url` and complete the handshake
+async fn nats_connect(url: &str) -> anyhow::Result<NatsConnection> {
+    let url = Url::parse(url)?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!("NATS URL without a host"))?;
+    let stream = TcpStream::connect((host, url.port().unwrap_or(NATS_DEFAULT_PORT))).await?;
+    stream.set_nodelay(true)?;
+    let (reader, writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut writer = BufWriter::new(writer);
+
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+    let info: serde_json::Value = line
+        .strip_prefix("INFO ")
+        .and_then(|info| serde_json::from_str(info).ok())
+        .ok_or_else(|| anyhow!("unexpected greeting {:?}", line.trim_end()))?;
+    if info["tls_required"] == true {
+        bail!("the server requires TLS, which is not supported");
+    }
+
+    let mut options = serde_json::json!({
+        "verbose": false,
+        "pedantic": false,
+        "name": "stock-exchange-sim-core",
+        "lang": "rust",
+        "version": env!("CARGO_PKG_VERSION"),
+    });
+    match (url.username(), url.password()) {
+        ("", _) => {}
+        (user, Some(password)) => {
+            options["user"] = user.into();
+            options["pass"] = password.into();
+        }
+        (token, None) => options["auth_token"] = token.into(),
+    }
+    writer
+        .write_all(format!("CONNECT {}\r\nPING\r\n", options).as_bytes())
+        .await?;
+    writer.flush().await?;
+
+    // The server answers the ping once it accepted the connection
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            bail!("connection closed during the handshake");
+        }
+        match line.trim_end() {
+            "PONG" => break,
+            "PING" => {
+                writer.write_all(b"PONG\r\n").await?;
+                writer.flush().await?;
+            }
+            error if error.starts_with("-ERR") => bail!("server refused the connection: {}", error),
+            _ => {}
+        }
+    }
+
+    let writer = Arc::new(tokio::sync::Mutex::new(writer));
+    let closed = Arc::new(AtomicBool::new(false));
+    let reader = tokio::spawn(nats_read(reader, writer.clone(), closed.clone()));
+
+    Ok(NatsConnection {
+        writer,
+        closed,
+        reader,
+    })
+}
+
+/// Answer the server's pings and log its errors until the connection closes
+async fn nats_read(
+    mut reader: BufReader<tokio::net::tcp::OwnedReadHalf>,
+    writer: Arc<tokio::sync::Mutex<BufWriter<OwnedWriteHalf>>>,
+    closed: Arc<AtomicBool>,
+) {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match reader.read_line(&mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        match line.trim_end() {
+            "PING" => {
+                let mut writer = writer.lock().await;
+                let pong = async {
+                    writer.write_all(b"PONG\r\n").await?;
+                    writer.flush().await
+                };
+                if pong.await.is_err() {
+                    break;
+                }
+            }
+            error if error.starts_with("-ERR") => {
+                tracing::warn!("NATS server reported {}", error);
+            }
+            _ => {}
+        }
+    }
+
+    closed.store(true, Ordering::Relaxed);
+}
@@ -0,0 +1,161 @@
+//! # Feature Flags
+//!
+//! Risky features are switched at runtime instead of with a redeploy. Every
+//! [`Feature`] the code checks has a default used while no flag of its name
+//! is stored; admins store flags to switch it off, or to roll it out to a
+//! list of users and a percentage of everyone else.
+//!
+//! Which users fall in the percentage is decided by a stable hash of the flag
+//! name and user ID, so raising the percentage only adds users and a user
+//! keeps the same answer on every instance. Each flag hashes users
+//! differently, so the first percent of one rollout is not the first percent
+//! of every rollout.
+//!
+//! Flags are cached on Redis for [`CACHE_TTL_SECS`] and dropped from the cache
+//! whenever an admin changes them. Without Redis they are read from the
+//! database.
+
+use std::collections::BTreeMap;
+
+use redis::AsyncCommands;
+
+use crate::{
+    AppState, Error, Result, models::feature_flag::FeatureFlag,
+    repository::feature_flag_repository::FeatureFlagRepository,
+};
+
+/// Redis key of the cached flags
+const CACHE_KEY: &str = "feature_flags";
+/// Lifetime of the cached flags, bounding how stale a missed invalidation leaves them
+pub const CACHE_TTL_SECS: u64 = 60;
+
+/// A feature the code checks before use
+#[derive(Debug, Clone, Copy)]
+pub struct Feature {
+    pub name: &'static str,
+    /// Whether the feature is on while no flag of its name is stored
+    pub default: bool,
+}
+
+/// Borrowing against holdings, `POST /loans`, and writing option contracts
+pub const MARGIN_TRADING: Feature = Feature {
+    name: "margin_trading",
+    default: true,
+};
+
+/// Every feature the code checks
+pub const FEATURES: &[Feature] = &[MARGIN_TRADING];
+
+/// Whether `flag` is on for the user
+pub fn evaluate(flag: &FeatureFlag, user_id: i32) -> bool {
+    flag.enabled
+        && (flag.user_ids.contains(&user_id)
+            || i16::from(bucket(&flag.name, user_id)) < flag.rollout_percent)
+}
+
+/// Stable bucket, 0 to 99, of the user in the rollout of a flag
+///
+/// FNV-1a, since the hashers of the standard library may change between
+/// releases.
+fn bucket(name: &str, user_id: i32) -> u8 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in format!("{}:{}", name, user_id).bytes() {
+        hash ^= u32::from(byte);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    (hash % 100) as u8
+}
+
+/// Whether `feature` is on for the user
+///
+/// Falls back to the default of the feature when the flags cannot be loaded.
+pub async fn is_enabled(state: &AppState, feature: Feature, user_id: i32) -> bool {
+    match load(state).await {
+        Ok(flags) => flags
+            .iter()
+            .find(|flag| flag.name == feature.name)
+            .map_or(feature.default, |flag| evaluate(flag, user_id)),
+        Err(e) => {
+            tracing::warn!("Failed to load feature flags: {}", e);
+            feature.default
+        }
+    }
+}
+
+/// Fails with `Forbidden` unless `feature` is on for the user
+pub async fn require(state: &AppState, feature: Feature, user_id: i32) -> Result<()> {
+    if is_enabled(state, feature, user_id).await {
+        Ok(())
+    } else {
+        Err(Error::Forbidden)
+    }
+}
+
+/// Every known feature and stored flag, and whether it is on for the user
+pub async fn enabled_for(state: &AppState, user_id: i32) -> Result<BTreeMap<String, bool>> {
+    let mut features: BTreeMap<String, bool> = FEATURES
+        .iter()
+        .map(|feature| (feature.name.to_string(), feature.default))
+        .collect();
+    for flag in load(state).await? {
+        let enabled = evaluate(&flag, user_id);
+        features.insert(flag.name, enabled);
+    }
+
+    Ok(features)
+}
+
+/// The stored flags, from the cache when possible
+async fn load(state: &AppState) -> Result<Vec<FeatureFlag>> {
+    if let Some(flags) = cached(state).await {
+        return Ok(flags);
+    }
+
+    let flags = FeatureFlagRepository::new(&state.pg_pool)
+        .get_flags()
+        .await?;
+    cache(state, &flags).await;
+
+    Ok(flags)
+}
+
+async fn cached(state: &AppState) -> Option<Vec<FeatureFlag>> {
+    let mut conn = state.redis_pool.get().await.ok()?;
+    let payload: Option<String> = conn.get(CACHE_KEY).await.ok()?;
+    serde_json::from_str(&payload?).ok()
+}
+
+async fn cache(state: &AppState, flags: &[FeatureFlag]) {
+    let payload = match serde_json::to_string(flags) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::error!("Failed to encode feature flags: {}", e);
+            return;
+        }
+    };
+
+    let result = match state.redis_pool.get().await {
+        Ok(mut conn) => conn
+            .set_ex::<_, _, ()>(CACHE_KEY, payload, CACHE_TTL_SECS)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        tracing::warn!("Failed to cache feature flags: {}", e);
+    }
+}
+
+/// Drop the cached flags after a change, so every instance reloads them
+pub async fn invalidate(state: &AppState) {
+    let result = match state.redis_pool.get().await {
+        Ok(mut conn) => conn
+            .del::<_, ()>(CACHE_KEY)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        tracing::warn!("Failed to invalidate cached feature flags: {}", e);
+    }
+}
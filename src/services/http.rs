@@ -0,0 +1,402 @@
+//! # Outgoing HTTP
+//!
+//! Requests the server makes itself, polling the REST price provider and
+//! delivering webhooks. The client is hyper's with rustls and Mozilla's root
+//! certificates, so the server does not build the typed client's HTTP stack,
+//! which only the `client` feature enables. Redirects are never followed and
+//! every request, including reading the whole answer, has to finish within
+//! the client's timeout.
+//!
+//! Webhook URLs are chosen by users, so their deliveries use a [`public`]
+//! client: it only speaks HTTPS and refuses hosts resolving to any address
+//! that is not on the public internet (see [`is_public`]). The check runs on
+//! the addresses the connection is then made to, so a DNS answer changing
+//! between the check and the connection cannot get around it.
+//!
+//! [`public`]: HttpClient::public
+
+use std::{
+    error::Error as StdError,
+    fmt, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_util::future::BoxFuture;
+use http_body_util::{BodyExt, Full};
+use hyper::{Method, Request, StatusCode, Uri, body::Bytes, header};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::{
+    client::legacy::{
+        Client,
+        connect::{HttpConnector, dns::Name},
+    },
+    rt::TokioExecutor,
+};
+use tower_service::Service;
+
+/// `User-Agent` of every request
+const USER_AGENT: &str = concat!("stock-exchange-sim-core/", env!("CARGO_PKG_VERSION"));
+
+/// Why a request got no answer
+#[derive(Debug)]
+pub enum HttpError {
+    /// The URL cannot be requested
+    InvalidUrl(String),
+    /// No complete answer within the timeout
+    Timeout(Duration),
+    /// Connecting, sending or reading the answer failed
+    Failed(String),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::InvalidUrl(reason) => write!(f, "Invalid URL: {}", reason),
+            HttpError::Timeout(timeout) => {
+                write!(f, "No answer within {} seconds", timeout.as_secs())
+            }
+            HttpError::Failed(reason) => f.write_str(reason),
+        }
+    }
+}
+
+/// An answer with its whole body
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub body: Bytes,
+}
+
+/// Client for outgoing requests
+#[derive(Clone)]
+pub struct HttpClient {
+    client: Client<HttpsConnector<HttpConnector<Resolver>>, Full<Bytes>>,
+    timeout: Duration,
+    public_only: bool,
+}
+
+impl HttpClient {
+    /// A client giving up on requests after `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        HttpClient::build(timeout, false)
+    }
+
+    /// Like [`HttpClient::new`], only making HTTPS requests to public addresses
+    pub fn public(timeout: Duration) -> Self {
+        HttpClient::build(timeout, true)
+    }
+
+    fn build(timeout: Duration, public_only: bool) -> Self {
+        let mut http = HttpConnector::new_with_resolver(Resolver { public_only });
+        // The TLS connector decides which schemes are allowed
+        http.enforce_http(false);
+        http.set_connect_timeout(Some(timeout));
+        let https = HttpsConnectorBuilder::new()
+            .with_provider_and_webpki_roots(rustls::crypto::ring::default_provider())
+            .expect("ring supports the default protocol versions");
+        let https = if public_only {
+            https.https_only()
+        } else {
+            https.https_or_http()
+        };
+        let https = https.enable_http1().wrap_connector(http);
+
+        HttpClient {
+            client: Client::builder(TokioExecutor::new()).build(https),
+            timeout,
+            public_only,
+        }
+    }
+
+    /// `GET` `url`
+    pub async fn get(&self, url: &str) -> Result<HttpResponse, HttpError> {
+        self.send(Method::GET, url, &[], Bytes::new()).await
+    }
+
+    /// `POST` `body` to `url` with extra `headers`
+    pub async fn post(
+        &self,
+        url: &str,
+        headers: &[(&str, String)],
+        body: impl Into<Bytes>,
+    ) -> Result<HttpResponse, HttpError> {
+        self.send(Method::POST, url, headers, body.into()).await
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        url: &str,
+        headers: &[(&str, String)],
+        body: Bytes,
+    ) -> Result<HttpResponse, HttpError> {
+        let uri: Uri = url
+            .parse()
+            .map_err(|e: hyper::http::uri::InvalidUri| HttpError::InvalidUrl(e.to_string()))?;
+        if self.public_only {
+            if uri.scheme_str() != Some("https") {
+                return Err(HttpError::InvalidUrl("only https is allowed".to_string()));
+            }
+            // Addresses in the URL are connected to without asking the resolver
+            let host = uri.host().unwrap_or_default();
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            if let Ok(ip) = host.parse::<IpAddr>() {
+                if !is_public(ip) {
+                    return Err(HttpError::InvalidUrl(format!(
+                        "{} is not a public address",
+                        ip
+                    )));
+                }
+            }
+        }
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::USER_AGENT, USER_AGENT);
+        for (name, value) in headers {
+            request = request.header(*name, value.as_str());
+        }
+        let request = request
+            .body(Full::new(body))
+            .map_err(|e| HttpError::InvalidUrl(e.to_string()))?;
+
+        let exchange = async {
+            let response = self
+                .client
+                .request(request)
+                .await
+                .map_err(|e| HttpError::Failed(describe(&e)))?;
+            let status = response.status();
+            let body = response
+                .into_body()
+                .collect()
+                .await
+                .map_err(|e| HttpError::Failed(describe(&e)))?
+                .to_bytes();
+            Ok(HttpResponse { status, body })
+        };
+
+        tokio::time::timeout(self.timeout, exchange)
+            .await
+            .unwrap_or(Err(HttpError::Timeout(self.timeout)))
+    }
+}
+
+/// Whether `ip` is an address on the public internet
+///
+/// Loopback, private, shared (carrier-grade NAT), link-local, unique-local,
+/// unspecified, broadcast, multicast, documentation and reserved addresses
+/// are not, and neither are IPv6 addresses embedding an IPv4 address that is
+/// not (IPv4-mapped, IPv4-compatible and NAT64).
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        // "This network", carrier-grade NAT, IETF protocol assignments,
+        // benchmarking and the reserved block above multicast
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 192 && b == 0 && ip.octets()[2] == 0)
+        || (a == 198 && (18..20).contains(&b))
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    // IPv4-mapped, IPv4-compatible and NAT64 addresses reach the IPv4 address
+    let embedded = match segments {
+        [0, 0, 0, 0, 0, 0xffff, ..] | [0, 0, 0, 0, 0, 0, ..] | [0x64, 0xff9b, 0, 0, 0, 0, ..] => {
+            Some(Ipv4Addr::from(
+                (u32::from(segments[6]) << 16) | u32::from(segments[7]),
+            ))
+        }
+        _ => None,
+    };
+    if let Some(ip) = embedded {
+        return is_public_v4(ip);
+    }
+
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique-local fc00::/7, link-local fe80::/10 and documentation 2001:db8::/32
+        || (segments[0] & 0xfe00) == 0xfc00
+        || (segments[0] & 0xffc0) == 0xfe80
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+}
+
+/// Resolves host names for the connector, refusing for a [`public`] client
+/// any name with an address that is not public
+///
+/// The connector connects to exactly the addresses returned, without
+/// resolving the name again.
+///
+/// [`public`]: HttpClient::public
+#[derive(Clone)]
+struct Resolver {
+    public_only: bool,
+}
+
+impl Service<Name> for Resolver {
+    type Response = std::vec::IntoIter<SocketAddr>;
+    type Error = io::Error;
+    type Future = BoxFuture<'static, io::Result<Self::Response>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let public_only = self.public_only;
+        Box::pin(async move {
+            // The connector sets the port of the URL
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            if public_only {
+                if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        format!("{} resolves to {}, which is not public", name, addr.ip()),
+                    ));
+                }
+            }
+            Ok(addrs.into_iter())
+        })
+    }
+}
+
+/// `error` followed by its causes, which hyper keeps out of its own message
+fn describe(error: &dyn StdError) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// URL of a server answering every connection with `answer` after `delay`
+    async fn serve(answer: &'static str, delay: Duration) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/prices", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = [0; 4096];
+                    let _ = stream.read(&mut request).await;
+                    tokio::time::sleep(delay).await;
+                    let _ = stream.write_all(answer.as_bytes()).await;
+                });
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn answers_are_read_whole() {
+        let url = serve(
+            "HTTP/1.1 404 Not Found\r\ncontent-length: 7\r\nconnection: close\r\n\r\nmissing",
+            Duration::ZERO,
+        )
+        .await;
+
+        let response = HttpClient::new(Duration::from_secs(5))
+            .get(&url)
+            .await
+            .unwrap();
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(&response.body[..], b"missing");
+    }
+
+    #[tokio::test]
+    async fn slow_answers_time_out() {
+        let url = serve(
+            "HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n",
+            Duration::from_secs(5),
+        )
+        .await;
+
+        let error = HttpClient::new(Duration::from_millis(100))
+            .post(&url, &[], "{}")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, HttpError::Timeout(_)), "{error}");
+    }
+
+    #[test]
+    fn only_internet_addresses_are_public() {
+        for public in [
+            "93.184.215.14",
+            "8.8.8.8",
+            "2606:4700::1111",
+            "64:ff9b::808:808",
+        ] {
+            assert!(is_public(public.parse().unwrap()), "{public}");
+        }
+        for internal in [
+            "0.0.0.0",
+            "127.0.0.1",
+            "10.20.30.40",
+            "172.31.255.255",
+            "192.168.0.1",
+            "169.254.169.254",
+            "100.100.100.200",
+            "255.255.255.255",
+            "::",
+            "::1",
+            "fd12:3456::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+            "::10.0.0.1",
+            "64:ff9b::a00:1",
+        ] {
+            assert!(!is_public(internal.parse().unwrap()), "{internal}");
+        }
+    }
+
+    #[tokio::test]
+    async fn public_clients_refuse_internal_hosts() {
+        let url = serve(
+            "HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n",
+            Duration::ZERO,
+        )
+        .await;
+        let client = HttpClient::public(Duration::from_secs(5));
+
+        // Plain http, then the listener itself over https, by address and by name
+        let error = client.post(&url, &[], "{}").await.unwrap_err();
+        assert!(matches!(error, HttpError::InvalidUrl(_)), "{error}");
+        let url = url.replace("http://", "https://");
+        let error = client.post(&url, &[], "{}").await.unwrap_err();
+        assert!(matches!(error, HttpError::InvalidUrl(_)), "{error}");
+        let url = url.replace("127.0.0.1", "localhost");
+        let error = client.post(&url, &[], "{}").await.unwrap_err();
+        assert!(error.to_string().contains("not public"), "{error}");
+    }
+}
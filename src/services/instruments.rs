@@ -0,0 +1,82 @@
+//! # Instrument Catalog
+//!
+//! Only tickers listed in the instrument catalog can be traded, whatever the
+//! price feed publishes. Deactivating an instrument stops new buys while
+//! holders can still sell out of their positions, and every order must be a
+//! whole number of lots. Indexes are quoted for reference and benchmarking
+//! only and cannot be traded.
+//!
+//! The price updater streams prices for every listed ticker; changes to the
+//! set of tickers are announced on a Redis channel so it can resubscribe.
+
+use redis::AsyncCommands;
+
+use crate::{
+    AppState, Error, Result, models::instrument::Instrument,
+    repository::instrument_repository::InstrumentRepository, services::liquidity::Side,
+};
+
+/// Asset classes an instrument may belong to
+pub const ASSET_CLASSES: [&str; 6] = ["equity", "etf", "bond", "commodity", "crypto", INDEX];
+
+/// Asset class of indexes, which are quoted but not traded
+pub const INDEX: &str = "index";
+
+/// Redis channel announcing that tickers were listed, delisted or renamed
+pub const CATALOG_CHANNEL: &str = "instruments:updated";
+
+/// Tell the price updaters that the set of listed tickers changed
+///
+/// The catalog change itself is already committed, so a failed announcement
+/// is only logged; updaters pick the change up when they next reconnect.
+pub async fn announce_catalog_change(state: &AppState) {
+    let published = async {
+        state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?
+            .publish::<_, _, ()>(CATALOG_CHANNEL, chrono::Utc::now().to_rfc3339())
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))
+    }
+    .await;
+
+    if let Err(e) = published {
+        tracing::warn!("Failed to announce instrument catalog change: {}", e);
+    }
+}
+
+/// Validate an order for `quantity` shares of `ticker` against the catalog
+pub async fn check_tradable(
+    state: &AppState,
+    ticker: &str,
+    side: Side,
+    quantity: i32,
+) -> Result<Instrument> {
+    let instrument = InstrumentRepository::new(&state.pg_pool)
+        .get_instrument(ticker)
+        .await?
+        .ok_or_else(|| Error::BadRequest(format!("Unknown ticker {}", ticker)))?;
+
+    if instrument.asset_class == INDEX {
+        return Err(Error::BadRequest(format!(
+            "{} is an index and cannot be traded",
+            ticker
+        )));
+    }
+    if side == Side::Buy && !instrument.active {
+        return Err(Error::BadRequest(format!(
+            "{} is inactive and can only be sold",
+            ticker
+        )));
+    }
+    if quantity % instrument.lot_size != 0 {
+        return Err(Error::BadRequest(format!(
+            "Quantity must be a multiple of the lot size of {}",
+            instrument.lot_size
+        )));
+    }
+
+    Ok(instrument)
+}
@@ -0,0 +1,218 @@
+//! # Leaderboard
+//!
+//! Users ranked by the time-weighted return of their account over the last
+//! day, week or all of their history. Ranking by return rather than equity
+//! keeps deposits from buying a place: cash moved in or out is netted out of
+//! each day's return.
+//!
+//! Each period is a Redis sorted set, `leaderboard:{period}`, of user IDs
+//! scored by their return. The snapshot worker rebuilds them after every
+//! daily capture, so every instance serves the same ranking. Users need two
+//! snapshots in a period to be ranked in it, and users who opted out are
+//! left out.
+
+use chrono::{Days, NaiveDate};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    AppState, Error, Result, repository::user_settings_repository::UserSettingsRepository,
+    services::metrics,
+};
+
+/// Time span users are ranked over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    /// Since the previous daily snapshot
+    Day,
+    /// The last seven days
+    #[default]
+    Week,
+    /// The whole history of the account
+    All,
+}
+
+impl Period {
+    pub const ALL: [Period; 3] = [Period::Day, Period::Week, Period::All];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::All => "all",
+        }
+    }
+
+    /// First snapshot date of the period ending on `today`
+    fn start(&self, today: NaiveDate) -> NaiveDate {
+        let days = match self {
+            Period::Day => 1,
+            Period::Week => 7,
+            // Before any snapshot
+            Period::All => return NaiveDate::from_ymd_opt(2000, 1, 1).unwrap_or(today),
+        };
+        today.checked_sub_days(Days::new(days)).unwrap_or(today)
+    }
+}
+
+fn ranking_key(period: Period) -> String {
+    format!("leaderboard:{}", period.as_str())
+}
+
+/// A user's place on the leaderboard
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ranking {
+    /// 1 for the best return
+    pub rank: u64,
+    pub user_id: i32,
+    /// Fraction, 0.05 = 5%
+    pub time_weighted_return: f64,
+}
+
+/// Rank every user who has not opted out by their returns up to `today`
+///
+/// Returns the number of users ranked over the whole history.
+pub async fn rebuild(state: &AppState, today: NaiveDate) -> Result<usize> {
+    let user_ids = UserSettingsRepository::new(&state.pg_pool)
+        .get_leaderboard_user_ids()
+        .await?;
+
+    let mut scores: Vec<Vec<(f64, i32)>> = vec![Vec::new(); Period::ALL.len()];
+    for user_id in user_ids {
+        for (period, period_scores) in Period::ALL.iter().zip(scores.iter_mut()) {
+            match user_return(state, user_id, *period, today).await {
+                Ok(Some(score)) => period_scores.push((score, user_id)),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Skipping user {} on the leaderboard: {}", user_id, e),
+            }
+        }
+    }
+
+    let mut conn = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    for (period, period_scores) in Period::ALL.iter().zip(&scores) {
+        let key = ranking_key(*period);
+        let staging = format!("{}:rebuild", key);
+        // Swap the new ranking in whole, so readers never see a partial one
+        let mut pipe = redis::pipe();
+        pipe.atomic().del(&staging).ignore();
+        if period_scores.is_empty() {
+            pipe.del(&key).ignore();
+        } else {
+            pipe.zadd_multiple(&staging, period_scores)
+                .ignore()
+                .rename(&staging, &key)
+                .ignore();
+        }
+        pipe.query_async::<()>(&mut *conn)
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+    }
+
+    Ok(scores.last().map_or(0, Vec::len))
+}
+
+/// Rank a single user again, e.g. after opting back in
+pub async fn refresh_user(state: &AppState, user_id: i32, today: NaiveDate) -> Result<()> {
+    let mut conn = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    for period in Period::ALL {
+        let key = ranking_key(period);
+        let result = match user_return(state, user_id, period, today).await? {
+            Some(score) => conn.zadd::<_, _, _, ()>(&key, user_id, score).await,
+            None => conn.zrem::<_, _, ()>(&key, user_id).await,
+        };
+        result.map_err(|e| Error::RedisError(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Take a user off every ranking at once, e.g. after opting out
+pub async fn remove_user(state: &AppState, user_id: i32) -> Result<()> {
+    let mut conn = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let mut pipe = redis::pipe();
+    for period in Period::ALL {
+        pipe.zrem(ranking_key(period), user_id).ignore();
+    }
+    pipe.query_async::<()>(&mut *conn)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// The `limit` best returns of the period, best first
+pub async fn top(state: &AppState, period: Period, limit: usize) -> Result<Vec<Ranking>> {
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+    let mut conn = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let entries: Vec<(i32, f64)> = conn
+        .zrevrange_withscores(ranking_key(period), 0, limit as isize - 1)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    Ok(entries
+        .into_iter()
+        .zip(1..)
+        .map(|((user_id, time_weighted_return), rank)| Ranking {
+            rank,
+            user_id,
+            time_weighted_return,
+        })
+        .collect())
+}
+
+/// The user's place in the period, `None` when unranked
+pub async fn rank_of(state: &AppState, period: Period, user_id: i32) -> Result<Option<Ranking>> {
+    let key = ranking_key(period);
+    let mut conn = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let (rank, score): (Option<u64>, Option<f64>) = redis::pipe()
+        .zrevrank(&key, user_id)
+        .zscore(&key, user_id)
+        .query_async(&mut *conn)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    Ok(rank.zip(score).map(|(rank, time_weighted_return)| Ranking {
+        rank: rank + 1,
+        user_id,
+        time_weighted_return,
+    }))
+}
+
+/// Time-weighted return of the user's account over the period
+async fn user_return(
+    state: &AppState,
+    user_id: i32,
+    period: Period,
+    today: NaiveDate,
+) -> Result<Option<f64>> {
+    let performance =
+        metrics::performance(state, user_id, period.start(today), today, None).await?;
+
+    Ok(performance
+        .map(|metrics| metrics.time_weighted_return)
+        .filter(|r| r.is_finite()))
+}
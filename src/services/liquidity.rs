@@ -0,0 +1,405 @@
+//! # Liquidity Model
+//!
+//! Each ticker has a liquidity profile describing how much stock sits near the
+//! mid price (`depth`), how wide the quoted spread is (`spread_bps`) and how
+//! quickly the book refills after being hit (`resilience`). Together with the
+//! last price they describe a synthetic level-2 book: price levels one spread
+//! apart, starting half a spread from the mid price, each holding `depth`
+//! shares. Market orders walk that book level by level and fill at the
+//! volume-weighted price of the levels they take. Volume consumed by recent
+//! trades is tracked per side in Redis and decays according to the resilience,
+//! so a burst of orders in a thin small-cap moves the price noticeably more
+//! than in a deep mega-cap, and the published depth shows the thinned book.
+
+use std::collections::HashMap;
+
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode};
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use serde::Serialize;
+use tracing::Instrument;
+use utoipa::ToSchema;
+
+use crate::{
+    AppState, Error, Result,
+    models::liquidity_profile::LiquidityProfile,
+    repository::liquidity_profile_repository::LiquidityProfileRepository,
+    services::quotes,
+    timing::{self, Phase},
+};
+
+/// Depth used for tickers without a configured profile
+pub const DEFAULT_DEPTH: i32 = 10_000;
+/// Spread used for tickers without a configured profile
+pub const DEFAULT_SPREAD_BPS: f64 = 5.0;
+/// Resilience used for tickers without a configured profile
+pub const DEFAULT_RESILIENCE: f64 = 0.1;
+/// Upper bound on slippage as a fraction of the mid price
+pub const MAX_SLIPPAGE: f64 = 0.5;
+/// Price levels per side of the published book when not specified
+pub const DEFAULT_BOOK_LEVELS: usize = 10;
+/// Decimal places of executed prices, matching the transactions table
+const PRICE_SCALE: i64 = 2;
+
+/// Side of the book a trade takes liquidity from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        }
+    }
+}
+
+/// Profile parameters used by the slippage model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiquidityParams {
+    pub depth: i32,
+    pub spread_bps: f64,
+    pub resilience: f64,
+}
+
+impl Default for LiquidityParams {
+    fn default() -> Self {
+        LiquidityParams {
+            depth: DEFAULT_DEPTH,
+            spread_bps: DEFAULT_SPREAD_BPS,
+            resilience: DEFAULT_RESILIENCE,
+        }
+    }
+}
+
+impl From<&LiquidityProfile> for LiquidityParams {
+    fn from(profile: &LiquidityProfile) -> Self {
+        LiquidityParams {
+            depth: profile.depth,
+            spread_bps: profile.spread_bps,
+            resilience: profile.resilience,
+        }
+    }
+}
+
+/// Liquidity parameters for a ticker, falling back to the defaults
+pub async fn params_for(state: &AppState, ticker: &str) -> Result<LiquidityParams> {
+    let profile = LiquidityProfileRepository::new(&state.pg_pool)
+        .get_profile(ticker)
+        .await?;
+
+    Ok(profile.as_ref().map(Into::into).unwrap_or_default())
+}
+
+/// Volume still missing from the book `elapsed_secs` after `consumed` shares were taken
+pub fn decayed_consumption(consumed: f64, elapsed_secs: f64, resilience: f64) -> f64 {
+    consumed * (1.0 - resilience).powf(elapsed_secs.max(0.0))
+}
+
+/// Distance of the `level`th price level from the mid price, as a fraction of it
+fn level_offset(params: &LiquidityParams, level: f64) -> f64 {
+    let spread = params.spread_bps / 10_000.0;
+    (spread / 2.0 + spread * level).min(MAX_SLIPPAGE)
+}
+
+/// Average slippage, as a fraction of the mid price, of taking `quantity` shares
+/// from a book that is already missing `consumed` shares
+///
+/// The order walks the levels of the book, so shares filled within the same
+/// level pay the same price.
+pub fn slippage_fraction(params: &LiquidityParams, consumed: f64, quantity: i32) -> f64 {
+    let depth = f64::from(params.depth.max(1));
+    let mut position = consumed.max(0.0);
+    if quantity <= 0 {
+        return level_offset(params, (position / depth).floor());
+    }
+
+    let end = position + f64::from(quantity);
+    let mut cost = 0.0;
+    while position < end {
+        let level = (position / depth).floor();
+        let offset = level_offset(params, level);
+        // Past the slippage cap every level has the same price
+        let level_end = if offset >= MAX_SLIPPAGE {
+            end
+        } else {
+            ((level + 1.0) * depth).min(end)
+        };
+        cost += offset * (level_end - position);
+        position = level_end;
+    }
+
+    cost / f64::from(quantity)
+}
+
+/// Apply slippage to the mid price for the given side
+pub fn apply_slippage(mid: &BigDecimal, side: Side, slippage: f64) -> BigDecimal {
+    let factor = match side {
+        Side::Buy => 1.0 + slippage,
+        Side::Sell => 1.0 - slippage,
+    };
+    let factor = BigDecimal::from_f64(factor).unwrap_or_else(|| BigDecimal::from(1));
+
+    (mid * factor).with_scale_round(PRICE_SCALE, RoundingMode::HalfUp)
+}
+
+/// A price level of the synthetic book
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct BookLevel {
+    pub price: BigDecimal,
+    pub quantity: i64,
+}
+
+/// Synthetic level-2 book of a ticker around its last price
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MarketDepth {
+    pub ticker: String,
+    /// Last price the book is centred on
+    pub mid: BigDecimal,
+    /// When the last price was published, if known
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Buy orders, best (highest) price first
+    pub bids: Vec<BookLevel>,
+    /// Sell orders, best (lowest) price first
+    pub asks: Vec<BookLevel>,
+}
+
+/// The first `levels` levels a taker on `side` can fill against, best first,
+/// once `consumed` shares have been taken
+///
+/// Levels past the slippage cap are merged into the last one shown.
+pub fn book_side(
+    params: &LiquidityParams,
+    mid: &BigDecimal,
+    side: Side,
+    consumed: f64,
+    levels: usize,
+) -> Vec<BookLevel> {
+    let depth = f64::from(params.depth.max(1));
+    let consumed = consumed.max(0.0);
+    let first = (consumed / depth).floor();
+
+    let mut book = Vec::with_capacity(levels);
+    for level in (0..).map(|n| first + f64::from(n)) {
+        if book.len() >= levels {
+            break;
+        }
+        let offset = level_offset(params, level);
+        let remaining = ((level + 1.0) * depth - consumed).min(depth).floor() as i64;
+        if remaining > 0 {
+            book.push(BookLevel {
+                price: apply_slippage(mid, side, offset),
+                quantity: remaining,
+            });
+        }
+        if offset >= MAX_SLIPPAGE {
+            break;
+        }
+    }
+
+    book
+}
+
+/// Redis hash tracking the volume recently taken from one side of a ticker's book
+fn consumption_key(ticker: &str, side: Side) -> String {
+    format!("liquidity:{}:{}", ticker, side.as_str())
+}
+
+/// Shares still missing from the book at `now`, per a consumption hash
+fn current_consumption(book: &HashMap<String, f64>, now: f64, resilience: f64) -> f64 {
+    match (book.get("consumed"), book.get("updated_at")) {
+        (Some(consumed), Some(updated_at)) => {
+            decayed_consumption(*consumed, now - updated_at, resilience)
+        }
+        _ => 0.0,
+    }
+}
+
+fn now_secs() -> f64 {
+    Utc::now().timestamp_millis() as f64 / 1000.0
+}
+
+/// The synthetic book of `ticker` around its latest quote
+///
+/// Returns `None` for tickers without any price.
+pub async fn market_depth(
+    state: &AppState,
+    ticker: &str,
+    levels: usize,
+) -> Result<Option<MarketDepth>> {
+    let Some(quote) = quotes::quotes(state, &[ticker.to_string()]).await?.pop() else {
+        return Ok(None);
+    };
+    let params = params_for(state, ticker).await?;
+
+    let (bought, sold): (HashMap<String, f64>, HashMap<String, f64>) = async {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        redis::pipe()
+            .hgetall(consumption_key(ticker, Side::Buy))
+            .hgetall(consumption_key(ticker, Side::Sell))
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await?;
+
+    // Buyers take from the asks and sellers from the bids
+    let now = now_secs();
+    let asks = book_side(
+        &params,
+        &quote.price,
+        Side::Buy,
+        current_consumption(&bought, now, params.resilience),
+        levels,
+    );
+    let bids = book_side(
+        &params,
+        &quote.price,
+        Side::Sell,
+        current_consumption(&sold, now, params.resilience),
+        levels,
+    );
+
+    Ok(Some(MarketDepth {
+        ticker: quote.ticker,
+        mid: quote.price,
+        timestamp: quote.timestamp,
+        bids,
+        asks,
+    }))
+}
+
+/// Price at which a market order of `quantity` shares executes
+///
+/// Reads the recent consumption for the ticker and side from Redis, walks the
+/// synthetic book from there and records the newly consumed volume.
+pub async fn execution_price(
+    state: &AppState,
+    ticker: &str,
+    side: Side,
+    mid: &BigDecimal,
+    quantity: i32,
+) -> Result<BigDecimal> {
+    let params = params_for(state, ticker).await?;
+
+    let key = consumption_key(ticker, side);
+    let (mut conn, book) = async {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        let book: HashMap<String, f64> = conn
+            .hgetall(&key)
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        Ok::<_, Error>((conn, book))
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await?;
+
+    let now = now_secs();
+    let consumed = current_consumption(&book, now, params.resilience);
+
+    let slippage = slippage_fraction(&params, consumed, quantity);
+
+    conn.hset_multiple::<_, _, _, ()>(
+        &key,
+        &[
+            ("consumed", consumed + f64::from(quantity)),
+            ("updated_at", now),
+        ],
+    )
+    .instrument(timing::span(Phase::Redis))
+    .await
+    .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    Ok(apply_slippage(mid, side, slippage))
+}
+
+#[cfg(test)]
+mod tests {
+    use bigdecimal::Zero;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// Floating point slack when comparing slippage fractions
+    const EPSILON: f64 = 1e-12;
+
+    fn params() -> impl Strategy<Value = LiquidityParams> {
+        (1..50_000i32, 0.0..500.0f64, 0.0..1.0f64).prop_map(|(depth, spread_bps, resilience)| {
+            LiquidityParams {
+                depth,
+                spread_bps,
+                resilience,
+            }
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn fills_are_priced_within_the_levels_they_walk(
+            params in params(),
+            consumed in 0.0..1_000_000.0f64,
+            quantity in 1..100_000i32,
+        ) {
+            let depth = f64::from(params.depth);
+            let first = level_offset(&params, (consumed / depth).floor());
+            let last = level_offset(&params, ((consumed + f64::from(quantity) - 1.0) / depth).floor());
+            let slippage = slippage_fraction(&params, consumed, quantity);
+
+            prop_assert!(slippage >= first - EPSILON, "{} below {}", slippage, first);
+            prop_assert!(slippage <= last + EPSILON, "{} above {}", slippage, last);
+            prop_assert!(slippage <= MAX_SLIPPAGE + EPSILON);
+        }
+
+        #[test]
+        fn larger_orders_never_fill_better(
+            params in params(),
+            consumed in 0.0..1_000_000.0f64,
+            quantity in 1..100_000i32,
+            more in 0..100_000i32,
+        ) {
+            let smaller = slippage_fraction(&params, consumed, quantity);
+            let larger = slippage_fraction(&params, consumed, quantity + more);
+            prop_assert!(larger >= smaller - EPSILON, "{} below {}", larger, smaller);
+        }
+
+        #[test]
+        fn takers_never_beat_the_mid_price(
+            cents in 1i64..10_000_000,
+            slippage in 0.0..=MAX_SLIPPAGE,
+        ) {
+            let mid = BigDecimal::new(cents.into(), PRICE_SCALE);
+            let bought = apply_slippage(&mid, Side::Buy, slippage);
+            let sold = apply_slippage(&mid, Side::Sell, slippage);
+
+            prop_assert!(bought >= mid);
+            prop_assert!(sold <= mid);
+            prop_assert!(sold >= BigDecimal::zero());
+            prop_assert_eq!(bought.fractional_digit_count(), PRICE_SCALE);
+        }
+
+        #[test]
+        fn the_book_refills_over_time(
+            consumed in 0.0..1_000_000.0f64,
+            elapsed in 0.0..10_000.0f64,
+            later in 0.0..10_000.0f64,
+            resilience in 0.0..=1.0f64,
+        ) {
+            let now = decayed_consumption(consumed, elapsed, resilience);
+            let then = decayed_consumption(consumed, elapsed + later, resilience);
+            prop_assert!((0.0..=consumed).contains(&now));
+            prop_assert!(then <= now);
+        }
+    }
+}
@@ -0,0 +1,441 @@
+//! # Secured Loans
+//!
+//! Users borrow cash against holdings they pledge as collateral; each loan
+//! belongs to the portfolio holding the collateral, and its cash is credited
+//! to and repaid from that portfolio. A loan may be
+//! taken up to `LOAN_MAX_LTV_PERCENT` of the collateral's market value and
+//! accrues `LOAN_INTEREST_PERCENT` per year, or nothing when opened under the
+//! `beginner` difficulty. Pledged shares cannot be sold while the loan is
+//! open, and outstanding debt is deducted from equity.
+//!
+//! The margin worker re-values every open loan once a minute. When the
+//! loan-to-value reaches `LOAN_MARGIN_CALL_LTV_PERCENT`, pledged shares are
+//! sold as market orders under the portfolio's difficulty until the loan is
+//! back at the maximum loan-to-value; sale proceeds repay the loan. A loan whose collateral is sold off entirely
+//! is closed as liquidated and any shortfall is written off.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode, ToPrimitive, Zero};
+use redis::{AsyncCommands, ExistenceCheck, SetExpiry, SetOptions};
+
+use crate::{
+    AppState, Error, Result,
+    models::{
+        loan::{Loan, LoanCollateral},
+        portfolio::Portfolio,
+    },
+    repository::{
+        ledger_repository::{LedgerRepository, TradePosting},
+        loan_repository::LoanRepository,
+        portfolio_repository::PortfolioRepository,
+    },
+    services::{liquidity::Side, matching, positions, price_cache::PriceCache, rules, sweep},
+    ws::{events, messages::AccountEvent},
+};
+
+/// How often open loans are re-valued
+pub const MARGIN_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A loan with its collateral valued at the latest prices
+#[derive(Debug, Clone)]
+pub struct LoanValuation {
+    pub loan: Loan,
+    pub collateral: Vec<LoanCollateral>,
+    /// Latest price of each collateral ticker that has one
+    pub prices: HashMap<String, BigDecimal>,
+    pub collateral_value: BigDecimal,
+    /// Outstanding debt over collateral value in percent; `None` when a
+    /// collateral price is missing or nothing is pledged
+    pub ltv_percent: Option<f64>,
+}
+
+/// Value loans and their collateral at the latest cached prices
+pub async fn value_loans(state: &AppState, loans: Vec<Loan>) -> Result<Vec<LoanValuation>> {
+    let loan_ids: Vec<i32> = loans.iter().map(|loan| loan.id).collect();
+    let collateral = LoanRepository::new(&state.pg_pool)
+        .get_collateral(&loan_ids)
+        .await?;
+
+    let mut tickers: Vec<String> = collateral.iter().map(|c| c.ticker.clone()).collect();
+    tickers.sort();
+    tickers.dedup();
+    let prices = PriceCache::new(&state.redis_pool).prices(&tickers).await?;
+
+    Ok(loans
+        .into_iter()
+        .map(|loan| {
+            let collateral: Vec<LoanCollateral> = collateral
+                .iter()
+                .filter(|c| c.loan_id == loan.id && c.quantity > 0)
+                .cloned()
+                .collect();
+            let prices: HashMap<String, BigDecimal> = collateral
+                .iter()
+                .filter_map(|c| Some((c.ticker.clone(), prices.get(&c.ticker)?.clone())))
+                .collect();
+            let collateral_value = collateral_value(&collateral, &prices);
+            let priced = collateral.iter().all(|c| prices.contains_key(&c.ticker));
+            let ltv_percent = match collateral_value.to_f64() {
+                Some(value) if priced && value > 0.0 => loan
+                    .outstanding
+                    .to_f64()
+                    .map(|outstanding| outstanding / value * 100.0),
+                _ => None,
+            };
+
+            LoanValuation {
+                loan,
+                collateral,
+                prices,
+                collateral_value,
+                ltv_percent,
+            }
+        })
+        .collect())
+}
+
+/// Borrow `amount` against the pledged `collateral` (ticker and share count)
+/// held in `portfolio`
+pub async fn borrow(
+    state: &AppState,
+    portfolio: &Portfolio,
+    amount: BigDecimal,
+    collateral: &[(String, i32)],
+) -> Result<LoanValuation> {
+    let holdings_repository = &state.repos.holdings;
+    let loan_repository = LoanRepository::new(&state.pg_pool);
+
+    for (index, (ticker, quantity)) in collateral.iter().enumerate() {
+        if collateral[..index].iter().any(|(other, _)| other == ticker) {
+            return Err(Error::BadRequest(format!("{} is pledged twice", ticker)));
+        }
+
+        let held = holdings_repository
+            .get_holding_by_portfolio_and_ticker(portfolio.id, ticker)
+            .await?
+            .map(|holding| holding.quantity)
+            .unwrap_or(0);
+        let pledged = loan_repository
+            .get_pledged_quantity(portfolio.id, ticker)
+            .await?;
+        if held - pledged < *quantity {
+            return Err(Error::BadRequest(format!(
+                "Insufficient unpledged shares of {}",
+                ticker
+            )));
+        }
+    }
+
+    let tickers: Vec<String> = collateral
+        .iter()
+        .map(|(ticker, _)| ticker.clone())
+        .collect();
+    let prices = PriceCache::new(&state.redis_pool).prices(&tickers).await?;
+    if let Some(ticker) = tickers.iter().find(|t| !prices.contains_key(*t)) {
+        return Err(Error::BadRequest(format!(
+            "No price available for {}",
+            ticker
+        )));
+    }
+
+    let pledged: Vec<LoanCollateral> = collateral
+        .iter()
+        .map(|(ticker, quantity)| LoanCollateral {
+            loan_id: 0,
+            ticker: ticker.clone(),
+            quantity: *quantity,
+        })
+        .collect();
+    let max_loan =
+        collateral_value(&pledged, &prices) * ltv_fraction(state.config.loan_max_ltv_percent);
+    if amount > max_loan {
+        return Err(Error::BadRequest(format!(
+            "Loan exceeds the maximum loan-to-value of {}%",
+            state.config.loan_max_ltv_percent
+        )));
+    }
+
+    let rules = rules::for_portfolio(state, portfolio).await?;
+    let loan = loan_repository
+        .create_loan(
+            portfolio.user_id,
+            portfolio.id,
+            amount.clone(),
+            rules.loan_interest_percent,
+        )
+        .await?;
+    for (ticker, quantity) in collateral {
+        loan_repository
+            .add_collateral(loan.id, ticker, *quantity)
+            .await?;
+    }
+    LedgerRepository::new(&state.pg_pool)
+        .post(portfolio.id, "loan", amount, None)
+        .await?;
+
+    tracing::info!("User {} opened loan {}", portfolio.user_id, loan.id);
+
+    Ok(value_loans(state, vec![loan]).await?.remove(0))
+}
+
+/// Repay up to `amount` of the user's loan from the cash of its portfolio
+///
+/// Paying at least the outstanding amount (rounded up to cents) closes the
+/// loan and releases the collateral; only what is owed is charged.
+pub async fn repay(
+    state: &AppState,
+    user_id: i32,
+    loan_id: i32,
+    amount: BigDecimal,
+) -> Result<Loan> {
+    let loan_repository = LoanRepository::new(&state.pg_pool);
+    let portfolios_repository = PortfolioRepository::new(&state.pg_pool);
+
+    let loan = loan_repository
+        .get_loan(loan_id)
+        .await?
+        .filter(|loan| loan.user_id == user_id)
+        .ok_or(Error::NotFound)?;
+    if loan.status != "open" {
+        return Err(Error::Conflict("Loan is not open".into()));
+    }
+
+    let payoff = loan.outstanding.with_scale_round(2, RoundingMode::Up);
+    let charge = if amount < payoff { amount } else { payoff };
+    let reduction = if charge < loan.outstanding {
+        charge.clone()
+    } else {
+        loan.outstanding.clone()
+    };
+
+    let portfolio = portfolios_repository
+        .get_portfolio(loan.portfolio_id)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let cash = sweep::sweep_out(state, &portfolio, &charge).await?;
+    if charge > cash {
+        return Err(Error::BadRequest(
+            "Insufficient balance for this repayment".into(),
+        ));
+    }
+
+    let loan = loan_repository
+        .repay(loan_id, reduction)
+        .await?
+        .ok_or_else(|| Error::Conflict("Loan changed during repayment, try again".into()))?;
+    LedgerRepository::new(&state.pg_pool)
+        .post(portfolio.id, "loan", -charge, None)
+        .await?;
+
+    Ok(loan)
+}
+
+/// Accrue interest and enforce margin calls every `MARGIN_CHECK_INTERVAL`
+pub async fn margin_worker(state: Arc<AppState>) -> Result<()> {
+    let mut interval = tokio::time::interval(MARGIN_CHECK_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+
+        // Only one instance checks margins per interval
+        match claim_margin_check(&state).await {
+            Ok(true) => {
+                if let Err(e) = check_margins(&state).await {
+                    tracing::error!("Failed to check loan margins: {}", e);
+                }
+            }
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Failed to claim loan margin check: {}", e),
+        }
+    }
+}
+
+/// Accrue interest on open loans and liquidate collateral of loans over the
+/// margin call loan-to-value
+pub async fn check_margins(state: &AppState) -> Result<()> {
+    let repository = LoanRepository::new(&state.pg_pool);
+    repository.accrue_interest().await?;
+
+    let loans = repository.get_open_loans().await?;
+    for valuation in value_loans(state, loans).await? {
+        let over_limit = valuation
+            .ltv_percent
+            .is_some_and(|ltv| ltv >= state.config.loan_margin_call_ltv_percent);
+        if !over_limit {
+            continue;
+        }
+
+        tracing::warn!(
+            "Margin call on loan {} at {:.2}% loan-to-value",
+            valuation.loan.id,
+            valuation.ltv_percent.unwrap_or_default()
+        );
+        let event = AccountEvent::MarginCall {
+            portfolio_id: valuation.loan.portfolio_id,
+            loan_id: valuation.loan.id,
+            ltv_percent: valuation.ltv_percent.unwrap_or_default(),
+        };
+        events::publish(state, valuation.loan.user_id, event).await;
+        if let Err(e) = liquidate(state, &valuation).await {
+            tracing::error!("Failed to liquidate loan {}: {}", valuation.loan.id, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Sell pledged shares until the loan is back at the maximum loan-to-value
+async fn liquidate(state: &AppState, valuation: &LoanValuation) -> Result<()> {
+    let loan = &valuation.loan;
+    let loan_repository = LoanRepository::new(&state.pg_pool);
+    let holdings_repository = &state.repos.holdings;
+    let transactions_repository = &state.repos.transactions;
+    let ledger_repository = LedgerRepository::new(&state.pg_pool);
+    let portfolios_repository = PortfolioRepository::new(&state.pg_pool);
+
+    let portfolio = portfolios_repository
+        .get_portfolio(loan.portfolio_id)
+        .await?
+        .ok_or(Error::NotFound)?;
+    let rules = rules::for_portfolio(state, &portfolio).await?;
+
+    // Selling x of collateral V to repay debt D reaches the target LTV t when
+    // (D - x) / (V - x) = t, i.e. x = (D - tV) / (1 - t)
+    let target = ltv_fraction(state.config.loan_max_ltv_percent);
+    let mut needed = (&loan.outstanding - &target * &valuation.collateral_value)
+        / (BigDecimal::from(1) - &target);
+    let mut outstanding = loan.outstanding.clone();
+    let mut remaining_collateral: i32 = valuation.collateral.iter().map(|c| c.quantity).sum();
+
+    for pledge in &valuation.collateral {
+        if needed <= BigDecimal::zero() || outstanding <= BigDecimal::zero() {
+            break;
+        }
+        let Some(price) = valuation.prices.get(&pledge.ticker) else {
+            continue;
+        };
+        let Some(holding) = holdings_repository
+            .get_holding_by_portfolio_and_ticker(loan.portfolio_id, &pledge.ticker)
+            .await?
+        else {
+            continue;
+        };
+
+        let wanted = (&needed / price)
+            .with_scale_round(0, RoundingMode::Up)
+            .to_i32()
+            .unwrap_or(i32::MAX);
+        let quantity = wanted.min(pledge.quantity).min(holding.quantity);
+        if quantity <= 0 {
+            continue;
+        }
+        if let Err(e) = matching::check_trade(state, &pledge.ticker, quantity).await {
+            tracing::warn!(
+                "Cannot liquidate {} for loan {} yet: {}",
+                pledge.ticker,
+                loan.id,
+                e
+            );
+            continue;
+        }
+
+        let price = rules
+            .execution_price(state, &pledge.ticker, Side::Sell, price, quantity)
+            .await?;
+        let fee = rules.fee(&(&price * quantity));
+        let transaction = transactions_repository
+            .create_transaction(
+                loan.user_id,
+                loan.portfolio_id,
+                &pledge.ticker,
+                quantity,
+                price.clone(),
+                "sell",
+                fee.clone(),
+            )
+            .await?;
+        let posting = TradePosting {
+            entry_type: "sell",
+            value: &price * BigDecimal::from(quantity),
+            fee: fee.clone(),
+        };
+        positions::remove_shares(state, holding, quantity, &price, transaction.id, posting).await?;
+        events::publish_fill(state, loan.portfolio_id, &transaction).await;
+        loan_repository
+            .reduce_collateral(loan.id, &pledge.ticker, quantity)
+            .await?;
+        remaining_collateral -= quantity;
+
+        // Proceeds after commission repay the loan; anything beyond the debt
+        // stays as cash
+        let proceeds = &price * BigDecimal::from(quantity) - fee;
+        let repayment = if proceeds < outstanding {
+            proceeds.clone()
+        } else {
+            outstanding.clone()
+        };
+        loan_repository.repay(loan.id, repayment.clone()).await?;
+        ledger_repository
+            .post(
+                loan.portfolio_id,
+                "loan",
+                -repayment.clone(),
+                Some(transaction.id),
+            )
+            .await?;
+
+        outstanding -= repayment;
+        needed -= proceeds;
+        tracing::warn!(
+            "Liquidated {} {} at {} for loan {}",
+            quantity,
+            pledge.ticker,
+            price,
+            loan.id
+        );
+    }
+
+    if remaining_collateral <= 0 && outstanding > BigDecimal::zero() {
+        loan_repository.close_liquidated(loan.id).await?;
+        tracing::warn!(
+            "Loan {} closed with a shortfall of {}",
+            loan.id,
+            outstanding
+        );
+    }
+
+    Ok(())
+}
+
+fn collateral_value(
+    collateral: &[LoanCollateral],
+    prices: &HashMap<String, BigDecimal>,
+) -> BigDecimal {
+    collateral
+        .iter()
+        .filter_map(|c| Some(prices.get(&c.ticker)? * BigDecimal::from(c.quantity)))
+        .fold(BigDecimal::from(0), |total, value| total + value)
+}
+
+fn ltv_fraction(percent: f64) -> BigDecimal {
+    BigDecimal::from_f64(percent / 100.0).unwrap_or_else(|| BigDecimal::from(0))
+}
+
+async fn claim_margin_check(state: &AppState) -> Result<bool> {
+    let options = SetOptions::default()
+        .conditional_set(ExistenceCheck::NX)
+        .with_expiration(SetExpiry::EX(MARGIN_CHECK_INTERVAL.as_secs() - 1));
+
+    let claimed: Option<String> = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?
+        .set_options("loans:margin_check", 1, options)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    Ok(claimed.is_some())
+}
@@ -0,0 +1,63 @@
+//! # Outgoing Mail
+//!
+//! The server does not speak SMTP itself. Mail is published as JSON on the
+//! Redis channel `mail` for a delivery worker (or, in development, anyone
+//! watching with `redis-cli subscribe mail`) to send on, and logged without
+//! its body. Mail sent while handling a request carries its `request_id`.
+
+use redis::AsyncCommands;
+use serde::Serialize;
+use tracing::Instrument;
+
+use crate::{
+    AppState, Error, Result,
+    timing::{self, Phase},
+};
+
+/// Redis pub/sub channel carrying outgoing mail
+pub const MAIL_CHANNEL: &str = "mail";
+
+/// A plain text message to one recipient
+#[derive(Debug, Serialize)]
+pub struct Mail<'a> {
+    pub to: &'a str,
+    pub subject: &'a str,
+    pub body: String,
+}
+
+/// Mail as published, with the request that sent it
+#[derive(Debug, Serialize)]
+struct OutgoingMail<'a, 'm> {
+    #[serde(flatten)]
+    mail: &'m Mail<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+/// Hand `mail` over for delivery
+pub async fn send(state: &AppState, mail: &Mail<'_>) -> Result<()> {
+    let outgoing = OutgoingMail {
+        mail,
+        request_id: crate::request_id::current(),
+    };
+    let payload = serde_json::to_string(&outgoing).map_err(|e| {
+        tracing::error!("Failed to encode mail: {}", e);
+        Error::InternalServerError
+    })?;
+
+    async {
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        conn.publish::<_, _, ()>(MAIL_CHANNEL, payload)
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await?;
+
+    tracing::info!("Sent mail \"{}\" to {}", mail.subject, mail.to);
+    Ok(())
+}
@@ -0,0 +1,158 @@
+//! # Matching Parameters
+//!
+//! Runtime-tunable parameters consulted when ticks are ingested and trades are
+//! executed. The active configuration lives in `AppState` and is reloaded from
+//! the database whenever an update is announced on the Redis config channel,
+//! so every instance picks up admin changes without a restart.
+
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use redis::AsyncCommands;
+use tracing::Instrument;
+
+use crate::{
+    AppState, Error, Result,
+    models::matching_config::MatchingConfig,
+    repository::matching_config_repository::MatchingConfigRepository,
+    timing::{self, Phase},
+};
+
+/// Redis channel announcing that the matching configuration changed
+pub const CONFIG_CHANNEL: &str = "matching_config:updated";
+
+/// Outcome of applying the price band and halt thresholds to an incoming tick
+#[derive(Debug, PartialEq)]
+pub struct TickDecision {
+    /// Price to publish, clamped to the allowed band
+    pub price: f64,
+    /// Whether the move was large enough to halt trading in the ticker
+    pub halt: bool,
+}
+
+/// Snapshot of the active matching configuration
+pub fn current(state: &AppState) -> MatchingConfig {
+    state
+        .matching_config
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// Apply tick-level price banding and volatility halts relative to the previous price
+pub fn apply_tick(config: &MatchingConfig, previous: Option<f64>, price: f64) -> TickDecision {
+    let previous = match previous {
+        Some(previous) if previous > 0.0 => previous,
+        _ => return TickDecision { price, halt: false },
+    };
+
+    let change_percent = (price - previous).abs() / previous * 100.0;
+    let band = previous * config.price_band_percent / 100.0;
+
+    TickDecision {
+        price: price.clamp(previous - band, previous + band),
+        halt: change_percent >= config.volatility_halt_percent,
+    }
+}
+
+/// Redis key marking a ticker as halted
+pub fn halt_key(ticker: &str) -> String {
+    format!("halt:{}", ticker)
+}
+
+/// Validate a trade against the active matching configuration
+pub async fn check_trade(state: &AppState, ticker: &str, quantity: i32) -> Result<()> {
+    let config = current(state);
+    if quantity > config.max_order_quantity {
+        return Err(Error::BadRequest(format!(
+            "Order quantity exceeds the maximum of {}",
+            config.max_order_quantity
+        )));
+    }
+
+    let halted: bool = async {
+        state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?
+            .exists(halt_key(ticker))
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))
+    }
+    .instrument(timing::span(Phase::Redis))
+    .await?;
+
+    if halted {
+        return Err(Error::BadRequest(format!(
+            "Trading in {} is temporarily halted",
+            ticker
+        )));
+    }
+
+    Ok(())
+}
+
+/// Persist a new configuration, apply it locally and notify the other instances
+pub async fn update(
+    state: &AppState,
+    price_band_percent: f64,
+    max_order_quantity: i32,
+    volatility_halt_percent: f64,
+    halt_duration_secs: i32,
+) -> Result<MatchingConfig> {
+    let repository = MatchingConfigRepository::new(&state.pg_pool);
+    let config = repository
+        .update_config(
+            price_band_percent,
+            max_order_quantity,
+            volatility_halt_percent,
+            halt_duration_secs,
+        )
+        .await?;
+
+    store(state, config.clone());
+
+    state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?
+        .publish::<_, _, ()>(CONFIG_CHANNEL, config.updated_at.to_rfc3339())
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    Ok(config)
+}
+
+/// Reload the configuration from the database whenever an update is announced
+pub async fn config_reloader(state: Arc<AppState>) -> Result<()> {
+    let client = redis::Client::open(state.config.redis_url.as_str())
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let mut pubsub = client
+        .get_async_pubsub()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    pubsub
+        .subscribe(CONFIG_CHANNEL)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    let mut messages = pubsub.on_message();
+    while messages.next().await.is_some() {
+        let config = MatchingConfigRepository::new(&state.pg_pool)
+            .get_config()
+            .await?;
+        tracing::info!("Reloaded matching config: {:?}", config);
+        store(&state, config);
+    }
+
+    Ok(())
+}
+
+fn store(state: &AppState, config: MatchingConfig) {
+    *state
+        .matching_config
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = config;
+}
@@ -0,0 +1,389 @@
+//! # Performance Metrics
+//!
+//! Risk and return statistics computed from the daily portfolio snapshots.
+//! Returns are time-weighted: each day's return is measured net of that day's
+//! deposits and withdrawals, so moving cash in or out of the account does not
+//! show up as performance. Snapshots are taken every calendar day, so
+//! annualization uses 365 periods per year, and the Sharpe ratio uses the
+//! money market yield as the risk-free rate.
+//!
+//! Benchmark comparisons only use the days with both a snapshot and a
+//! benchmark price; the portfolio and the benchmark are measured over exactly
+//! the same periods.
+
+use std::collections::HashMap;
+
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::NaiveDate;
+
+use crate::{
+    AppState, Result,
+    models::{cash_flow::DailyCashFlow, portfolio_snapshot::PortfolioSnapshot},
+    repository::{
+        benchmark_price_repository::BenchmarkPriceRepository,
+        cash_flow_repository::CashFlowRepository,
+        portfolio_snapshot_repository::PortfolioSnapshotRepository,
+    },
+};
+
+/// Snapshot periods per year
+pub const PERIODS_PER_YEAR: f64 = 365.0;
+
+/// Account equity at the start of a day
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EquityPoint {
+    pub date: NaiveDate,
+    pub equity: f64,
+    /// Net deposits minus withdrawals since the previous point
+    pub flow: f64,
+}
+
+/// Performance statistics over a range of snapshots
+///
+/// Returns, volatility and drawdown are fractions (0.05 = 5%).
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerformanceMetrics {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Number of daily returns the statistics are based on
+    pub periods: usize,
+    pub time_weighted_return: f64,
+    pub annualized_return: f64,
+    /// `None` with fewer than two returns
+    pub annualized_volatility: Option<f64>,
+    /// `None` when the volatility is unknown or zero
+    pub sharpe_ratio: Option<f64>,
+    pub max_drawdown: f64,
+    /// Comparison with the selected benchmark, `None` without one or with
+    /// fewer than two days of benchmark prices
+    pub benchmark: Option<BenchmarkComparison>,
+}
+
+/// Performance relative to a benchmark ticker over the days both are known
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkComparison {
+    pub ticker: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Number of daily returns the comparison is based on
+    pub periods: usize,
+    /// Time-weighted return of the portfolio over the compared periods
+    pub portfolio_return: f64,
+    pub benchmark_return: f64,
+    /// Portfolio return minus benchmark return
+    pub tracking_difference: f64,
+    /// Annualized standard deviation of the daily return differences;
+    /// `None` with fewer than two returns
+    pub tracking_error: Option<f64>,
+    /// `None` when the benchmark did not move
+    pub beta: Option<f64>,
+    /// Annualized Jensen's alpha; `None` without a beta
+    pub alpha: Option<f64>,
+}
+
+/// A day of the history with the cumulative returns since the first day
+/// both the portfolio and the benchmark are known
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkPoint {
+    pub price: BigDecimal,
+    pub cumulative_return: f64,
+    pub benchmark_cumulative_return: f64,
+}
+
+/// Compute the user's metrics from the snapshots between `from` and `to`
+///
+/// Returns `None` when the range holds fewer than two snapshots.
+pub async fn performance(
+    state: &AppState,
+    user_id: i32,
+    from: NaiveDate,
+    to: NaiveDate,
+    benchmark: Option<&str>,
+) -> Result<Option<PerformanceMetrics>> {
+    let snapshots = PortfolioSnapshotRepository::new(&state.pg_pool)
+        .get_snapshots(user_id, from, to)
+        .await?;
+    let flows = CashFlowRepository::new(&state.pg_pool)
+        .get_daily_flows(user_id, from, to)
+        .await?;
+
+    let risk_free_rate = state.config.money_market_yield_percent / 100.0;
+    let Some(mut metrics) = compute(&equity_points(&snapshots, &flows), risk_free_rate) else {
+        return Ok(None);
+    };
+
+    if let Some(ticker) = benchmark {
+        let prices = benchmark_prices(state, ticker, from, to).await?;
+        let (points, prices) = align(&snapshots, &flows, &prices);
+        metrics.benchmark = compare(ticker, &points, &prices, risk_free_rate);
+    }
+
+    Ok(Some(metrics))
+}
+
+/// Benchmark prices and cumulative returns for the days of `snapshots`
+///
+/// Days without a benchmark price are missing from the result.
+pub async fn benchmark_history(
+    state: &AppState,
+    user_id: i32,
+    snapshots: &[PortfolioSnapshot],
+    ticker: &str,
+) -> Result<HashMap<NaiveDate, BenchmarkPoint>> {
+    let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) else {
+        return Ok(HashMap::new());
+    };
+    let (from, to) = (first.snapshot_date, last.snapshot_date);
+
+    let flows = CashFlowRepository::new(&state.pg_pool)
+        .get_daily_flows(user_id, from, to)
+        .await?;
+    let prices = benchmark_prices(state, ticker, from, to).await?;
+    let (points, aligned) = align(snapshots, &flows, &prices);
+
+    Ok(points
+        .iter()
+        .zip(cumulative_returns(&points, &aligned))
+        .map(
+            |(point, (cumulative_return, benchmark_cumulative_return))| {
+                let benchmark = BenchmarkPoint {
+                    price: prices[&point.date].clone(),
+                    cumulative_return,
+                    benchmark_cumulative_return,
+                };
+                (point.date, benchmark)
+            },
+        )
+        .collect())
+}
+
+async fn benchmark_prices(
+    state: &AppState,
+    ticker: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<HashMap<NaiveDate, BigDecimal>> {
+    Ok(BenchmarkPriceRepository::new(&state.pg_pool)
+        .get_prices(ticker, from, to)
+        .await?
+        .into_iter()
+        .map(|p| (p.price_date, p.price))
+        .collect())
+}
+
+/// Equity points of the snapshots, with the cash flows between consecutive ones
+fn equity_points<'a>(
+    snapshots: impl IntoIterator<Item = &'a PortfolioSnapshot>,
+    flows: &[DailyCashFlow],
+) -> Vec<EquityPoint> {
+    // A snapshot dated D is taken at the start of D, so flows made on the days
+    // from the previous snapshot up to D - 1 happened in between
+    let mut points = Vec::new();
+    let mut previous: Option<NaiveDate> = None;
+    for snapshot in snapshots {
+        let flow = match previous {
+            Some(previous) => flows
+                .iter()
+                .filter(|f| f.flow_date >= previous && f.flow_date < snapshot.snapshot_date)
+                .filter_map(|f| f.amount.to_f64())
+                .sum(),
+            None => 0.0,
+        };
+        previous = Some(snapshot.snapshot_date);
+        points.push(EquityPoint {
+            date: snapshot.snapshot_date,
+            equity: snapshot.equity.to_f64().unwrap_or_default(),
+            flow,
+        });
+    }
+
+    points
+}
+
+/// Equity points and benchmark prices of the days that have both
+fn align(
+    snapshots: &[PortfolioSnapshot],
+    flows: &[DailyCashFlow],
+    prices: &HashMap<NaiveDate, BigDecimal>,
+) -> (Vec<EquityPoint>, Vec<f64>) {
+    let priced = snapshots
+        .iter()
+        .filter(|s| prices.contains_key(&s.snapshot_date));
+    let points = equity_points(priced, flows);
+    let aligned = points
+        .iter()
+        .map(|p| prices[&p.date].to_f64().unwrap_or_default())
+        .collect();
+
+    (points, aligned)
+}
+
+/// Time-weighted return of each period between consecutive points
+///
+/// Periods starting from zero equity have no meaningful return and are skipped.
+pub fn period_returns(points: &[EquityPoint]) -> Vec<f64> {
+    points
+        .windows(2)
+        .filter(|pair| pair[0].equity > 0.0)
+        .map(|pair| (pair[1].equity - pair[1].flow) / pair[0].equity - 1.0)
+        .collect()
+}
+
+/// Compute the statistics for a series of equity points ordered by date
+pub fn compute(points: &[EquityPoint], risk_free_rate: f64) -> Option<PerformanceMetrics> {
+    let (first, last) = (points.first()?, points.last()?);
+    if points.len() < 2 {
+        return None;
+    }
+
+    let returns = period_returns(points);
+    let time_weighted_return = returns.iter().map(|r| 1.0 + r).product::<f64>() - 1.0;
+
+    let days = (last.date - first.date).num_days();
+    let annualized_return = if days > 0 && time_weighted_return > -1.0 {
+        (1.0 + time_weighted_return).powf(PERIODS_PER_YEAR / days as f64) - 1.0
+    } else {
+        time_weighted_return
+    };
+
+    let annualized_volatility = sample_std_dev(&returns).map(|s| s * PERIODS_PER_YEAR.sqrt());
+    let sharpe_ratio = annualized_volatility
+        .filter(|volatility| *volatility > 0.0)
+        .map(|volatility| {
+            let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+            (mean * PERIODS_PER_YEAR - risk_free_rate) / volatility
+        });
+
+    Some(PerformanceMetrics {
+        from: first.date,
+        to: last.date,
+        periods: returns.len(),
+        time_weighted_return,
+        annualized_return,
+        annualized_volatility,
+        sharpe_ratio,
+        max_drawdown: max_drawdown(&returns),
+        benchmark: None,
+    })
+}
+
+/// Compare equity points with the benchmark prices of the same days
+///
+/// Periods starting from zero equity or a zero price are skipped. Returns
+/// `None` when no period is left.
+pub fn compare(
+    ticker: &str,
+    points: &[EquityPoint],
+    prices: &[f64],
+    risk_free_rate: f64,
+) -> Option<BenchmarkComparison> {
+    if points.len() != prices.len() {
+        return None;
+    }
+    let (first, last) = (points.first()?, points.last()?);
+
+    let (portfolio, benchmark): (Vec<f64>, Vec<f64>) = points
+        .windows(2)
+        .zip(prices.windows(2))
+        .filter(|(pair, price)| pair[0].equity > 0.0 && price[0] > 0.0)
+        .map(|(pair, price)| {
+            (
+                (pair[1].equity - pair[1].flow) / pair[0].equity - 1.0,
+                price[1] / price[0] - 1.0,
+            )
+        })
+        .unzip();
+    if portfolio.is_empty() {
+        return None;
+    }
+
+    let portfolio_return = portfolio.iter().map(|r| 1.0 + r).product::<f64>() - 1.0;
+    let benchmark_return = benchmark.iter().map(|r| 1.0 + r).product::<f64>() - 1.0;
+    let active: Vec<f64> = portfolio
+        .iter()
+        .zip(&benchmark)
+        .map(|(p, b)| p - b)
+        .collect();
+
+    let beta = sample_std_dev(&benchmark)
+        .filter(|std_dev| *std_dev > 0.0)
+        .map(|std_dev| sample_covariance(&portfolio, &benchmark) / std_dev.powi(2));
+    let alpha = beta.map(|beta| {
+        let annualized_mean = |returns: &[f64]| mean(returns) * PERIODS_PER_YEAR;
+        annualized_mean(&portfolio)
+            - risk_free_rate
+            - beta * (annualized_mean(&benchmark) - risk_free_rate)
+    });
+
+    Some(BenchmarkComparison {
+        ticker: ticker.to_string(),
+        from: first.date,
+        to: last.date,
+        periods: portfolio.len(),
+        portfolio_return,
+        benchmark_return,
+        tracking_difference: portfolio_return - benchmark_return,
+        tracking_error: sample_std_dev(&active).map(|s| s * PERIODS_PER_YEAR.sqrt()),
+        beta,
+        alpha,
+    })
+}
+
+/// Cumulative returns of the portfolio and the benchmark at each point
+///
+/// The portfolio grows by its time-weighted period returns, so cash flows do
+/// not count as performance; periods starting from zero equity add nothing.
+pub fn cumulative_returns(points: &[EquityPoint], prices: &[f64]) -> Vec<(f64, f64)> {
+    let Some(base) = prices.first().copied() else {
+        return Vec::new();
+    };
+
+    let mut wealth = 1.0;
+    let mut series = Vec::with_capacity(points.len());
+    for (i, price) in prices.iter().enumerate().take(points.len()) {
+        if i > 0 && points[i - 1].equity > 0.0 {
+            wealth *= (points[i].equity - points[i].flow) / points[i - 1].equity;
+        }
+        let benchmark = if base > 0.0 { price / base - 1.0 } else { 0.0 };
+        series.push((wealth - 1.0, benchmark));
+    }
+
+    series
+}
+
+/// Largest peak-to-trough decline of the growth of one unit invested
+pub fn max_drawdown(returns: &[f64]) -> f64 {
+    let mut wealth = 1.0;
+    let mut peak = 1.0_f64;
+    let mut drawdown = 0.0_f64;
+
+    for r in returns {
+        wealth *= 1.0 + r;
+        peak = peak.max(wealth);
+        drawdown = drawdown.max((peak - wealth) / peak);
+    }
+
+    drawdown
+}
+
+fn sample_std_dev(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+
+    Some(sample_covariance(values, values).sqrt())
+}
+
+/// Sample covariance of two equally long series with at least two values
+fn sample_covariance(a: &[f64], b: &[f64]) -> f64 {
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - mean_a) * (y - mean_b))
+        .sum::<f64>()
+        / (a.len() - 1) as f64
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
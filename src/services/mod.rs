@@ -1 +1,40 @@
+pub mod allowance;
+pub mod archive;
+pub mod balance;
+pub mod baskets;
+pub mod bonds;
+pub mod bots;
+pub mod classes;
+pub mod clock;
+pub mod competitions;
+pub mod corporate_actions;
+pub mod cost_basis;
 pub mod db;
+pub mod dividends;
+pub mod event_stream;
+pub mod feature_flags;
+pub mod http;
+pub mod instruments;
+pub mod leaderboard;
+pub mod liquidity;
+pub mod loans;
+pub mod mailer;
+pub mod matching;
+pub mod metrics;
+pub mod movers;
+pub mod options;
+pub mod portfolio;
+pub mod positions;
+pub mod price_cache;
+pub mod price_provider;
+pub mod price_updater;
+pub mod quotes;
+pub mod replay;
+pub mod rules;
+pub mod snapshots;
+pub mod sweep;
+pub mod tax_report;
+pub mod trading;
+pub mod transaction_import;
+pub mod user_cache;
+pub mod webhooks;
@@ -0,0 +1,95 @@
+//! # Market Movers
+//!
+//! Daily market statistics: the tickers that gained and lost the most since
+//! the previous day's close, from the daily candles, and the tickers with the
+//! most shares traded in the simulator. Days are UTC calendar days, matching
+//! the daily candles.
+
+use std::cmp::Ordering;
+
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    AppState, Result,
+    models::{price_candle::CandleInterval, transaction::TradedVolume},
+    repository::price_candle_repository::PriceCandleRepository,
+    services::portfolio,
+};
+
+/// Price change of a ticker since the previous day's close
+#[derive(Debug, Clone)]
+pub struct Mover {
+    pub ticker: String,
+    pub price: BigDecimal,
+    pub previous_close: BigDecimal,
+    pub change: BigDecimal,
+    pub change_percent: BigDecimal,
+}
+
+/// Top movers of a day
+#[derive(Debug)]
+pub struct MarketMovers {
+    /// Start of the day the statistics cover
+    pub day: DateTime<Utc>,
+    pub gainers: Vec<Mover>,
+    pub losers: Vec<Mover>,
+    pub most_traded: Vec<TradedVolume>,
+}
+
+/// The `limit` biggest gainers, losers and most traded tickers of today
+///
+/// Tickers without a previous close are left out of the gainers and losers;
+/// unchanged tickers are neither.
+pub async fn movers(state: &AppState, limit: usize) -> Result<MarketMovers> {
+    let day = CandleInterval::OneDay.bucket_start(Utc::now());
+
+    let mut moves: Vec<Mover> = PriceCandleRepository::new(&state.pg_pool)
+        .get_daily_moves(day)
+        .await?
+        .into_iter()
+        .filter(|m| m.previous_close > BigDecimal::zero())
+        .map(|m| {
+            let change = &m.close - &m.previous_close;
+            Mover {
+                change_percent: portfolio::percent_of(&change, &m.previous_close),
+                ticker: m.ticker,
+                price: m.close,
+                previous_close: m.previous_close,
+                change,
+            }
+        })
+        .collect();
+    moves.sort_by(|a, b| match b.change_percent.cmp(&a.change_percent) {
+        Ordering::Equal => a.ticker.cmp(&b.ticker),
+        ordering => ordering,
+    });
+
+    let zero = BigDecimal::from(0);
+    let gainers = moves
+        .iter()
+        .filter(|m| m.change_percent > zero)
+        .take(limit)
+        .cloned()
+        .collect();
+    let losers = moves
+        .iter()
+        .rev()
+        .filter(|m| m.change_percent < zero)
+        .take(limit)
+        .cloned()
+        .collect();
+
+    let most_traded = state
+        .repos
+        .transactions
+        .get_most_traded(day.naive_utc(), limit as i64)
+        .await?;
+
+    Ok(MarketMovers {
+        day,
+        gainers,
+        losers,
+        most_traded,
+    })
+}
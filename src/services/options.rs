@@ -0,0 +1,643 @@
+//! # Options
+//!
+//! Admins list call and put contracts on listed underlyings, each with a
+//! strike and an expiry and covering [`MULTIPLIER`] shares. Contracts are
+//! priced off the underlying's latest price with Black-Scholes, using
+//! `OPTION_VOLATILITY_PERCENT` and `OPTION_RISK_FREE_PERCENT`; every trade
+//! fills at the model price, rounded to cents, and pays the commission of
+//! the underlying under the portfolio's difficulty.
+//!
+//! Buying opens or adds to a long position, or closes a short one; selling
+//! closes a long position, or opens or adds to a short one. Writing
+//! contracts requires the `margin_trading` feature and moves the margin
+//! requirement of the new contracts out of cash into the position, where it
+//! stays until the contracts are bought back or settle.
+//!
+//! The expiry worker cash-settles contracts once they expire, at the
+//! underlying's latest price: in-the-money long positions are exercised and
+//! credited their intrinsic value, in-the-money short positions are assigned
+//! and pay it out of their margin and cash, and out-of-the-money positions
+//! expire worthless. Shortfalls beyond the portfolio's cash are written off,
+//! like those of liquidated loans.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode, ToPrimitive, Zero};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    AppState, Error, Result,
+    config::Config,
+    models::{
+        option::{OptionContract, OptionPosition, OptionTrade},
+        portfolio::Portfolio,
+    },
+    repository::{
+        ledger_repository::LedgerRepository, option_repository::OptionRepository,
+        portfolio_repository::PortfolioRepository,
+    },
+    services::{feature_flags, price_cache::PriceCache, rules},
+};
+
+/// Shares covered by one contract
+pub const MULTIPLIER: i32 = 100;
+/// Kinds of contracts
+pub const KINDS: [&str; 2] = ["call", "put"];
+/// How often expired contracts are looked for
+pub const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+/// Model price of a contract at an underlying price
+#[derive(Debug, Clone)]
+pub struct OptionQuote {
+    /// Premium per share, rounded to cents
+    pub price: BigDecimal,
+    /// Change of the premium per unit change of the underlying
+    pub delta: f64,
+}
+
+/// A position valued at the latest underlying price
+#[derive(Debug, Clone)]
+pub struct OptionPositionValuation {
+    pub position: OptionPosition,
+    /// Model premium per share, `None` while the underlying has no price
+    pub mark: Option<BigDecimal>,
+    /// Value of the contracts, negative for short positions; positions
+    /// without a mark are valued at their average premium
+    pub market_value: BigDecimal,
+    pub unrealized_pnl: BigDecimal,
+}
+
+/// Black-Scholes price and delta of a European `kind` option
+///
+/// `years` to expiry and `volatility` and `rate` as fractions. Contracts at
+/// or past expiry are worth their intrinsic value.
+pub fn black_scholes(
+    kind: &str,
+    spot: f64,
+    strike: f64,
+    years: f64,
+    volatility: f64,
+    rate: f64,
+) -> (f64, f64) {
+    let is_call = kind == "call";
+    if years <= 0.0 || spot <= 0.0 {
+        let intrinsic = if is_call {
+            (spot - strike).max(0.0)
+        } else {
+            (strike - spot).max(0.0)
+        };
+        let in_the_money = intrinsic > 0.0;
+        let delta = match (is_call, in_the_money) {
+            (true, true) => 1.0,
+            (false, true) => -1.0,
+            _ => 0.0,
+        };
+        return (intrinsic, delta);
+    }
+
+    let deviation = volatility * years.sqrt();
+    let d1 = ((spot / strike).ln() + (rate + volatility * volatility / 2.0) * years) / deviation;
+    let d2 = d1 - deviation;
+    let discounted_strike = strike * (-rate * years).exp();
+    if is_call {
+        (
+            spot * norm_cdf(d1) - discounted_strike * norm_cdf(d2),
+            norm_cdf(d1),
+        )
+    } else {
+        (
+            discounted_strike * norm_cdf(-d2) - spot * norm_cdf(-d1),
+            norm_cdf(d1) - 1.0,
+        )
+    }
+}
+
+/// Standard normal cumulative distribution
+fn norm_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+/// Error function, accurate to 1.5e-7 (Abramowitz and Stegun 7.1.26)
+fn erf(x: f64) -> f64 {
+    let sign = x.signum();
+    let x = x.abs();
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let polynomial = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+
+    sign * (1.0 - polynomial * (-x * x).exp())
+}
+
+/// Model price of a contract when the underlying trades at `spot`
+pub fn quote(
+    config: &Config,
+    kind: &str,
+    strike: &BigDecimal,
+    expires_at: DateTime<Utc>,
+    spot: &BigDecimal,
+    now: DateTime<Utc>,
+) -> OptionQuote {
+    let years = (expires_at - now).num_milliseconds() as f64 / 1000.0 / SECONDS_PER_YEAR;
+    let (price, delta) = black_scholes(
+        kind,
+        spot.to_f64().unwrap_or_default(),
+        strike.to_f64().unwrap_or_default(),
+        years,
+        config.option_volatility_percent / 100.0,
+        config.option_risk_free_percent / 100.0,
+    );
+
+    OptionQuote {
+        price: BigDecimal::from_f64(price)
+            .unwrap_or_default()
+            .with_scale_round(2, RoundingMode::HalfUp),
+        delta,
+    }
+}
+
+/// Value per share of exercising a `kind` contract when the underlying
+/// trades at `spot`
+pub fn intrinsic_value(kind: &str, strike: &BigDecimal, spot: &BigDecimal) -> BigDecimal {
+    let value = if kind == "call" {
+        spot - strike
+    } else {
+        strike - spot
+    };
+    value.max(BigDecimal::from(0))
+}
+
+/// Margin per share held against a short contract sold for `premium`
+///
+/// The premium plus 20% of the underlying less the amount the contract is
+/// out of the money, but at least 10% of the underlying for calls or of the
+/// strike for puts.
+pub fn margin_requirement(
+    kind: &str,
+    strike: &BigDecimal,
+    spot: &BigDecimal,
+    premium: &BigDecimal,
+) -> BigDecimal {
+    let zero = BigDecimal::from(0);
+    let (out_of_the_money, minimum) = if kind == "call" {
+        (
+            (strike - spot).max(zero.clone()),
+            spot / BigDecimal::from(10),
+        )
+    } else {
+        (
+            (spot - strike).max(zero.clone()),
+            strike / BigDecimal::from(10),
+        )
+    };
+    let requirement = (spot / BigDecimal::from(5) - out_of_the_money).max(minimum);
+
+    premium + requirement
+}
+
+/// Value positions at the latest prices of their underlyings
+pub async fn value_positions(
+    state: &AppState,
+    positions: Vec<OptionPosition>,
+) -> Result<Vec<OptionPositionValuation>> {
+    let mut underlyings: Vec<String> = positions.iter().map(|p| p.underlying.clone()).collect();
+    underlyings.sort();
+    underlyings.dedup();
+    let prices = PriceCache::new(&state.redis_pool)
+        .prices(&underlyings)
+        .await?;
+    let now = Utc::now();
+
+    Ok(positions
+        .into_iter()
+        .map(|position| {
+            let mark = prices.get(&position.underlying).map(|spot| {
+                quote(
+                    &state.config,
+                    &position.kind,
+                    &position.strike,
+                    position.expires_at,
+                    spot,
+                    now,
+                )
+                .price
+            });
+            let shares = BigDecimal::from(position.quantity * MULTIPLIER);
+            let market_value = mark.as_ref().unwrap_or(&position.average_premium) * &shares;
+            let unrealized_pnl = &market_value - &position.average_premium * &shares;
+
+            OptionPositionValuation {
+                position,
+                mark,
+                market_value,
+                unrealized_pnl,
+            }
+        })
+        .collect())
+}
+
+/// Value of option positions and the margin they hold, counted towards equity
+pub fn total_value(valuations: &[OptionPositionValuation]) -> BigDecimal {
+    valuations
+        .iter()
+        .fold(BigDecimal::from(0), |total, valuation| {
+            total + &valuation.market_value + &valuation.position.margin
+        })
+}
+
+/// A contract that can be traded, with its model price
+struct Tradable {
+    contract: OptionContract,
+    spot: BigDecimal,
+    premium: BigDecimal,
+    rules: rules::TradingRules,
+}
+
+async fn tradable(state: &AppState, portfolio: &Portfolio, contract_id: i32) -> Result<Tradable> {
+    if portfolio.competition_id.is_some() || portfolio.class_id.is_some() {
+        return Err(Error::BadRequest(
+            "Options cannot be traded in competition or class portfolios".into(),
+        ));
+    }
+
+    let contract = OptionRepository::new(&state.pg_pool)
+        .get_contract(contract_id)
+        .await?
+        .ok_or_else(|| Error::BadRequest(format!("Unknown option contract {}", contract_id)))?;
+    let now = Utc::now();
+    if contract.settled_at.is_some() || contract.expires_at <= now {
+        return Err(Error::BadRequest(format!(
+            "Option contract {} has expired",
+            contract_id
+        )));
+    }
+
+    let spot = PriceCache::new(&state.redis_pool)
+        .prices(std::slice::from_ref(&contract.underlying))
+        .await?
+        .remove(&contract.underlying)
+        .ok_or_else(|| {
+            Error::BadRequest(format!("No price available for {}", contract.underlying))
+        })?;
+    let premium = quote(
+        &state.config,
+        &contract.kind,
+        &contract.strike,
+        contract.expires_at,
+        &spot,
+        now,
+    )
+    .price;
+    if premium <= BigDecimal::zero() {
+        return Err(Error::BadRequest(format!(
+            "Option contract {} has no value to trade",
+            contract_id
+        )));
+    }
+    let rules = rules::for_portfolio(state, portfolio).await?;
+
+    Ok(Tradable {
+        contract,
+        spot,
+        premium,
+        rules,
+    })
+}
+
+/// Buy `quantity` contracts into `portfolio` at the model price, closing
+/// a short position first
+pub async fn buy(
+    state: &AppState,
+    portfolio: &Portfolio,
+    contract_id: i32,
+    quantity: i32,
+) -> Result<OptionTrade> {
+    let option_repository = OptionRepository::new(&state.pg_pool);
+    let Tradable {
+        contract,
+        premium,
+        rules,
+        ..
+    } = tradable(state, portfolio, contract_id).await?;
+
+    let value = &premium * BigDecimal::from(quantity * MULTIPLIER);
+    let fee = rules.fee(&value);
+    let cost = &value + &fee;
+    let position = option_repository
+        .get_position(portfolio.id, contract.id)
+        .await?;
+
+    match position {
+        Some(short) if short.quantity < 0 => {
+            if quantity > -short.quantity {
+                return Err(Error::BadRequest(format!(
+                    "Buy at most {} contracts to close the short position",
+                    -short.quantity
+                )));
+            }
+            let released = (&short.margin * BigDecimal::from(quantity)
+                / BigDecimal::from(-short.quantity))
+            .with_scale_round(2, RoundingMode::Down);
+            let balance = &portfolio.balance + &released - &cost;
+            if balance < BigDecimal::zero() {
+                return Err(Error::BadRequest(
+                    "Insufficient balance for this transaction".into(),
+                ));
+            }
+
+            if short.quantity + quantity == 0 {
+                option_repository.delete_position(short.id).await?;
+            } else {
+                option_repository
+                    .upsert_position(
+                        portfolio.user_id,
+                        portfolio.id,
+                        contract.id,
+                        short.quantity + quantity,
+                        short.average_premium,
+                        short.margin - &released,
+                    )
+                    .await?;
+            }
+            let ledger_repository = LedgerRepository::new(&state.pg_pool);
+            ledger_repository
+                .post(portfolio.id, "margin", released, None)
+                .await?;
+            ledger_repository
+                .post_trade(portfolio.id, "premium", -value, fee.clone(), None)
+                .await?;
+        }
+        long => {
+            if cost > portfolio.balance {
+                return Err(Error::BadRequest(
+                    "Insufficient balance for this transaction".into(),
+                ));
+            }
+
+            let (held, average_premium) = long
+                .map(|long| (long.quantity, long.average_premium))
+                .unwrap_or_else(|| (0, BigDecimal::from(0)));
+            let total = held + quantity;
+            let average_premium = (average_premium * BigDecimal::from(held)
+                + &premium * BigDecimal::from(quantity))
+                / BigDecimal::from(total);
+            option_repository
+                .upsert_position(
+                    portfolio.user_id,
+                    portfolio.id,
+                    contract.id,
+                    total,
+                    average_premium.with_scale_round(4, RoundingMode::HalfUp),
+                    BigDecimal::from(0),
+                )
+                .await?;
+            LedgerRepository::new(&state.pg_pool)
+                .post_trade(portfolio.id, "premium", -value, fee.clone(), None)
+                .await?;
+        }
+    }
+
+    option_repository
+        .record_trade(
+            portfolio.user_id,
+            portfolio.id,
+            contract.id,
+            "buy",
+            quantity,
+            premium,
+            fee,
+        )
+        .await
+}
+
+/// Sell `quantity` contracts out of `portfolio` at the model price,
+/// writing new contracts once no long position is left to close
+pub async fn sell(
+    state: &AppState,
+    portfolio: &Portfolio,
+    contract_id: i32,
+    quantity: i32,
+) -> Result<OptionTrade> {
+    let option_repository = OptionRepository::new(&state.pg_pool);
+    let Tradable {
+        contract,
+        spot,
+        premium,
+        rules,
+    } = tradable(state, portfolio, contract_id).await?;
+
+    let value = &premium * BigDecimal::from(quantity * MULTIPLIER);
+    let fee = rules.fee(&value);
+    let proceeds = &value - &fee;
+    let position = option_repository
+        .get_position(portfolio.id, contract.id)
+        .await?;
+
+    match position {
+        Some(long) if long.quantity > 0 => {
+            if quantity > long.quantity {
+                return Err(Error::BadRequest(format!(
+                    "Sell at most {} contracts to close the long position",
+                    long.quantity
+                )));
+            }
+
+            if long.quantity == quantity {
+                option_repository.delete_position(long.id).await?;
+            } else {
+                option_repository
+                    .upsert_position(
+                        portfolio.user_id,
+                        portfolio.id,
+                        contract.id,
+                        long.quantity - quantity,
+                        long.average_premium,
+                        BigDecimal::from(0),
+                    )
+                    .await?;
+            }
+            LedgerRepository::new(&state.pg_pool)
+                .post_trade(portfolio.id, "premium", value, fee.clone(), None)
+                .await?;
+        }
+        short => {
+            feature_flags::require(state, feature_flags::MARGIN_TRADING, portfolio.user_id).await?;
+
+            let margin = (margin_requirement(&contract.kind, &contract.strike, &spot, &premium)
+                * BigDecimal::from(quantity * MULTIPLIER))
+            .with_scale_round(2, RoundingMode::Up);
+            let balance = &portfolio.balance + &proceeds - &margin;
+            if balance < BigDecimal::zero() {
+                return Err(Error::BadRequest(
+                    "Insufficient balance for the margin of this position".into(),
+                ));
+            }
+
+            let (written, average_premium, held_margin) = short
+                .map(|short| (-short.quantity, short.average_premium, short.margin))
+                .unwrap_or_else(|| (0, BigDecimal::from(0), BigDecimal::from(0)));
+            let total = written + quantity;
+            let average_premium = (average_premium * BigDecimal::from(written)
+                + &premium * BigDecimal::from(quantity))
+                / BigDecimal::from(total);
+            option_repository
+                .upsert_position(
+                    portfolio.user_id,
+                    portfolio.id,
+                    contract.id,
+                    -total,
+                    average_premium.with_scale_round(4, RoundingMode::HalfUp),
+                    held_margin + &margin,
+                )
+                .await?;
+            let ledger_repository = LedgerRepository::new(&state.pg_pool);
+            ledger_repository
+                .post_trade(portfolio.id, "premium", value, fee.clone(), None)
+                .await?;
+            ledger_repository
+                .post(portfolio.id, "margin", -margin, None)
+                .await?;
+        }
+    }
+
+    option_repository
+        .record_trade(
+            portfolio.user_id,
+            portfolio.id,
+            contract.id,
+            "sell",
+            quantity,
+            premium,
+            fee,
+        )
+        .await
+}
+
+/// Settle expired contracts every `EXPIRY_CHECK_INTERVAL`
+pub async fn expiry_worker(state: Arc<AppState>) -> Result<()> {
+    let mut interval = tokio::time::interval(EXPIRY_CHECK_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+        if let Err(e) = settle_expired(&state).await {
+            tracing::warn!("Failed to settle expired option contracts: {}", e);
+        }
+    }
+}
+
+/// Cash-settle every expired contract whose underlying has a price
+async fn settle_expired(state: &AppState) -> Result<()> {
+    let option_repository = OptionRepository::new(&state.pg_pool);
+    let contracts = option_repository.get_due_contracts().await?;
+    let mut underlyings: Vec<String> = contracts.iter().map(|c| c.underlying.clone()).collect();
+    underlyings.sort();
+    underlyings.dedup();
+    let prices: HashMap<String, BigDecimal> = PriceCache::new(&state.redis_pool)
+        .prices(&underlyings)
+        .await?;
+
+    for contract in contracts {
+        let Some(spot) = prices.get(&contract.underlying) else {
+            tracing::warn!(
+                "No price of {} to settle option contract {}",
+                contract.underlying,
+                contract.id
+            );
+            continue;
+        };
+        if !option_repository
+            .settle_contract(contract.id, spot.clone())
+            .await?
+        {
+            continue;
+        }
+
+        let intrinsic = intrinsic_value(&contract.kind, &contract.strike, spot)
+            .with_scale_round(4, RoundingMode::HalfUp);
+        let positions = option_repository
+            .get_positions_by_contract(contract.id)
+            .await?;
+        for position in &positions {
+            if let Err(e) = settle_position(state, position, &intrinsic).await {
+                tracing::error!("Failed to settle option position {}: {}", position.id, e);
+            }
+        }
+        tracing::info!(
+            "Settled option contract {} at {} with {} positions",
+            contract.id,
+            spot,
+            positions.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Pay out or collect the intrinsic value of a position and close it
+async fn settle_position(
+    state: &AppState,
+    position: &OptionPosition,
+    intrinsic: &BigDecimal,
+) -> Result<()> {
+    let portfolios_repository = PortfolioRepository::new(&state.pg_pool);
+    let ledger_repository = LedgerRepository::new(&state.pg_pool);
+    let option_repository = OptionRepository::new(&state.pg_pool);
+    let in_the_money = *intrinsic > BigDecimal::zero();
+    let amount = intrinsic * BigDecimal::from(position.quantity.abs() * MULTIPLIER);
+
+    let trade_type = if position.quantity > 0 {
+        if in_the_money {
+            ledger_repository
+                .post(position.portfolio_id, "settlement", amount, None)
+                .await?;
+            "exercise"
+        } else {
+            "expiry"
+        }
+    } else {
+        let portfolio = portfolios_repository
+            .get_portfolio(position.portfolio_id)
+            .await?
+            .ok_or(Error::NotFound)?;
+        if position.margin > BigDecimal::zero() {
+            ledger_repository
+                .post(portfolio.id, "margin", position.margin.clone(), None)
+                .await?;
+        }
+        if in_the_money {
+            let available = &portfolio.balance + &position.margin;
+            let mut charge = amount.with_scale_round(2, RoundingMode::HalfUp);
+            if charge > available {
+                tracing::warn!(
+                    "Writing off {} of option assignment in portfolio {}",
+                    &charge - &available,
+                    portfolio.id
+                );
+                charge = available.max(BigDecimal::from(0));
+            }
+            ledger_repository
+                .post(portfolio.id, "settlement", -charge, None)
+                .await?;
+            "assignment"
+        } else {
+            "expiry"
+        }
+    };
+
+    option_repository
+        .record_trade(
+            position.user_id,
+            position.portfolio_id,
+            position.contract_id,
+            trade_type,
+            position.quantity.abs(),
+            intrinsic.clone(),
+            BigDecimal::from(0),
+        )
+        .await?;
+    option_repository.delete_position(position.id).await
+}
@@ -0,0 +1,229 @@
+//! # Portfolio Valuation
+//!
+//! Values a portfolio's positions at the latest cached prices and derives
+//! cash, money market balance, loan debt, option positions, market value,
+//! unrealized P&L and total equity. Bond positions include the interest
+//! accrued since their last coupon. The money market belongs to the user's default portfolio;
+//! a whole account is valued by combining all of its portfolios except
+//! those entered in competitions.
+
+use std::collections::HashMap;
+
+use bigdecimal::{BigDecimal, RoundingMode, Zero};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    AppState, Result,
+    models::{holding::Holding, option::OptionPosition, portfolio::Portfolio},
+    repository::{
+        loan_repository::LoanRepository, money_market_repository::MoneyMarketRepository,
+        option_repository::OptionRepository, portfolio_repository::PortfolioRepository,
+    },
+    services::{bonds, options, price_cache::PriceCache},
+};
+
+/// Valuation of a single position
+#[derive(Debug, Clone)]
+pub struct PositionValuation {
+    pub ticker: String,
+    pub quantity: i32,
+    pub average_price: BigDecimal,
+    /// Latest price, `None` when no price is cached for the ticker
+    pub current_price: Option<BigDecimal>,
+    /// Interest accrued on a bond position since its last coupon
+    pub accrued_interest: BigDecimal,
+    pub cost_basis: BigDecimal,
+    pub market_value: BigDecimal,
+    pub unrealized_pnl: BigDecimal,
+    pub unrealized_pnl_percent: BigDecimal,
+    /// When the position was opened
+    pub created_at: DateTime<Utc>,
+    /// Last change of the position
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Valuation of a portfolio or a whole account
+#[derive(Debug, Clone)]
+pub struct PortfolioValuation {
+    pub cash: BigDecimal,
+    /// Cash swept into the money market
+    pub money_market: BigDecimal,
+    /// Outstanding debt on open secured loans
+    pub loans: BigDecimal,
+    /// Model value of option positions, short ones negative, plus the margin
+    /// they hold
+    pub options: BigDecimal,
+    pub market_value: BigDecimal,
+    pub cost_basis: BigDecimal,
+    pub unrealized_pnl: BigDecimal,
+    /// Cash, money market balance, options and market value of all
+    /// positions, less loans
+    pub equity: BigDecimal,
+    pub positions: Vec<PositionValuation>,
+}
+
+/// Value a portfolio at the latest cached prices
+pub async fn value_portfolio(
+    state: &AppState,
+    portfolio: &Portfolio,
+) -> Result<PortfolioValuation> {
+    let holdings = state
+        .repos
+        .holdings
+        .get_holdings_by_portfolio(portfolio.id)
+        .await?;
+
+    let money_market = if portfolio.is_default {
+        money_market_balance(state, portfolio.user_id).await?
+    } else {
+        BigDecimal::from(0)
+    };
+    let loans = LoanRepository::new(&state.pg_pool)
+        .get_portfolio_outstanding_total(portfolio.id)
+        .await?;
+
+    let tickers: Vec<String> = holdings.iter().map(|h| h.ticker.clone()).collect();
+    let prices = PriceCache::new(&state.redis_pool).prices(&tickers).await?;
+    let accrued = bonds::accrued_interest_by_ticker(state, &tickers).await?;
+    let option_positions = OptionRepository::new(&state.pg_pool)
+        .get_positions_by_portfolio(portfolio.id)
+        .await?;
+
+    let valuation = valuate(
+        portfolio.balance.clone(),
+        money_market,
+        loans,
+        holdings,
+        &prices,
+        &accrued,
+    );
+    with_options(state, valuation, option_positions).await
+}
+
+/// Value all of the user's portfolios together at the latest cached prices
+///
+/// Competition portfolios are left out, since their starting cash was never
+/// deposited. Positions in the same ticker held in different portfolios are listed separately.
+pub async fn value_account(state: &AppState, user_id: i32) -> Result<PortfolioValuation> {
+    let cash = PortfolioRepository::new(&state.pg_pool)
+        .get_total_balance(user_id)
+        .await?;
+    let holdings = state.repos.holdings.get_holdings_by_user(user_id).await?;
+
+    let money_market = money_market_balance(state, user_id).await?;
+    let loans = LoanRepository::new(&state.pg_pool)
+        .get_outstanding_total(user_id)
+        .await?;
+
+    let tickers: Vec<String> = holdings.iter().map(|h| h.ticker.clone()).collect();
+    let prices = PriceCache::new(&state.redis_pool).prices(&tickers).await?;
+    let accrued = bonds::accrued_interest_by_ticker(state, &tickers).await?;
+    let option_positions = OptionRepository::new(&state.pg_pool)
+        .get_positions_by_user(user_id)
+        .await?;
+
+    let valuation = valuate(cash, money_market, loans, holdings, &prices, &accrued);
+    with_options(state, valuation, option_positions).await
+}
+
+/// Add the value of option positions to a valuation's equity
+async fn with_options(
+    state: &AppState,
+    mut valuation: PortfolioValuation,
+    positions: Vec<OptionPosition>,
+) -> Result<PortfolioValuation> {
+    if positions.is_empty() {
+        return Ok(valuation);
+    }
+
+    let options = options::total_value(&options::value_positions(state, positions).await?);
+    valuation.equity += &options;
+    valuation.options = options;
+
+    Ok(valuation)
+}
+
+async fn money_market_balance(state: &AppState, user_id: i32) -> Result<BigDecimal> {
+    Ok(MoneyMarketRepository::new(&state.pg_pool)
+        .get_account(user_id)
+        .await?
+        .map(|account| account.balance)
+        .unwrap_or_else(|| BigDecimal::from(0)))
+}
+
+/// Value positions at the given prices, adding the interest accrued per bond
+/// in `accrued`
+///
+/// Positions without a known price are valued at their average price, so they
+/// count towards equity at cost and contribute no unrealized P&L beyond their
+/// accrued interest.
+pub fn valuate(
+    cash: BigDecimal,
+    money_market: BigDecimal,
+    loans: BigDecimal,
+    holdings: Vec<Holding>,
+    prices: &HashMap<String, BigDecimal>,
+    accrued: &HashMap<String, BigDecimal>,
+) -> PortfolioValuation {
+    let zero = BigDecimal::from(0);
+    let positions: Vec<PositionValuation> = holdings
+        .into_iter()
+        .map(|h| {
+            let quantity = BigDecimal::from(h.quantity);
+            let current_price = prices.get(&h.ticker).cloned();
+            let cost_basis = &h.average_price * &quantity;
+            let accrued_interest = accrued
+                .get(&h.ticker)
+                .map_or_else(|| BigDecimal::from(0), |accrued| accrued * &quantity);
+            let market_value = match &current_price {
+                Some(price) => price * &quantity,
+                None => cost_basis.clone(),
+            } + &accrued_interest;
+            let unrealized_pnl = &market_value - &cost_basis;
+            let unrealized_pnl_percent = percent_of(&unrealized_pnl, &cost_basis);
+
+            PositionValuation {
+                ticker: h.ticker,
+                quantity: h.quantity,
+                average_price: h.average_price,
+                current_price,
+                accrued_interest,
+                cost_basis,
+                market_value,
+                unrealized_pnl,
+                unrealized_pnl_percent,
+                created_at: h.created_at,
+                updated_at: h.updated_at,
+            }
+        })
+        .collect();
+
+    let market_value = positions
+        .iter()
+        .fold(zero.clone(), |total, p| total + &p.market_value);
+    let cost_basis = positions
+        .iter()
+        .fold(zero, |total, p| total + &p.cost_basis);
+    let unrealized_pnl = &market_value - &cost_basis;
+    let equity = &cash + &money_market + &market_value - &loans;
+
+    PortfolioValuation {
+        cash,
+        money_market,
+        loans,
+        options: BigDecimal::from(0),
+        market_value,
+        cost_basis,
+        unrealized_pnl,
+        equity,
+        positions,
+    }
+}
+
+/// `part` as a percentage of `whole`, rounded to 4 decimal places
+pub fn percent_of(part: &BigDecimal, whole: &BigDecimal) -> BigDecimal {
+    if whole.is_zero() {
+        return BigDecimal::zero();
+    }
+    (part * BigDecimal::from(100) / whole).with_scale_round(4, RoundingMode::HalfUp)
+}
@@ -0,0 +1,185 @@
+//! # Positions
+//!
+//! Shared bookkeeping for shares entering and leaving a position, used by
+//! market orders, dividend reinvestment, bond redemptions and loan
+//! liquidations.
+//!
+//! Shares are added and removed under a lock of the position, in one
+//! database transaction with everything the trade books: the holding, its
+//! lots, the realized gains and the cash. Either the whole trade is booked
+//! or none of it is. A sale fails with a conflict when another one took the
+//! shares first, as does a purchase racing another one to open the
+//! position.
+
+use bigdecimal::BigDecimal;
+
+use crate::{
+    AppState, Error, Result,
+    models::{holding::Holding, tax_lot::TaxLot, user_settings::CostBasisMethod},
+    repository::{
+        ledger_repository::TradePosting, tax_lot_repository::TaxLotRepository,
+        user_settings_repository::UserSettingsRepository,
+    },
+    services::cost_basis::{self, RealizedLot},
+};
+
+/// Error of a trade that lost a race with another update of the position
+pub fn position_changed() -> Error {
+    Error::Conflict("Position changed by another request, try again".into())
+}
+
+/// Average price of a position of `held` shares at `average_price` once
+/// `quantity` more are bought at `price`
+pub fn average_after_buy(
+    held: i32,
+    average_price: &BigDecimal,
+    quantity: i32,
+    price: &BigDecimal,
+) -> BigDecimal {
+    (average_price * held + price * quantity) / (held + quantity)
+}
+
+/// Average price of the shares left in a position at `average_price` once
+/// the `realized` slices are sold out of its open `lots`
+///
+/// With specific-lot methods the shares left are valued at their open lots.
+pub fn average_after_sell(
+    method: CostBasisMethod,
+    average_price: &BigDecimal,
+    lots: &[TaxLot],
+    realized: &[RealizedLot],
+) -> BigDecimal {
+    match method {
+        CostBasisMethod::Average => average_price.clone(),
+        CostBasisMethod::Fifo | CostBasisMethod::Lifo => {
+            cost_basis::remaining_average(lots, realized).unwrap_or_else(|| average_price.clone())
+        }
+    }
+}
+
+/// Add `quantity` shares bought at `price` to a portfolio's position
+///
+/// Updates the holding's average price (or creates the holding), opens a
+/// tax lot linked to the purchase transaction and books the `posting`, all
+/// in one database transaction holding the position's lock.
+#[allow(clippy::too_many_arguments)]
+pub async fn add_shares(
+    state: &AppState,
+    user_id: i32,
+    portfolio_id: i32,
+    ticker: &str,
+    quantity: i32,
+    price: &BigDecimal,
+    transaction_id: i32,
+    posting: TradePosting<'_>,
+) -> Result<()> {
+    let bought = TaxLotRepository::new(&state.pg_pool)
+        .buy_position(
+            user_id,
+            portfolio_id,
+            ticker,
+            quantity,
+            price,
+            transaction_id,
+            posting,
+            |holding| average_after_buy(holding.quantity, &holding.average_price, quantity, price),
+        )
+        .await?;
+    if !bought {
+        // Another purchase opened the position at the same time
+        return Err(position_changed());
+    }
+
+    Ok(())
+}
+
+/// Remove `quantity` shares sold at `price` from a portfolio's position
+///
+/// Updates the holding, consumes tax lots according to the user's
+/// cost-basis method, records the realized gains against the sell
+/// transaction and books the `posting`, all in one database transaction
+/// holding the position's lock, so concurrent sells never take the same lot
+/// twice. Returns the total realized gain.
+pub async fn remove_shares(
+    state: &AppState,
+    holding: Holding,
+    quantity: i32,
+    price: &BigDecimal,
+    transaction_id: i32,
+    posting: TradePosting<'_>,
+) -> Result<BigDecimal> {
+    let cost_basis_method = UserSettingsRepository::new(&state.pg_pool)
+        .get_cost_basis_method(holding.user_id)
+        .await?;
+
+    let realized = TaxLotRepository::new(&state.pg_pool)
+        .sell_position(
+            holding.portfolio_id,
+            &holding.ticker,
+            quantity,
+            transaction_id,
+            cost_basis_method,
+            posting,
+            |holding, lots| {
+                let realized = cost_basis::realize(
+                    cost_basis_method,
+                    lots,
+                    quantity,
+                    &holding.average_price,
+                    price,
+                );
+                let average_price = average_after_sell(
+                    cost_basis_method,
+                    &holding.average_price,
+                    lots,
+                    &realized,
+                );
+                (realized, average_price)
+            },
+        )
+        .await?
+        // Another sell took the shares since the holding was read
+        .ok_or_else(position_changed)?;
+
+    let realized_gain = realized
+        .iter()
+        .fold(BigDecimal::from(0), |total, lot| total + &lot.gain);
+
+    Ok(realized_gain)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn buys_average_into_the_position() {
+        let average = average_after_buy(10, &BigDecimal::from(100), 30, &BigDecimal::from(120));
+        assert_eq!(average, BigDecimal::from(115));
+    }
+
+    fn price() -> impl Strategy<Value = BigDecimal> {
+        (1i64..1_000_000).prop_map(|cents| BigDecimal::new(cents.into(), 2))
+    }
+
+    proptest! {
+        #[test]
+        fn averages_stay_within_the_fill_prices(
+            held in 1..100_000i32,
+            average in price(),
+            quantity in 1..100_000i32,
+            fill in price(),
+        ) {
+            let after = average_after_buy(held, &average, quantity, &fill);
+
+            prop_assert!(after >= average.clone().min(fill.clone()));
+            prop_assert!(after <= average.clone().max(fill.clone()));
+            // The position still costs what was paid for it
+            let cost = &average * BigDecimal::from(held) + &fill * BigDecimal::from(quantity);
+            let valued = &after * BigDecimal::from(held + quantity);
+            prop_assert!((valued - cost).abs() < BigDecimal::new(1.into(), 6));
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! # Price Cache
+//!
+//! The price updater keeps the latest price of every ticker on Redis under the
+//! ticker itself, and its publish time in Unix milliseconds under
+//! [`price_time_key`]. [`PriceCache`] reads them back for any number of
+//! tickers in a single round trip, so valuing a portfolio costs one `MGET`
+//! however many positions it holds.
+//!
+//! Values that do not parse are treated as missing, like tickers the cache
+//! knows nothing about.
+
+use std::collections::HashMap;
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use tracing::Instrument;
+
+use crate::{
+    Error, Result,
+    timing::{self, Phase},
+};
+
+/// Redis key holding the time of the latest price update of `ticker` in Unix milliseconds
+pub fn price_time_key(ticker: &str) -> String {
+    format!("price_time:{}", ticker)
+}
+
+/// Latest cached price of a ticker
+#[derive(Debug, Clone)]
+pub struct CachedPrice {
+    pub price: BigDecimal,
+    /// When the price was published; `None` for prices cached without a time
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// Batched reads of the prices cached on Redis
+pub struct PriceCache<'a> {
+    pool: &'a bb8::Pool<bb8_redis::RedisConnectionManager>,
+}
+
+impl<'a> PriceCache<'a> {
+    pub fn new(pool: &'a bb8::Pool<bb8_redis::RedisConnectionManager>) -> Self {
+        PriceCache { pool }
+    }
+
+    /// Latest prices of `tickers` with one `MGET`; tickers without a price are left out
+    pub async fn prices(&self, tickers: &[String]) -> Result<HashMap<String, BigDecimal>> {
+        if tickers.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let values: Vec<Option<String>> = async {
+            self.pool
+                .get()
+                .await
+                .map_err(|e| Error::RedisError(e.to_string()))?
+                .mget(tickers)
+                .await
+                .map_err(|e| Error::RedisError(e.to_string()))
+        }
+        .instrument(timing::span(Phase::Redis))
+        .await?;
+
+        Ok(tickers
+            .iter()
+            .zip(values)
+            .filter_map(|(ticker, value)| Some((ticker.clone(), parse_price(value)?)))
+            .collect())
+    }
+
+    /// Latest prices of `tickers` with their publish times, in one pipeline;
+    /// tickers without a price are left out
+    pub async fn prices_with_times(
+        &self,
+        tickers: &[String],
+    ) -> Result<HashMap<String, CachedPrice>> {
+        if tickers.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let time_keys: Vec<String> = tickers.iter().map(|t| price_time_key(t)).collect();
+        let (prices, times): (Vec<Option<String>>, Vec<Option<i64>>) = async {
+            let mut conn = self
+                .pool
+                .get()
+                .await
+                .map_err(|e| Error::RedisError(e.to_string()))?;
+            redis::pipe()
+                .mget(tickers)
+                .mget(&time_keys)
+                .query_async(&mut *conn)
+                .await
+                .map_err(|e| Error::RedisError(e.to_string()))
+        }
+        .instrument(timing::span(Phase::Redis))
+        .await?;
+
+        Ok(tickers
+            .iter()
+            .zip(prices.into_iter().zip(times))
+            .filter_map(|(ticker, (price, millis))| {
+                let cached = CachedPrice {
+                    price: parse_price(price)?,
+                    timestamp: millis.and_then(DateTime::from_timestamp_millis),
+                };
+                Some((ticker.clone(), cached))
+            })
+            .collect())
+    }
+
+    /// Latest price of `ticker` with its publish time
+    pub async fn price(&self, ticker: &str) -> Result<Option<CachedPrice>> {
+        let ticker = ticker.to_string();
+        Ok(self
+            .prices_with_times(std::slice::from_ref(&ticker))
+            .await?
+            .remove(&ticker))
+    }
+}
+
+fn parse_price(value: Option<String>) -> Option<BigDecimal> {
+    value?.parse().ok()
+}
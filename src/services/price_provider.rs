@@ -0,0 +1,455 @@
+//! # Price Providers
+//!
+//! Sources of live prices for the price updater. The gRPC price feed is the
+//! default; a REST provider polls an HTTP market-data API instead, and the
+//! synthetic provider makes prices up so the simulator runs without any
+//! external service. The provider is chosen with `PRICE_PROVIDER`.
+//!
+//! Providers only produce raw prices: price banding, halts, caching and
+//! publishing are applied by the updater whatever the source.
+//!
+//! Simulated news only moves synthetic prices: when a scheduled news event is
+//! published, the synthetic provider jumps the ticker's price by the event's
+//! impact and scales its volatility for a while. Other providers follow the
+//! real market, so their news is informational.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures_util::{
+    StreamExt,
+    future::BoxFuture,
+    stream::{self, BoxStream},
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_distr::StandardNormal;
+use sqlx::PgPool;
+
+use crate::{
+    AppState, Error, Result, config::Config, grpc::GrpcPriceProvider,
+    models::news_event::NewsEvent, repository::news_repository::NewsRepository,
+    services::http::HttpClient,
+};
+
+/// Price assumed for tickers the synthetic provider has never seen
+const SYNTHETIC_START_PRICE: f64 = 100.0;
+/// Seconds in a year, the unit of synthetic drift and volatility
+const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+/// REST requests in flight at once while polling
+const REST_CONCURRENCY: usize = 8;
+/// Time a REST request has to answer
+const REST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A price published by a provider
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceTick {
+    pub ticker: String,
+    pub price: f64,
+}
+
+/// Prices streamed by a provider until it fails or runs out
+pub type PriceStream = BoxStream<'static, Result<PriceTick>>;
+
+/// A source of live prices
+pub trait PriceProvider: Send + Sync {
+    /// Short name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Start streaming prices of `tickers`
+    ///
+    /// Fails when the source cannot be reached; errors while streaming end
+    /// the stream, after which the updater subscribes again.
+    fn subscribe(&self, tickers: Vec<String>) -> BoxFuture<'_, Result<PriceStream>>;
+}
+
+/// The provider selected by the configuration
+pub fn from_config(state: &AppState) -> Box<dyn PriceProvider> {
+    let config = &state.config;
+    match config.price_provider.as_str() {
+        "rest" => Box::new(RestPriceProvider::new(config)),
+        "synthetic" => Box::new(SyntheticPriceProvider::new(state)),
+        _ => Box::new(GrpcPriceProvider::new(config)),
+    }
+}
+
+/// Polls an HTTP API for the price of every ticker in turn
+///
+/// The URL template's `{ticker}` placeholder is replaced by each ticker and
+/// the price is read from the JSON response at the configured JSON pointer.
+/// Tickers that fail to load are skipped for the round; the stream only
+/// fails when a whole round returns nothing.
+#[derive(Clone)]
+pub struct RestPriceProvider {
+    http: HttpClient,
+    url_template: String,
+    price_pointer: String,
+    interval: Duration,
+}
+
+impl RestPriceProvider {
+    pub fn new(config: &Config) -> Self {
+        RestPriceProvider {
+            http: HttpClient::new(REST_TIMEOUT),
+            url_template: config.price_rest_url.clone().unwrap_or_default(),
+            price_pointer: config.price_rest_pointer.clone(),
+            interval: Duration::from_secs(config.price_poll_interval_secs),
+        }
+    }
+
+    async fn fetch(&self, ticker: &str) -> Result<f64> {
+        let url = self.url_template.replace("{ticker}", ticker);
+        let response = self
+            .http
+            .get(&url)
+            .await
+            .map_err(|e| Error::PriceFeed(e.to_string()))?;
+        if !response.status.is_success() {
+            return Err(Error::PriceFeed(format!(
+                "{} answered {}",
+                url, response.status
+            )));
+        }
+        let body: serde_json::Value =
+            serde_json::from_slice(&response.body).map_err(|e| Error::PriceFeed(e.to_string()))?;
+
+        // APIs disagree on whether prices are numbers or strings
+        match body.pointer(&self.price_pointer) {
+            Some(serde_json::Value::Number(price)) => price.as_f64(),
+            Some(serde_json::Value::String(price)) => price.parse().ok(),
+            _ => None,
+        }
+        .filter(|price| price.is_finite() && *price > 0.0)
+        .ok_or_else(|| {
+            Error::PriceFeed(format!(
+                "No price at {} in the response for {}",
+                self.price_pointer, ticker
+            ))
+        })
+    }
+
+    async fn poll(&self, tickers: &[String]) -> Result<Vec<PriceTick>> {
+        let results: Vec<(String, Result<f64>)> = stream::iter(tickers.to_vec())
+            .map(|ticker| async move {
+                let price = self.fetch(&ticker).await;
+                (ticker, price)
+            })
+            .buffer_unordered(REST_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut ticks = Vec::with_capacity(results.len());
+        let mut last_error = None;
+        for (ticker, result) in results {
+            match result {
+                Ok(price) => ticks.push(PriceTick { ticker, price }),
+                Err(e) => {
+                    tracing::warn!("Failed to poll the price of {}: {}", ticker, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if ticks.is_empty() => Err(e),
+            _ => Ok(ticks),
+        }
+    }
+}
+
+impl PriceProvider for RestPriceProvider {
+    fn name(&self) -> &'static str {
+        "rest"
+    }
+
+    fn subscribe(&self, tickers: Vec<String>) -> BoxFuture<'_, Result<PriceStream>> {
+        let provider = Arc::new(self.clone());
+
+        Box::pin(async move {
+            // Fail the subscription right away when the API is unreachable
+            let first = provider.poll(&tickers).await?;
+            let mut interval = tokio::time::interval(provider.interval);
+            interval.tick().await;
+
+            let later = stream::unfold(
+                (provider, tickers, interval),
+                |(provider, tickers, mut interval)| async move {
+                    interval.tick().await;
+                    let round = provider.poll(&tickers).await;
+                    Some((round, (provider, tickers, interval)))
+                },
+            );
+
+            let ticks = stream::once(async { Ok(first) })
+                .chain(later)
+                .flat_map(|round| match round {
+                    Ok(ticks) => stream::iter(ticks).map(Ok).left_stream(),
+                    Err(e) => stream::once(async { Err(e) }).right_stream(),
+                })
+                .boxed();
+            Ok(ticks)
+        })
+    }
+}
+
+/// Simulates every ticker as an independent geometric Brownian motion
+///
+/// Drift and volatility are annualized; each step advances simulated time by
+/// the tick interval times the time scale, so demos can compress a trading
+/// day into minutes. Walks start from the cached price, so restarting the
+/// server or switching providers does not make prices jump.
+///
+/// With a seed, every ticker instead starts at the default price and draws
+/// its moves from a generator seeded with the seed and the ticker, so runs
+/// and instances with the same seed publish the same path for a ticker
+/// whichever other tickers are listed. Seeded walks are kept across
+/// resubscriptions, so listing a ticker does not restart the others.
+///
+/// Published news events are claimed every step: the price jumps by the
+/// event's impact and the volatility is scaled for the event's duration.
+pub struct SyntheticPriceProvider {
+    redis_pool: Arc<bb8::Pool<bb8_redis::RedisConnectionManager>>,
+    pg_pool: Arc<PgPool>,
+    interval: Duration,
+    motion: BrownianMotion,
+    seed: Option<u64>,
+    /// Walks of every ticker seen so far, only kept when seeded
+    seeded_walks: Arc<Mutex<HashMap<String, Walk>>>,
+    /// Volatility factors from news, per ticker; kept across resubscriptions
+    boosts: Arc<Mutex<HashMap<String, VolatilityBoost>>>,
+}
+
+/// The simulated price of a ticker and the generator of its moves
+#[derive(Debug, Clone)]
+struct Walk {
+    /// Unrounded price, so rounding to cents does not accumulate
+    price: f64,
+    rng: StdRng,
+}
+
+/// Volatility scaling caused by a news event
+#[derive(Debug, Clone, Copy)]
+struct VolatilityBoost {
+    factor: f64,
+    until: Instant,
+}
+
+/// Parameters of a geometric Brownian motion sampled at fixed steps
+#[derive(Debug, Clone, Copy)]
+struct BrownianMotion {
+    drift: f64,
+    volatility: f64,
+    /// Simulated years per step
+    step_years: f64,
+}
+
+impl BrownianMotion {
+    /// Price after one step from `price`, given a standard normal draw `z`
+    fn step(self, price: f64, z: f64) -> f64 {
+        let drift = (self.drift - self.volatility.powi(2) / 2.0) * self.step_years;
+        let shock = self.volatility * self.step_years.sqrt() * z;
+        price * (drift + shock).exp()
+    }
+
+    /// The same motion with its volatility scaled by `factor`
+    fn scaled(self, factor: f64) -> Self {
+        BrownianMotion {
+            volatility: self.volatility * factor,
+            ..self
+        }
+    }
+}
+
+impl SyntheticPriceProvider {
+    pub fn new(state: &AppState) -> Self {
+        let config = &state.config;
+        let interval = Duration::from_secs(config.price_poll_interval_secs);
+        SyntheticPriceProvider {
+            redis_pool: state.redis_pool.clone(),
+            pg_pool: state.pg_pool.clone(),
+            interval,
+            motion: BrownianMotion {
+                drift: config.synthetic_drift,
+                volatility: config.synthetic_volatility,
+                step_years: interval.as_secs_f64() * config.synthetic_time_scale / SECONDS_PER_YEAR,
+            },
+            seed: config.synthetic_seed,
+            seeded_walks: Arc::new(Mutex::new(HashMap::new())),
+            boosts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Walks of `tickers`, continuing the seeded walks of known tickers
+    async fn walks(&self, tickers: &[String]) -> Result<Arc<Mutex<HashMap<String, Walk>>>> {
+        let Some(seed) = self.seed else {
+            let walks = self
+                .starting_prices(tickers)
+                .await?
+                .into_iter()
+                .map(|(ticker, price)| {
+                    let rng = StdRng::from_os_rng();
+                    (ticker, Walk { price, rng })
+                })
+                .collect();
+            return Ok(Arc::new(Mutex::new(walks)));
+        };
+
+        let mut walks = self
+            .seeded_walks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for ticker in tickers {
+            walks.entry(ticker.clone()).or_insert_with(|| Walk {
+                price: SYNTHETIC_START_PRICE,
+                rng: StdRng::seed_from_u64(ticker_seed(seed, ticker)),
+            });
+        }
+        drop(walks);
+
+        Ok(self.seeded_walks.clone())
+    }
+
+    async fn starting_prices(&self, tickers: &[String]) -> Result<HashMap<String, f64>> {
+        if tickers.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let cached: Vec<Option<f64>> = redis::cmd("MGET")
+            .arg(tickers)
+            .query_async(
+                &mut *self
+                    .redis_pool
+                    .get()
+                    .await
+                    .map_err(|e| Error::RedisError(e.to_string()))?,
+            )
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+
+        Ok(tickers
+            .iter()
+            .zip(cached)
+            .map(|(ticker, price)| (ticker.clone(), price.unwrap_or(SYNTHETIC_START_PRICE)))
+            .collect())
+    }
+}
+
+impl PriceProvider for SyntheticPriceProvider {
+    fn name(&self) -> &'static str {
+        "synthetic"
+    }
+
+    fn subscribe(&self, tickers: Vec<String>) -> BoxFuture<'_, Result<PriceStream>> {
+        Box::pin(async move {
+            let walks = self.walks(&tickers).await?;
+            let interval = tokio::time::interval(self.interval);
+            let motion = self.motion;
+            let pg_pool = self.pg_pool.clone();
+            let boosts = self.boosts.clone();
+            let tickers = Arc::new(tickers);
+
+            let rounds = stream::unfold(interval, move |mut interval| {
+                let pg_pool = pg_pool.clone();
+                let walks = walks.clone();
+                let boosts = boosts.clone();
+                let tickers = tickers.clone();
+                async move {
+                    interval.tick().await;
+                    let news = claim_news(&pg_pool, &tickers).await;
+
+                    let mut walks = walks
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    let mut boosts = boosts
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    let now = Instant::now();
+                    for event in news {
+                        apply_news(&event, &mut walks, &mut boosts, now);
+                    }
+                    boosts.retain(|_, boost| boost.until > now);
+
+                    let ticks: Vec<PriceTick> = tickers
+                        .iter()
+                        .filter_map(|ticker| {
+                            let walk = walks.get_mut(ticker)?;
+                            let motion = match boosts.get(ticker) {
+                                Some(boost) => motion.scaled(boost.factor),
+                                None => motion,
+                            };
+                            walk.price = motion.step(walk.price, walk.rng.sample(StandardNormal));
+                            Some(PriceTick {
+                                ticker: ticker.clone(),
+                                price: (walk.price * 100.0).round() / 100.0,
+                            })
+                        })
+                        .collect();
+                    drop(boosts);
+                    drop(walks);
+                    Some((ticks, interval))
+                }
+            });
+
+            let ticks = rounds.flat_map(stream::iter).map(Ok).boxed();
+            Ok(ticks)
+        })
+    }
+}
+
+/// Seed of a ticker's walk, stable across builds and platforms (FNV-1a)
+fn ticker_seed(seed: u64, ticker: &str) -> u64 {
+    ticker
+        .bytes()
+        .fold(seed ^ 0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+}
+
+/// Claim the published news of the simulated tickers, oldest first
+///
+/// Failures are logged and retried on the next step, so an unreachable
+/// database delays news instead of stopping prices.
+async fn claim_news(pg_pool: &PgPool, tickers: &[String]) -> Vec<NewsEvent> {
+    match NewsRepository::new(pg_pool).claim_due(tickers).await {
+        Ok(mut news) => {
+            news.sort_by_key(|event| (event.publish_at, event.id));
+            news
+        }
+        Err(e) => {
+            tracing::warn!("Failed to claim news events: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Jump the price of the event's ticker and start its volatility boost
+fn apply_news(
+    event: &NewsEvent,
+    walks: &mut HashMap<String, Walk>,
+    boosts: &mut HashMap<String, VolatilityBoost>,
+    now: Instant,
+) {
+    let Some(walk) = walks.get_mut(&event.ticker) else {
+        return;
+    };
+    walk.price *= 1.0 + event.price_impact_percent / 100.0;
+    tracing::info!(
+        "Applied {} news to {}: {:+}% to {:.2}",
+        event.event_type,
+        event.ticker,
+        event.price_impact_percent,
+        walk.price
+    );
+
+    if event.volatility_duration_secs > 0 {
+        boosts.insert(
+            event.ticker.clone(),
+            VolatilityBoost {
+                factor: event.volatility_multiplier,
+                until: now + Duration::from_secs(event.volatility_duration_secs as u64),
+            },
+        );
+    }
+}
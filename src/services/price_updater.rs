@@ -0,0 +1,301 @@
+//! # Price Updater
+//!
+//! Streams prices of every listed instrument from the configured price
+//! provider into Redis. Each update is banded against the previous price,
+//! cached with its time, published on the ticker's pub/sub channel for live
+//! subscribers and aggregated into candles. A supervisor restarts the updater
+//! whenever the provider fails and exposes its state through `GET /health`.
+//!
+//! Tickers driven by a running historical replay ignore the provider until
+//! the replay ends; the replay publishes their prices through the same steps.
+//! Index and ETF baskets are not streamed; the basket worker prices them from
+//! their constituents.
+
+use std::{sync::Arc, time::Duration};
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    AppState, Error, Result,
+    repository::{
+        instrument_repository::InstrumentRepository, price_candle_repository::PriceCandleRepository,
+    },
+    services::{
+        event_stream::DomainEvent,
+        instruments, matching, price_cache,
+        price_provider::{self, PriceProvider, PriceTick},
+        replay,
+    },
+};
+
+/// Delay before the first restart of a failed price updater
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// Longest delay between restarts while the feed stays unreachable
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Connection state of the price feed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedStatus {
+    /// First connection attempt in progress
+    #[default]
+    Connecting,
+    /// Streaming prices
+    Connected,
+    /// The last attempt failed; waiting to retry
+    Reconnecting,
+}
+
+/// Health of the price updater, reported by `GET /health`
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct FeedHealth {
+    pub status: FeedStatus,
+    /// When the current stream was opened
+    pub connected_since: Option<DateTime<Utc>>,
+    /// When the last price update was received
+    pub last_update_at: Option<DateTime<Utc>>,
+    /// Why the updater last stopped
+    pub last_error: Option<String>,
+    /// How many times the updater was restarted
+    pub restarts: u64,
+}
+
+/// Current health of the price feed
+pub fn health(state: &AppState) -> FeedHealth {
+    state
+        .feed_health
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+fn update_health(state: &AppState, update: impl FnOnce(&mut FeedHealth)) {
+    update(
+        &mut state
+            .feed_health
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+    );
+}
+
+/// Keep the price updater running, restarting it whenever it stops
+///
+/// Restarts back off exponentially from one second up to a minute while the
+/// feed stays unreachable; the delay resets once a stream was established.
+pub async fn supervise(state: Arc<AppState>) {
+    let provider = price_provider::from_config(&state);
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        let error = match price_updater(&state, provider.as_ref()).await {
+            Ok(()) => "price stream ended".to_string(),
+            Err(e) => e.to_string(),
+        };
+
+        if health(&state).status == FeedStatus::Connected {
+            backoff = INITIAL_BACKOFF;
+        }
+        tracing::error!(
+            "Price updater stopped: {}; restarting in {:?}",
+            error,
+            backoff
+        );
+        update_health(&state, |health| {
+            health.status = FeedStatus::Reconnecting;
+            health.connected_since = None;
+            health.last_error = Some(error);
+        });
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+        update_health(&state, |health| health.restarts += 1);
+    }
+}
+
+/// Redis pub/sub channel announcing price updates of `ticker`
+pub fn price_channel(ticker: &str) -> String {
+    format!("prices:{}", ticker)
+}
+
+/// Pattern matching the price channels of every ticker
+pub const PRICE_CHANNEL_PATTERN: &str = "prices:*";
+
+/// JSON payload published on a ticker's price channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceMessage {
+    pub ticker: String,
+    pub price: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Stream prices of every listed instrument other than baskets from
+/// `provider` into Redis and the candle history
+///
+/// The stream is reopened with the new ticker set whenever the instrument
+/// catalog changes.
+pub async fn price_updater(state: &AppState, provider: &dyn PriceProvider) -> Result<()> {
+    let redis_client = redis::Client::open(state.config.redis_url.as_str())
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let mut pubsub = redis_client
+        .get_async_pubsub()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    pubsub
+        .subscribe(instruments::CATALOG_CHANNEL)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let mut catalog_changes = pubsub.into_on_message();
+
+    let repository = InstrumentRepository::new(&state.pg_pool);
+    let mut tickers = repository.get_feed_tickers().await?;
+
+    loop {
+        let mut stream = provider.subscribe(tickers.clone()).await?;
+
+        tracing::info!(
+            "Streaming {} prices for {} instruments",
+            provider.name(),
+            tickers.len()
+        );
+        update_health(state, |health| {
+            health.status = FeedStatus::Connected;
+            health.connected_since = Some(Utc::now());
+        });
+
+        loop {
+            tokio::select! {
+                tick = stream.next() => match tick {
+                    Some(tick) => apply_update(state, tick?).await?,
+                    None => return Ok(()),
+                },
+                change = catalog_changes.next() => {
+                    if change.is_none() {
+                        return Err(Error::RedisError(
+                            "Instrument catalog subscription closed".into(),
+                        ));
+                    }
+                    let listed = repository.get_feed_tickers().await?;
+                    if listed != tickers {
+                        tickers = listed;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Cache, publish and record a single price update from the provider
+async fn apply_update(state: &AppState, update: PriceTick) -> Result<()> {
+    let mut conn = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    let replaying: bool = conn
+        .exists(replay::replay_key(&update.ticker))
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    if replaying {
+        return Ok(());
+    }
+
+    let received_at = publish_tick(state, &mut conn, &update).await?;
+    update_health(state, |health| health.last_update_at = Some(received_at));
+
+    Ok(())
+}
+
+/// Band `update` against the previous price, halt the ticker on a large
+/// move, then cache, publish and record it, returning when it was received
+pub async fn publish_tick(
+    state: &AppState,
+    conn: &mut redis::aio::MultiplexedConnection,
+    update: &PriceTick,
+) -> Result<DateTime<Utc>> {
+    let previous: Option<f64> = conn
+        .get(&update.ticker)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    let config = matching::current(state);
+    let decision = matching::apply_tick(&config, previous, update.price);
+
+    if decision.halt {
+        tracing::warn!(
+            "Halting {} for {}s after a move from {:?} to {}",
+            update.ticker,
+            config.halt_duration_secs,
+            previous,
+            update.price
+        );
+        conn.set_ex::<_, _, ()>(
+            matching::halt_key(&update.ticker),
+            update.price,
+            config.halt_duration_secs as u64,
+        )
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    }
+
+    let received_at = Utc::now();
+    store_price(state, conn, &update.ticker, decision.price, received_at).await?;
+
+    Ok(received_at)
+}
+
+/// Cache `price` of `ticker` as of `at`, publish it and record its candles
+///
+/// Shared by the feed and manual overrides, so a price reaches every
+/// consumer the same way whatever its source.
+pub async fn store_price(
+    state: &AppState,
+    conn: &mut redis::aio::MultiplexedConnection,
+    ticker: &str,
+    price: f64,
+    at: DateTime<Utc>,
+) -> Result<()> {
+    // The price, its time and the notification go out together so
+    // subscribers never see a price the cache does not have yet
+    let message = PriceMessage {
+        ticker: ticker.to_string(),
+        price,
+        timestamp: at,
+    };
+    let payload = serde_json::to_string(&message).map_err(|_| Error::InternalServerError)?;
+    redis::pipe()
+        .atomic()
+        .set(ticker, price)
+        .ignore()
+        .set(price_cache::price_time_key(ticker), at.timestamp_millis())
+        .ignore()
+        .publish(price_channel(ticker), payload)
+        .ignore()
+        .query_async::<()>(conn)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    state.event_stream.emit(DomainEvent::PriceTick {
+        ticker: ticker.to_string(),
+        price,
+        timestamp: at,
+    });
+
+    // Candles are for charting only, so failing to record one must not stop the feed
+    if let Ok(price) = BigDecimal::try_from(price) {
+        if let Err(e) = PriceCandleRepository::new(&state.pg_pool)
+            .record_tick(ticker, price, at)
+            .await
+        {
+            tracing::warn!("Failed to record candle for {}: {}", ticker, e);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,187 @@
+//! # Quotes
+//!
+//! Last price, day change and day range of tickers. Prices and their update
+//! times are read from the Redis cache written by the price feed; tickers the
+//! cache knows nothing about (e.g. after a Redis restart) fall back to the
+//! latest daily candle stored in the database.
+//!
+//! Days are UTC calendar days, matching the daily candles. The change is
+//! measured against the close of the previous day with a candle.
+//!
+//! Prices older than `MAX_PRICE_AGE_SECS` are stale: quotes flag them and
+//! trades refuse to execute at them, so a stalled feed never fills orders at
+//! an hours-old price.
+
+use std::collections::HashMap;
+
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{
+    AppState, Error, Result,
+    models::price_candle::{CandleInterval, DailyCandle},
+    repository::price_candle_repository::PriceCandleRepository,
+    services::{
+        portfolio,
+        price_cache::{CachedPrice, PriceCache},
+    },
+};
+
+/// Where a quote's price came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteSource {
+    Cache,
+    Database,
+}
+
+impl QuoteSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QuoteSource::Cache => "cache",
+            QuoteSource::Database => "database",
+        }
+    }
+}
+
+/// Latest price of a ticker with its day statistics
+#[derive(Debug, Clone)]
+pub struct Quote {
+    pub ticker: String,
+    pub price: BigDecimal,
+    /// When the price was published; `None` for cached prices without a time
+    pub timestamp: Option<DateTime<Utc>>,
+    pub previous_close: Option<BigDecimal>,
+    pub change: Option<BigDecimal>,
+    pub change_percent: Option<BigDecimal>,
+    pub day_high: BigDecimal,
+    pub day_low: BigDecimal,
+    pub source: QuoteSource,
+    /// Whether the price is too old to trade on
+    pub stale: bool,
+}
+
+/// Prices published before this instant are stale; `None` when the guard is off
+pub fn stale_before(state: &AppState) -> Option<DateTime<Utc>> {
+    let max_age = state.config.max_price_age_secs;
+    (max_age > 0).then(|| Utc::now() - TimeDelta::seconds(max_age as i64))
+}
+
+/// Whether a price published at `timestamp` is stale; prices of unknown age are
+fn is_stale(stale_before: Option<DateTime<Utc>>, timestamp: Option<DateTime<Utc>>) -> bool {
+    stale_before.is_some_and(|cutoff| timestamp.map_or(true, |timestamp| timestamp < cutoff))
+}
+
+/// Cached price of `ticker` to execute a trade at
+///
+/// Fails when there is no price or it is stale.
+pub async fn trade_price(state: &AppState, ticker: &str) -> Result<BigDecimal> {
+    let CachedPrice { price, timestamp } = PriceCache::new(&state.redis_pool)
+        .price(ticker)
+        .await?
+        .ok_or_else(|| Error::BadRequest("Invalid ticker or price not available".into()))?;
+    if price <= BigDecimal::zero() {
+        return Err(Error::BadRequest("Price must be positive".into()));
+    }
+    if is_stale(stale_before(state), timestamp) {
+        return Err(Error::BadRequest(format!(
+            "The price of {} is stale; trading resumes once the price feed updates it",
+            ticker
+        )));
+    }
+
+    Ok(price)
+}
+
+/// Quotes of `tickers` in the given order; tickers without any price are skipped
+pub async fn quotes(state: &AppState, tickers: &[String]) -> Result<Vec<Quote>> {
+    if tickers.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut prices = PriceCache::new(&state.redis_pool)
+        .prices_with_times(tickers)
+        .await?;
+
+    let mut candles: HashMap<String, Vec<DailyCandle>> = HashMap::new();
+    for candle in PriceCandleRepository::new(&state.pg_pool)
+        .get_latest_daily(tickers)
+        .await?
+    {
+        candles
+            .entry(candle.ticker.clone())
+            .or_default()
+            .push(candle);
+    }
+
+    let today = CandleInterval::OneDay.bucket_start(Utc::now());
+    let stale_before = stale_before(state);
+    Ok(tickers
+        .iter()
+        .filter_map(|ticker| {
+            let cached = prices
+                .remove(ticker)
+                .map(|cached| (cached.price, cached.timestamp));
+            let candles = candles.remove(ticker).unwrap_or_default();
+            quote(ticker, cached, &candles, today, stale_before)
+        })
+        .collect())
+}
+
+/// Build a quote from the cached price and the latest daily candles, newest first
+fn quote(
+    ticker: &str,
+    cached: Option<(BigDecimal, Option<DateTime<Utc>>)>,
+    candles: &[DailyCandle],
+    today: DateTime<Utc>,
+    stale_before: Option<DateTime<Utc>>,
+) -> Option<Quote> {
+    let (latest, earlier) = (candles.first(), candles.get(1));
+
+    // Without a cached price, the day of the latest candle is reported as the
+    // current day
+    let (price, timestamp, source, session, previous) = match cached {
+        Some((price, timestamp)) => match latest {
+            Some(candle) if candle.bucket_start == today => {
+                (price, timestamp, QuoteSource::Cache, Some(candle), earlier)
+            }
+            _ => (price, timestamp, QuoteSource::Cache, None, latest),
+        },
+        None => {
+            let candle = latest?;
+            (
+                candle.close.clone(),
+                Some(candle.updated_at),
+                QuoteSource::Database,
+                Some(candle),
+                earlier,
+            )
+        }
+    };
+
+    let (day_high, day_low) = match session {
+        Some(candle) => (
+            (&candle.high).max(&price).clone(),
+            (&candle.low).min(&price).clone(),
+        ),
+        None => (price.clone(), price.clone()),
+    };
+    let previous_close = previous.map(|candle| candle.close.clone());
+    let change = previous_close.as_ref().map(|close| &price - close);
+    let change_percent = previous_close
+        .as_ref()
+        .zip(change.as_ref())
+        .map(|(close, change)| portfolio::percent_of(change, close));
+
+    Some(Quote {
+        ticker: ticker.to_string(),
+        price,
+        timestamp,
+        previous_close,
+        change,
+        change_percent,
+        day_high,
+        day_low,
+        source,
+        stale: is_stale(stale_before, timestamp),
+    })
+}
@@ -0,0 +1,169 @@
+//! # Historical Replays
+//!
+//! Admins upload a historical tick dataset, such as a flash crash, and
+//! replay it through the price pipeline so classes can trade through famous
+//! market days. A running replay publishes each tick once the replay has
+//! advanced to its historical time: from the start on, every real second
+//! covers `speed` historical seconds. Ticks go through the same banding,
+//! halts, caching, publishing and candles as feed prices, stamped with the
+//! time they are published.
+//!
+//! While a replay runs, the tickers it covers ignore the live price provider.
+//! The worker refreshes a short-lived marker per ticker every step, so the
+//! feed takes over again shortly after a replay finishes, is stopped or its
+//! instance goes away. Every instance steps running replays; claiming ticks
+//! by advancing the replay's position makes sure each tick is published once.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use redis::AsyncCommands;
+
+use crate::{
+    AppState, Error, Result,
+    models::replay::Replay,
+    repository::replay_repository::ReplayRepository,
+    services::{price_provider::PriceTick, price_updater},
+};
+
+/// How often running replays publish their due ticks
+const REPLAY_STEP: Duration = Duration::from_millis(250);
+/// Most ticks a replay publishes per step
+const MAX_TICKS_PER_STEP: i64 = 1000;
+/// Seconds the live feed stays away from a replayed ticker after the last step
+const REPLAY_MARKER_TTL_SECS: u64 = 5;
+
+/// Redis key marking a ticker as driven by a running replay
+pub fn replay_key(ticker: &str) -> String {
+    format!("replay:{}", ticker)
+}
+
+/// Historical time a replay has reached at the real time `now`
+pub fn replay_time(replay: &Replay, now: DateTime<Utc>) -> DateTime<Utc> {
+    let Some(started_at) = replay.started_at else {
+        return replay.data_from;
+    };
+    let elapsed = (now - started_at).num_milliseconds().max(0) as f64 * replay.speed;
+    replay.data_from + TimeDelta::milliseconds(elapsed as i64)
+}
+
+/// Publish the due ticks of every running replay a few times a second
+pub async fn replay_worker(state: Arc<AppState>) -> Result<()> {
+    let mut interval = tokio::time::interval(REPLAY_STEP);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // Tickers of each running replay, loaded once per run
+    let mut tickers: HashMap<i32, Vec<String>> = HashMap::new();
+
+    loop {
+        interval.tick().await;
+
+        let running = match ReplayRepository::new(&state.pg_pool).get_running().await {
+            Ok(running) => running,
+            Err(e) => {
+                tracing::error!("Failed to look up running replays: {}", e);
+                continue;
+            }
+        };
+        tickers.retain(|id, _| running.iter().any(|replay| replay.id == *id));
+
+        for replay in &running {
+            if let Err(e) = step(&state, replay, &mut tickers).await {
+                tracing::error!("Failed to step replay {}: {}", replay.id, e);
+            }
+        }
+    }
+}
+
+/// Stop a running replay and hand its tickers back to the live feed
+pub async fn stop(state: &AppState, replay_id: i32) -> Result<Option<Replay>> {
+    let Some(replay) = ReplayRepository::new(&state.pg_pool)
+        .stop(replay_id)
+        .await?
+    else {
+        return Ok(None);
+    };
+    release(state, replay_id).await?;
+
+    Ok(Some(replay))
+}
+
+async fn step(
+    state: &AppState,
+    replay: &Replay,
+    tickers: &mut HashMap<i32, Vec<String>>,
+) -> Result<()> {
+    let repository = ReplayRepository::new(&state.pg_pool);
+    let replayed = match tickers.get(&replay.id) {
+        Some(replayed) => replayed,
+        None => {
+            let loaded = repository.get_tickers(replay.id).await?;
+            tickers.entry(replay.id).or_insert(loaded)
+        }
+    };
+
+    let mut conn = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let mut markers = redis::pipe();
+    for ticker in replayed {
+        markers
+            .set_ex(replay_key(ticker), replay.id, REPLAY_MARKER_TTL_SECS)
+            .ignore();
+    }
+    markers
+        .query_async::<()>(&mut *conn)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    let until = replay_time(replay, Utc::now());
+    let ticks = repository
+        .get_ticks(replay.id, replay.position, until, MAX_TICKS_PER_STEP)
+        .await?;
+    let Some(last) = ticks.last() else {
+        return Ok(());
+    };
+    if !repository
+        .advance(replay.id, replay.position, last.seq)
+        .await?
+    {
+        return Ok(());
+    }
+
+    for tick in &ticks {
+        let update = PriceTick {
+            ticker: tick.ticker.clone(),
+            price: tick.price,
+        };
+        price_updater::publish_tick(state, &mut conn, &update).await?;
+    }
+
+    if last.seq >= replay.tick_count {
+        tracing::info!("Replay {} ({}) finished", replay.id, replay.name);
+        drop(conn);
+        release(state, replay.id).await?;
+    }
+
+    Ok(())
+}
+
+/// Remove the markers keeping the replay's tickers away from the live feed
+async fn release(state: &AppState, replay_id: i32) -> Result<()> {
+    let tickers = ReplayRepository::new(&state.pg_pool)
+        .get_tickers(replay_id)
+        .await?;
+    if tickers.is_empty() {
+        return Ok(());
+    }
+    let keys: Vec<String> = tickers.iter().map(|ticker| replay_key(ticker)).collect();
+
+    state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?
+        .del::<_, ()>(keys)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))
+}
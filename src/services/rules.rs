@@ -0,0 +1,108 @@
+//! # Difficulty Modes
+//!
+//! A difficulty bundles the trading costs a portfolio is subject to:
+//!
+//! - `beginner` fills market orders at the quoted price without commission
+//!   and opens interest-free loans
+//! - `realistic` charges `TRADE_FEE_PERCENT` of the order value as
+//!   commission, pays spread and slippage from the ticker's liquidity profile
+//!   and opens loans at `LOAN_INTEREST_PERCENT`
+//!
+//! Portfolios of a competition follow the competition's difficulty, so every
+//! entrant plays by the same rules; all other portfolios follow their owner's
+//! setting. The execution engine consults the [`TradingRules`] of the
+//! portfolio for every order, including margin call liquidations. Loans keep
+//! the interest rate they were opened with.
+
+use bigdecimal::{BigDecimal, FromPrimitive, RoundingMode};
+
+use crate::{
+    AppState, Error, Result,
+    config::Config,
+    models::{difficulty::Difficulty, portfolio::Portfolio},
+    repository::{
+        competition_repository::CompetitionRepository,
+        user_settings_repository::UserSettingsRepository,
+    },
+    services::liquidity::{self, Side},
+};
+
+/// Costs applied to the orders and loans of a portfolio
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradingRules {
+    pub difficulty: Difficulty,
+    /// Commission in percent of the order value
+    pub fee_percent: f64,
+    /// Whether market orders pay spread and slippage
+    pub slippage: bool,
+    /// Annual interest in percent charged on new loans
+    pub loan_interest_percent: f64,
+}
+
+impl TradingRules {
+    pub fn new(difficulty: Difficulty, config: &Config) -> Self {
+        match difficulty {
+            Difficulty::Beginner => TradingRules {
+                difficulty,
+                fee_percent: 0.0,
+                slippage: false,
+                loan_interest_percent: 0.0,
+            },
+            Difficulty::Realistic => TradingRules {
+                difficulty,
+                fee_percent: config.trade_fee_percent,
+                slippage: true,
+                loan_interest_percent: config.loan_interest_percent,
+            },
+        }
+    }
+
+    /// Commission on an order worth `value`, rounded to cents
+    pub fn fee(&self, value: &BigDecimal) -> BigDecimal {
+        let percent = BigDecimal::from_f64(self.fee_percent).unwrap_or_default();
+        (value * percent / BigDecimal::from(100)).with_scale_round(2, RoundingMode::HalfUp)
+    }
+
+    /// Price at which a market order of `quantity` shares executes against `mid`
+    pub async fn execution_price(
+        &self,
+        state: &AppState,
+        ticker: &str,
+        side: Side,
+        mid: &BigDecimal,
+        quantity: i32,
+    ) -> Result<BigDecimal> {
+        if self.slippage {
+            liquidity::execution_price(state, ticker, side, mid, quantity).await
+        } else {
+            Ok(mid.with_scale_round(2, RoundingMode::HalfUp))
+        }
+    }
+}
+
+/// The rules the orders and loans of `portfolio` follow
+pub async fn for_portfolio(state: &AppState, portfolio: &Portfolio) -> Result<TradingRules> {
+    let difficulty = match portfolio.competition_id {
+        Some(competition_id) => {
+            let competition = CompetitionRepository::new(&state.pg_pool)
+                .get_competition(competition_id)
+                .await?
+                .ok_or(Error::InternalServerError)?;
+            competition.difficulty.parse().map_err(|e| {
+                tracing::error!(
+                    "Invalid difficulty for competition ID {}: {}",
+                    competition_id,
+                    e
+                );
+                Error::InternalServerError
+            })?
+        }
+        None => {
+            UserSettingsRepository::new(&state.pg_pool)
+                .get_difficulty(portfolio.user_id)
+                .await?
+        }
+    };
+
+    Ok(TradingRules::new(difficulty, &state.config))
+}
@@ -0,0 +1,110 @@
+//! # Portfolio Snapshots
+//!
+//! Background job recording each user's equity across all of their
+//! portfolios once per day, used to draw equity curves. A capture runs at startup and then at every UTC midnight;
+//! snapshots are keyed by date, so repeated captures on the same day (e.g.
+//! after a restart or from several instances) replace each other. The cached
+//! prices of the tickers users picked as benchmarks are recorded alongside,
+//! so performance can be compared day by day, and the leaderboard is ranked
+//! again from the new snapshots.
+
+use std::sync::Arc;
+
+use chrono::{Days, NaiveDate, Utc};
+
+use crate::{
+    AppState, Result,
+    repository::{
+        benchmark_price_repository::BenchmarkPriceRepository,
+        portfolio_snapshot_repository::PortfolioSnapshotRepository,
+        user_settings_repository::UserSettingsRepository,
+    },
+    services::{leaderboard, portfolio, price_cache::PriceCache},
+};
+
+/// Capture a snapshot for every user now and after each UTC midnight
+pub async fn snapshot_worker(state: Arc<AppState>) -> Result<()> {
+    loop {
+        let today = Utc::now().date_naive();
+        match capture_all(&state, today).await {
+            Ok(count) => tracing::info!("Captured {} portfolio snapshots for {}", count, today),
+            Err(e) => tracing::error!("Failed to capture portfolio snapshots: {}", e),
+        }
+        match capture_benchmarks(&state, today).await {
+            Ok(count) => tracing::info!("Captured {} benchmark prices for {}", count, today),
+            Err(e) => tracing::error!("Failed to capture benchmark prices: {}", e),
+        }
+        match leaderboard::rebuild(&state, today).await {
+            Ok(count) => tracing::info!("Ranked {} users on the leaderboard", count),
+            Err(e) => tracing::error!("Failed to rebuild the leaderboard: {}", e),
+        }
+
+        tokio::time::sleep(until_next_day()).await;
+    }
+}
+
+/// Record the current valuation of every user's portfolio under `date`
+///
+/// Users whose portfolio cannot be valued are logged and skipped so one bad
+/// account does not prevent the others from being captured.
+pub async fn capture_all(state: &AppState, date: NaiveDate) -> Result<usize> {
+    let user_ids = state.repos.users.get_user_ids().await?;
+    let repository = PortfolioSnapshotRepository::new(&state.pg_pool);
+
+    let mut captured = 0;
+    for user_id in user_ids {
+        let valuation = match portfolio::value_account(state, user_id).await {
+            Ok(valuation) => valuation,
+            Err(e) => {
+                tracing::warn!("Skipping snapshot for user {}: {}", user_id, e);
+                continue;
+            }
+        };
+
+        repository
+            .upsert_snapshot(
+                user_id,
+                date,
+                // Swept cash is still cash from the user's point of view
+                valuation.cash + valuation.money_market,
+                valuation.market_value,
+                valuation.equity,
+            )
+            .await?;
+        captured += 1;
+    }
+
+    Ok(captured)
+}
+
+/// Record the cached price of every benchmark ticker under `date`
+///
+/// Benchmarks without a cached price are skipped.
+pub async fn capture_benchmarks(state: &AppState, date: NaiveDate) -> Result<usize> {
+    let tickers = UserSettingsRepository::new(&state.pg_pool)
+        .get_benchmark_tickers()
+        .await?;
+    let prices = PriceCache::new(&state.redis_pool).prices(&tickers).await?;
+    let repository = BenchmarkPriceRepository::new(&state.pg_pool);
+
+    for (ticker, price) in &prices {
+        repository.upsert_price(ticker, date, price.clone()).await?;
+    }
+
+    Ok(prices.len())
+}
+
+/// Time left until the next UTC midnight
+pub fn until_next_day() -> std::time::Duration {
+    let now = Utc::now();
+    let next = now
+        .date_naive()
+        .checked_add_days(Days::new(1))
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc());
+
+    match next {
+        Some(next) => (next - now).to_std().unwrap_or_default(),
+        None => std::time::Duration::from_secs(24 * 60 * 60),
+    }
+}
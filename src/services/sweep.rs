@@ -0,0 +1,174 @@
+//! # Cash Sweep
+//!
+//! Users can opt into a money market pseudo-instrument: every night idle cash
+//! in the default portfolio is swept into it and earns the configured annual
+//! yield, accrued daily. When a buy or withdrawal in any of the user's
+//! portfolios needs more cash than the portfolio holds, the shortfall is swept
+//! back out automatically, so the swept balance always counts as buying power.
+
+use std::sync::Arc;
+
+use bigdecimal::{BigDecimal, FromPrimitive, Zero};
+use chrono::{NaiveDate, Utc};
+use redis::{AsyncCommands, ExistenceCheck, SetExpiry, SetOptions};
+
+use crate::{
+    AppState, Error, Result,
+    models::portfolio::Portfolio,
+    repository::{
+        ledger_repository::LedgerRepository, money_market_repository::MoneyMarketRepository,
+        portfolio_repository::PortfolioRepository,
+        user_settings_repository::UserSettingsRepository,
+    },
+    services::snapshots,
+};
+
+/// How long the per-day accrual marker is kept in Redis
+const ACCRUAL_MARKER_TTL_SECS: u64 = 2 * 24 * 60 * 60;
+
+/// Accrue interest and sweep idle cash after every UTC midnight
+pub async fn sweep_worker(state: Arc<AppState>) -> Result<()> {
+    loop {
+        tokio::time::sleep(snapshots::until_next_day()).await;
+
+        let today = Utc::now().date_naive();
+        if let Err(e) = run_nightly(&state, today).await {
+            tracing::error!("Nightly cash sweep failed: {}", e);
+        }
+    }
+}
+
+/// Accrue one day of interest, then move idle cash of opted-in users into the money market
+///
+/// Interest is accrued at most once per date across all instances.
+pub async fn run_nightly(state: &AppState, date: NaiveDate) -> Result<()> {
+    if claim_accrual(state, date).await? {
+        let accrued = MoneyMarketRepository::new(&state.pg_pool)
+            .accrue_interest(daily_rate(state))
+            .await?;
+        tracing::info!("Accrued money market interest on {} accounts", accrued);
+    }
+
+    let user_ids = UserSettingsRepository::new(&state.pg_pool)
+        .get_cash_sweep_user_ids()
+        .await?;
+    for user_id in user_ids {
+        if let Err(e) = sweep_in(state, user_id).await {
+            tracing::warn!("Failed to sweep cash for user {}: {}", user_id, e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Move the whole cash balance of the user's default portfolio into the money market
+pub async fn sweep_in(state: &AppState, user_id: i32) -> Result<()> {
+    let portfolios_repository = PortfolioRepository::new(&state.pg_pool);
+    let portfolio = portfolios_repository
+        .get_default_portfolio(user_id)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    if portfolio.balance <= BigDecimal::zero() {
+        return Ok(());
+    }
+
+    LedgerRepository::new(&state.pg_pool)
+        .post(portfolio.id, "sweep", -portfolio.balance.clone(), None)
+        .await?;
+    MoneyMarketRepository::new(&state.pg_pool)
+        .deposit(user_id, portfolio.balance)
+        .await?;
+
+    Ok(())
+}
+
+/// Make sure the portfolio has at least `needed` cash, sweeping the shortfall
+/// out of the owner's money market when possible
+///
+/// Returns the portfolio's cash balance afterwards, which is still below
+/// `needed` when the money market cannot cover the whole shortfall.
+/// Competition and class portfolios are never funded from the money market.
+pub async fn sweep_out(
+    state: &AppState,
+    portfolio: &Portfolio,
+    needed: &BigDecimal,
+) -> Result<BigDecimal> {
+    let cash = &portfolio.balance;
+    if cash >= needed || portfolio.competition_id.is_some() || portfolio.class_id.is_some() {
+        return Ok(cash.clone());
+    }
+
+    let money_market = MoneyMarketRepository::new(&state.pg_pool);
+    let available = match money_market.get_account(portfolio.user_id).await? {
+        Some(account) => account.balance,
+        None => return Ok(cash.clone()),
+    };
+
+    let shortfall = needed - cash;
+    let amount = if available < shortfall {
+        available
+    } else {
+        shortfall
+    };
+    if amount <= BigDecimal::zero()
+        || !money_market
+            .withdraw(portfolio.user_id, amount.clone())
+            .await?
+    {
+        return Ok(cash.clone());
+    }
+
+    let entry = LedgerRepository::new(&state.pg_pool)
+        .post(portfolio.id, "sweep", amount, None)
+        .await?;
+
+    Ok(entry.balance_after)
+}
+
+/// Move the whole money market balance back into the default portfolio's cash
+pub async fn sweep_all_out(state: &AppState, user_id: i32) -> Result<()> {
+    let money_market = MoneyMarketRepository::new(&state.pg_pool);
+    let balance = match money_market.get_account(user_id).await? {
+        Some(account) if account.balance > BigDecimal::zero() => account.balance,
+        _ => return Ok(()),
+    };
+
+    let portfolios_repository = PortfolioRepository::new(&state.pg_pool);
+    let portfolio = portfolios_repository
+        .get_default_portfolio(user_id)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    if money_market.withdraw(user_id, balance.clone()).await? {
+        LedgerRepository::new(&state.pg_pool)
+            .post(portfolio.id, "sweep", balance, None)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Daily interest rate derived from the configured annual yield
+pub fn daily_rate(state: &AppState) -> BigDecimal {
+    BigDecimal::from_f64(state.config.money_market_yield_percent / 100.0 / 365.0)
+        .unwrap_or_else(|| BigDecimal::from(0))
+}
+
+/// Mark interest for `date` as accrued, returning false if another run already did
+async fn claim_accrual(state: &AppState, date: NaiveDate) -> Result<bool> {
+    let options = SetOptions::default()
+        .conditional_set(ExistenceCheck::NX)
+        .with_expiration(SetExpiry::EX(ACCRUAL_MARKER_TTL_SECS));
+
+    let claimed: Option<String> = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?
+        .set_options(format!("money_market:accrued:{}", date), 1, options)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    Ok(claimed.is_some())
+}
@@ -0,0 +1,124 @@
+//! # Realized Gains Report
+//!
+//! Annual capital gains report built from the realized gain records written
+//! on every sell. Each record is one slice of a sell matched against a tax
+//! lot and is classified by how long the shares were held: more than one year
+//! is long-term, anything else short-term. Shares sold from positions opened
+//! before lot tracking have no acquisition date and cannot be classified.
+//! Years run on UTC calendar days and cover every portfolio of the user, like
+//! a real tax return.
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Months, NaiveDate, Utc};
+
+use crate::{
+    AppState, Error, Result, models::realized_gain::RealizedGain,
+    repository::tax_lot_repository::TaxLotRepository,
+};
+
+/// How long sold shares were held
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoldingPeriod {
+    ShortTerm,
+    LongTerm,
+    /// Shares without an acquisition date
+    Unknown,
+}
+
+impl HoldingPeriod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HoldingPeriod::ShortTerm => "short_term",
+            HoldingPeriod::LongTerm => "long_term",
+            HoldingPeriod::Unknown => "unknown",
+        }
+    }
+}
+
+/// A realized gain record with its holding period
+#[derive(Debug, Clone)]
+pub struct ClassifiedGain {
+    pub gain: RealizedGain,
+    pub holding_period: HoldingPeriod,
+}
+
+/// Realized gains of one calendar year
+#[derive(Debug, Clone)]
+pub struct RealizedGainsReport {
+    pub year: i32,
+    pub lots: Vec<ClassifiedGain>,
+    pub proceeds: BigDecimal,
+    pub cost_basis: BigDecimal,
+    pub short_term_gain: BigDecimal,
+    pub long_term_gain: BigDecimal,
+    pub unknown_term_gain: BigDecimal,
+    pub total_gain: BigDecimal,
+}
+
+/// Classify shares acquired at `acquired_at` and sold at `realized_at`
+///
+/// Shares are held long-term when sold after the anniversary of their
+/// acquisition.
+pub fn holding_period(
+    acquired_at: Option<DateTime<Utc>>,
+    realized_at: DateTime<Utc>,
+) -> HoldingPeriod {
+    let Some(acquired_at) = acquired_at else {
+        return HoldingPeriod::Unknown;
+    };
+
+    match acquired_at.date_naive().checked_add_months(Months::new(12)) {
+        Some(anniversary) if realized_at.date_naive() > anniversary => HoldingPeriod::LongTerm,
+        _ => HoldingPeriod::ShortTerm,
+    }
+}
+
+/// Build the user's realized gains report for `year`
+pub async fn realized_gains(
+    state: &AppState,
+    user_id: i32,
+    year: i32,
+) -> Result<RealizedGainsReport> {
+    let start_of = |year: i32| {
+        NaiveDate::from_ymd_opt(year, 1, 1)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|start| start.and_utc())
+            .ok_or_else(|| Error::BadRequest(format!("Invalid year {}", year)))
+    };
+    let from = start_of(year)?;
+    let to = start_of(year + 1)?;
+
+    let gains = TaxLotRepository::new(&state.pg_pool)
+        .get_realized_gains(user_id, from, to)
+        .await?;
+
+    let zero = || BigDecimal::from(0);
+    let mut report = RealizedGainsReport {
+        year,
+        lots: Vec::with_capacity(gains.len()),
+        proceeds: zero(),
+        cost_basis: zero(),
+        short_term_gain: zero(),
+        long_term_gain: zero(),
+        unknown_term_gain: zero(),
+        total_gain: zero(),
+    };
+
+    for gain in gains {
+        let holding_period = holding_period(gain.acquired_at, gain.realized_at);
+        report.proceeds += &gain.proceeds;
+        report.cost_basis += &gain.cost_basis;
+        report.total_gain += &gain.gain;
+        match holding_period {
+            HoldingPeriod::ShortTerm => report.short_term_gain += &gain.gain,
+            HoldingPeriod::LongTerm => report.long_term_gain += &gain.gain,
+            HoldingPeriod::Unknown => report.unknown_term_gain += &gain.gain,
+        }
+        report.lots.push(ClassifiedGain {
+            gain,
+            holding_period,
+        });
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,266 @@
+//! # Trading
+//!
+//! Market orders against the latest price, shared by the trading endpoints
+//! and the bot traders. Every order goes through the same checks, pays the
+//! costs of the portfolio's difficulty, keeps the holding and its tax lots up
+//! to date and pushes a fill event to the owner. Orders and fills are also
+//! streamed as `order.placed` and `trade.executed` domain events.
+
+use bigdecimal::BigDecimal;
+use uuid::Uuid;
+
+use crate::{
+    AppState, Error, Result,
+    models::{portfolio::Portfolio, transaction::Transaction},
+    repository::{ledger_repository::TradePosting, loan_repository::LoanRepository},
+    services::{
+        classes, competitions, event_stream::DomainEvent, instruments, liquidity::Side, matching,
+        positions, quotes, rules, sweep,
+    },
+    ws::events,
+};
+
+/// Buy `quantity` shares of `ticker` into `portfolio` at market
+pub async fn buy(
+    state: &AppState,
+    portfolio: &Portfolio,
+    ticker: &str,
+    quantity: i32,
+) -> Result<Transaction> {
+    let transactions_repository = &state.repos.transactions;
+    let order_id = placed(state, portfolio, ticker, "buy", quantity);
+
+    competitions::check_trading(state, portfolio).await?;
+    classes::check_trading(state, portfolio, ticker, Side::Buy).await?;
+    instruments::check_tradable(state, ticker, Side::Buy, quantity).await?;
+    matching::check_trade(state, ticker, quantity).await?;
+
+    let price = quotes::trade_price(state, ticker).await?;
+
+    // Realistic market orders pay the spread plus slippage from the ticker's
+    // liquidity profile, and a commission on top
+    let rules = rules::for_portfolio(state, portfolio).await?;
+    let price = rules
+        .execution_price(state, ticker, Side::Buy, &price, quantity)
+        .await?;
+    let value = BigDecimal::from(quantity) * &price;
+    let fee = rules.fee(&value);
+
+    let total_cost = &value + &fee;
+    // Pull any shortfall out of the money market for users with cash sweep
+    let balance_bd = sweep::sweep_out(state, portfolio, &total_cost).await?;
+    check_affordable(&balance_bd, &total_cost)?;
+
+    // Create transaction record first
+    let transaction = transactions_repository
+        .create_transaction(
+            portfolio.user_id,
+            portfolio.id,
+            ticker,
+            quantity,
+            price.clone(),
+            "buy",
+            fee.clone(),
+        )
+        .await?;
+
+    // Update or create the holding, open a tax lot and debit the cost and
+    // the commission together; a concurrent debit may have spent the cash
+    // since the check above
+    let posting = TradePosting {
+        entry_type: "buy",
+        value: -value,
+        fee,
+    };
+    if let Err(e) = positions::add_shares(
+        state,
+        portfolio.user_id,
+        portfolio.id,
+        ticker,
+        quantity,
+        &price,
+        transaction.id,
+        posting,
+    )
+    .await
+    {
+        transactions_repository
+            .delete_transaction(transaction.id)
+            .await?;
+        return Err(e);
+    }
+    events::publish_fill(state, portfolio.id, &transaction).await;
+    executed(state, order_id, portfolio.id, &transaction);
+
+    Ok(transaction)
+}
+
+/// Sell `quantity` shares of `ticker` out of `portfolio` at market,
+/// returning the transaction and the gain it realized
+pub async fn sell(
+    state: &AppState,
+    portfolio: &Portfolio,
+    ticker: &str,
+    quantity: i32,
+) -> Result<(Transaction, BigDecimal)> {
+    let transactions_repository = &state.repos.transactions;
+    let holdings_repository = &state.repos.holdings;
+    let order_id = placed(state, portfolio, ticker, "sell", quantity);
+
+    competitions::check_trading(state, portfolio).await?;
+    instruments::check_tradable(state, ticker, Side::Sell, quantity).await?;
+    matching::check_trade(state, ticker, quantity).await?;
+
+    let price = quotes::trade_price(state, ticker).await?;
+
+    let holding = holdings_repository
+        .get_holding_by_portfolio_and_ticker(portfolio.id, ticker)
+        .await?;
+
+    let holding = holding.ok_or_else(insufficient_holdings)?;
+
+    // Shares pledged as loan collateral stay in the account until the loan is repaid
+    let pledged = LoanRepository::new(&state.pg_pool)
+        .get_pledged_quantity(portfolio.id, ticker)
+        .await?;
+    check_sellable(holding.quantity, pledged, quantity)?;
+
+    // Realistic market orders pay the spread plus slippage from the ticker's
+    // liquidity profile, and a commission out of the proceeds
+    let rules = rules::for_portfolio(state, portfolio).await?;
+    let price = rules
+        .execution_price(state, ticker, Side::Sell, &price, quantity)
+        .await?;
+    let fee = rules.fee(&(&price * quantity));
+
+    // Create transaction record first
+    let transaction = transactions_repository
+        .create_transaction(
+            portfolio.user_id,
+            portfolio.id,
+            ticker,
+            quantity,
+            price.clone(),
+            "sell",
+            fee.clone(),
+        )
+        .await?;
+
+    // Update the holding quantity, consume tax lots, record the realized
+    // gain and credit the proceeds less the commission together, as a
+    // concurrent sell may have taken the shares since the check above
+    let posting = TradePosting {
+        entry_type: "sell",
+        value: &price * quantity,
+        fee,
+    };
+    let realized_gain =
+        match positions::remove_shares(state, holding, quantity, &price, transaction.id, posting)
+            .await
+        {
+            Ok(realized_gain) => realized_gain,
+            Err(e) => {
+                transactions_repository
+                    .delete_transaction(transaction.id)
+                    .await?;
+                return Err(e);
+            }
+        };
+    events::publish_fill(state, portfolio.id, &transaction).await;
+    executed(state, order_id, portfolio.id, &transaction);
+
+    Ok((transaction, realized_gain))
+}
+
+/// Check that `balance` covers a buy costing `total_cost` with its commission
+pub fn check_affordable(balance: &BigDecimal, total_cost: &BigDecimal) -> Result<()> {
+    if total_cost > balance {
+        return Err(Error::BadRequest(
+            "Insufficient balance for this transaction".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check that a position of `held` shares, `pledged` of them as loan
+/// collateral, can sell `quantity`
+pub fn check_sellable(held: i32, pledged: i32, quantity: i32) -> Result<()> {
+    if held < quantity {
+        return Err(insufficient_holdings());
+    }
+    if held - pledged < quantity {
+        return Err(Error::BadRequest(
+            "Insufficient unpledged holdings for this transaction".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn insufficient_holdings() -> Error {
+    Error::BadRequest("Insufficient holdings for this transaction".into())
+}
+
+/// Stream an order as placed, returning the ID its fill is streamed with
+fn placed(
+    state: &AppState,
+    portfolio: &Portfolio,
+    ticker: &str,
+    side: &'static str,
+    quantity: i32,
+) -> Uuid {
+    let order_id = Uuid::new_v4();
+    state.event_stream.emit(DomainEvent::OrderPlaced {
+        order_id,
+        user_id: portfolio.user_id,
+        portfolio_id: portfolio.id,
+        ticker: ticker.to_string(),
+        side,
+        quantity,
+    });
+
+    order_id
+}
+
+/// Stream the fill of the order `order_id`
+fn executed(state: &AppState, order_id: Uuid, portfolio_id: i32, transaction: &Transaction) {
+    state.event_stream.emit(DomainEvent::TradeExecuted {
+        order_id,
+        transaction_id: transaction.public_id,
+        user_id: transaction.user_id,
+        portfolio_id,
+        ticker: transaction.ticker.clone(),
+        side: transaction.transaction_type.clone(),
+        quantity: transaction.quantity,
+        price: transaction.price.clone(),
+        fee: transaction.fee.clone(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buys_need_the_cost_and_commission_in_cash() {
+        let balance = BigDecimal::from(1000);
+        assert!(check_affordable(&balance, &BigDecimal::from(1000)).is_ok());
+        assert!(check_affordable(&balance, &"1000.01".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn sells_leave_pledged_shares_in_place() {
+        assert!(check_sellable(10, 0, 10).is_ok());
+        assert!(check_sellable(10, 4, 6).is_ok());
+
+        let Err(Error::BadRequest(short)) = check_sellable(5, 0, 6) else {
+            panic!("sold more than held");
+        };
+        assert!(short.starts_with("Insufficient holdings"));
+        let Err(Error::BadRequest(pledged)) = check_sellable(10, 5, 6) else {
+            panic!("sold pledged shares");
+        };
+        assert!(pledged.starts_with("Insufficient unpledged"));
+    }
+}
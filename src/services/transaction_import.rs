@@ -0,0 +1,487 @@
+//! # Transaction Import
+//!
+//! Users migrating from another paper-trading platform upload their trade
+//! history as CSV, one trade per line under the header
+//! `date,ticker,side,quantity,price[,fee]`. The upload is parsed as it
+//! arrives with an [`ImportParser`], collecting an error for every invalid
+//! line instead of stopping at the first.
+//!
+//! Once every line is valid, the trades are replayed into the portfolio in
+//! date order the way market orders are booked: the cash balance pays for
+//! buys and receives sells, holdings and tax lots are rebuilt, sells realize
+//! gains under the user's cost-basis method, and every transaction, lot and
+//! gain is dated when the trade happened. The replay is worked out in full
+//! first and then booked in a single database transaction, so nothing is
+//! imported when any line is invalid or the booking fails, and a fixed file
+//! can simply be uploaded again.
+
+use std::collections::HashMap;
+
+use bigdecimal::{BigDecimal, RoundingMode, Zero};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    AppState, Error, Result,
+    models::{portfolio::Portfolio, tax_lot::TaxLot, user_settings::CostBasisMethod},
+    repository::{
+        import_repository::ImportRepository, instrument_repository::InstrumentRepository,
+        user_settings_repository::UserSettingsRepository,
+    },
+    services::{
+        balance::AMOUNT_SCALE,
+        cost_basis::{self, RealizedLot},
+        positions,
+    },
+};
+
+/// Most trades a single upload may hold
+pub const MAX_IMPORT_ROWS: usize = 5000;
+/// Longest line accepted, in bytes
+pub const MAX_LINE_LEN: usize = 512;
+/// Most line errors reported back; the rest are only counted
+const MAX_REPORTED_ERRORS: usize = 100;
+/// Largest quantity of a single imported trade
+const MAX_QUANTITY: i32 = 1_000_000;
+
+/// A valid line of an upload
+#[derive(Debug, Clone)]
+pub struct ImportRow {
+    pub line: usize,
+    pub at: DateTime<Utc>,
+    pub ticker: String,
+    pub side: &'static str,
+    pub quantity: i32,
+    pub price: BigDecimal,
+    pub fee: BigDecimal,
+}
+
+/// A trade of the replay with the lots it sells out of
+#[derive(Debug, Clone)]
+pub struct ImportedTrade<'a> {
+    pub row: &'a ImportRow,
+    /// Slices of a sell, each `lot_id` being the index in the replay of the
+    /// buy that opened the lot
+    pub realized: Vec<RealizedLot>,
+}
+
+/// A position the replay opens, as the replay leaves it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedPosition {
+    pub ticker: String,
+    pub quantity: i32,
+    pub average_price: BigDecimal,
+    pub opened_at: DateTime<Utc>,
+    /// Date of the sell that emptied the position, if one did
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+/// Why a line of an upload was rejected
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RowError {
+    /// Line number in the file, the header being line 1
+    pub line: usize,
+    pub message: String,
+}
+
+/// Outcome of an upload
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ImportReport {
+    /// Trades found in the file
+    pub rows: usize,
+    /// Trades imported, zero unless every line was valid
+    pub imported: usize,
+    /// Invalid lines, at most the first 100
+    pub errors: Vec<RowError>,
+    /// Invalid lines, including those not listed in `errors`
+    pub error_count: usize,
+}
+
+/// Incremental CSV parser fed with the chunks of an upload
+#[derive(Debug, Default)]
+pub struct ImportParser {
+    /// Bytes of the line not yet terminated
+    pending: Vec<u8>,
+    /// Number of the last line read
+    line: usize,
+    header_seen: bool,
+    has_fee: bool,
+    rows: Vec<ImportRow>,
+    report: ImportReport,
+}
+
+impl ImportParser {
+    pub fn new() -> Self {
+        ImportParser::default()
+    }
+
+    /// Parse the complete lines of `chunk`, keeping a trailing partial line
+    ///
+    /// Fails when the file cannot be a valid upload at all: a bad header, an
+    /// overlong line or too many trades.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<()> {
+        self.pending.extend_from_slice(chunk);
+        while let Some(end) = self.pending.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.parse_line(&line)?;
+        }
+        if self.pending.len() > MAX_LINE_LEN {
+            return Err(Error::BadRequest(format!(
+                "Line {} is longer than {} bytes",
+                self.line + 1,
+                MAX_LINE_LEN
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Parse the last line and return the valid trades with the report so far
+    pub fn finish(mut self) -> Result<(Vec<ImportRow>, ImportReport)> {
+        let line = std::mem::take(&mut self.pending);
+        self.parse_line(&line)?;
+        if !self.header_seen {
+            return Err(Error::BadRequest("CSV upload is empty".into()));
+        }
+
+        Ok((self.rows, self.report))
+    }
+
+    fn parse_line(&mut self, line: &[u8]) -> Result<()> {
+        self.line += 1;
+        if line.len() > MAX_LINE_LEN + 2 {
+            return Err(Error::BadRequest(format!(
+                "Line {} is longer than {} bytes",
+                self.line, MAX_LINE_LEN
+            )));
+        }
+        let Ok(line) = std::str::from_utf8(line) else {
+            return Err(Error::BadRequest(format!(
+                "Line {} is not valid UTF-8",
+                self.line
+            )));
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(());
+        }
+
+        if !self.header_seen {
+            let header: Vec<String> = line
+                .trim_start_matches('\u{feff}')
+                .split(',')
+                .map(|column| column.trim().to_lowercase())
+                .collect();
+            self.has_fee = if header == ["date", "ticker", "side", "quantity", "price"] {
+                false
+            } else if header == ["date", "ticker", "side", "quantity", "price", "fee"] {
+                true
+            } else {
+                return Err(Error::BadRequest(
+                    "CSV header must be date,ticker,side,quantity,price[,fee]".into(),
+                ));
+            };
+            self.header_seen = true;
+            return Ok(());
+        }
+
+        self.report.rows += 1;
+        if self.report.rows > MAX_IMPORT_ROWS {
+            return Err(Error::BadRequest(format!(
+                "CSV upload has more than {} trades",
+                MAX_IMPORT_ROWS
+            )));
+        }
+        match parse_row(self.line, line, self.has_fee) {
+            Ok(row) => self.rows.push(row),
+            Err(message) => self.report.reject(self.line, message),
+        }
+
+        Ok(())
+    }
+}
+
+impl ImportReport {
+    /// Record that `line` is invalid
+    pub fn reject(&mut self, line: usize, message: String) {
+        self.error_count += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(RowError { line, message });
+        }
+    }
+}
+
+fn parse_row(number: usize, line: &str, has_fee: bool) -> std::result::Result<ImportRow, String> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let (date, ticker, side, quantity, price, fee) = match (has_fee, &fields[..]) {
+        (false, [date, ticker, side, quantity, price]) => {
+            (*date, *ticker, *side, *quantity, *price, "0")
+        }
+        (true, [date, ticker, side, quantity, price, fee]) => {
+            (*date, *ticker, *side, *quantity, *price, *fee)
+        }
+        _ => return Err(format!("expected {} columns", if has_fee { 6 } else { 5 })),
+    };
+
+    let at = DateTime::parse_from_rfc3339(date)
+        .map(|at| at.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+        })
+        .map_err(|_| "date must be RFC 3339 or YYYY-MM-DD".to_string())?;
+    if at > Utc::now() {
+        return Err("date is in the future".into());
+    }
+    if ticker.is_empty() || ticker.len() > 10 {
+        return Err("ticker must have 1 to 10 characters".into());
+    }
+    let side = match side.to_lowercase().as_str() {
+        "buy" => "buy",
+        "sell" => "sell",
+        _ => return Err("side must be buy or sell".into()),
+    };
+    let quantity = quantity
+        .parse::<i32>()
+        .ok()
+        .filter(|quantity| (1..=MAX_QUANTITY).contains(quantity))
+        .ok_or_else(|| format!("quantity must be a whole number from 1 to {}", MAX_QUANTITY))?;
+    let price = price
+        .parse::<BigDecimal>()
+        .ok()
+        .filter(|price| *price > BigDecimal::zero())
+        .ok_or("price must be a positive number")?;
+    let fee = fee
+        .parse::<BigDecimal>()
+        .ok()
+        .filter(|fee| *fee >= BigDecimal::zero())
+        .ok_or("fee must be a non-negative number")?;
+
+    Ok(ImportRow {
+        line: number,
+        at,
+        ticker: ticker.to_uppercase(),
+        side,
+        quantity,
+        // Trades are booked in cents
+        price: price.with_scale_round(AMOUNT_SCALE, RoundingMode::HalfUp),
+        fee: fee.with_scale_round(AMOUNT_SCALE, RoundingMode::HalfUp),
+    })
+}
+
+/// Check the parsed trades against the catalog and replay them into
+/// `portfolio`, filling in `report`
+///
+/// The portfolio must not have traded yet, so that the imported history is
+/// all it holds, and competition and class portfolios only take trades made
+/// under their rules.
+pub async fn import(
+    state: &AppState,
+    portfolio: &Portfolio,
+    mut rows: Vec<ImportRow>,
+    report: &mut ImportReport,
+) -> Result<()> {
+    if portfolio.competition_id.is_some() || portfolio.class_id.is_some() {
+        return Err(Error::BadRequest(
+            "Trades cannot be imported into competition or class portfolios".into(),
+        ));
+    }
+
+    let instruments = InstrumentRepository::new(&state.pg_pool);
+    let mut listed: HashMap<String, bool> = HashMap::new();
+    for row in &rows {
+        if !listed.contains_key(&row.ticker) {
+            let known = instruments.get_instrument(&row.ticker).await?.is_some();
+            listed.insert(row.ticker.clone(), known);
+        }
+    }
+
+    // Replay the trades in date order, keeping the file's order within a date
+    rows.sort_by_key(|row| row.at);
+    let mut cash = portfolio.balance.clone();
+    let mut held: HashMap<&str, i32> = HashMap::new();
+    for row in &rows {
+        if !listed[&row.ticker] {
+            report.reject(row.line, format!("unknown ticker {}", row.ticker));
+            continue;
+        }
+        let value = &row.price * row.quantity;
+        let shares = held.entry(row.ticker.as_str()).or_default();
+        if row.side == "buy" {
+            cash -= value + &row.fee;
+            if cash < BigDecimal::zero() {
+                report.reject(row.line, "insufficient balance for this trade".into());
+            }
+            *shares += row.quantity;
+        } else {
+            cash += value - &row.fee;
+            if *shares < row.quantity {
+                report.reject(row.line, "insufficient holdings for this trade".into());
+            }
+            *shares -= row.quantity;
+        }
+    }
+    if report.error_count > 0 {
+        report.errors.sort_by_key(|error| error.line);
+        return Ok(());
+    }
+
+    let method = UserSettingsRepository::new(&state.pg_pool)
+        .get_cost_basis_method(portfolio.user_id)
+        .await?;
+    let (trades, positions) = replay(&rows, method);
+    let imported = ImportRepository::new(&state.pg_pool)
+        .import_history(portfolio.user_id, portfolio.id, method, &trades, &positions)
+        .await?;
+    if !imported {
+        return Err(Error::Conflict(
+            "Trades can only be imported into a portfolio without transactions".into(),
+        ));
+    }
+    report.imported = trades.len();
+
+    tracing::info!(
+        "Imported {} trades into portfolio {}",
+        report.imported,
+        portfolio.id
+    );
+
+    Ok(())
+}
+
+/// Work out what replaying `rows`, in order, into an empty portfolio books:
+/// the lots every sell takes its shares from and the positions left
+///
+/// Every sell must be covered by the shares of earlier buys.
+pub fn replay(
+    rows: &[ImportRow],
+    method: CostBasisMethod,
+) -> (Vec<ImportedTrade<'_>>, Vec<ImportedPosition>) {
+    let mut trades = Vec::with_capacity(rows.len());
+    let mut positions: Vec<ImportedPosition> = Vec::new();
+    // Index in `positions` of each ticker's open position, and its open lots
+    let mut open: HashMap<&str, (usize, Vec<TaxLot>)> = HashMap::new();
+
+    for (index, row) in rows.iter().enumerate() {
+        let mut realized = Vec::new();
+        if row.side == "buy" {
+            let lot = TaxLot {
+                id: index as i32,
+                remaining_quantity: row.quantity,
+                price: row.price.clone(),
+                acquired_at: row.at,
+            };
+            if let Some((position, lots)) = open.get_mut(row.ticker.as_str()) {
+                let position = &mut positions[*position];
+                position.average_price = positions::average_after_buy(
+                    position.quantity,
+                    &position.average_price,
+                    row.quantity,
+                    &row.price,
+                );
+                position.quantity += row.quantity;
+                lots.push(lot);
+            } else {
+                open.insert(row.ticker.as_str(), (positions.len(), vec![lot]));
+                positions.push(ImportedPosition {
+                    ticker: row.ticker.clone(),
+                    quantity: row.quantity,
+                    average_price: row.price.clone(),
+                    opened_at: row.at,
+                    closed_at: None,
+                });
+            }
+        } else if let Some((position_index, mut lots)) = open.remove(row.ticker.as_str()) {
+            let position = &mut positions[position_index];
+            realized = cost_basis::realize(
+                method,
+                &lots,
+                row.quantity,
+                &position.average_price,
+                &row.price,
+            );
+            position.average_price =
+                positions::average_after_sell(method, &position.average_price, &lots, &realized);
+            position.quantity -= row.quantity;
+            for slice in &realized {
+                if let Some(lot) = lots.iter_mut().find(|lot| Some(lot.id) == slice.lot_id) {
+                    lot.remaining_quantity -= slice.quantity;
+                }
+            }
+            lots.retain(|lot| lot.remaining_quantity > 0);
+
+            if position.quantity > 0 {
+                open.insert(row.ticker.as_str(), (position_index, lots));
+            } else {
+                position.closed_at = Some(row.at);
+            }
+        }
+        trades.push(ImportedTrade { row, realized });
+    }
+
+    (trades, positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(day: u32, side: &'static str, quantity: i32, price: i64) -> ImportRow {
+        ImportRow {
+            line: day as usize + 1,
+            at: NaiveDate::from_ymd_opt(2024, 1, day)
+                .unwrap()
+                .and_time(NaiveTime::MIN)
+                .and_utc(),
+            ticker: "AAPL".into(),
+            side,
+            quantity,
+            price: BigDecimal::from(price),
+            fee: BigDecimal::zero(),
+        }
+    }
+
+    #[test]
+    fn sells_take_their_lots_and_close_the_position() {
+        let rows = [
+            row(1, "buy", 5, 10),
+            row(2, "buy", 5, 20),
+            row(3, "sell", 7, 30),
+            row(4, "sell", 3, 30),
+            row(5, "buy", 2, 40),
+        ];
+        let (trades, positions) = replay(&rows, CostBasisMethod::Fifo);
+
+        // The first sell empties the oldest lot, the second the rest of the newer one
+        let taken: Vec<Vec<(Option<i32>, i32)>> = trades
+            .iter()
+            .map(|trade| {
+                trade
+                    .realized
+                    .iter()
+                    .map(|lot| (lot.lot_id, lot.quantity))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            taken,
+            [
+                vec![],
+                vec![],
+                vec![(Some(0), 5), (Some(1), 2)],
+                vec![(Some(1), 3)],
+                vec![],
+            ]
+        );
+        assert_eq!(trades[2].realized[0].gain, BigDecimal::from(100));
+
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].quantity, 0);
+        assert_eq!(positions[0].closed_at, Some(rows[3].at));
+        assert_eq!(positions[1].quantity, 2);
+        assert_eq!(positions[1].average_price, BigDecimal::from(40));
+        assert_eq!(positions[1].opened_at, rows[4].at);
+        assert_eq!(positions[1].closed_at, None);
+    }
+}
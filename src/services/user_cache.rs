@@ -0,0 +1,80 @@
+//! # User Cache
+//!
+//! Profile and settings requests look up the account behind their token on
+//! every call. The user row is cached on Redis for [`CACHE_TTL_SECS`] and
+//! dropped from the cache whenever its email, password or role changes, so
+//! those requests skip Postgres while the row is warm. Without Redis users
+//! are read from the database.
+//!
+//! The cached row leaves out the password hash: checks of the password read
+//! the user from the database. Cash is not cached either, since every ledger
+//! entry changes it and trades are checked against the balance Postgres holds.
+
+use redis::AsyncCommands;
+
+use crate::{AppState, Result, models::user::User};
+
+/// Lifetime of a cached user, bounding how stale a missed invalidation leaves it
+pub const CACHE_TTL_SECS: u64 = 30;
+
+fn cache_key(user_id: i32) -> String {
+    format!("cached_user:{}", user_id)
+}
+
+/// The user with ID `user_id`, from the cache when possible
+///
+/// The password hash of the returned user is empty.
+pub async fn get_user(state: &AppState, user_id: i32) -> Result<Option<User>> {
+    if let Some(user) = cached(state, user_id).await {
+        return Ok(Some(user));
+    }
+
+    let Some(mut user) = state.repos.users.get_user_by_id(user_id).await? else {
+        return Ok(None);
+    };
+    user.password.clear();
+    cache(state, &user).await;
+
+    Ok(Some(user))
+}
+
+async fn cached(state: &AppState, user_id: i32) -> Option<User> {
+    let mut conn = state.redis_pool.get().await.ok()?;
+    let payload: Option<String> = conn.get(cache_key(user_id)).await.ok()?;
+    serde_json::from_str(&payload?).ok()
+}
+
+async fn cache(state: &AppState, user: &User) {
+    let payload = match serde_json::to_string(user) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::error!("Failed to encode user ID {}: {}", user.id, e);
+            return;
+        }
+    };
+
+    let result = match state.redis_pool.get().await {
+        Ok(mut conn) => conn
+            .set_ex::<_, _, ()>(cache_key(user.id), payload, CACHE_TTL_SECS)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        tracing::warn!("Failed to cache user ID {}: {}", user.id, e);
+    }
+}
+
+/// Drop the cached user after a change, so every instance reloads it
+pub async fn invalidate(state: &AppState, user_id: i32) {
+    let result = match state.redis_pool.get().await {
+        Ok(mut conn) => conn
+            .del::<_, ()>(cache_key(user_id))
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        tracing::warn!("Failed to invalidate cached user ID {}: {}", user_id, e);
+    }
+}
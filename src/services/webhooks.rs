@@ -0,0 +1,303 @@
+//! # Webhooks
+//!
+//! Users register URLs their account events are posted to, for integrations
+//! that cannot keep a WebSocket open. Every event [`publish`]ed for a user is
+//! queued in `webhook_deliveries` once for each of their webhooks subscribed
+//! to its kind, whether or not the user turned the kind off for their sockets.
+//!
+//! The delivery worker of every instance claims due deliveries and posts each
+//! as JSON, the account event with its `ts`. Requests carry
+//! `X-Webhook-Delivery` (the delivery ID, the same on every attempt),
+//! `X-Webhook-Event`, `X-Webhook-Timestamp` (Unix seconds) and
+//! `X-Webhook-Signature: sha256=<hex>`, the HMAC-SHA256 of
+//! `{timestamp}.{body}` keyed by the webhook's secret, so receivers can check
+//! that the request came from this server and is recent.
+//!
+//! Any `2xx` answer delivers the event; other answers, redirects and requests
+//! failing or taking longer than [`DELIVERY_TIMEOUT`] are retried after
+//! `WEBHOOK_RETRY_BASE_SECS`, doubling the wait after every attempt, until
+//! `WEBHOOK_MAX_ATTEMPTS` attempts failed. Finished deliveries are kept for
+//! [`DELIVERY_RETENTION_DAYS`] as the webhook's delivery log.
+//!
+//! Deliveries are made from inside the network, so URLs must be `https` and
+//! name a public host ([`check_url`]), and the worker only connects to public
+//! addresses, checked on every attempt; `WEBHOOK_ALLOW_INSECURE` lifts both
+//! for development.
+//!
+//! [`publish`]: crate::ws::events::publish
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use futures_util::future;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use url::{Host, Url};
+use uuid::Uuid;
+
+use crate::{
+    AppState, Error, Result,
+    models::webhook::DueDelivery,
+    repository::webhook_repository::WebhookRepository,
+    services::http::{self, HttpClient},
+    ws::messages::AccountEvent,
+};
+
+/// Prefix telling webhook secrets apart from other credentials
+const SECRET_PREFIX: &str = "whsec_";
+/// Time between looking for due deliveries
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Deliveries attempted at once by an instance
+const BATCH_SIZE: i64 = 50;
+/// Time a receiver has to answer
+pub const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time a claimed delivery is left to its instance before others retry it
+const CLAIM_LEASE: Duration = Duration::from_secs(60);
+/// Days finished deliveries are kept
+pub const DELIVERY_RETENTION_DAYS: i32 = 30;
+/// Time between deleting deliveries past their retention
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Longest error message kept for a failed attempt
+const MAX_ERROR_LEN: usize = 500;
+
+/// Body of a delivery
+#[derive(Debug, Serialize)]
+struct WebhookEvent<'a> {
+    ts: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a AccountEvent,
+}
+
+/// A new signing secret
+pub fn generate_secret() -> String {
+    format!(
+        "{}{}{}",
+        SECRET_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+/// Check that deliveries to `url` stay on the public internet
+///
+/// Hosts are only checked as far as the URL tells; the addresses they resolve
+/// to are checked on every delivery. With `allow_insecure`, any `http` or
+/// `https` URL is accepted.
+pub fn check_url(url: &Url, allow_insecure: bool) -> Result<()> {
+    if allow_insecure {
+        if !matches!(url.scheme(), "http" | "https") || !url.has_host() {
+            return Err(Error::BadRequest(
+                "url must be an http or https URL".to_string(),
+            ));
+        }
+        return Ok(());
+    }
+
+    if url.scheme() != "https" {
+        return Err(Error::BadRequest("url must be an https URL".to_string()));
+    }
+    let public = match url.host() {
+        Some(Host::Ipv4(ip)) => http::is_public(ip.into()),
+        Some(Host::Ipv6(ip)) => http::is_public(ip.into()),
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            // Names only meaningful inside a network
+            domain.contains('.')
+                && ![".localhost", ".local", ".internal"]
+                    .iter()
+                    .any(|suffix| domain.ends_with(suffix))
+        }
+        None => false,
+    };
+    if !public {
+        return Err(Error::BadRequest(
+            "url must point at a public host".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Hex HMAC-SHA256 of `{timestamp}.{body}` keyed by `secret`
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// Queue `event` of `user_id` for their webhooks subscribed to its kind
+///
+/// Like the other ways of announcing events, failing to queue one is logged
+/// and never fails the operation that caused it.
+pub async fn enqueue(state: &AppState, user_id: i32, event: &AccountEvent, ts: DateTime<Utc>) {
+    let payload = match serde_json::to_value(WebhookEvent { ts, event }) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::error!("Failed to encode webhook event: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = WebhookRepository::new(&state.pg_pool)
+        .enqueue_deliveries(user_id, event.kind(), &payload)
+        .await
+    {
+        tracing::warn!(
+            "Failed to queue webhook deliveries of user {}: {}",
+            user_id,
+            e
+        );
+    }
+}
+
+/// Attempt due deliveries every `POLL_INTERVAL`
+pub async fn delivery_worker(state: Arc<AppState>) -> Result<()> {
+    let http = if state.config.webhook_allow_insecure {
+        HttpClient::new(DELIVERY_TIMEOUT)
+    } else {
+        HttpClient::public(DELIVERY_TIMEOUT)
+    };
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut last_prune: Option<Instant> = None;
+
+    loop {
+        interval.tick().await;
+
+        let repository = WebhookRepository::new(&state.pg_pool);
+        if last_prune.map_or(true, |at| at.elapsed() >= PRUNE_INTERVAL) {
+            last_prune = Some(Instant::now());
+            match repository
+                .delete_finished_deliveries(DELIVERY_RETENTION_DAYS)
+                .await
+            {
+                Ok(0) => {}
+                Ok(deleted) => tracing::info!("Deleted {} old webhook deliveries", deleted),
+                Err(e) => tracing::warn!("Failed to delete old webhook deliveries: {}", e),
+            }
+        }
+
+        let due = match repository
+            .claim_due_deliveries(BATCH_SIZE, CLAIM_LEASE.as_secs_f64())
+            .await
+        {
+            Ok(due) => due,
+            Err(e) => {
+                tracing::warn!("Failed to claim webhook deliveries: {}", e);
+                continue;
+            }
+        };
+        future::join_all(
+            due.into_iter()
+                .map(|delivery| attempt(&state, &http, delivery)),
+        )
+        .await;
+    }
+}
+
+/// Post `delivery` once and record the outcome
+async fn attempt(state: &AppState, http: &HttpClient, delivery: DueDelivery) {
+    let body = delivery.payload.to_string();
+    let timestamp = Utc::now().timestamp();
+    let signature = sign(&delivery.secret, timestamp, &body);
+
+    let headers = [
+        ("content-type", "application/json".to_string()),
+        ("x-webhook-delivery", delivery.id.to_string()),
+        ("x-webhook-event", delivery.event.clone()),
+        ("x-webhook-timestamp", timestamp.to_string()),
+        ("x-webhook-signature", format!("sha256={}", signature)),
+    ];
+    let result = http.post(&delivery.url, &headers, body).await;
+    let (response_status, error) = match result {
+        Ok(response) if response.status.is_success() => {
+            (Some(response.status.as_u16() as i32), None)
+        }
+        Ok(response) => (
+            Some(response.status.as_u16() as i32),
+            Some(format!("Answered {}", response.status)),
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    let repository = WebhookRepository::new(&state.pg_pool);
+    let result = match error {
+        None => {
+            repository
+                .mark_delivered(delivery.id, response_status.unwrap_or_default())
+                .await
+        }
+        Some(error) => {
+            let error: String = error.chars().take(MAX_ERROR_LEN).collect();
+            let attempts = delivery.attempts as u32 + 1;
+            let retry_in = (attempts < state.config.webhook_max_attempts).then(|| {
+                state.config.webhook_retry_base_secs as f64 * 2f64.powi(attempts as i32 - 1)
+            });
+            if retry_in.is_none() {
+                tracing::warn!(
+                    "Giving up webhook delivery {} after {} attempts: {}",
+                    delivery.id,
+                    attempts,
+                    error
+                );
+            }
+            repository
+                .mark_failed(delivery.id, response_status, &error, retry_in)
+                .await
+        }
+    };
+    if let Err(e) = result {
+        tracing::error!("Failed to record webhook delivery {}: {}", delivery.id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(url: &str, allow_insecure: bool) -> Result<()> {
+        check_url(&Url::parse(url).unwrap(), allow_insecure)
+    }
+
+    #[test]
+    fn urls_must_be_public_https() {
+        assert!(check("https://hooks.example.com/events", false).is_ok());
+        assert!(check("https://93.184.215.14/hook", false).is_ok());
+
+        for rejected in [
+            "http://hooks.example.com/events",
+            "ftp://hooks.example.com/events",
+            "https://localhost/hook",
+            "https://api.localhost/hook",
+            "https://printer.local/hook",
+            "https://metadata.google.internal/computeMetadata/v1",
+            "https://postgres:5432/hook",
+            "https://127.0.0.1/hook",
+            "https://0x7f.1/hook",
+            "https://10.0.0.8/hook",
+            "https://100.64.0.1/hook",
+            "https://169.254.169.254/latest/meta-data",
+            "https://[::]/hook",
+            "https://[fc00::5]/hook",
+            "https://[::ffff:10.0.0.1]/hook",
+        ] {
+            assert!(
+                matches!(check(rejected, false), Err(Error::BadRequest(_))),
+                "{rejected}"
+            );
+        }
+    }
+
+    #[test]
+    fn development_allows_local_receivers() {
+        assert!(check("http://127.0.0.1:8080/hook", true).is_ok());
+        assert!(check("https://localhost/hook", true).is_ok());
+        assert!(check("ftp://127.0.0.1/hook", true).is_err());
+    }
+}
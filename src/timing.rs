@@ -0,0 +1,240 @@
+//! # Server-Timing
+//!
+//! Profiling aid enabled with `SERVER_TIMING_ENABLED`. Every response carries a
+//! `Server-Timing` header breaking its latency down into phases, in milliseconds:
+//!
+//! - `auth`: token validation and password hashing
+//! - `db`: PostgreSQL statements, taken from the query events `sqlx` emits
+//! - `redis`: Redis round trips on the request path, including pool checkout
+//! - `serialization`: reading and parsing JSON request bodies and encoding
+//!   JSON responses
+//! - `total`: the whole request as seen by the router
+//!
+//! Phases are measured with tracing spans. The middleware wraps each request
+//! in a request span, code on the request path opens phase spans with
+//! [`span`], and [`ServerTimingLayer`] adds the duration of every closed phase
+//! span to the request it belongs to. Phase spans are `TRACE` level, so they
+//! stay out of the regular logs.
+
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{FromRequest, Request, rejection::JsonRejection},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use tracing::{
+    Event, Instrument, Level, Span, Subscriber,
+    field::{Field, Visit},
+    span,
+};
+use tracing_subscriber::{
+    Layer, Registry,
+    filter::Targets,
+    layer::Context,
+    registry::{LookupSpan, Scope},
+};
+
+/// Target of the request and phase spans
+const TARGET: &str = "stock_exchange_sim_core::timing";
+/// Target of the per-statement events emitted by `sqlx`
+const SQLX_TARGET: &str = "sqlx::query";
+/// Name of the span covering a whole request
+const REQUEST_SPAN: &str = "request";
+
+const SERVER_TIMING: HeaderName = HeaderName::from_static("server-timing");
+
+/// Phase of a request reported in the `Server-Timing` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Auth,
+    Db,
+    Redis,
+    Serialization,
+}
+
+impl Phase {
+    const ALL: [Phase; 4] = [Phase::Auth, Phase::Db, Phase::Redis, Phase::Serialization];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Phase::Auth => "auth",
+            Phase::Db => "db",
+            Phase::Redis => "redis",
+            Phase::Serialization => "serialization",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Phase> {
+        Phase::ALL.into_iter().find(|phase| phase.as_str() == name)
+    }
+}
+
+/// Create a span measuring `phase`; enter it or instrument a future with it
+pub fn span(phase: Phase) -> Span {
+    match phase {
+        Phase::Auth => tracing::trace_span!(target: TARGET, "auth"),
+        Phase::Db => tracing::trace_span!(target: TARGET, "db"),
+        Phase::Redis => tracing::trace_span!(target: TARGET, "redis"),
+        Phase::Serialization => tracing::trace_span!(target: TARGET, "serialization"),
+    }
+}
+
+/// The timing layer, filtered to the spans and events it measures
+pub fn layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    ServerTimingLayer.with_filter(
+        Targets::new()
+            .with_target(TARGET, Level::TRACE)
+            .with_target(SQLX_TARGET, Level::TRACE),
+    )
+}
+
+/// Time accumulated per phase by one request
+#[derive(Debug, Default, Clone, Copy)]
+struct Timings([Duration; Phase::ALL.len()]);
+
+impl Timings {
+    fn add(&mut self, phase: Phase, duration: Duration) {
+        self.0[phase as usize] += duration;
+    }
+
+    fn header_value(&self, total: Duration) -> String {
+        Phase::ALL
+            .into_iter()
+            .map(|phase| (phase.as_str(), self.0[phase as usize]))
+            .chain([("total", total)])
+            .map(|(name, duration)| format!("{};dur={:.3}", name, duration.as_secs_f64() * 1000.0))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// When a phase span was created
+struct Started(Instant);
+
+/// Tracing layer adding phase durations to the enclosing request span
+pub struct ServerTimingLayer;
+
+impl<S> Layer<S> for ServerTimingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if span.metadata().target() != TARGET {
+            return;
+        }
+
+        if span.name() == REQUEST_SPAN {
+            span.extensions_mut().insert(Timings::default());
+        } else {
+            span.extensions_mut().insert(Started(Instant::now()));
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(phase) = Phase::from_name(span.name()) else {
+            return;
+        };
+        let Some(started) = span.extensions().get::<Started>().map(|s| s.0) else {
+            return;
+        };
+
+        if let Some(scope) = ctx.span_scope(&id) {
+            add_to_request(scope, phase, started.elapsed());
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if event.metadata().target() != SQLX_TARGET {
+            return;
+        }
+
+        let mut visitor = ElapsedVisitor(None);
+        event.record(&mut visitor);
+        let (Some(elapsed), Some(scope)) = (visitor.0, ctx.event_scope(event)) else {
+            return;
+        };
+        add_to_request(scope, Phase::Db, elapsed);
+    }
+}
+
+fn add_to_request<'a, R: LookupSpan<'a>>(scope: Scope<'a, R>, phase: Phase, duration: Duration) {
+    for span in scope {
+        if let Some(timings) = span.extensions_mut().get_mut::<Timings>() {
+            timings.add(phase, duration);
+            return;
+        }
+    }
+}
+
+/// Reads the `elapsed_secs` field of a `sqlx` query event
+struct ElapsedVisitor(Option<Duration>);
+
+impl Visit for ElapsedVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        if field.name() == "elapsed_secs" {
+            self.0 = Duration::try_from_secs_f64(value).ok();
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+/// Middleware measuring each request and attaching the `Server-Timing` header
+pub async fn server_timing(request: Request, next: Next) -> Response {
+    let span = tracing::trace_span!(target: TARGET, REQUEST_SPAN);
+    let started = Instant::now();
+    let mut response = next.run(request).instrument(span.clone()).await;
+    let total = started.elapsed();
+
+    let timings = span
+        .with_subscriber(|(id, dispatch)| {
+            let span = dispatch.downcast_ref::<Registry>()?.span(id)?;
+            span.extensions().get::<Timings>().copied()
+        })
+        .flatten()
+        .unwrap_or_default();
+
+    if let Ok(value) = HeaderValue::from_str(&timings.header_value(total)) {
+        response.headers_mut().insert(SERVER_TIMING, value);
+    }
+
+    response
+}
+
+/// `axum::Json` that reports parsing and encoding as the `serialization` phase
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = JsonRejection;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::Json(value) = axum::Json::<T>::from_request(request, state)
+            .instrument(span(Phase::Serialization))
+            .await?;
+        Ok(Json(value))
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        let _timing = span(Phase::Serialization).entered();
+        axum::Json(self.0).into_response()
+    }
+}
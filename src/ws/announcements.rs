@@ -0,0 +1,127 @@
+//! # System Announcements
+//!
+//! Admins broadcast announcements ("market closes early today") to every
+//! client subscribed to the `system` channel. Announcements are stored in
+//! Postgres and [`publish`]ed on the Redis channel `announcements`; each
+//! instance listens on it and hands them to its subscribers through
+//! [`Announcements`]. Subscribing first sends the announcements that have not
+//! expired, so clients connecting later still see them.
+
+use std::sync::Arc;
+
+use redis::AsyncCommands;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use super::outbox::Outbox;
+use crate::{
+    AppState, models::announcement::Announcement,
+    repository::announcement_repository::AnnouncementRepository,
+};
+
+/// Redis pub/sub channel carrying new announcements to every instance
+const ANNOUNCEMENT_CHANNEL: &str = "announcements";
+/// Announcements buffered before slow sockets skip the oldest
+const BUFFER: usize = 16;
+
+/// Broadcast channel of the announcements received by this instance
+pub struct Announcements {
+    sender: broadcast::Sender<Arc<Announcement>>,
+}
+
+impl Default for Announcements {
+    fn default() -> Self {
+        Announcements {
+            sender: broadcast::channel(BUFFER).0,
+        }
+    }
+}
+
+impl Announcements {
+    /// Receive every future announcement
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Announcement>> {
+        self.sender.subscribe()
+    }
+
+    fn publish(&self, announcement: Announcement) {
+        // Sending only fails while no socket is subscribed
+        let _ = self.sender.send(Arc::new(announcement));
+    }
+}
+
+/// Announce `announcement` to the `system` subscribers of every instance
+///
+/// The announcement is already stored, so failing to publish it is logged and
+/// subscribers see it the next time they subscribe.
+pub async fn publish(state: &AppState, announcement: &Announcement) {
+    let payload = match serde_json::to_string(announcement) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::error!("Failed to encode announcement: {}", e);
+            return;
+        }
+    };
+
+    let result = match state.redis_pool.get().await {
+        Ok(mut conn) => conn
+            .publish::<_, _, ()>(ANNOUNCEMENT_CHANNEL, payload)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        tracing::warn!("Failed to publish announcement {}: {}", announcement.id, e);
+    }
+}
+
+/// Forward announcements from Redis to the sockets of this instance
+pub async fn announcement_dispatcher(state: Arc<AppState>) {
+    super::fanout::listen(&state, ANNOUNCEMENT_CHANNEL, |channel, payload| {
+        match serde_json::from_str::<Announcement>(&payload) {
+            Ok(announcement) => state.announcements.publish(announcement),
+            Err(e) => tracing::warn!("Ignoring malformed announcement on {}: {}", channel, e),
+        }
+    })
+    .await
+}
+
+/// Queue the active announcements, then every new one, until the task is
+/// aborted or the connection is gone
+pub async fn stream_announcements(state: AppState, outbox: Outbox) {
+    // Join the broadcast before loading so no announcement falls between
+    let mut updates = state.announcements.subscribe();
+    let active = match AnnouncementRepository::new(&state.pg_pool)
+        .get_active()
+        .await
+    {
+        Ok(active) => active,
+        Err(e) => {
+            tracing::error!("Failed to load announcements: {}", e);
+            Vec::new()
+        }
+    };
+    let mut last_sent = 0;
+    for announcement in active {
+        last_sent = announcement.id;
+        if !outbox.push(announcement.into()) {
+            return;
+        }
+    }
+
+    loop {
+        let announcement = match updates.recv().await {
+            Ok(announcement) => announcement,
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Dropped {} announcements for a slow client", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        if announcement.id <= last_sent {
+            continue;
+        }
+        last_sent = announcement.id;
+        if !outbox.push(announcement.as_ref().clone().into()) {
+            return;
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! # Candle Stream
+//!
+//! Streams the candles of a ticker over the `candles` channel for live
+//! charts. The forming candle is seeded from the candle history and then
+//! folded from the price fanout the way the price updater records it, so a
+//! frame goes out on every tick. When the interval closes, either on its
+//! deadline or on the first tick of the next bucket, the candle is sent once
+//! more with `final` set.
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Duration, Utc};
+use tokio::{sync::broadcast::error::RecvError, time::Instant};
+
+use super::{messages::ServerMessage, outbox::Outbox};
+use crate::{
+    AppState,
+    models::price_candle::{CandleInterval, PriceCandle},
+    repository::price_candle_repository::PriceCandleRepository,
+};
+
+/// Send the candles of `ticker` to `outbox` until the task is aborted or the
+/// connection is gone
+pub async fn stream_candles(
+    ticker: String,
+    interval: CandleInterval,
+    state: AppState,
+    outbox: Outbox,
+) {
+    // Join the fanout before reading the history so no tick falls between
+    let mut updates = state.price_fanout.subscribe(&ticker);
+    let frame = |candle: &PriceCandle, is_final: bool| ServerMessage::Candle {
+        ticker: ticker.clone(),
+        interval,
+        time: candle.bucket_start,
+        open: candle.open.clone(),
+        high: candle.high.clone(),
+        low: candle.low.clone(),
+        close: candle.close.clone(),
+        ticks: candle.tick_count,
+        is_final,
+    };
+
+    let mut forming = current_candle(&ticker, interval, &state).await;
+    if let Some(candle) = &forming {
+        if !outbox.push(frame(candle, false)) {
+            return;
+        }
+    }
+    // Start of the latest finalized bucket, whose late ticks are ignored
+    let mut closed: Option<DateTime<Utc>> = None;
+
+    loop {
+        let close_at = forming
+            .as_ref()
+            .map(|candle| deadline(candle.bucket_start + Duration::seconds(interval.seconds())));
+        let update = tokio::select! {
+            update = updates.recv() => update,
+            _ = sleep_until(close_at) => {
+                if let Some(candle) = forming.take() {
+                    closed = Some(candle.bucket_start);
+                    if !outbox.push(frame(&candle, true)) {
+                        return;
+                    }
+                }
+                continue;
+            }
+        };
+        let update = match update {
+            Ok(update) => update,
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    "Dropped {} price updates of {} for a slow candle stream",
+                    skipped,
+                    ticker
+                );
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        let Ok(price) = BigDecimal::try_from(update.price) else {
+            continue;
+        };
+        let bucket = interval.bucket_start(update.timestamp);
+        if closed.is_some_and(|closed| bucket <= closed) {
+            continue;
+        }
+
+        let candle = match forming.as_mut() {
+            Some(candle) if candle.bucket_start == bucket => {
+                candle.apply(price);
+                candle
+            }
+            Some(candle) if candle.bucket_start > bucket => continue,
+            _ => {
+                if let Some(candle) = forming.take() {
+                    closed = Some(candle.bucket_start);
+                    if !outbox.push(frame(&candle, true)) {
+                        return;
+                    }
+                }
+                forming.insert(PriceCandle::opened(bucket, price))
+            }
+        };
+        if !outbox.push(frame(candle, false)) {
+            return;
+        }
+    }
+}
+
+/// The recorded candle of the bucket now forming, if it has any ticks yet
+async fn current_candle(
+    ticker: &str,
+    interval: CandleInterval,
+    state: &AppState,
+) -> Option<PriceCandle> {
+    let bucket = interval.bucket_start(Utc::now());
+    match PriceCandleRepository::new(&state.pg_pool)
+        .get_candles(ticker, interval, bucket, bucket)
+        .await
+    {
+        Ok(candles) => candles.into_iter().next(),
+        Err(e) => {
+            tracing::error!("Failed to load the forming candle of {}: {}", ticker, e);
+            None
+        }
+    }
+}
+
+/// Monotonic instant of a wall clock time, or now if it has passed
+fn deadline(at: DateTime<Utc>) -> Instant {
+    Instant::now() + (at - Utc::now()).to_std().unwrap_or_default()
+}
+
+/// Sleep until `at`, or forever without a deadline
+async fn sleep_until(at: Option<Instant>) {
+    match at {
+        Some(at) => tokio::time::sleep_until(at).await,
+        None => std::future::pending().await,
+    }
+}
@@ -0,0 +1,242 @@
+//! # Account Events
+//!
+//! Pushes fills, margin calls and settled deposits to the sockets of the
+//! user they concern. Services [`publish`] events on the user's Redis channel
+//! so that they reach sockets on every instance; each instance listens on all
+//! user channels and hands events to the connections of the user in its
+//! [`UserSockets`] registry.
+//!
+//! Published events are also logged in the user's sorted set
+//! `user_event_log:{user_id}` for `WS_RESUME_WINDOW_SECS`, so that resumed
+//! sessions can replay what they missed (see [`resume`](super::resume)).
+//!
+//! Users can turn each kind of event off in their profile (`PATCH /me`);
+//! events they turned off are neither pushed nor logged.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    messages::{AccountEvent, ServerMessage},
+    outbox::Outbox,
+};
+use crate::{
+    AppState, Error, Result, models::transaction::Transaction,
+    repository::user_settings_repository::UserSettingsRepository, services::webhooks,
+};
+
+/// Pattern matching the event channels of every user
+const USER_CHANNEL_PATTERN: &str = "user_events:*";
+
+/// Redis pub/sub channel announcing events of `user_id`
+fn user_channel(user_id: i32) -> String {
+    format!("user_events:{}", user_id)
+}
+
+/// Sorted set of the recent events of `user_id`, scored by publish time in
+/// milliseconds
+fn event_log_key(user_id: i32) -> String {
+    format!("user_event_log:{}", user_id)
+}
+
+/// Event as published on a user's channel
+#[derive(Debug, Serialize, Deserialize)]
+struct UserEvent {
+    user_id: i32,
+    ts: DateTime<Utc>,
+    /// Request that caused the event, for tracing it across services
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    #[serde(flatten)]
+    event: AccountEvent,
+}
+
+/// Open WebSocket connections of this instance by user
+#[derive(Default)]
+pub struct UserSockets {
+    next_id: AtomicU64,
+    users: Mutex<HashMap<i32, Vec<(u64, Outbox)>>>,
+}
+
+impl UserSockets {
+    /// Deliver the events of `user_id` to `outbox` until unregistered
+    pub fn register(&self, user_id: i32, outbox: Outbox) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.users
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(user_id)
+            .or_default()
+            .push((id, outbox));
+        id
+    }
+
+    /// Forget a connection returned by [`register`](Self::register)
+    pub fn unregister(&self, user_id: i32, id: u64) {
+        let mut users = self
+            .users
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(sockets) = users.get_mut(&user_id) {
+            sockets.retain(|(socket, _)| *socket != id);
+            if sockets.is_empty() {
+                users.remove(&user_id);
+            }
+        }
+    }
+
+    fn deliver(&self, user_id: i32, message: ServerMessage) {
+        let users = self
+            .users
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for (_, outbox) in users.get(&user_id).into_iter().flatten() {
+            outbox.push(message.clone());
+        }
+    }
+}
+
+/// Announce `event` to the sockets of `user_id` on every instance
+///
+/// Events are best effort: failing to publish one is logged and never fails
+/// the operation that caused it. They are also queued for the user's
+/// [`webhooks`], whatever the user's notification settings.
+pub async fn publish(state: &AppState, user_id: i32, event: AccountEvent) {
+    let ts = Utc::now();
+    webhooks::enqueue(state, user_id, &event, ts).await;
+    if !wants(state, user_id, &event).await {
+        return;
+    }
+
+    let message = UserEvent {
+        user_id,
+        ts,
+        request_id: crate::request_id::current(),
+        event,
+    };
+    let score = message.ts.timestamp_millis();
+    let window = state.config.ws_resume_window_secs;
+    let payload = match serde_json::to_string(&message) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::error!("Failed to encode account event: {}", e);
+            return;
+        }
+    };
+
+    let log = event_log_key(user_id);
+    let result = match state.redis_pool.get().await {
+        Ok(mut conn) => redis::pipe()
+            .publish(user_channel(user_id), &payload)
+            .ignore()
+            .zadd(&log, &payload, score)
+            .ignore()
+            .zrembyscore(&log, "-inf", score - window as i64 * 1000)
+            .ignore()
+            .expire(&log, window as i64)
+            .ignore()
+            .query_async::<()>(&mut *conn)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        tracing::warn!("Failed to publish account event of user {}: {}", user_id, e);
+    }
+}
+
+/// Whether `user_id` wants to be told about events like `event`; settings
+/// that cannot be read leave every event on
+async fn wants(state: &AppState, user_id: i32, event: &AccountEvent) -> bool {
+    let settings = match UserSettingsRepository::new(&state.pg_pool)
+        .get_settings(user_id)
+        .await
+    {
+        Ok(Some(settings)) => settings,
+        Ok(None) => return true,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to read notification settings of user {}: {}",
+                user_id,
+                e
+            );
+            return true;
+        }
+    };
+
+    match event {
+        AccountEvent::OrderFilled { .. } => settings.notify_order_fills,
+        AccountEvent::MarginCall { .. } => settings.notify_margin_calls,
+        AccountEvent::DepositSettled { .. } => settings.notify_deposits,
+    }
+}
+
+/// Events of `user_id` published after `since`, oldest first, as far back as
+/// the resume window reaches
+pub async fn missed_since(
+    state: &AppState,
+    user_id: i32,
+    since: DateTime<Utc>,
+) -> Result<Vec<ServerMessage>> {
+    let mut conn = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let payloads: Vec<String> = conn
+        .zrangebyscore(
+            event_log_key(user_id),
+            format!("({}", since.timestamp_millis()),
+            "+inf",
+        )
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    Ok(payloads
+        .iter()
+        .filter_map(|payload| serde_json::from_str::<UserEvent>(payload).ok())
+        .map(|message| ServerMessage::Event {
+            event: message.event,
+            ts: message.ts,
+        })
+        .collect())
+}
+
+/// Announce an executed buy or sell
+pub async fn publish_fill(state: &AppState, portfolio_id: i32, transaction: &Transaction) {
+    let event = AccountEvent::OrderFilled {
+        portfolio_id,
+        transaction_id: transaction.public_id,
+        ticker: transaction.ticker.clone(),
+        side: transaction.transaction_type.clone(),
+        quantity: transaction.quantity,
+        price: transaction.price.clone(),
+    };
+    publish(state, transaction.user_id, event).await;
+}
+
+/// Forward account events from Redis to the sockets of this instance
+pub async fn account_dispatcher(state: Arc<AppState>) {
+    super::fanout::listen(&state, USER_CHANNEL_PATTERN, |channel, payload| {
+        match serde_json::from_str::<UserEvent>(&payload) {
+            Ok(message) => state.user_sockets.deliver(
+                message.user_id,
+                ServerMessage::Event {
+                    event: message.event,
+                    ts: message.ts,
+                },
+            ),
+            Err(e) => tracing::warn!("Ignoring malformed account event on {}: {}", channel, e),
+        }
+    })
+    .await
+}
@@ -0,0 +1,118 @@
+//! # Price Fanout
+//!
+//! A single Redis pub/sub subscriber per instance listens on the price channel
+//! of every ticker and hands each update to the sockets subscribed to its
+//! ticker through per-ticker broadcast channels, so sockets never poll Redis
+//! for prices. Channels are created on the first subscription and dropped
+//! once the last socket has gone.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures_util::StreamExt;
+use tokio::sync::broadcast;
+
+use crate::{
+    AppState, Error, Result,
+    services::price_updater::{PRICE_CHANNEL_PATTERN, PriceMessage},
+};
+
+/// Updates buffered per ticker before slow sockets skip the oldest
+const TICKER_BUFFER: usize = 16;
+/// Delay before resubscribing after the pub/sub connection drops
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Per-ticker broadcast channels of the price updates of this instance
+#[derive(Default)]
+pub struct PriceFanout {
+    tickers: Mutex<HashMap<String, broadcast::Sender<Arc<PriceMessage>>>>,
+}
+
+impl PriceFanout {
+    /// Receive every future price update of `ticker`
+    pub fn subscribe(&self, ticker: &str) -> broadcast::Receiver<Arc<PriceMessage>> {
+        self.tickers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(ticker.to_string())
+            .or_insert_with(|| broadcast::channel(TICKER_BUFFER).0)
+            .subscribe()
+    }
+
+    /// Hand an update to the sockets subscribed to its ticker
+    fn publish(&self, message: PriceMessage) {
+        let mut tickers = self
+            .tickers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(sender) = tickers.get(&message.ticker) {
+            let ticker = message.ticker.clone();
+            // Sending only fails once every receiver is gone
+            if sender.send(Arc::new(message)).is_err() {
+                tickers.remove(&ticker);
+            }
+        }
+    }
+}
+
+/// Forward price updates from Redis to the fanout, resubscribing whenever the
+/// pub/sub connection drops
+pub async fn price_dispatcher(state: Arc<AppState>) {
+    listen(
+        &state,
+        PRICE_CHANNEL_PATTERN,
+        |channel, payload| match serde_json::from_str::<PriceMessage>(&payload) {
+            Ok(price) => state.price_fanout.publish(price),
+            Err(e) => tracing::warn!("Ignoring malformed price message on {}: {}", channel, e),
+        },
+    )
+    .await
+}
+
+/// Hand every payload published on channels matching `pattern` to `handle`,
+/// resubscribing whenever the pub/sub connection drops
+pub(super) async fn listen(state: &AppState, pattern: &str, mut handle: impl FnMut(&str, String)) {
+    loop {
+        if let Err(e) = dispatch(state, pattern, &mut handle).await {
+            tracing::error!("Subscriber of {} failed: {}", pattern, e);
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn dispatch(
+    state: &AppState,
+    pattern: &str,
+    handle: &mut impl FnMut(&str, String),
+) -> Result<()> {
+    let client = redis::Client::open(state.config.redis_url.as_str())
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let mut pubsub = client
+        .get_async_pubsub()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    pubsub
+        .psubscribe(pattern)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        match message.get_payload::<String>() {
+            Ok(payload) => handle(message.get_channel_name(), payload),
+            Err(e) => tracing::warn!(
+                "Ignoring unreadable message on {}: {}",
+                message.get_channel_name(),
+                e
+            ),
+        }
+    }
+
+    Err(Error::RedisError(format!(
+        "subscription to {} closed",
+        pattern
+    )))
+}
@@ -1,85 +1,727 @@
+//! # WebSocket Handler
+//!
+//! Streams live prices and order books over `/ws` as typed frames (see
+//! [`messages`](super::messages)), JSON by default or MessagePack when the
+//! client offers the `msgpack` subprotocol. A connection keeps a set of
+//! subscriptions that the client edits at any time:
+//!
+//! ```json
+//! {"type": "subscribe", "channel": "price", "tickers": ["AAPL", "MSFT"]}
+//! {"type": "unsubscribe", "channel": "depth", "tickers": ["AAPL"]}
+//! ```
+//!
+//! Every ticker of a command is acknowledged with a `subscribed` or
+//! `unsubscribed` frame, or rejected with an `error` frame. The socket is
+//! split so commands are read while updates are written.
+//!
+//! Price subscriptions send the cached price once, then forward every update
+//! the instance's [`fanout`](super::fanout) receives from Redis pub/sub.
+//! Order books are rebuilt and sent every few seconds. The `candles` channel
+//! takes an `interval` and streams forming and finalized candles, see
+//! [`candles`](super::candles). The `portfolio` channel takes no tickers and
+//! streams the user's equity, see [`portfolio`](super::portfolio). The
+//! `system` channel, also without tickers, streams operator announcements,
+//! see [`announcements`](super::announcements).
+//!
+//! Account events of the connected user (fills, margin calls, deposits) are
+//! pushed as `event` frames without subscribing, see [`events`](super::events).
+//!
+//! A user holds at most `WS_MAX_CONNECTIONS_PER_USER` connections across all
+//! instances (see [`limits`](super::limits)); excess connections receive a
+//! `too_many_connections` error and are closed.
+//!
+//! Clients sending more than `WS_MAX_MESSAGES_PER_SEC` messages a second (after
+//! a burst of `WS_MESSAGE_BURST`) get a `rate_limited` error for each excess
+//! message, which is ignored. Outgoing frames wait in a bounded
+//! [`Outbox`] that drops the oldest updates when a client reads too slowly.
+//!
+//! The `welcome` frame carries a session ID. A client reconnecting with
+//! `/ws?resume={session}` within `WS_RESUME_WINDOW_SECS` gets its
+//! subscriptions back, acknowledged as usual, followed by the account events
+//! it missed; see [`resume`](super::resume).
+//!
+//! The server pings every `WS_PING_INTERVAL_SECS`. A client that sends
+//! nothing, not even a pong, for `WS_IDLE_TIMEOUT_SECS` is disconnected and
+//! its forwarding tasks stopped, so dead connections do not pile up.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
 use axum::{
     Extension,
     extract::{
-        WebSocketUpgrade,
-        ws::{Message, WebSocket},
+        Query, WebSocketUpgrade,
+        ws::{CloseFrame, Message, WebSocket, close_code},
     },
     response::IntoResponse,
 };
-
-use crate::{auth::jwt::Claims, AppState};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use futures_util::{
+    SinkExt, StreamExt,
+    stream::{SplitSink, SplitStream},
+};
 use redis::AsyncCommands;
+use serde::Deserialize;
+use tokio::{sync::broadcast, task::AbortHandle};
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use super::{
+    announcements, candles, events,
+    limits::{ConnectionSlot, TokenBucket},
+    messages::{Channel, ClientMessage, Encoding, ErrorCode, PROTOCOL_VERSION, ServerMessage},
+    outbox::Outbox,
+    portfolio,
+    resume::{self, SavedSession},
+};
+use crate::{
+    AppState, Error, ErrorResponse, Result,
+    auth::jwt::Claims,
+    models::price_candle::CandleInterval,
+    services::{liquidity, price_cache::PriceCache},
+};
 
-pub async fn ws_handler(ws: WebSocketUpgrade, state: Extension<AppState>, _claims: Claims) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_connection(socket, state))
+/// How often order book snapshots are sent
+const DEPTH_INTERVAL: Duration = Duration::from_secs(3);
+
+/// The subscriptions of one connection, with the task forwarding each price
+/// subscription from the fanout
+type Subscriptions = Arc<Mutex<HashMap<Topic, Option<AbortHandle>>>>;
+/// Channel, ticker and, for candles, interval of a subscription
+pub type Topic = (Channel, String, Option<CandleInterval>);
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WsQuery {
+    /// Protocol version the client speaks
+    version: Option<u32>,
+    /// Session of an earlier connection to resume
+    resume: Option<String>,
 }
 
-async fn handle_connection(mut socket: WebSocket, _state: Extension<AppState>) {
-    tracing::info!("New WebSocket connection established");
+/// Open a WebSocket for prices, order books and account events
+///
+/// Clients pick the JSON or binary encoding with `Sec-WebSocket-Protocol`;
+/// messages are described in the README.
+#[utoipa::path(
+    get,
+    path = "/ws",
+    tag = "websocket",
+    params(WsQuery),
+    responses(
+        (status = 101, description = "Switching to the WebSocket protocol"),
+        (status = 400, description = "Unsupported protocol version", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorResponse),
+    ),
+    security(("bearerAuth" = []))
+)]
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    state: Extension<AppState>,
+    claims: Claims,
+    Query(query): Query<WsQuery>,
+) -> Result<impl IntoResponse> {
+    if let Some(version) = query.version.filter(|v| *v != PROTOCOL_VERSION) {
+        return Err(Error::BadRequest(format!(
+            "Unsupported protocol version {}; this server speaks version {}",
+            version, PROTOCOL_VERSION
+        )));
+    }
 
-    if socket
-        .send(Message::Text("Welcome to Stock-Sim WebSocket!".into()))
-        .await
-        .is_err()
-    {
+    let ws = ws.protocols(Encoding::SUBPROTOCOLS);
+    let encoding = Encoding::from_subprotocol(
+        ws.selected_protocol()
+            .and_then(|protocol| protocol.to_str().ok()),
+    );
+    Ok(ws.on_upgrade(move |socket| {
+        handle_connection(socket, state.0, claims.user_id, encoding, query.resume)
+    }))
+}
+
+async fn handle_connection(
+    socket: WebSocket,
+    state: AppState,
+    user_id: i32,
+    encoding: Encoding,
+    resume: Option<String>,
+) {
+    tracing::info!("New WebSocket connection established ({:?})", encoding);
+
+    let resumed = match &resume {
+        Some(id) => resume::take(&state, id, user_id).await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to resume WebSocket session: {}", e);
+            None
+        }),
+        None => None,
+    };
+    let session = Uuid::new_v4().to_string();
+
+    let (mut sink, stream) = socket.split();
+    let welcome = ServerMessage::Welcome {
+        version: PROTOCOL_VERSION,
+        session: session.clone(),
+        resumed: resumed.is_some(),
+    };
+    if send(&mut sink, &welcome, encoding).await.is_err() {
         tracing::warn!("Failed to send greeting, client disconnected");
         return;
     }
 
-    while let Some(Ok(msg)) = socket.recv().await {
-        match msg {
-            Message::Text(text) => {
-                if let Some(ticker) = text.strip_prefix("subscribe:") {
-                    let ticker = ticker.trim().to_uppercase();
-
-                    if !is_valid_ticker(&ticker, &_state).await {
-                        let _ = socket
-                            .send(Message::Text(
-                                format!("Error: Invalid ticker {}", ticker).into(),
-                            ))
-                            .await;
-                        let _ = socket.send(Message::Close(None)).await;
-                        break;
-                    }
+    // Without Redis the limit cannot be checked, which must not take WebSockets down
+    let slot = match ConnectionSlot::acquire(&state, user_id).await {
+        Ok(Some(slot)) => Some(slot),
+        Ok(None) => {
+            let limit = state.config.ws_max_connections_per_user;
+            let refusal = ServerMessage::error(
+                ErrorCode::TooManyConnections,
+                format!("At most {} connections per user", limit),
+                None,
+            );
+            let _ = send(&mut sink, &refusal, encoding).await;
+            let _ = sink
+                .send(Message::Close(Some(CloseFrame {
+                    code: close_code::POLICY,
+                    reason: "too many connections".into(),
+                })))
+                .await;
+            // The refused client may still resume once it has a free slot
+            if let (Some(id), Some(saved)) = (&resume, &resumed) {
+                resume::save(&state, id, saved, state.config.ws_resume_window_secs).await;
+            }
+            return;
+        }
+        Err(e) => {
+            tracing::warn!("Failed to count WebSocket connections: {}", e);
+            None
+        }
+    };
 
-                    // regularly send updates every 1 second
-                    let ticker = ticker.clone();
-                    let state = _state.clone();
-                    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3));
-                    loop {
-                        interval.tick().await;
+    let subscriptions = Subscriptions::default();
+    let outbox = Outbox::default();
+    let registration = state.user_sockets.register(user_id, outbox.clone());
+    let mut writer = tokio::spawn(write_frames(
+        sink,
+        encoding,
+        outbox.clone(),
+        subscriptions.clone(),
+        state.clone(),
+    ));
 
-                        let price = get_price_from_service(&ticker, &state).await;
-                        let response = format!("update:{}:{}", ticker, price);
+    if let Some(saved) = resumed {
+        restore(saved, &subscriptions, &state, user_id, &outbox).await;
+    }
+    save_session(
+        &session,
+        user_id,
+        &subscriptions,
+        &state,
+        live_session_ttl(&state),
+    )
+    .await;
 
-                        if socket.send(Message::Text(response.into())).await.is_err() {
-                            tracing::info!("Client disconnected, stopping updates for {}", ticker);
-                            break;
-                        }
+    // Stop on whichever half finishes first: a closed or idle socket ends both
+    let reader = read_commands(
+        stream,
+        outbox.clone(),
+        &subscriptions,
+        &state,
+        user_id,
+        slot.as_ref(),
+        &session,
+    );
+    tokio::select! {
+        _ = reader => writer.abort(),
+        _ = &mut writer => {}
+    }
+    outbox.close();
+
+    state.user_sockets.unregister(user_id, registration);
+    let window = state.config.ws_resume_window_secs;
+    save_session(&session, user_id, &subscriptions, &state, window).await;
+    if let Some(slot) = slot {
+        slot.release(&state).await;
+    }
+    for forwarder in subscriptions
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .drain()
+        .filter_map(|(_, forwarder)| forwarder)
+    {
+        forwarder.abort();
+    }
+
+    tracing::info!("WebSocket connection closed");
+}
+
+/// Apply client commands to the subscription set until the client leaves
+async fn read_commands(
+    mut stream: SplitStream<WebSocket>,
+    outbox: Outbox,
+    subscriptions: &Subscriptions,
+    state: &AppState,
+    user_id: i32,
+    slot: Option<&ConnectionSlot>,
+    session: &str,
+) {
+    let idle_timeout = Duration::from_secs(state.config.ws_idle_timeout_secs);
+    let mut bucket = TokenBucket::new(
+        state.config.ws_max_messages_per_sec,
+        state.config.ws_message_burst,
+    );
+    loop {
+        // Pongs count as activity, so only unresponsive clients time out
+        let msg = match tokio::time::timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(msg))) => msg,
+            Ok(_) => return,
+            Err(_) => {
+                tracing::info!(
+                    "WebSocket client idle for {:?}, disconnecting",
+                    idle_timeout
+                );
+                return;
+            }
+        };
+        match msg {
+            Message::Text(_) | Message::Binary(_) if !bucket.try_take() => {
+                let refusal = ServerMessage::error(
+                    ErrorCode::RateLimited,
+                    format!(
+                        "At most {} messages per second, message ignored",
+                        state.config.ws_max_messages_per_sec
+                    ),
+                    None,
+                );
+                if !outbox.push(refusal) {
+                    return;
+                }
+            }
+            Message::Text(_) | Message::Binary(_) => {
+                let sent = match decode(&msg) {
+                    Ok(command) => {
+                        apply_command(command, subscriptions, state, user_id, &outbox).await
                     }
-                } else {
-                    let _ = socket
-                        .send(Message::Text(
-                            "Send subscribe:<TICKER> to start receiving updates".into(),
-                        ))
-                        .await;
-                    continue;
+                    Err(e) => outbox.push(ServerMessage::error(
+                        ErrorCode::InvalidMessage,
+                        format!("Invalid message: {}", e),
+                        None,
+                    )),
+                };
+                if !sent {
+                    return;
                 }
+                save_session(
+                    session,
+                    user_id,
+                    subscriptions,
+                    state,
+                    live_session_ttl(state),
+                )
+                .await;
+            }
+            Message::Pong(_) => {
+                if let Some(slot) = slot {
+                    slot.refresh(state).await;
+                }
+                save_session(
+                    session,
+                    user_id,
+                    subscriptions,
+                    state,
+                    live_session_ttl(state),
+                )
+                .await;
             }
             Message::Close(frame) => {
                 tracing::info!("Received close message: {:?}", frame);
-                break;
+                return;
             }
             _ => {}
         }
     }
+}
 
-    tracing::info!("WebSocket connection closed");
+/// Update the subscription set, replying once per ticker
+///
+/// Returns `false` once the writer is gone.
+async fn apply_command(
+    command: ClientMessage,
+    subscriptions: &Subscriptions,
+    state: &AppState,
+    user_id: i32,
+    outbox: &Outbox,
+) -> bool {
+    match command {
+        ClientMessage::Subscribe {
+            channel,
+            tickers,
+            interval,
+        } => {
+            if channel == Channel::Candles && interval.is_none() {
+                return outbox.push(missing_interval());
+            }
+            let interval = interval.filter(|_| channel == Channel::Candles);
+            for ticker in targets(channel, tickers) {
+                let topic = (channel, ticker.clone(), interval);
+                let added = match subscribe(topic, subscriptions, state).await {
+                    Ok(added) => added,
+                    Err(reply) => {
+                        if !outbox.push(reply) {
+                            return false;
+                        }
+                        continue;
+                    }
+                };
+                let reply = ServerMessage::Subscribed {
+                    channel,
+                    ticker: ticker.clone(),
+                    interval,
+                };
+                if !outbox.push(reply) {
+                    return false;
+                }
+
+                // Forward only after the acknowledgement so it arrives first
+                let forwarder = match (channel, interval) {
+                    _ if !added => continue,
+                    (Channel::Price, _) => tokio::spawn(forward_prices(
+                        ticker.clone(),
+                        state.clone(),
+                        outbox.clone(),
+                    )),
+                    (Channel::Portfolio, _) => tokio::spawn(portfolio::stream_equity(
+                        user_id,
+                        state.clone(),
+                        outbox.clone(),
+                    )),
+                    (Channel::System, _) => tokio::spawn(announcements::stream_announcements(
+                        state.clone(),
+                        outbox.clone(),
+                    )),
+                    (Channel::Candles, Some(interval)) => tokio::spawn(candles::stream_candles(
+                        ticker.clone(),
+                        interval,
+                        state.clone(),
+                        outbox.clone(),
+                    )),
+                    (Channel::Depth | Channel::Candles, _) => continue,
+                };
+                subscriptions
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .insert((channel, ticker, interval), Some(forwarder.abort_handle()));
+            }
+        }
+        ClientMessage::Unsubscribe {
+            channel,
+            tickers,
+            interval,
+        } => {
+            if channel == Channel::Candles && interval.is_none() {
+                return outbox.push(missing_interval());
+            }
+            let interval = interval.filter(|_| channel == Channel::Candles);
+            for ticker in targets(channel, tickers) {
+                let removed = subscriptions
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .remove(&(channel, ticker.clone(), interval));
+                let reply = match removed {
+                    Some(forwarder) => {
+                        if let Some(forwarder) = forwarder {
+                            forwarder.abort();
+                        }
+                        ServerMessage::Unsubscribed {
+                            channel,
+                            ticker,
+                            interval,
+                        }
+                    }
+                    None if channel == Channel::Portfolio => ServerMessage::error(
+                        ErrorCode::NotSubscribed,
+                        "Not subscribed to the portfolio",
+                        None,
+                    ),
+                    None if channel == Channel::System => ServerMessage::error(
+                        ErrorCode::NotSubscribed,
+                        "Not subscribed to announcements",
+                        None,
+                    ),
+                    None => ServerMessage::error(
+                        ErrorCode::NotSubscribed,
+                        format!("Not subscribed to {}", ticker),
+                        Some(ticker),
+                    ),
+                };
+                if !outbox.push(reply) {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Resubscribe to the topics of a resumed session, then replay the account
+/// events published since it was last seen
+async fn restore(
+    saved: SavedSession,
+    subscriptions: &Subscriptions,
+    state: &AppState,
+    user_id: i32,
+    outbox: &Outbox,
+) {
+    for (channel, ticker, interval) in saved.topics {
+        let command = ClientMessage::Subscribe {
+            channel,
+            tickers: vec![ticker],
+            interval,
+        };
+        if !apply_command(command, subscriptions, state, user_id, outbox).await {
+            return;
+        }
+    }
+
+    match events::missed_since(state, user_id, saved.seen_at).await {
+        Ok(missed) => {
+            for event in missed {
+                if !outbox.push(event) {
+                    return;
+                }
+            }
+        }
+        Err(e) => tracing::warn!("Failed to replay missed account events: {}", e),
+    }
+}
+
+/// Keep the subscriptions of the connection resumable for `ttl_secs`
+async fn save_session(
+    session: &str,
+    user_id: i32,
+    subscriptions: &Subscriptions,
+    state: &AppState,
+    ttl_secs: u64,
+) {
+    let topics = subscriptions
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .keys()
+        .cloned()
+        .collect();
+    resume::save(
+        state,
+        session,
+        &SavedSession::new(user_id, topics),
+        ttl_secs,
+    )
+    .await;
+}
+
+/// Lifetime of the saved session of an open connection, long enough for it
+/// to go idle and still be resumed afterwards
+fn live_session_ttl(state: &AppState) -> u64 {
+    state.config.ws_idle_timeout_secs + state.config.ws_resume_window_secs
+}
+
+/// Add a subscription, telling whether it is new or the error to reply with
+async fn subscribe(
+    topic: Topic,
+    subscriptions: &Subscriptions,
+    state: &AppState,
+) -> std::result::Result<bool, ServerMessage> {
+    let (channel, ticker, _) = &topic;
+    if !takes_no_tickers(*channel) && !is_valid_ticker(ticker, state).await {
+        return Err(ServerMessage::error(
+            ErrorCode::InvalidTicker,
+            format!("Invalid ticker {}", ticker),
+            Some(ticker.to_string()),
+        ));
+    }
+
+    let mut subscriptions = subscriptions
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if subscriptions.contains_key(&topic) {
+        return Ok(false);
+    }
+    let limit = state.config.ws_max_subscriptions;
+    if subscriptions.len() >= limit {
+        return Err(ServerMessage::error(
+            ErrorCode::TooManySubscriptions,
+            format!("At most {} subscriptions per connection", limit),
+            Some(ticker.to_string()),
+        ));
+    }
+    subscriptions.insert(topic, None);
+    Ok(true)
+}
+
+/// Reply to a candles subscription without an interval
+fn missing_interval() -> ServerMessage {
+    ServerMessage::error(
+        ErrorCode::InvalidMessage,
+        "The candles channel needs an interval",
+        None,
+    )
+}
+
+/// Whether a channel has a single subscription per connection rather than
+/// one per ticker
+fn takes_no_tickers(channel: Channel) -> bool {
+    matches!(channel, Channel::Portfolio | Channel::System)
+}
+
+/// Trimmed, upper-cased tickers without blanks
+///
+/// The portfolio and system channels have no tickers; their single
+/// subscription is keyed by an empty ticker.
+fn targets(channel: Channel, tickers: Vec<String>) -> Vec<String> {
+    if takes_no_tickers(channel) {
+        return vec![String::new()];
+    }
+    tickers
+        .into_iter()
+        .map(|ticker| ticker.trim().to_uppercase())
+        .filter(|ticker| !ticker.is_empty())
+        .collect()
+}
+
+/// Send queued frames as they come, order books on every interval and pings
+/// to keep the client talking
+async fn write_frames(
+    mut sink: SplitSink<WebSocket, Message>,
+    encoding: Encoding,
+    outbox: Outbox,
+    subscriptions: Subscriptions,
+    state: AppState,
+) {
+    let mut interval = tokio::time::interval(DEPTH_INTERVAL);
+    let ping_period = Duration::from_secs(state.config.ws_ping_interval_secs);
+    let mut pings =
+        tokio::time::interval_at(tokio::time::Instant::now() + ping_period, ping_period);
+    loop {
+        let messages = tokio::select! {
+            message = outbox.pop() => match message {
+                Some(message) => vec![message],
+                None => return,
+            },
+            _ = interval.tick() => depth_updates(&subscriptions, &state).await,
+            _ = pings.tick() => {
+                if sink.send(Message::Ping(Default::default())).await.is_err() {
+                    tracing::info!("Client disconnected, stopping updates");
+                    return;
+                }
+                continue;
+            }
+        };
+
+        for message in &messages {
+            if send(&mut sink, message, encoding).await.is_err() {
+                tracing::info!("Client disconnected, stopping updates");
+                return;
+            }
+        }
+    }
 }
 
-async fn is_valid_ticker(ticker: &str, _state: &AppState) -> bool {
+/// Encode and send a frame, failing when the client is gone
+async fn send(
+    sink: &mut SplitSink<WebSocket, Message>,
+    message: &ServerMessage,
+    encoding: Encoding,
+) -> std::result::Result<(), axum::Error> {
+    let frame = match encoding {
+        Encoding::Json => serde_json::to_string(message)
+            .map(|text| Message::Text(text.into()))
+            .map_err(|e| e.to_string()),
+        Encoding::MessagePack => rmp_serde::to_vec_named(message)
+            .map(|bytes| Message::Binary(bytes.into()))
+            .map_err(|e| e.to_string()),
+    };
+    match frame {
+        Ok(frame) => sink.send(frame).await,
+        Err(e) => {
+            tracing::error!("Failed to encode websocket message: {}", e);
+            Ok(())
+        }
+    }
+}
+
+/// Decode a client frame: text as JSON, binary as MessagePack
+fn decode(msg: &Message) -> std::result::Result<ClientMessage, String> {
+    match msg {
+        Message::Text(text) => serde_json::from_str(text).map_err(|e| e.to_string()),
+        Message::Binary(bytes) => rmp_serde::from_slice(bytes).map_err(|e| e.to_string()),
+        _ => Err("not a data frame".to_string()),
+    }
+}
+
+/// Queue the current price of `ticker`, then every update the fanout receives
+async fn forward_prices(ticker: String, state: AppState, outbox: Outbox) {
+    // Join the fanout before reading the snapshot so no update falls between
+    let mut updates = state.price_fanout.subscribe(&ticker);
+    if let Some((price, ts)) = get_price_from_service(&ticker, &state).await {
+        let snapshot = ServerMessage::Price {
+            ticker: ticker.clone(),
+            price,
+            ts,
+        };
+        if !outbox.push(snapshot) {
+            return;
+        }
+    }
+
+    loop {
+        let update = match updates.recv().await {
+            Ok(update) => update,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    "Dropped {} price updates of {} for a slow client",
+                    skipped,
+                    ticker
+                );
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        let Ok(price) = update.price.to_string().parse() else {
+            continue;
+        };
+        let message = ServerMessage::Price {
+            ticker: update.ticker.clone(),
+            price,
+            ts: Some(update.timestamp),
+        };
+        if !outbox.push(message) {
+            return;
+        }
+    }
+}
+
+/// Current order books of the depth subscriptions of a connection
+async fn depth_updates(subscriptions: &Subscriptions, state: &AppState) -> Vec<ServerMessage> {
+    let mut tickers: Vec<String> = subscriptions
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .keys()
+        .filter(|(channel, _, _)| *channel == Channel::Depth)
+        .map(|(_, ticker, _)| ticker.clone())
+        .collect();
+    tickers.sort();
+
+    let mut messages = Vec::with_capacity(tickers.len());
+    for ticker in tickers {
+        messages.extend(get_depth_from_service(&ticker, state).await.map(Into::into));
+    }
+    messages
+}
+
+async fn is_valid_ticker(ticker: &str, state: &AppState) -> bool {
     // check against redis
-    match _state.redis_pool.get().await {
+    match state.redis_pool.get().await {
         Ok(mut conn) => match conn.exists::<_, bool>(ticker).await {
             Ok(exists) => exists,
             Err(e) => {
@@ -93,18 +735,27 @@ async fn is_valid_ticker(ticker: &str, _state: &AppState) -> bool {
         }
     }
 }
-async fn get_price_from_service(_ticker: &str, _state: &AppState) -> f64 {
-    match _state.redis_pool.get().await {
-        Ok(mut conn) => match conn.get::<_, f64>(_ticker).await {
-            Ok(price) => price,
-            Err(e) => {
-                tracing::error!("Failed to get price from redis: {}", e);
-                0.0
-            }
-        },
+
+/// The cached price of `ticker` with its publish time
+async fn get_price_from_service(
+    ticker: &str,
+    state: &AppState,
+) -> Option<(BigDecimal, Option<DateTime<Utc>>)> {
+    match PriceCache::new(&state.redis_pool).price(ticker).await {
+        Ok(cached) => cached.map(|cached| (cached.price, cached.timestamp)),
         Err(e) => {
-            tracing::error!("Failed to get redis connection: {}", e);
-            0.0
+            tracing::error!("Failed to get price from redis: {}", e);
+            None
+        }
+    }
+}
+
+async fn get_depth_from_service(ticker: &str, state: &AppState) -> Option<liquidity::MarketDepth> {
+    match liquidity::market_depth(state, ticker, liquidity::DEFAULT_BOOK_LEVELS).await {
+        Ok(depth) => depth,
+        Err(e) => {
+            tracing::error!("Failed to build order book: {}", e);
+            None
         }
     }
 }
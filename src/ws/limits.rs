@@ -0,0 +1,180 @@
+//! # Connection Limits
+//!
+//! Caps the WebSocket connections of a user across all instances. Each
+//! connection holds a slot in the user's Redis sorted set
+//! `ws_connections:{user_id}`, scored by when it last answered a ping.
+//! Slots not refreshed within the idle timeout belong to connections of a
+//! crashed instance and are ignored, so they never lock a user out.
+//!
+//! Messages from a client are rate limited per connection by a
+//! [`TokenBucket`] refilling `WS_MAX_MESSAGES_PER_SEC` tokens a second up to
+//! `WS_MESSAGE_BURST`.
+
+use redis::AsyncCommands;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::{AppState, Error, Result};
+
+fn connections_key(user_id: i32) -> String {
+    format!("ws_connections:{}", user_id)
+}
+
+/// A connection counted against its user's limit
+pub struct ConnectionSlot {
+    user_id: i32,
+    id: String,
+}
+
+impl ConnectionSlot {
+    /// Take a slot for a new connection of `user_id`
+    ///
+    /// Returns `None` when the user already holds `WS_MAX_CONNECTIONS_PER_USER`
+    /// live connections.
+    pub async fn acquire(state: &AppState, user_id: i32) -> Result<Option<Self>> {
+        let slot = ConnectionSlot {
+            user_id,
+            id: Uuid::new_v4().to_string(),
+        };
+        let key = connections_key(user_id);
+        let now = chrono::Utc::now().timestamp();
+
+        let mut conn = state
+            .redis_pool
+            .get()
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        // Join first and count after, so racing connections cannot both slip under the limit
+        let (held,): (usize,) = redis::pipe()
+            .atomic()
+            .zrembyscore(&key, "-inf", now - stale_after(state))
+            .ignore()
+            .zadd(&key, &slot.id, now)
+            .ignore()
+            .expire(&key, stale_after(state))
+            .ignore()
+            .zcard(&key)
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+
+        if held > state.config.ws_max_connections_per_user {
+            slot.release(state).await;
+            return Ok(None);
+        }
+        Ok(Some(slot))
+    }
+
+    /// Mark the connection as still alive
+    pub async fn refresh(&self, state: &AppState) {
+        let key = connections_key(self.user_id);
+        let result = match state.redis_pool.get().await {
+            Ok(mut conn) => redis::pipe()
+                .zadd(&key, &self.id, chrono::Utc::now().timestamp())
+                .ignore()
+                .expire(&key, stale_after(state))
+                .ignore()
+                .query_async::<()>(&mut *conn)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to refresh WebSocket connection slot: {}", e);
+        }
+    }
+
+    /// Give the slot back once the connection has closed
+    pub async fn release(&self, state: &AppState) {
+        let result = match state.redis_pool.get().await {
+            Ok(mut conn) => conn
+                .zrem::<_, _, ()>(connections_key(self.user_id), &self.id)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to release WebSocket connection slot: {}", e);
+        }
+    }
+}
+
+/// Live WebSocket connections of every user across all instances
+pub async fn count_all(state: &AppState) -> Result<u64> {
+    let mut conn = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let mut keys: Vec<String> = Vec::new();
+    let mut cursor = 0u64;
+    loop {
+        let (next, page): (u64, Vec<String>) = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg("ws_connections:*")
+            .arg("COUNT")
+            .arg(500)
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| Error::RedisError(e.to_string()))?;
+        keys.extend(page);
+        if next == 0 {
+            break;
+        }
+        cursor = next;
+    }
+    if keys.is_empty() {
+        return Ok(0);
+    }
+
+    let live_since = chrono::Utc::now().timestamp() - stale_after(state);
+    let mut pipe = redis::pipe();
+    for key in &keys {
+        pipe.zcount(key, live_since, "+inf");
+    }
+    let counts: Vec<u64> = pipe
+        .query_async(&mut *conn)
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    Ok(counts.into_iter().sum())
+}
+
+/// Seconds after which an unrefreshed slot no longer counts
+fn stale_after(state: &AppState) -> i64 {
+    state.config.ws_idle_timeout_secs as i64
+}
+
+/// Token bucket limiting the messages of one connection
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket refilling `rate` tokens per second up to `burst`
+    pub fn new(rate: u32, burst: u32) -> Self {
+        TokenBucket {
+            rate: f64::from(rate),
+            burst: f64::from(burst),
+            tokens: f64::from(burst),
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Take a token, or return `false` when the bucket is empty
+    pub fn try_take(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.refilled_at = now;
+
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
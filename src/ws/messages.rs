@@ -0,0 +1,287 @@
+//! # WebSocket Messages
+//!
+//! Frames exchanged over `/ws`. Every frame is an object tagged with `type`,
+//! encoded as JSON text unless the client negotiates MessagePack binary
+//! frames (see [`Encoding`]). The protocol is versioned: clients may request a
+//! version with `/ws?version=N`, and the server announces the version it
+//! speaks in its `welcome` frame.
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    models::{announcement::Announcement, price_candle::CandleInterval},
+    services::{
+        liquidity::{BookLevel, MarketDepth},
+        portfolio::PortfolioValuation,
+    },
+};
+
+/// Version of the protocol spoken by this server
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Encoding of the frames the server sends on a connection
+///
+/// Picked with the `Sec-WebSocket-Protocol` header; JSON unless the client
+/// offers `msgpack`. Client frames are decoded by their kind either way: text
+/// as JSON, binary as MessagePack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl Encoding {
+    /// Subprotocols the server accepts, most preferred first
+    pub const SUBPROTOCOLS: [&'static str; 2] = ["msgpack", "json"];
+
+    pub fn from_subprotocol(protocol: Option<&str>) -> Self {
+        match protocol {
+            Some("msgpack") => Encoding::MessagePack,
+            _ => Encoding::Json,
+        }
+    }
+}
+
+/// Kind of updates a subscription delivers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    /// Latest prices
+    #[default]
+    Price,
+    /// Order book snapshots
+    Depth,
+    /// Equity of the user's whole account; takes no tickers
+    Portfolio,
+    /// Forming and finalized candles of an interval
+    Candles,
+    /// Announcements from the operators; takes no tickers
+    System,
+}
+
+/// Frame sent by the client
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe {
+        #[serde(default)]
+        channel: Channel,
+        #[serde(default)]
+        tickers: Vec<String>,
+        /// Candle width, required by the candles channel
+        #[serde(default)]
+        interval: Option<CandleInterval>,
+    },
+    Unsubscribe {
+        #[serde(default)]
+        channel: Channel,
+        #[serde(default)]
+        tickers: Vec<String>,
+        /// Candle width, required by the candles channel
+        #[serde(default)]
+        interval: Option<CandleInterval>,
+    },
+}
+
+/// Frame sent by the server
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Welcome {
+        version: u32,
+        /// ID to present as `/ws?resume={session}` after reconnecting
+        session: String,
+        /// Whether the subscriptions of a previous session were restored
+        resumed: bool,
+    },
+    Subscribed {
+        channel: Channel,
+        /// Empty, and left out, for the portfolio channel
+        #[serde(skip_serializing_if = "String::is_empty")]
+        ticker: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        interval: Option<CandleInterval>,
+    },
+    Unsubscribed {
+        channel: Channel,
+        #[serde(skip_serializing_if = "String::is_empty")]
+        ticker: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        interval: Option<CandleInterval>,
+    },
+    Price {
+        ticker: String,
+        price: BigDecimal,
+        /// When the price was published, if known
+        ts: Option<DateTime<Utc>>,
+    },
+    Depth {
+        ticker: String,
+        mid: BigDecimal,
+        ts: Option<DateTime<Utc>>,
+        bids: Vec<BookLevel>,
+        asks: Vec<BookLevel>,
+    },
+    /// Candle of a subscribed ticker, sent on every price update while it
+    /// forms and once more, final, when its interval closes
+    Candle {
+        ticker: String,
+        interval: CandleInterval,
+        /// Start of the bucket
+        time: DateTime<Utc>,
+        open: BigDecimal,
+        high: BigDecimal,
+        low: BigDecimal,
+        close: BigDecimal,
+        ticks: i32,
+        #[serde(rename = "final")]
+        is_final: bool,
+    },
+    /// Equity of the user's whole account
+    Portfolio {
+        equity: BigDecimal,
+        cash: BigDecimal,
+        money_market: BigDecimal,
+        loans: BigDecimal,
+        market_value: BigDecimal,
+        unrealized_pnl: BigDecimal,
+        ts: DateTime<Utc>,
+    },
+    /// Announcement from the operators, sent on subscribing to the `system`
+    /// channel while active and as soon as it is made
+    Announcement {
+        id: i32,
+        message: String,
+        /// `info`, `warning` or `critical`
+        severity: String,
+        expires_at: Option<DateTime<Utc>>,
+        ts: DateTime<Utc>,
+    },
+    Error {
+        code: ErrorCode,
+        message: String,
+        /// Ticker the error is about, if any
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ticker: Option<String>,
+    },
+    /// Something happened to the account of the connected user
+    Event {
+        #[serde(flatten)]
+        event: AccountEvent,
+        ts: DateTime<Utc>,
+    },
+}
+
+/// Event on a user's account, pushed to every socket of that user without
+/// subscribing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AccountEvent {
+    /// A buy or sell executed, including sales forced by a margin call
+    OrderFilled {
+        portfolio_id: i32,
+        transaction_id: Uuid,
+        ticker: String,
+        /// `buy` or `sell`
+        side: String,
+        quantity: i32,
+        price: BigDecimal,
+    },
+    /// A loan reached the margin call loan-to-value and its collateral is
+    /// being liquidated
+    MarginCall {
+        portfolio_id: i32,
+        loan_id: i32,
+        ltv_percent: f64,
+    },
+    /// A deposit was credited to a portfolio
+    DepositSettled {
+        portfolio_id: i32,
+        amount: BigDecimal,
+        balance: BigDecimal,
+    },
+}
+
+impl AccountEvent {
+    /// Every kind of event, as named in the `event` field
+    pub const KINDS: &'static [&'static str] = &["order_filled", "margin_call", "deposit_settled"];
+
+    /// The kind of this event, as named in the `event` field
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AccountEvent::OrderFilled { .. } => "order_filled",
+            AccountEvent::MarginCall { .. } => "margin_call",
+            AccountEvent::DepositSettled { .. } => "deposit_settled",
+        }
+    }
+}
+
+/// Machine-readable reason of an `error` frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The frame is not a valid client message
+    InvalidMessage,
+    /// The ticker has no price
+    InvalidTicker,
+    /// Unsubscribing from a ticker that was not subscribed
+    NotSubscribed,
+    /// The connection holds the maximum number of subscriptions
+    TooManySubscriptions,
+    /// The user holds the maximum number of connections; the connection is closed
+    TooManyConnections,
+    /// The client sent messages faster than allowed; the message was ignored
+    RateLimited,
+}
+
+impl ServerMessage {
+    pub fn error(code: ErrorCode, message: impl Into<String>, ticker: Option<String>) -> Self {
+        ServerMessage::Error {
+            code,
+            message: message.into(),
+            ticker,
+        }
+    }
+}
+
+impl From<PortfolioValuation> for ServerMessage {
+    fn from(valuation: PortfolioValuation) -> Self {
+        ServerMessage::Portfolio {
+            equity: valuation.equity,
+            cash: valuation.cash,
+            money_market: valuation.money_market,
+            loans: valuation.loans,
+            market_value: valuation.market_value,
+            unrealized_pnl: valuation.unrealized_pnl,
+            ts: Utc::now(),
+        }
+    }
+}
+
+impl From<Announcement> for ServerMessage {
+    fn from(announcement: Announcement) -> Self {
+        ServerMessage::Announcement {
+            id: announcement.id,
+            message: announcement.message,
+            severity: announcement.severity,
+            expires_at: announcement.expires_at,
+            ts: announcement.created_at,
+        }
+    }
+}
+
+impl From<MarketDepth> for ServerMessage {
+    fn from(depth: MarketDepth) -> Self {
+        ServerMessage::Depth {
+            ticker: depth.ticker,
+            mid: depth.mid,
+            ts: depth.timestamp,
+            bids: depth.bids,
+            asks: depth.asks,
+        }
+    }
+}
@@ -1 +1,10 @@
-pub mod handler;
\ No newline at end of file
+pub mod announcements;
+pub mod candles;
+pub mod events;
+pub mod fanout;
+pub mod handler;
+pub mod limits;
+pub mod messages;
+pub mod outbox;
+pub mod portfolio;
+pub mod resume;
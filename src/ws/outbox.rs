@@ -0,0 +1,121 @@
+//! # Outbox
+//!
+//! Bounded queue of the frames waiting to be written to one connection.
+//! Producers never wait: when a slow client lets the queue fill up, the
+//! oldest price, order book, forming candle or portfolio update is dropped to
+//! make room, as a newer one supersedes it anyway. Replies and account events are only
+//! dropped when the queue holds nothing else.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::Notify;
+
+use super::messages::ServerMessage;
+
+/// Frames queued for a connection before the oldest is dropped
+pub const OUTBOX_CAPACITY: usize = 64;
+
+/// Sending half and receiving half of a connection's frame queue
+#[derive(Clone)]
+pub struct Outbox {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    queue: Mutex<Queue>,
+    ready: Notify,
+}
+
+#[derive(Default)]
+struct Queue {
+    frames: VecDeque<ServerMessage>,
+    closed: bool,
+    dropped: u64,
+}
+
+impl Default for Outbox {
+    fn default() -> Self {
+        Outbox {
+            inner: Arc::new(Inner {
+                queue: Mutex::new(Queue::default()),
+                ready: Notify::new(),
+            }),
+        }
+    }
+}
+
+impl Outbox {
+    /// Queue a frame, dropping the oldest when full
+    ///
+    /// Returns `false` once the connection is closed.
+    pub fn push(&self, message: ServerMessage) -> bool {
+        let mut queue = self.lock();
+        if queue.closed {
+            return false;
+        }
+        if queue.frames.len() >= OUTBOX_CAPACITY {
+            let oldest = queue.frames.iter().position(is_update).unwrap_or(0);
+            queue.frames.remove(oldest);
+            queue.dropped += 1;
+            if queue.dropped.is_power_of_two() {
+                tracing::warn!(
+                    "Slow WebSocket client, {} frames dropped so far",
+                    queue.dropped
+                );
+            }
+        }
+        queue.frames.push_back(message);
+        drop(queue);
+        self.inner.ready.notify_one();
+        true
+    }
+
+    /// Wait for the next frame, or `None` once closed
+    pub async fn pop(&self) -> Option<ServerMessage> {
+        loop {
+            {
+                let mut queue = self.lock();
+                if queue.closed {
+                    return None;
+                }
+                if let Some(message) = queue.frames.pop_front() {
+                    return Some(message);
+                }
+            }
+            self.inner.ready.notified().await;
+        }
+    }
+
+    /// Discard queued frames and refuse new ones
+    pub fn close(&self) {
+        let mut queue = self.lock();
+        queue.closed = true;
+        queue.frames.clear();
+        drop(queue);
+        self.inner.ready.notify_one();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queue> {
+        self.inner
+            .queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Whether a newer frame of the same kind supersedes `message`
+fn is_update(message: &ServerMessage) -> bool {
+    matches!(
+        message,
+        ServerMessage::Price { .. }
+            | ServerMessage::Depth { .. }
+            | ServerMessage::Candle {
+                is_final: false,
+                ..
+            }
+            | ServerMessage::Portfolio { .. }
+    )
+}
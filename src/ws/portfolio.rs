@@ -0,0 +1,74 @@
+//! # Portfolio Stream
+//!
+//! Streams the equity of a user's whole account over the `portfolio`
+//! channel. The account is valued once on subscribing and again whenever a
+//! held ticker's price changes, at most once per [`THROTTLE`]. The set of
+//! held tickers is refreshed on every valuation, so buys and sells are picked
+//! up with the next price change.
+
+use std::{sync::Arc, time::Duration};
+
+use tokio::{
+    sync::{
+        Notify,
+        broadcast::{self, error::RecvError},
+    },
+    task::JoinSet,
+    time::Instant,
+};
+
+use super::outbox::Outbox;
+use crate::{
+    AppState,
+    services::{portfolio, price_updater::PriceMessage},
+};
+
+/// Shortest time between two valuations sent to a connection
+pub const THROTTLE: Duration = Duration::from_secs(1);
+
+/// Send the account's equity to `outbox` until the task is aborted or the
+/// connection is gone
+pub async fn stream_equity(user_id: i32, state: AppState, outbox: Outbox) {
+    let changed = Arc::new(Notify::new());
+    let mut watched: Vec<String> = Vec::new();
+    // Dropping the set stops watching the tickers it holds
+    let mut watchers = JoinSet::new();
+
+    loop {
+        let started = Instant::now();
+        match portfolio::value_account(&state, user_id).await {
+            Ok(valuation) => {
+                let mut held: Vec<String> = valuation
+                    .positions
+                    .iter()
+                    .map(|position| position.ticker.clone())
+                    .collect();
+                held.sort();
+                held.dedup();
+                if held != watched {
+                    watchers.shutdown().await;
+                    for ticker in &held {
+                        let updates = state.price_fanout.subscribe(ticker);
+                        watchers.spawn(watch(updates, changed.clone()));
+                    }
+                    watched = held;
+                }
+                if !outbox.push(valuation.into()) {
+                    return;
+                }
+            }
+            Err(e) => tracing::error!("Failed to value account of user {}: {}", user_id, e),
+        }
+
+        changed.notified().await;
+        tokio::time::sleep_until(started + THROTTLE).await;
+    }
+}
+
+/// Wake `changed` on every price update in `updates`
+async fn watch(mut updates: broadcast::Receiver<Arc<PriceMessage>>, changed: Arc<Notify>) {
+    // Lagging only means several updates arrived at once
+    while !matches!(updates.recv().await, Err(RecvError::Closed)) {
+        changed.notify_one();
+    }
+}
@@ -0,0 +1,81 @@
+//! # Session Resumption
+//!
+//! Every connection is a session whose ID is announced in the `welcome`
+//! frame. The session's subscriptions are kept in Redis under
+//! `ws_session:{id}`, so a client reconnecting to `/ws?resume={id}` within
+//! `WS_RESUME_WINDOW_SECS` gets them back on any instance, along with the
+//! account events it missed (see [`events`](super::events)). A session is
+//! resumed at most once; the new connection starts a session of its own.
+//!
+//! Sessions are saved whenever their subscriptions change, whenever the
+//! client answers a ping and once more on closing, so a client whose
+//! instance crashed resumes from its last pong and may see a few events
+//! twice.
+
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+
+use super::handler::Topic;
+use crate::{AppState, Error, Result};
+
+fn session_key(id: &str) -> String {
+    format!("ws_session:{}", id)
+}
+
+/// Subscriptions of a connection as kept for resuming
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedSession {
+    user_id: i32,
+    pub topics: Vec<Topic>,
+    /// When the connection was last known to be alive
+    pub seen_at: DateTime<Utc>,
+}
+
+impl SavedSession {
+    pub fn new(user_id: i32, topics: Vec<Topic>) -> Self {
+        SavedSession {
+            user_id,
+            topics,
+            seen_at: Utc::now(),
+        }
+    }
+}
+
+/// Keep `session` resumable for `ttl_secs`
+pub async fn save(state: &AppState, id: &str, session: &SavedSession, ttl_secs: u64) {
+    let result = match serde_json::to_string(session) {
+        Ok(payload) => match state.redis_pool.get().await {
+            Ok(mut conn) => conn
+                .set_ex::<_, _, ()>(session_key(id), payload, ttl_secs)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        },
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        tracing::warn!("Failed to save WebSocket session: {}", e);
+    }
+}
+
+/// Claim the session `id` of `user_id`, or `None` when it expired, was
+/// already resumed or belongs to someone else
+pub async fn take(state: &AppState, id: &str, user_id: i32) -> Result<Option<SavedSession>> {
+    let mut conn = state
+        .redis_pool
+        .get()
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+    let payload: Option<String> = conn
+        .get_del(session_key(id))
+        .await
+        .map_err(|e| Error::RedisError(e.to_string()))?;
+
+    let Some(payload) = payload else {
+        return Ok(None);
+    };
+    let session: SavedSession = serde_json::from_str(&payload)
+        .map_err(|e| Error::RedisError(format!("Malformed WebSocket session: {}", e)))?;
+    Ok(Some(session).filter(|session| session.user_id == user_id))
+}
@@ -0,0 +1,28 @@
+//! Starting balance and the activity the weekly allowance is paid on.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use support::TestApp;
+
+#[tokio::test]
+async fn new_accounts_start_with_the_configured_balance() {
+    let app = TestApp::spawn_with_env(&[("STARTING_BALANCE", "2500.50")]).await;
+    let client = app.register_user().await;
+
+    assert_eq!(
+        client.balance().await.unwrap(),
+        "2500.5".parse::<BigDecimal>().unwrap()
+    );
+    let portfolios = client.portfolios().await.unwrap();
+    assert_eq!(portfolios[0].cash, "2500.50".parse::<BigDecimal>().unwrap());
+
+    // Logging in marks the account as active for the weekly allowance
+    let last_login: Option<chrono::DateTime<chrono::Utc>> =
+        sqlx::query_scalar("SELECT last_login_at FROM users WHERE id = $1")
+            .bind(client.profile().await.unwrap().id)
+            .fetch_one(&app.pg_pool)
+            .await
+            .unwrap();
+    assert!(last_login.is_some());
+}
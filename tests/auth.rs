@@ -0,0 +1,343 @@
+//! Registration, login and logout.
+
+mod support;
+
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    time::Duration,
+};
+
+use bigdecimal::BigDecimal;
+use futures_util::StreamExt;
+use reqwest::StatusCode;
+use serde_json::Value;
+use stock_exchange_sim_core::client::{Client, ClientError};
+use support::{PASSWORD, TestApp};
+
+#[tokio::test]
+async fn logout_revokes_only_the_current_token() {
+    let app = TestApp::spawn().await;
+    let email = format!("user-{}@example.com", uuid::Uuid::new_v4());
+    let mut client = app.client();
+    client.register(&email, PASSWORD).await.unwrap();
+    client.login(&email, PASSWORD).await.unwrap();
+    let mut other_device = app.client();
+    other_device.login(&email, PASSWORD).await.unwrap();
+
+    assert_eq!(client.balance().await.unwrap(), BigDecimal::from(1000));
+    client.logout().await.unwrap();
+
+    let error = client.balance().await.unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == StatusCode::UNAUTHORIZED));
+    let error = client.logout().await.unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == StatusCode::UNAUTHORIZED));
+
+    // Tokens issued by other logins keep working
+    assert_eq!(
+        other_device.balance().await.unwrap(),
+        BigDecimal::from(1000)
+    );
+}
+
+#[tokio::test]
+async fn admin_role_grants_access_to_admin_endpoints() {
+    let app = TestApp::spawn().await;
+    let email = format!("admin-{}@example.com", uuid::Uuid::new_v4());
+    let mut client = app.client();
+    client.register(&email, PASSWORD).await.unwrap();
+    client.login(&email, PASSWORD).await.unwrap();
+    // Looked up once, so the profile below is served from the cache
+    let profile = client.profile().await.unwrap();
+    assert_eq!(profile.role, "user");
+    let user_id = profile.id;
+
+    let list_instruments = |token: String| {
+        reqwest::Client::new()
+            .get(format!("{}/admin/instruments", app.base_url))
+            .bearer_auth(token)
+            .send()
+    };
+    let response = list_instruments(client.token().unwrap().to_string())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .admin(
+            reqwest::Method::PUT,
+            &format!("/admin/users/{}/role", user_id),
+        )
+        .json(&serde_json::json!({"role": "admin"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(client.profile().await.unwrap().role, "admin");
+
+    // The role is read from the token, so it applies from the next login
+    client.login(&email, PASSWORD).await.unwrap();
+    let response = list_instruments(client.token().unwrap().to_string())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .admin(
+            reqwest::Method::PUT,
+            &format!("/admin/users/{}/role", user_id),
+        )
+        .json(&serde_json::json!({"role": "owner"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn repeated_failed_logins_are_throttled_and_locked_out() {
+    let app = TestApp::spawn_with_env(&[("LOGIN_MAX_FAILURES", "2")]).await;
+    let email = format!("user-{}@example.com", uuid::Uuid::new_v4());
+    app.client().register(&email, PASSWORD).await.unwrap();
+    let login = |email: String, password: &'static str| {
+        app.http()
+            .post(format!("{}/auth/login", app.base_url))
+            .json(&serde_json::json!({"email": email, "password": password}))
+            .send()
+    };
+
+    // Unknown emails fail exactly like wrong passwords
+    let unknown = login(
+        format!("nobody-{}@example.com", uuid::Uuid::new_v4()),
+        "wrong-password",
+    )
+    .await
+    .unwrap();
+    let wrong = login(email.clone(), "wrong-password").await.unwrap();
+    assert_eq!(unknown.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED);
+    let error = |body: serde_json::Value| body["error"].clone();
+    assert_eq!(
+        error(unknown.json().await.unwrap()),
+        error(wrong.json().await.unwrap())
+    );
+
+    // The first failure blocks the email for a second, even for the right password
+    let response = login(email.clone(), PASSWORD).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let response = login(email.clone(), "wrong-password").await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    tokio::time::sleep(Duration::from_millis(2100)).await;
+    let response = login(email.clone(), PASSWORD).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after > 60);
+}
+
+#[tokio::test]
+async fn failed_logins_lock_out_their_ip_only() {
+    let app = TestApp::spawn().await;
+    let email = format!("user-{}@example.com", uuid::Uuid::new_v4());
+    app.client().register(&email, PASSWORD).await.unwrap();
+
+    // Guesses spread over many emails still add up to the default limit of
+    // 20 failures per address
+    for _ in 0..20 {
+        let guess = format!("nobody-{}@example.com", uuid::Uuid::new_v4());
+        let error = app
+            .client()
+            .login(&guess, "wrong-password")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ClientError::Api { status, .. } if status == 401));
+    }
+    let error = app.client().login(&email, PASSWORD).await.unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == 429));
+
+    // Another address is unaffected
+    let other = reqwest::Client::builder()
+        .local_address(IpAddr::V4(Ipv4Addr::LOCALHOST))
+        .build()
+        .unwrap();
+    let mut client = Client::with_http_client(other, &app.base_url);
+    client.login(&email, PASSWORD).await.unwrap();
+}
+
+#[tokio::test]
+async fn deleted_accounts_free_their_email() {
+    let app = TestApp::spawn().await;
+    let email = format!("user-{}@example.com", uuid::Uuid::new_v4());
+    app.client().register(&email, PASSWORD).await.unwrap();
+    sqlx::query("UPDATE users SET deleted_at = NOW() WHERE email = $1")
+        .bind(&email)
+        .execute(&app.pg_pool)
+        .await
+        .unwrap();
+
+    let error = app.client().login(&email, PASSWORD).await.unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == 401));
+    app.client().register(&email, PASSWORD).await.unwrap();
+    app.client().login(&email, PASSWORD).await.unwrap();
+}
+
+#[tokio::test]
+async fn changing_the_password_signs_out_other_sessions() {
+    let app = TestApp::spawn().await;
+    let email = format!("user-{}@example.com", uuid::Uuid::new_v4());
+    let new_password = "changed-integration-password";
+    let mut client = app.client();
+    client.register(&email, PASSWORD).await.unwrap();
+    client.login(&email, PASSWORD).await.unwrap();
+    let mut other_device = app.client();
+    other_device.login(&email, PASSWORD).await.unwrap();
+
+    let error = client
+        .change_password("wrong-password", new_password)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == StatusCode::BAD_REQUEST));
+
+    // Tokens issued in the same second as the change survive it
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    client
+        .change_password(PASSWORD, new_password)
+        .await
+        .unwrap();
+
+    assert_eq!(client.balance().await.unwrap(), BigDecimal::from(1000));
+    let error = other_device.balance().await.unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == StatusCode::UNAUTHORIZED));
+    assert!(other_device.login(&email, PASSWORD).await.is_err());
+    // Wait out the backoff of the failed login
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    other_device.login(&email, new_password).await.unwrap();
+}
+
+#[tokio::test]
+async fn email_changes_once_the_new_address_confirms() {
+    let app = TestApp::spawn().await;
+    let email = format!("user-{}@example.com", uuid::Uuid::new_v4());
+    let new_email = format!("moved-{}@example.com", uuid::Uuid::new_v4());
+    let mut client = app.client();
+    client.register(&email, PASSWORD).await.unwrap();
+    client.login(&email, PASSWORD).await.unwrap();
+    let mut pubsub = app.subscribe("mail").await;
+
+    let error = client
+        .change_email(&new_email, "wrong-password")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == StatusCode::BAD_REQUEST));
+    client.change_email(&new_email, PASSWORD).await.unwrap();
+
+    // Other tests may be mailing at the same time
+    let mut messages = pubsub.on_message();
+    let mail = loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), messages.next())
+            .await
+            .expect("confirmation was not mailed")
+            .unwrap();
+        let mail: Value = serde_json::from_str(&message.get_payload::<String>().unwrap()).unwrap();
+        if mail["to"] == new_email.as_str() {
+            break mail;
+        }
+    };
+    let body = mail["body"].as_str().unwrap();
+    let token = body
+        .split("\"token\": \"")
+        .nth(1)
+        .and_then(|rest| rest.split('"').next())
+        .expect("mail carries the token");
+
+    // The email stays until the change is confirmed
+    client.login(&email, PASSWORD).await.unwrap();
+    assert_eq!(client.profile().await.unwrap().email, email);
+    client.confirm_email(token).await.unwrap();
+    assert_eq!(client.profile().await.unwrap().email, new_email);
+    assert!(client.login(&email, PASSWORD).await.is_err());
+    client.login(&new_email, PASSWORD).await.unwrap();
+
+    let error = client.confirm_email(token).await.unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == StatusCode::BAD_REQUEST));
+}
+
+#[tokio::test]
+async fn tokens_of_retired_signing_keys_stay_valid() {
+    let retired_secret = "retired-integration-secret-of-32-characters";
+    let app = TestApp::spawn_with_env(&[
+        ("JWT_KEY_ID", "current"),
+        (
+            "JWT_VERIFICATION_KEYS",
+            &format!("retired:{}", retired_secret),
+        ),
+    ])
+    .await;
+    let email = format!("user-{}@example.com", uuid::Uuid::new_v4());
+    let mut client = app.client();
+    client.register(&email, PASSWORD).await.unwrap();
+    client.login(&email, PASSWORD).await.unwrap();
+    let user_id: i32 = sqlx::query_scalar("SELECT id FROM users WHERE email = $1")
+        .bind(&email)
+        .fetch_one(&app.pg_pool)
+        .await
+        .unwrap();
+
+    let header = jsonwebtoken::decode_header(client.token().unwrap()).unwrap();
+    assert_eq!(header.kid.as_deref(), Some("current"));
+
+    let sign = |kid: &str, secret: &str| {
+        let header = jsonwebtoken::Header {
+            kid: Some(kid.to_string()),
+            ..Default::default()
+        };
+        let claims = serde_json::json!({
+            "user_id": user_id,
+            "exp": chrono::Utc::now().timestamp() + 3600,
+            "iat": chrono::Utc::now().timestamp(),
+            "jti": uuid::Uuid::new_v4().to_string(),
+            "role": "user",
+        });
+        let key = jsonwebtoken::EncodingKey::from_secret(secret.as_bytes());
+        app.client()
+            .with_token(jsonwebtoken::encode(&header, &claims, &key).unwrap())
+    };
+
+    assert_eq!(
+        sign("retired", retired_secret).balance().await.unwrap(),
+        BigDecimal::from(1000)
+    );
+    for forged in [
+        sign("retired", "some-other-secret-that-is-32-characters"),
+        sign("unknown", retired_secret),
+    ] {
+        let error = forged.balance().await.unwrap_err();
+        assert!(
+            matches!(error, ClientError::Api { status, .. } if status == StatusCode::UNAUTHORIZED)
+        );
+    }
+}
+
+#[tokio::test]
+async fn weak_and_denied_passwords_are_refused_with_advice() {
+    let app = TestApp::spawn_with_env(&[("PASSWORD_DENY_LIST", "Sim-Trader-Deny-2025")]).await;
+    let email = format!("user-{}@example.com", uuid::Uuid::new_v4());
+    let client = app.client();
+
+    for password in ["password123", "sim-trader-deny-2025"] {
+        let error = client.register(&email, password).await.unwrap_err();
+        let ClientError::Api { status, message } = error else {
+            panic!("expected an API error, got {:?}", error);
+        };
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", password);
+        assert!(message.contains("Password is too"), "{}", message);
+    }
+
+    client.register(&email, PASSWORD).await.unwrap();
+}
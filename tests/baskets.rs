@@ -0,0 +1,114 @@
+//! Indexes and ETFs priced from weighted baskets.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+use stock_exchange_sim_core::client::ClientError;
+use support::{TestApp, unique_ticker};
+
+fn is_status(error: ClientError, expected: StatusCode) -> bool {
+    matches!(error, ClientError::Api { status, .. } if status == expected)
+}
+
+/// List `ticker` under `asset_class`
+async fn list_as(app: &TestApp, ticker: &str, asset_class: &str) {
+    app.list_instrument(ticker).await;
+    sqlx::query("UPDATE instruments SET asset_class = $2 WHERE ticker = $1")
+        .bind(ticker)
+        .bind(asset_class)
+        .execute(&app.pg_pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn basket_price_is_the_weighted_sum_of_constituents() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let (index, first, second) = (unique_ticker(), unique_ticker(), unique_ticker());
+    list_as(&app, &index, "index").await;
+    app.list_instrument(&first).await;
+    app.list_instrument(&second).await;
+    app.set_price(&first, 100.0).await;
+    app.set_price(&second, 200.0).await;
+
+    let response = app
+        .admin(Method::PUT, &format!("/admin/instruments/{}/basket", index))
+        .json(&json!({
+            "constituents": [
+                {"ticker": first, "weight": 0.5},
+                {"ticker": second.to_lowercase(), "weight": 0.25},
+            ]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let basket = client.basket(&index).await.unwrap();
+    assert_eq!(basket.constituents.len(), 2);
+    assert_eq!(basket.price, Some(BigDecimal::from(100)));
+
+    // Indexes are for reference only
+    client.deposit("1000").await.unwrap();
+    let error = client.buy(&index, 1).await.unwrap_err();
+    assert!(is_status(error, StatusCode::BAD_REQUEST));
+
+    // A constituent cannot be delisted while a basket holds it
+    let response = app
+        .admin(Method::DELETE, &format!("/admin/instruments/{}", first))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app
+        .admin(
+            Method::DELETE,
+            &format!("/admin/instruments/{}/basket", index),
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let error = client.basket(&index).await.unwrap_err();
+    assert!(is_status(error, StatusCode::NOT_FOUND));
+}
+
+#[tokio::test]
+async fn only_indexes_and_etfs_hold_flat_baskets() {
+    let app = TestApp::spawn().await;
+    let (equity, etf, index, constituent) = (
+        unique_ticker(),
+        unique_ticker(),
+        unique_ticker(),
+        unique_ticker(),
+    );
+    app.list_instrument(&equity).await;
+    list_as(&app, &etf, "etf").await;
+    list_as(&app, &index, "index").await;
+    app.list_instrument(&constituent).await;
+    let set_basket = |basket: &str, ticker: &str| {
+        app.admin(
+            Method::PUT,
+            &format!("/admin/instruments/{}/basket", basket),
+        )
+        .json(&json!({"constituents": [{"ticker": ticker, "weight": 1.0}]}))
+        .send()
+    };
+
+    let response = set_basket(&equity, &constituent).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = set_basket(&etf, "NOPE").await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = set_basket(&etf, &constituent).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Baskets cannot nest in either direction
+    let response = set_basket(&index, &etf).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = set_basket(&constituent, &index).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,80 @@
+//! Benchmark comparison in portfolio history and metrics.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use chrono::{Days, Utc};
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn compares_portfolio_with_selected_benchmark() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let benchmark = unique_ticker();
+
+    let settings = client.set_benchmark(Some(&benchmark)).await.unwrap();
+    assert_eq!(
+        settings.benchmark_ticker.as_deref(),
+        Some(benchmark.as_str())
+    );
+
+    let user_id: i32 =
+        sqlx::query_scalar("SELECT user_id FROM user_settings WHERE benchmark_ticker = $1")
+            .bind(&benchmark)
+            .fetch_one(&app.pg_pool)
+            .await
+            .unwrap();
+
+    // Three days: the portfolio gains 10% a day, the benchmark 5%
+    let today = Utc::now().date_naive();
+    let days = [3, 2, 1].map(|n| today - Days::new(n));
+    for (day, (equity, price)) in
+        days.iter()
+            .zip([(1000.0, 100.0), (1100.0, 105.0), (1210.0, 110.25)])
+    {
+        sqlx::query(
+            "INSERT INTO portfolio_snapshots (user_id, snapshot_date, cash, market_value, equity) \
+             VALUES ($1, $2, $3, 0, $3)",
+        )
+        .bind(user_id)
+        .bind(day)
+        .bind(BigDecimal::try_from(equity).unwrap())
+        .execute(&app.pg_pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO benchmark_prices (ticker, price_date, price) VALUES ($1, $2, $3)")
+            .bind(&benchmark)
+            .bind(day)
+            .bind(BigDecimal::try_from(price).unwrap())
+            .execute(&app.pg_pool)
+            .await
+            .unwrap();
+    }
+
+    let history = client.portfolio_history(None, None).await.unwrap();
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0].cumulative_return, Some(0.0));
+    let last = &history[2];
+    assert_eq!(
+        last.benchmark_price,
+        Some(BigDecimal::try_from(110.25).unwrap())
+    );
+    assert!((last.cumulative_return.unwrap() - 0.21).abs() < 1e-9);
+    assert!((last.benchmark_cumulative_return.unwrap() - 0.1025).abs() < 1e-9);
+
+    let metrics = client.portfolio_metrics(None, None).await.unwrap();
+    let comparison = metrics.benchmark.expect("benchmark comparison missing");
+    assert_eq!(comparison.ticker, benchmark);
+    assert_eq!(comparison.periods, 2);
+    assert!((comparison.portfolio_return - 0.21).abs() < 1e-9);
+    assert!((comparison.benchmark_return - 0.1025).abs() < 1e-9);
+    assert!((comparison.tracking_difference - 0.1075).abs() < 1e-9);
+
+    // Clearing the benchmark drops the comparison
+    let settings = client.set_benchmark(None).await.unwrap();
+    assert_eq!(settings.benchmark_ticker, None);
+    let metrics = client.portfolio_metrics(None, None).await.unwrap();
+    assert!(metrics.benchmark.is_none());
+    let history = client.portfolio_history(None, None).await.unwrap();
+    assert!(history.iter().all(|s| s.benchmark_price.is_none()));
+}
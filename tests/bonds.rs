@@ -0,0 +1,105 @@
+//! Bond terms and accrued interest.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use chrono::{Months, Utc};
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+use support::{TestApp, unique_ticker};
+
+/// List `ticker` as a bond instrument
+async fn list_bond(app: &TestApp, ticker: &str) {
+    app.list_instrument(ticker).await;
+    sqlx::query("UPDATE instruments SET asset_class = 'bond' WHERE ticker = $1")
+        .bind(ticker)
+        .execute(&app.pg_pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn bond_positions_accrue_interest_between_coupons() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    list_bond(&app, &ticker).await;
+
+    // Semiannual coupons with the last one paid a month ago
+    let today = Utc::now().date_naive();
+    let maturity_date = today + Months::new(5) + Months::new(60);
+    let response = app
+        .admin(Method::PUT, &format!("/admin/bonds/{}", ticker))
+        .json(&json!({
+            "face_value": 1000.0,
+            "coupon_rate_percent": 5.0,
+            "coupons_per_year": 2,
+            "maturity_date": maturity_date,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let bond = client.bond(&ticker).await.unwrap();
+    assert_eq!(bond.coupon, BigDecimal::from(25));
+    assert_eq!(bond.next_coupon_date, Some(today + Months::new(5)));
+    let (low, high) = (BigDecimal::from(3), BigDecimal::from(6));
+    assert!(bond.accrued_interest > low && bond.accrued_interest < high);
+
+    app.set_price(&ticker, 990.0).await;
+    client.deposit("5000").await.unwrap();
+    client.buy(&ticker, 2).await.unwrap();
+
+    let holdings = client.holdings().await.unwrap();
+    let accrued = &holdings[0].accrued_interest;
+    let (low, high) = (BigDecimal::from(6), BigDecimal::from(12));
+    assert!(*accrued > low && *accrued < high);
+    assert_eq!(
+        holdings[0].market_value,
+        BigDecimal::from(1980) + accrued.clone()
+    );
+    let portfolio = client.portfolio().await.unwrap();
+    assert_eq!(&portfolio.positions[0].accrued_interest, accrued);
+}
+
+#[tokio::test]
+async fn only_bonds_take_terms() {
+    let app = TestApp::spawn().await;
+    let ticker = unique_ticker();
+    app.list_instrument(&ticker).await;
+    let maturity_date = Utc::now().date_naive() + Months::new(24);
+    let terms = |coupons_per_year: i32| {
+        json!({
+            "face_value": 100.0,
+            "coupon_rate_percent": 3.0,
+            "coupons_per_year": coupons_per_year,
+            "maturity_date": maturity_date,
+        })
+    };
+
+    let response = app
+        .admin(Method::PUT, &format!("/admin/bonds/{}", ticker))
+        .json(&terms(4))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    list_bond(&app, &ticker).await;
+    let response = app
+        .admin(Method::PUT, &format!("/admin/bonds/{}", ticker))
+        .json(&terms(3))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .admin(Method::PUT, &format!("/admin/bonds/{}", ticker))
+        .json(&terms(4))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,70 @@
+//! Server-managed bot traders.
+
+mod support;
+
+use reqwest::{Method, StatusCode};
+use serde_json::{Value, json};
+use support::TestApp;
+
+#[tokio::test]
+async fn admins_add_and_pause_bots() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+
+    let response = app
+        .admin(Method::POST, "/admin/bots")
+        .json(&json!({ "strategy": "momentum" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bot: Value = response.json().await.unwrap();
+    let id = bot["id"].as_i64().unwrap();
+    assert_eq!(bot["strategy"], "momentum");
+    assert_eq!(bot["active"], true);
+
+    // Bots are regular accounts, visible to everyone
+    let user_id = bot["user_id"].as_str().unwrap().parse().unwrap();
+    let profile = client.user(user_id).await.unwrap();
+    assert_eq!(
+        profile.display_name.as_deref(),
+        Some(format!("Momentum Bot {}", id).as_str())
+    );
+
+    let response = app
+        .admin(Method::PATCH, &format!("/admin/bots/{}", id))
+        .json(&json!({ "active": false }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bot: Value = response.json().await.unwrap();
+    assert_eq!(bot["active"], false);
+
+    let bots: Vec<Value> = app
+        .admin(Method::GET, "/admin/bots")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let listed = bots.iter().find(|b| b["id"] == id).unwrap();
+    assert_eq!(listed["active"], false);
+
+    let response = app
+        .admin(Method::PATCH, "/admin/bots/999999")
+        .json(&json!({ "active": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app
+        .admin(Method::POST, "/admin/bots")
+        .json(&json!({ "strategy": "arbitrage" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_client_error());
+}
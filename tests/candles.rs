@@ -0,0 +1,95 @@
+//! OHLCV candles for charting.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use reqwest::StatusCode;
+use stock_exchange_sim_core::client::{
+    ClientError,
+    types::{CandleInterval, CandleQuery},
+};
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn lists_candles_with_simulator_volume() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+
+    app.set_price(&ticker, 100.0).await;
+    client.buy(&ticker, 3).await.unwrap();
+    client.sell(&ticker, 1).await.unwrap();
+
+    // The feed is not running in tests, so store the candles it would have built
+    let current = Utc::now().duration_trunc(Duration::hours(1)).unwrap();
+    let previous = current - Duration::hours(1);
+    for (bucket_start, open, close) in [(previous, 90.0, 95.0), (current, 95.0, 100.0)] {
+        sqlx::query(
+            "INSERT INTO price_candles (ticker, interval, bucket_start, open, high, low, close, tick_count) \
+             VALUES ($1, '1h', $2, $3, $4, $3, $4, 10)",
+        )
+        .bind(&ticker)
+        .bind(bucket_start)
+        .bind(BigDecimal::try_from(open).unwrap())
+        .bind(BigDecimal::try_from(close).unwrap())
+        .execute(&app.pg_pool)
+        .await
+        .unwrap();
+    }
+
+    let query = CandleQuery {
+        interval: Some(CandleInterval::OneHour),
+        ..Default::default()
+    };
+    let candles = client.candles(&ticker, &query).await.unwrap();
+    assert_eq!(candles.len(), 2);
+    assert_eq!(candles[0].time, previous);
+    assert_eq!(candles[0].open, BigDecimal::from(90));
+    assert_eq!(candles[0].volume, 0);
+    assert_eq!(candles[1].time, current);
+    assert_eq!(candles[1].close, BigDecimal::from(100));
+    assert_eq!(candles[1].volume, 4);
+    assert_eq!(candles[1].ticks, 10);
+
+    let older: Vec<DateTime<Utc>> = client
+        .candles(
+            &ticker,
+            &CandleQuery {
+                to: Some(previous),
+                ..query.clone()
+            },
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|candle| candle.time)
+        .collect();
+    assert_eq!(older, vec![previous]);
+
+    // Only 1h candles were stored
+    let minutes = client
+        .candles(&ticker, &CandleQuery::default())
+        .await
+        .unwrap();
+    assert!(minutes.is_empty());
+
+    let error = client
+        .candles(
+            &ticker,
+            &CandleQuery {
+                interval: Some(CandleInterval::OneMinute),
+                from: Some(current - Duration::days(30)),
+                to: Some(current),
+            },
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ClientError::Api {
+            status: StatusCode::BAD_REQUEST,
+            ..
+        }
+    ));
+}
@@ -0,0 +1,114 @@
+//! Classes with instructors, students and their class portfolios.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use reqwest::StatusCode;
+use stock_exchange_sim_core::client::{
+    ClientError,
+    types::{ClassRole, CreateClassRequest, UpdateClassRequest},
+};
+use support::{TestApp, unique_ticker};
+
+fn assert_status<T: std::fmt::Debug>(result: Result<T, ClientError>, expected: StatusCode) {
+    match result {
+        Err(ClientError::Api { status, .. }) => assert_eq!(status, expected),
+        other => panic!("expected {}, got {:?}", expected, other),
+    }
+}
+
+#[tokio::test]
+async fn students_trade_allowed_tickers_in_class_portfolios() {
+    let app = TestApp::spawn().await;
+    let teacher = app.register_user().await;
+    let student = app.register_user().await;
+    let allowed = unique_ticker();
+    let other = unique_ticker();
+
+    let class = teacher
+        .create_class(&CreateClassRequest {
+            name: format!("Class {}", uuid::Uuid::new_v4().simple()),
+            starting_balance: 2000.0,
+            allowed_tickers: Some(vec![allowed.to_lowercase()]),
+        })
+        .await
+        .unwrap();
+    assert_eq!(class.role, ClassRole::Instructor);
+    assert_eq!(class.allowed_tickers, Some(vec![allowed.clone()]));
+    let code = class.invite_code.unwrap();
+
+    let joined = student.join_class(&code.to_lowercase()).await.unwrap();
+    assert_eq!(joined.role, ClassRole::Student);
+    assert!(joined.invite_code.is_none());
+    assert_status(student.join_class(&code).await, StatusCode::CONFLICT);
+
+    let pupil = student.clone().with_portfolio(joined.portfolio_id.unwrap());
+    assert_eq!(pupil.balance().await.unwrap(), BigDecimal::from(2000));
+    assert_status(pupil.deposit("100").await, StatusCode::BAD_REQUEST);
+    app.set_price(&allowed, 10.0).await;
+    app.set_price(&other, 10.0).await;
+    assert_status(pupil.buy(&other, 1).await, StatusCode::BAD_REQUEST);
+    pupil.buy(&allowed, 10).await.unwrap();
+    // The class portfolio is kept out of the account
+    assert!(student.holdings().await.unwrap().is_empty());
+
+    let dashboard = teacher.class_dashboard(class.id).await.unwrap();
+    assert_eq!(dashboard.students.len(), 1);
+    assert_eq!(dashboard.students[0].positions, 1);
+    assert_eq!(
+        dashboard.students[0].user_id,
+        student.profile().await.unwrap().id
+    );
+    assert_status(
+        student.class_dashboard(class.id).await,
+        StatusCode::FORBIDDEN,
+    );
+
+    // Lifting the restriction lets the student buy anything
+    let updated = teacher
+        .update_class(
+            class.id,
+            &UpdateClassRequest {
+                allowed_tickers: Some(Vec::new()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert!(updated.allowed_tickers.is_none());
+    pupil.buy(&other, 1).await.unwrap();
+}
+
+#[tokio::test]
+async fn invite_codes_can_be_replaced() {
+    let app = TestApp::spawn().await;
+    let teacher = app.register_user().await;
+    let student = app.register_user().await;
+    let outsider = app.register_user().await;
+
+    let class = teacher
+        .create_class(&CreateClassRequest {
+            name: format!("Class {}", uuid::Uuid::new_v4().simple()),
+            starting_balance: 1000.0,
+            allowed_tickers: None,
+        })
+        .await
+        .unwrap();
+    let old_code = class.invite_code.unwrap();
+    let new_code = teacher
+        .reset_class_invite_code(class.id)
+        .await
+        .unwrap()
+        .invite_code
+        .unwrap();
+    assert_ne!(old_code, new_code);
+
+    assert_status(student.join_class(&old_code).await, StatusCode::NOT_FOUND);
+    student.join_class(&new_code).await.unwrap();
+    assert_eq!(student.classes().await.unwrap()[0].id, class.id);
+    assert_status(outsider.class(class.id).await, StatusCode::NOT_FOUND);
+    assert_status(
+        student.reset_class_invite_code(class.id).await,
+        StatusCode::FORBIDDEN,
+    );
+}
@@ -0,0 +1,162 @@
+//! Trading competitions and their isolated portfolios.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use chrono::{Duration, Utc};
+use reqwest::{Method, StatusCode};
+use serde_json::{Value, json};
+use stock_exchange_sim_core::client::{ClientError, types::CompetitionStatus};
+use support::{TestApp, unique_ticker};
+
+fn assert_status<T: std::fmt::Debug>(result: Result<T, ClientError>, expected: StatusCode) {
+    match result {
+        Err(ClientError::Api { status, .. }) => assert_eq!(status, expected),
+        other => panic!("expected {}, got {:?}", expected, other),
+    }
+}
+
+async fn create_competition(app: &TestApp, starts_in: Duration, ends_in: Duration) -> i32 {
+    let now = Utc::now();
+    let response = app
+        .admin(Method::POST, "/admin/competitions")
+        .json(&json!({
+            "name": format!("Cup {}", uuid::Uuid::new_v4().simple()),
+            "starts_at": now + starts_in,
+            "ends_at": now + ends_in,
+            "starting_balance": 5000.0,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    body["id"].as_i64().unwrap() as i32
+}
+
+#[tokio::test]
+async fn competition_portfolios_trade_apart_from_the_account() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let rival = app.register_user().await;
+    let ticker = unique_ticker();
+    let id = create_competition(&app, Duration::hours(-1), Duration::hours(1)).await;
+
+    let competition = client.join_competition(id).await.unwrap();
+    assert_eq!(competition.status, CompetitionStatus::Running);
+    let portfolio_id = competition.portfolio_id.unwrap();
+    assert_status(client.join_competition(id).await, StatusCode::CONFLICT);
+    rival.join_competition(id).await.unwrap();
+
+    let entrant = client.clone().with_portfolio(portfolio_id);
+    assert_eq!(entrant.balance().await.unwrap(), BigDecimal::from(5000));
+    assert_status(entrant.deposit("100").await, StatusCode::BAD_REQUEST);
+    assert_status(entrant.withdraw("100").await, StatusCode::BAD_REQUEST);
+    let main = client.portfolios().await.unwrap()[0].id;
+    assert_status(
+        client.transfer(main, portfolio_id, 100.0).await,
+        StatusCode::BAD_REQUEST,
+    );
+
+    // Trades in the competition portfolio leave the account untouched
+    app.set_price(&ticker, 50.0).await;
+    entrant.buy(&ticker, 10).await.unwrap();
+    assert!(client.holdings().await.unwrap().is_empty());
+    assert_eq!(client.balance().await.unwrap(), BigDecimal::from(1000));
+
+    let standings = client.competition_standings(id).await.unwrap();
+    assert!(!standings.r#final);
+    assert_eq!(standings.entrants, 2);
+    let you = standings.you.unwrap();
+    assert!(you.is_you);
+    assert_eq!(standings.standings.len(), 2);
+    assert_eq!(standings.standings[0].rank, 1);
+
+    let listed = client.competitions().await.unwrap();
+    let listed = listed.iter().find(|c| c.id == id).unwrap();
+    assert_eq!(listed.portfolio_id, Some(portfolio_id));
+    assert_eq!(listed.starting_balance, BigDecimal::from(5000));
+}
+
+#[tokio::test]
+async fn upcoming_competitions_do_not_trade_and_can_be_cancelled() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    let id = create_competition(&app, Duration::hours(1), Duration::hours(2)).await;
+
+    let competition = client.join_competition(id).await.unwrap();
+    assert_eq!(competition.status, CompetitionStatus::Upcoming);
+    let entrant = client
+        .clone()
+        .with_portfolio(competition.portfolio_id.unwrap());
+    app.set_price(&ticker, 50.0).await;
+    assert_status(entrant.buy(&ticker, 1).await, StatusCode::BAD_REQUEST);
+
+    let response = app
+        .admin(Method::DELETE, &format!("/admin/competitions/{}", id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(client.portfolios().await.unwrap().len(), 1);
+    assert_status(client.join_competition(id).await, StatusCode::NOT_FOUND);
+
+    let response = app
+        .admin(Method::POST, "/admin/competitions")
+        .json(&json!({
+            "name": "Over",
+            "starts_at": Utc::now() - Duration::hours(2),
+            "ends_at": Utc::now() - Duration::hours(1),
+            "starting_balance": 5000.0,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn accelerated_competitions_run_a_day_per_hour() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let now = Utc::now();
+    let response = app
+        .admin(Method::POST, "/admin/competitions")
+        .json(&json!({
+            "name": format!("Sprint {}", uuid::Uuid::new_v4().simple()),
+            "starts_at": now - Duration::hours(1),
+            "ends_at": now + Duration::hours(1),
+            "starting_balance": 5000.0,
+            "clock_speed": 24.0,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["clock_speed"], 24.0);
+
+    // One real hour in, the competition's clock is a day past its start
+    let competition = client
+        .join_competition(body["id"].as_i64().unwrap() as i32)
+        .await
+        .unwrap();
+    assert_eq!(competition.clock_speed, 24.0);
+    let ahead = competition.sim_time - Utc::now();
+    assert!(ahead > Duration::hours(22) && ahead < Duration::hours(24));
+
+    let response = app
+        .admin(Method::POST, "/admin/competitions")
+        .json(&json!({
+            "name": "Too fast",
+            "starts_at": now,
+            "ends_at": now + Duration::hours(1),
+            "starting_balance": 5000.0,
+            "clock_speed": 5000.0,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
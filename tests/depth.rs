@@ -0,0 +1,77 @@
+//! Synthetic order book and market orders walking it.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+use stock_exchange_sim_core::client::{ClientError, types::BookLevel};
+use support::{TestApp, unique_ticker};
+
+fn level(price: &str, quantity: i64) -> BookLevel {
+    BookLevel {
+        price: price.parse().unwrap(),
+        quantity,
+    }
+}
+
+#[tokio::test]
+async fn market_orders_walk_the_book() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    client.deposit("2000").await.unwrap();
+
+    app.set_price(&ticker, 10.0).await;
+    let response = app
+        .admin(Method::PUT, &format!("/admin/liquidity/{}", ticker))
+        .json(&json!({ "depth": 100, "spread_bps": 100.0, "resilience": 0.0001 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Levels 1% apart, the best half a percent from the price
+    let depth = client.market_depth(&ticker, Some(3)).await.unwrap();
+    assert_eq!(depth.mid, BigDecimal::from(10));
+    assert_eq!(
+        depth.asks,
+        vec![
+            level("10.05", 100),
+            level("10.15", 100),
+            level("10.25", 100)
+        ]
+    );
+    assert_eq!(
+        depth.bids,
+        vec![level("9.95", 100), level("9.85", 100), level("9.75", 100)]
+    );
+
+    // 100 shares at 10.05 and 50 at 10.15
+    let buy = client.buy(&ticker, 150).await.unwrap();
+    assert_eq!(buy.price, "10.08".parse::<BigDecimal>().unwrap());
+
+    let depth = client.market_depth(&ticker, Some(2)).await.unwrap();
+    assert_eq!(depth.asks, vec![level("10.15", 50), level("10.25", 100)]);
+    assert_eq!(depth.bids[0], level("9.95", 100));
+
+    let error = client
+        .market_depth(&unique_ticker(), None)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ClientError::Api {
+            status: StatusCode::NOT_FOUND,
+            ..
+        }
+    ));
+    let error = client.market_depth(&ticker, Some(0)).await.unwrap_err();
+    assert!(matches!(
+        error,
+        ClientError::Api {
+            status: StatusCode::BAD_REQUEST,
+            ..
+        }
+    ));
+}
@@ -0,0 +1,85 @@
+//! Difficulty presets deciding the trading costs of a portfolio.
+
+mod support;
+
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{Duration, Utc};
+use reqwest::{Method, StatusCode};
+use serde_json::{Value, json};
+use stock_exchange_sim_core::client::types::Difficulty;
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn realistic_orders_pay_fees_and_slippage_beginner_orders_do_not() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 50.0).await;
+
+    assert_eq!(
+        client.settings().await.unwrap().difficulty,
+        Difficulty::Realistic
+    );
+    let buy = client.buy(&ticker, 10).await.unwrap();
+    let mid = BigDecimal::from(50);
+    assert!(buy.price > mid);
+    // 0.1% commission on the order value
+    let value = &buy.price * BigDecimal::from(10);
+    assert_eq!(
+        buy.fee,
+        (&value / BigDecimal::from(1000)).with_scale_round(2, bigdecimal::RoundingMode::HalfUp)
+    );
+    let cash = BigDecimal::from(1000) - value - &buy.fee;
+    assert_eq!(client.balance().await.unwrap(), cash);
+
+    let settings = client.set_difficulty(Difficulty::Beginner).await.unwrap();
+    assert_eq!(settings.difficulty, Difficulty::Beginner);
+    let buy = client.buy(&ticker, 4).await.unwrap();
+    assert_eq!(buy.price, BigDecimal::from(50));
+    assert_eq!(buy.fee, BigDecimal::from(0));
+    let sell = client.sell(&ticker, 14).await.unwrap();
+    assert_eq!(sell.price, BigDecimal::from(50));
+    assert_eq!(sell.fee, BigDecimal::from(0));
+    let cash = cash - BigDecimal::from(200) + BigDecimal::from(700);
+    assert_eq!(client.balance().await.unwrap(), cash);
+}
+
+#[tokio::test]
+async fn competitions_impose_their_difficulty_on_entrants() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 20.0).await;
+
+    let now = Utc::now();
+    let response = app
+        .admin(Method::POST, "/admin/competitions")
+        .json(&json!({
+            "name": format!("Cup {}", uuid::Uuid::new_v4().simple()),
+            "starts_at": now - Duration::hours(1),
+            "ends_at": now + Duration::hours(1),
+            "starting_balance": 5000.0,
+            "difficulty": "beginner",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["difficulty"], "beginner");
+    let id = body["id"].as_i64().unwrap() as i32;
+
+    // The user's own setting stays realistic
+    let competition = client.join_competition(id).await.unwrap();
+    assert_eq!(competition.difficulty, Difficulty::Beginner);
+    let entrant = client
+        .clone()
+        .with_portfolio(competition.portfolio_id.unwrap());
+    let buy = entrant.buy(&ticker, 10).await.unwrap();
+    assert_eq!(buy.price, BigDecimal::from(20));
+    assert_eq!(buy.fee, BigDecimal::from(0));
+    assert_eq!(entrant.balance().await.unwrap(), BigDecimal::from(4800));
+
+    let buy = client.buy(&ticker, 10).await.unwrap();
+    assert!(buy.fee > BigDecimal::zero());
+}
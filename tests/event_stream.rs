@@ -0,0 +1,70 @@
+//! Domain events streamed to the message broker.
+
+mod support;
+
+use std::time::Duration;
+
+use serde_json::Value;
+use support::{TestApp, unique_ticker};
+
+/// Events published to the Redis stream `stream`, waiting until `done` holds
+async fn events(app: &TestApp, stream: &str, done: impl Fn(&[Value]) -> bool) -> Vec<Value> {
+    for _ in 0..50 {
+        let events: Vec<Value> = app
+            .stream_payloads(stream)
+            .await
+            .iter()
+            .map(|payload| serde_json::from_str(payload).unwrap())
+            .collect();
+        if done(&events) {
+            return events;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("events on {} did not arrive", stream);
+}
+
+#[tokio::test]
+async fn trades_are_streamed_to_redis() {
+    // A prefix of its own keeps the streams apart from other tests on a shared Redis
+    let prefix = format!("test-{}", uuid::Uuid::new_v4().simple());
+    let app =
+        TestApp::spawn_with_env(&[("EVENT_BROKER", "redis"), ("EVENT_SUBJECT_PREFIX", &prefix)])
+            .await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    let registered = events(&app, &format!("{}.user.registered", prefix), |events| {
+        !events.is_empty()
+    })
+    .await;
+    assert_eq!(registered[0]["type"], "user.registered");
+    assert_eq!(registered[0]["version"], 1);
+    let user_id = registered[0]["data"]["user_id"].as_i64().unwrap();
+
+    let bought = client.buy(&ticker, 2).await.unwrap();
+    // Refused orders are placed but never executed
+    client.sell(&ticker, 5).await.unwrap_err();
+
+    let placed = events(&app, &format!("{}.order.placed", prefix), |events| {
+        events.len() == 2
+    })
+    .await;
+    let executed = events(&app, &format!("{}.trade.executed", prefix), |events| {
+        !events.is_empty()
+    })
+    .await;
+    assert_eq!(executed.len(), 1);
+    let trade = &executed[0]["data"];
+    assert_eq!(trade["order_id"], placed[0]["data"]["order_id"]);
+    assert_eq!(trade["transaction_id"], bought.id.to_string());
+    assert_eq!(trade["user_id"], user_id);
+    assert_eq!(trade["ticker"], ticker.as_str());
+    assert_eq!(trade["side"], "buy");
+    assert_eq!(trade["quantity"], 2);
+    assert_eq!(placed[1]["data"]["side"], "sell");
+    assert_eq!(placed[1]["data"]["quantity"], 5);
+    assert!(executed[0]["occurred_at"].is_string());
+    assert_ne!(executed[0]["id"], placed[0]["id"]);
+}
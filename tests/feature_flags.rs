@@ -0,0 +1,101 @@
+//! Feature flags managed by admins.
+
+mod support;
+
+use reqwest::{Method, StatusCode};
+use serde_json::{Value, json};
+use support::TestApp;
+
+fn unique_flag() -> String {
+    format!("test_{}", uuid::Uuid::new_v4().simple())
+}
+
+#[tokio::test]
+async fn flags_roll_out_to_listed_users() {
+    let app = TestApp::spawn().await;
+    let tester = app.register_user().await;
+    let other = app.register_user().await;
+    let tester_id = tester.profile().await.unwrap().id;
+    let flag = unique_flag();
+
+    let response = app
+        .admin(Method::PUT, &format!("/admin/feature-flags/{}", flag))
+        .json(&json!({ "enabled": true, "rollout_percent": 0, "user_ids": [tester_id] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["user_ids"], json!([tester_id]));
+
+    assert_eq!(tester.features().await.unwrap().get(&flag), Some(&true));
+    assert_eq!(other.features().await.unwrap().get(&flag), Some(&false));
+    assert_eq!(
+        other.features().await.unwrap().get("margin_trading"),
+        Some(&true)
+    );
+
+    // Disabling switches the flag off even for listed users
+    app.admin(Method::PUT, &format!("/admin/feature-flags/{}", flag))
+        .json(&json!({ "enabled": false, "rollout_percent": 100, "user_ids": [tester_id] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(tester.features().await.unwrap().get(&flag), Some(&false));
+
+    app.admin(Method::PUT, &format!("/admin/feature-flags/{}", flag))
+        .json(&json!({ "enabled": true, "rollout_percent": 100 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(other.features().await.unwrap().get(&flag), Some(&true));
+
+    let response = app
+        .admin(Method::DELETE, &format!("/admin/feature-flags/{}", flag))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!tester.features().await.unwrap().contains_key(&flag));
+}
+
+#[tokio::test]
+async fn invalid_flags_are_rejected() {
+    let app = TestApp::spawn().await;
+
+    let response = app
+        .admin(Method::PUT, "/admin/feature-flags/Not-A-Flag")
+        .json(&json!({ "enabled": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .admin(
+            Method::PUT,
+            &format!("/admin/feature-flags/{}", unique_flag()),
+        )
+        .json(&json!({ "enabled": true, "rollout_percent": 101 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .admin(
+            Method::DELETE,
+            &format!("/admin/feature-flags/{}", unique_flag()),
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/admin/feature-flags", app.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
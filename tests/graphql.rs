@@ -0,0 +1,124 @@
+//! The GraphQL API next to the REST endpoints.
+
+mod support;
+
+use serde_json::{Value, json};
+use stock_exchange_sim_core::client::Client;
+use support::{TestApp, unique_ticker};
+
+/// Run `query` against `/graphql`, as the user of `client` when given
+async fn graphql(app: &TestApp, client: Option<&Client>, query: &str) -> Value {
+    let mut request = reqwest::Client::new()
+        .post(format!("{}/graphql", app.base_url))
+        .json(&json!({ "query": query }));
+    if let Some(token) = client.and_then(Client::token) {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.unwrap();
+    assert!(response.status().is_success());
+    response.json().await.unwrap()
+}
+
+#[tokio::test]
+async fn quotes_are_public() {
+    let app = TestApp::spawn().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 42.5).await;
+
+    let query = format!(
+        r#"{{ quote(ticker: "{}") {{ ticker price stale }} }}"#,
+        ticker
+    );
+    let body = graphql(&app, None, &query).await;
+    assert!(body.get("errors").is_none(), "{}", body);
+    assert_eq!(body["data"]["quote"]["ticker"], ticker.as_str());
+    assert_eq!(
+        body["data"]["quote"]["price"]
+            .as_str()
+            .unwrap()
+            .parse::<f64>()
+            .unwrap(),
+        42.5
+    );
+    assert_eq!(body["data"]["quote"]["stale"], false);
+    // Only the selected fields are returned
+    assert!(body["data"]["quote"].get("dayHigh").is_none());
+}
+
+#[tokio::test]
+async fn orders_show_up_in_the_portfolio() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    let mutation = format!(
+        r#"mutation {{ buy(ticker: "{}", quantity: 5) {{ id ticker quantity transactionType }} }}"#,
+        ticker
+    );
+    let body = graphql(&app, Some(&client), &mutation).await;
+    assert!(body.get("errors").is_none(), "{}", body);
+    assert_eq!(body["data"]["buy"]["quantity"], 5);
+    assert_eq!(body["data"]["buy"]["transactionType"], "buy");
+    let transaction_id = body["data"]["buy"]["id"].clone();
+
+    let query = r#"{
+        portfolio {
+            isDefault
+            holdings { ticker quantity }
+            transactions(first: 1) { transactions { id } nextCursor }
+        }
+    }"#;
+    let body = graphql(&app, Some(&client), query).await;
+    assert!(body.get("errors").is_none(), "{}", body);
+    let portfolio = &body["data"]["portfolio"];
+    assert_eq!(portfolio["isDefault"], true);
+    assert_eq!(
+        portfolio["holdings"],
+        json!([{ "ticker": ticker, "quantity": 5 }])
+    );
+    assert_eq!(
+        portfolio["transactions"]["transactions"][0]["id"],
+        transaction_id
+    );
+    assert_eq!(portfolio["transactions"]["nextCursor"], Value::Null);
+
+    let mutation = format!(
+        r#"mutation {{ sell(ticker: "{}", quantity: 2) {{ quantity realizedGain }} }}"#,
+        ticker
+    );
+    let body = graphql(&app, Some(&client), &mutation).await;
+    assert!(body.get("errors").is_none(), "{}", body);
+    assert!(body["data"]["sell"]["realizedGain"].is_string());
+}
+
+#[tokio::test]
+async fn portfolios_need_a_token_and_errors_carry_the_status() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+
+    let body = graphql(&app, None, "{ portfolio { cash } }").await;
+    assert_eq!(body["data"], Value::Null);
+    assert_eq!(body["errors"][0]["extensions"]["status"], 401);
+
+    // Another user's portfolio does not exist for this one
+    let other = app.register_user().await;
+    let id = graphql(&app, Some(&other), "{ portfolio { id } }").await["data"]["portfolio"]["id"]
+        .as_i64()
+        .unwrap();
+    let body = graphql(
+        &app,
+        Some(&client),
+        &format!("{{ portfolio(id: {}) {{ cash }} }}", id),
+    )
+    .await;
+    assert_eq!(body["errors"][0]["extensions"]["status"], 404);
+
+    let body = graphql(
+        &app,
+        Some(&client),
+        r#"mutation { buy(ticker: "", quantity: 1) { id } }"#,
+    )
+    .await;
+    assert!(body["errors"][0]["message"].is_string());
+}
@@ -0,0 +1,200 @@
+//! The gRPC trading API and the API keys authenticating it.
+
+mod support;
+
+use std::time::Duration;
+
+use bigdecimal::BigDecimal;
+use stock_exchange_sim_core::client::trading::{
+    CancelOrderRequest, GetPortfolioRequest, PlaceOrderRequest, Side, StreamOrderEventsRequest,
+    trading_client::TradingClient,
+};
+use support::{TestApp, free_port, unique_ticker};
+use tonic::{Code, Request, transport::Channel};
+
+/// Start a server with the trading API on a free port, and connect to it
+async fn spawn() -> (TestApp, TradingClient<Channel>) {
+    let addr = format!("127.0.0.1:{}", free_port());
+    let app = TestApp::spawn_with_env(&[("GRPC_LISTEN_ADDR", &addr)]).await;
+    let trading = TradingClient::connect(format!("http://{}", addr))
+        .await
+        .unwrap();
+    (app, trading)
+}
+
+/// `message` sent with `metadata` set to `value`
+fn with<T>(message: T, metadata: &'static str, value: &str) -> Request<T> {
+    let mut request = Request::new(message);
+    request
+        .metadata_mut()
+        .insert(metadata, value.parse().unwrap());
+    request
+}
+
+fn order(ticker: &str, side: Side, quantity: i32) -> PlaceOrderRequest {
+    PlaceOrderRequest {
+        ticker: ticker.to_string(),
+        side: side.into(),
+        quantity,
+        portfolio_id: None,
+    }
+}
+
+#[tokio::test]
+async fn api_keys_place_orders_and_read_the_portfolio() {
+    let (app, mut trading) = spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    let created = client.create_api_key("momentum bot").await.unwrap();
+    assert!(created.key.starts_with("sk_"));
+    let keys = client.api_keys().await.unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].name, "momentum bot");
+    assert!(keys[0].last_used_at.is_none());
+
+    let bought = trading
+        .place_order(with(
+            order(&ticker, Side::Buy, 5),
+            "x-api-key",
+            &created.key,
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(bought.side(), Side::Buy);
+    assert_eq!(bought.quantity, 5);
+    assert!(bought.realized_gain.is_none());
+    let sold = trading
+        .place_order(with(
+            order(&ticker, Side::Sell, 2),
+            "x-api-key",
+            &created.key,
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(sold.realized_gain.is_some());
+
+    // Bearer tokens work as well
+    let authorization = format!("Bearer {}", client.token().unwrap());
+    let portfolio = trading
+        .get_portfolio(with(
+            GetPortfolioRequest { portfolio_id: None },
+            "authorization",
+            &authorization,
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(portfolio.positions.len(), 1);
+    assert_eq!(portfolio.positions[0].ticker, ticker);
+    assert_eq!(portfolio.positions[0].quantity, 3);
+    assert_eq!(
+        portfolio.cash.parse::<BigDecimal>().unwrap(),
+        client.balance().await.unwrap()
+    );
+
+    // Orders are filled when placed
+    let status = trading
+        .cancel_order(with(
+            CancelOrderRequest {
+                order_id: bought.id.clone(),
+            },
+            "x-api-key",
+            &created.key,
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    assert!(client.api_keys().await.unwrap()[0].last_used_at.is_some());
+    client.delete_api_key(created.api_key.id).await.unwrap();
+    let status = trading
+        .get_portfolio(with(
+            GetPortfolioRequest { portfolio_id: None },
+            "x-api-key",
+            &created.key,
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn calls_need_credentials_and_map_api_errors() {
+    let (app, mut trading) = spawn().await;
+    let client = app.register_user().await;
+    let other = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+    let key = client.create_api_key("bot").await.unwrap().key;
+
+    let status = trading
+        .place_order(Request::new(order(&ticker, Side::Buy, 1)))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+    let status = trading
+        .place_order(with(order(&ticker, Side::Buy, 1), "x-api-key", "sk_wrong"))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+
+    let status = trading
+        .place_order(with(
+            order(&ticker, Side::Unspecified, 1),
+            "x-api-key",
+            &key,
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    let status = trading
+        .place_order(with(order(&ticker, Side::Sell, 1), "x-api-key", &key))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    // Another user's order is not found
+    let theirs = other.buy(&ticker, 1).await.unwrap();
+    let status = trading
+        .cancel_order(with(
+            CancelOrderRequest {
+                order_id: theirs.id.to_string(),
+            },
+            "x-api-key",
+            &key,
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn fills_are_streamed_from_any_client() {
+    let (app, mut trading) = spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+    let key = client.create_api_key("bot").await.unwrap().key;
+
+    let mut events = trading
+        .stream_order_events(with(StreamOrderEventsRequest {}, "x-api-key", &key))
+        .await
+        .unwrap()
+        .into_inner();
+    // The stream is registered once the call is answered
+    let bought = client.buy(&ticker, 4).await.unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(10), events.message())
+        .await
+        .expect("no order event")
+        .unwrap()
+        .unwrap();
+    assert_eq!(event.order_id, bought.id.to_string());
+    assert_eq!(event.ticker, ticker);
+    assert_eq!(event.side(), Side::Buy);
+    assert_eq!(event.quantity, 4);
+}
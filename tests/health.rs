@@ -0,0 +1,30 @@
+//! Health reporting of the supervised price feed.
+
+mod support;
+
+use std::time::Duration;
+
+use stock_exchange_sim_core::client::types::FeedStatus;
+use support::TestApp;
+
+#[tokio::test]
+async fn reports_unreachable_price_feed_as_degraded() {
+    let app = TestApp::spawn().await;
+    let client = app.client();
+
+    // No price feed runs during tests, so the updater keeps failing and restarting
+    let mut health = client.health().await.unwrap();
+    for _ in 0..50 {
+        if health.price_feed.restarts > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        health = client.health().await.unwrap();
+    }
+
+    assert_eq!(health.status, "degraded");
+    assert_ne!(health.price_feed.status, FeedStatus::Connected);
+    assert!(health.price_feed.restarts > 0);
+    assert!(health.price_feed.last_error.is_some());
+    assert!(health.price_feed.connected_since.is_none());
+}
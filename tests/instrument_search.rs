@@ -0,0 +1,55 @@
+//! Ticker search over the instrument catalog.
+
+mod support;
+
+use reqwest::StatusCode;
+use stock_exchange_sim_core::client::ClientError;
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn ranks_symbol_name_and_fuzzy_matches() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+
+    // Names share a random word so other tests' instruments never match
+    let word = unique_ticker().to_lowercase();
+    let exact = unique_ticker();
+    let by_name = unique_ticker();
+    let inactive = unique_ticker();
+    for (ticker, name, active) in [
+        (&exact, "Unrelated Group".to_string(), true),
+        (&by_name, format!("{}holdings Inc.", word), true),
+        (&inactive, format!("{} Delisted", word), false),
+    ] {
+        sqlx::query("INSERT INTO instruments (ticker, name, active) VALUES ($1, $2, $3)")
+            .bind(ticker)
+            .bind(name)
+            .bind(active)
+            .execute(&app.pg_pool)
+            .await
+            .unwrap();
+    }
+
+    // The exact symbol ranks above the name match
+    let results = client.search_instruments(&exact, None).await.unwrap();
+    assert_eq!(results[0].ticker, exact);
+
+    let results = client.search_instruments(&word, None).await.unwrap();
+    assert_eq!(results[0].ticker, by_name);
+    assert!(results.iter().all(|r| r.ticker != inactive));
+    assert_eq!(results[0].lot_size, 1);
+
+    // A typo still finds the name
+    let typo = format!("{}holdnigs", word);
+    let results = client.search_instruments(&typo, Some(5)).await.unwrap();
+    assert!(results.iter().any(|r| r.ticker == by_name));
+
+    let error = client.search_instruments(" ", None).await.unwrap_err();
+    assert!(matches!(
+        error,
+        ClientError::Api {
+            status: StatusCode::BAD_REQUEST,
+            ..
+        }
+    ));
+}
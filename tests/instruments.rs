@@ -0,0 +1,123 @@
+//! Instrument catalog administration and trade validation.
+
+mod support;
+
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use reqwest::{Method, StatusCode};
+use serde_json::{Value, json};
+use stock_exchange_sim_core::client::ClientError;
+use support::{TestApp, unique_ticker};
+
+fn is_status(error: ClientError, expected: StatusCode) -> bool {
+    matches!(error, ClientError::Api { status, .. } if status == expected)
+}
+
+#[tokio::test]
+async fn trades_only_listed_instruments() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+
+    let response = app
+        .admin(Method::POST, "/admin/instruments")
+        .json(&json!({
+            "ticker": ticker.to_lowercase(),
+            "name": "Test Corp",
+            "sector": "Technology",
+            "lot_size": 5
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let instrument: Value = response.json().await.unwrap();
+    assert_eq!(instrument["ticker"], ticker.as_str());
+    assert_eq!(instrument["asset_class"], "equity");
+    assert_eq!(instrument["active"], true);
+
+    let duplicate = app
+        .admin(Method::POST, "/admin/instruments")
+        .json(&json!({ "ticker": ticker, "name": "Again" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(duplicate.status(), StatusCode::CONFLICT);
+
+    app.set_price(&ticker, 10.0).await;
+    let error = client.buy(&ticker, 3).await.unwrap_err();
+    assert!(is_status(error, StatusCode::BAD_REQUEST));
+    client.buy(&ticker, 10).await.unwrap();
+
+    // Deactivated instruments can still be sold
+    let response = app
+        .admin(Method::PUT, &format!("/admin/instruments/{}", ticker))
+        .json(&json!({ "name": "Test Corp", "lot_size": 5, "active": false }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let error = client.buy(&ticker, 5).await.unwrap_err();
+    assert!(is_status(error, StatusCode::BAD_REQUEST));
+
+    let held = app
+        .admin(Method::DELETE, &format!("/admin/instruments/{}", ticker))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(held.status(), StatusCode::CONFLICT);
+
+    client.sell(&ticker, 10).await.unwrap();
+    let deleted = app
+        .admin(Method::DELETE, &format!("/admin/instruments/{}", ticker))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(deleted.status(), StatusCode::OK);
+
+    // A cached price alone does not make a ticker tradable
+    let error = client.sell(&ticker, 5).await.unwrap_err();
+    assert!(is_status(error, StatusCode::BAD_REQUEST));
+    let missing = app
+        .admin(Method::GET, &format!("/admin/instruments/{}", ticker))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn announces_catalog_changes_to_price_updaters() {
+    let app = TestApp::spawn().await;
+    let ticker = unique_ticker();
+    let mut pubsub = app.subscribe("instruments:updated").await;
+
+    let response = app
+        .admin(Method::POST, "/admin/instruments")
+        .json(&json!({ "ticker": ticker, "name": "Listed Corp" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let mut messages = pubsub.on_message();
+    tokio::time::timeout(Duration::from_secs(5), messages.next())
+        .await
+        .expect("listing was not announced")
+        .unwrap();
+    drop(messages);
+
+    let response = app
+        .admin(Method::DELETE, &format!("/admin/instruments/{}", ticker))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let mut messages = pubsub.on_message();
+    tokio::time::timeout(Duration::from_secs(5), messages.next())
+        .await
+        .expect("delisting was not announced")
+        .unwrap();
+}
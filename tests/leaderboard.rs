@@ -0,0 +1,57 @@
+//! Leaderboard of returns and opting out of it.
+
+mod support;
+
+use reqwest::StatusCode;
+use stock_exchange_sim_core::client::types::{LeaderboardPeriod, UpdateProfileRequest};
+use support::TestApp;
+
+#[tokio::test]
+async fn new_users_are_unranked_and_can_opt_out() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+
+    // Without snapshots there is no return to rank by
+    let leaderboard = client
+        .leaderboard(LeaderboardPeriod::All, Some(5))
+        .await
+        .unwrap();
+    assert_eq!(leaderboard.period, LeaderboardPeriod::All);
+    assert!(leaderboard.entries.len() <= 5);
+    assert!(leaderboard.you.is_none());
+    assert!(leaderboard.entries.iter().all(|entry| !entry.is_you));
+
+    assert!(!client.profile().await.unwrap().leaderboard_opt_out);
+    let profile = client
+        .update_profile(&UpdateProfileRequest {
+            leaderboard_opt_out: Some(true),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(profile.leaderboard_opt_out);
+    assert!(client.profile().await.unwrap().leaderboard_opt_out);
+}
+
+#[tokio::test]
+async fn invalid_leaderboard_queries_are_rejected() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+
+    for query in ["period=month", "limit=0", "limit=101"] {
+        let response = reqwest::Client::new()
+            .get(format!("{}/leaderboard?{}", app.base_url, query))
+            .bearer_auth(client.token().unwrap())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", query);
+    }
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/leaderboard", app.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
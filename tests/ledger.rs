@@ -0,0 +1,194 @@
+//! Append-only cash ledger of portfolios and their deposit and withdrawal
+//! history.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use stock_exchange_sim_core::client::ClientError;
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn every_cash_movement_is_a_ledger_entry() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 50.0).await;
+
+    client.deposit("500").await.unwrap();
+    client.buy(&ticker, 5).await.unwrap();
+    client.withdraw("100").await.unwrap();
+
+    let page = client.ledger(None, None).await.unwrap();
+    assert!(page.next_cursor.is_none());
+    let types: Vec<&str> = page
+        .entries
+        .iter()
+        .rev()
+        .map(|entry| entry.entry_type.as_str())
+        .filter(|entry_type| *entry_type != "fee")
+        .collect();
+    assert_eq!(types, ["opening", "deposit", "buy", "withdrawal"]);
+    let buy = &page.entries[page.entries.len() - 3];
+    assert_eq!(buy.amount, BigDecimal::from(-250));
+    assert!(buy.transaction_id.is_some());
+
+    // The balance is the sum of the entries and the last balance posted
+    let balance = client.portfolios().await.unwrap()[0].cash.clone();
+    let total: BigDecimal = page.entries.iter().map(|entry| &entry.amount).sum();
+    assert_eq!(total, balance);
+    assert_eq!(page.entries[0].balance_after, balance);
+
+    // Pages follow the cursor
+    let first = client.ledger(None, Some(2)).await.unwrap();
+    assert_eq!(first.entries.len(), 2);
+    let rest = client.ledger(first.next_cursor, None).await.unwrap();
+    assert_eq!(first.entries.len() + rest.entries.len(), page.entries.len());
+}
+
+#[tokio::test]
+async fn balances_only_change_through_the_ledger() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let main = client.portfolios().await.unwrap()[0].id;
+    let savings = client.create_portfolio("Savings").await.unwrap().id;
+
+    client.transfer(main, savings, 250.0).await.unwrap();
+    let sent = client.ledger(None, None).await.unwrap().entries[0].clone();
+    assert_eq!(sent.entry_type, "transfer");
+    assert_eq!(sent.amount, BigDecimal::from(-250));
+    assert_eq!(sent.counter_portfolio_id, Some(savings));
+    let received = client
+        .clone()
+        .with_portfolio(savings)
+        .ledger(None, None)
+        .await
+        .unwrap()
+        .entries[0]
+        .clone();
+    assert_eq!(received.amount, BigDecimal::from(250));
+    assert_eq!(received.counter_portfolio_id, Some(main));
+
+    let overwrite = sqlx::query("UPDATE portfolios SET balance = 1000000 WHERE id = $1")
+        .bind(main)
+        .execute(&app.pg_pool)
+        .await;
+    assert!(overwrite.is_err());
+    let rewrite = sqlx::query("UPDATE ledger_entries SET amount = 0 WHERE portfolio_id = $1")
+        .bind(main)
+        .execute(&app.pg_pool)
+        .await;
+    assert!(rewrite.is_err());
+    assert_eq!(client.balance().await.unwrap(), BigDecimal::from(750));
+}
+
+#[tokio::test]
+async fn deposits_and_withdrawals_are_kept_as_history() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+
+    let deposit = client.deposit("500").await.unwrap();
+    assert_eq!(deposit.movement_type, "deposit");
+    assert_eq!(deposit.amount, BigDecimal::from(500));
+    assert_eq!(deposit.balance_after, BigDecimal::from(1500));
+    let withdrawal = client.withdraw("200").await.unwrap();
+    assert_eq!(withdrawal.movement_type, "withdrawal");
+    assert_eq!(withdrawal.amount, BigDecimal::from(200));
+    assert_eq!(withdrawal.balance_after, BigDecimal::from(1300));
+
+    let history = client.cash_history(None, None).await.unwrap();
+    let ids: Vec<i32> = history
+        .movements
+        .iter()
+        .map(|movement| movement.id)
+        .collect();
+    assert_eq!(ids, [withdrawal.id, deposit.id]);
+    assert!(history.next_cursor.is_none());
+
+    let first = client.cash_history(None, Some(1)).await.unwrap();
+    assert_eq!(first.next_cursor, Some(withdrawal.id));
+    let rest = client.cash_history(first.next_cursor, None).await.unwrap();
+    assert_eq!(rest.movements.len(), 1);
+    assert_eq!(rest.movements[0].id, deposit.id);
+
+    // History belongs to the selected portfolio
+    let other = client.create_portfolio("Other").await.unwrap().id;
+    let other_history = client
+        .clone()
+        .with_portfolio(other)
+        .cash_history(None, None)
+        .await
+        .unwrap();
+    assert!(other_history.movements.is_empty());
+}
+
+#[tokio::test]
+async fn amounts_are_exact_decimal_strings() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let deposit = |amount: Value| {
+        reqwest::Client::new()
+            .post(format!("{}/api/v1/balance/deposit", app.base_url))
+            .bearer_auth(client.token().unwrap())
+            .json(&json!({ "amount": amount }))
+            .send()
+    };
+
+    let movement = client.deposit("0.10").await.unwrap();
+    assert_eq!(movement.amount, "0.10".parse::<BigDecimal>().unwrap());
+    client.deposit("0.20").await.unwrap();
+    assert_eq!(
+        client.balance().await.unwrap(),
+        "1000.30".parse::<BigDecimal>().unwrap()
+    );
+
+    for amount in ["0.001", "abc", "0", "-5", "1000000.01"] {
+        let response = deposit(json!(amount)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", amount);
+    }
+
+    // Numbers are still accepted, marked deprecated
+    let response = deposit(json!(12.5)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["deprecation"], "true");
+    let response = deposit(json!("12.50")).await.unwrap();
+    assert!(response.headers().get("deprecation").is_none());
+    assert_eq!(
+        client.balance().await.unwrap(),
+        "1025.30".parse::<BigDecimal>().unwrap()
+    );
+}
+
+#[tokio::test]
+async fn concurrent_debits_never_overdraw_the_cash() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+
+    let withdrawals = (0..8).map(|_| client.withdraw("300"));
+    let results = futures_util::future::join_all(withdrawals).await;
+    let made = results.iter().filter(|result| result.is_ok()).count();
+    for result in results {
+        if let Err(error) = result {
+            assert!(matches!(
+                error,
+                ClientError::Api {
+                    status: StatusCode::BAD_REQUEST,
+                    ..
+                }
+            ));
+        }
+    }
+    assert_eq!(made, 3);
+    assert_eq!(client.balance().await.unwrap(), BigDecimal::from(100));
+
+    // The database rejects overdrafts however they are posted
+    let portfolio = client.portfolios().await.unwrap()[0].id;
+    let overdraft = sqlx::query(
+        "INSERT INTO ledger_entries (portfolio_id, entry_type, amount) VALUES ($1, 'fee', -101)",
+    )
+    .bind(portfolio)
+    .execute(&app.pg_pool)
+    .await;
+    assert!(overdraft.is_err());
+}
@@ -0,0 +1,45 @@
+//! Serving the admin API on a listener of its own.
+
+mod support;
+
+use reqwest::StatusCode;
+use support::{ADMIN_KEY, TestApp, free_port};
+
+#[tokio::test]
+async fn admin_listener_takes_the_admin_api_off_the_public_one() {
+    let admin_addr = format!("127.0.0.1:{}", free_port());
+    let admin_url = format!("http://{}", admin_addr);
+    let app = TestApp::spawn_with_env(&[("ADMIN_LISTEN_ADDR", &admin_addr)]).await;
+    let http = reqwest::Client::new();
+
+    let response = app
+        .admin(reqwest::Method::GET, "/admin/stats")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = http
+        .get(format!("{}/admin/stats", admin_url))
+        .header("X-Admin-Key", ADMIN_KEY)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key("x-request-id"));
+
+    let response = http
+        .get(format!("{}/health", admin_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Only the admin API is served there
+    let response = http
+        .get(format!("{}/market/movers", admin_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,68 @@
+//! Secured loans against pledged holdings.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use reqwest::StatusCode;
+use stock_exchange_sim_core::client::{ClientError, types::Collateral};
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn borrow_against_holdings_and_repay() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+
+    app.set_price(&ticker, 50.0).await;
+    client.buy(&ticker, 10).await.unwrap();
+    let pledge = vec![Collateral {
+        ticker: ticker.clone(),
+        quantity: 8,
+    }];
+
+    // 8 shares at 50 support at most 200 at the default 50% loan-to-value
+    let too_much = client.borrow(250.0, pledge.clone()).await.unwrap_err();
+    assert!(matches!(
+        too_much,
+        ClientError::Api {
+            status: StatusCode::BAD_REQUEST,
+            ..
+        }
+    ));
+
+    let cash_before = client.portfolio().await.unwrap().cash;
+    let loan = client.borrow(150.0, pledge).await.unwrap();
+    assert_eq!(loan.status, "open");
+    assert_eq!(loan.principal, BigDecimal::from(150));
+    assert_eq!(loan.collateral.len(), 1);
+    assert_eq!(loan.ltv_percent, Some(37.5));
+
+    let portfolio = client.portfolio().await.unwrap();
+    assert_eq!(portfolio.cash, cash_before + BigDecimal::from(150));
+    assert_eq!(portfolio.loans, BigDecimal::from(150));
+
+    // Only the two unpledged shares can be sold
+    let sell = client.sell(&ticker, 3).await.unwrap_err();
+    assert!(matches!(
+        sell,
+        ClientError::Api {
+            status: StatusCode::BAD_REQUEST,
+            ..
+        }
+    ));
+    client.sell(&ticker, 2).await.unwrap();
+
+    let partial = client.repay_loan(loan.id, 50.0).await.unwrap();
+    assert_eq!(partial.status, "open");
+    assert_eq!(partial.outstanding, BigDecimal::from(100));
+
+    // Overpaying only charges what is owed and releases the collateral
+    let repaid = client.repay_loan(loan.id, 1000.0).await.unwrap();
+    assert_eq!(repaid.status, "repaid");
+    assert!(repaid.closed_at.is_some());
+    client.sell(&ticker, 8).await.unwrap();
+
+    let loans = client.loans().await.unwrap();
+    assert_eq!(loans.len(), 1);
+    assert_eq!(client.portfolio().await.unwrap().loans, BigDecimal::from(0));
+}
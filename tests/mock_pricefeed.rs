@@ -0,0 +1,118 @@
+//! The development price feed serving the `PriceFeed` proto.
+
+use std::{net::TcpListener, process::Stdio, time::Duration};
+
+use price_feed::{PriceRequest, price_feed_client::PriceFeedClient};
+use tokio::process::{Child, Command};
+use tonic::transport::Channel;
+
+mod price_feed {
+    tonic::include_proto!("pricefeed");
+}
+
+/// How long to wait for the feed to accept connections
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Start the feed with `env`, returning the process and a connected client
+async fn spawn_feed(env: &[(&str, &str)]) -> (Child, PriceFeedClient<Channel>) {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let feed = Command::new(env!("CARGO_BIN_EXE_mock-pricefeed"))
+        .env("MOCK_FEED_ADDR", format!("127.0.0.1:{}", port))
+        .envs(env.iter().copied())
+        .stdout(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+
+    let url = format!("http://127.0.0.1:{}", port);
+    let deadline = tokio::time::Instant::now() + STARTUP_TIMEOUT;
+    loop {
+        match PriceFeedClient::connect(url.clone()).await {
+            Ok(client) => return (feed, client),
+            Err(_) if tokio::time::Instant::now() < deadline => {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            Err(e) => panic!("mock price feed did not start: {}", e),
+        }
+    }
+}
+
+#[tokio::test]
+async fn serves_listed_and_requested_tickers() {
+    let (_feed, mut client) = spawn_feed(&[
+        ("MOCK_FEED_TICKERS", "AAPL=190.5,MSFT=420"),
+        ("MOCK_FEED_VOLATILITY", "0"),
+    ])
+    .await;
+
+    let quote = client
+        .get_price(PriceRequest {
+            ticker: "AAPL".into(),
+            tickers: Vec::new(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(quote.ticker, "AAPL");
+    assert_eq!(quote.price, 190.5);
+    assert!(quote.timestamp > 0);
+
+    // Tickers the feed was not configured with start at 100
+    let quote = client
+        .get_price(PriceRequest {
+            ticker: "NEWCO".into(),
+            tickers: Vec::new(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(quote.price, 100.0);
+}
+
+#[tokio::test]
+async fn streams_only_the_requested_tickers() {
+    let (_feed, mut client) = spawn_feed(&[
+        ("MOCK_FEED_TICKERS", "AAPL=190,MSFT=420,KO=60"),
+        ("MOCK_FEED_INTERVAL_MS", "20"),
+        ("MOCK_FEED_SEED", "7"),
+    ])
+    .await;
+
+    let mut stream = client
+        .stream_prices(PriceRequest {
+            ticker: "ALL".into(),
+            tickers: vec!["AAPL".into(), "KO".into()],
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    let mut updates = Vec::new();
+    while updates.len() < 10 {
+        let update = tokio::time::timeout(STARTUP_TIMEOUT, stream.message())
+            .await
+            .expect("no price within the timeout")
+            .unwrap()
+            .expect("stream ended");
+        updates.push(update);
+    }
+
+    // Current prices come first, then every step of the walk
+    assert_eq!(updates[0].ticker, "AAPL");
+    assert_eq!(updates[1].ticker, "KO");
+    assert!(
+        updates
+            .iter()
+            .all(|update| update.ticker == "AAPL" || update.ticker == "KO")
+    );
+    assert!(updates.iter().all(|update| update.price > 0.0));
+    let moved = updates
+        .iter()
+        .filter(|update| update.ticker == "AAPL")
+        .any(|update| update.price != 190.0);
+    assert!(moved);
+}
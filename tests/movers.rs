@@ -0,0 +1,82 @@
+//! Daily top movers and most traded tickers.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use chrono::{Days, Utc};
+use reqwest::StatusCode;
+use stock_exchange_sim_core::client::ClientError;
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn ranks_daily_gainers_losers_and_volume() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let gainer = unique_ticker();
+    let loser = unique_ticker();
+
+    // The feed is not running in tests, so store the daily candles it would have built
+    let today = Utc::now().date_naive();
+    let yesterday = today - Days::new(1);
+    for (ticker, previous_close, close) in [(&gainer, 10.0, 100.0), (&loser, 100.0, 1.0)] {
+        for (day, close) in [(yesterday, previous_close), (today, close)] {
+            sqlx::query(
+                "INSERT INTO price_candles (ticker, interval, bucket_start, open, high, low, close, tick_count) \
+                 VALUES ($1, '1d', $2, $3, $3, $3, $3, 1)",
+            )
+            .bind(ticker)
+            .bind(day.and_hms_opt(0, 0, 0).unwrap().and_utc())
+            .bind(BigDecimal::try_from(close).unwrap())
+            .execute(&app.pg_pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    app.set_price(&gainer, 100.0).await;
+    client.buy(&gainer, 4).await.unwrap();
+    client.sell(&gainer, 1).await.unwrap();
+
+    let movers = client.market_movers(Some(50)).await.unwrap();
+    assert_eq!(movers.date, today);
+
+    let up = movers
+        .gainers
+        .iter()
+        .find(|m| m.ticker == gainer)
+        .expect("gainer missing");
+    assert_eq!(up.previous_close, BigDecimal::from(10));
+    assert_eq!(up.change, BigDecimal::from(90));
+    assert_eq!(up.change_percent, BigDecimal::from(900));
+    assert!(movers.losers.iter().all(|m| m.ticker != gainer));
+
+    let down = movers
+        .losers
+        .iter()
+        .find(|m| m.ticker == loser)
+        .expect("loser missing");
+    assert_eq!(down.change, BigDecimal::from(-99));
+    assert_eq!(down.change_percent, BigDecimal::from(-99));
+
+    let traded = movers
+        .most_traded
+        .iter()
+        .find(|v| v.ticker == gainer)
+        .expect("traded ticker missing");
+    assert_eq!(traded.volume, 5);
+    assert_eq!(traded.trades, 2);
+
+    let movers = client.market_movers(Some(1)).await.unwrap();
+    assert!(movers.gainers.len() <= 1);
+    assert!(movers.losers.len() <= 1);
+    assert!(movers.most_traded.len() <= 1);
+
+    let error = client.market_movers(Some(0)).await.unwrap_err();
+    assert!(matches!(
+        error,
+        ClientError::Api {
+            status: StatusCode::BAD_REQUEST,
+            ..
+        }
+    ));
+}
@@ -0,0 +1,96 @@
+//! Simulated news events and their effect on synthetic prices.
+
+mod support;
+
+use chrono::{TimeDelta, Utc};
+use reqwest::{Method, StatusCode};
+use serde_json::{Value, json};
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn published_news_moves_synthetic_prices() {
+    let ticker = unique_ticker();
+    // Without drift or volatility only news moves the price
+    let app = TestApp::spawn_with_env(&[
+        ("PRICE_PROVIDER", "synthetic"),
+        ("PRICE_POLL_INTERVAL_SECS", "1"),
+        ("SYNTHETIC_DRIFT", "0"),
+        ("SYNTHETIC_VOLATILITY", "0"),
+    ])
+    .await;
+    app.set_price(&ticker, 40.0).await;
+
+    let response = app
+        .admin(Method::POST, "/admin/news")
+        .json(&json!({
+            "ticker": ticker,
+            "event_type": "earnings_beat",
+            "headline": "Quarterly earnings beat estimates",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let published: Value = response.json().await.unwrap();
+    assert_eq!(published["price_impact_percent"], 5.0);
+
+    let response = app
+        .admin(Method::POST, "/admin/news")
+        .json(&json!({
+            "ticker": ticker,
+            "event_type": "downgrade",
+            "headline": "Analysts downgrade to sell",
+            "publish_at": Utc::now() + TimeDelta::hours(1),
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let scheduled: Value = response.json().await.unwrap();
+
+    // Listing directly in the database is not announced, so force a resubscribe
+    let response = app
+        .admin(Method::POST, "/admin/instruments")
+        .json(&json!({ "ticker": unique_ticker(), "name": "Newsless Corp" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    app.wait_for_price(&ticker, |price| price == 42.0).await;
+
+    // Only published news is public
+    let client = app.register_user().await;
+    let news = client.market_news(Some(&ticker), None).await.unwrap();
+    assert_eq!(news.len(), 1);
+    assert_eq!(news[0].event_type, "earnings_beat");
+    assert_eq!(news[0].headline, "Quarterly earnings beat estimates");
+
+    let response = app
+        .admin(Method::DELETE, &format!("/admin/news/{}", published["id"]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = app
+        .admin(Method::DELETE, &format!("/admin/news/{}", scheduled["id"]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let all: Vec<Value> = app
+        .admin(Method::GET, "/admin/news")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let ours: Vec<&Value> = all
+        .iter()
+        .filter(|n| n["ticker"] == ticker.as_str())
+        .collect();
+    assert_eq!(ours.len(), 1);
+    assert!(!ours[0]["applied_at"].is_null());
+}
@@ -0,0 +1,42 @@
+//! OpenAPI document and Swagger UI.
+
+mod support;
+
+use stock_exchange_sim_core::schema::OPENAPI_JSON;
+use support::TestApp;
+
+#[tokio::test]
+async fn served_spec_matches_the_shipped_schema() {
+    let app = TestApp::spawn().await;
+
+    let response = reqwest::get(format!("{}/openapi.json", app.base_url))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let served: serde_json::Value = response.json().await.unwrap();
+    let shipped: serde_json::Value = serde_json::from_str(OPENAPI_JSON).unwrap();
+    assert_eq!(
+        served, shipped,
+        "api/openapi.json is stale; regenerate it with `cargo run -- openapi`"
+    );
+    assert!(served["paths"]["/api/v1/transactions/buy"]["post"].is_object());
+    assert!(served["paths"]["/transactions/buy"].is_null());
+    assert!(served["components"]["securitySchemes"]["bearerAuth"].is_object());
+}
+
+#[tokio::test]
+async fn swagger_ui_is_served_under_docs() {
+    let app = TestApp::spawn().await;
+
+    let response = reqwest::get(format!("{}/docs/", app.base_url))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let policy = response.headers()["content-security-policy"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(policy.contains("'unsafe-inline'"));
+    assert!(response.text().await.unwrap().contains("swagger-ui"));
+}
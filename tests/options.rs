@@ -0,0 +1,135 @@
+//! Option contracts listed by admins and traded at their model price.
+
+mod support;
+
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{Duration, Utc};
+use reqwest::{Method, StatusCode};
+use serde_json::{Value, json};
+use stock_exchange_sim_core::client::ClientError;
+use support::{TestApp, unique_ticker};
+
+/// List a contract on `ticker` expiring in 30 days, returning its id
+async fn list_contract(app: &TestApp, ticker: &str, kind: &str, strike: f64) -> i32 {
+    let response = app
+        .admin(Method::POST, "/admin/options")
+        .json(&json!({
+            "underlying": ticker,
+            "kind": kind,
+            "strike": strike,
+            "expires_at": Utc::now() + Duration::days(30),
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    body["id"].as_i64().unwrap() as i32
+}
+
+#[tokio::test]
+async fn buy_a_call_and_sell_it_back() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+    let contract_id = list_contract(&app, &ticker, "call", 10.0).await;
+
+    let chain = client.option_chain(&ticker).await.unwrap();
+    assert_eq!(chain.multiplier, 100);
+    assert_eq!(chain.contracts.len(), 1);
+    let price = chain.contracts[0].price.clone().unwrap();
+    let one = BigDecimal::from(1);
+    assert!(price > BigDecimal::zero() && price < one);
+    let delta = chain.contracts[0].delta.unwrap();
+    assert!(delta > 0.5 && delta < 0.6);
+
+    let bought = client.buy_option(contract_id, 2).await.unwrap();
+    assert_eq!(bought.trade_type, "buy");
+    assert_eq!(bought.price, price);
+    let cost = &price * BigDecimal::from(200) + &bought.fee;
+    let portfolio = client.portfolio().await.unwrap();
+    assert_eq!(portfolio.cash, BigDecimal::from(1000) - cost);
+    assert_eq!(portfolio.options, &price * BigDecimal::from(200));
+
+    let positions = client.option_positions().await.unwrap();
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].quantity, 2);
+    assert_eq!(positions[0].underlying, ticker);
+
+    // Selling more than is held would write contracts on top of closing
+    let error = client.sell_option(contract_id, 3).await.unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == 400));
+
+    let sold = client.sell_option(contract_id, 2).await.unwrap();
+    assert_eq!(sold.trade_type, "sell");
+    assert!(client.option_positions().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn writing_a_put_holds_margin_until_bought_back() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+    let contract_id = list_contract(&app, &ticker, "put", 10.0).await;
+
+    let written = client.sell_option(contract_id, 1).await.unwrap();
+    let positions = client.option_positions().await.unwrap();
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].quantity, -1);
+    // Premium plus 20% of the underlying, per share
+    let margin = (&written.price + BigDecimal::from(2)) * BigDecimal::from(100);
+    assert_eq!(positions[0].margin, margin.with_scale(2));
+
+    let premium = &written.price * BigDecimal::from(100);
+    let portfolio = client.portfolio().await.unwrap();
+    assert_eq!(
+        portfolio.cash,
+        BigDecimal::from(1000) + &premium - &written.fee - &margin
+    );
+    assert_eq!(portfolio.options, &margin - &premium);
+
+    client.buy_option(contract_id, 1).await.unwrap();
+    assert!(client.option_positions().await.unwrap().is_empty());
+    let portfolio = client.portfolio().await.unwrap();
+    assert_eq!(portfolio.options, BigDecimal::from(0));
+    let starting_cash = BigDecimal::from(1000);
+    assert!(portfolio.cash < starting_cash);
+}
+
+#[tokio::test]
+async fn admins_list_valid_contracts_once() {
+    let app = TestApp::spawn().await;
+    let ticker = unique_ticker();
+    app.list_instrument(&ticker).await;
+    let expires_at = Utc::now() + Duration::days(30);
+
+    let list = |kind: &str, expires_at| {
+        app.admin(Method::POST, "/admin/options")
+            .json(&json!({
+                "underlying": ticker,
+                "kind": kind,
+                "strike": 25.0,
+                "expires_at": expires_at,
+            }))
+            .send()
+    };
+    let response = list("call", expires_at).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let contract_id = response.json::<Value>().await.unwrap()["id"].clone();
+
+    let duplicate = list("call", expires_at).await.unwrap();
+    assert_eq!(duplicate.status(), StatusCode::CONFLICT);
+    let expired = list("put", Utc::now() - Duration::days(1)).await.unwrap();
+    assert_eq!(expired.status(), StatusCode::BAD_REQUEST);
+    let unknown_kind = list("straddle", expires_at).await.unwrap();
+    assert_eq!(unknown_kind.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .admin(Method::DELETE, &format!("/admin/options/{}", contract_id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
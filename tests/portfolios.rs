@@ -0,0 +1,87 @@
+//! Multiple portfolios per user.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use reqwest::StatusCode;
+use stock_exchange_sim_core::client::ClientError;
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn portfolios_keep_cash_and_positions_apart() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+
+    // Every account starts with a default portfolio holding the starting cash
+    let portfolios = client.portfolios().await.unwrap();
+    assert_eq!(portfolios.len(), 1);
+    let main = &portfolios[0];
+    assert!(main.is_default);
+    assert_eq!(main.cash, BigDecimal::from(1000));
+
+    let speculative = client.create_portfolio("Speculative").await.unwrap();
+    assert!(!speculative.is_default);
+    assert_eq!(speculative.cash, BigDecimal::from(0));
+    let duplicate = client.create_portfolio("speculative").await.unwrap_err();
+    assert!(matches!(
+        duplicate,
+        ClientError::Api {
+            status: StatusCode::CONFLICT,
+            ..
+        }
+    ));
+
+    client.transfer(main.id, speculative.id, 400.0).await.unwrap();
+    let speculative_client = client.clone().with_portfolio(speculative.id);
+    assert_eq!(client.balance().await.unwrap(), BigDecimal::from(600));
+    assert_eq!(speculative_client.balance().await.unwrap(), BigDecimal::from(400));
+
+    // Trades only touch the selected portfolio
+    app.set_price(&ticker, 50.0).await;
+    speculative_client.buy(&ticker, 5).await.unwrap();
+    assert!(client.holdings().await.unwrap().is_empty());
+    assert!(client.transactions().await.unwrap().is_empty());
+    let holdings = speculative_client.holdings().await.unwrap();
+    assert_eq!(holdings.len(), 1);
+    assert_eq!(holdings[0].quantity, 5);
+    assert_eq!(speculative_client.transactions().await.unwrap().len(), 1);
+
+    let sell = client.sell(&ticker, 1).await.unwrap_err();
+    assert!(matches!(
+        sell,
+        ClientError::Api {
+            status: StatusCode::BAD_REQUEST,
+            ..
+        }
+    ));
+
+    let portfolio = speculative_client.portfolio().await.unwrap();
+    assert_eq!(portfolio.positions.len(), 1);
+    assert_eq!(portfolio.money_market, BigDecimal::from(0));
+    let transferred = BigDecimal::from(400);
+    assert!(portfolio.cash < transferred);
+
+    let overdraw = client
+        .transfer(speculative.id, main.id, 1000.0)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        overdraw,
+        ClientError::Api {
+            status: StatusCode::BAD_REQUEST,
+            ..
+        }
+    ));
+
+    // Other users cannot select the portfolio
+    let other = app.register_user().await.with_portfolio(speculative.id);
+    let foreign = other.balance().await.unwrap_err();
+    assert!(matches!(
+        foreign,
+        ClientError::Api {
+            status: StatusCode::NOT_FOUND,
+            ..
+        }
+    ));
+}
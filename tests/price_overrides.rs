@@ -0,0 +1,109 @@
+//! Manual price overrides and candle backfills by admins.
+
+mod support;
+
+use std::time::Duration;
+
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDate, Utc};
+use futures_util::StreamExt;
+use reqwest::{Method, StatusCode};
+use serde_json::{Value, json};
+use stock_exchange_sim_core::client::types::{CandleInterval, CandleQuery};
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn overridden_prices_are_cached_and_published() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 100.0).await;
+    let mut pubsub = app.subscribe(&format!("prices:{}", ticker)).await;
+
+    let response = app
+        .admin(Method::POST, &format!("/admin/prices/{}", ticker))
+        .json(&json!({ "price": 123.45 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let message = tokio::time::timeout(Duration::from_secs(5), pubsub.on_message().next())
+        .await
+        .expect("override was not published")
+        .unwrap();
+    let message: Value = serde_json::from_str(&message.get_payload::<String>().unwrap()).unwrap();
+    assert_eq!(message["price"], 123.45);
+
+    let quote = client.quote(&ticker).await.unwrap();
+    assert_eq!(quote.price, BigDecimal::try_from(123.45).unwrap());
+
+    let response = app
+        .admin(Method::POST, &format!("/admin/prices/{}", unique_ticker()))
+        .json(&json!({ "price": 1.0 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn backfills_candles_from_csv() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.list_instrument(&ticker).await;
+
+    let csv = "timestamp,open,high,low,close\n\
+               2025-01-02,100,105,99,104\n\
+               2025-01-03T00:00:00Z,104,110,103,108.5\n";
+    let response = app
+        .admin(Method::POST, &format!("/admin/prices/{}/candles", ticker))
+        .header("Content-Type", "text/csv")
+        .body(csv)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["candles"], 2);
+
+    let from = NaiveDate::from_ymd_opt(2025, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+    let candles = client
+        .candles(
+            &ticker,
+            &CandleQuery {
+                interval: Some(CandleInterval::OneDay),
+                from: Some(from),
+                to: Some(from + chrono::Duration::days(10)),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(candles.len(), 2);
+    assert_eq!(candles[1].close, "108.5".parse::<BigDecimal>().unwrap());
+
+    // Rows are checked before anything is written
+    let tomorrow = (Utc::now() + chrono::Duration::days(1)).date_naive();
+    for csv in [
+        "open,high,low,close\n100,105,99,104\n".to_string(),
+        "timestamp,open,high,low,close\n2025-01-04T12:00:00Z,100,105,99,104\n".to_string(),
+        "timestamp,open,high,low,close\n2025-01-04,100,98,99,104\n".to_string(),
+        format!(
+            "timestamp,open,high,low,close\n{},100,105,99,104\n",
+            tomorrow
+        ),
+    ] {
+        let response = app
+            .admin(Method::POST, &format!("/admin/prices/{}/candles", ticker))
+            .body(csv.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", csv);
+    }
+}
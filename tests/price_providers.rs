@@ -0,0 +1,83 @@
+//! Price providers other than the gRPC feed.
+
+mod support;
+
+use axum::{Json, Router, extract::Path, http::StatusCode, routing::get};
+use serde_json::json;
+use stock_exchange_sim_core::client::types::FeedStatus;
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn polls_prices_from_a_rest_api() {
+    let ticker = unique_ticker();
+
+    // A market-data API that only knows our ticker, quoting prices as strings
+    let known = ticker.clone();
+    let api = Router::new().route(
+        "/quote/{symbol}",
+        get(move |Path(symbol): Path<String>| {
+            let known = known.clone();
+            async move {
+                if symbol == known {
+                    Ok(Json(json!({ "data": { "last": "42.5" } })))
+                } else {
+                    Err(StatusCode::NOT_FOUND)
+                }
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/quote/{{ticker}}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, api).await.unwrap() });
+
+    let app = TestApp::spawn_with_env(&[
+        ("PRICE_PROVIDER", "rest"),
+        ("PRICE_REST_URL", &url),
+        ("PRICE_REST_POINTER", "/data/last"),
+        ("PRICE_POLL_INTERVAL_SECS", "1"),
+    ])
+    .await;
+    app.list_instrument(&ticker).await;
+    // Listing directly in the database is not announced, so force a resubscribe
+    let response = app
+        .admin(reqwest::Method::POST, "/admin/instruments")
+        .json(&json!({ "ticker": unique_ticker(), "name": "Unquoted Corp" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    app.wait_for_price(&ticker, |price| price == 42.5).await;
+
+    let health = app.client().health().await.unwrap();
+    assert_eq!(health.status, "ok");
+    assert_eq!(health.price_feed.status, FeedStatus::Connected);
+}
+
+#[tokio::test]
+async fn generates_synthetic_prices_without_a_feed() {
+    let ticker = unique_ticker();
+    let app = TestApp::spawn_with_env(&[
+        ("PRICE_PROVIDER", "synthetic"),
+        ("PRICE_POLL_INTERVAL_SECS", "1"),
+        // A simulated day per second keeps moves visible at cent precision
+        ("SYNTHETIC_TIME_SCALE", "86400"),
+        ("SYNTHETIC_VOLATILITY", "0.5"),
+    ])
+    .await;
+    app.set_price(&ticker, 50.0).await;
+    let response = app
+        .admin(reqwest::Method::POST, "/admin/instruments")
+        .json(&json!({ "ticker": unique_ticker(), "name": "Synthetic Corp" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    // The walk starts from the cached price; a few daily moves stay well within half of it
+    let price = app.wait_for_price(&ticker, |price| price != 50.0).await;
+    assert!(price > 25.0 && price < 100.0, "unexpected price {}", price);
+
+    let health = app.client().health().await.unwrap();
+    assert_eq!(health.price_feed.status, FeedStatus::Connected);
+}
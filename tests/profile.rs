@@ -0,0 +1,105 @@
+//! The profile of the authenticated user.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use reqwest::StatusCode;
+use serde_json::json;
+use stock_exchange_sim_core::client::{
+    ClientError,
+    types::{CostBasisMethod, UpdateNotificationsRequest, UpdateProfileRequest},
+    ws::{AccountEvent, ServerMessage},
+};
+use support::{TestApp, TestSocket};
+
+#[tokio::test]
+async fn updates_only_the_given_profile_fields() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+
+    let profile = client.profile().await.unwrap();
+    assert_eq!(profile.role, "user");
+    assert_eq!(profile.display_name, None);
+    assert_eq!(profile.base_currency, "USD");
+    assert_eq!(profile.cost_basis_method, CostBasisMethod::Average);
+    assert!(profile.notifications.order_fills && profile.notifications.deposits);
+    assert_eq!(profile.ui_settings, json!({}));
+
+    let profile = client
+        .update_profile(&UpdateProfileRequest {
+            display_name: Some("  Trader Joe ".into()),
+            cost_basis_method: Some(CostBasisMethod::Fifo),
+            notifications: Some(UpdateNotificationsRequest {
+                margin_calls: Some(false),
+                ..Default::default()
+            }),
+            ui_settings: Some(json!({"theme": "dark", "watchlist": ["ACME"]})),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(profile.display_name.as_deref(), Some("Trader Joe"));
+    assert_eq!(profile.cost_basis_method, CostBasisMethod::Fifo);
+    assert!(!profile.notifications.margin_calls);
+    assert!(profile.notifications.order_fills && profile.notifications.deposits);
+    assert_eq!(profile.ui_settings["theme"], "dark");
+
+    // The cost-basis method is shared with the settings endpoint
+    let settings = client.settings().await.unwrap();
+    assert_eq!(settings.cost_basis_method, CostBasisMethod::Fifo);
+
+    let profile = client
+        .update_profile(&UpdateProfileRequest {
+            display_name: Some(String::new()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(profile.display_name, None);
+    assert!(!profile.notifications.margin_calls);
+
+    for invalid in [
+        UpdateProfileRequest::default(),
+        UpdateProfileRequest {
+            base_currency: Some("XYZ".into()),
+            ..Default::default()
+        },
+        UpdateProfileRequest {
+            ui_settings: Some(json!(["not", "an", "object"])),
+            ..Default::default()
+        },
+    ] {
+        let error = client.update_profile(&invalid).await.unwrap_err();
+        assert!(
+            matches!(error, ClientError::Api { status, .. } if status == StatusCode::BAD_REQUEST)
+        );
+    }
+}
+
+#[tokio::test]
+async fn disabled_notifications_are_not_pushed() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let mut socket = TestSocket::connect(&client).await;
+
+    let deposits = |enabled| UpdateProfileRequest {
+        notifications: Some(UpdateNotificationsRequest {
+            deposits: Some(enabled),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    client.update_profile(&deposits(false)).await.unwrap();
+    client.deposit("100").await.unwrap();
+    client.update_profile(&deposits(true)).await.unwrap();
+    client.deposit("200").await.unwrap();
+
+    // Only the deposit made with notifications on arrives
+    match socket.recv().await {
+        ServerMessage::Event {
+            event: AccountEvent::DepositSettled { amount, .. },
+            ..
+        } => assert_eq!(amount, BigDecimal::from(200)),
+        other => panic!("unexpected message {:?}", other),
+    }
+}
@@ -0,0 +1,116 @@
+//! Single and batch quotes from the cache and the stored candles.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use reqwest::StatusCode;
+use sqlx::PgPool;
+use stock_exchange_sim_core::client::ClientError;
+use support::{TestApp, unique_ticker};
+
+async fn insert_daily_candle(
+    pool: &PgPool,
+    ticker: &str,
+    day: DateTime<Utc>,
+    (high, low, close): (i32, i32, i32),
+) {
+    sqlx::query(
+        "INSERT INTO price_candles (ticker, interval, bucket_start, open, high, low, close) \
+         VALUES ($1, '1d', $2, $5, $3, $4, $5)",
+    )
+    .bind(ticker)
+    .bind(day)
+    .bind(BigDecimal::from(high))
+    .bind(BigDecimal::from(low))
+    .bind(BigDecimal::from(close))
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn quotes_from_cache_with_database_fallback() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let cached = unique_ticker();
+    let cold = unique_ticker();
+    let unknown = unique_ticker();
+
+    let today = Utc::now().duration_trunc(Duration::days(1)).unwrap();
+    let yesterday = today - Duration::days(1);
+    insert_daily_candle(&app.pg_pool, &cached, yesterday, (102, 97, 100)).await;
+    insert_daily_candle(&app.pg_pool, &cached, today, (110, 98, 104)).await;
+    insert_daily_candle(&app.pg_pool, &cold, yesterday, (52, 48, 50)).await;
+    insert_daily_candle(&app.pg_pool, &cold, today, (56, 49, 55)).await;
+
+    app.set_price(&cached, 105.0).await;
+    let quote = client.quote(&cached).await.unwrap();
+    assert_eq!(quote.source, "cache");
+    assert_eq!(quote.price, BigDecimal::from(105));
+    assert_eq!(quote.previous_close, Some(BigDecimal::from(100)));
+    assert_eq!(quote.change, Some(BigDecimal::from(5)));
+    assert_eq!(quote.change_percent, Some(BigDecimal::from(5)));
+    assert_eq!(quote.day_high, BigDecimal::from(110));
+    assert_eq!(quote.day_low, BigDecimal::from(98));
+
+    // Nothing cached: the latest daily candle stands in
+    let quote = client.quote(&cold).await.unwrap();
+    assert_eq!(quote.source, "database");
+    assert_eq!(quote.price, BigDecimal::from(55));
+    assert_eq!(quote.change, Some(BigDecimal::from(5)));
+    assert!(quote.timestamp.is_some());
+
+    let error = client.quote(&unknown).await.unwrap_err();
+    assert!(matches!(
+        error,
+        ClientError::Api {
+            status: StatusCode::NOT_FOUND,
+            ..
+        }
+    ));
+
+    let batch = client
+        .quotes(&[&cold, &unknown, &cached, &cold])
+        .await
+        .unwrap();
+    let tickers: Vec<&str> = batch.iter().map(|q| q.ticker.as_str()).collect();
+    assert_eq!(tickers, vec![cold.as_str(), cached.as_str()]);
+
+    let error = client.quotes(&[]).await.unwrap_err();
+    assert!(matches!(
+        error,
+        ClientError::Api {
+            status: StatusCode::BAD_REQUEST,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn refuses_trades_at_stale_prices() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+
+    app.set_price(&ticker, 20.0).await;
+    assert!(!client.quote(&ticker).await.unwrap().stale);
+
+    // The feed stalled ten minutes ago
+    app.set_price_time(&ticker, Utc::now() - Duration::minutes(10))
+        .await;
+    assert!(client.quote(&ticker).await.unwrap().stale);
+    let error = client.buy(&ticker, 1).await.unwrap_err();
+    assert!(matches!(
+        error,
+        ClientError::Api {
+            status: StatusCode::BAD_REQUEST,
+            ..
+        }
+    ));
+
+    // A fresh tick lifts the guard
+    app.set_price(&ticker, 21.0).await;
+    assert!(!client.quote(&ticker).await.unwrap().stale);
+    client.buy(&ticker, 1).await.unwrap();
+}
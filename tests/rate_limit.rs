@@ -0,0 +1,77 @@
+//! Request rate limits per client IP and per user.
+
+mod support;
+
+use reqwest::StatusCode;
+use support::TestApp;
+
+#[tokio::test]
+async fn limits_trades_more_strictly_than_other_requests() {
+    let app = TestApp::spawn_with_env(&[
+        ("RATE_LIMIT_PER_MINUTE", "1000"),
+        ("RATE_LIMIT_TRADES_PER_MINUTE", "3"),
+    ])
+    .await;
+    let client = app.register_user().await;
+    let token = client.token().unwrap().to_string();
+    let http = reqwest::Client::new();
+    let buy = || {
+        http.post(format!("{}/transactions/buy", app.base_url))
+            .bearer_auth(&token)
+            .json(&serde_json::json!({"ticker": "NOPE", "quantity": 1}))
+            .send()
+    };
+
+    for remaining in (0..3).rev() {
+        let response = buy().await.unwrap();
+        assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["ratelimit-limit"], "3");
+        assert_eq!(
+            response.headers()["ratelimit-remaining"],
+            remaining.to_string().as_str()
+        );
+    }
+
+    let response = buy().await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response.headers()["retry-after"]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=60).contains(&retry_after));
+
+    // Other endpoints only count against the global limit
+    let response = http
+        .get(format!("{}/balance", app.base_url))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["ratelimit-limit"], "1000");
+}
+
+#[tokio::test]
+async fn option_orders_count_against_the_trade_limit() {
+    let app = TestApp::spawn_with_env(&[
+        ("RATE_LIMIT_PER_MINUTE", "1000"),
+        ("RATE_LIMIT_TRADES_PER_MINUTE", "1"),
+    ])
+    .await;
+    let client = app.register_user().await;
+    let token = client.token().unwrap().to_string();
+    let http = app.http();
+    let buy = || {
+        http.post(format!("{}/options/buy", app.base_url))
+            .bearer_auth(&token)
+            .json(&serde_json::json!({"contract_id": 0, "quantity": 1}))
+            .send()
+    };
+
+    let response = buy().await.unwrap();
+    assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["ratelimit-limit"], "1");
+    let response = buy().await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
@@ -0,0 +1,66 @@
+//! Annual realized gains report.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use chrono::{Datelike, Utc};
+use stock_exchange_sim_core::client::types::{CostBasisMethod, HoldingPeriod};
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn classifies_realized_lots_by_holding_period() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    client
+        .set_cost_basis_method(CostBasisMethod::Fifo)
+        .await
+        .unwrap();
+
+    app.set_price(&ticker, 10.0).await;
+    let old = client.buy(&ticker, 2).await.unwrap();
+    client.buy(&ticker, 3).await.unwrap();
+
+    // Pretend the first lot was bought two years ago
+    sqlx::query(
+        r#"
+        UPDATE tax_lots SET acquired_at = acquired_at - INTERVAL '2 years'
+        WHERE transaction_id = (SELECT id FROM transactions WHERE public_id = $1)
+        "#,
+    )
+    .bind(old.id)
+    .execute(&app.pg_pool)
+    .await
+    .unwrap();
+
+    app.set_price(&ticker, 12.0).await;
+    let sell = client.sell(&ticker, 4).await.unwrap();
+
+    let report = client.realized_gains(None).await.unwrap();
+    assert_eq!(report.year, Utc::now().year());
+    assert_eq!(report.lots.len(), 2);
+    assert!(
+        report
+            .lots
+            .iter()
+            .all(|lot| lot.sell_transaction_id == sell.id)
+    );
+
+    let long_term = &report.lots[0];
+    assert_eq!(long_term.holding_period, HoldingPeriod::LongTerm);
+    assert_eq!(long_term.quantity, 2);
+    assert_eq!(long_term.cost_basis_method, CostBasisMethod::Fifo);
+    let short_term = &report.lots[1];
+    assert_eq!(short_term.holding_period, HoldingPeriod::ShortTerm);
+    assert_eq!(short_term.quantity, 2);
+
+    assert_eq!(report.long_term_gain, long_term.gain);
+    assert_eq!(report.short_term_gain, short_term.gain);
+    assert_eq!(report.unknown_term_gain, BigDecimal::from(0));
+    assert_eq!(report.total_gain, &report.proceeds - &report.cost_basis);
+    assert_eq!(Some(report.total_gain), sell.realized_gain);
+
+    let last_year = client.realized_gains(Some(report.year - 1)).await.unwrap();
+    assert!(last_year.lots.is_empty());
+    assert_eq!(last_year.total_gain, BigDecimal::from(0));
+}
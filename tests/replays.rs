@@ -0,0 +1,108 @@
+//! Historical tick datasets replayed through the price pipeline.
+
+mod support;
+
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn replays_publish_historical_ticks_in_order() {
+    let app = TestApp::spawn().await;
+    let ticker = unique_ticker();
+    app.list_instrument(&ticker).await;
+
+    let csv = format!(
+        "timestamp,ticker,price\n\
+         2010-05-06T14:45:02Z,{t},101.5\n\
+         2010-05-06T14:45:00Z,{t},100\n\
+         2010-05-06T14:45:01Z,{t},100.8\n",
+        t = ticker.to_lowercase()
+    );
+    let response = app
+        .admin(Method::POST, "/admin/replays?name=Flash%20crash&speed=10")
+        .header("Content-Type", "text/csv")
+        .body(csv)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["tick_count"], 3);
+    assert_eq!(body["data_from"], "2010-05-06T14:45:00Z");
+    let id = body["id"].as_i64().unwrap();
+
+    let response = app
+        .admin(Method::POST, &format!("/admin/replays/{}/start", id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "running");
+
+    // A running replay cannot be started again or deleted
+    let response = app
+        .admin(Method::POST, &format!("/admin/replays/{}/start", id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = app
+        .admin(Method::DELETE, &format!("/admin/replays/{}", id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // The last historical tick wins
+    assert_eq!(
+        app.wait_for_price(&ticker, |price| price == 101.5).await,
+        101.5
+    );
+
+    let response = app
+        .admin(Method::GET, "/admin/replays")
+        .send()
+        .await
+        .unwrap();
+    let replays: Vec<Value> = response.json().await.unwrap();
+    let replay = replays.iter().find(|r| r["id"] == id).unwrap();
+    assert_eq!(replay["status"], "finished");
+    assert_eq!(replay["position"], 3);
+
+    let response = app
+        .admin(Method::DELETE, &format!("/admin/replays/{}", id))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn replays_of_unlisted_tickers_are_rejected() {
+    let app = TestApp::spawn().await;
+
+    let csv = format!(
+        "timestamp,ticker,price\n2010-05-06T14:45:00Z,{},100\n",
+        unique_ticker()
+    );
+    let response = app
+        .admin(Method::POST, "/admin/replays?name=Unlisted&speed=1")
+        .header("Content-Type", "text/csv")
+        .body(csv)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .admin(Method::POST, "/admin/replays?name=Empty&speed=1")
+        .header("Content-Type", "text/csv")
+        .body("timestamp,ticker,price\n")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,52 @@
+//! Request IDs assigned to every request.
+
+mod support;
+
+use support::TestApp;
+
+#[tokio::test]
+async fn request_ids_are_echoed_or_generated() {
+    let app = TestApp::spawn().await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("{}/health", app.base_url))
+        .header("X-Request-Id", "trace-123")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["x-request-id"], "trace-123");
+
+    let response = client
+        .get(format!("{}/health", app.base_url))
+        .send()
+        .await
+        .unwrap();
+    let generated = response.headers()["x-request-id"].to_str().unwrap();
+    assert!(uuid::Uuid::parse_str(generated).is_ok());
+
+    let response = client
+        .get(format!("{}/health", app.base_url))
+        .header("X-Request-Id", "not a valid id")
+        .send()
+        .await
+        .unwrap();
+    assert_ne!(response.headers()["x-request-id"], "not a valid id");
+}
+
+#[tokio::test]
+async fn error_bodies_include_the_request_id() {
+    let app = TestApp::spawn().await;
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/balance", app.base_url))
+        .header("X-Request-Id", "failed-trade-42")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+    assert_eq!(response.headers()["x-request-id"], "failed-trade-42");
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["request_id"], "failed-trade-42");
+}
@@ -0,0 +1,63 @@
+//! Security headers and limits applied to every request.
+
+mod support;
+
+use support::TestApp;
+
+#[tokio::test]
+async fn every_response_carries_the_configured_security_headers() {
+    let app = TestApp::spawn_with_env(&[
+        ("HSTS_MAX_AGE_SECS", "31536000"),
+        ("CONTENT_SECURITY_POLICY", "default-src 'self'"),
+    ])
+    .await;
+
+    for path in ["/health", "/no-such-endpoint", "/balance"] {
+        let response = reqwest::get(format!("{}{}", app.base_url, path))
+            .await
+            .unwrap();
+        let headers = response.headers();
+        assert_eq!(headers["x-content-type-options"], "nosniff", "{}", path);
+        assert_eq!(headers["x-frame-options"], "DENY", "{}", path);
+        assert_eq!(
+            headers["strict-transport-security"], "max-age=31536000; includeSubDomains",
+            "{}",
+            path
+        );
+        assert_eq!(
+            headers["content-security-policy"], "default-src 'self'",
+            "{}",
+            path
+        );
+    }
+}
+
+#[tokio::test]
+async fn hsts_is_off_by_default() {
+    let app = TestApp::spawn().await;
+
+    let response = reqwest::get(format!("{}/health", app.base_url))
+        .await
+        .unwrap();
+    assert!(!response.headers().contains_key("strict-transport-security"));
+    assert!(response.headers().contains_key("content-security-policy"));
+}
+
+#[tokio::test]
+async fn oversized_bodies_are_rejected_with_a_json_error() {
+    let app = TestApp::spawn_with_env(&[("MAX_REQUEST_SIZE", "1024")]).await;
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/auth/register", app.base_url))
+        .json(&serde_json::json!({
+            "email": "big@example.com",
+            "password": "x".repeat(2048),
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 413);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "Request body too large");
+}
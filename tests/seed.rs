@@ -0,0 +1,72 @@
+//! Demo data from the `seed` subcommand.
+
+mod support;
+
+use chrono::{Duration, Utc};
+use stock_exchange_sim_core::client::types::{CandleInterval, CandleQuery};
+use support::TestApp;
+
+/// Password of every demo account
+const DEMO_PASSWORD: &str = "demo-trader-2025";
+
+#[tokio::test]
+async fn seeded_accounts_have_a_year_of_history() {
+    let app = TestApp::spawn_seeded().await;
+
+    let mut client = app.client();
+    client
+        .login("alice@demo.local", DEMO_PASSWORD)
+        .await
+        .unwrap();
+
+    let holdings = client.holdings().await.unwrap();
+    assert!(holdings.iter().any(|holding| holding.ticker == "SPY"));
+    assert!(
+        holdings
+            .iter()
+            .all(|holding| holding.current_price.is_some())
+    );
+
+    let transactions = client.transactions().await.unwrap();
+    let oldest = transactions.iter().map(|t| t.created_at).min().unwrap();
+    assert!(oldest < (Utc::now() - Duration::days(300)).naive_utc());
+
+    let history = client.portfolio_history(None, None).await.unwrap();
+    assert!(history.len() >= 360);
+    assert!(
+        history
+            .iter()
+            .any(|snapshot| snapshot.benchmark_price.is_some())
+    );
+
+    let candles = client
+        .candles(
+            "AAPL",
+            &CandleQuery {
+                interval: Some(CandleInterval::OneDay),
+                from: Some(Utc::now() - Duration::days(400)),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert!(candles.len() >= 365);
+}
+
+#[tokio::test]
+async fn seeded_accounts_trade_in_different_styles() {
+    let app = TestApp::spawn_seeded().await;
+
+    let mut trader = app.client();
+    trader.login("bob@demo.local", DEMO_PASSWORD).await.unwrap();
+    let trades = trader.transactions().await.unwrap();
+    assert!(trades.iter().any(|t| t.transaction_type == "sell"));
+
+    let mut newcomer = app.client();
+    newcomer
+        .login("dave@demo.local", DEMO_PASSWORD)
+        .await
+        .unwrap();
+    assert!(newcomer.transactions().await.unwrap().is_empty());
+    assert!(newcomer.holdings().await.unwrap().is_empty());
+}
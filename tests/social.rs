@@ -0,0 +1,119 @@
+//! Following traders, public portfolios and the trade feed.
+
+mod support;
+
+use reqwest::StatusCode;
+use stock_exchange_sim_core::client::{
+    ClientError,
+    types::{UpdatePrivacyRequest, UpdateProfileRequest},
+};
+use support::{TestApp, unique_ticker};
+use uuid::Uuid;
+
+fn assert_status<T: std::fmt::Debug>(result: Result<T, ClientError>, expected: StatusCode) {
+    match result {
+        Err(ClientError::Api { status, .. }) => assert_eq!(status, expected),
+        other => panic!("expected {}, got {:?}", expected, other),
+    }
+}
+
+#[tokio::test]
+async fn followers_see_public_trades_without_hidden_quantities() {
+    let app = TestApp::spawn().await;
+    let trader = app.register_user().await;
+    let follower = app.register_user().await;
+    let ticker = unique_ticker();
+    let trader_id = trader.profile().await.unwrap().id;
+    let follower_id = follower.profile().await.unwrap().id;
+
+    let profile = trader
+        .update_profile(&UpdateProfileRequest {
+            privacy: Some(UpdatePrivacyRequest {
+                trade_delay_minutes: Some(0),
+                hide_quantities: Some(true),
+            }),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(profile.privacy.trade_delay_minutes, 0);
+    assert!(profile.privacy.hide_quantities);
+
+    follower.follow(trader_id).await.unwrap();
+    follower.follow(trader_id).await.unwrap();
+    let followers = trader.followers(trader_id).await.unwrap();
+    assert_eq!(followers.len(), 1);
+    assert_eq!(followers[0].user_id, follower_id);
+    assert_eq!(follower.following(follower_id).await.unwrap().len(), 1);
+
+    // Trades in private portfolios stay out of the feed
+    app.set_price(&ticker, 20.0).await;
+    trader.buy(&ticker, 5).await.unwrap();
+    assert!(follower.feed(None, None).await.unwrap().trades.is_empty());
+
+    let portfolio_id = trader.portfolios().await.unwrap()[0].id;
+    assert!(
+        trader
+            .set_portfolio_public(portfolio_id, true)
+            .await
+            .unwrap()
+            .is_public
+    );
+    let feed = follower.feed(None, None).await.unwrap();
+    assert_eq!(feed.trades.len(), 1);
+    assert_eq!(feed.trades[0].ticker, ticker);
+    assert_eq!(feed.trades[0].quantity, None);
+    assert!(feed.next_cursor.is_none());
+
+    let public = follower.user(trader_id).await.unwrap();
+    assert!(public.you_follow);
+    assert_eq!(public.followers, 1);
+    assert_eq!(public.portfolios[0].id, portfolio_id);
+    let positions = follower
+        .public_portfolio(trader_id, portfolio_id)
+        .await
+        .unwrap()
+        .positions;
+    assert_eq!(positions.len(), 1);
+    assert_eq!(positions[0].quantity, None);
+
+    follower.unfollow(trader_id).await.unwrap();
+    assert!(follower.feed(None, None).await.unwrap().trades.is_empty());
+    assert_status(follower.unfollow(trader_id).await, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn private_portfolios_and_self_follows_are_rejected() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let other = app.register_user().await;
+    let id = client.profile().await.unwrap().id;
+    let portfolio_id = client.portfolios().await.unwrap()[0].id;
+
+    assert_status(client.follow(id).await, StatusCode::BAD_REQUEST);
+    assert_status(client.follow(Uuid::new_v4()).await, StatusCode::NOT_FOUND);
+    assert_status(
+        other.public_portfolio(id, portfolio_id).await,
+        StatusCode::NOT_FOUND,
+    );
+    assert_status(
+        other.set_portfolio_public(portfolio_id, true).await,
+        StatusCode::NOT_FOUND,
+    );
+
+    // Trades are held back for 15 minutes by default
+    assert_eq!(
+        client.profile().await.unwrap().privacy.trade_delay_minutes,
+        15
+    );
+    let invalid = client
+        .update_profile(&UpdateProfileRequest {
+            privacy: Some(UpdatePrivacyRequest {
+                trade_delay_minutes: Some(-1),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .await;
+    assert_status(invalid, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,41 @@
+//! System statistics for admins.
+
+mod support;
+
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn stats_count_users_sessions_and_trades() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 50.0).await;
+    client.buy(&ticker, 3).await.unwrap();
+    client.sell(&ticker, 1).await.unwrap();
+
+    let response = app.admin(Method::GET, "/admin/stats").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let stats: Value = response.json().await.unwrap();
+
+    assert!(stats["registered_users"].as_i64().unwrap() >= 1);
+    assert!(stats["active_sessions"].as_u64().unwrap() >= 1);
+    assert!(stats["trades_today"].as_i64().unwrap() >= 2);
+    assert_eq!(stats["open_orders"], 0);
+    assert!(stats["ws_connections"].is_u64());
+    let volume = stats["volume_by_ticker"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|volume| volume["ticker"] == ticker.as_str())
+        .expect("traded ticker missing from the stats");
+    assert_eq!(volume["volume"], 4);
+    assert_eq!(volume["trades"], 2);
+    assert!(stats["feed"]["status"].is_string());
+
+    let response = reqwest::get(format!("{}/admin/stats", app.base_url))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
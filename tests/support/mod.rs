@@ -0,0 +1,528 @@
+//! # Integration Test Support
+//!
+//! Boots the server binary against ephemeral PostgreSQL and Redis instances
+//! and hands out typed API clients for driving it end-to-end.
+//!
+//! Stores are started with testcontainers, so a running Docker daemon is
+//! required. Set `TEST_DATABASE_URL` and `TEST_REDIS_URL` to run against
+//! existing instances instead (e.g. CI service containers); tests only create
+//! uniquely named users and tickers, so the stores can be shared.
+
+#![allow(dead_code)]
+
+use std::{
+    net::{IpAddr, Ipv4Addr, TcpListener},
+    process::Stdio,
+    time::Duration,
+};
+
+use futures_util::{SinkExt, StreamExt};
+use redis::AsyncCommands;
+use sqlx::PgPool;
+use stock_exchange_sim_core::client::{
+    Client,
+    ws::{ClientMessage, MSGPACK_SUBPROTOCOL, ServerMessage},
+};
+use testcontainers_modules::{
+    postgres::Postgres,
+    redis::Redis,
+    testcontainers::{ContainerAsync, runners::AsyncRunner},
+};
+use tokio::{
+    net::TcpStream,
+    process::{Child, Command},
+};
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream,
+    tungstenite::{self, client::IntoClientRequest, http::HeaderValue},
+};
+
+/// Password used for every test account
+pub const PASSWORD: &str = "integration-password";
+
+/// Key accepted by the admin endpoints
+pub const ADMIN_KEY: &str = "integration-admin-key";
+
+/// How long to wait for the server to answer `/health`
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long to wait for a WebSocket message
+const WS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A running server instance with its backing stores
+///
+/// The server process and containers are torn down when the value is dropped.
+pub struct TestApp {
+    pub base_url: String,
+    /// Loopback address the clients of this instance connect from, so the
+    /// failed logins of one test never count against another
+    pub client_ip: IpAddr,
+    pub pg_pool: PgPool,
+    redis: redis::Client,
+    _server: Child,
+    _postgres: Option<ContainerAsync<Postgres>>,
+    _redis: Option<ContainerAsync<Redis>>,
+}
+
+impl TestApp {
+    /// Start the stores and the server on a random port, waiting until it is healthy
+    pub async fn spawn() -> TestApp {
+        TestApp::spawn_with_env(&[]).await
+    }
+
+    /// Like [`TestApp::spawn`], with extra environment variables for the server
+    pub async fn spawn_with_env(env: &[(&str, &str)]) -> TestApp {
+        let (database_url, postgres) = match std::env::var("TEST_DATABASE_URL") {
+            Ok(url) => (url, None),
+            Err(_) => start_postgres().await,
+        };
+        let (redis_url, redis) = match std::env::var("TEST_REDIS_URL") {
+            Ok(url) => (url, None),
+            Err(_) => start_redis().await,
+        };
+
+        TestApp::boot(database_url, redis_url, env, postgres, redis).await
+    }
+
+    /// Like [`TestApp::spawn`], serving history reads through a second pool
+    /// with the test database standing in for the replica
+    pub async fn spawn_with_read_replica() -> TestApp {
+        let (database_url, postgres) = match std::env::var("TEST_DATABASE_URL") {
+            Ok(url) => (url, None),
+            Err(_) => start_postgres().await,
+        };
+        let (redis_url, redis) = match std::env::var("TEST_REDIS_URL") {
+            Ok(url) => (url, None),
+            Err(_) => start_redis().await,
+        };
+
+        let read_url = database_url.clone();
+        let env = [("DATABASE_READ_URL", read_url.as_str())];
+        TestApp::boot(database_url, redis_url, &env, postgres, redis).await
+    }
+
+    /// Like [`TestApp::spawn`], on fresh stores filled by the `seed` subcommand
+    ///
+    /// Always starts its own containers, since seeding refuses databases that
+    /// already have users.
+    pub async fn spawn_seeded() -> TestApp {
+        let (database_url, postgres) = start_postgres().await;
+        let (redis_url, redis) = start_redis().await;
+
+        let status = Command::new(env!("CARGO_BIN_EXE_stock-exchange-sim-core"))
+            .arg("seed")
+            .env("DATABASE_URL", &database_url)
+            .env("REDIS_URL", &redis_url)
+            .env(
+                "JWT_SECRET",
+                "integration-test-secret-at-least-32-characters",
+            )
+            .env("LOG_LEVEL", "warn")
+            .stdout(Stdio::null())
+            .status()
+            .await
+            .expect("failed to run the seed subcommand");
+        assert!(status.success(), "seeding failed: {}", status);
+
+        TestApp::boot(database_url, redis_url, &[], postgres, redis).await
+    }
+
+    /// Start the server against the given stores, waiting until it is healthy
+    async fn boot(
+        database_url: String,
+        redis_url: String,
+        env: &[(&str, &str)],
+        postgres: Option<ContainerAsync<Postgres>>,
+        redis_container: Option<ContainerAsync<Redis>>,
+    ) -> TestApp {
+        let port = free_port();
+        let server = Command::new(env!("CARGO_BIN_EXE_stock-exchange-sim-core"))
+            .env("DATABASE_URL", &database_url)
+            .env("REDIS_URL", &redis_url)
+            // No price feed runs during tests; prices are written to Redis directly
+            .env("GRPC_SERVER_URL", "http://127.0.0.1:1")
+            .env("JWT_SECRET", "integration-test-secret-at-least-32-characters")
+            .env("ADMIN_API_KEY", ADMIN_KEY)
+            .env("SERVER_PORT", port.to_string())
+            .env("LOG_LEVEL", "warn")
+            .env("RATE_LIMIT_PER_MINUTE", "0")
+            .envs(env.iter().copied())
+            .stdout(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .expect("failed to start server binary");
+
+        let base_url = format!("http://127.0.0.1:{}", port);
+        wait_until_healthy(&base_url).await;
+
+        let pg_pool = PgPool::connect(&database_url)
+            .await
+            .expect("failed to connect to test database");
+        let redis = redis::Client::open(redis_url).expect("invalid redis url");
+
+        // Any address of 127.0.0.0/8 reaches the server, which listens on 127.0.0.1
+        let id = uuid::Uuid::new_v4();
+        let [a, b, c, ..] = *id.as_bytes();
+        let client_ip = IpAddr::V4(Ipv4Addr::new(127, a.max(1), b, c));
+
+        TestApp {
+            base_url,
+            client_ip,
+            pg_pool,
+            redis,
+            _server: server,
+            _postgres: postgres,
+            _redis: redis_container,
+        }
+    }
+
+    /// An HTTP client connecting from this instance's client IP
+    pub fn http(&self) -> reqwest::Client {
+        reqwest::Client::builder()
+            .local_address(self.client_ip)
+            .build()
+            .expect("failed to build HTTP client")
+    }
+
+    /// An unauthenticated client for this instance
+    pub fn client(&self) -> Client {
+        Client::with_http_client(self.http(), &self.base_url)
+    }
+
+    /// Register a fresh user and return a client logged in as them
+    pub async fn register_user(&self) -> Client {
+        let email = format!("user-{}@example.com", uuid::Uuid::new_v4());
+        let mut client = self.client();
+        client
+            .register(&email, PASSWORD)
+            .await
+            .expect("registration failed");
+        client.login(&email, PASSWORD).await.expect("login failed");
+        client
+    }
+
+    /// A request to an admin endpoint carrying the admin key
+    pub fn admin(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.http()
+            .request(method, format!("{}{}", self.base_url, path))
+            .header("X-Admin-Key", ADMIN_KEY)
+    }
+
+    /// A Redis pub/sub connection listening on `channel`
+    pub async fn subscribe(&self, channel: &str) -> redis::aio::PubSub {
+        let mut pubsub = self
+            .redis
+            .get_async_pubsub()
+            .await
+            .expect("failed to connect to redis");
+        pubsub
+            .subscribe(channel)
+            .await
+            .expect("failed to subscribe");
+        pubsub
+    }
+
+    /// The `payload` fields of the entries of the Redis stream `key`, oldest first
+    pub async fn stream_payloads(&self, key: &str) -> Vec<String> {
+        let mut conn = self
+            .redis
+            .get_multiplexed_async_connection()
+            .await
+            .expect("failed to connect to redis");
+        let entries: Vec<(String, Vec<(String, String)>)> = redis::cmd("XRANGE")
+            .arg(key)
+            .arg("-")
+            .arg("+")
+            .query_async(&mut conn)
+            .await
+            .expect("failed to read stream");
+        entries
+            .into_iter()
+            .flat_map(|(_, fields)| fields)
+            .filter(|(field, _)| field == "payload")
+            .map(|(_, payload)| payload)
+            .collect()
+    }
+
+    /// The price cached for `ticker`, if any
+    pub async fn price(&self, ticker: &str) -> Option<f64> {
+        let mut conn = self
+            .redis
+            .get_multiplexed_async_connection()
+            .await
+            .expect("failed to connect to redis");
+        conn.get(ticker).await.expect("failed to get price")
+    }
+
+    /// Poll until the cached price of `ticker` satisfies `done`
+    pub async fn wait_for_price(&self, ticker: &str, done: impl Fn(f64) -> bool) -> f64 {
+        for _ in 0..100 {
+            if let Some(price) = self.price(ticker).await.filter(|price| done(*price)) {
+                return price;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("price of {} was not updated", ticker);
+    }
+
+    /// List `ticker` in the instrument catalog with default details
+    pub async fn list_instrument(&self, ticker: &str) {
+        sqlx::query(
+            "INSERT INTO instruments (ticker, name) VALUES ($1, $1) ON CONFLICT DO NOTHING",
+        )
+        .bind(ticker)
+        .execute(&self.pg_pool)
+        .await
+        .expect("failed to list instrument");
+    }
+
+    /// Publish a price for `ticker` the way the price feed does, listing the
+    /// ticker first so it can be traded
+    pub async fn set_price(&self, ticker: &str, price: f64) {
+        self.list_instrument(ticker).await;
+        self.set_price_time(ticker, chrono::Utc::now()).await;
+        let mut conn = self
+            .redis
+            .get_multiplexed_async_connection()
+            .await
+            .expect("failed to connect to redis");
+        conn.set::<_, _, ()>(ticker, price.to_string())
+            .await
+            .expect("failed to set price");
+        self.publish_price(ticker, price, chrono::Utc::now()).await;
+    }
+
+    /// Notify price subscribers of a tick at `at` without caching the price
+    pub async fn publish_price(&self, ticker: &str, price: f64, at: chrono::DateTime<chrono::Utc>) {
+        let mut conn = self
+            .redis
+            .get_multiplexed_async_connection()
+            .await
+            .expect("failed to connect to redis");
+        let message = serde_json::json!({
+            "ticker": ticker,
+            "price": price,
+            "timestamp": at,
+        });
+        conn.publish::<_, _, ()>(format!("prices:{}", ticker), message.to_string())
+            .await
+            .expect("failed to publish price");
+    }
+
+    /// Forget the cached price of `ticker`, as if it had never been published
+    pub async fn clear_price(&self, ticker: &str) {
+        let mut conn = self
+            .redis
+            .get_multiplexed_async_connection()
+            .await
+            .expect("failed to connect to redis");
+        conn.del::<_, ()>(&[ticker.to_string(), format!("price_time:{}", ticker)])
+            .await
+            .expect("failed to clear price");
+    }
+
+    /// Backdate or refresh when the price of `ticker` was last published
+    pub async fn set_price_time(&self, ticker: &str, at: chrono::DateTime<chrono::Utc>) {
+        let mut conn = self
+            .redis
+            .get_multiplexed_async_connection()
+            .await
+            .expect("failed to connect to redis");
+        conn.set::<_, _, ()>(format!("price_time:{}", ticker), at.timestamp_millis())
+            .await
+            .expect("failed to set price time");
+    }
+}
+
+/// A WebSocket connection to `/ws`
+pub struct TestSocket {
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    /// Whether the server agreed to MessagePack frames
+    pub msgpack: bool,
+    /// The welcome frame the server greeted with
+    pub welcome: ServerMessage,
+}
+
+impl TestSocket {
+    /// Connect as the user `client` is logged in as, skipping the welcome frame
+    pub async fn connect(client: &Client) -> Self {
+        TestSocket::open(client.ws_url(), client, None).await
+    }
+
+    /// Like [`TestSocket::connect`], resuming the session of an earlier connection
+    pub async fn resume(client: &Client, session: &str) -> Self {
+        TestSocket::open(client.ws_resume_url(session), client, None).await
+    }
+
+    /// The session ID announced in the welcome frame
+    pub fn session(&self) -> &str {
+        match &self.welcome {
+            ServerMessage::Welcome { session, .. } => session,
+            other => panic!("unexpected greeting {:?}", other),
+        }
+    }
+
+    /// Like [`TestSocket::connect`], offering the MessagePack subprotocol
+    pub async fn connect_msgpack(client: &Client) -> Self {
+        TestSocket::open(client.ws_url(), client, Some(MSGPACK_SUBPROTOCOL)).await
+    }
+
+    async fn open(url: String, client: &Client, subprotocol: Option<&str>) -> Self {
+        let mut request = url.into_client_request().unwrap();
+        let headers = request.headers_mut();
+        headers.insert(
+            "Authorization",
+            HeaderValue::from_str(&format!("Bearer {}", client.token().unwrap())).unwrap(),
+        );
+        if let Some(subprotocol) = subprotocol {
+            headers.insert(
+                "Sec-WebSocket-Protocol",
+                HeaderValue::from_str(subprotocol).unwrap(),
+            );
+        }
+        let (socket, response) = tokio_tungstenite::connect_async(request)
+            .await
+            .expect("failed to connect to the websocket");
+        let msgpack = response
+            .headers()
+            .get("Sec-WebSocket-Protocol")
+            .is_some_and(|protocol| protocol == MSGPACK_SUBPROTOCOL);
+
+        let mut socket = TestSocket {
+            socket,
+            msgpack,
+            welcome: ServerMessage::Welcome {
+                version: 0,
+                session: String::new(),
+                resumed: false,
+            },
+        };
+        socket.welcome = socket.recv().await;
+        socket
+    }
+
+    /// Send a command in the negotiated encoding
+    pub async fn send(&mut self, message: ClientMessage) {
+        if self.msgpack {
+            self.socket
+                .send(tungstenite::Message::binary(message.to_msgpack()))
+                .await
+                .expect("failed to send websocket message");
+        } else {
+            self.send_text(&message.to_text()).await;
+        }
+    }
+
+    /// Send a raw text frame
+    pub async fn send_text(&mut self, text: &str) {
+        self.socket
+            .send(tungstenite::Message::text(text))
+            .await
+            .expect("failed to send websocket message");
+    }
+
+    /// The next message from the server, panicking if it is not in the
+    /// negotiated encoding
+    pub async fn recv(&mut self) -> ServerMessage {
+        tokio::time::timeout(WS_TIMEOUT, async {
+            loop {
+                match self.socket.next().await {
+                    Some(Ok(tungstenite::Message::Text(text))) => {
+                        assert!(!self.msgpack, "JSON frame on a MessagePack connection");
+                        return ServerMessage::parse(&text).expect("invalid server message");
+                    }
+                    Some(Ok(tungstenite::Message::Binary(bytes))) => {
+                        assert!(self.msgpack, "binary frame on a JSON connection");
+                        return ServerMessage::from_msgpack(&bytes)
+                            .expect("invalid server message");
+                    }
+                    Some(Ok(_)) => continue,
+                    other => panic!("websocket closed: {:?}", other),
+                }
+            }
+        })
+        .await
+        .expect("no websocket message received")
+    }
+
+    /// Read frames for `wait`, answering pings, and count the pings received
+    ///
+    /// Returns `None` once the server has closed the connection.
+    pub async fn listen(&mut self, wait: Duration) -> Option<usize> {
+        let deadline = tokio::time::Instant::now() + wait;
+        let mut pings = 0;
+        loop {
+            match tokio::time::timeout_at(deadline, self.socket.next()).await {
+                Err(_) => return Some(pings),
+                Ok(Some(Ok(tungstenite::Message::Ping(_)))) => pings += 1,
+                Ok(Some(Ok(tungstenite::Message::Close(_)))) | Ok(Some(Err(_))) | Ok(None) => {
+                    return None;
+                }
+                Ok(Some(Ok(_))) => {}
+            }
+        }
+    }
+}
+
+/// A ticker no other test uses, so tests can share stores
+pub fn unique_ticker() -> String {
+    let id = uuid::Uuid::new_v4().simple().to_string().to_uppercase();
+    format!("T{}", &id[..6])
+}
+
+/// A PostgreSQL container with its connection URL
+async fn start_postgres() -> (String, Option<ContainerAsync<Postgres>>) {
+    let container = Postgres::default()
+        .start()
+        .await
+        .expect("failed to start postgres container");
+    let url = format!(
+        "postgres://postgres:postgres@{}:{}/postgres",
+        container.get_host().await.expect("postgres host"),
+        container
+            .get_host_port_ipv4(5432)
+            .await
+            .expect("postgres port"),
+    );
+    (url, Some(container))
+}
+
+/// A Redis container with its connection URL
+async fn start_redis() -> (String, Option<ContainerAsync<Redis>>) {
+    let container = Redis::default()
+        .start()
+        .await
+        .expect("failed to start redis container");
+    let url = format!(
+        "redis://{}:{}",
+        container.get_host().await.expect("redis host"),
+        container
+            .get_host_port_ipv4(6379)
+            .await
+            .expect("redis port"),
+    );
+    (url, Some(container))
+}
+
+pub fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .expect("no free port available")
+}
+
+async fn wait_until_healthy(base_url: &str) {
+    let http = reqwest::Client::new();
+    let deadline = tokio::time::Instant::now() + STARTUP_TIMEOUT;
+
+    while tokio::time::Instant::now() < deadline {
+        if let Ok(response) = http.get(format!("{}/health", base_url)).send().await {
+            if response.status().is_success() {
+                return;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+
+    panic!("server did not become healthy within {:?}", STARTUP_TIMEOUT);
+}
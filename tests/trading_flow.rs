@@ -0,0 +1,289 @@
+//! End-to-end trading flows against a real server, PostgreSQL and Redis.
+
+mod support;
+
+use bigdecimal::{BigDecimal, Zero};
+use reqwest::StatusCode;
+use stock_exchange_sim_core::client::{ClientError, types::CostBasisMethod};
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn register_deposit_buy_sell_and_value_portfolio() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+
+    // New accounts start with 1000 in cash
+    assert_eq!(client.balance().await.unwrap(), BigDecimal::from(1000));
+    client.deposit("500").await.unwrap();
+    assert_eq!(client.balance().await.unwrap(), BigDecimal::from(1500));
+
+    app.set_price(&ticker, 100.0).await;
+    let buy = client.buy(&ticker, 10).await.unwrap();
+    assert_eq!(buy.transaction_type, "buy");
+    assert_eq!(buy.quantity, 10);
+    // Market buys pay the spread, so they never execute below the mid price
+    let mid = BigDecimal::from(100);
+    assert!(buy.price >= mid);
+
+    let holdings = client.holdings().await.unwrap();
+    assert_eq!(holdings.len(), 1);
+    assert_eq!(holdings[0].ticker, ticker);
+    assert_eq!(holdings[0].quantity, 10);
+    assert_eq!(holdings[0].current_price, Some(BigDecimal::from(100)));
+    assert_eq!(
+        holdings[0].market_value,
+        BigDecimal::from(100) * BigDecimal::from(10)
+    );
+    assert_eq!(
+        holdings[0].unrealized_pnl,
+        &holdings[0].market_value - &holdings[0].average_price * BigDecimal::from(10)
+    );
+    assert_eq!(holdings[0].percent_of_portfolio, BigDecimal::from(100));
+
+    app.set_price(&ticker, 120.0).await;
+    let sell = client.sell(&ticker, 4).await.unwrap();
+    assert_eq!(sell.transaction_type, "sell");
+    let mid = BigDecimal::from(120);
+    assert!(sell.price <= mid);
+    assert!(sell.realized_gain.unwrap() > BigDecimal::zero());
+
+    let portfolio = client.portfolio().await.unwrap();
+    assert_eq!(portfolio.positions.len(), 1);
+    assert_eq!(portfolio.positions[0].quantity, 6);
+    assert_eq!(
+        portfolio.positions[0].current_price,
+        Some(BigDecimal::from(120))
+    );
+    assert_eq!(
+        portfolio.equity,
+        &portfolio.cash + &portfolio.money_market + &portfolio.market_value - &portfolio.loans
+    );
+
+    let transactions = client.transactions().await.unwrap();
+    assert_eq!(transactions.len(), 2);
+}
+
+#[tokio::test]
+async fn positions_keep_when_they_were_opened_and_last_changed() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    let profile = client.profile().await.unwrap();
+    assert!(profile.created_at.is_some());
+    assert!(profile.updated_at >= profile.created_at);
+
+    let buy = client.buy(&ticker, 5).await.unwrap();
+    assert_eq!(buy.updated_at, Some(buy.created_at));
+    let opened = client.holdings().await.unwrap().remove(0);
+    let created_at = opened.created_at.unwrap();
+    assert!(opened.updated_at.unwrap() >= created_at);
+
+    client.buy(&ticker, 5).await.unwrap();
+    let holding = client.holdings().await.unwrap().remove(0);
+    assert_eq!(holding.created_at, Some(created_at));
+    assert!(holding.updated_at > opened.updated_at);
+    let portfolio = client.portfolio().await.unwrap();
+    assert_eq!(portfolio.positions[0].updated_at, holding.updated_at);
+}
+
+#[tokio::test]
+async fn rejects_trades_that_cannot_be_settled() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+
+    // No price has been published for the ticker yet
+    let error = client.buy(&ticker, 1).await.unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == StatusCode::BAD_REQUEST));
+
+    app.set_price(&ticker, 100.0).await;
+
+    let error = client.buy(&ticker, 100).await.unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == StatusCode::BAD_REQUEST));
+
+    let error = client.sell(&ticker, 1).await.unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == StatusCode::BAD_REQUEST));
+
+    assert!(client.transactions().await.unwrap().is_empty());
+    assert_eq!(client.balance().await.unwrap(), BigDecimal::from(1000));
+}
+
+#[tokio::test]
+async fn requires_authentication() {
+    let app = TestApp::spawn().await;
+
+    let error = app.client().balance().await.unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == StatusCode::UNAUTHORIZED));
+}
+
+#[tokio::test]
+async fn fifo_sells_realize_gains_against_the_oldest_lot() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+
+    let settings = client
+        .set_cost_basis_method(CostBasisMethod::Fifo)
+        .await
+        .unwrap();
+    assert_eq!(settings.cost_basis_method, CostBasisMethod::Fifo);
+
+    app.set_price(&ticker, 10.0).await;
+    let first = client.buy(&ticker, 5).await.unwrap();
+    app.set_price(&ticker, 20.0).await;
+    client.buy(&ticker, 5).await.unwrap();
+
+    app.set_price(&ticker, 30.0).await;
+    let sell = client.sell(&ticker, 5).await.unwrap();
+
+    let expected = (&sell.price - &first.price) * BigDecimal::from(5);
+    assert_eq!(sell.realized_gain.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn concurrent_trades_never_clobber_a_position() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    let buys = (0..5).map(|_| client.buy(&ticker, 2));
+    for result in futures_util::future::join_all(buys).await {
+        result.unwrap();
+    }
+    assert_eq!(client.holdings().await.unwrap()[0].quantity, 10);
+
+    // Only as many sells go through as there are shares
+    let sells = (0..4).map(|_| client.sell(&ticker, 5));
+    let results = futures_util::future::join_all(sells).await;
+    let sold = results.iter().filter(|result| result.is_ok()).count();
+    for error in results.into_iter().filter_map(Result::err) {
+        assert!(matches!(
+            error,
+            ClientError::Api {
+                status: StatusCode::BAD_REQUEST | StatusCode::CONFLICT,
+                ..
+            }
+        ));
+    }
+    assert_eq!(sold, 2);
+    assert!(client.holdings().await.unwrap().is_empty());
+    assert_eq!(client.transactions().await.unwrap().len(), 7);
+}
+
+#[tokio::test]
+async fn concurrent_sells_never_take_a_lot_twice() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+    client.buy(&ticker, 10).await.unwrap();
+
+    // Every sell takes its shares from the one lot
+    let sells = (0..4).map(|_| client.sell(&ticker, 3));
+    let results = futures_util::future::join_all(sells).await;
+    let sold: Vec<_> = results
+        .iter()
+        .filter_map(|result| result.as_ref().ok())
+        .collect();
+    assert_eq!(sold.len(), 3);
+
+    let holdings = client.holdings().await.unwrap();
+    assert_eq!(holdings[0].quantity, 1);
+    let (remaining, realized): (i64, i64) = sqlx::query_as(
+        r#"
+        SELECT (SELECT SUM(remaining_quantity) FROM tax_lots WHERE ticker = $1),
+               (SELECT SUM(quantity) FROM realized_gains WHERE ticker = $1)
+        "#,
+    )
+    .bind(&ticker)
+    .fetch_one(&app.pg_pool)
+    .await
+    .unwrap();
+    assert_eq!((remaining, realized), (1, 9));
+    // Every sell that went through was credited
+    assert_eq!(client.transactions().await.unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn selling_out_closes_the_position() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    client.buy(&ticker, 4).await.unwrap();
+    let opened = client.holdings().await.unwrap()[0].id;
+    client.sell(&ticker, 1).await.unwrap();
+    assert!(
+        client
+            .closed_positions(None, None)
+            .await
+            .unwrap()
+            .positions
+            .is_empty()
+    );
+
+    let last = client.sell(&ticker, 3).await.unwrap();
+    assert!(client.holdings().await.unwrap().is_empty());
+    let closed = client.closed_positions(None, None).await.unwrap();
+    assert_eq!(closed.positions.len(), 1);
+    assert_eq!(closed.positions[0].id, opened);
+    assert_eq!(closed.positions[0].ticker, ticker);
+    assert!(closed.positions[0].closed_at >= closed.positions[0].opened_at);
+    assert!(last.realized_gain.is_some());
+
+    // Buying again opens a new position
+    client.buy(&ticker, 2).await.unwrap();
+    let holdings = client.holdings().await.unwrap();
+    assert_eq!(holdings.len(), 1);
+    assert_ne!(holdings[0].id, opened);
+    assert_eq!(holdings[0].quantity, 2);
+    client.sell(&ticker, 2).await.unwrap();
+
+    let first = client.closed_positions(None, Some(1)).await.unwrap();
+    assert_ne!(first.positions[0].id, opened);
+    let rest = client
+        .closed_positions(first.next_cursor, None)
+        .await
+        .unwrap();
+    assert_eq!(rest.positions.len(), 1);
+    assert_eq!(rest.positions[0].id, opened);
+}
+
+#[tokio::test]
+async fn positions_are_valued_at_their_own_cached_prices() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let tickers: Vec<String> = (0..3).map(|_| unique_ticker()).collect();
+    for (ticker, price) in tickers.iter().zip([10.0, 20.0, 30.0]) {
+        app.set_price(ticker, price).await;
+        client.buy(ticker, 1).await.unwrap();
+    }
+
+    // Positions missing from the cache are valued at cost
+    app.clear_price(&tickers[1]).await;
+    let holdings = client.holdings().await.unwrap();
+    let holding = |ticker: &str| {
+        holdings
+            .iter()
+            .find(|holding| holding.ticker == ticker)
+            .unwrap()
+    };
+    assert_eq!(
+        holding(&tickers[0]).current_price,
+        Some(BigDecimal::from(10))
+    );
+    assert_eq!(holding(&tickers[1]).current_price, None);
+    assert_eq!(
+        holding(&tickers[1]).market_value,
+        holding(&tickers[1]).average_price
+    );
+    assert_eq!(
+        holding(&tickers[2]).current_price,
+        Some(BigDecimal::from(30))
+    );
+}
@@ -0,0 +1,299 @@
+//! Paging and filtering of the transaction history.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use reqwest::{Method, StatusCode};
+use serde_json::{Value, json};
+use stock_exchange_sim_core::client::{
+    ClientError,
+    types::{SortOrder, TransactionQuery},
+};
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn pages_through_filtered_history() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let cheap = unique_ticker();
+    let pricey = unique_ticker();
+
+    app.set_price(&cheap, 10.0).await;
+    app.set_price(&pricey, 100.0).await;
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        ids.push(client.buy(&cheap, 1).await.unwrap().id);
+    }
+    ids.push(client.buy(&pricey, 1).await.unwrap().id);
+    ids.push(client.sell(&cheap, 1).await.unwrap().id);
+
+    // Newest first by default, with a cursor until the last page
+    let mut query = TransactionQuery {
+        limit: Some(2),
+        ..Default::default()
+    };
+    let mut listed = Vec::new();
+    loop {
+        let page = client.transaction_page(&query).await.unwrap();
+        assert!(page.transactions.len() <= 2);
+        listed.extend(page.transactions.into_iter().map(|tx| tx.id));
+        match page.next_cursor {
+            Some(cursor) => query.cursor = Some(cursor),
+            None => break,
+        }
+    }
+    let mut newest_first = ids.clone();
+    newest_first.reverse();
+    assert_eq!(listed, newest_first);
+
+    let all = client.transactions().await.unwrap();
+    assert_eq!(all.len(), ids.len());
+
+    let oldest = client
+        .transaction_page(&TransactionQuery {
+            order: Some(SortOrder::Asc),
+            limit: Some(1),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(oldest.transactions[0].id, ids[0]);
+    assert_eq!(oldest.next_cursor, Some(ids[0]));
+
+    let cheap_buys = client
+        .transaction_page(&TransactionQuery {
+            ticker: Some(cheap.clone()),
+            transaction_type: Some("buy".into()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(cheap_buys.transactions.len(), 3);
+    assert_eq!(cheap_buys.next_cursor, None);
+
+    let expensive = client
+        .transaction_page(&TransactionQuery {
+            min_price: Some(BigDecimal::from(50)),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(expensive.transactions.len(), 1);
+    assert_eq!(expensive.transactions[0].ticker, pricey);
+
+    let today = expensive.transactions[0].created_at.date();
+    let in_range = client
+        .transaction_page(&TransactionQuery {
+            from: Some(today),
+            to: Some(today),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(in_range.transactions.len(), ids.len());
+
+    let error = client
+        .transaction_page(&TransactionQuery {
+            limit: Some(1000),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ClientError::Api {
+            status: StatusCode::BAD_REQUEST,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn rows_are_named_by_public_ids() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    let first = client.buy(&ticker, 1).await.unwrap();
+    let second = client.buy(&ticker, 1).await.unwrap();
+    assert_ne!(first.id, second.id);
+
+    // Serial keys are not accepted in their place
+    let serial: i32 = sqlx::query_scalar("SELECT id FROM transactions WHERE public_id = $1")
+        .bind(second.id)
+        .fetch_one(&app.pg_pool)
+        .await
+        .unwrap();
+    let page = client
+        .transaction_page(&TransactionQuery {
+            limit: Some(1),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(page.transactions[0].id, second.id);
+    assert_eq!(page.next_cursor, Some(second.id));
+    let response = reqwest::Client::new()
+        .get(format!(
+            "{}/api/v1/transactions?cursor={}",
+            app.base_url, serial
+        ))
+        .bearer_auth(client.token().unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let rest = client
+        .transaction_page(&TransactionQuery {
+            cursor: page.next_cursor,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let ids: Vec<_> = rest.transactions.iter().map(|tx| tx.id).collect();
+    assert_eq!(ids, [first.id]);
+}
+
+#[tokio::test]
+async fn history_is_read_through_the_replica_pool() {
+    let app = TestApp::spawn_with_read_replica().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    let bought = client.buy(&ticker, 2).await.unwrap();
+    let sold = client.sell(&ticker, 2).await.unwrap();
+
+    let ids: Vec<_> = client
+        .transactions()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|tx| tx.id)
+        .collect();
+    assert_eq!(ids, [sold.id, bought.id]);
+    assert!(client.holdings().await.unwrap().is_empty());
+    let closed = client.closed_positions(None, None).await.unwrap();
+    assert_eq!(closed.positions.len(), 1);
+    assert_eq!(closed.positions[0].ticker, ticker);
+}
+
+#[tokio::test]
+async fn deleted_transactions_leave_the_history() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    let kept = client.buy(&ticker, 1).await.unwrap();
+    let deleted = client.buy(&ticker, 1).await.unwrap();
+    sqlx::query("UPDATE transactions SET deleted_at = NOW() WHERE public_id = $1")
+        .bind(deleted.id)
+        .execute(&app.pg_pool)
+        .await
+        .unwrap();
+
+    let ids: Vec<_> = client
+        .transactions()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|tx| tx.id)
+        .collect();
+    assert_eq!(ids, [kept.id]);
+}
+
+#[tokio::test]
+async fn old_transactions_move_to_the_archive() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    // Old enough that no other test's history is archived along
+    let csv = format!(
+        "date,ticker,side,quantity,price\n\
+         2001-03-01,{t},sell,2,15\n\
+         2001-01-10,{t},buy,5,10\n",
+        t = ticker
+    );
+    client.import_transactions(&csv).await.unwrap();
+    let recent = client.buy(&ticker, 1).await.unwrap();
+    let before_archive = client.realized_gains(Some(2001)).await.unwrap();
+
+    let response = app
+        .admin(Method::POST, "/admin/transactions/archive")
+        .json(&json!({ "before": "2002-01-01" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["archived"].as_u64().unwrap() >= 2);
+
+    let hot: Vec<_> = client
+        .transactions()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|tx| tx.id)
+        .collect();
+    assert_eq!(hot, [recent.id]);
+    let archived = client
+        .archived_transaction_page(&TransactionQuery::default())
+        .await
+        .unwrap();
+    let dates: Vec<String> = archived
+        .transactions
+        .iter()
+        .map(|tx| tx.created_at.date().to_string())
+        .collect();
+    assert_eq!(dates, ["2001-03-01", "2001-01-10"]);
+    let sells = client
+        .archived_transaction_page(&TransactionQuery {
+            transaction_type: Some("sell".into()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(sells.transactions.len(), 1);
+
+    // Gains keep naming the archived sell
+    let gains = client.realized_gains(Some(2001)).await.unwrap();
+    assert_eq!(gains.lots.len(), 1);
+    assert_eq!(
+        gains.lots[0].sell_transaction_id,
+        before_archive.lots[0].sell_transaction_id
+    );
+    assert_eq!(gains.lots[0].sell_transaction_id, sells.transactions[0].id);
+
+    // References are checked against the whole history in place of foreign keys
+    let foreign_key_violation = |error: sqlx::Error| {
+        error
+            .as_database_error()
+            .and_then(|error| error.code())
+            .is_some_and(|code| code == "23503")
+    };
+    let dangling = sqlx::query("UPDATE tax_lots SET transaction_id = -1 WHERE ticker = $1")
+        .bind(&ticker)
+        .execute(&app.pg_pool)
+        .await
+        .unwrap_err();
+    assert!(foreign_key_violation(dangling));
+    let referenced = sqlx::query("DELETE FROM transactions WHERE public_id = $1")
+        .bind(recent.id)
+        .execute(&app.pg_pool)
+        .await
+        .unwrap_err();
+    assert!(foreign_key_violation(referenced));
+
+    let response = app
+        .admin(Method::POST, "/admin/transactions/archive")
+        .json(&json!({ "before": "2999-01-01" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
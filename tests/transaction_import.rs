@@ -0,0 +1,131 @@
+//! Importing a trade history from CSV.
+
+mod support;
+
+use bigdecimal::BigDecimal;
+use stock_exchange_sim_core::client::{ClientError, types::CostBasisMethod};
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn history_is_replayed_in_date_order() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.list_instrument(&ticker).await;
+    client
+        .set_cost_basis_method(CostBasisMethod::Fifo)
+        .await
+        .unwrap();
+
+    // Out of order on purpose; the sell needs the earlier buys
+    let csv = format!(
+        "Date,Ticker,Side,Quantity,Price,Fee\n\
+         2024-03-01,{t},sell,4,15,1\n\
+         2024-01-10,{t},buy,5,10,0\n\
+         2024-02-01T15:30:00Z,{t},buy,5,12,0.5\n",
+        t = ticker.to_lowercase()
+    );
+    let report = client.import_transactions(&csv).await.unwrap();
+    assert_eq!(report.rows, 3);
+    assert_eq!(report.imported, 3);
+    assert!(report.errors.is_empty());
+
+    // 1000 - 50 - 60.5 + 60 - 1
+    assert_eq!(
+        client.balance().await.unwrap(),
+        "948.5".parse::<BigDecimal>().unwrap()
+    );
+    let holdings = client.holdings().await.unwrap();
+    assert_eq!(holdings.len(), 1);
+    assert_eq!(holdings[0].ticker, ticker);
+    assert_eq!(holdings[0].quantity, 6);
+
+    let transactions = client.transactions().await.unwrap();
+    let dates: Vec<String> = transactions
+        .iter()
+        .map(|tx| tx.created_at.date().to_string())
+        .collect();
+    assert_eq!(dates, ["2024-03-01", "2024-02-01", "2024-01-10"]);
+
+    // The sell realized its gain against the oldest lot on its own date
+    let gains = client.realized_gains(Some(2024)).await.unwrap();
+    assert_eq!(gains.lots.len(), 1);
+    assert_eq!(gains.lots[0].quantity, 4);
+    assert_eq!(gains.lots[0].cost_basis, BigDecimal::from(40));
+    assert_eq!(
+        gains.lots[0].realized_at.date_naive().to_string(),
+        "2024-03-01"
+    );
+}
+
+#[tokio::test]
+async fn histories_larger_than_other_requests_are_imported() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.list_instrument(&ticker).await;
+
+    // Padded lines make the upload larger than the default 1MB request limit
+    let line = format!("2024-01-10,{},buy,1,0.1", ticker);
+    let line = format!("{:<500}\n", line);
+    let csv = format!("date,ticker,side,quantity,price\n{}", line.repeat(2500));
+    assert!(csv.len() > 1024 * 1024);
+    let report = client.import_transactions(&csv).await.unwrap();
+    assert_eq!(report.rows, 2500);
+    assert_eq!(report.imported, 2500);
+
+    let holdings = client.holdings().await.unwrap();
+    assert_eq!(holdings[0].quantity, 2500);
+}
+
+#[tokio::test]
+async fn invalid_lines_are_reported_and_nothing_is_imported() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.list_instrument(&ticker).await;
+
+    let csv = format!(
+        "date,ticker,side,quantity,price\n\
+         2024-01-10,{t},buy,5,10\n\
+         2024-13-01,{t},buy,5,10\n\
+         2024-01-11,{t},hold,5,10\n\
+         2024-01-12,{t},buy,0,10\n\
+         2024-01-13,NOSUCHTICK,buy,1,10\n\
+         2024-01-14,{t},sell,50,10\n\
+         2024-01-15,{t},buy,1000,10\n\
+         2024-01-16,{t},buy,1\n",
+        t = ticker
+    );
+    let report = client.import_transactions(&csv).await.unwrap();
+    assert_eq!(report.rows, 8);
+    assert_eq!(report.imported, 0);
+    let lines: Vec<usize> = report.errors.iter().map(|error| error.line).collect();
+    assert_eq!(lines, [3, 4, 5, 6, 7, 8, 9]);
+    assert_eq!(report.error_count, 7);
+
+    assert!(client.transactions().await.unwrap().is_empty());
+    assert_eq!(client.balance().await.unwrap(), BigDecimal::from(1000));
+}
+
+#[tokio::test]
+async fn only_fresh_portfolios_import() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    let error = client
+        .import_transactions("ticker,quantity\n")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == 400));
+
+    client.buy(&ticker, 1).await.unwrap();
+    let csv = format!(
+        "date,ticker,side,quantity,price\n2024-01-10,{},buy,1,10\n",
+        ticker
+    );
+    let error = client.import_transactions(&csv).await.unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == 409));
+}
@@ -0,0 +1,63 @@
+//! The versioned API prefix and the deprecated unversioned paths.
+
+mod support;
+
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+use support::{TestApp, unique_ticker};
+
+#[tokio::test]
+async fn routes_are_served_under_the_version_prefix() {
+    let app = TestApp::spawn().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 42.0).await;
+
+    let response = reqwest::get(format!("{}/api/v1/market/quote/{}", app.base_url, ticker))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("deprecation").is_none());
+    let quote: Value = response.json().await.unwrap();
+    assert_eq!(quote["ticker"], ticker.as_str());
+
+    let response = app
+        .admin(Method::GET, "/api/v1/admin/stats")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("deprecation").is_none());
+
+    // The typed client speaks the versioned API
+    let client = app.register_user().await;
+    assert_eq!(client.quote(&ticker).await.unwrap().ticker, ticker);
+}
+
+#[tokio::test]
+async fn unversioned_paths_still_work_but_are_deprecated() {
+    let app = TestApp::spawn().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 42.0).await;
+
+    let path = format!("/market/quote/{}", ticker);
+    let response = reqwest::get(format!("{}{}", app.base_url, path))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["deprecation"], "true");
+    assert_eq!(
+        response.headers()["link"],
+        format!("</api/v1{}>; rel=\"successor-version\"", path).as_str()
+    );
+
+    let response = app.admin(Method::GET, "/admin/stats").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["deprecation"], "true");
+
+    // Infrastructure endpoints are not versioned
+    let response = reqwest::get(format!("{}/health", app.base_url))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("deprecation").is_none());
+}
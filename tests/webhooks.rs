@@ -0,0 +1,256 @@
+//! Webhooks posting account events, and their delivery log.
+
+mod support;
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use axum::{Router, extract::State, http::HeaderMap, http::StatusCode, routing::post};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use stock_exchange_sim_core::client::ClientError;
+use support::{TestApp, unique_ticker};
+use tokio::sync::mpsc;
+
+/// Lets webhooks post to [`Receiver`]s, which listen on plain HTTP on loopback
+const INSECURE: (&str, &str) = ("WEBHOOK_ALLOW_INSECURE", "true");
+
+/// A request received by a [`Receiver`]
+struct Received {
+    headers: HeaderMap,
+    body: String,
+}
+
+/// A local endpoint recording what is posted to it, failing the first
+/// `failures` requests with `500`
+struct Receiver {
+    url: String,
+    requests: mpsc::UnboundedReceiver<Received>,
+}
+
+impl Receiver {
+    async fn start(failures: usize) -> Receiver {
+        let (sender, requests) = mpsc::unbounded_channel();
+        let failures = Arc::new(AtomicUsize::new(failures));
+        let app = Router::new().route(
+            "/hook",
+            post(
+                |State((sender, failures)): State<(
+                    mpsc::UnboundedSender<Received>,
+                    Arc<AtomicUsize>,
+                )>,
+                 headers: HeaderMap,
+                 body: String| async move {
+                    sender.send(Received { headers, body }).ok();
+                    let failed = failures
+                        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                            left.checked_sub(1)
+                        })
+                        .is_ok();
+                    if failed {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        StatusCode::NO_CONTENT
+                    }
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app.with_state((sender, failures)))
+                .await
+                .unwrap()
+        });
+
+        Receiver { url, requests }
+    }
+
+    async fn next(&mut self) -> Received {
+        tokio::time::timeout(Duration::from_secs(15), self.requests.recv())
+            .await
+            .expect("no webhook delivery")
+            .unwrap()
+    }
+}
+
+fn header<'a>(received: &'a Received, name: &str) -> &'a str {
+    received.headers[name].to_str().unwrap()
+}
+
+#[tokio::test]
+async fn fills_are_posted_signed() {
+    let app = TestApp::spawn_with_env(&[INSECURE]).await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+    let mut receiver = Receiver::start(0).await;
+
+    let webhook = client
+        .create_webhook(&receiver.url, &["order_filled"])
+        .await
+        .unwrap();
+    assert!(webhook.secret.starts_with("whsec_"));
+    assert_eq!(webhook.webhook.events, ["order_filled"]);
+    let bought = client.buy(&ticker, 3).await.unwrap();
+
+    let received = receiver.next().await;
+    assert_eq!(header(&received, "x-webhook-event"), "order_filled");
+    let timestamp = header(&received, "x-webhook-timestamp");
+    let mut mac = Hmac::<Sha256>::new_from_slice(webhook.secret.as_bytes()).unwrap();
+    mac.update(format!("{}.{}", timestamp, received.body).as_bytes());
+    assert_eq!(
+        header(&received, "x-webhook-signature"),
+        format!("sha256={:x}", mac.finalize().into_bytes())
+    );
+    let payload: serde_json::Value = serde_json::from_str(&received.body).unwrap();
+    assert_eq!(payload["event"], "order_filled");
+    assert_eq!(payload["transaction_id"], bought.id.to_string());
+    assert_eq!(payload["ticker"], ticker.as_str());
+    assert!(payload["ts"].is_string());
+
+    // The log shows the delivery once it is recorded
+    let delivery_id: i64 = header(&received, "x-webhook-delivery").parse().unwrap();
+    let mut page = client
+        .webhook_deliveries(webhook.webhook.id, None, None)
+        .await
+        .unwrap();
+    for _ in 0..50 {
+        if page.deliveries[0].status == "delivered" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        page = client
+            .webhook_deliveries(webhook.webhook.id, None, None)
+            .await
+            .unwrap();
+    }
+    assert_eq!(page.deliveries.len(), 1);
+    assert_eq!(page.deliveries[0].id, delivery_id);
+    assert_eq!(page.deliveries[0].status, "delivered");
+    assert_eq!(page.deliveries[0].response_status, Some(204));
+    assert_eq!(page.deliveries[0].payload, payload);
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried() {
+    let app = TestApp::spawn_with_env(&[INSECURE, ("WEBHOOK_RETRY_BASE_SECS", "1")]).await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+    let mut receiver = Receiver::start(2).await;
+
+    let webhook = client
+        .create_webhook(&receiver.url, &["order_filled", "deposit_settled"])
+        .await
+        .unwrap();
+    client.buy(&ticker, 1).await.unwrap();
+
+    let first = receiver.next().await;
+    let second = receiver.next().await;
+    let third = receiver.next().await;
+    // Every attempt is the same delivery
+    for retry in [&second, &third] {
+        assert_eq!(
+            header(retry, "x-webhook-delivery"),
+            header(&first, "x-webhook-delivery")
+        );
+        assert_eq!(retry.body, first.body);
+    }
+
+    let mut delivery = None;
+    for _ in 0..50 {
+        let page = client
+            .webhook_deliveries(webhook.webhook.id, None, None)
+            .await
+            .unwrap();
+        if page.deliveries[0].status == "delivered" {
+            delivery = page.deliveries.into_iter().next();
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let delivery = delivery.expect("delivery not recorded");
+    assert_eq!(delivery.attempts, 3);
+    assert!(delivery.error.is_none());
+}
+
+#[tokio::test]
+async fn webhooks_are_validated_and_private() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let other = app.register_user().await;
+
+    for (url, events) in [
+        ("ftp://example.com/hook", vec!["order_filled"]),
+        ("not a url", vec!["order_filled"]),
+        ("http://example.com/hook", vec!["order_filled"]),
+        ("https://example.com/hook", vec!["alert_triggered"]),
+        ("https://example.com/hook", vec![]),
+    ] {
+        let error = client.create_webhook(url, &events).await.unwrap_err();
+        assert!(
+            matches!(error, ClientError::Api { status, .. } if status == 400),
+            "{} {:?}",
+            url,
+            events
+        );
+    }
+
+    let webhook = client
+        .create_webhook("https://example.com/hook", &["margin_call"])
+        .await
+        .unwrap();
+    assert_eq!(client.webhooks().await.unwrap().len(), 1);
+    assert!(other.webhooks().await.unwrap().is_empty());
+
+    let error = other
+        .webhook_deliveries(webhook.webhook.id, None, None)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == 404));
+    let error = other.delete_webhook(webhook.webhook.id).await.unwrap_err();
+    assert!(matches!(error, ClientError::Api { status, .. } if status == 404));
+
+    client.delete_webhook(webhook.webhook.id).await.unwrap();
+    assert!(client.webhooks().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn webhooks_cannot_reach_inside_the_network() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+
+    for url in [
+        "https://127.0.0.1/hook",
+        "https://localhost:8080/hook",
+        "https://169.254.169.254/latest/meta-data",
+        "https://10.1.2.3/hook",
+        "https://172.16.0.1/hook",
+        "https://192.168.1.1/hook",
+        "https://0.0.0.0/hook",
+        "https://2130706433/hook",
+        "https://[::1]/hook",
+        "https://[fd00::1]/hook",
+        "https://[fe80::1]/hook",
+        "https://[::ffff:127.0.0.1]/hook",
+        "https://redis:6379/hook",
+        "https://metadata.google.internal/hook",
+    ] {
+        let error = client
+            .create_webhook(url, &["order_filled"])
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error, ClientError::Api { status, .. } if status == 400),
+            "{}",
+            url
+        );
+    }
+    assert!(client.webhooks().await.unwrap().is_empty());
+}
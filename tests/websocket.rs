@@ -0,0 +1,559 @@
+//! WebSocket subscriptions and message framing.
+
+mod support;
+
+use std::time::Duration;
+
+use bigdecimal::BigDecimal;
+use stock_exchange_sim_core::client::{
+    types::CandleInterval,
+    ws::{AccountEvent, Channel, ClientMessage, ErrorCode, ServerMessage},
+};
+use support::{TestApp, TestSocket, unique_ticker};
+
+#[tokio::test]
+async fn subscribes_and_unsubscribes_many_tickers() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let (first, second) = (unique_ticker(), unique_ticker());
+    app.set_price(&first, 10.0).await;
+    app.set_price(&second, 20.0).await;
+
+    let mut socket = TestSocket::connect(&client).await;
+    socket
+        .send(ClientMessage::subscribe(&[&first, &second]))
+        .await;
+
+    // Every acknowledgement is followed by the current price of its ticker
+    let (mut acknowledged, mut snapshots) = (Vec::new(), Vec::new());
+    while acknowledged.len() < 2 || snapshots.len() < 2 {
+        match socket.recv().await {
+            ServerMessage::Subscribed {
+                channel, ticker, ..
+            } => {
+                assert_eq!(channel, Channel::Price);
+                acknowledged.push(ticker);
+            }
+            ServerMessage::Price { ticker, price, ts } => {
+                assert!(acknowledged.contains(&ticker));
+                assert!(ts.is_some());
+                snapshots.push((ticker, price));
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+    assert_eq!(acknowledged, vec![first.clone(), second.clone()]);
+    snapshots.sort_by(|a, b| a.1.cmp(&b.1));
+    assert_eq!(
+        snapshots,
+        vec![
+            (first.clone(), BigDecimal::from(10)),
+            (second.clone(), BigDecimal::from(20))
+        ]
+    );
+
+    // Unknown tickers are rejected without closing the connection
+    let unknown = unique_ticker();
+    socket.send(ClientMessage::subscribe(&[&unknown])).await;
+    match socket.recv().await {
+        ServerMessage::Error { code, ticker, .. } => {
+            assert_eq!(code, ErrorCode::InvalidTicker);
+            assert_eq!(ticker, Some(unknown));
+        }
+        other => panic!("unexpected message {:?}", other),
+    }
+
+    // Published prices are pushed without waiting for a poll
+    app.set_price(&first, 11.0).await;
+    match socket.recv().await {
+        ServerMessage::Price { ticker, price, .. } => {
+            assert_eq!((ticker, price), (first.clone(), BigDecimal::from(11)));
+        }
+        other => panic!("unexpected message {:?}", other),
+    }
+
+    socket.send(ClientMessage::unsubscribe(&[&first])).await;
+    match socket.recv().await {
+        ServerMessage::Unsubscribed {
+            channel, ticker, ..
+        } => {
+            assert_eq!((channel, ticker), (Channel::Price, first.clone()));
+        }
+        other => panic!("unexpected message {:?}", other),
+    }
+
+    // Only the remaining subscription keeps updating
+    app.set_price(&first, 12.0).await;
+    app.set_price(&second, 21.0).await;
+    match socket.recv().await {
+        ServerMessage::Price { ticker, price, .. } => {
+            assert_eq!((ticker, price), (second.clone(), BigDecimal::from(21)));
+        }
+        other => panic!("unexpected message {:?}", other),
+    }
+
+    socket.send(ClientMessage::unsubscribe(&[&first])).await;
+    match socket.recv().await {
+        ServerMessage::Error { code, .. } => assert_eq!(code, ErrorCode::NotSubscribed),
+        other => panic!("unexpected message {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn rejects_malformed_messages_and_unknown_versions() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+
+    let mut socket = TestSocket::connect(&client).await;
+    socket.send_text("subscribe:AAPL").await;
+    match socket.recv().await {
+        ServerMessage::Error { code, ticker, .. } => {
+            assert_eq!(code, ErrorCode::InvalidMessage);
+            assert_eq!(ticker, None);
+        }
+        other => panic!("unexpected message {:?}", other),
+    }
+
+    let url = client.ws_url().replace("version=1", "version=99");
+    let response = reqwest::Client::new()
+        .get(url.replacen("ws://", "http://", 1))
+        .bearer_auth(client.token().unwrap())
+        .header("Connection", "upgrade")
+        .header("Upgrade", "websocket")
+        .header("Sec-WebSocket-Version", "13")
+        .header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn pings_clients_and_drops_idle_ones() {
+    let app = TestApp::spawn_with_env(&[
+        ("WS_PING_INTERVAL_SECS", "1"),
+        ("WS_IDLE_TIMEOUT_SECS", "2"),
+    ])
+    .await;
+    let client = app.register_user().await;
+
+    // Answering pings keeps a quiet client connected past the idle timeout
+    let mut responsive = TestSocket::connect(&client).await;
+    let pings = responsive.listen(Duration::from_secs(4)).await;
+    assert!(pings.is_some_and(|pings| pings >= 2), "{:?}", pings);
+
+    // A client that stops reading never answers and is disconnected
+    let mut silent = TestSocket::connect(&client).await;
+    tokio::time::sleep(Duration::from_secs(4)).await;
+    assert_eq!(silent.listen(Duration::from_secs(5)).await, None);
+}
+
+#[tokio::test]
+async fn pushes_account_events_to_the_owner_only() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let other = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    let mut socket = TestSocket::connect(&client).await;
+    let mut other_socket = TestSocket::connect(&other).await;
+
+    client.deposit("500").await.unwrap();
+    match socket.recv().await {
+        ServerMessage::Event {
+            event: AccountEvent::DepositSettled { amount, .. },
+            ..
+        } => assert_eq!(amount, BigDecimal::from(500)),
+        other => panic!("unexpected message {:?}", other),
+    }
+
+    let buy = client.buy(&ticker, 10).await.unwrap();
+    match socket.recv().await {
+        ServerMessage::Event {
+            event:
+                AccountEvent::OrderFilled {
+                    transaction_id,
+                    ticker: filled,
+                    side,
+                    quantity,
+                    price,
+                    ..
+                },
+            ..
+        } => {
+            assert_eq!(transaction_id, buy.id);
+            assert_eq!((filled, side, quantity), (ticker.clone(), "buy".into(), 10));
+            assert_eq!(price, buy.price);
+        }
+        other => panic!("unexpected message {:?}", other),
+    }
+
+    // The other user only hears about their own account
+    other.deposit("7").await.unwrap();
+    match other_socket.recv().await {
+        ServerMessage::Event {
+            event: AccountEvent::DepositSettled { amount, .. },
+            ..
+        } => assert_eq!(amount, BigDecimal::from(7)),
+        other => panic!("unexpected message {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn streams_account_equity_as_prices_change() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+    client.buy(&ticker, 10).await.unwrap();
+
+    let mut socket = TestSocket::connect(&client).await;
+    socket.send(ClientMessage::subscribe_portfolio()).await;
+    assert_eq!(
+        socket.recv().await,
+        ServerMessage::Subscribed {
+            channel: Channel::Portfolio,
+            ticker: String::new(),
+            interval: None,
+        }
+    );
+    let cash = match socket.recv().await {
+        ServerMessage::Portfolio { equity, cash, .. } => {
+            assert_eq!(equity, &cash + BigDecimal::from(100));
+            cash
+        }
+        other => panic!("unexpected message {:?}", other),
+    };
+
+    app.set_price(&ticker, 12.0).await;
+    match socket.recv().await {
+        ServerMessage::Portfolio {
+            equity,
+            market_value,
+            ..
+        } => {
+            assert_eq!(market_value, BigDecimal::from(120));
+            assert_eq!(equity, &cash + BigDecimal::from(120));
+        }
+        other => panic!("unexpected message {:?}", other),
+    }
+
+    socket
+        .send(ClientMessage::Unsubscribe {
+            channel: Channel::Portfolio,
+            tickers: Vec::new(),
+            interval: None,
+        })
+        .await;
+    assert_eq!(
+        socket.recv().await,
+        ServerMessage::Unsubscribed {
+            channel: Channel::Portfolio,
+            ticker: String::new(),
+            interval: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn limits_connections_per_user_and_subscriptions_per_connection() {
+    let app = TestApp::spawn_with_env(&[
+        ("WS_MAX_CONNECTIONS_PER_USER", "2"),
+        ("WS_MAX_SUBSCRIPTIONS", "1"),
+    ])
+    .await;
+    let client = app.register_user().await;
+    let (first, second) = (unique_ticker(), unique_ticker());
+    app.set_price(&first, 10.0).await;
+    app.set_price(&second, 20.0).await;
+
+    let _kept = TestSocket::connect(&client).await;
+    let dropped = TestSocket::connect(&client).await;
+    let mut refused = TestSocket::connect(&client).await;
+    match refused.recv().await {
+        ServerMessage::Error { code, .. } => assert_eq!(code, ErrorCode::TooManyConnections),
+        other => panic!("unexpected message {:?}", other),
+    }
+    assert_eq!(refused.listen(Duration::from_secs(2)).await, None);
+
+    // Closing a connection frees its slot
+    drop(dropped);
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let mut socket = TestSocket::connect(&client).await;
+    socket
+        .send(ClientMessage::subscribe(&[&first, &second]))
+        .await;
+    let mut rejected = None;
+    while rejected.is_none() {
+        match socket.recv().await {
+            ServerMessage::Error { code, ticker, .. } => rejected = Some((code, ticker)),
+            ServerMessage::Subscribed { ticker, .. } => assert_eq!(ticker, first),
+            ServerMessage::Price { .. } => {}
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+    assert_eq!(
+        rejected,
+        Some((ErrorCode::TooManySubscriptions, Some(second)))
+    );
+}
+
+#[tokio::test]
+async fn rate_limits_client_messages() {
+    let app =
+        TestApp::spawn_with_env(&[("WS_MAX_MESSAGES_PER_SEC", "1"), ("WS_MESSAGE_BURST", "2")])
+            .await;
+    let client = app.register_user().await;
+
+    let mut socket = TestSocket::connect(&client).await;
+    for _ in 0..4 {
+        socket.send_text("ping").await;
+    }
+    let mut codes = Vec::new();
+    for _ in 0..4 {
+        match socket.recv().await {
+            ServerMessage::Error { code, .. } => codes.push(code),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+    assert_eq!(
+        codes,
+        vec![
+            ErrorCode::InvalidMessage,
+            ErrorCode::InvalidMessage,
+            ErrorCode::RateLimited,
+            ErrorCode::RateLimited,
+        ]
+    );
+
+    // The bucket refills over time
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    socket.send_text("ping").await;
+    match socket.recv().await {
+        ServerMessage::Error { code, .. } => assert_eq!(code, ErrorCode::InvalidMessage),
+        other => panic!("unexpected message {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn streams_forming_and_finalized_candles() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    let mut socket = TestSocket::connect(&client).await;
+    socket
+        .send(ClientMessage::subscribe_candles(
+            &[&ticker],
+            CandleInterval::OneMinute,
+        ))
+        .await;
+    assert_eq!(
+        socket.recv().await,
+        ServerMessage::Subscribed {
+            channel: Channel::Candles,
+            ticker: ticker.clone(),
+            interval: Some(CandleInterval::OneMinute),
+        }
+    );
+
+    // Ticks are stamped an hour ahead so the minute cannot close on its own
+    let minute = chrono::Duration::minutes(1);
+    let start = chrono::Utc::now() + chrono::Duration::hours(1);
+    app.publish_price(&ticker, 11.0, start).await;
+    app.publish_price(&ticker, 12.0, start).await;
+    app.publish_price(&ticker, 9.0, start + minute).await;
+
+    let mut frames = Vec::new();
+    while frames.len() < 4 {
+        match socket.recv().await {
+            ServerMessage::Candle {
+                open,
+                high,
+                low,
+                close,
+                ticks,
+                is_final,
+                ..
+            } => frames.push((open, high, low, close, ticks, is_final)),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+    let price = |price: u32| BigDecimal::from(price);
+    assert_eq!(
+        frames,
+        vec![
+            (price(11), price(11), price(11), price(11), 1, false),
+            (price(11), price(12), price(11), price(12), 2, false),
+            (price(11), price(12), price(11), price(12), 2, true),
+            (price(9), price(9), price(9), price(9), 1, false),
+        ]
+    );
+
+    // Candles need an interval
+    socket
+        .send(ClientMessage::Subscribe {
+            channel: Channel::Candles,
+            tickers: vec![ticker.clone()],
+            interval: None,
+        })
+        .await;
+    match socket.recv().await {
+        ServerMessage::Error { code, .. } => assert_eq!(code, ErrorCode::InvalidMessage),
+        other => panic!("unexpected message {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn speaks_messagepack_when_negotiated() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.5).await;
+
+    let mut socket = TestSocket::connect_msgpack(&client).await;
+    assert!(socket.msgpack);
+    socket.send(ClientMessage::subscribe(&[&ticker])).await;
+    assert_eq!(
+        socket.recv().await,
+        ServerMessage::Subscribed {
+            channel: Channel::Price,
+            ticker: ticker.clone(),
+            interval: None,
+        }
+    );
+    match socket.recv().await {
+        ServerMessage::Price { price, ts, .. } => {
+            assert_eq!(price, "10.5".parse::<BigDecimal>().unwrap());
+            assert!(ts.is_some());
+        }
+        other => panic!("unexpected message {:?}", other),
+    }
+
+    // JSON stays the default, and text commands are still understood
+    let mut json = TestSocket::connect(&client).await;
+    assert!(!json.msgpack);
+    json.send(ClientMessage::subscribe(&[&ticker])).await;
+    match json.recv().await {
+        ServerMessage::Subscribed { ticker: acked, .. } => assert_eq!(acked, ticker),
+        other => panic!("unexpected message {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn resumes_subscriptions_and_replays_missed_events() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+    let ticker = unique_ticker();
+    app.set_price(&ticker, 10.0).await;
+
+    let mut socket = TestSocket::connect(&client).await;
+    socket.send(ClientMessage::subscribe(&[&ticker])).await;
+    assert!(matches!(
+        socket.recv().await,
+        ServerMessage::Subscribed { .. }
+    ));
+    assert!(matches!(socket.recv().await, ServerMessage::Price { .. }));
+    let session = socket.session().to_string();
+    drop(socket);
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // Events published while disconnected are replayed after the subscriptions
+    client.deposit("250").await.unwrap();
+    let mut resumed = TestSocket::resume(&client, &session).await;
+    match &resumed.welcome {
+        ServerMessage::Welcome {
+            resumed: restored,
+            session: renewed,
+            ..
+        } => {
+            assert!(restored);
+            assert_ne!(renewed, &session);
+        }
+        other => panic!("unexpected greeting {:?}", other),
+    }
+    assert_eq!(
+        resumed.recv().await,
+        ServerMessage::Subscribed {
+            channel: Channel::Price,
+            ticker: ticker.clone(),
+            interval: None,
+        }
+    );
+    let mut replayed = false;
+    while !replayed {
+        match resumed.recv().await {
+            ServerMessage::Price { ticker: priced, .. } => assert_eq!(priced, ticker),
+            ServerMessage::Event {
+                event: AccountEvent::DepositSettled { amount, .. },
+                ..
+            } => {
+                assert_eq!(amount, BigDecimal::from(250));
+                replayed = true;
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    // A session is resumed once; later attempts start afresh
+    let fresh = TestSocket::resume(&client, &session).await;
+    assert!(matches!(
+        fresh.welcome,
+        ServerMessage::Welcome { resumed: false, .. }
+    ));
+}
+
+#[tokio::test]
+async fn broadcasts_and_replays_system_announcements() {
+    let app = TestApp::spawn().await;
+    let client = app.register_user().await;
+
+    let announce = |message: &str| {
+        app.admin(reqwest::Method::POST, "/admin/announcements")
+            .json(&serde_json::json!({"message": message, "severity": "warning"}))
+            .send()
+    };
+    let earlier = announce("Market closes early today").await.unwrap();
+    assert_eq!(earlier.status(), 200);
+
+    // Active announcements are sent to clients subscribing later
+    let mut socket = TestSocket::connect(&client).await;
+    socket.send(ClientMessage::subscribe_system()).await;
+    assert_eq!(
+        socket.recv().await,
+        ServerMessage::Subscribed {
+            channel: Channel::System,
+            ticker: String::new(),
+            interval: None,
+        }
+    );
+    let mut seen = Vec::new();
+    while !seen.contains(&"Market closes early today".to_string()) {
+        match socket.recv().await {
+            ServerMessage::Announcement { message, .. } => seen.push(message),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    // New announcements are pushed as they are made
+    announce("Trading resumes at 14:00").await.unwrap();
+    match socket.recv().await {
+        ServerMessage::Announcement {
+            message, severity, ..
+        } => {
+            assert_eq!(message, "Trading resumes at 14:00");
+            assert_eq!(severity, "warning");
+        }
+        other => panic!("unexpected message {:?}", other),
+    }
+
+    let invalid = app
+        .admin(reqwest::Method::POST, "/admin/announcements")
+        .json(&serde_json::json!({"message": "Hello", "severity": "loud"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(invalid.status(), 400);
+}